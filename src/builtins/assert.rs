@@ -0,0 +1,157 @@
+use super::compare::{self, OPERATORS};
+use super::test::test;
+use shell::Shell;
+use shell::errors::{ErrorKind, IonError};
+use shell::status::*;
+use std::io::{self, Write};
+use std::iter;
+
+const HELP: &'static str = r#"NAME
+    assert - Fail with a description of an expression which does not hold
+
+SYNOPSIS
+    assert [ --help ] [ -m MESSAGE ] EXPRESSION
+
+DESCRIPTION
+    Succeeds when the EXPRESSION holds, and otherwise reports the expression, along with the
+    values which it compared, and fails. When the values span several lines, the lines which
+    differ between them are marked with `-` for the left value, and `+` for the right value.
+
+    The EXPRESSION is either a comparison of two values, as accepted by the compare builtin,
+    such as `$count == 3` or `$ratio < 0.5 :float`, or a test of strings and files, as
+    accepted by the test builtin, such as `-f FILE`, `-z STRING`, or `$a = $b`.
+
+OPTIONS
+    -m MESSAGE
+        describe the failed assertion with the MESSAGE
+"#;
+
+/// Evaluates the expression as a comparison, if it is one, and otherwise as a test.
+fn evaluate(expression: &[&str]) -> Result<bool, String> {
+    let is_comparison = match expression.len() {
+        3 => true,
+        4 => expression[3].starts_with(':'),
+        _ => false,
+    } && OPERATORS.contains(&expression[1]);
+    if is_comparison {
+        let kind = expression.get(3).map(|kind| &kind[1..]);
+        compare::evaluate(expression[0], expression[1], expression[2], kind)
+    } else {
+        // The test builtin expects to be given its own name first.
+        let arguments = iter::once(&"assert").chain(expression).cloned().collect::<Vec<&str>>();
+        test(&arguments)
+    }
+}
+
+/// Describes the two values which an expression compared, as a diff of their lines if either
+/// of them spans several lines.
+fn describe(left: &str, right: &str) -> String {
+    if left.contains('\n') || right.contains('\n') {
+        format!("  diff (- left, + right):\n{}", diff(left, right))
+    } else {
+        format!("  left:  {:?}\n  right: {:?}", left, right)
+    }
+}
+
+/// Lists the lines of both values, marking the lines which only belong to the left value with
+/// `-`, and the lines which only belong to the right value with `+`.
+fn diff(left: &str, right: &str) -> String {
+    let left = left.lines().collect::<Vec<&str>>();
+    let right = right.lines().collect::<Vec<&str>>();
+    // The length of the longest sequence of lines which is common to the remainders of both
+    // values, beginning at each pair of lines.
+    let mut common = vec![vec![0; right.len() + 1]; left.len() + 1];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            let length = if left[i] == right[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+            common[i][j] = length;
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() || j < right.len() {
+        if i < left.len() && j < right.len() && left[i] == right[j] {
+            lines.push(format!("    {}", left[i]));
+            i += 1;
+            j += 1;
+        } else if j == right.len() || (i < left.len() && common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(format!("  - {}", left[i]));
+            i += 1;
+        } else {
+            lines.push(format!("  + {}", right[j]));
+            j += 1;
+        }
+    }
+    lines.join("\n")
+}
+
+pub(crate) fn assert(args: &[&str], shell: &mut Shell) -> i32 {
+    if args.get(1) == Some(&"--help") {
+        let stdout = io::stdout();
+        let _ = stdout.lock().write_all(HELP.as_bytes());
+        return SUCCESS;
+    }
+    let (message, expression) = match args.get(1) {
+        Some(&"-m") if args.len() > 2 => (Some(args[2]), &args[3..]),
+        Some(&"-m") => {
+            eprintln!("ion: assert: -m: expected a message");
+            return BAD_ARG;
+        }
+        _ => (None, &args[1..]),
+    };
+    if expression.is_empty() {
+        eprintln!("ion: assert: expected an expression");
+        return BAD_ARG;
+    }
+
+    match evaluate(expression) {
+        Ok(true) => SUCCESS,
+        Ok(false) => {
+            let message = match message {
+                Some(message) => format!("assertion failed: {}: {}", message, expression.join(" ")),
+                None => format!("assertion failed: {}", expression.join(" ")),
+            };
+            let mut error = IonError::new(ErrorKind::Assertion, message).context("assert");
+            // Tests of a single value, such as `-f FILE`, have no values to describe.
+            if expression.len() == 3 || expression.len() == 4 {
+                error.snippet = Some(describe(expression[0], expression[2]));
+            }
+            shell.report(error);
+            FAILURE
+        }
+        Err(why) => {
+            eprintln!("ion: assert: {}", why);
+            BAD_ARG
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expressions() {
+        assert_eq!(evaluate(&["3", "==", "3.0"]), Ok(true));
+        assert_eq!(evaluate(&["3", "==", "3.0", ":str"]), Ok(false));
+        assert_eq!(evaluate(&["abc", "=", "abc"]), Ok(true));
+        assert_eq!(evaluate(&["2", "-gt", "10"]), Ok(false));
+        assert_eq!(evaluate(&["-z", ""]), Ok(true));
+        assert_eq!(evaluate(&["-d", "/nonexistent/directory"]), Ok(false));
+        assert!(evaluate(&["1", "==", "x", ":int"]).is_err());
+    }
+
+    #[test]
+    fn descriptions() {
+        assert_eq!(describe("3", "5"), "  left:  \"3\"\n  right: \"5\"");
+        assert_eq!(
+            describe("one\ntwo\nthree", "one\n2\nthree\nfour"),
+            "  diff (- left, + right):\n    one\n  - two\n  + 2\n    three\n  + four"
+        );
+    }
+}
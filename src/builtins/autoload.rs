@@ -0,0 +1,71 @@
+use shell::Shell;
+use shell::autoload::Autoload;
+use shell::status::*;
+use std::io::{self, Write};
+
+const HELP: &'static str = r#"NAME
+    autoload - List and load the functions which are defined within FPATH
+
+SYNOPSIS
+    autoload [ --help ] [-a] [-r] [FUNCTION...]
+
+DESCRIPTION
+    Each file named NAME.ion within the directories of FPATH, which are separated by colons,
+    defines the function NAME. The file is sourced the first time that the function is called,
+    so that large libraries of functions do not slow the startup of the shell. The given
+    FUNCTIONs are loaded at once. If no functions are given, every function which may be
+    autoloaded will be printed, along with the file which defines it.
+
+OPTIONS
+    -a  Load every function which may be autoloaded.
+
+    -r  Search the directories of FPATH again, such as after a file was added to them.
+"#;
+
+pub(crate) fn autoload(args: &[&str], shell: &mut Shell) -> i32 {
+    let mut all = false;
+    let mut rescan = false;
+    let mut names = Vec::new();
+    for &arg in &args[1..] {
+        match arg {
+            "--help" => {
+                let stdout = io::stdout();
+                let _ = stdout.lock().write_all(HELP.as_bytes());
+                return SUCCESS;
+            }
+            "-a" => all = true,
+            "-r" => rescan = true,
+            _ if arg.starts_with('-') => {
+                eprintln!("ion: autoload: {}: invalid option", arg);
+                return BAD_ARG;
+            }
+            _ => names.push(arg.to_owned()),
+        }
+    }
+
+    let fpath = shell.variables.get_var_or_empty("FPATH");
+    shell.autoloads.refresh(&fpath, rescan);
+    if all {
+        names.extend(shell.autoloads.files().keys().cloned());
+    } else if names.is_empty() {
+        if !rescan {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            for (name, path) in shell.autoloads.files() {
+                let _ = writeln!(stdout, "{} {}", name, path.display());
+            }
+        }
+        return SUCCESS;
+    }
+
+    let mut status = SUCCESS;
+    for name in names {
+        if !shell.autoloads.files().contains_key(&name) && !shell.functions.contains_key(&*name) {
+            eprintln!("ion: autoload: {}: function not found within FPATH", name);
+            status = FAILURE;
+        } else if !shell.autoload(&name) {
+            status = FAILURE;
+        }
+    }
+    status
+}
@@ -1,45 +1,80 @@
-use calc::{eval, eval_polish, CalcError, Value};
-use std::io::{self, Write};
+use calc::eval_polish;
+use parser::shell_expand::evaluate_arithmetic;
+use shell::Shell;
+use shell::status::*;
+use std::io::{self, BufRead, Write};
+use sys;
 
-fn calc_or_polish_calc(args: String) -> Result<Value, CalcError> {
-    match eval(&args) {
-        Ok(t) => Ok(t),
-        Err(_) => eval_polish(&args),
-    }
+const HELP: &'static str = r#"NAME
+    calc - Evaluate arithmetic expressions
+
+SYNOPSIS
+    calc [ --help ] [EXPRESSION...]
+
+DESCRIPTION
+    Evaluates the EXPRESSION, which is formed by joining each argument with a space, in the same
+    manner as an arithmetic expansion, and prints its value. Variables of the shell may be read
+    and assigned within it, as in `calc total += 5`. Expressions in Polish notation, such as
+    `+ 1 2`, are also accepted. Expressions which contain `*` should be quoted, so that they are
+    not expanded as globs.
+
+    If no expression is given, expressions are read from standard input, one per line, until
+    either the end of the input or `exit` is reached. A prompt is printed before each line when
+    standard input is a terminal.
+"#;
+
+/// Evaluates an expression as an arithmetic expansion would, falling back to Polish notation.
+fn evaluate(expression: &str, shell: &mut Shell) -> Result<String, String> {
+    evaluate_arithmetic(expression, shell).or_else(|why| {
+        eval_polish(expression).map(|value| value.to_string()).map_err(|_| why)
+    })
 }
 
-pub(crate) fn calc(args: &[&str]) -> Result<(), String> {
+pub(crate) fn calc(args: &[&str], shell: &mut Shell) -> i32 {
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
-    if !args.is_empty() {
-        let result = calc_or_polish_calc(args.join(" "));
-        let _ = match result {
-            Ok(v) => writeln!(stdout, "{}", v),
-            Err(e) => writeln!(stdout, "{}", e),
+    if args.get(1) == Some(&"--help") {
+        let _ = stdout.write_all(HELP.as_bytes());
+        return SUCCESS;
+    }
+
+    if args.len() > 1 {
+        return match evaluate(&args[1..].join(" "), shell) {
+            Ok(value) => {
+                let _ = writeln!(stdout, "{}", value);
+                SUCCESS
+            }
+            Err(why) => {
+                eprintln!("ion: calc: {}", why);
+                FAILURE
+            }
         };
-    } else {
-        let prompt = b"ion-calc: ";
-        loop {
-            let _ = stdout.write(prompt);
+    }
+
+    // A prompt is only printed when the expressions are being typed.
+    let is_interactive = sys::isatty(sys::STDIN_FILENO);
+    let stdin = io::stdin();
+    let mut stdin = stdin.lock();
+    loop {
+        if is_interactive {
+            let _ = stdout.write_all(b"ion-calc: ");
             let _ = stdout.flush();
-            let mut input = String::new();
-            let _ = io::stdin().read_line(&mut input);
-            if input.is_empty() {
-                break;
-            } else {
-                match input.trim() {
-                    "" => (),
-                    "exit" => break,
-                    s => {
-                        let result = calc_or_polish_calc(s.to_string());
-                        let _ = match result {
-                            Ok(v) => writeln!(stdout, "{}", v),
-                            Err(e) => writeln!(stdout, "{}", e),
-                        };
-                    }
+        }
+        let mut input = String::new();
+        match stdin.read_line(&mut input) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => (),
+        }
+        match input.trim() {
+            "" => (),
+            "exit" => break,
+            expression => match evaluate(expression, shell) {
+                Ok(value) => {
+                    let _ = writeln!(stdout, "{}", value);
                 }
-            }
+                Err(why) => eprintln!("ion: calc: {}", why),
+            },
         }
     }
-    Ok(())
+    SUCCESS
 }
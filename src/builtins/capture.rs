@@ -0,0 +1,107 @@
+use parser::quote;
+use shell::{FlowLogic, Shell};
+use shell::capture::Capture;
+use shell::flags::KEEP_NEWLINES;
+use shell::status::*;
+use shell::variables::Variables;
+use std::io::{self, Write};
+use sys;
+
+const HELP: &'static str = r#"NAME
+    capture - Store the output, errors, and status of a command within variables
+
+SYNOPSIS
+    capture [ --help ] [-o VARIABLE] [-e VARIABLE] COMMAND [ARGUMENT...]
+
+DESCRIPTION
+    Executes the COMMAND with its ARGUMENTS, which are not expanded again, and stores its
+    standard output within OUT, and its standard error within ERR, without their trailing
+    newlines, unless the keepnewlines option is enabled. The exit status of the command is
+    returned, and so is stored within `?`. The command is executed within the shell itself, so
+    that a function which is captured may still change the shell's variables.
+
+        capture git status --short
+        if test $? -ne 0
+            echo "git failed: $ERR"
+        end
+
+OPTIONS
+    -o VARIABLE
+        Store the standard output within VARIABLE, rather than within OUT.
+
+    -e VARIABLE
+        Store the standard error within VARIABLE, rather than within ERR.
+"#;
+
+pub(crate) fn capture(args: &[&str], shell: &mut Shell) -> i32 {
+    let (mut out, mut err) = ("OUT", "ERR");
+    let mut args = args[1..].iter();
+    let mut command = Vec::new();
+    while let Some(&arg) = args.next() {
+        let variable = match arg {
+            "--help" => {
+                let stdout = io::stdout();
+                let _ = stdout.lock().write_all(HELP.as_bytes());
+                return SUCCESS;
+            }
+            "-o" => &mut out,
+            "-e" => &mut err,
+            "--" => {
+                command.extend(args.by_ref().map(|arg| quote(arg)));
+                break;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("ion: capture: {}: invalid option", arg);
+                return BAD_ARG;
+            }
+            _ => {
+                command.push(quote(arg));
+                command.extend(args.by_ref().map(|arg| quote(arg)));
+                break;
+            }
+        };
+        match args.next() {
+            Some(&name) if !name.is_empty() && Variables::is_valid_variable_name(name) => {
+                *variable = name
+            }
+            Some(name) => {
+                eprintln!("ion: capture: {}: invalid variable name", name);
+                return BAD_ARG;
+            }
+            None => {
+                eprintln!("ion: capture: {}: expected a variable", arg);
+                return BAD_ARG;
+            }
+        }
+    }
+    if command.is_empty() {
+        eprintln!("ion: capture: expected a command, as in `capture git status`");
+        return BAD_ARG;
+    }
+
+    let stdout = match Capture::start(sys::STDOUT_FILENO) {
+        Ok(capture) => capture,
+        Err(why) => {
+            eprintln!("ion: capture: {}", why);
+            return FAILURE;
+        }
+    };
+    let stderr = match Capture::start(sys::STDERR_FILENO) {
+        Ok(capture) => capture,
+        Err(why) => {
+            let _ = stdout.finish();
+            eprintln!("ion: capture: {}", why);
+            return FAILURE;
+        }
+    };
+    shell.on_command(&command.join(" "));
+    let status = shell.previous_status;
+    let (output, errors) = (stdout.finish(), stderr.finish());
+
+    let keep_newlines = shell.flags & KEEP_NEWLINES != 0;
+    for &(name, value) in &[(out, &output), (err, &errors)] {
+        let value = if keep_newlines { value.as_str() } else { value.trim_right_matches('\n') };
+        shell.variables.set_var(name, value);
+    }
+    status
+}
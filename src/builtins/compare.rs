@@ -0,0 +1,148 @@
+use decimal::Decimal;
+use parser::assignments::is_boolean;
+use shell::Shell;
+use shell::status::*;
+use std::cmp::Ordering;
+use std::io::{self, Write};
+use units;
+
+const HELP: &'static str = r#"NAME
+    compare - Compare two values as numbers or strings
+
+SYNOPSIS
+    compare [ --help ] LEFT OPERATOR RIGHT [:TYPE]
+
+DESCRIPTION
+    Succeeds when LEFT and RIGHT satisfy the OPERATOR, which is one of ==, !=, <, <=, >, or >=.
+    Conditions such as `if $x > 5` are evaluated by this builtin.
+
+    If a TYPE is given, both values must be of that type, which is one of int, float, bigint,
+    decimal, bool, or str. Otherwise, both values are compared as numbers if they are both
+    numbers, and as strings if they are not.
+"#;
+
+/// The operators by which two values may be compared.
+pub(crate) const OPERATORS: [&'static str; 6] = ["==", "!=", "<", "<=", ">", ">="];
+
+/// The types as which two values may be compared, which are given after a colon.
+pub(crate) const TYPES: [&'static str; 6] = ["int", "float", "bigint", "decimal", "bool", "str"];
+
+fn parse_int(value: &str) -> Result<i64, String> {
+    units::parse_integer(value).ok_or_else(|| format!("'{}' is not an int", value))
+}
+
+fn parse_float(value: &str) -> Result<f64, String> {
+    match units::parse_float(value) {
+        Some(number) if !number.is_nan() => Ok(number),
+        _ => Err(format!("'{}' is not a float", value)),
+    }
+}
+
+fn parse_bool(value: &str) -> Result<&str, String> {
+    is_boolean(value).map_err(|_| format!("'{}' is not a bool", value))
+}
+
+fn parse_decimal(value: &str, integer: bool) -> Result<Decimal, String> {
+    match units::parse_decimal(value) {
+        Some(ref number) if integer && !number.is_integer() => None,
+        number => number,
+    }.ok_or_else(|| format!("'{}' is not a {}", value, if integer { "bigint" } else { "decimal" }))
+}
+
+/// Orders the two values as the given type, or else as numbers if both of them are numbers, and
+/// as strings otherwise.
+fn order(left: &str, right: &str, kind: Option<&str>) -> Result<Ordering, String> {
+    match kind {
+        Some("int") => Ok(parse_int(left)?.cmp(&parse_int(right)?)),
+        Some("float") => Ok(parse_float(left)?.partial_cmp(&parse_float(right)?).unwrap()),
+        Some("bigint") => Ok(parse_decimal(left, true)?.cmp(&parse_decimal(right, true)?)),
+        Some("decimal") => Ok(parse_decimal(left, false)?.cmp(&parse_decimal(right, false)?)),
+        Some("bool") => Ok(parse_bool(left)?.cmp(parse_bool(right)?)),
+        Some("str") => Ok(left.cmp(right)),
+        Some(kind) => Err(format!("'{}' is not a type which may be compared", kind)),
+        None => if let (Ok(a), Ok(b)) = (parse_decimal(left, false), parse_decimal(right, false)) {
+            Ok(a.cmp(&b))
+        } else if let (Ok(a), Ok(b)) = (parse_float(left), parse_float(right)) {
+            Ok(a.partial_cmp(&b).unwrap())
+        } else {
+            Ok(left.cmp(right))
+        },
+    }
+}
+
+pub(crate) fn evaluate(
+    left: &str,
+    operator: &str,
+    right: &str,
+    kind: Option<&str>,
+) -> Result<bool, String> {
+    if !OPERATORS.contains(&operator) {
+        return Err(format!("'{}' is not a comparison operator", operator));
+    }
+    let ordering = order(left, right, kind)?;
+    Ok(match operator {
+        "==" => ordering == Ordering::Equal,
+        "!=" => ordering != Ordering::Equal,
+        "<" => ordering == Ordering::Less,
+        "<=" => ordering != Ordering::Greater,
+        ">" => ordering == Ordering::Greater,
+        _ => ordering != Ordering::Less,
+    })
+}
+
+pub(crate) fn compare(args: &[&str], _: &mut Shell) -> i32 {
+    if args.get(1) == Some(&"--help") {
+        let stdout = io::stdout();
+        let _ = stdout.lock().write_all(HELP.as_bytes());
+        return SUCCESS;
+    }
+    let kind = match args.len() {
+        4 => None,
+        5 if args[4].starts_with(':') => Some(&args[4][1..]),
+        _ => {
+            eprintln!("ion: compare: expected two values, an operator, and an optional type");
+            return BAD_ARG;
+        }
+    };
+    match evaluate(args[1], args[2], args[3], kind) {
+        Ok(true) => SUCCESS,
+        Ok(false) => FAILURE,
+        Err(why) => {
+            eprintln!("ion: compare: {}", why);
+            BAD_ARG
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(evaluate("10", ">", "5", None), Ok(true));
+        assert_eq!(evaluate("10", "<", "9", None), Ok(false));
+        assert_eq!(evaluate("abc", "<", "abd", None), Ok(true));
+        assert_eq!(evaluate("1.50", "==", "1.5", None), Ok(true));
+        assert_eq!(evaluate("0xff", "==", "255", None), Ok(true));
+        assert_eq!(evaluate("1e3", ">=", "1000", None), Ok(true));
+        assert_eq!(evaluate("1048576", "<", "2MiB", None), Ok(true));
+        assert_eq!(evaluate("250ms", "==", "0.25", Some("float")), Ok(true));
+        // Numbers are only compared as numbers when both values are numbers.
+        assert_eq!(evaluate("10", "<", "9a", None), Ok(true));
+
+        assert_eq!(evaluate("007", "==", "7", Some("int")), Ok(true));
+        assert_eq!(evaluate("007", "==", "7", Some("str")), Ok(false));
+        assert_eq!(evaluate("0.1", "<=", "0.10", Some("decimal")), Ok(true));
+        let big = "123456789012345678901234567890";
+        assert_eq!(evaluate(big, ">", "9223372036854775807", Some("bigint")), Ok(true));
+        assert_eq!(evaluate("y", "==", "true", Some("bool")), Ok(true));
+        assert_eq!(evaluate("2.5", "!=", "2", Some("float")), Ok(true));
+
+        assert!(evaluate("1.5", "==", "1", Some("int")).is_err());
+        assert!(evaluate("1.5", "==", "1", Some("bigint")).is_err());
+        assert!(evaluate("maybe", "==", "true", Some("bool")).is_err());
+        assert!(evaluate("1", "==", "1", Some("char")).is_err());
+        assert!(evaluate("1", "=~", "1", None).is_err());
+    }
+}
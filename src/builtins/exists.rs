@@ -8,6 +8,7 @@ use std::io::{self, BufWriter};
 use std::os::unix::fs::PermissionsExt;
 
 use shell::Shell;
+use shell::variables::Variables;
 #[cfg(test)]
 use shell::flow_control::{Function, Statement};
 
@@ -25,7 +26,7 @@ OPTIONS
         array var is not empty
 
     -b BINARY
-        binary is in PATH
+        binary is in PATH, or is a builtin
 
     -d PATH
         path is a directory
@@ -41,6 +42,9 @@ OPTIONS
     -s STRING
         string var is not empty
 
+    NAME[KEY]
+        map has the key, or array has an element at the index
+
     STRING
         string is not empty
         This is the same as test -n
@@ -63,6 +67,9 @@ EXAMPLES
     Test if a function named 'myFunc' exists
         exists --fn myFunc && myFunc || echo "No function with name myFunc found"
 
+    Test if the map myMap has the key 'name'
+        exists myMap[name] && echo "myMap has the key name" || echo "name is not in myMap"
+
 AUTHOR
     Written by Fabian Würfl.
     Heavily based on implementation of the test builtin, which was written by Michael Murph.
@@ -112,7 +119,10 @@ fn evaluate_arguments<W: io::Write>(
                 })
             })
         }
-        Some(string) => Ok(string_is_nonzero(string)),
+        Some(string) => match Variables::is_hashmap_reference(string) {
+            Some((ref name, ref key)) if !name.is_empty() => Ok(key_exists(name, key, shell)),
+            _ => Ok(string_is_nonzero(string)),
+        },
         None => Ok(false),
     }
 }
@@ -147,8 +157,13 @@ fn path_is_directory(filepath: &str) -> bool {
     fs::metadata(filepath).ok().map_or(false, |metadata| metadata.file_type().is_dir())
 }
 
-/// Returns true if the binary is found in path (and is executable)
+/// Returns true if the binary is found in path (and is executable), or is a builtin, so that
+/// scripts may detect whether a command exists before wrapping it
 fn binary_is_in_path(binaryname: &str, shell: &Shell) -> bool {
+    if shell.builtins.contains_key(binaryname) {
+        return true;
+    }
+
     // TODO: Maybe this function should reflect the logic for spawning new processes
     // TODO: Right now they use an entirely different logic which means that it *might* be possible
     // TODO: that `exists` reports a binary to be in the path, while the shell cannot find it or
@@ -203,6 +218,17 @@ fn string_var_is_not_empty(stringvar: &str, shell: &Shell) -> bool {
     }
 }
 
+/// Returns true if the map has the given key, or if the array has an element at the given index
+fn key_exists(name: &str, key: &str, shell: &Shell) -> bool {
+    if let Some(map) = shell.variables.get_map(name) {
+        map.contains_key(key)
+    } else if let Some(array) = shell.variables.get_array(name) {
+        key.parse::<usize>().ok().map_or(false, |index| index < array.len())
+    } else {
+        false
+    }
+}
+
 /// Returns true if a function with the given name is defined
 fn function_is_defined(function: &str, shell: &Shell) -> bool {
     match shell.functions.get(function) {
@@ -256,6 +282,7 @@ fn test_evaluate_arguments() {
     assert_eq!(evaluate_arguments(&["-b", "executable_file"], &mut sink, &shell), Ok(true));
     assert_eq!(evaluate_arguments(&["-b", "empty_file"], &mut sink, &shell), Ok(false));
     assert_eq!(evaluate_arguments(&["-b", "file_does_not_exist"], &mut sink, &shell), Ok(false));
+    assert_eq!(evaluate_arguments(&["-b", "jobs"], &mut sink, &shell), Ok(true));
 
     // restore original PATH. Not necessary for the currently defined test cases but this might
     // change in the future? Better safe than sorry!
@@ -291,6 +318,16 @@ fn test_evaluate_arguments() {
     shell.variables.set_array("array", SmallVec::from_vec(vec));
     assert_eq!(evaluate_arguments(&["-s", "array"], &mut sink, &shell), Ok(false));
 
+    // check `exists NAME[KEY]`
+    shell.variables.set_hashmap_value("map", "key", "value");
+    assert_eq!(evaluate_arguments(&["map[key]"], &mut sink, &shell), Ok(true));
+    assert_eq!(evaluate_arguments(&["map['key']"], &mut sink, &shell), Ok(true));
+    assert_eq!(evaluate_arguments(&["map[other]"], &mut sink, &shell), Ok(false));
+    assert_eq!(evaluate_arguments(&["array[0]"], &mut sink, &shell), Ok(true));
+    assert_eq!(evaluate_arguments(&["array[1]"], &mut sink, &shell), Ok(false));
+    assert_eq!(evaluate_arguments(&["undefined[0]"], &mut sink, &shell), Ok(false));
+    assert_eq!(evaluate_arguments(&["[0]"], &mut sink, &shell), Ok(true));
+
     // check `exists --fn`
     let name_str = "test_function";
     let name = SmallString::from_str(name_str);
@@ -300,10 +337,11 @@ fn test_evaluate_arguments() {
         kind: Primitive::Any,
     });
     let mut statements = Vec::new();
-    statements.push(Statement::End);
+    statements.push(Statement::End(None));
     let description = "description".to_owned();
 
-    shell.functions.insert(name.clone(), Function::new(Some(description), name, args, statements));
+    let function = Function::new(Some(description), name.clone(), args, statements, None, None);
+    shell.functions.insert(name, function);
 
     assert_eq!(evaluate_arguments(&["--fn", name_str], &mut sink, &shell), Ok(true));
     shell.functions.remove(name_str);
@@ -439,10 +477,11 @@ fn test_function_is_defined() {
         kind: Primitive::Any,
     });
     let mut statements = Vec::new();
-    statements.push(Statement::End);
+    statements.push(Statement::End(None));
     let description = "description".to_owned();
 
-    shell.functions.insert(name.clone(), Function::new(Some(description), name, args, statements));
+    let function = Function::new(Some(description), name.clone(), args, statements, None, None);
+    shell.functions.insert(name, function);
 
     assert_eq!(function_is_defined(name_str, &shell), true);
     shell.functions.remove(name_str);
@@ -1,9 +1,73 @@
 use fnv::FnvHashMap;
+use parser::assignments::Primitive;
 use shell::flow_control::Function;
 use shell::status::*;
 use std::io::{self, Write};
 use types::Identifier;
 
+const HELP: &'static str = r#"NAME
+    fn - List the functions which are defined, and print their documentation or source
+
+SYNOPSIS
+    fn [ --help ] [-l | -s FUNCTION...]
+
+DESCRIPTION
+    If no arguments are given, the name of each function will be printed, along with its
+    description. A function is documented by the comments which directly follow the line that
+    begins its definition:
+
+        fn square n:int -- Square a number
+            # Prints the square of N, which must be a whole number.
+            echo $((n * n))
+        end
+
+OPTIONS
+    -l, --list
+        Print the signature, description, and documentation of each function.
+
+    -s, --source
+        Print the definition of each FUNCTION, as it was written.
+"#;
+
+/// The signature of a function, as it would be defined: its name, followed by its arguments and
+/// their types, and its description, as in `square n:int -- Square a number`.
+fn signature(name: &str, function: &Function) -> String {
+    let mut signature = name.to_owned();
+    for arg in function.get_args() {
+        signature.push(' ');
+        signature.push_str(&arg.name);
+        if arg.kind != Primitive::Any {
+            signature.push(':');
+            signature.push_str(&arg.kind.to_string());
+        }
+    }
+    if let Some(description) = function.get_description() {
+        signature.push_str(" -- ");
+        signature.push_str(description);
+    }
+    signature
+}
+
+/// The documentation of a function: the comments which directly follow the line that begins its
+/// definition, without their leading `#`.
+fn docstring(source: &str) -> Vec<&str> {
+    source
+        .lines()
+        .skip(1)
+        .map(str::trim)
+        .take_while(|line| line.starts_with('#'))
+        .map(|line| line[1..].trim())
+        .collect()
+}
+
+/// Prints the signature of a function, followed by its documentation, if any.
+pub(crate) fn describe<W: Write>(output: &mut W, name: &str, function: &Function) {
+    let _ = writeln!(output, "{}", signature(name, function));
+    for line in function.get_source().map(docstring).unwrap_or_default() {
+        let _ = writeln!(output, "    {}", line);
+    }
+}
+
 fn print_functions(functions: &FnvHashMap<Identifier, Function>) {
     let stdout = io::stdout();
     let stdout = &mut stdout.lock();
@@ -18,7 +82,96 @@ fn print_functions(functions: &FnvHashMap<Identifier, Function>) {
     }
 }
 
-pub(crate) fn fn_(functions: &mut FnvHashMap<Identifier, Function>) -> i32 {
-    print_functions(functions);
+fn list_functions(functions: &FnvHashMap<Identifier, Function>) {
+    let stdout = io::stdout();
+    let stdout = &mut stdout.lock();
+    let mut names = functions.keys().collect::<Vec<&Identifier>>();
+    names.sort();
+    for name in names {
+        describe(stdout, name, &functions[name]);
+    }
+}
+
+fn print_sources(names: &[&str], functions: &FnvHashMap<Identifier, Function>) -> i32 {
+    if names.is_empty() {
+        eprintln!("ion: fn: no functions were supplied");
+        return BAD_ARG;
+    }
+    let stdout = io::stdout();
+    let stdout = &mut stdout.lock();
+    let mut status = SUCCESS;
+    for &name in names {
+        match functions.get(name).map(Function::get_source) {
+            Some(Some(source)) => {
+                let _ = writeln!(stdout, "{}", source);
+            }
+            Some(None) => {
+                eprintln!("ion: fn: {}: the source of the function is not available", name);
+                status = FAILURE;
+            }
+            None => {
+                eprintln!("ion: fn: {}: function not found", name);
+                status = FAILURE;
+            }
+        }
+    }
+    status
+}
+
+pub(crate) fn fn_(args: &[&str], functions: &FnvHashMap<Identifier, Function>) -> i32 {
+    match args.get(1) {
+        None => print_functions(functions),
+        Some(&"--help") => {
+            let stdout = io::stdout();
+            let _ = stdout.lock().write_all(HELP.as_bytes());
+        }
+        Some(&"-l") | Some(&"--list") => list_functions(functions),
+        Some(&"-s") | Some(&"--source") => return print_sources(&args[2..], functions),
+        Some(arg) => {
+            eprintln!("ion: fn: {}: invalid option", arg);
+            return BAD_ARG;
+        }
+    }
     SUCCESS
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::assignments::KeyBuf;
+
+    #[test]
+    fn signatures() {
+        let args = vec![
+            KeyBuf {
+                name: "name".into(),
+                kind: Primitive::Any,
+            },
+            KeyBuf {
+                name: "times".into(),
+                kind: Primitive::Integer,
+            },
+            KeyBuf {
+                name: "rest".into(),
+                kind: Primitive::StrArray,
+            },
+        ];
+        let description = Some("Greet someone".to_owned());
+        let function = Function::new(description, "greet".into(), args, Vec::new(), None, None);
+        assert_eq!(
+            signature("greet", &function),
+            "greet name times:int rest:str[] -- Greet someone"
+        );
+        let function = Function::new(None, "f".into(), Vec::new(), Vec::new(), None, None);
+        assert_eq!(signature("f", &function), "f");
+    }
+
+    #[test]
+    fn docstrings() {
+        let source = "fn square n:int\n    # Prints the square of N.\n    #\n    #Twice.\n    echo \
+                      $((n * n))\n    # Not documentation.\nend";
+        assert_eq!(docstring(source), vec!["Prints the square of N.", "", "Twice."]);
+        assert!(docstring("fn f; echo one; end").is_empty());
+        assert!(docstring("fn f\n    echo # not documentation\nend").is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+use shell::Shell;
+use shell::status::*;
+use std::io::{self, Write};
+
+const HELP: &'static str = r#"NAME
+    guard - Require confirmation before commands which match a pattern are executed
+
+SYNOPSIS
+    guard [ --help ] [-r PATTERN... | PATTERN FUNCTION]
+
+DESCRIPTION
+    Registers a guard, which calls FUNCTION before executing any builtin or external command
+    that matches PATTERN. The pattern is matched against the command after its aliases and
+    variables have been expanded, such as `rm -rf /*`, and against each of its redirections,
+    such as `> /etc/*`. The function is given the command and the pattern, and the command is
+    only executed if the function succeeds. If no arguments are given, every registered guard
+    will be printed.
+
+OPTIONS
+    -r  Remove the guards with the given PATTERNs.
+"#;
+
+pub(crate) fn guard(args: &[&str], shell: &mut Shell) -> i32 {
+    let stderr = io::stderr();
+
+    match args.get(1) {
+        None => {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            for guard in shell.guards.list() {
+                let _ = writeln!(stdout, "guard '{}' {}", guard.pattern, guard.function);
+            }
+        }
+        Some(&"--help") => {
+            let stdout = io::stdout();
+            let _ = stdout.lock().write_all(HELP.as_bytes());
+        }
+        Some(&"-r") => {
+            if args.len() < 3 {
+                let _ = stderr.lock().write_all(b"ion: guard: no patterns were supplied\n");
+                return BAD_ARG;
+            }
+            let mut status = SUCCESS;
+            for pattern in &args[2..] {
+                if !shell.guards.remove(pattern) {
+                    let _ = writeln!(stderr.lock(), "ion: guard: {}: no such guard", pattern);
+                    status = FAILURE;
+                }
+            }
+            return status;
+        }
+        Some(&pattern) => {
+            let function = match args.get(2) {
+                Some(&function) if args.len() == 3 => function,
+                _ => {
+                    let message = b"ion: guard: expected a pattern and a function\n";
+                    let _ = stderr.lock().write_all(message);
+                    return BAD_ARG;
+                }
+            };
+            if let Err(why) = shell.guards.set(pattern, function) {
+                let _ = writeln!(stderr.lock(), "ion: guard: {}", why);
+                return FAILURE;
+            }
+        }
+    }
+
+    SUCCESS
+}
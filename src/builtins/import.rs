@@ -0,0 +1,87 @@
+use shell::Shell;
+use shell::imports::{is_valid_name, is_valid_namespace, Import};
+use shell::status::*;
+use std::io::{self, Write};
+
+const HELP: &'static str = r#"NAME
+    import - Source a library of functions once
+
+SYNOPSIS
+    import [ --help ] [-n] LIBRARY [as NAMESPACE]
+
+DESCRIPTION
+    Sources the file LIBRARY.ion, which is searched for within the directory of the script that
+    imports it, and then within the directories of IMPORT_PATH, which are separated by colons.
+    A library may be within a subdirectory, as in `import net/http`. Each library is only sourced
+    once, however many times it is imported. If a NAMESPACE is given, the functions which the
+    library defines are called as NAMESPACE::FUNCTION instead, and may call one another without
+    their namespace. If no arguments are given, every library which was imported will be
+    printed, along with its namespace.
+
+OPTIONS
+    -n  Import the library into a namespace of its own name, as in `math_utils::mean`.
+"#;
+
+pub(crate) fn import(args: &[&str], shell: &mut Shell) -> i32 {
+    let mut namespaced = false;
+    let mut words = Vec::new();
+    for &arg in &args[1..] {
+        match arg {
+            "--help" => {
+                let stdout = io::stdout();
+                let _ = stdout.lock().write_all(HELP.as_bytes());
+                return SUCCESS;
+            }
+            "-n" => namespaced = true,
+            _ if arg.starts_with('-') => {
+                eprintln!("ion: import: {}: invalid option", arg);
+                return BAD_ARG;
+            }
+            _ => words.push(arg),
+        }
+    }
+
+    let (name, namespace) = match (words.len(), words.get(1)) {
+        (0, _) if !namespaced => {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            for &(ref path, ref namespace) in shell.imports.imported() {
+                match *namespace {
+                    Some(ref namespace) => {
+                        let _ = writeln!(stdout, "{} as {}", path.display(), namespace);
+                    }
+                    None => {
+                        let _ = writeln!(stdout, "{}", path.display());
+                    }
+                }
+            }
+            return SUCCESS;
+        }
+        (1, _) if namespaced => (words[0], words[0].rsplit('/').next()),
+        (1, _) => (words[0], None),
+        (3, Some(&"as")) if !namespaced => (words[0], Some(words[2])),
+        _ => {
+            eprintln!("ion: import: expected a library, as in `import math [as NAMESPACE]`");
+            return BAD_ARG;
+        }
+    };
+
+    if !is_valid_name(name) {
+        eprintln!("ion: import: {}: invalid library name", name);
+        return BAD_ARG;
+    }
+    if let Some(namespace) = namespace {
+        if !is_valid_namespace(namespace) {
+            eprintln!("ion: import: {}: invalid namespace", namespace);
+            return BAD_ARG;
+        }
+    }
+
+    match shell.import(name, namespace) {
+        Ok(()) => SUCCESS,
+        Err(why) => {
+            eprintln!("ion: import: {}", why);
+            FAILURE
+        }
+    }
+}
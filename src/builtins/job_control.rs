@@ -1,10 +1,11 @@
 //! Contains the `jobs`, `disown`, `bg`, and `fg` commands that manage job control in the shell.
 
 use shell::Shell;
-use shell::job_control::{JobControl, ProcessState};
+use shell::job_control::{BackgroundProcess, JobControl, ProcessState};
 use shell::signals;
 use shell::status::*;
-use std::io::{stderr, Write};
+use std::io::{stderr, stdout, Write};
+use std::time::Duration;
 
 /// Disowns given process job IDs, and optionally marks jobs to not receive SIGHUP signals.
 /// The `-a` flag selects all jobs, `-r` selects all running jobs, and `-h` specifies to mark
@@ -74,16 +75,91 @@ pub(crate) fn disown(shell: &mut Shell, args: &[&str]) -> i32 {
     SUCCESS
 }
 
-/// Display a list of all jobs running in the background.
-pub(crate) fn jobs(shell: &mut Shell) {
-    let stderr = stderr();
-    let mut stderr = stderr.lock();
-    for (id, process) in shell.background.lock().unwrap().iter().enumerate() {
-        if process.state != ProcessState::Empty {
-            let _ =
-                writeln!(stderr, "[{}] {} {}\t{}", id, process.pid, process.state, process.name);
+/// Display a list of the jobs in the background, each in the format given by `JOBS_FORMAT`.
+/// The `-r` and `-s` flags select only the running or stopped jobs, and the `-p` flag lists only
+/// their process IDs. Jobs that have finished are reported once, and then removed from the list.
+pub(crate) fn jobs(shell: &mut Shell, args: &[&str]) -> i32 {
+    const RUNNING: u8 = 1;
+    const STOPPED: u8 = 2;
+    const PIDS: u8 = 4;
+
+    let mut flags = 0u8;
+    for &arg in args {
+        match arg {
+            "-r" => flags |= RUNNING,
+            "-s" => flags |= STOPPED,
+            "-p" => flags |= PIDS,
+            _ => {
+                eprintln!("ion: jobs: invalid option: '{}'", arg);
+                return FAILURE;
+            }
         }
     }
+
+    let format = shell.variables.get_var_or_empty("JOBS_FORMAT");
+    let stdout = stdout();
+    let mut stdout = stdout.lock();
+    for (id, process) in shell.background.lock().unwrap().iter_mut().enumerate() {
+        let selected = match process.state {
+            ProcessState::Running => flags & (RUNNING | STOPPED) != STOPPED,
+            ProcessState::Stopped => flags & (RUNNING | STOPPED) != RUNNING,
+            ProcessState::Done(_) => flags & (RUNNING | STOPPED) == 0,
+            ProcessState::Empty => false,
+        };
+        if !selected {
+            continue;
+        }
+        let _ = if flags & PIDS != 0 {
+            writeln!(stdout, "{}", process.pid)
+        } else {
+            writeln!(stdout, "{}", describe(&format, id, process))
+        };
+        if let ProcessState::Done(_) = process.state {
+            process.state = ProcessState::Empty;
+        }
+    }
+    SUCCESS
+}
+
+/// Describes a job with the given template, in which `{id}`, `{pid}`, `{pgid}`, `{state}`,
+/// `{elapsed}`, and `{command}` are replaced with its fields.
+fn describe(format: &str, id: usize, process: &BackgroundProcess) -> String {
+    let mut description = String::with_capacity(format.len() + process.name.len());
+    let mut remaining = format;
+    while let Some(start) = remaining.find('{') {
+        description.push_str(&remaining[..start]);
+        remaining = &remaining[start..];
+        let end = remaining.find('}').unwrap_or(0);
+        match &remaining[..end + 1] {
+            "{id}" => description.push_str(&id.to_string()),
+            "{pid}" => description.push_str(&process.pid.to_string()),
+            "{pgid}" => description.push_str(&process.pgid.to_string()),
+            "{state}" => description.push_str(&process.state.to_string()),
+            "{elapsed}" => description.push_str(&elapsed(process.started.elapsed())),
+            "{command}" => description.push_str(&process.name),
+            // Braces which do not name a field are written as they are.
+            _ => {
+                description.push('{');
+                remaining = &remaining[1..];
+                continue;
+            }
+        }
+        remaining = &remaining[end + 1..];
+    }
+    description.push_str(remaining);
+    description
+}
+
+/// Formats the time for which a job has run as `[[DAYS-]HOURS:]MINUTES:SECONDS`, as `ps` does.
+fn elapsed(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (days, hours) = (seconds / 86_400, seconds / 3600 % 24);
+    let clock = format!("{:02}:{:02}", seconds / 60 % 60, seconds % 60);
+    match (days, hours) {
+        (0, 0) => clock,
+        (0, _) => format!("{:02}:{}", hours, clock),
+        _ => format!("{}-{:02}:{}", days, hours, clock),
+    }
 }
 
 /// Hands control of the foreground process to the specified jobs, recording their exit status.
@@ -107,7 +183,7 @@ pub(crate) fn fg(shell: &mut Shell, args: &[&str]) -> i32 {
             // Same as above, but also resumes the stopped process in advance.
             ProcessState::Stopped => shell.set_bg_task_in_foreground(job.pid, true),
             // Informs the user that the specified job ID no longer exists.
-            ProcessState::Empty => {
+            ProcessState::Done(_) | ProcessState::Empty => {
                 let stderr = stderr();
                 let _ = writeln!(stderr.lock(), "ion: fg: job {} does not exist", njob);
                 FAILURE
@@ -149,7 +225,7 @@ pub(crate) fn bg(shell: &mut Shell, args: &[&str]) -> i32 {
                     return true;
                 }
                 ProcessState::Stopped => signals::resume(job.pid),
-                ProcessState::Empty => {
+                ProcessState::Done(_) | ProcessState::Empty => {
                     eprintln!("ion: bg: job {} does not exist", njob);
                     return true;
                 }
@@ -186,3 +262,32 @@ pub(crate) fn bg(shell: &mut Shell, args: &[&str]) -> i32 {
         SUCCESS
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn descriptions() {
+        let process = BackgroundProcess {
+            pid:           4242,
+            pgid:          4240,
+            ignore_sighup: false,
+            state:         ProcessState::Stopped,
+            name:          "sleep 10 &".into(),
+            started:       Instant::now(),
+        };
+        let format = "[{id}] {pid} {pgid} {state} {elapsed} {unknown} {command";
+        assert_eq!(describe(format, 3, &process), "[3] 4242 4240 Stopped 00:00 {unknown} {command");
+        assert_eq!(describe("{command}", 3, &process), "sleep 10 &");
+    }
+
+    #[test]
+    fn elapsed_times() {
+        assert_eq!(elapsed(Duration::from_secs(0)), "00:00");
+        assert_eq!(elapsed(Duration::from_secs(75)), "01:15");
+        assert_eq!(elapsed(Duration::from_secs(3725)), "01:02:05");
+        assert_eq!(elapsed(Duration::from_secs(180_000)), "2-02:00:00");
+    }
+}
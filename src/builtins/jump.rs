@@ -0,0 +1,76 @@
+use shell::Shell;
+use shell::frecency::Frecency;
+use shell::status::*;
+use std::io::{self, Write};
+
+const HELP: &'static str = r#"NAME
+    j - Jump to the most frecent directory which matches the patterns
+
+SYNOPSIS
+    j [ --help ] [-l] [PATTERN...]
+
+DESCRIPTION
+    Changes to the directory which matches each PATTERN in order, and which is ranked highest by
+    its frecency: how frequently, and how recently, it was visited within interactive sessions.
+    Patterns ignore case, and the last pattern must match the name of the directory itself, so
+    that `j ion` changes to ~/src/ion rather than to ~/src/ion/examples. The visits are recorded
+    within the file given by FRECENCY_FILE. If no patterns are given, every directory which was
+    visited is listed instead, along with its frecency.
+
+OPTIONS
+    -l  List the directories which match the patterns, from the least frecent, rather than
+        changing to the most frecent.
+"#;
+
+pub(crate) fn jump(args: &[&str], shell: &mut Shell) -> i32 {
+    let mut list = false;
+    let mut patterns = Vec::new();
+    for &arg in &args[1..] {
+        match arg {
+            "--help" => {
+                let stdout = io::stdout();
+                let _ = stdout.lock().write_all(HELP.as_bytes());
+                return SUCCESS;
+            }
+            "-l" => list = true,
+            _ if arg.starts_with('-') => {
+                eprintln!("ion: j: {}: invalid option", arg);
+                return BAD_ARG;
+            }
+            _ => patterns.push(arg),
+        }
+    }
+
+    if shell.frecency_file().is_none() {
+        eprintln!("ion: j: FRECENCY_FILE is not set");
+        return FAILURE;
+    }
+    let directories = shell.frecent_directories(&patterns);
+
+    if list || patterns.is_empty() {
+        // The most frecent directory is listed last, so that it is nearest to the prompt.
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        for &(frecency, ref directory) in directories.iter().rev() {
+            let _ = writeln!(stdout, "{:<10.1} {}", frecency, directory);
+        }
+        return SUCCESS;
+    }
+
+    match directories.first() {
+        Some(&(_, ref directory)) => {
+            match shell.directory_stack.cd(&["cd", directory.as_str()], &shell.variables) {
+                Ok(()) => SUCCESS,
+                Err(why) => {
+                    let stderr = io::stderr();
+                    let _ = stderr.lock().write_all(why.as_bytes());
+                    FAILURE
+                }
+            }
+        }
+        None => {
+            eprintln!("ion: j: no directory matches {}", patterns.join(" "));
+            FAILURE
+        }
+    }
+}
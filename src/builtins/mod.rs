@@ -2,23 +2,47 @@ pub mod source;
 pub mod variables;
 pub mod functions;
 pub mod calc;
+pub mod compare;
 
+mod assert;
+mod autoload;
+mod capture;
 mod conditionals;
 mod job_control;
 mod test;
 mod echo;
 mod set;
+mod shopt;
+mod table;
 mod exists;
+mod guard;
+mod import;
 mod ion;
-
+mod jump;
+mod pragma;
+mod privileges;
+mod trap;
+
+use self::assert::assert;
+use self::autoload::autoload;
+use self::capture::capture;
+use self::compare::compare;
 use self::conditionals::{contains, ends_with, starts_with};
 use self::echo::echo;
 use self::exists::exists;
-use self::functions::fn_;
+use self::functions::{describe, fn_};
+use self::guard::guard;
+use self::import::import;
 use self::ion::ion_docs;
+use self::jump::jump;
+use self::pragma::pragma;
+use self::privileges::privileges;
+use self::shopt::shopt;
 use self::source::source;
+use self::table::table;
 use self::test::test;
-use self::variables::{alias, drop_alias, drop_array, drop_variable};
+use self::trap::trap;
+use self::variables::{alias, drop_alias, drop_array, drop_variable, secret};
 
 use std::env;
 use std::error::Error;
@@ -27,8 +51,9 @@ use std::path::Path;
 
 use parser::QuoteTerminator;
 use shell::{self, FlowLogic, Shell, ShellHistory};
-use shell::job_control::{JobControl, ProcessState};
+use shell::job_control::JobControl;
 use shell::status::*;
+use shell::variables::Echo;
 use sys;
 
 macro_rules! map {
@@ -43,22 +68,35 @@ macro_rules! map {
 
 pub const BUILTINS: &'static BuiltinMap = &map!(
     "echo" => builtin_echo : "Display a line of text",
-    "cd" => builtin_cd : "Change the current directory\n    cd <path>",
+    "cd" => builtin_cd : "Change the current directory\n    cd [-N | <path>]",
     "dirs" => builtin_dirs : "Display the current directory stack",
     "pushd" => builtin_pushd : "Push a directory to the stack",
     "popd" => builtin_popd : "Pop a directory from the stack",
+    "j" => builtin_j : "Jump to the most frecent directory which matches the patterns\n    \
+        j [-l] [PATTERN...]",
     "alias" => builtin_alias : "View, set or unset aliases",
     "unalias" => builtin_unalias : "Delete an alias",
-    "fn" => builtin_fn : "Print list of functions",
-    "read" => builtin_read : "Read some variables\n    read <variable>",
+    "fn" => builtin_fn : "Print list of functions, with their documentation or source\n    \
+        fn [-l | -s FUNCTION...]",
+    "read" => builtin_read : "Read some variables, hiding secrets as they are typed\n    \
+        read [-s | -m] <variable>...",
+    "secret" => builtin_secret : "Mask the values of variables wherever they are printed\n    \
+        secret [-u] [VARIABLE]...",
     "drop" => builtin_drop : "Delete a variable",
     "matches" => builtin_matches : "Checks if a string matches a given regex",
     "not" => builtin_not : "Reverses the exit status value of the given command.",
     "set" => builtin_set : "Set or unset values of shell options and positional parameters.",
+    "shopt" => builtin_shopt : "Enable, disable, or list shell options\n    \
+        shopt [-s | -u | -q] [OPTION]...",
     "eval" => builtin_eval : "evaluates the evaluated expression",
+    "capture" => builtin_capture : "Store the output, errors, and status of a command within \
+        variables\n    capture [-o VARIABLE] [-e VARIABLE] COMMAND [ARGUMENT...]",
     "exit" => builtin_exit : "Exits the current session",
+    "return" => builtin_return : "Stops evaluating the file which is being sourced\n    \
+        return [STATUS]",
     "wait" => builtin_wait : "Waits until all running background processes have completed",
-    "jobs" => builtin_jobs : "Displays all jobs that are attached to the background",
+    "jobs" => builtin_jobs : "Displays all jobs that are attached to the background\n    \
+        jobs [-r | -s] [-p]",
     "bg" => builtin_bg : "Resumes a stopped background process",
     "fg" => builtin_fg : "Resumes and sets a background process as the active process",
     "suspend" => builtin_suspend : "Suspends the shell with a SIGTSTOP signal",
@@ -67,12 +105,29 @@ pub const BUILTINS: &'static BuiltinMap = &map!(
     "history" => builtin_history : "Display a log of all commands previously executed",
     "source" => builtin_source : "Evaluate the file following the command or re-initialize the \
         init file",
+    "autoload" => builtin_autoload : "List and load the functions which are defined within \
+        FPATH\n    autoload [-a] [-r] [FUNCTION...]",
+    "import" => builtin_import : "Source a library of functions once\n    import [-n] LIBRARY \
+        [as NAMESPACE]",
     "test" => builtin_test : "Performs tests on files and text",
+    "trap" => builtin_trap : "Executes commands when the shell receives a signal or event\n    \
+        trap [HANDLER | -] [EVENT]...",
+    "guard" => builtin_guard : "Requires confirmation before commands which match a pattern are \
+        executed\n    guard [-r PATTERN... | PATTERN FUNCTION]",
     "calc" => builtin_calc : "Calculate a mathematical expression",
+    "compare" => builtin_compare : "Compare two values as numbers or strings\n    \
+        compare LEFT OPERATOR RIGHT [:TYPE]",
+    "assert" => builtin_assert : "Fail with a description of an expression which does not \
+        hold\n    assert [-m MESSAGE] EXPRESSION",
+    "table" => builtin_table : "Print rows of delimited fields as an aligned table",
+    "pragma" => builtin_pragma : "Configure how the current script is checked\n    \
+        pragma warn [WARNING] [LEVEL]",
+    "privileges" => builtin_privileges : "Query, verify, or drop the privileges of the shell\n    \
+        privileges [show | require USER | refuse USER | drop USER [GROUP]]",
     "true" => builtin_true : "Do nothing, successfully",
     "false" => builtin_false : "Do nothing, unsuccessfully",
-    "help" => builtin_help : "Display helpful information about a given command or list commands \
-        if none specified\n    help <command>",
+    "help" => builtin_help : "Display helpful information about a given command or function, or \
+        list commands if none specified\n    help <command>",
     "and" => builtin_and : "Execute the command if the shell's previous status is success",
     "or" => builtin_or : "Execute the command if the shell's previous status is failure",
     "starts-with" => starts_with : "Evaluates if the supplied argument starts with a given string",
@@ -83,6 +138,11 @@ pub const BUILTINS: &'static BuiltinMap = &map!(
     "ion-docs" => ion_docs : "Opens the Ion manual"
 );
 
+/// Builtins which act upon processes other than the shell, and which are therefore printed
+/// instead of being executed while `dryrun` is enabled.
+pub(crate) const CONTROLS_PROCESSES: [&'static str; 5] =
+    ["bg", "disown", "fg", "ion-docs", "suspend"];
+
 /// Structure which represents a Terminal's command.
 /// This command structure contains a name, and the code which run the
 /// functionnality associated to this one, with zero, one or several argument(s).
@@ -155,17 +215,27 @@ fn builtin_popd(args: &[&str], shell: &mut Shell) -> i32 {
 }
 
 fn builtin_alias(args: &[&str], shell: &mut Shell) -> i32 {
-    let args_str = args[1..].join(" ");
-    alias(&mut shell.variables, &args_str)
+    alias(&mut shell.variables, &args[1..])
 }
 
 fn builtin_unalias(args: &[&str], shell: &mut Shell) -> i32 {
     drop_alias(&mut shell.variables, args)
 }
 
-fn builtin_fn(_: &[&str], shell: &mut Shell) -> i32 { fn_(&mut shell.functions) }
+fn builtin_fn(args: &[&str], shell: &mut Shell) -> i32 { fn_(args, &shell.functions) }
+
+fn builtin_read(args: &[&str], shell: &mut Shell) -> i32 {
+    // Secrets are either not echoed at all, or echoed as asterisks.
+    let echo = match args.get(1) {
+        Some(&"-s") => Some(Echo::Silent),
+        Some(&"-m") => Some(Echo::Masked),
+        _ => None,
+    };
+    let names = if echo.is_some() { &args[2..] } else { &args[1..] };
+    shell.variables.read(names, echo)
+}
 
-fn builtin_read(args: &[&str], shell: &mut Shell) -> i32 { shell.variables.read(args) }
+fn builtin_secret(args: &[&str], shell: &mut Shell) -> i32 { secret(&mut shell.variables, args) }
 
 fn builtin_drop(args: &[&str], shell: &mut Shell) -> i32 {
     if args.len() >= 2 && args[1] == "-a" {
@@ -177,7 +247,11 @@ fn builtin_drop(args: &[&str], shell: &mut Shell) -> i32 {
 
 fn builtin_not(args: &[&str], shell: &mut Shell) -> i32 {
     let cmd = args[1..].join(" ");
+    shell.conditions += 1;
     shell.on_command(&cmd);
+    shell.conditions -= 1;
+    // The negated status is itself the result of a condition.
+    shell.conditional_status = true;
     match shell.previous_status {
         SUCCESS => FAILURE,
         FAILURE => SUCCESS,
@@ -186,11 +260,26 @@ fn builtin_not(args: &[&str], shell: &mut Shell) -> i32 {
 }
 
 fn builtin_set(args: &[&str], shell: &mut Shell) -> i32 { set::set(args, shell) }
+fn builtin_shopt(args: &[&str], shell: &mut Shell) -> i32 { shopt(args, shell) }
+fn builtin_trap(args: &[&str], shell: &mut Shell) -> i32 { trap(args, shell) }
+fn builtin_guard(args: &[&str], shell: &mut Shell) -> i32 { guard(args, shell) }
+fn builtin_table(args: &[&str], shell: &mut Shell) -> i32 { table(args, shell) }
+fn builtin_compare(args: &[&str], shell: &mut Shell) -> i32 { compare(args, shell) }
+fn builtin_assert(args: &[&str], shell: &mut Shell) -> i32 { assert(args, shell) }
+fn builtin_pragma(args: &[&str], shell: &mut Shell) -> i32 { pragma(args, shell) }
+
+fn builtin_privileges(args: &[&str], shell: &mut Shell) -> i32 { privileges(args, shell) }
+
 fn builtin_eval(args: &[&str], shell: &mut Shell) -> i32 {
     let evaluated_command = args[1..].join(" ");
     let mut buffer = QuoteTerminator::new(evaluated_command);
-    if buffer.check_termination() {
+    if shell.depth >= shell.recursion_limit() {
+        eprintln!("ion: eval: maximum recursion depth exceeded");
+        FAILURE
+    } else if buffer.check_termination() {
+        shell.depth += 1;
         shell.on_command(&buffer.consume());
+        shell.depth -= 1;
         shell.previous_status
     } else {
         let stderr = io::stderr();
@@ -199,11 +288,20 @@ fn builtin_eval(args: &[&str], shell: &mut Shell) -> i32 {
         FAILURE
     }
 }
+fn builtin_capture(args: &[&str], shell: &mut Shell) -> i32 { capture(args, shell) }
+
 fn builtin_history(args: &[&str], shell: &mut Shell) -> i32 { shell.print_history(args) }
 
+fn builtin_autoload(args: &[&str], shell: &mut Shell) -> i32 { autoload(args, shell) }
+
+fn builtin_import(args: &[&str], shell: &mut Shell) -> i32 { import(args, shell) }
+
+fn builtin_j(args: &[&str], shell: &mut Shell) -> i32 { jump(args, shell) }
+
 fn builtin_source(args: &[&str], shell: &mut Shell) -> i32 {
     match source(shell, args) {
-        Ok(()) => SUCCESS,
+        // The status of the file is that of its last command, or the status which it returned.
+        Ok(()) => shell.previous_status,
         Err(why) => {
             let stderr = io::stderr();
             let mut stderr = stderr.lock();
@@ -238,17 +336,7 @@ fn builtin_test(args: &[&str], _: &mut Shell) -> i32 {
     }
 }
 
-fn builtin_calc(args: &[&str], _: &mut Shell) -> i32 {
-    match calc::calc(&args[1..]) {
-        Ok(()) => SUCCESS,
-        Err(why) => {
-            let stderr = io::stderr();
-            let mut stderr = stderr.lock();
-            let _ = writeln!(stderr, "{}", why);
-            FAILURE
-        }
-    }
-}
+fn builtin_calc(args: &[&str], shell: &mut Shell) -> i32 { calc::calc(args, shell) }
 
 fn builtin_true(_: &[&str], _: &mut Shell) -> i32 { SUCCESS }
 
@@ -259,10 +347,7 @@ fn builtin_wait(_: &[&str], shell: &mut Shell) -> i32 {
     SUCCESS
 }
 
-fn builtin_jobs(_: &[&str], shell: &mut Shell) -> i32 {
-    job_control::jobs(shell);
-    SUCCESS
-}
+fn builtin_jobs(args: &[&str], shell: &mut Shell) -> i32 { job_control::jobs(shell, &args[1..]) }
 
 fn builtin_bg(args: &[&str], shell: &mut Shell) -> i32 { job_control::bg(shell, &args[1..]) }
 
@@ -287,6 +372,8 @@ fn builtin_help(args: &[&str], shell: &mut Shell) -> i32 {
                 let _ = stdout.write_all(bltin.help.as_bytes());
                 let _ = stdout.write_all(b"\n");
             }
+        } else if let Some(function) = shell.functions.get(*command) {
+            describe(&mut stdout, command, function);
         } else {
             let _ = stdout.write_all(b"Command helper not found [run 'help']...");
             let _ = stdout.write_all(b"\n");
@@ -306,7 +393,7 @@ fn builtin_help(args: &[&str], shell: &mut Shell) -> i32 {
 fn builtin_exit(args: &[&str], shell: &mut Shell) -> i32 {
     // Kill all active background tasks before exiting the shell.
     for process in shell.background.lock().unwrap().iter() {
-        if process.state != ProcessState::Empty {
+        if !process.is_finished() {
             let _ = sys::kill(process.pid, sys::SIGTERM);
         }
     }
@@ -314,6 +401,27 @@ fn builtin_exit(args: &[&str], shell: &mut Shell) -> i32 {
     shell.exit(args.get(1).and_then(|status| status.parse::<i32>().ok()).unwrap_or(previous_status))
 }
 
+fn builtin_return(args: &[&str], shell: &mut Shell) -> i32 {
+    if shell.sourcing == 0 {
+        eprintln!("ion: return: may only be used within a sourced file");
+        return FAILURE;
+    }
+    let status = match args.get(1) {
+        Some(status) => match status.parse::<i32>() {
+            Ok(status) => status,
+            Err(_) => {
+                eprintln!("ion: return: {}: expected a number", status);
+                return BAD_ARG;
+            }
+        },
+        None => shell.previous_status,
+    };
+    // The file is interrupted, so that every block and function within it is left at once.
+    shell.returning = Some(status);
+    shell.break_flow = true;
+    status
+}
+
 use regex::Regex;
 fn builtin_matches(args: &[&str], _: &mut Shell) -> i32 {
     if args[1..].len() != 2 {
@@ -0,0 +1,70 @@
+use shell::Shell;
+use shell::status::*;
+use shell::warnings::WARNINGS;
+use std::io::{self, Write};
+
+const HELP: &'static str = r#"NAME
+    pragma - Configure how the current script is checked
+
+SYNOPSIS
+    pragma [ --help ] warn [WARNING] [LEVEL]
+
+DESCRIPTION
+    Sets the level of the given WARNING, or of every warning if none is given, until the end of
+    the script which contains the pragma. Without a LEVEL, the level of each warning is listed.
+
+    The LEVEL is one of:
+
+        off     the warning is ignored
+        warn    the warning is printed to stderr
+        error   the warning is reported as an error, and the offending code is not executed
+
+WARNINGS
+    deprecated
+        Syntax which is deprecated, such as backtick command substitutions. Enabled by default.
+    undefined
+        A reference to a variable which has not been set.
+    shadow
+        A function which has the same name as a builtin. Enabled by default.
+"#;
+
+pub(crate) fn pragma(args: &[&str], shell: &mut Shell) -> i32 {
+    match args.get(1) {
+        Some(&"--help") => {
+            let stdout = io::stdout();
+            let _ = stdout.lock().write_all(HELP.as_bytes());
+            SUCCESS
+        }
+        Some(&"warn") => {
+            let result = match args.len() {
+                2 => {
+                    let stdout = io::stdout();
+                    let mut stdout = stdout.lock();
+                    for &warning in &WARNINGS {
+                        let level = shell.warnings.level(warning);
+                        let _ = writeln!(stdout, "{:<12}{}", warning.name(), level);
+                    }
+                    Ok(())
+                }
+                3 => shell.warnings.set(None, args[2]),
+                4 => shell.warnings.set(Some(args[2]), args[3]),
+                _ => Err("expected a warning and a level".into()),
+            };
+            match result {
+                Ok(()) => SUCCESS,
+                Err(why) => {
+                    eprintln!("ion: pragma: warn: {}", why);
+                    BAD_ARG
+                }
+            }
+        }
+        Some(name) => {
+            eprintln!("ion: pragma: {}: no such pragma", name);
+            BAD_ARG
+        }
+        None => {
+            eprintln!("ion: pragma: expected a pragma, such as `warn`");
+            BAD_ARG
+        }
+    }
+}
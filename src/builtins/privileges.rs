@@ -0,0 +1,194 @@
+use shell::Shell;
+use shell::status::*;
+use std::env;
+use std::io::{self, Write};
+use std::process;
+use sys::privileges;
+use sys::variables::get_user_home;
+
+const HELP: &'static str = r#"NAME
+    privileges - Query, verify, or drop the privileges of the shell
+
+SYNOPSIS
+    privileges [ --help ] [show | require USER | refuse USER | drop USER [GROUP]]
+
+DESCRIPTION
+    Without a subcommand, prints the real and effective users and groups of the shell, along with
+    its supplementary groups. Users and groups may be given by name or by ID.
+
+SUBCOMMANDS
+    show
+        Prints the users and groups of the shell, in the manner of `id`.
+    require USER
+        Fails unless the effective user of the shell is USER.
+    refuse USER
+        Fails if the effective user of the shell is USER, as in `privileges refuse root`.
+    drop USER [GROUP]
+        Permanently changes the user, the group, and the supplementary groups of the shell to
+        those of USER, or to GROUP if it is given, and sets the USER and HOME variables. This
+        requires root, and cannot be undone. If root privileges could be regained afterwards,
+        the shell exits rather than continuing.
+"#;
+
+/// Describes an ID by its number and, if it has one, its name, as in `0(root)`.
+fn describe(id: u32, name: Option<String>) -> String {
+    match name {
+        Some(name) => format!("{}({})", id, name),
+        None => id.to_string(),
+    }
+}
+
+/// Describes the users and groups of the shell in the manner of `id`, given the real and
+/// effective user and group IDs. Effective IDs are only shown if they differ from real IDs.
+fn identity<U, G>(ids: (u32, u32, u32, u32), groups: &[u32], user: U, group: G) -> String
+    where U: Fn(u32) -> Option<String>,
+          G: Fn(u32) -> Option<String>
+{
+    let (uid, euid, gid, egid) = ids;
+    let (user_id, group_id) = (describe(uid, user(uid)), describe(gid, group(gid)));
+    let mut identity = format!("uid={} gid={}", user_id, group_id);
+    if euid != uid {
+        identity.push_str(&format!(" euid={}", describe(euid, user(euid))));
+    }
+    if egid != gid {
+        identity.push_str(&format!(" egid={}", describe(egid, group(egid))));
+    }
+    if !groups.is_empty() {
+        let groups = groups.iter().map(|&gid| describe(gid, group(gid))).collect::<Vec<String>>();
+        identity.push_str(&format!(" groups={}", groups.join(",")));
+    }
+    identity
+}
+
+/// Resolves a user, given by name or by ID, to its ID and to the ID of its primary group.
+fn resolve_user(user: &str) -> Result<(u32, Option<u32>), String> {
+    match user.parse::<u32>() {
+        Ok(uid) => {
+            let name = privileges::user_name(uid);
+            let primary = name.and_then(|name| privileges::user_by_name(&name));
+            Ok((uid, primary.map(|(_, gid)| gid)))
+        }
+        Err(_) => privileges::user_by_name(user)
+            .map(|(uid, gid)| (uid, Some(gid)))
+            .ok_or_else(|| format!("{}: no such user", user)),
+    }
+}
+
+fn resolve_group(group: &str) -> Result<u32, String> {
+    group
+        .parse::<u32>()
+        .ok()
+        .or_else(|| privileges::group_by_name(group))
+        .ok_or_else(|| format!("{}: no such group", group))
+}
+
+fn show() -> Result<(), String> {
+    let groups = privileges::groups().map_err(|why| why.to_string())?;
+    let identity =
+        identity(privileges::ids(), &groups, privileges::user_name, privileges::group_name);
+    let stdout = io::stdout();
+    let _ = writeln!(stdout.lock(), "{}", identity);
+    Ok(())
+}
+
+/// Checks whether the effective user of the shell is, or is not, the given user.
+fn check(user: &str, required: bool) -> Result<(), String> {
+    let (uid, _) = resolve_user(user)?;
+    let (_, euid, _, _) = privileges::ids();
+    match (euid == uid, required) {
+        (true, true) | (false, false) => Ok(()),
+        (false, true) => Err(format!(
+            "require: the effective user is {}, not {}",
+            describe(euid, privileges::user_name(euid)),
+            user
+        )),
+        (true, false) => Err(format!("refuse: the effective user is {}", user)),
+    }
+}
+
+fn drop_privileges(user: &str, group: Option<&str>) -> Result<(), String> {
+    let (uid, primary) = resolve_user(user)?;
+    let gid = match group {
+        Some(group) => resolve_group(group)?,
+        None => primary.ok_or_else(|| format!("drop: {}: a group must be given", user))?,
+    };
+    let name = privileges::user_name(uid);
+    privileges::set_groups(name.as_ref().map(|name| name.as_str()), gid)
+        .and_then(|_| privileges::set_ids(uid, gid))
+        .map_err(|why| format!("drop: {}: {}", user, why))?;
+
+    // A shell which may still become root must not execute the remainder of the script.
+    if uid != 0 && privileges::can_regain_root() {
+        eprintln!("ion: privileges: drop: {}: root privileges could be regained", user);
+        process::exit(FAILURE);
+    }
+    if let Some(name) = name {
+        if let Some(home) = get_user_home(&name) {
+            env::set_var("HOME", home);
+        }
+        env::set_var("USER", name);
+    }
+    Ok(())
+}
+
+pub(crate) fn privileges(args: &[&str], _: &mut Shell) -> i32 {
+    let result = match (args.get(1), args.len()) {
+        (Some(&"--help"), _) => {
+            let stdout = io::stdout();
+            let _ = stdout.lock().write_all(HELP.as_bytes());
+            return SUCCESS;
+        }
+        (None, _) | (Some(&"show"), 2) => show(),
+        (Some(&"require"), 3) => check(args[2], true),
+        (Some(&"refuse"), 3) => check(args[2], false),
+        (Some(&"drop"), 3) => drop_privileges(args[2], None),
+        (Some(&"drop"), 4) => drop_privileges(args[2], Some(args[3])),
+        (Some(&"show"), _) => {
+            eprintln!("ion: privileges: show: expected no arguments");
+            return BAD_ARG;
+        }
+        (Some(&"require"), _) | (Some(&"refuse"), _) | (Some(&"drop"), _) => {
+            eprintln!("ion: privileges: {}: expected a user", args[1]);
+            return BAD_ARG;
+        }
+        (Some(name), _) => {
+            eprintln!("ion: privileges: {}: no such subcommand", name);
+            return BAD_ARG;
+        }
+    };
+    match result {
+        Ok(()) => SUCCESS,
+        Err(why) => {
+            eprintln!("ion: privileges: {}", why);
+            FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(id: u32) -> Option<String> {
+        match id {
+            0 => Some("root".into()),
+            1000 => Some("deploy".into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn identities() {
+        assert_eq!(identity((0, 0, 0, 0), &[], name, name), "uid=0(root) gid=0(root)");
+        assert_eq!(
+            identity((1000, 0, 1000, 1000), &[1000, 27], name, name),
+            "uid=1000(deploy) gid=1000(deploy) euid=0(root) groups=1000(deploy),27"
+        );
+    }
+
+    #[test]
+    fn ids_are_resolved() {
+        assert_eq!(resolve_group("27"), Ok(27));
+        assert_eq!(resolve_user("4294967295").map(|(uid, _)| uid), Ok(4294967295));
+    }
+}
@@ -1,6 +1,8 @@
-use liner::KeyBindings;
+use super::shopt::print_options;
 use shell::Shell;
+use shell::editor::KeyBindings;
 use shell::flags::*;
+use shell::status::*;
 use std::io::{self, Write};
 use std::iter;
 
@@ -8,18 +10,68 @@ const HELP: &'static str = r#"NAME
     set - Set or unset values of shell options and positional parameters.
 
 SYNOPSIS
-    set [ --help ] [-e | +e] [-x | +x] [-o [vi | emacs]] [- | --] [STRING]...
+    set [ --help ] [-e | +e] [-u | +u] [-x | +x] [-o | +o OPTION] [- | --] [STRING]...
 
 DESCRIPTION
     Shell options may be set using the '-' character, and unset using the '+' character.
 
 OPTIONS
-    -e  Exit immediately if a command exits with a non-zero status.
+    -e  Exit immediately if a command exits with a non-zero status. Commands evaluated as
+        conditions, such as the expression of an `if` statement or the left-hand side of
+        `&&` and `||`, will not cause the shell to exit.
 
-    -o  Specifies that an argument will follow that sets the key map.
-        The keymap argument may be either `vi` or `emacs`.
+    -o  Specifies that an argument will follow that sets the key map, or the given option.
+        The keymap argument may be either `vi` or `emacs`. If no argument follows, every
+        option is listed along with its state. The following options may be given by name:
 
-    -x  Specifies that commands will be printed as they are executed.
+            errexit   Equivalent to `-e`.
+            nounset   Equivalent to `-u`.
+            failglob  Commands will fail if a glob does not match any paths.
+            xtrace    Equivalent to `-x`.
+            deprecations
+                      Warn when deprecated syntax, such as backtick command
+                      substitutions, is used, at the level which is given to the
+                      `deprecated` warning by `pragma warn`. Enabled by default.
+            keepnewlines
+                      Trailing newlines will be kept in the output of a quoted
+                      process expansion, such as "$(cmd)".
+            wordsplit Each word in the output of an unquoted process expansion
+                      will be supplied as a separate argument, as in POSIX shells.
+            typedenv  Environment variables whose values are encoded as arrays,
+                      such as `[a b c]`, or as maps, such as `{key=value}`, are
+                      imported as array and map variables of the same name.
+            pipefail  The status of a pipeline will be that of the last command
+                      within it to fail, rather than that of its last command.
+            noclobber The `>` redirection will not overwrite existing files.
+            noexec    Commands will be read, but not executed.
+            dryrun    External commands, redirections to files, and builtins which
+                      control other processes will be printed to stderr instead of
+                      being executed.
+            history   Interactive commands will be recorded in the history.
+                      Enabled by default.
+            sandbox   `$RANDOM`, the current time, `$USER`, and `$HOSTNAME` will
+                      have fixed values, and external commands which were given
+                      fake commands by `ion --sandbox=FILE` will be replaced by them.
+            cleanenv  External commands will only receive the environment variables
+                      which are allowed by the `ENV_ALLOW` array, or the default ones.
+            audit     Every executed command, including those of scripts, will be
+                      appended to the file given by `AUDIT_FILE`, along with the time,
+                      the user, the working directory, and its exit status.
+            title     The terminal's title will be set to each interactive command
+                      while it is executed, and to the current directory at the prompt.
+            reportcwd The current directory will be reported to the terminal at each
+                      prompt with OSC 7, so that new tabs may be opened within it.
+            marks     Each prompt, and the output of each command, will be marked with
+                      OSC 133, so that the terminal may jump between commands.
+            autocd    A command which names a directory, rather than a function,
+                      builtin, or program, will change into it.
+
+    -u  Referencing a variable which has not been set is an error. The current command will
+        not be executed, and a non-interactive shell will exit.
+
+    -x  Specifies that commands will be printed to stderr as they are executed, after
+        they have been expanded. Each command is prefixed with the expansion of `PS4`,
+        whose first character is repeated once more for each level of function nesting.
 
     --  Following arguments will be set as positional arguments in the shell.
         If no argument are supplied, arguments will be unset.
@@ -63,24 +115,25 @@ pub(crate) fn set(args: &[&str], shell: &mut Shell) -> i32 {
                     b'e' => shell.flags |= ERR_EXIT,
                     b'o' => match args_iter.next() {
                         Some(&mode) if mode == "vi" => {
-                            if let Some(context) = shell.context.as_mut() {
-                                context.key_bindings = KeyBindings::Vi;
+                            if let Some(editor) = shell.editor.as_mut() {
+                                editor.set_key_bindings(KeyBindings::Vi);
                             }
                         }
                         Some(&mode) if mode == "emacs" => {
-                            if let Some(context) = shell.context.as_mut() {
-                                context.key_bindings = KeyBindings::Emacs;
+                            if let Some(editor) = shell.editor.as_mut() {
+                                editor.set_key_bindings(KeyBindings::Emacs);
                             }
                         }
-                        Some(_) => {
-                            let _ = stderr.lock().write_all(b"set: invalid keymap\n");
-                            return 0;
-                        }
+                        Some(&option) => if !shell.set_option(option, true) {
+                            let _ = stderr.lock().write_all(b"set: invalid option\n");
+                            return BAD_ARG;
+                        },
                         None => {
-                            let _ = stderr.lock().write_all(b"set: no keymap given\n");
+                            print_options(shell);
                             return 0;
                         }
                     },
+                    b'u' => shell.flags |= NO_UNSET,
                     b'x' => shell.flags |= PRINT_COMMS,
                     _ => return 0,
                 }
@@ -88,8 +141,19 @@ pub(crate) fn set(args: &[&str], shell: &mut Shell) -> i32 {
         } else if arg.starts_with('+') {
             for flag in arg.bytes().skip(1) {
                 match flag {
-                    b'e' => shell.flags &= 255 ^ ERR_EXIT,
-                    b'x' => shell.flags &= 255 ^ PRINT_COMMS,
+                    b'e' => shell.flags &= !ERR_EXIT,
+                    b'o' => match args_iter.next() {
+                        Some(&option) => if !shell.set_option(option, false) {
+                            let _ = stderr.lock().write_all(b"set: invalid option\n");
+                            return BAD_ARG;
+                        },
+                        None => {
+                            print_options(shell);
+                            return 0;
+                        }
+                    },
+                    b'u' => shell.flags &= !NO_UNSET,
+                    b'x' => shell.flags &= !PRINT_COMMS,
                     _ => return 0,
                 }
             }
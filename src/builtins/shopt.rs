@@ -0,0 +1,70 @@
+use shell::Shell;
+use shell::options::{self, OPTIONS};
+use shell::status::*;
+use std::io::{self, Write};
+
+const HELP: &'static str = r#"NAME
+    shopt - Enable, disable, or list shell options
+
+SYNOPSIS
+    shopt [ --help ] [-s | -u | -q] [OPTION]...
+
+DESCRIPTION
+    Toggles the options which control the behavior of the shell. If no options are given,
+    every option will be listed along with whether it is enabled. Options may also be
+    toggled with `set -o` and `set +o`, and queried from scripts with `$option(OPTION)`.
+
+OPTIONS
+    -s  Enable each of the given options.
+
+    -u  Disable each of the given options.
+
+    -q  Print nothing, and succeed only if each of the given options is enabled.
+"#;
+
+/// Prints the name of each of the given options along with whether it is enabled.
+fn print(shell: &Shell, names: &[&str]) {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for name in names {
+        if let Some(option) = options::find(name) {
+            let state = if shell.flags & option.flag != 0 { "on" } else { "off" };
+            let _ = writeln!(stdout, "{:<14}{:<5}{}", option.name, state, option.description);
+        }
+    }
+}
+
+/// Prints every option along with whether it is enabled.
+pub(crate) fn print_options(shell: &Shell) {
+    print(shell, &OPTIONS.iter().map(|option| option.name).collect::<Vec<&str>>());
+}
+
+pub(crate) fn shopt(args: &[&str], shell: &mut Shell) -> i32 {
+    let (action, names) = match args.get(1) {
+        Some(&"--help") => {
+            let stdout = io::stdout();
+            let _ = stdout.lock().write_all(HELP.as_bytes());
+            return SUCCESS;
+        }
+        Some(&flag) if flag == "-s" || flag == "-u" || flag == "-q" => (flag, &args[2..]),
+        _ => ("", &args[1..]),
+    };
+
+    if let Some(name) = names.iter().find(|name| options::find(name).is_none()) {
+        eprintln!("ion: shopt: {}: no such option", name);
+        return BAD_ARG;
+    }
+
+    match action {
+        "-s" | "-u" => for name in names {
+            shell.set_option(name, action == "-s");
+        },
+        "-q" => {
+            let enabled = names.iter().all(|name| shell.get_option(name) == Some(true));
+            return if enabled { SUCCESS } else { FAILURE };
+        }
+        _ if names.is_empty() => print_options(shell),
+        _ => print(shell, names),
+    }
+    SUCCESS
+}
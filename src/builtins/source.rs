@@ -1,8 +1,11 @@
+use parser::Location;
 use shell::{FlowLogic, Shell};
 use std::fs::File;
 use std::io::Read;
+use types::Array;
 
-/// Evaluates the given file and returns 'SUCCESS' if it succeeds.
+/// Evaluates the given file and returns 'SUCCESS' if it succeeds. Any arguments which follow the
+/// file replace `@args` while it is evaluated, and `return` stops it from being evaluated.
 pub(crate) fn source(shell: &mut Shell, arguments: &[&str]) -> Result<(), String> {
     match arguments.get(1) {
         Some(argument) => if let Ok(mut file) = File::open(&argument) {
@@ -11,10 +14,44 @@ pub(crate) fn source(shell: &mut Shell, arguments: &[&str]) -> Result<(), String
             file.read_to_string(&mut command_list)
                 .map_err(|message| format!("ion: {}: failed to read {}\n", message, argument))
                 .map(|_| {
-                    for command in command_list.lines() {
+                    let location = shell.location.take();
+                    // Pragmas only apply to the script which contains them.
+                    let warnings = shell.warnings;
+                    // The file is given its own arguments, with its path as `$0`, if any follow it.
+                    let args = if arguments.len() > 2 {
+                        let args: Array = arguments[1..].iter().map(|&arg| arg.into()).collect();
+                        let previous = shell.variables.get_array("args").cloned();
+                        shell.variables.set_array("args", args);
+                        Some(previous)
+                    } else {
+                        None
+                    };
+
+                    shell.sourcing += 1;
+                    for (id, command) in command_list.lines().enumerate() {
+                        shell.location = Some(Location::new(*argument, id + 1));
                         shell.on_command(command);
+                        if shell.returning.is_some() {
+                            break;
+                        }
+                    }
+                    shell.sourcing -= 1;
+                    if let Some(status) = shell.returning.take() {
+                        // Only the file which returned was interrupted.
+                        shell.break_flow = false;
+                        shell.previous_status = status;
+                        shell.variables.set_var("?", &status.to_string());
+                    }
+
+                    match args {
+                        Some(Some(previous)) => shell.variables.set_array("args", previous),
+                        Some(None) => {
+                            shell.variables.unset_array("args");
+                        }
+                        None => (),
                     }
-                    ()
+                    shell.location = location;
+                    shell.warnings = warnings;
                 })
         } else {
             Err(format!("ion: failed to open {}\n", argument))
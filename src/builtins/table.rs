@@ -0,0 +1,211 @@
+use shell::Shell;
+use shell::status::*;
+use std::io::{self, BufRead, Write};
+use std::iter;
+use unicode_segmentation::UnicodeSegmentation;
+
+const HELP: &'static str = r#"NAME
+    table - Print rows of delimited fields as an aligned table
+
+SYNOPSIS
+    table [ --help ] [-d DELIMITER] [-H HEADERS] [-w WIDTH] [-m MAP | ROW...]
+
+DESCRIPTION
+    Splits each ROW into fields, and prints the fields of every row in aligned columns. If no
+    rows are given, they are read from standard input, one per line. Columns in which every
+    field is a number are aligned to the right, and all others to the left.
+
+OPTIONS
+    -d DELIMITER  Split rows by DELIMITER, rather than by runs of whitespace.
+
+    -H HEADERS    Print HEADERS, which are split like a row, above the table, and underline them.
+
+    -w WIDTH      Truncate fields which are wider than WIDTH, ending them with an ellipsis.
+
+    -m MAP        Print the keys and values of the map variable MAP as two columns, which are
+                  sorted by key.
+"#;
+
+fn width(field: &str) -> usize { UnicodeSegmentation::graphemes(field, true).count() }
+
+fn is_number(field: &str) -> bool { field.parse::<f64>().is_ok() }
+
+/// Splits a row into its fields, by the delimiter if one is given, or else by whitespace.
+fn split(row: &str, delimiter: Option<&str>) -> Vec<String> {
+    match delimiter {
+        Some(delimiter) => row.split(delimiter).map(|field| field.trim().to_owned()).collect(),
+        None => row.split_whitespace().map(String::from).collect(),
+    }
+}
+
+/// Shortens the field to the maximum width, replacing its last grapheme with an ellipsis.
+fn truncate(field: &str, max_width: usize) -> String {
+    if max_width == 0 || width(field) <= max_width {
+        return field.to_owned();
+    }
+    let mut truncated = UnicodeSegmentation::graphemes(field, true)
+        .take(max_width - 1)
+        .collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+/// Aligns the fields of each row into columns, returning each line of the table. Fields are
+/// separated by two spaces, and trailing whitespace is removed from each line.
+fn format_table(
+    headers: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+    max_width: Option<usize>,
+) -> Vec<String> {
+    let truncate_all = |row: Vec<String>| {
+        row.iter().map(|field| truncate(field, max_width.unwrap_or(0))).collect::<Vec<_>>()
+    };
+    let headers = headers.map(&truncate_all);
+    let rows = rows.into_iter().map(&truncate_all).collect::<Vec<_>>();
+
+    let columns = headers
+        .iter()
+        .chain(rows.iter())
+        .map(|row| row.len())
+        .max()
+        .unwrap_or(0);
+    let mut widths = vec![0; columns];
+    let mut numeric = vec![true; columns];
+    for row in headers.iter().chain(rows.iter()) {
+        for (id, field) in row.iter().enumerate() {
+            widths[id] = widths[id].max(width(field));
+        }
+    }
+    for row in &rows {
+        for (id, field) in row.iter().enumerate() {
+            numeric[id] &= is_number(field);
+        }
+    }
+
+    let align = |row: &[String]| {
+        let mut line = String::new();
+        for (id, field) in row.iter().enumerate() {
+            if id != 0 {
+                line.push_str("  ");
+            }
+            let padding = iter::repeat(' ').take(widths[id] - width(field));
+            if numeric[id] {
+                line.extend(padding);
+                line.push_str(field);
+            } else {
+                line.push_str(field);
+                line.extend(padding);
+            }
+        }
+        line.trim_right().to_owned()
+    };
+
+    let mut lines = Vec::with_capacity(rows.len() + 2);
+    if let Some(ref headers) = headers {
+        lines.push(align(headers));
+        let total = widths.iter().sum::<usize>() + 2 * columns.saturating_sub(1);
+        lines.push(iter::repeat('-').take(total).collect());
+    }
+    lines.extend(rows.iter().map(|row| align(row)));
+    lines
+}
+
+pub(crate) fn table(args: &[&str], shell: &mut Shell) -> i32 {
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+    let (mut delimiter, mut headers, mut max_width, mut map) = (None, None, None, None);
+    let mut args = args[1..].iter();
+    let mut rows = Vec::new();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "--help" => {
+                let stdout = io::stdout();
+                let _ = stdout.lock().write_all(HELP.as_bytes());
+                return SUCCESS;
+            }
+            "-d" | "-H" | "-w" | "-m" => {
+                let value = match args.next() {
+                    Some(&value) => value,
+                    None => {
+                        let _ = writeln!(stderr, "ion: table: {} requires an argument", arg);
+                        return BAD_ARG;
+                    }
+                };
+                match arg {
+                    "-d" => delimiter = Some(value),
+                    "-H" => headers = Some(value),
+                    "-w" => match value.parse::<usize>() {
+                        Ok(width) if width > 0 => max_width = Some(width),
+                        _ => {
+                            let _ = writeln!(stderr, "ion: table: invalid width: {}", value);
+                            return BAD_ARG;
+                        }
+                    },
+                    _ => map = Some(value),
+                }
+            }
+            _ => rows.push(arg.to_owned()),
+        }
+    }
+
+    let mut rows = rows.iter().map(|row| split(row, delimiter)).collect::<Vec<_>>();
+    if let Some(name) = map {
+        let map = match shell.variables.get_map(name) {
+            Some(map) => map,
+            None => {
+                let _ = writeln!(stderr, "ion: table: {} is not a map", name);
+                return FAILURE;
+            }
+        };
+        let mut entries = map
+            .iter()
+            .map(|(key, value)| vec![(**key).to_owned(), value.clone()])
+            .collect::<Vec<_>>();
+        entries.sort();
+        rows.extend(entries);
+    } else if rows.is_empty() {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            match line {
+                Ok(line) => rows.push(split(&line, delimiter)),
+                Err(why) => {
+                    let _ = writeln!(stderr, "ion: table: {}", why);
+                    return FAILURE;
+                }
+            }
+        }
+    }
+
+    let headers = headers.map(|headers| split(headers, delimiter));
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for line in format_table(headers, rows, max_width) {
+        let _ = writeln!(stdout, "{}", line);
+    }
+    SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(rows: &[&str]) -> Vec<Vec<String>> {
+        rows.iter().map(|row| split(row, Some(","))).collect()
+    }
+
+    #[test]
+    fn tables() {
+        let headers = Some(split("name,size", Some(",")));
+        let lines = format_table(headers, rows(&["ion,1024", "a-longer-name,64"]), None);
+        let expected = vec![
+            "name           size",
+            "-------------------",
+            "ion            1024",
+            "a-longer-name    64",
+        ];
+        assert_eq!(lines, expected);
+
+        let lines = format_table(None, rows(&["ion, shell", "a-longer-name"]), Some(6));
+        assert_eq!(lines, vec!["ion     shell", "a-lon…"]);
+    }
+}
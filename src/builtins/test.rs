@@ -1,10 +1,12 @@
 use smallstring::SmallString;
+use std::cmp::Ordering;
 use std::error::Error;
 use std::fs;
 use std::io::{self, BufWriter};
 use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
 use std::path::Path;
 use std::time::SystemTime;
+use versions;
 
 const MAN_PAGE: &'static str = r#"NAME
     test - perform tests on files and text
@@ -49,6 +51,24 @@ OPTIONS
     INTEGER -ne INTEGER
         the first INTEGER is not equal to the first INTEGER
 
+    VERSION -veq VERSION
+        the versions are equal, such that 1.2 is equal to 1.2.0
+
+    VERSION -vge VERSION
+        the first VERSION is newer than or equal to the second VERSION
+
+    VERSION -vgt VERSION
+        the first VERSION is newer than the second VERSION, such that 1.10.0 is newer than 1.9.2
+
+    VERSION -vle VERSION
+        the first VERSION is older than or equal to the second VERSION
+
+    VERSION -vlt VERSION
+        the first VERSION is older than the second VERSION, such that 1.0.0-rc.1 is older than 1.0.0
+
+    VERSION -vne VERSION
+        the versions are not equal
+
     FILE -ef FILE
         both files have the same device and inode numbers
 
@@ -159,6 +179,12 @@ fn evaluate_expression(first: &str, operator: &str, second: &str) -> Result<bool
         "-ef" => Ok(files_have_same_device_and_inode_numbers(first, second)),
         "-nt" => Ok(file_is_newer_than(first, second)),
         "-ot" => Ok(file_is_newer_than(second, first)),
+        "-veq" => Ok(versions::compare(first, second) == Ordering::Equal),
+        "-vge" => Ok(versions::compare(first, second) != Ordering::Less),
+        "-vgt" => Ok(versions::compare(first, second) == Ordering::Greater),
+        "-vle" => Ok(versions::compare(first, second) != Ordering::Greater),
+        "-vlt" => Ok(versions::compare(first, second) == Ordering::Less),
+        "-vne" => Ok(versions::compare(first, second) != Ordering::Equal),
         _ => {
             let (left, right) = parse_integers(first, second)?;
             match operator {
@@ -393,6 +419,17 @@ fn test_integers_arguments() {
     assert_eq!(evaluate_arguments(&["5", "-ne", "5"], &mut buffer), Ok(false));
 }
 
+#[test]
+fn test_versions_arguments() {
+    let mut buffer = BufWriter::new(io::sink());
+    assert_eq!(evaluate_arguments(&["1.10.0", "-vgt", "1.9.2"], &mut buffer), Ok(true));
+    assert_eq!(evaluate_arguments(&["1.10.0", "-vlt", "1.9.2"], &mut buffer), Ok(false));
+    assert_eq!(evaluate_arguments(&["1.2", "-veq", "1.2.0"], &mut buffer), Ok(true));
+    assert_eq!(evaluate_arguments(&["1.2", "-vne", "1.2.0"], &mut buffer), Ok(false));
+    assert_eq!(evaluate_arguments(&["1.0.0-rc.1", "-vge", "1.0.0"], &mut buffer), Ok(false));
+    assert_eq!(evaluate_arguments(&["1.0.0-rc.1", "-vle", "1.0.0"], &mut buffer), Ok(true));
+}
+
 #[test]
 fn test_file_exists() {
     assert_eq!(file_exists("testing/empty_file"), true);
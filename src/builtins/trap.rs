@@ -0,0 +1,98 @@
+use shell::Shell;
+use shell::status::*;
+use shell::traps::{self, EVENTS};
+use std::io::{self, Write};
+
+const HELP: &'static str = r#"NAME
+    trap - Execute commands when the shell receives a signal or event
+
+SYNOPSIS
+    trap [ --help ] [-l] [-p [EVENT]...] [HANDLER | -] [EVENT]...
+
+DESCRIPTION
+    Registers HANDLER to be evaluated when any of the given EVENTs occur. Handlers are
+    executed in between statements, rather than at the moment that a signal is received.
+    An empty HANDLER will cause the given signals to be ignored, and a HANDLER of `-` will
+    reset the EVENTs to their default behavior. If no arguments are given, every registered
+    handler will be printed.
+
+EVENTS
+    EXIT  Triggered right before the shell exits.
+    ERR   Triggered when a statement fails with a non-zero exit status.
+    HUP   Triggered when the shell receives SIGHUP.
+    INT   Triggered when the shell receives SIGINT.
+    TERM  Triggered when the shell receives SIGTERM.
+
+OPTIONS
+    -l  List the names of the events that may be trapped.
+
+    -p  Print the handlers for the given events, or all handlers if no events are given.
+"#;
+
+fn print_traps(shell: &Shell, events: &[usize]) {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for &event in events {
+        if let Some(handler) = shell.traps.get(event) {
+            let _ = writeln!(stdout, "trap -- '{}' {}", handler, EVENTS[event]);
+        }
+    }
+}
+
+fn parse_events(names: &[&str]) -> Result<Vec<usize>, String> {
+    names
+        .iter()
+        .map(|name| {
+            traps::parse_event(name).ok_or_else(|| format!("ion: trap: {}: invalid event\n", name))
+        })
+        .collect()
+}
+
+pub(crate) fn trap(args: &[&str], shell: &mut Shell) -> i32 {
+    let stderr = io::stderr();
+    let all_events = (0..EVENTS.len()).collect::<Vec<usize>>();
+
+    match args.get(1) {
+        None => print_traps(shell, &all_events),
+        Some(&"--help") => {
+            let stdout = io::stdout();
+            let _ = stdout.lock().write_all(HELP.as_bytes());
+        }
+        Some(&"-l") => {
+            let stdout = io::stdout();
+            let _ = writeln!(stdout.lock(), "{}", EVENTS.join(" "));
+        }
+        Some(&"-p") => match parse_events(&args[2..]) {
+            Ok(ref events) if events.is_empty() => print_traps(shell, &all_events),
+            Ok(events) => print_traps(shell, &events),
+            Err(why) => {
+                let _ = stderr.lock().write_all(why.as_bytes());
+                return FAILURE;
+            }
+        },
+        Some(&handler) => {
+            if args.len() < 3 {
+                let _ = stderr.lock().write_all(b"ion: trap: no events were supplied\n");
+                return BAD_ARG;
+            }
+
+            let events = match parse_events(&args[2..]) {
+                Ok(events) => events,
+                Err(why) => {
+                    let _ = stderr.lock().write_all(why.as_bytes());
+                    return FAILURE;
+                }
+            };
+
+            for event in events {
+                if handler == "-" {
+                    shell.traps.reset(event);
+                } else {
+                    shell.traps.set(event, handler.into());
+                }
+            }
+        }
+    }
+
+    SUCCESS
+}
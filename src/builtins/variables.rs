@@ -35,8 +35,58 @@ enum Operator {
     Multiply,
 }
 
+/// The kinds of aliases, which are selected by the `-g` and `-s` options of `alias` and `unalias`.
+#[derive(Clone, Copy, PartialEq)]
+enum AliasKind {
+    Command,
+    Global,
+    Suffix,
+}
+
+impl AliasKind {
+    /// Takes the option which selects the kind of alias from the front of the arguments.
+    fn parse<'a, S: AsRef<str>>(args: &'a [S]) -> (AliasKind, &'a [S]) {
+        match args.first().map(|arg| arg.as_ref()) {
+            Some("-g") => (AliasKind::Global, &args[1..]),
+            Some("-s") => (AliasKind::Suffix, &args[1..]),
+            _ => (AliasKind::Command, args),
+        }
+    }
+
+    fn aliases(self, vars: &mut Variables) -> &mut VariableContext {
+        match self {
+            AliasKind::Command => &mut vars.aliases,
+            AliasKind::Global => &mut vars.global_aliases,
+            AliasKind::Suffix => &mut vars.suffix_aliases,
+        }
+    }
+
+    /// Suffixes are stored with their leading dot, which may be omitted, as in `alias -s py`.
+    fn key(self, name: &str) -> Identifier {
+        if self == AliasKind::Suffix && !name.starts_with('.') {
+            format!(".{}", name).into()
+        } else {
+            name.into()
+        }
+    }
+
+    fn is_valid(self, name: &str) -> bool {
+        match self {
+            AliasKind::Command => Variables::is_valid_variable_name(name),
+            AliasKind::Global => !name.is_empty() && !name.chars().any(|c| {
+                c.is_whitespace() || c == '\'' || c == '"' || c == '$' || c == '@'
+            }),
+            AliasKind::Suffix => {
+                let suffix = if name.starts_with('.') { &name[1..] } else { name };
+                !suffix.is_empty()
+                    && suffix.chars().all(|c| c.is_alphanumeric() || c == '.' || c == '_')
+            }
+        }
+    }
+}
+
 /// Parses alias as a `(key, value)` tuple.
-fn parse_alias(args: &str) -> Binding {
+fn parse_alias(args: &str, kind: AliasKind) -> Binding {
     // Write all the arguments into a single `String`
     let mut char_iter = args.chars();
 
@@ -96,7 +146,7 @@ fn parse_alias(args: &str) -> Binding {
         let value: Value = char_iter.skip_while(|&x| x == ' ').collect();
         if value.is_empty() {
             Binding::KeyOnly(key)
-        } else if !Variables::is_valid_variable_name(&key) {
+        } else if !kind.is_valid(&key) {
             Binding::InvalidKey(key)
         } else {
             match operator {
@@ -111,18 +161,20 @@ fn parse_alias(args: &str) -> Binding {
 }
 
 /// The `alias` command will define an alias for another command, and thus may be used as a
-/// command itself.
-pub(crate) fn alias(vars: &mut Variables, args: &str) -> i32 {
-    match parse_alias(args) {
+/// command itself. With `-g`, it defines an alias which replaces any word of a command, and with
+/// `-s`, it defines the command which opens files that have a suffix.
+pub(crate) fn alias(vars: &mut Variables, args: &[&str]) -> i32 {
+    let (kind, args) = AliasKind::parse(args);
+    match parse_alias(&args.join(" "), kind) {
         Binding::InvalidKey(key) => {
             let stderr = io::stderr();
             let _ = writeln!(&mut stderr.lock(), "ion: alias name, '{}', is invalid", key);
             return FAILURE;
         }
         Binding::KeyValue(key, value) => {
-            vars.aliases.insert(key, value);
+            kind.aliases(vars).insert(kind.key(&key), value);
         }
-        Binding::ListEntries => print_list(&vars.aliases),
+        Binding::ListEntries => print_list(kind.aliases(vars)),
         Binding::KeyOnly(key) => {
             let stderr = io::stderr();
             let _ = writeln!(&mut stderr.lock(), "ion: please provide value for alias '{}'", key);
@@ -143,13 +195,14 @@ pub(crate) fn drop_alias<I: IntoIterator>(vars: &mut Variables, args: I) -> i32
     where I::Item: AsRef<str>
 {
     let args = args.into_iter().collect::<Vec<I::Item>>();
-    if args.len() <= 1 {
+    let (kind, aliases) = AliasKind::parse(&args[1..]);
+    if aliases.is_empty() {
         let stderr = io::stderr();
         let _ = writeln!(&mut stderr.lock(), "ion: you must specify an alias name");
         return FAILURE;
     }
-    for alias in args.iter().skip(1) {
-        if vars.aliases.remove(alias.as_ref()).is_none() {
+    for alias in aliases {
+        if kind.aliases(vars).remove(&kind.key(alias.as_ref())).is_none() {
             let stderr = io::stderr();
             let _ = writeln!(&mut stderr.lock(), "ion: undefined alias: {}", alias.as_ref());
             return FAILURE;
@@ -207,6 +260,37 @@ pub(crate) fn drop_variable<I: IntoIterator>(vars: &mut Variables, args: I) -> i
     SUCCESS
 }
 
+/// Marking a variable as a secret masks its value wherever the shell prints it. Without any
+/// variables, the names of the secret variables are listed.
+pub(crate) fn secret<I: IntoIterator>(vars: &mut Variables, args: I) -> i32
+    where I::Item: AsRef<str>
+{
+    let args = args.into_iter().collect::<Vec<I::Item>>();
+    let reveal = args.get(1).map_or(false, |arg| arg.as_ref() == "-u");
+    let names = if reveal { &args[2..] } else { &args[1..] };
+    if names.is_empty() {
+        if reveal {
+            eprintln!("ion: secret: you must specify a variable name");
+            return FAILURE;
+        }
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        for name in vars.get_secrets() {
+            let _ = writeln!(stdout, "{}", name);
+        }
+        return SUCCESS;
+    }
+
+    for name in names {
+        if !Variables::is_valid_variable_name(name.as_ref()) {
+            eprintln!("ion: secret: {}: invalid variable name", name.as_ref());
+            return FAILURE;
+        }
+        vars.set_secret(name.as_ref(), !reveal);
+    }
+    SUCCESS
+}
+
 
 #[cfg(test)]
 mod test {
@@ -217,7 +301,7 @@ mod test {
     struct VariableExpander(pub Variables);
 
     impl Expander for VariableExpander {
-        fn variable(&self, var: &str, _: bool) -> Option<Value> { self.0.get_var(var) }
+        fn variable(&mut self, var: &str, _: bool) -> Option<Value> { self.0.get_var(var) }
     }
 
     // TODO: Rewrite tests now that let is part of the grammar.
@@ -250,7 +334,7 @@ mod test {
         variables.set_var("FOO", "BAR");
         let return_status = drop_variable(&mut variables, vec!["drop", "FOO"]);
         assert_eq!(SUCCESS, return_status);
-        let expanded = expand_string("$FOO", &VariableExpander(variables), false).join("");
+        let expanded = expand_string("$FOO", &mut VariableExpander(variables), false).join("");
         assert_eq!("", expanded);
     }
 
@@ -274,7 +358,7 @@ mod test {
         variables.set_array("FOO", array!["BAR"]);
         let return_status = drop_array(&mut variables, vec!["drop", "-a", "FOO"]);
         assert_eq!(SUCCESS, return_status);
-        let expanded = expand_string("@FOO", &VariableExpander(variables), false).join("");
+        let expanded = expand_string("@FOO", &mut VariableExpander(variables), false).join("");
         assert_eq!("", expanded);
     }
 
@@ -291,4 +375,38 @@ mod test {
         let return_status = drop_array(&mut variables, vec!["drop", "FOO"]);
         assert_eq!(FAILURE, return_status);
     }
+
+    #[test]
+    fn secret_marks_variables() {
+        let mut variables = Variables::default();
+        variables.set_var("TOKEN", "s3cr3t");
+        assert_eq!(SUCCESS, secret(&mut variables, vec!["secret", "TOKEN"]));
+        assert!(variables.is_secret("TOKEN"));
+        assert_eq!(variables.mask("curl -H s3cr3t"), "curl -H ****");
+        assert_eq!(SUCCESS, secret(&mut variables, vec!["secret", "-u", "TOKEN"]));
+        assert!(!variables.is_secret("TOKEN"));
+        assert_eq!(FAILURE, secret(&mut variables, vec!["secret", "-u"]));
+        assert_eq!(FAILURE, secret(&mut variables, vec!["secret", "a-b"]));
+    }
+
+    #[test]
+    fn global_and_suffix_aliases() {
+        let mut variables = Variables::default();
+        assert_eq!(SUCCESS, alias(&mut variables, &["-g", "...", "=", "../.."]));
+        assert_eq!(SUCCESS, alias(&mut variables, &["-s", "py=python3"]));
+        assert_eq!(SUCCESS, alias(&mut variables, &["-s", ".tar.gz", "=", "tar", "-xzf"]));
+        assert_eq!(variables.global_aliases.get("..."), Some(&"../..".to_owned()));
+        assert_eq!(variables.suffix_aliases.get(".py"), Some(&"python3".to_owned()));
+        assert_eq!(variables.suffix_aliases.get(".tar.gz"), Some(&"tar -xzf".to_owned()));
+        assert!(variables.aliases.is_empty());
+
+        assert_eq!(FAILURE, alias(&mut variables, &["...", "=", "../.."]));
+        assert_eq!(FAILURE, alias(&mut variables, &["-s", ". = python3"]));
+        assert_eq!(FAILURE, alias(&mut variables, &["-g", "$x", "=", "y"]));
+
+        assert_eq!(SUCCESS, drop_alias(&mut variables, vec!["unalias", "-s", "py"]));
+        assert_eq!(FAILURE, drop_alias(&mut variables, vec!["unalias", "-s", "py"]));
+        assert_eq!(SUCCESS, drop_alias(&mut variables, vec!["unalias", "-g", "..."]));
+        assert_eq!(FAILURE, drop_alias(&mut variables, vec!["unalias", "-g"]));
+    }
 }
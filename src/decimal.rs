@@ -0,0 +1,320 @@
+//! Arbitrary-precision decimal numbers, which are used by variables of the `bigint` and `decimal`
+//! types, and by arithmetic expansions which are annotated with either type, so that large
+//! integers never overflow, and decimal fractions are never rounded.
+
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+
+/// The number of digits after the decimal point to which a quotient is truncated, when it cannot
+/// be represented exactly.
+pub(crate) const DIVISION_SCALE: usize = 20;
+
+/// The largest number of digits that a power may have, which is estimated as the number of digits
+/// of its base multiplied by its exponent, so that powers which would take too long to compute
+/// are refused.
+const MAX_POWER_DIGITS: u64 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Decimal {
+    negative: bool,
+    /// The digits of the magnitude, from the least significant, without any leading zeros.
+    digits:   Vec<u8>,
+    /// The number of digits which follow the decimal point, without any trailing zeros.
+    scale:    usize,
+}
+
+fn trim(digits: &mut Vec<u8>) {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+}
+
+/// Multiplies a magnitude by ten to the given power.
+fn shift(digits: &[u8], places: usize) -> Vec<u8> {
+    if digits.is_empty() {
+        return Vec::new();
+    }
+    let mut shifted = vec![0; places];
+    shifted.extend_from_slice(digits);
+    shifted
+}
+
+fn compare(a: &[u8], b: &[u8]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for id in 0..a.len().max(b.len()) {
+        let digit = a.get(id).cloned().unwrap_or(0) + b.get(id).cloned().unwrap_or(0) + carry;
+        sum.push(digit % 10);
+        carry = digit / 10;
+    }
+    if carry != 0 {
+        sum.push(carry);
+    }
+    sum
+}
+
+/// Subtracts the magnitude `b` from `a`, which must not be the lesser of the two.
+fn subtract(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for id in 0..a.len() {
+        let mut digit = a[id] as i8 - b.get(id).cloned().unwrap_or(0) as i8 - borrow;
+        borrow = if digit < 0 { 1 } else { 0 };
+        if digit < 0 {
+            digit += 10;
+        }
+        difference.push(digit as u8);
+    }
+    trim(&mut difference);
+    difference
+}
+
+fn multiply(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut columns = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            columns[i + j] += x as u32 * y as u32;
+        }
+    }
+    let mut product = Vec::with_capacity(columns.len());
+    let mut carry = 0;
+    for column in columns {
+        let value = column + carry;
+        product.push((value % 10) as u8);
+        carry = value / 10;
+    }
+    trim(&mut product);
+    product
+}
+
+/// Divides the magnitude `a` by `b`, which must not be zero, truncating the quotient.
+fn divide(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder = Vec::new();
+    for id in (0..a.len()).rev() {
+        remainder.insert(0, a[id]);
+        trim(&mut remainder);
+        while compare(&remainder, b) != Ordering::Less {
+            remainder = subtract(&remainder, b);
+            quotient[id] += 1;
+        }
+    }
+    trim(&mut quotient);
+    quotient
+}
+
+impl Decimal {
+    fn new(negative: bool, mut digits: Vec<u8>, mut scale: usize) -> Decimal {
+        trim(&mut digits);
+        let zeros = digits.iter().take_while(|&&digit| digit == 0).count().min(scale);
+        digits.drain(..zeros);
+        scale = if digits.is_empty() { 0 } else { scale - zeros };
+        Decimal { negative: negative && !digits.is_empty(), digits, scale }
+    }
+
+    pub(crate) fn from_bool(value: bool) -> Decimal {
+        Decimal::new(false, vec![value as u8], 0)
+    }
+
+    /// Parses a number, which may be preceded by a sign, and may have a fractional part.
+    pub(crate) fn parse(literal: &str) -> Option<Decimal> {
+        let (negative, number) = match literal.as_bytes().first() {
+            Some(&b'-') => (true, &literal[1..]),
+            Some(&b'+') => (false, &literal[1..]),
+            _ => (false, literal),
+        };
+        let (integer, fraction) = match number.find('.') {
+            Some(id) => (&number[..id], &number[id + 1..]),
+            None => (number, ""),
+        };
+        if integer.is_empty() && fraction.is_empty()
+            || !integer.bytes().chain(fraction.bytes()).all(|byte| byte >= b'0' && byte <= b'9')
+        {
+            return None;
+        }
+        let digits = integer.bytes().chain(fraction.bytes()).rev().map(|byte| byte - b'0');
+        Some(Decimal::new(negative, digits.collect(), fraction.len()))
+    }
+
+    pub(crate) fn is_integer(&self) -> bool { self.scale == 0 }
+
+    pub(crate) fn is_zero(&self) -> bool { self.digits.is_empty() }
+
+    pub(crate) fn is_negative(&self) -> bool { self.negative }
+
+    /// The magnitudes of both numbers, multiplied so that they have the same scale.
+    fn align(&self, other: &Decimal) -> (Vec<u8>, Vec<u8>, usize) {
+        let scale = self.scale.max(other.scale);
+        let a = shift(&self.digits, scale - self.scale);
+        (a, shift(&other.digits, scale - other.scale), scale)
+    }
+
+    pub(crate) fn negate(&self) -> Decimal {
+        Decimal::new(!self.negative, self.digits.clone(), self.scale)
+    }
+
+    pub(crate) fn add(&self, other: &Decimal) -> Decimal {
+        let (a, b, scale) = self.align(other);
+        if self.negative == other.negative {
+            Decimal::new(self.negative, add(&a, &b), scale)
+        } else if compare(&a, &b) == Ordering::Less {
+            Decimal::new(other.negative, subtract(&b, &a), scale)
+        } else {
+            Decimal::new(self.negative, subtract(&a, &b), scale)
+        }
+    }
+
+    pub(crate) fn subtract(&self, other: &Decimal) -> Decimal { self.add(&other.negate()) }
+
+    pub(crate) fn multiply(&self, other: &Decimal) -> Decimal {
+        let digits = multiply(&self.digits, &other.digits);
+        Decimal::new(self.negative != other.negative, digits, self.scale + other.scale)
+    }
+
+    /// Divides by the other number, truncating the quotient to the given number of digits after
+    /// the decimal point. Returns `None` when dividing by zero.
+    pub(crate) fn divide(&self, other: &Decimal, scale: usize) -> Option<Decimal> {
+        if other.is_zero() {
+            return None;
+        }
+        let numerator = shift(&self.digits, other.scale + scale);
+        let denominator = shift(&other.digits, self.scale);
+        let quotient = divide(&numerator, &denominator);
+        Some(Decimal::new(self.negative != other.negative, quotient, scale))
+    }
+
+    /// The remainder of truncated division, which has the sign of the dividend.
+    pub(crate) fn remainder(&self, other: &Decimal) -> Option<Decimal> {
+        let quotient = self.divide(other, 0)?;
+        Some(self.subtract(&quotient.multiply(other)))
+    }
+
+    /// Raises the number to a power, which must be a non-negative integer. Returns `None` if the
+    /// result would have more than `MAX_POWER_DIGITS` digits.
+    pub(crate) fn power(&self, exponent: &Decimal) -> Option<Decimal> {
+        if exponent.negative || !exponent.is_integer() || exponent.digits.len() > 6 {
+            return None;
+        }
+        let mut exponent = exponent.to_string().parse::<u64>().ok()?;
+        if self.digits.len() as u64 * exponent > MAX_POWER_DIGITS {
+            return None;
+        }
+        let (mut base, mut result) = (self.clone(), Decimal::from_bool(true));
+        while exponent != 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base);
+            }
+            exponent >>= 1;
+            if exponent != 0 {
+                base = base.multiply(&base);
+            }
+        }
+        Some(result)
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (negative, _) => {
+                let (a, b, _) = self.align(other);
+                let ordering = compare(&a, &b);
+                if negative { ordering.reverse() } else { ordering }
+            }
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut digits = self.digits.iter().rev().map(|digit| (b'0' + digit) as char);
+        let mut number = String::with_capacity(self.digits.len() + 3);
+        if self.negative {
+            number.push('-');
+        }
+        if self.digits.len() <= self.scale {
+            number.push('0');
+        } else {
+            number.extend(digits.by_ref().take(self.digits.len() - self.scale));
+        }
+        if self.scale != 0 {
+            number.push('.');
+            for _ in self.digits.len()..self.scale {
+                number.push('0');
+            }
+            number.extend(digits);
+        }
+        f.write_str(&number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(literal: &str) -> Decimal { Decimal::parse(literal).unwrap() }
+
+    #[test]
+    fn parsing() {
+        let cases = [
+            ("0", "0"),
+            ("-0.0", "0"),
+            ("007.500", "7.5"),
+            ("+.25", "0.25"),
+            ("-3.", "-3"),
+            ("-0.001", "-0.001"),
+            ("123456789012345678901234567890", "123456789012345678901234567890"),
+        ];
+        for &(literal, expected) in cases.iter() {
+            assert_eq!(parse(literal).to_string(), expected);
+        }
+        for literal in &["", ".", "-", "1.2.3", "1e5", "0x10", "--1"] {
+            assert_eq!(Decimal::parse(literal), None);
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(parse("0.1").add(&parse("0.2")).to_string(), "0.3");
+        assert_eq!(parse("1.5").subtract(&parse("3.75")).to_string(), "-2.25");
+        assert_eq!(parse("-1.5").add(&parse("1.5")).to_string(), "0");
+        let big = parse("9223372036854775807");
+        assert_eq!(big.add(&parse("1")).to_string(), "9223372036854775808");
+        assert_eq!(big.multiply(&big).to_string(), "85070591730234615847396907784232501249");
+        assert_eq!(parse("-1.25").multiply(&parse("0.2")).to_string(), "-0.25");
+
+        assert_eq!(parse("1").divide(&parse("3"), 5).unwrap().to_string(), "0.33333");
+        assert_eq!(parse("-7").divide(&parse("2"), 0).unwrap().to_string(), "-3");
+        assert_eq!(parse("10.5").divide(&parse("0.25"), 20).unwrap().to_string(), "42");
+        assert_eq!(parse("1").divide(&parse("0"), 20), None);
+        assert_eq!(parse("-7").remainder(&parse("3")).unwrap().to_string(), "-1");
+        assert_eq!(parse("5.5").remainder(&parse("2")).unwrap().to_string(), "1.5");
+
+        let power = parse("2").power(&parse("100")).unwrap();
+        assert_eq!(power.to_string(), "1267650600228229401496703205376");
+        assert_eq!(parse("1.1").power(&parse("2")).unwrap().to_string(), "1.21");
+        assert_eq!(parse("2").power(&parse("-1")), None);
+        assert_eq!(parse("2").power(&parse("0.5")), None);
+        assert_eq!(parse("2").power(&parse("10000")).unwrap().to_string().len(), 3011);
+        assert_eq!(parse("12").power(&parse("100000")), None);
+        assert_eq!(parse("0").power(&parse("999999")).unwrap().to_string(), "0");
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(parse("-2") < parse("-1.5"));
+        assert!(parse("0.10") == parse("0.1"));
+        assert!(parse("10") > parse("9.999"));
+        assert!(parse("-0.5") < parse("0"));
+    }
+}
@@ -35,6 +35,10 @@ mod sys;
 #[path = "sys/unix/mod.rs"]
 mod sys;
 
+#[cfg(windows)]
+#[path = "sys/windows.rs"]
+mod sys;
+
 #[macro_use]
 mod types;
 #[macro_use]
@@ -42,6 +46,12 @@ mod parser;
 mod builtins;
 pub mod shell;
 mod ascii_helpers;
+mod versions;
+mod radix;
+mod decimal;
+mod units;
 
 pub use builtins::Builtin;
+pub use parser::{completeness, Completeness, Incomplete};
 pub use shell::Shell;
+pub use shell::errors::{ErrorKind, IonError};
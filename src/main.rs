@@ -39,6 +39,10 @@ mod sys;
 #[path = "sys/unix/mod.rs"]
 mod sys;
 
+#[cfg(windows)]
+#[path = "sys/windows.rs"]
+mod sys;
+
 #[macro_use]
 mod types;
 #[macro_use]
@@ -46,6 +50,10 @@ mod parser;
 mod builtins;
 mod shell;
 mod ascii_helpers;
+mod versions;
+mod radix;
+mod decimal;
+mod units;
 
 use shell::{signals, Binary, Shell};
 use std::sync::atomic::Ordering;
@@ -70,12 +78,6 @@ fn main() {
     // for this shell to manage its own process group / children / etc.
     signals::block();
 
-    if let Ok(pid) = sys::getpid() {
-        if sys::setpgid(0, pid).is_ok() {
-            let _ = sys::tcsetpgrp(0, pid);
-        }
-    }
-
     let shell = Shell::new_bin();
     shell.main();
 }
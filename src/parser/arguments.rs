@@ -1,9 +1,9 @@
-const DOUBLE: u8 = 1;
-const COMM_1: u8 = 2;
-const COMM_2: u8 = 4;
-const VARIAB: u8 = 8;
-const ARRAY: u8 = 16;
-const METHOD: u8 = 32;
+use super::quoting::{Class, Quotes};
+
+const COMM_1: u8 = 1;
+const COMM_2: u8 = 2;
+const VARIAB: u8 = 4;
+const ARRAY: u8 = 8;
 
 /// An efficient `Iterator` structure for splitting arguments
 pub(crate) struct ArgumentSplitter<'a> {
@@ -22,23 +22,6 @@ impl<'a> ArgumentSplitter<'a> {
     }
 }
 
-impl<'a> ArgumentSplitter<'a> {
-    fn scan_singlequotes<B: Iterator<Item = u8>>(&mut self, bytes: &mut B) {
-        while let Some(character) = bytes.next() {
-            match character {
-                b'\\' => {
-                    self.read += 2;
-                    let _ = bytes.next();
-                    continue;
-                }
-                b'\'' => break,
-                _ => (),
-            }
-            self.read += 1;
-        }
-    }
-}
-
 impl<'a> Iterator for ArgumentSplitter<'a> {
     type Item = &'a str;
 
@@ -49,16 +32,14 @@ impl<'a> Iterator for ArgumentSplitter<'a> {
         }
         let start = self.read;
 
-        let (mut level, mut alevel) = (0, 0);
+        let (mut level, mut alevel, mut methods) = (0, 0, 0);
+        let mut quotes = Quotes::new();
         let mut bytes = data.iter().cloned().skip(self.read);
         while let Some(character) = bytes.next() {
+            let class = quotes.scan(character);
             match character {
-                // Skip the next byte.
-                b'\\' => {
-                    self.read += 2;
-                    let _ = bytes.next();
-                    continue;
-                }
+                // Quotes, escapes, and the bytes which they quote have no special meaning.
+                _ if !class.expands() => (),
                 // Disable COMM_1 and enable COMM_2 + ARRAY.
                 b'@' => {
                     self.flags = (self.flags & (255 ^ COMM_1)) | (COMM_2 + ARRAY);
@@ -77,24 +58,24 @@ impl<'a> Iterator for ArgumentSplitter<'a> {
                 b']' => alevel -= 1,
                 // Increment the parenthesis level.
                 b'(' if self.flags & COMM_1 != 0 => level += 1,
-                // Disable VARIAB + ARRAY and enable METHOD.
+                // Disable VARIAB + ARRAY and increment the method level, as methods may be nested.
                 b'(' if self.flags & (VARIAB + ARRAY) != 0 => {
-                    self.flags = (self.flags & (255 ^ (VARIAB + ARRAY))) | METHOD;
+                    self.flags &= 255 ^ (VARIAB + ARRAY);
+                    methods += 1;
+                }
+                // Decrement the method level, unless another method is chained onto it.
+                b')' if methods != 0 => {
+                    methods -= 1;
+                    if let Some(&b'.') = data.get(self.read + 1) {
+                        self.flags |= VARIAB;
+                    }
                 }
-                // Disable METHOD if enabled.
-                b')' if self.flags & METHOD != 0 => self.flags ^= METHOD,
                 // Otherwise decrement the parenthesis level.
                 b')' => level -= 1,
-                // Toggle double quote rules.
-                b'"' => self.flags ^= DOUBLE,
-                // Loop through characters until single quote rules are completed.
-                b'\'' if self.flags & DOUBLE == 0 => {
-                    self.scan_singlequotes(&mut bytes);
-                    self.read += 2;
-                    continue;
-                }
                 // Break from the loop once a root-level space is found.
-                b' ' if (self.flags & (DOUBLE + METHOD)) + level + alevel == 0 => break,
+                b' ' if class == Class::Unquoted && methods + level + alevel == 0 => {
+                    break
+                }
                 _ => (),
             }
             self.read += 1;
@@ -128,6 +109,22 @@ mod tests {
         compare(input, expected);
     }
 
+    #[test]
+    fn method_chains() {
+        let input = "echo $var.replace(a, b).trim() @array.reverse()[1] $file.txt";
+        let expected =
+            vec!["echo", "$var.replace(a, b).trim()", "@array.reverse()[1]", "$file.txt"];
+        compare(input, expected);
+    }
+
+    #[test]
+    fn nested_methods() {
+        let input = "if $and($verbose, $not($quiet)) @reverse(@split($(echo a b), ' '))";
+        let expected =
+            vec!["if", "$and($verbose, $not($quiet))", "@reverse(@split($(echo a b), ' '))"];
+        compare(input, expected);
+    }
+
     #[test]
     fn processes() {
         let input = "echo $(echo one $(echo two)) @[echo one @[echo two]]";
@@ -157,4 +154,18 @@ mod tests {
         ];
         compare(input, expected);
     }
+
+    #[test]
+    fn escapes() {
+        let input = r#"echo one\ two 'three\' four "five\" six" 'it'\''s'"#;
+        let expected = vec![
+            "echo",
+            r"one\ two",
+            r"'three\'",
+            "four",
+            r#""five\" six""#,
+            r"'it'\''s'",
+        ];
+        compare(input, expected);
+    }
 }
@@ -2,12 +2,15 @@ use super::*;
 use super::checker::*;
 use super::super::ArgumentSplitter;
 use std::fmt::{self, Display, Formatter};
+use types::{Array, Value};
 
 #[derive(Debug, PartialEq)]
 pub(crate) enum AssignmentError<'a> {
     InvalidOperator(&'a str),
     InvalidValue(Primitive, Primitive),
     TypeError(TypeError<'a>),
+    /// A destructuring assignment received a different number of values than it has keys.
+    Arity(usize, usize),
 }
 
 impl<'a> Display for AssignmentError<'a> {
@@ -18,6 +21,9 @@ impl<'a> Display for AssignmentError<'a> {
                 write!(f, "expected {}, but received {}", expected, actual)
             }
             AssignmentError::TypeError(ref type_err) => write!(f, "{}", type_err),
+            AssignmentError::Arity(keys, values) => {
+                write!(f, "expected {} values to destructure, but received {}", keys, values)
+            }
         }
     }
 }
@@ -26,13 +32,15 @@ impl<'a> Display for AssignmentError<'a> {
 /// assignment request.
 ///
 /// Each request will tell the shell whether the assignment is asking to update an array or a
-/// string, and will contain the key/value pair to assign.
+/// string, and will contain the key/value pair to assign. Many keys which are given a single
+/// array, as in `let a b c = @array`, are instead destructured from the array all at once.
 pub(crate) struct AssignmentActions<'a> {
-    keys:     KeyIterator<'a>,
-    operator: Operator,
-    values:   ArgumentSplitter<'a>,
-    prevkey:  &'a str,
-    prevval:  &'a str,
+    keys:        KeyIterator<'a>,
+    operator:    Operator,
+    values:      ArgumentSplitter<'a>,
+    prevkey:     &'a str,
+    prevval:     &'a str,
+    destructure: Option<&'a str>,
 }
 
 impl<'a> AssignmentActions<'a> {
@@ -43,13 +51,67 @@ impl<'a> AssignmentActions<'a> {
             values: ArgumentSplitter::new(values),
             prevkey: "",
             prevval: "",
+            destructure: destructured(keys, values),
+        }
+    }
+
+    fn destructure(&mut self, array: &'a str) -> Result<Action<'a>, AssignmentError<'a>> {
+        // The array is the only value, which every key is given a part of.
+        self.values.next();
+        let mut keys = Vec::new();
+        for key in &mut self.keys {
+            let key = key.map_err(AssignmentError::TypeError)?;
+            match key.kind {
+                Primitive::AnyArray
+                | Primitive::BooleanArray
+                | Primitive::FloatArray
+                | Primitive::IntegerArray
+                | Primitive::StrArray => {
+                    return Err(AssignmentError::InvalidValue(key.kind, Primitive::Any))
+                }
+                _ => keys.push(key),
+            }
+        }
+        Ok(Action::Destructure(keys, self.operator, array))
+    }
+}
+
+/// The array which is destructured by an assignment, if it gives more than one key a single
+/// value which is an array: either an array expression, such as `@array[0..3]`, `@(command)`,
+/// or `@split($target, '@')`, or an array literal, such as `[one two]`.
+fn destructured<'a>(keys: &str, values: &'a str) -> Option<&'a str> {
+    let mut values = ArgumentSplitter::new(values);
+    match (values.next(), values.next()) {
+        (Some(value), None) if value.starts_with('@') || is_array(value) => {
+            if KeyIterator::new(keys).nth(1).is_some() {
+                Some(value)
+            } else {
+                None
+            }
         }
+        _ => None,
+    }
+}
+
+/// Pairs each of the keys of a destructuring assignment with its value, of which there must be
+/// exactly as many as there are keys.
+pub(crate) fn destructure<'a, 'b>(
+    keys: &'b [Key<'a>],
+    values: Array,
+) -> Result<Vec<(&'b Key<'a>, Value)>, AssignmentError<'a>> {
+    if keys.len() == values.len() {
+        Ok(keys.iter().zip(values).collect())
+    } else {
+        Err(AssignmentError::Arity(keys.len(), values.len()))
     }
 }
 
 impl<'a> Iterator for AssignmentActions<'a> {
     type Item = Result<Action<'a>, AssignmentError<'a>>;
     fn next(&mut self) -> Option<Result<Action<'a>, AssignmentError<'a>>> {
+        if let Some(array) = self.destructure.take() {
+            return Some(self.destructure(array));
+        }
         if let Some(key) = self.keys.next() {
             match key {
                 Ok(key) => match self.values.next() {
@@ -84,6 +146,8 @@ impl<'a> Iterator for AssignmentActions<'a> {
 pub(crate) enum Action<'a> {
     UpdateString(Key<'a>, Operator, &'a str),
     UpdateArray(Key<'a>, Operator, &'a str),
+    /// Assigns each of the values of the array to each of the keys, in order.
+    Destructure(Vec<Key<'a>>, Operator, &'a str),
 }
 
 impl<'a> Action<'a> {
@@ -238,4 +302,55 @@ mod tests {
             ),)
         );
     }
+
+    #[test]
+    fn destructuring() {
+        let key = |name, kind| Key { name, kind };
+        let (keys, op, values) = split("a b c = @arr[0..3]");
+        let actions = AssignmentActions::new(&keys, op, &values).collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![Ok(Action::Destructure(
+                vec![
+                    key("a", Primitive::Any),
+                    key("b", Primitive::Any),
+                    key("c", Primitive::Any),
+                ],
+                Operator::Equal,
+                "@arr[0..3]",
+            ))]
+        );
+
+        let (keys, op, values) = split("user host:str = @split($target, '@')");
+        let actions = AssignmentActions::new(&keys, op, &values).collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![Ok(Action::Destructure(
+                vec![key("user", Primitive::Any), key("host", Primitive::Str)],
+                Operator::Equal,
+                "@split($target, '@')",
+            ))]
+        );
+
+        // A single key is given the whole array, and arrays cannot be destructured into arrays.
+        let (keys, op, values) = split("a = @arr");
+        let actions = AssignmentActions::new(&keys, op, &values).collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![Ok(Action::UpdateString(key("a", Primitive::Any), Operator::Equal, "@arr"))]
+        );
+        let (keys, op, values) = split("a b[] = @arr");
+        let actions = AssignmentActions::new(&keys, op, &values).collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![Err(AssignmentError::InvalidValue(Primitive::AnyArray, Primitive::Any))]
+        );
+
+        let keys = [key("a", Primitive::Any), key("b", Primitive::Integer)];
+        let pairs = destructure(&keys, array!["one", "2"]).unwrap();
+        assert_eq!(pairs, vec![(&keys[0], "one".into()), (&keys[1], "2".into())]);
+        let error = destructure(&keys, array!["one"]).unwrap_err();
+        assert_eq!(error, AssignmentError::Arity(2, 1));
+        assert_eq!(error.to_string(), "expected 2 values to destructure, but received 1");
+    }
 }
@@ -1,6 +1,8 @@
 use super::{Primitive, ReturnValue, TypeError};
 use super::super::Expander;
 use super::super::expand_string;
+use types::Array;
+use units;
 
 use std::iter::Iterator;
 
@@ -69,31 +71,42 @@ fn is_boolean_string(value: &ReturnValue) -> Result<&str, ()> {
     }
 }
 
+/// Integers written in hexadecimal, octal, or binary, in scientific notation, or with the suffix
+/// of a unit, as in `4K`, are stored in decimal.
 fn is_integer_string(value: ReturnValue) -> Result<ReturnValue, ()> {
-    let is_ok = if let ReturnValue::Str(ref num) = value {
-        num.parse::<i64>().is_ok()
+    if let ReturnValue::Str(ref num) = value {
+        units::parse_integer(num).map(|num| ReturnValue::Str(num.to_string())).ok_or(())
     } else {
         unreachable!()
-    };
+    }
+}
 
-    if is_ok {
-        Ok(value)
+/// Numbers of arbitrary precision are stored without redundant zeros, as in `1.5` for `01.50`.
+fn is_precise_string(value: ReturnValue, integer: bool) -> Result<ReturnValue, ()> {
+    if let ReturnValue::Str(ref num) = value {
+        match units::parse_decimal(num) {
+            Some(ref num) if !integer || num.is_integer() => Ok(ReturnValue::Str(num.to_string())),
+            _ => Err(()),
+        }
     } else {
-        Err(())
+        unreachable!()
     }
 }
 
-fn is_float_string(value: ReturnValue) -> Result<ReturnValue, ()> {
-    let is_ok = if let ReturnValue::Str(ref num) = value {
-        num.parse::<f64>().is_ok()
+/// Floats which are written with the suffix of a unit, as in `250ms`, are stored without it.
+fn float(num: &str) -> Option<String> {
+    if num.parse::<f64>().is_ok() {
+        Some(num.to_owned())
     } else {
-        unreachable!()
-    };
+        units::parse_float(num).map(|num| num.to_string())
+    }
+}
 
-    if is_ok {
-        Ok(value)
+fn is_float_string(value: ReturnValue) -> Result<ReturnValue, ()> {
+    if let ReturnValue::Str(ref num) = value {
+        float(num).map(ReturnValue::Str).ok_or(())
     } else {
-        Err(())
+        unreachable!()
     }
 }
 
@@ -115,43 +128,39 @@ fn is_boolean_array(values: &mut ReturnValue) -> bool {
 }
 
 fn is_integer_array(value: ReturnValue) -> Result<ReturnValue, ()> {
-    let is_ok = if let ReturnValue::Vector(ref nums) = value {
-        nums.iter().all(|num| num.parse::<i64>().is_ok())
+    if let ReturnValue::Vector(ref nums) = value {
+        let mut integers = Array::new();
+        for num in nums {
+            integers.push(units::parse_integer(num).ok_or(())?.to_string());
+        }
+        Ok(ReturnValue::Vector(integers))
     } else {
         unreachable!()
-    };
-
-    if is_ok {
-        Ok(value)
-    } else {
-        Err(())
     }
 }
 
 fn is_float_array(value: ReturnValue) -> Result<ReturnValue, ()> {
-    let is_ok = if let ReturnValue::Vector(ref nums) = value {
-        nums.iter().all(|num| num.parse::<f64>().is_ok())
+    if let ReturnValue::Vector(ref nums) = value {
+        let mut floats = Array::new();
+        for num in nums {
+            floats.push(float(num).ok_or(())?);
+        }
+        Ok(ReturnValue::Vector(floats))
     } else {
         unreachable!()
-    };
-
-    if is_ok {
-        Ok(value)
-    } else {
-        Err(())
     }
 }
 
-fn get_string<E: Expander>(shell: &E, value: &str) -> ReturnValue {
+fn get_string<E: Expander>(shell: &mut E, value: &str) -> ReturnValue {
     ReturnValue::Str(expand_string(value, shell, false).join(" "))
 }
 
-fn get_array<E: Expander>(shell: &E, value: &str) -> ReturnValue {
+fn get_array<E: Expander>(shell: &mut E, value: &str) -> ReturnValue {
     ReturnValue::Vector(expand_string(value, shell, false))
 }
 
 pub(crate) fn value_check<'a, E: Expander>(
-    shell: &E,
+    shell: &mut E,
     value: &'a str,
     expected: Primitive,
 ) -> Result<ReturnValue, TypeError<'a>> {
@@ -160,15 +169,8 @@ pub(crate) fn value_check<'a, E: Expander>(
     let is_array = is_array(value);
     match expected {
         Primitive::Any if is_array => Ok(array!()),
-        Primitive::Any => Ok(string!()),
         Primitive::AnyArray if is_array => Ok(array!()),
-        Primitive::Str if !is_array => Ok(string!()),
         Primitive::StrArray if is_array => Ok(array!()),
-        Primitive::Boolean if !is_array => {
-            let value = string!();
-            let value = is_boolean_string(&value).map_err(|_| TypeError::BadValue(expected))?;
-            Ok(ReturnValue::Str(value.to_owned()))
-        }
         Primitive::BooleanArray if is_array => {
             let mut values = array!();
             if is_boolean_array(&mut values) {
@@ -177,18 +179,43 @@ pub(crate) fn value_check<'a, E: Expander>(
                 Err(TypeError::BadValue(expected))
             }
         }
-        Primitive::Integer if !is_array => {
-            is_integer_string(string!()).map_err(|_| TypeError::BadValue(expected))
-        }
         Primitive::IntegerArray if is_array => {
             is_integer_array(array!()).map_err(|_| TypeError::BadValue(expected))
         }
-        Primitive::Float if !is_array => {
-            is_float_string(string!()).map_err(|_| TypeError::BadValue(expected))
-        }
         Primitive::FloatArray if is_array => {
             is_float_array(array!()).map_err(|_| TypeError::BadValue(expected))
         }
+        Primitive::Any
+        | Primitive::Str
+        | Primitive::Boolean
+        | Primitive::Integer
+        | Primitive::BigInt
+        | Primitive::Decimal
+        | Primitive::Float if !is_array => string_check(string!(), expected),
+        _ => Err(TypeError::BadValue(expected)),
+    }
+}
+
+/// Checks a string which has already been expanded against the type of its key, normalizing it
+/// as `value_check` would, such as when it is one of the values of a destructured array.
+pub(crate) fn string_check<'a>(
+    value: ReturnValue,
+    expected: Primitive,
+) -> Result<ReturnValue, TypeError<'a>> {
+    match expected {
+        Primitive::Any | Primitive::Str => Ok(value),
+        Primitive::Boolean => {
+            let value = is_boolean_string(&value).map_err(|_| TypeError::BadValue(expected))?;
+            Ok(ReturnValue::Str(value.to_owned()))
+        }
+        Primitive::Integer => is_integer_string(value).map_err(|_| TypeError::BadValue(expected)),
+        Primitive::BigInt => {
+            is_precise_string(value, true).map_err(|_| TypeError::BadValue(expected))
+        }
+        Primitive::Decimal => {
+            is_precise_string(value, false).map_err(|_| TypeError::BadValue(expected))
+        }
+        Primitive::Float => is_float_string(value).map_err(|_| TypeError::BadValue(expected)),
         _ => Err(TypeError::BadValue(expected)),
     }
 }
@@ -217,10 +244,36 @@ mod test {
         assert_eq!(is_boolean("other"), Err(()));
     }
 
+    #[test]
+    fn is_precise_string_() {
+        let big = "123456789012345678901234567890";
+        let expected = Ok(ReturnValue::Str(big.into()));
+        assert_eq!(is_precise_string(ReturnValue::Str(big.into()), true), expected);
+        assert_eq!(is_precise_string(ReturnValue::Str("1.5".into()), true), Err(()));
+        let expected = Ok(ReturnValue::Str("1.5".into()));
+        assert_eq!(is_precise_string(ReturnValue::Str("01.50".into()), false), expected);
+    }
+
     #[test]
     fn is_integer_array_() {
         let expected = Ok(ReturnValue::Vector(array!["1", "2", "3"]));
         assert_eq!(is_integer_array(ReturnValue::Vector(array!["1", "2", "3"])), expected);
         assert_eq!(is_integer_array(ReturnValue::Vector(array!["1", "2", "three"])), Err(()));
+
+        let expected = Ok(ReturnValue::Vector(array!["255", "2", "-7"]));
+        assert_eq!(is_integer_array(ReturnValue::Vector(array!["0xff", "0b10", "-0o7"])), expected);
+
+        let expected = Ok(ReturnValue::Vector(array!["1500", "4000", "2097152"]));
+        assert_eq!(is_integer_array(ReturnValue::Vector(array!["1.5e3", "4K", "2MiB"])), expected);
+        assert_eq!(is_integer_array(ReturnValue::Vector(array!["250ms"])), Err(()));
+    }
+
+    #[test]
+    fn is_float_string_() {
+        let expected = Ok(ReturnValue::Str("1.5e3".into()));
+        assert_eq!(is_float_string(ReturnValue::Str("1.5e3".into())), expected);
+        let expected = Ok(ReturnValue::Str("0.25".into()));
+        assert_eq!(is_float_string(ReturnValue::Str("250ms".into())), expected);
+        assert_eq!(is_float_string(ReturnValue::Str("250xs".into())), Err(()));
     }
 }
@@ -63,6 +63,8 @@ pub(crate) enum Primitive {
     IntegerArray,
     Float,
     FloatArray,
+    BigInt,
+    Decimal,
 }
 
 impl Primitive {
@@ -77,6 +79,8 @@ impl Primitive {
             "int[]" => Primitive::IntegerArray,
             "float" => Primitive::Float,
             "float[]" => Primitive::FloatArray,
+            "bigint" => Primitive::BigInt,
+            "decimal" => Primitive::Decimal,
             _ => return None,
         };
         Some(data)
@@ -95,6 +99,8 @@ impl Display for Primitive {
             Primitive::Integer => write!(f, "int"),
             Primitive::IntegerArray => write!(f, "int[]"),
             Primitive::StrArray => write!(f, "str[]"),
+            Primitive::BigInt => write!(f, "bigint"),
+            Primitive::Decimal => write!(f, "decimal"),
         }
     }
 }
@@ -128,32 +134,22 @@ impl<'a> KeyIterator<'a> {
         }
     }
 
-    // Executes when a semicolon was not found, but an array character was.
-    fn parse_array(&mut self, name: &'a str) -> Result<Key<'a>, TypeError<'a>> {
-        let start = self.read;
-        for byte in self.data.bytes().skip(self.read) {
-            if byte == b' ' {
-                match &self.data[start..self.read] {
-                    "]" => {
-                        return Ok(Key {
-                            name,
-                            kind: Primitive::AnyArray,
-                        })
-                    }
-                    data @ _ => return Err(TypeError::Invalid(data)),
-                }
-            }
-            self.read += 1;
-        }
-
-        match &self.data[start..] {
-            "]" => {
-                return Ok(Key {
-                    name,
-                    kind: Primitive::AnyArray,
-                })
-            }
-            data @ _ => return Err(TypeError::Invalid(data)),
+    // Executes when a semicolon was not found, but an array character was. Empty brackets
+    // declare an array, whereas a selection within them, as in `array[-1]`, refers to elements
+    // of an existing array, and is kept as a part of the name.
+    fn parse_array(&mut self, start: usize, name: &'a str) -> Result<Key<'a>, TypeError<'a>> {
+        let brackets = self.read;
+        self.read = self.data[brackets..].find(' ').map_or(self.data.len(), |id| brackets + id);
+        match &self.data[brackets..self.read] {
+            "]" => Ok(Key {
+                name,
+                kind: Primitive::AnyArray,
+            }),
+            data if data.len() > 1 && data.ends_with(']') => Ok(Key {
+                name: &self.data[start..self.read],
+                kind: Primitive::Any,
+            }),
+            data => Err(TypeError::Invalid(data)),
         }
     }
 }
@@ -180,7 +176,7 @@ impl<'a> Iterator for KeyIterator<'a> {
                 b'[' => {
                     // NOTE: Borrowck issue?
                     let read = self.read;
-                    return Some(self.parse_array(&self.data[start..read - 1].trim()));
+                    return Some(self.parse_array(start, &self.data[start..read - 1].trim()));
                 }
                 _ => (),
             }
@@ -239,5 +235,22 @@ mod tests {
             })
         );
         assert_eq!(parser.next().unwrap(), Err(TypeError::Invalid("a")));
+
+        let mut parser = KeyIterator::new("a[-1] b[..10..2] c[ d");
+        assert_eq!(
+            parser.next().unwrap(),
+            Ok(Key {
+                name: "a[-1]",
+                kind: Primitive::Any,
+            })
+        );
+        assert_eq!(
+            parser.next().unwrap(),
+            Ok(Key {
+                name: "b[..10..2]",
+                kind: Primitive::Any,
+            })
+        );
+        assert_eq!(parser.next().unwrap(), Err(TypeError::Invalid("")));
     }
 }
@@ -4,8 +4,8 @@ mod splitter;
 mod keys;
 mod operator;
 
-pub(crate) use self::actions::{Action, AssignmentActions, AssignmentError};
-pub(crate) use self::checker::{is_array, value_check};
+pub(crate) use self::actions::{destructure, Action, AssignmentActions, AssignmentError};
+pub(crate) use self::checker::{is_array, is_boolean, string_check, value_check};
 pub(crate) use self::keys::{Key, KeyBuf, KeyIterator, Primitive, TypeError};
 pub(crate) use self::operator::Operator;
 pub(crate) use self::splitter::split_assignment;
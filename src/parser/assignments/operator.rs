@@ -7,6 +7,7 @@ pub(crate) enum Operator {
     Subtract,
     Divide,
     IntegerDivide,
+    Modulo,
     Multiply,
     Exponent,
     Equal,
@@ -20,6 +21,7 @@ impl Operator {
             "-=" => Ok(Operator::Subtract),
             "/=" => Ok(Operator::Divide),
             "//=" => Ok(Operator::IntegerDivide),
+            "%=" => Ok(Operator::Modulo),
             "*=" => Ok(Operator::Multiply),
             "**=" => Ok(Operator::Exponent),
             _ => Err(AssignmentError::InvalidOperator(data)),
@@ -34,6 +36,7 @@ impl Display for Operator {
             Operator::Subtract => write!(f, "-="),
             Operator::Divide => write!(f, "/="),
             Operator::IntegerDivide => write!(f, "//="),
+            Operator::Modulo => write!(f, "%="),
             Operator::Multiply => write!(f, "*="),
             Operator::Exponent => write!(f, "**="),
             Operator::Equal => write!(f, "="),
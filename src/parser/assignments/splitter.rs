@@ -10,25 +10,35 @@ pub(crate) fn split_assignment<'a>(
     let mut read = 0;
     let mut bytes = statement.bytes();
     let mut start = 0;
+    // Keys may select elements of an array, as in `array[-1]`, within which a `-` is not an
+    // operator.
+    let mut level = 0;
 
     while let Some(byte) = bytes.next() {
-        if b'=' == byte {
-            if let None = statement.as_bytes().get(read + 1) {
-                return (Some(&statement[..read].trim()), Some("="), None);
+        match byte {
+            b'[' => level += 1,
+            b']' if level > 0 => level -= 1,
+            _ if level > 0 => (),
+            b'=' => {
+                if let None = statement.as_bytes().get(read + 1) {
+                    return (Some(&statement[..read].trim()), Some("="), None);
+                }
+                start = read;
+                read += 1;
+                break;
             }
-            start = read;
-            read += 1;
-            break;
-        } else if is_operator(byte) {
-            start = read;
-            read += 1;
-            while let Some(byte) = bytes.next() {
+            _ if is_operator(byte) => {
+                start = read;
                 read += 1;
-                if byte == b'=' {
-                    break;
+                while let Some(byte) = bytes.next() {
+                    read += 1;
+                    if byte == b'=' {
+                        break;
+                    }
                 }
+                break;
             }
-            break;
+            _ => (),
         }
         read += 1;
     }
@@ -48,7 +58,9 @@ pub(crate) fn split_assignment<'a>(
     (Some(keys), Some(operator), Some(values.trim()))
 }
 
-fn is_operator(byte: u8) -> bool { byte == b'+' || byte == b'-' || byte == b'*' || byte == b'/' }
+fn is_operator(byte: u8) -> bool {
+    byte == b'+' || byte == b'-' || byte == b'*' || byte == b'/' || byte == b'%'
+}
 
 #[cfg(test)]
 mod tests {
@@ -66,6 +78,8 @@ mod tests {
         assert_eq!(
             split_assignment("def ghi += 124 523"),
             (Some("def ghi"), Some("+="), Some("124 523"),)
-        )
+        );
+        assert_eq!(split_assignment("abc[-1] -= 1"), (Some("abc[-1]"), Some("-="), Some("1")));
+        assert_eq!(split_assignment("abc %= 3"), (Some("abc"), Some("%="), Some("3")));
     }
 }
@@ -0,0 +1,144 @@
+//! Determines whether a buffer of input is a complete statement, which may be executed, so that
+//! the interactive loop, embedders, and notebook-style frontends may know when to read more.
+
+use super::{QuoteTerminator, StatementSplitter};
+
+/// Whether a buffer of input may be executed, and if not, why.
+#[derive(Debug, PartialEq)]
+pub enum Completeness {
+    /// Every statement within the buffer is complete.
+    Complete,
+    /// More input is required before the buffer may be executed.
+    Incomplete(Incomplete),
+    /// The buffer cannot be executed, no matter what follows it.
+    Error(String),
+}
+
+/// The reason for which more input is required.
+#[derive(Debug, PartialEq)]
+pub enum Incomplete {
+    /// A single or double quote has not been closed.
+    Quote,
+    /// An array, opened with `[`, has not been closed.
+    Bracket,
+    /// A subshell, method, or arithmetic expression, opened with `(`, has not been closed.
+    Paren,
+    /// The terminator of a here document, as in `cat << EOF`, has not been given.
+    HereDocument,
+    /// The last line ends with `\`, `&&`, or `||`.
+    Continuation,
+    /// The block which was opened with the given keyword, such as `for`, has not been given
+    /// its `end`.
+    End(&'static str),
+}
+
+/// The keywords which open a block that must be closed with `end`.
+const BLOCKS: &'static [&'static str] =
+    &["if", "while", "repeat", "retry", "for", "match", "with", "fn"];
+
+/// The keyword of the block which the statement opens, if it opens one.
+fn opens_block(statement: &str) -> Option<&'static str> {
+    if statement.starts_with("time ") {
+        return opens_block(statement[5..].trim_left());
+    }
+    let keyword = statement.split_whitespace().next()?;
+    let block = BLOCKS.iter().find(|&&block| block == keyword)?;
+    let arguments = statement[keyword.len()..].trim_left();
+    if arguments.is_empty() || (keyword == "fn" && arguments.starts_with('-')) {
+        None
+    } else {
+        Some(*block)
+    }
+}
+
+/// Determines whether the buffer, which may contain many lines, is a complete statement, by the
+/// same rules with which the shell reads its input: quotes, arrays, and parentheses must be
+/// closed, lines may not end with a continuation, and every block must be ended.
+pub fn completeness(buffer: &str) -> Completeness {
+    let mut blocks = Vec::new();
+    let mut lines = buffer.lines();
+    while let Some(line) = lines.next() {
+        let mut terminator = QuoteTerminator::new(line.into());
+        while !terminator.check_termination() {
+            match lines.next() {
+                Some(line) => terminator.append(line.into()),
+                None => return Completeness::Incomplete(terminator.incomplete()),
+            }
+        }
+
+        let command = terminator.consume();
+        for statement in StatementSplitter::new(&command) {
+            let statement = match statement {
+                Ok(statement) => statement,
+                Err(why) => return Completeness::Error(why.to_string()),
+            };
+            if let Some(block) = opens_block(statement) {
+                blocks.push(block);
+            } else if statement == "end" || statement.starts_with("end ") {
+                if blocks.pop().is_none() {
+                    return Completeness::Error("no block to end".into());
+                }
+            } else if statement == "else" || statement.starts_with("else if ") {
+                if blocks.last() != Some(&"if") {
+                    return Completeness::Error("not an if statement".into());
+                }
+            }
+        }
+    }
+
+    match blocks.pop() {
+        Some(block) => Completeness::Incomplete(Incomplete::End(block)),
+        None => Completeness::Complete,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete() {
+        assert_eq!(completeness(""), Completeness::Complete);
+        assert_eq!(completeness("echo one; echo two"), Completeness::Complete);
+        assert_eq!(completeness("for x in 1..3\n    echo $x\nend"), Completeness::Complete);
+        let branches = "if true; echo a; else if false; else; end";
+        assert_eq!(completeness(branches), Completeness::Complete);
+        assert_eq!(completeness("echo 'a\nb'"), Completeness::Complete);
+        assert_eq!(completeness("echo $(seq\n1 3)"), Completeness::Complete);
+        assert_eq!(completeness("fn -h"), Completeness::Complete);
+        let lines = "while read line\n    echo $line\nend < log";
+        assert_eq!(completeness(lines), Completeness::Complete);
+    }
+
+    #[test]
+    fn incomplete() {
+        let incomplete = |reason| Completeness::Incomplete(reason);
+        assert_eq!(completeness("echo 'one"), incomplete(Incomplete::Quote));
+        assert_eq!(completeness("echo \"one\ntwo"), incomplete(Incomplete::Quote));
+        assert_eq!(completeness("let a = [1 2"), incomplete(Incomplete::Bracket));
+        assert_eq!(completeness("echo $(seq 1"), incomplete(Incomplete::Paren));
+        assert_eq!(completeness("cat << EOF\none"), incomplete(Incomplete::HereDocument));
+        assert_eq!(completeness("echo one \\"), incomplete(Incomplete::Continuation));
+        assert_eq!(completeness("true &&"), incomplete(Incomplete::Continuation));
+        assert_eq!(completeness("for x in 1..3"), incomplete(Incomplete::End("for")));
+        assert_eq!(
+            completeness("fn greet name\n    if test $name\n        echo $name\n    end"),
+            incomplete(Incomplete::End("fn"))
+        );
+        assert_eq!(completeness("time while true"), incomplete(Incomplete::End("while")));
+    }
+
+    #[test]
+    fn erroneous() {
+        assert_eq!(completeness("end"), Completeness::Error("no block to end".into()));
+        assert_eq!(completeness("else"), Completeness::Error("not an if statement".into()));
+        assert_eq!(
+            completeness("for x in 1..3\n    else\nend"),
+            Completeness::Error("not an if statement".into())
+        );
+        assert!(match completeness("echo ${a") {
+            Completeness::Error(_) => true,
+            _ => false,
+        });
+    }
+}
@@ -9,7 +9,7 @@ pub(crate) enum ForExpression {
 }
 
 impl ForExpression {
-    pub(crate) fn new<E: Expander>(expression: &[String], expanders: &E) -> ForExpression {
+    pub(crate) fn new<E: Expander>(expression: &[String], expanders: &mut E) -> ForExpression {
         let output: Vec<_> = expression
             .iter()
             .flat_map(|expression| expand_string(expression, expanders, true))
@@ -70,7 +70,7 @@ mod tests {
     struct VariableExpander(pub Variables);
 
     impl Expander for VariableExpander {
-        fn variable(&self, var: &str, _: bool) -> Option<Value> { self.0.get_var(var) }
+        fn variable(&mut self, var: &str, _: bool) -> Option<Value> { self.0.get_var(var) }
     }
 
     #[test]
@@ -78,7 +78,7 @@ mod tests {
         let variables = Variables::default();
         let input = &["1...10".to_owned()];
         assert_eq!(
-            ForExpression::new(input, &VariableExpander(variables)),
+            ForExpression::new(input, &mut VariableExpander(variables)),
             ForExpression::Range(1, 11)
         );
     }
@@ -88,7 +88,7 @@ mod tests {
         let variables = Variables::default();
         let input = &["1..10".to_owned()];
         assert_eq!(
-            ForExpression::new(input, &VariableExpander(variables)),
+            ForExpression::new(input, &mut VariableExpander(variables)),
             ForExpression::Range(1, 10)
         );
     }
@@ -104,7 +104,7 @@ mod tests {
             "5".to_owned(),
         ];
         assert_eq!(
-            ForExpression::new(&output.clone(), &VariableExpander(variables)),
+            ForExpression::new(&output.clone(), &mut VariableExpander(variables)),
             ForExpression::Multiple(output)
         );
     }
@@ -114,7 +114,7 @@ mod tests {
         let mut variables = Variables::default();
         variables.set_var("A", "1 2 3 4 5");
         assert_eq!(
-            ForExpression::new(&["$A".to_owned()], &VariableExpander(variables)),
+            ForExpression::new(&["$A".to_owned()], &mut VariableExpander(variables)),
             ForExpression::Normal("1 2 3 4 5".to_owned())
         );
     }
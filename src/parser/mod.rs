@@ -1,13 +1,18 @@
 mod arguments;
+mod completeness;
 pub mod assignments;
 mod loops;
 pub mod pipelines;
 pub mod shell_expand;
 mod statement;
 mod quotes;
+pub(crate) mod quoting;
 
 pub(crate) use self::arguments::ArgumentSplitter;
+pub use self::completeness::{completeness, Completeness, Incomplete};
 pub(crate) use self::loops::for_grammar::ForExpression;
 pub(crate) use self::quotes::QuoteTerminator;
-pub(crate) use self::shell_expand::{expand_string, Expander, Select};
-pub(crate) use self::statement::{parse_and_validate, StatementSplitter};
+pub(crate) use self::shell_expand::{expand_string, is_boolean_method, quote, timestamp, Expander,
+                                    Select};
+pub use self::statement::Location;
+pub(crate) use self::statement::{parse, replace_backticks, StatementParser, StatementSplitter};
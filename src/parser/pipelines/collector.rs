@@ -4,6 +4,7 @@ use std::collections::HashSet;
 use std::iter::Peekable;
 
 use super::{Input, PipeItem, Pipeline, RedirectFrom, Redirection};
+use parser::quoting::{Class, Quote, Quotes};
 use shell::{Job, JobKind};
 use types::*;
 
@@ -31,49 +32,21 @@ impl<'a> Collector<'a> {
         }
     }
 
-    fn single_quoted<I>(
-        &self,
-        bytes: &mut Peekable<I>,
-        start: usize,
-    ) -> Result<&'a str, &'static str>
+    /// Skips over the quoted string which begins with the quote at `start`, returning it with
+    /// its quotes intact.
+    fn quoted<I>(&self, bytes: &mut Peekable<I>, start: usize) -> Result<&'a str, &'static str>
         where I: Iterator<Item = (usize, u8)>
     {
-        while let Some(&(i, b)) = bytes.peek() {
-            match b {
-                // We return an inclusive range to keep the quote type intact
-                b'\'' => {
-                    bytes.next();
-                    return Ok(&self.data[start..i + 1]);
-                }
-                _ => (),
+        let mut quotes = Quotes::new();
+        while let Some((i, b)) = bytes.next() {
+            if quotes.scan(b) == Class::Close {
+                return Ok(&self.data[start..i + 1]);
             }
-            bytes.next();
         }
-        Err("ion: syntax error: unterminated single quote")
-    }
-
-    fn double_quoted<I>(
-        &self,
-        bytes: &mut Peekable<I>,
-        start: usize,
-    ) -> Result<&'a str, &'static str>
-        where I: Iterator<Item = (usize, u8)>
-    {
-        while let Some(&(i, b)) = bytes.peek() {
-            match b {
-                b'\\' => {
-                    bytes.next();
-                }
-                // We return an inclusive range to keep the quote type intact
-                b'"' => {
-                    bytes.next();
-                    return Ok(&self.data[start..i + 1]);
-                }
-                _ => (),
-            }
-            bytes.next();
+        match quotes.quote() {
+            Some(Quote::Single) => Err("ion: syntax error: unterminated single quote"),
+            _ => Err("ion: syntax error: unterminated quote"),
         }
-        Err("ion: syntax error: unterminated quote")
     }
 
     fn arg<I>(&self, bytes: &mut Peekable<I>) -> Result<Option<&'a str>, &'static str>
@@ -140,22 +113,17 @@ impl<'a> Collector<'a> {
                                 break;
                             }
                         }
-                        // Reaching this block means that either there is no next byte, or the next
-                        // byte is none of '>' or '|', indicating that this is not the beginning of
-                        // a redirection for stderr
-                        bytes.next();
                     }
-                }
-                // Evaluate a quoted string but do not return it
-                // We pass in i, the index of a quote, but start a character later. This ensures
-                // the production rules will produce strings with the quotes intact
-                b'"' => {
+                    // Reaching this point means that either there is no next byte, the next
+                    // byte is none of '>' or '|', or the `^` is nested, as in the exclusive or
+                    // of an arithmetic expansion, so this is not the start of a redirection
                     bytes.next();
-                    self.double_quoted(bytes, i)?;
                 }
-                b'\'' => {
-                    bytes.next();
-                    self.single_quoted(bytes, i)?;
+                // Evaluate a quoted string but do not return it
+                // We pass in i, the index of the quote, so that the production rules will
+                // produce strings with the quotes intact
+                b'"' | b'\'' => {
+                    self.quoted(bytes, i)?;
                 }
                 // If we see a backslash, assume that it is leading up to an escaped character
                 // and skip the next character
@@ -305,6 +273,11 @@ impl<'a> Collector<'a> {
                             bytes.next();
                             try_add_item!(JobKind::Or);
                         }
+                        // Bash's spelling of `&|`, which pipes both stdout and stderr.
+                        Some(&(_, b'&')) => {
+                            bytes.next();
+                            try_add_item!(JobKind::Pipe(RedirectFrom::Both));
+                        }
                         Some(_) | None => {
                             try_add_item!(JobKind::Pipe(RedirectFrom::Stdout));
                         }
@@ -710,6 +683,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn nested_caret() {
+        if let Statement::Pipeline(pipeline) = parse("echo $((4 ^ 5)) a^b") {
+            let items = pipeline.items;
+            assert_eq!("$((4 ^ 5))", items[0].job.args[1]);
+            assert_eq!("a^b", items[0].job.args[2]);
+        } else {
+            assert!(false);
+        }
+    }
+
     #[test]
     fn several_blank_lines() {
         if let Statement::Default = parse("\n\n\n") {
@@ -790,6 +774,7 @@ mod tests {
                     ],
                 },
             ],
+            line:  None,
         };
         assert_eq!(parse(input), Statement::Pipeline(expected));
     }
@@ -823,6 +808,7 @@ mod tests {
                     ],
                 },
             ],
+            line:  None,
         };
         assert_eq!(parse(input), Statement::Pipeline(expected));
     }
@@ -856,6 +842,27 @@ mod tests {
                     ],
                 },
             ],
+            line:  None,
+        };
+        assert_eq!(parse(input), Statement::Pipeline(expected));
+    }
+
+    #[test]
+    fn pipes_from_each_stream() {
+        let input = "make ^| grep error |& tee log &| cat";
+        let item = |args, kind| PipeItem {
+            job:     Job::new(args, kind),
+            inputs:  Vec::new(),
+            outputs: Vec::new(),
+        };
+        let expected = Pipeline {
+            items: vec![
+                item(array!["make"], JobKind::Pipe(RedirectFrom::Stderr)),
+                item(array!["grep", "error"], JobKind::Pipe(RedirectFrom::Both)),
+                item(array!["tee", "log"], JobKind::Pipe(RedirectFrom::Both)),
+                item(array!["cat"], JobKind::Last),
+            ],
+            line:  None,
         };
         assert_eq!(parse(input), Statement::Pipeline(expected));
     }
@@ -906,6 +913,7 @@ mod tests {
                     outputs: vec![],
                 },
             ],
+            line:  None,
         };
         assert_eq!(Statement::Pipeline(expected), parse(input));
     }
@@ -921,6 +929,7 @@ mod tests {
                     outputs: vec![],
                 },
             ],
+            line:  None,
         };
         assert_eq!(Statement::Pipeline(expected), parse(input));
     }
@@ -949,6 +958,7 @@ mod tests {
                     ],
                 },
             ],
+            line:  None,
         };
         assert_eq!(Statement::Pipeline(expected), parse(input));
     }
@@ -984,8 +994,22 @@ mod tests {
                     ],
                 },
             ],
+            line:  None,
         };
         assert_eq!(parse(input), Statement::Pipeline(expected));
     }
 
+    #[test]
+    fn sole_variables() {
+        let variable = |input| match parse(input) {
+            Statement::Pipeline(pipeline) => pipeline.sole_variable().map(String::from),
+            _ => None,
+        };
+        assert_eq!(variable("$enabled"), Some("enabled".into()));
+        assert_eq!(variable("${enabled}"), Some("enabled".into()));
+        assert_eq!(variable("$enabled arg"), None);
+        assert_eq!(variable("$enabled | cat"), None);
+        assert_eq!(variable("$contains($a, b)"), None);
+        assert_eq!(variable("enabled"), None);
+    }
 }
@@ -2,7 +2,7 @@ mod collector;
 
 pub(crate) use self::collector::*;
 
-use super::expand_string;
+use super::{expand_string, is_boolean_method};
 use shell::{Job, JobKind, Shell};
 use std::fmt;
 
@@ -34,6 +34,8 @@ pub(crate) enum Input {
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) struct Pipeline {
     pub items: Vec<PipeItem>,
+    /// The line of the script at which the pipeline was written, if it was parsed from one.
+    pub line:  Option<usize>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -52,7 +54,7 @@ impl PipeItem {
         }
     }
 
-    pub(crate) fn expand(&mut self, shell: &Shell) {
+    pub(crate) fn expand(&mut self, shell: &mut Shell) {
         self.job.expand(shell);
 
         for input in self.inputs.iter_mut() {
@@ -71,10 +73,17 @@ impl PipeItem {
 }
 
 impl Pipeline {
-    pub(crate) fn new() -> Self { Pipeline { items: Vec::new() } }
+    pub(crate) fn new() -> Self {
+        Pipeline {
+            items: Vec::new(),
+            line:  None,
+        }
+    }
 
-    pub(crate) fn expand(&mut self, shell: &Shell) {
-        self.items.iter_mut().for_each(|i| i.expand(shell));
+    pub(crate) fn expand(&mut self, shell: &mut Shell) {
+        for item in &mut self.items {
+            item.expand(shell);
+        }
     }
 
     pub(crate) fn requires_piping(&self) -> bool {
@@ -82,6 +91,33 @@ impl Pipeline {
             || self.items.iter().any(|it| it.inputs.len() > 0)
             || self.items.last().unwrap().job.kind == JobKind::Background
     }
+
+    /// Whether the pipeline consists solely of a method which expands to a boolean.
+    pub(crate) fn is_boolean_method(&self) -> bool {
+        self.items.len() == 1 && !self.requires_piping() && self.items[0].job.args.len() == 1
+            && is_boolean_method(&self.items[0].job.args[0])
+    }
+
+    /// The name of the variable that the pipeline consists of, if it consists solely of a
+    /// variable, such as `$x` or `${x}`.
+    pub(crate) fn sole_variable(&self) -> Option<&str> {
+        if self.items.len() != 1 || self.requires_piping() || self.items[0].job.args.len() != 1 {
+            return None;
+        }
+        let arg = &self.items[0].job.args[0];
+        let name = if arg.starts_with("${") && arg.ends_with('}') {
+            &arg[2..arg.len() - 1]
+        } else if arg.starts_with('$') {
+            &arg[1..]
+        } else {
+            return None;
+        };
+        if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            Some(name)
+        } else {
+            None
+        }
+    }
 }
 
 impl fmt::Display for Pipeline {
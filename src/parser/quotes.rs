@@ -1,13 +1,14 @@
+use super::completeness::Incomplete;
+use super::quoting::{Class, Quotes};
 use std::str;
 
 bitflags! {
     pub struct Flags : u8 {
-        const SQUOTE = 1;
-        const DQUOTE = 2;
-        const TRIM   = 4;
-        const ARRAY  = 8;
-        const COMM   = 16;
-        const EOF    = 32;
+        const TRIM   = 1;
+        const ARRAY  = 2;
+        const COMM   = 4;
+        const EOF    = 8;
+        const PAREN  = 16;
     }
 }
 
@@ -16,7 +17,9 @@ pub(crate) struct QuoteTerminator {
     eof:        Option<String>,
     eof_buffer: String,
     array:      usize,
+    paren:      usize,
     read:       usize,
+    quotes:     Quotes,
     flags:      Flags,
 }
 
@@ -27,7 +30,9 @@ impl QuoteTerminator {
             eof:        None,
             eof_buffer: String::new(),
             array:      0,
+            paren:      0,
             read:       0,
+            quotes:     Quotes::new(),
             flags:      Flags::empty(),
         }
     }
@@ -54,13 +59,13 @@ impl QuoteTerminator {
                     let mut bytes = self.buffer.bytes().skip(self.read);
                     while let Some(character) = bytes.next() {
                         self.read += 1;
+                        if self.quotes.scan(character) != Class::Unquoted {
+                            continue;
+                        }
                         match character {
-                            b'\\' => {
-                                let _ = bytes.next();
-                            }
-                            b'\'' if !self.flags.intersects(DQUOTE) => self.flags ^= SQUOTE,
-                            b'"' if !self.flags.intersects(SQUOTE) => self.flags ^= DQUOTE,
-                            b'<' if !self.flags.contains(SQUOTE | DQUOTE) => {
+                            // Within parentheses, such as those of an arithmetic expansion,
+                            // `<<` is a shift rather than the start of a here-document.
+                            b'<' if self.paren == 0 => {
                                 let as_bytes = self.buffer.as_bytes();
                                 if Some(&b'<') == as_bytes.get(self.read) {
                                     self.read += 1;
@@ -74,17 +79,27 @@ impl QuoteTerminator {
                                     }
                                 }
                             }
-                            b'[' if !self.flags.intersects(DQUOTE | SQUOTE) => {
+                            b'[' => {
                                 self.flags |= ARRAY;
                                 self.array += 1;
                             }
-                            b']' if !self.flags.intersects(DQUOTE | SQUOTE) => {
+                            b']' => {
                                 self.array -= 1;
                                 if self.array == 0 {
                                     self.flags -= ARRAY
                                 }
                             }
-                            b'#' if !self.flags.intersects(DQUOTE | SQUOTE) => if self.read > 1 {
+                            b'(' => {
+                                self.flags |= PAREN;
+                                self.paren += 1;
+                            }
+                            b')' if self.paren != 0 => {
+                                self.paren -= 1;
+                                if self.paren == 0 {
+                                    self.flags -= PAREN
+                                }
+                            }
+                            b'#' => if self.read > 1 {
                                 let character = self.buffer.as_bytes().get(self.read - 2).unwrap();
                                 if [b' ', b'\n'].contains(character) {
                                     instance |= COMM;
@@ -102,36 +117,36 @@ impl QuoteTerminator {
                     self.buffer.push('\n');
                     return false;
                 } else if instance.contains(COMM) {
-                    self.buffer.truncate(self.read - 1);
-                    return !self.flags.intersects(SQUOTE | DQUOTE | ARRAY);
+                    // Lines which only contain a comment are kept, so that the comments which
+                    // document a function remain within its source.
+                    if !self.buffer[..self.read - 1].trim().is_empty() {
+                        self.buffer.truncate(self.read - 1);
+                    }
+                    return !self.quotes.is_quoted() && !self.flags.intersects(ARRAY | PAREN);
                 }
             }
 
-            if self.flags.intersects(SQUOTE | DQUOTE | ARRAY) {
-                if let Some(b'\\') = self.buffer.bytes().last() {
-                    let _ = self.buffer.pop();
-                    self.read -= 1;
-                    self.flags |= TRIM;
-                } else {
-                    self.read += 1;
-                    self.buffer.push(if self.flags.contains(ARRAY) { ' ' } else { '\n' });
-                }
+            if self.quotes.is_escaping() {
+                // A backslash which ends the line escapes the newline, continuing the line.
+                let _ = self.buffer.pop();
+                self.read -= 1;
+                self.quotes.clear_escape();
+                self.flags |= TRIM;
+                false
+            } else if self.quotes.is_quoted() || self.flags.intersects(ARRAY | PAREN) {
+                self.read += 1;
+                // Newlines are kept within quotes, but are otherwise only whitespace.
+                let newline = self.quotes.is_quoted() && !self.flags.contains(ARRAY);
+                self.buffer.push(if newline { '\n' } else { ' ' });
                 false
             } else {
-                if let Some(b'\\') = self.buffer.bytes().last() {
-                    let _ = self.buffer.pop();
-                    self.read -= 1;
-                    self.flags |= TRIM;
-                    false
+                // If the last two bytes are either '&&' or '||', we aren't terminated yet.
+                let bytes = self.buffer.as_bytes();
+                if bytes.len() >= 2 {
+                    let bytes = &bytes[bytes.len() - 2..];
+                    bytes != &[b'&', b'&'] && bytes != &[b'|', b'|']
                 } else {
-                    // If the last two bytes are either '&&' or '||', we aren't terminated yet.
-                    let bytes = self.buffer.as_bytes();
-                    if bytes.len() >= 2 {
-                        let bytes = &bytes[bytes.len() - 2..];
-                        bytes != &[b'&', b'&'] && bytes != &[b'|', b'|']
-                    } else {
-                        true
-                    }
+                    true
                 }
             }
         };
@@ -149,4 +164,57 @@ impl QuoteTerminator {
     }
 
     pub(crate) fn consume(self) -> String { self.buffer }
+
+    /// Why the input has not been terminated yet, after `check_termination` has failed.
+    pub(crate) fn incomplete(&self) -> Incomplete {
+        if self.eof.is_some() {
+            Incomplete::HereDocument
+        } else if self.quotes.is_quoted() {
+            Incomplete::Quote
+        } else if self.flags.contains(ARRAY) {
+            Incomplete::Bracket
+        } else if self.flags.contains(PAREN) {
+            Incomplete::Paren
+        } else {
+            Incomplete::Continuation
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terminate(line: &str) -> Option<String> {
+        let mut terminator = QuoteTerminator::new(line.into());
+        if terminator.check_termination() {
+            Some(terminator.consume())
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(terminate("echo one # it's"), Some("echo one ".into()));
+        assert_eq!(terminate("    # it's documented"), Some("    # it's documented".into()));
+        assert_eq!(terminate("echo 'one # two"), None);
+    }
+
+    #[test]
+    fn parentheses() {
+        assert_eq!(terminate("echo $(seq 1 3) @(ls)"), Some("echo $(seq 1 3) @(ls)".into()));
+        assert_eq!(terminate("echo :)"), Some("echo :)".into()));
+        assert_eq!(terminate("echo '(' \"(\""), Some("echo '(' \"(\"".into()));
+
+        let mut terminator = QuoteTerminator::new("echo $(seq".into());
+        assert!(!terminator.check_termination());
+        assert_eq!(terminator.incomplete(), Incomplete::Paren);
+        terminator.append("1 3)".into());
+        assert!(terminator.check_termination());
+        assert_eq!(terminator.consume(), "echo $(seq 1 3)");
+
+        assert_eq!(terminate("echo $((1 << 3))"), Some("echo $((1 << 3))".into()));
+        assert_eq!(terminate("echo $((n <<= 2))"), Some("echo $((n <<= 2))".into()));
+    }
 }
@@ -0,0 +1,142 @@
+//! The quoting rules of the shell, which are shared by each of its scanners, so that every stage
+//! of parsing agrees upon which bytes are quoted. A backslash escapes the byte which follows it,
+//! except within single quotes, within which every byte is literal, so that `'it'\''s'` is read
+//! as `it's`, as it would be by POSIX shells.
+
+/// The kind of quotes which are open.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Quote {
+    Single,
+    Double,
+}
+
+/// The role of a byte within its string, according to the quotes which surround it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Class {
+    /// Neither quoted nor escaped, and so it may have a special meaning.
+    Unquoted,
+    /// Within double quotes, within which only expansions have a special meaning.
+    Double,
+    /// Within single quotes, within which nothing has a special meaning.
+    Single,
+    /// A backslash, which escapes the byte that follows it.
+    Escape,
+    /// A byte which was escaped by a backslash.
+    Escaped,
+    /// A quote which opens a quoted string.
+    Open,
+    /// A quote which closes a quoted string.
+    Close,
+}
+
+impl Class {
+    /// Whether expansions, such as `$var` or `$(cmd)`, are recognized at this byte.
+    pub(crate) fn expands(self) -> bool { self == Class::Unquoted || self == Class::Double }
+}
+
+/// Scans a string one byte at a time, classifying each byte by the quotes which surround it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Quotes {
+    quote:   Option<Quote>,
+    escaped: bool,
+}
+
+impl Quotes {
+    pub(crate) fn new() -> Quotes {
+        Quotes {
+            quote:   None,
+            escaped: false,
+        }
+    }
+
+    /// The quotes which are open after the last byte which was scanned.
+    pub(crate) fn quote(&self) -> Option<Quote> { self.quote }
+
+    pub(crate) fn is_quoted(&self) -> bool { self.quote.is_some() }
+
+    pub(crate) fn is_double(&self) -> bool { self.quote == Some(Quote::Double) }
+
+    /// Whether the last byte which was scanned is a backslash, which escapes the next byte.
+    pub(crate) fn is_escaping(&self) -> bool { self.escaped }
+
+    /// Forgets the backslash which was scanned last, as when it has been removed from the
+    /// string.
+    pub(crate) fn clear_escape(&mut self) { self.escaped = false; }
+
+    /// Classifies the next byte of the string.
+    pub(crate) fn scan(&mut self, byte: u8) -> Class {
+        if self.escaped {
+            self.escaped = false;
+            return Class::Escaped;
+        }
+        match (self.quote, byte) {
+            (Some(Quote::Single), b'\'') | (Some(Quote::Double), b'"') => {
+                self.quote = None;
+                Class::Close
+            }
+            (Some(Quote::Single), _) => Class::Single,
+            (_, b'\\') => {
+                self.escaped = true;
+                Class::Escape
+            }
+            (None, b'\'') => {
+                self.quote = Some(Quote::Single);
+                Class::Open
+            }
+            (None, b'"') => {
+                self.quote = Some(Quote::Double);
+                Class::Open
+            }
+            (Some(Quote::Double), _) => Class::Double,
+            (None, _) => Class::Unquoted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classes() {
+        use self::Class::*;
+        let cases: &[(&str, &[Class])] = &[
+            ("a b", &[Unquoted, Unquoted, Unquoted]),
+            ("'a'", &[Open, Single, Close]),
+            ("\"a\"", &[Open, Double, Close]),
+            ("\\a", &[Escape, Escaped]),
+            ("\\'a", &[Escape, Escaped, Unquoted]),
+            ("'\\'", &[Open, Single, Close]),
+            ("'it'\\''s'", &[Open, Single, Single, Close, Escape, Escaped, Open, Single, Close]),
+            ("\"\\\"\"", &[Open, Escape, Escaped, Close]),
+            ("\"'\"", &[Open, Double, Close]),
+            ("'\"'", &[Open, Single, Close]),
+            ("\"$a\"$", &[Open, Double, Double, Close, Unquoted]),
+            ("\\\\a", &[Escape, Escaped, Unquoted]),
+        ];
+        for &(input, expected) in cases {
+            let mut quotes = Quotes::new();
+            let classes = input.bytes().map(|byte| quotes.scan(byte)).collect::<Vec<_>>();
+            assert_eq!(&classes[..], expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn open_quotes() {
+        let cases = [
+            ("'a", Some(Quote::Single)),
+            ("\"a", Some(Quote::Double)),
+            ("\"a'", Some(Quote::Double)),
+            ("'a\\'", None),
+            ("\"a\\\"", Some(Quote::Double)),
+            ("\\'", None),
+        ];
+        for &(input, expected) in cases.iter() {
+            let mut quotes = Quotes::new();
+            for byte in input.bytes() {
+                quotes.scan(byte);
+            }
+            assert_eq!(quotes.quote(), expected, "{}", input);
+        }
+    }
+}
@@ -0,0 +1,708 @@
+//! Evaluation of arithmetic expansions, such as `$((x * 5 + y))`. As in POSIX shells, an
+//! expansion may also assign to variables, as in `$((i += 1))` or `$((i++))`, and may consist of
+//! several comma-separated expressions, which expand to the value of the last.
+
+use super::Expander;
+use super::calc;
+use decimal::{Decimal, DIVISION_SCALE};
+use units;
+
+fn is_name_byte(byte: u8) -> bool {
+    match byte {
+        b'0'...b'9' | b'A'...b'Z' | b'_' | b'a'...b'z' => true,
+        _ => false,
+    }
+}
+
+/// Whether the word is the name of a variable, rather than a number.
+fn is_name(word: &str) -> bool {
+    word.bytes().next().map_or(false, |byte| !(byte >= b'0' && byte <= b'9'))
+        && word.bytes().all(is_name_byte)
+}
+
+/// Evaluates an expression in which all variables have been substituted. Expressions which are
+/// annotated with a type are evaluated with arbitrary precision, and expressions of integers
+/// with `Integers`, while all others, such as those with fractions, are left to the `calc` crate.
+/// Numbers in scientific notation, or with the suffix of a unit, are accepted by each of them.
+fn calculate(expression: &str, precise: Option<Precise>) -> Result<String, String> {
+    if let Some(precise) = precise {
+        return numbers(&precise, expression).map(|value| value.to_string()).ok_or_else(|| {
+            let name = if precise == Precise::BigInt { "bigint" } else { "decimal" };
+            format!("unable to evaluate '{}' as a {}", expression.trim(), name)
+        });
+    }
+    if let Some(value) = numbers(&Integers, expression) {
+        return Ok(value.to_string());
+    }
+    match units::parse_float(expression.trim()) {
+        Some(value) if value.is_finite() => Ok(value.to_string()),
+        _ => {
+            let expression = plain_numbers(expression);
+            calc::eval(&expression).map(|value| value.to_string()).map_err(Into::into)
+        }
+    }
+}
+
+/// Rewrites numbers in scientific notation, or with the suffix of a unit, as plain numbers,
+/// which are understood by the `calc` crate.
+fn plain_numbers(expression: &str) -> String {
+    let tokens = tokenize(expression);
+    let is_plain = |token: &Token| match *token {
+        Token::Number(literal) => units::parse_decimal(literal).is_none()
+            || literal.bytes().all(|byte| byte == b'.' || (byte >= b'0' && byte <= b'9')),
+        _ => true,
+    };
+    if tokens.iter().all(is_plain) {
+        return expression.to_owned();
+    }
+    let mut plain = String::with_capacity(expression.len());
+    for token in tokens {
+        match token {
+            Token::Number(literal) => match units::parse_decimal(literal) {
+                Some(number) => plain.push_str(&number.to_string()),
+                None => plain.push_str(literal),
+            },
+            Token::Operator(operator) => plain.push_str(operator),
+            Token::Open => plain.push('('),
+            Token::Close => plain.push(')'),
+        }
+        plain.push(' ');
+    }
+    plain
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Token<'a> {
+    Number(&'a str),
+    Operator(&'static str),
+    Open,
+    Close,
+}
+
+/// Operators which consist of more than one character must precede their prefixes.
+const OPERATORS: &[&str] = &[
+    "**", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "*", "/", "%", "+", "-", "<", ">", "&",
+    "^", "|", "!", "~",
+];
+
+fn tokenize(expression: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_left();
+    while !rest.is_empty() {
+        if rest.starts_with('(') || rest.starts_with(')') {
+            tokens.push(if rest.starts_with('(') { Token::Open } else { Token::Close });
+            rest = &rest[1..];
+        } else if let Some(operator) = OPERATORS.iter().find(|&&op| rest.starts_with(op)) {
+            tokens.push(Token::Operator(operator));
+            rest = &rest[operator.len()..];
+        } else {
+            // Any other character is taken as a number of its own, which will fail to parse.
+            let is_number_byte = |b: &u8| is_name_byte(*b) || *b == b'.';
+            let mut length = match rest.bytes().take_while(&is_number_byte).count() {
+                0 => rest.chars().next().map_or(0, char::len_utf8),
+                length => length,
+            };
+            // The sign of an exponent, as in `1.5e-3`, belongs to the number.
+            let bytes = rest.as_bytes();
+            let is_exponent = bytes[0] >= b'0' && bytes[0] <= b'9'
+                && (bytes[length - 1] == b'e' || bytes[length - 1] == b'E')
+                && (rest[length..].starts_with('-') || rest[length..].starts_with('+'))
+                && bytes.get(length + 1).map_or(false, |&b| b >= b'0' && b <= b'9');
+            if is_exponent {
+                length += 1 + rest[length + 1..].bytes().take_while(&is_number_byte).count();
+            }
+            tokens.push(Token::Number(&rest[..length]));
+            rest = &rest[length..];
+        }
+        rest = rest.trim_left();
+    }
+    tokens
+}
+
+/// The precedence of a binary operator, as in C, where operators of a higher precedence bind
+/// more tightly.
+fn precedence(operator: &str) -> u8 {
+    match operator {
+        "**" => 11,
+        "*" | "/" | "%" => 10,
+        "+" | "-" => 9,
+        "<<" | ">>" => 8,
+        "<" | "<=" | ">" | ">=" => 7,
+        "==" | "!=" => 6,
+        "&" => 5,
+        "^" => 4,
+        "|" => 3,
+        "&&" => 2,
+        "||" => 1,
+        _ => 0,
+    }
+}
+
+/// The numbers with which an expression is evaluated, and how each operator applies to them.
+/// Each method returns `None` if the operation is not supported, or if it has no result.
+trait Arithmetic {
+    type Number;
+
+    fn parse(&self, literal: &str) -> Option<Self::Number>;
+    fn unary(&self, operator: &str, value: Self::Number) -> Option<Self::Number>;
+    fn binary(&self, operator: &str, left: Self::Number, right: Self::Number)
+        -> Option<Self::Number>;
+}
+
+/// Integers of 64 bits, with which expressions are evaluated by default.
+struct Integers;
+
+impl Arithmetic for Integers {
+    type Number = i64;
+
+    fn parse(&self, literal: &str) -> Option<i64> { units::parse_integer(literal) }
+
+    fn unary(&self, operator: &str, value: i64) -> Option<i64> {
+        match operator {
+            "-" => value.checked_neg(),
+            "+" => Some(value),
+            "!" => Some((value == 0) as i64),
+            "~" => Some(!value),
+            _ => None,
+        }
+    }
+
+    fn binary(&self, operator: &str, left: i64, right: i64) -> Option<i64> {
+        let shift =
+            |amount: i64| if amount >= 0 && amount < 64 { Some(amount as u32) } else { None };
+        match operator {
+            "**" if right >= 0 && right <= u32::max_value() as i64 => {
+                left.checked_pow(right as u32)
+            }
+            // Division which is not exact is left to produce a fraction.
+            "/" if right != 0 && left % right == 0 => left.checked_div(right),
+            "%" => left.checked_rem(right),
+            "*" => left.checked_mul(right),
+            "+" => left.checked_add(right),
+            "-" => left.checked_sub(right),
+            "<<" => left.checked_shl(shift(right)?),
+            ">>" => left.checked_shr(shift(right)?),
+            "<" => Some((left < right) as i64),
+            "<=" => Some((left <= right) as i64),
+            ">" => Some((left > right) as i64),
+            ">=" => Some((left >= right) as i64),
+            "==" => Some((left == right) as i64),
+            "!=" => Some((left != right) as i64),
+            "&" => Some(left & right),
+            "^" => Some(left ^ right),
+            "|" => Some(left | right),
+            "&&" => Some((left != 0 && right != 0) as i64),
+            "||" => Some((left != 0 || right != 0) as i64),
+            _ => None,
+        }
+    }
+}
+
+/// Numbers of arbitrary precision, which are selected by annotating an expansion with a type,
+/// as in `$((:decimal 0.1 + 0.2))`. Bitwise operators are not supported.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Precise {
+    /// Integers, whose quotients are truncated.
+    BigInt,
+    /// Decimal numbers, whose quotients are truncated to `DIVISION_SCALE` decimal places.
+    Decimal,
+}
+
+impl Arithmetic for Precise {
+    type Number = Decimal;
+
+    fn parse(&self, literal: &str) -> Option<Decimal> {
+        match units::parse_decimal(literal) {
+            Some(ref number) if *self == Precise::BigInt && !number.is_integer() => None,
+            number => number,
+        }
+    }
+
+    fn unary(&self, operator: &str, value: Decimal) -> Option<Decimal> {
+        match operator {
+            "-" => Some(value.negate()),
+            "+" => Some(value),
+            "!" => Some(Decimal::from_bool(value.is_zero())),
+            _ => None,
+        }
+    }
+
+    fn binary(&self, operator: &str, left: Decimal, right: Decimal) -> Option<Decimal> {
+        let boolean = |value: bool| Some(Decimal::from_bool(value));
+        match operator {
+            "**" => left.power(&right),
+            "/" if *self == Precise::BigInt => left.divide(&right, 0),
+            "/" => left.divide(&right, DIVISION_SCALE),
+            "%" => left.remainder(&right),
+            "*" => Some(left.multiply(&right)),
+            "+" => Some(left.add(&right)),
+            "-" => Some(left.subtract(&right)),
+            "<" => boolean(left < right),
+            "<=" => boolean(left <= right),
+            ">" => boolean(left > right),
+            ">=" => boolean(left >= right),
+            "==" => boolean(left == right),
+            "!=" => boolean(left != right),
+            "&&" => boolean(!left.is_zero() && !right.is_zero()),
+            "||" => boolean(!left.is_zero() || !right.is_zero()),
+            _ => None,
+        }
+    }
+}
+
+fn unary<A: Arithmetic>(arithmetic: &A, tokens: &[Token], id: &mut usize) -> Option<A::Number> {
+    let token = *tokens.get(*id)?;
+    *id += 1;
+    match token {
+        Token::Number(literal) => arithmetic.parse(literal),
+        Token::Open => {
+            let value = binary(arithmetic, tokens, id, 1)?;
+            match tokens.get(*id) {
+                Some(&Token::Close) => {
+                    *id += 1;
+                    Some(value)
+                }
+                _ => None,
+            }
+        }
+        Token::Operator(operator) => {
+            let value = unary(arithmetic, tokens, id)?;
+            arithmetic.unary(operator, value)
+        }
+        Token::Close => None,
+    }
+}
+
+fn binary<A: Arithmetic>(
+    arithmetic: &A,
+    tokens: &[Token],
+    id: &mut usize,
+    minimum: u8,
+) -> Option<A::Number> {
+    let mut left = unary(arithmetic, tokens, id)?;
+    while let Some(&Token::Operator(operator)) = tokens.get(*id) {
+        let precedence = precedence(operator);
+        if precedence == 0 || precedence < minimum {
+            break;
+        }
+        *id += 1;
+        // Powers are right associative, and all other operators are left associative.
+        let next = if operator == "**" { precedence } else { precedence + 1 };
+        let right = binary(arithmetic, tokens, id, next)?;
+        left = arithmetic.binary(operator, left, right)?;
+    }
+    Some(left)
+}
+
+/// Evaluates an expression of numbers, with the operators and precedence of C. Returns `None`
+/// if the expression contains anything else, or if any operation within it has no result.
+fn numbers<A: Arithmetic>(arithmetic: &A, expression: &str) -> Option<A::Number> {
+    let tokens = tokenize(expression);
+    let mut id = 0;
+    let value = binary(arithmetic, &tokens, &mut id, 1)?;
+    if id == tokens.len() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// The value of a variable which is being assigned to, which is `0` if it is unset or empty.
+fn current_value<E: Expander>(name: &str, expander: &mut E) -> String {
+    match expander.variable(name, false) {
+        Some(ref value) if !value.is_empty() => value.clone(),
+        _ => "0".into(),
+    }
+}
+
+/// Splits an assignment, such as `x += 1`, into the name of its variable, the operator to
+/// apply to the current value (which is empty for `=`), and the expression that is assigned.
+fn split_assignment(expression: &str) -> Option<(&str, &str, &str)> {
+    let bytes = expression.as_bytes();
+    let name_end = bytes.iter().take_while(|&&byte| is_name_byte(byte)).count();
+    let name = &expression[..name_end];
+    if !is_name(name) {
+        return None;
+    }
+
+    let rest = expression[name_end..].trim_left();
+    let operator_end = rest
+        .bytes()
+        .take_while(|&byte| b"+-*/%&|^<>".contains(&byte))
+        .count();
+    let operator = &rest[..operator_end];
+    let rest = &rest[operator_end..];
+    // `==`, `<=`, and `>=` are comparisons, rather than assignments.
+    if !rest.starts_with('=') || rest.starts_with("==") || operator == "<" || operator == ">" {
+        return None;
+    }
+    Some((name, operator, &rest[1..]))
+}
+
+/// The word which follows a prefix increment.
+fn following(increment: &str) -> &str {
+    let word = &increment[2..];
+    &word[..word.bytes().take_while(|&byte| is_name_byte(byte)).count()]
+}
+
+/// Splits the expression at each comma which is not within parentheses.
+fn split_commas(expression: &str) -> Vec<&str> {
+    let (mut depth, mut start, mut parts) = (0, 0, Vec::new());
+    for (id, byte) in expression.bytes().enumerate() {
+        match byte {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b',' if depth == 0 => {
+                parts.push(&expression[start..id]);
+                start = id + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(&expression[start..]);
+    parts
+}
+
+/// The length of the parenthesized group at the start of the expression, including both of its
+/// parentheses, or `None` if it is never closed.
+fn group_length(expression: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (id, byte) in expression.bytes().enumerate() {
+        match byte {
+            b'(' => depth += 1,
+            b')' if depth == 1 => return Some(id + 1),
+            b')' => depth -= 1,
+            _ => (),
+        }
+    }
+    None
+}
+
+const FUNCTIONS: &[&str] = &[
+    "abs", "acos", "asin", "atan", "atan2", "ceil", "cos", "exp", "floor", "log", "log10", "log2",
+    "max", "min", "pow", "round", "sin", "sqrt", "tan",
+];
+
+/// Applies a mathematical function to its arguments. Each function takes one argument, except
+/// for `pow` and `atan2`, which take two, `log`, which takes an optional base, and `min` and
+/// `max`, which take any number of them.
+fn call(name: &str, arguments: &[f64]) -> Result<f64, String> {
+    let expected = match name {
+        "pow" | "atan2" => 2,
+        "log" if arguments.len() == 2 => 2,
+        "min" | "max" => arguments.len().max(1),
+        _ => 1,
+    };
+    if arguments.len() != expected {
+        let plural = if expected == 1 { "" } else { "s" };
+        return Err(format!("{}: expected {} argument{}", name, expected, plural));
+    }
+
+    let x = arguments[0];
+    let value = match name {
+        "abs" => x.abs(),
+        "acos" => x.acos(),
+        "asin" => x.asin(),
+        "atan" => x.atan(),
+        "atan2" => x.atan2(arguments[1]),
+        "ceil" => x.ceil(),
+        "cos" => x.cos(),
+        "exp" => x.exp(),
+        "floor" => x.floor(),
+        "log" if expected == 2 => x.log(arguments[1]),
+        "log" => x.ln(),
+        "log10" => x.log10(),
+        "log2" => x.log2(),
+        "max" => arguments.iter().cloned().fold(x, f64::max),
+        "min" => arguments.iter().cloned().fold(x, f64::min),
+        "pow" => x.powf(arguments[1]),
+        "round" => x.round(),
+        "sin" => x.sin(),
+        "sqrt" => x.sqrt(),
+        _ => x.tan(),
+    };
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(format!("{}: result is undefined", name))
+    }
+}
+
+/// Splits a conditional expression, such as `a > b ? a : b`, into its condition and the
+/// expressions to evaluate when it is true and when it is false.
+fn split_conditional(expression: &str) -> Option<(&str, &str, &str)> {
+    let (mut depth, mut question, mut nested) = (0, None, 0);
+    for (id, byte) in expression.bytes().enumerate() {
+        match byte {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b'?' if depth == 0 && question.is_some() => nested += 1,
+            b'?' if depth == 0 => question = Some(id),
+            b':' if depth == 0 && nested != 0 => nested -= 1,
+            b':' if depth == 0 => if let Some(question) = question {
+                let condition = &expression[..question];
+                return Some((condition, &expression[question + 1..id], &expression[id + 1..]));
+            },
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Evaluates a single expression, performing any assignments or increments within it.
+fn evaluate<E: Expander>(
+    expression: &str,
+    expander: &mut E,
+    precise: Option<Precise>,
+) -> Result<String, String> {
+    if let Some((condition, if_true, if_false)) = split_conditional(expression) {
+        // Only the expression which is chosen is evaluated, along with its assignments.
+        if split_assignment(condition).is_none() {
+            let condition = evaluate(condition.trim(), expander, precise)?;
+            let is_true = condition.parse::<f64>().map(|value| value != 0.0).unwrap_or(false);
+            let chosen = if is_true { if_true.trim() } else { if_false.trim() };
+            return evaluate(chosen, expander, precise);
+        }
+    }
+
+    if let Some((name, operator, value)) = split_assignment(expression) {
+        let value = evaluate(value, expander, precise)?;
+        let value = if operator.is_empty() {
+            value
+        } else {
+            let current = current_value(name, expander);
+            let expression = [current.as_str(), " ", operator, " (", value.as_str(), ")"].concat();
+            calculate(&expression, precise)?
+        };
+        expander.assign(name, &value);
+        return Ok(value);
+    }
+
+    let mut intermediate = String::with_capacity(expression.len());
+    let bytes = expression.as_bytes();
+    let mut id = 0;
+    while id < bytes.len() {
+        let rest = &expression[id..];
+        if is_name_byte(bytes[id]) {
+            let start = id;
+            while id < bytes.len() && is_name_byte(bytes[id]) {
+                id += 1;
+            }
+            let word = &expression[start..id];
+            let increment = &expression[id..];
+            if FUNCTIONS.contains(&word) && increment.trim_left().starts_with('(') {
+                let open = id + increment.len() - increment.trim_left().len();
+                let length = group_length(&expression[open..])
+                    .ok_or_else(|| format!("{}: missing closing parenthesis", word))?;
+                let inner = &expression[open + 1..open + length - 1];
+                let mut arguments = Vec::new();
+                if !inner.trim().is_empty() {
+                    for argument in split_commas(inner) {
+                        let value = evaluate(argument.trim(), expander, precise)?;
+                        let value = value
+                            .parse::<f64>()
+                            .map_err(|_| format!("{}: '{}' is not a number", word, value))?;
+                        arguments.push(value);
+                    }
+                }
+                // The result is enclosed so that a negative result is not taken as an operator.
+                intermediate.push('(');
+                intermediate.push_str(&call(word, &arguments)?.to_string());
+                intermediate.push(')');
+                id = open + length;
+            } else if is_name(word) && (increment.starts_with("++") || increment.starts_with("--"))
+            {
+                // A postfix increment expands to the value from before it was incremented.
+                let current = current_value(word, expander);
+                let expression = [current.as_str(), " ", &increment[..1], " 1"].concat();
+                let value = calculate(&expression, precise)?;
+                expander.assign(word, &value);
+                intermediate.push_str(&current);
+                id += 2;
+            } else {
+                match expander.variable(word, false) {
+                    Some(value) => intermediate.push_str(&value),
+                    None => intermediate.push_str(word),
+                }
+            }
+        } else if (rest.starts_with("++") || rest.starts_with("--")) && is_name(following(rest)) {
+            // A prefix increment expands to the value from after it was incremented.
+            let name = following(rest);
+            let current = current_value(name, expander);
+            let expression = [current.as_str(), " ", &rest[..1], " 1"].concat();
+            let value = calculate(&expression, precise)?;
+            expander.assign(name, &value);
+            intermediate.push_str(&value);
+            id += 2 + name.len();
+        } else {
+            let character = rest.chars().next().unwrap();
+            intermediate.push(character);
+            id += character.len_utf8();
+        }
+    }
+
+    calculate(&intermediate, precise)
+}
+
+/// Evaluates the contents of an arithmetic expansion, for example:
+/// ```ignore
+/// x * 5 + y => 22
+/// ```
+/// if `x=5` and `y=7`
+///
+/// An expansion which begins with the type `:bigint` or `:decimal` is evaluated with arbitrary
+/// precision, as in `$((:decimal price * 1.08))`.
+pub(crate) fn evaluate_arithmetic<E: Expander>(
+    input: &str,
+    expander: &mut E,
+) -> Result<String, String> {
+    let input = input.trim_left();
+    let (input, precise) = if input.starts_with(":bigint ") {
+        (&input[8..], Some(Precise::BigInt))
+    } else if input.starts_with(":decimal ") {
+        (&input[9..], Some(Precise::Decimal))
+    } else {
+        (input, None)
+    };
+
+    let mut result = String::new();
+    for expression in split_commas(input) {
+        result = evaluate(expression.trim(), expander, precise)?;
+    }
+    Ok(result)
+}
+
+/// Expands an arithmetic expansion, which expands to its error if it cannot be evaluated.
+pub(super) fn expand_arithmetic<E: Expander>(output: &mut String, input: &str, expander: &mut E) {
+    match evaluate_arithmetic(input, expander) {
+        Ok(value) => output.push_str(&value),
+        Err(why) => output.push_str(&why),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use types::Value;
+
+    #[derive(Default)]
+    struct Variables(HashMap<String, String>);
+
+    impl Expander for Variables {
+        fn variable(&mut self, name: &str, _: bool) -> Option<Value> { self.0.get(name).cloned() }
+
+        fn assign(&mut self, name: &str, value: &str) {
+            self.0.insert(name.into(), value.into());
+        }
+    }
+
+    impl Variables {
+        fn get(&self, name: &str) -> Option<String> { self.0.get(name).cloned() }
+    }
+
+    fn expand_with(variables: &mut Variables, expression: &str) -> String {
+        let mut output = String::new();
+        expand_arithmetic(&mut output, expression, variables);
+        output
+    }
+
+    #[test]
+    fn assignments() {
+        let mut variables = Variables::default();
+
+        assert_eq!(expand_with(&mut variables, "i = 2 * 3"), "6");
+        assert_eq!(variables.get("i"), Some("6".into()));
+        assert_eq!(expand_with(&mut variables, "i += 1"), "7");
+        assert_eq!(expand_with(&mut variables, "i++"), "7");
+        assert_eq!(variables.get("i"), Some("8".into()));
+        assert_eq!(expand_with(&mut variables, "--i * 2"), "14");
+        assert_eq!(variables.get("i"), Some("7".into()));
+        assert_eq!(expand_with(&mut variables, "j = i, j *= 2, j - 4"), "10");
+        assert_eq!(variables.get("j"), Some("14".into()));
+        assert_eq!(expand_with(&mut variables, "n++ + n"), "1");
+
+        assert_eq!(expand_with(&mut variables, "i > 5 ? i++ : n++"), "7");
+        assert_eq!((variables.get("i"), variables.get("n")), (Some("8".into()), Some("1".into())));
+        assert_eq!(expand_with(&mut variables, "x = i < 5 ? 1 : i < 10 ? 2 : 3"), "2");
+
+        assert_eq!(split_assignment("x <<= 2"), Some(("x", "<<", " 2")));
+        assert_eq!(split_assignment("x == 2"), None);
+        assert_eq!(split_assignment("x <= 2"), None);
+        assert_eq!(split_assignment("2 = 2"), None);
+    }
+
+    #[test]
+    fn functions() {
+        let mut variables = Variables::default();
+
+        assert_eq!(expand_with(&mut variables, "sqrt(16)"), "4");
+        assert_eq!(expand_with(&mut variables, "pow(2, 10) - abs(-24)"), "1000");
+        assert_eq!(expand_with(&mut variables, "floor(-1.5) + ceil(0.2) + round(2.5)"), "2");
+        assert_eq!(expand_with(&mut variables, "log2(8), log10(1000)"), "3");
+        assert_eq!(expand_with(&mut variables, "max(1, x = 5, 3) * min(2, sin(0))"), "0");
+        assert_eq!(variables.get("x"), Some("5".into()));
+        assert_eq!(expand_with(&mut variables, "sqrt(-1)"), "sqrt: result is undefined");
+        assert_eq!(expand_with(&mut variables, "pow(2)"), "pow: expected 2 arguments");
+        assert_eq!(expand_with(&mut variables, "abs(1"), "abs: missing closing parenthesis");
+    }
+
+    #[test]
+    fn precision() {
+        let mut variables = Variables::default();
+        let mut expand = |expression: &str| expand_with(&mut variables, expression);
+
+        assert_eq!(expand(":decimal 0.1 + 0.2"), "0.3");
+        assert_eq!(expand(":decimal price = 19.99, price * 3 - 0.97"), "59");
+        assert_eq!(expand(":decimal 2 / 3"), "0.66666666666666666666");
+        assert_eq!(expand(":decimal 1.5 > 1.25 ? 1 : 0"), "1");
+        assert_eq!(expand(":bigint 9223372036854775807 + 1"), "9223372036854775808");
+        assert_eq!(expand(":bigint n = 2 ** 70, n /= 3"), "393530540239137101141");
+        assert_eq!(expand(":bigint 7 / -2"), "-3");
+        assert_eq!(expand(":bigint 1.5"), "unable to evaluate '1.5' as a bigint");
+        assert_eq!(expand(":decimal 1 / 0"), "unable to evaluate '1 / 0' as a decimal");
+        assert_eq!(expand(":decimal 1 << 2"), "unable to evaluate '1 << 2' as a decimal");
+    }
+
+    #[test]
+    fn units() {
+        let mut variables = Variables::default();
+        let mut expand = |expression: &str| expand_with(&mut variables, expression);
+
+        assert_eq!(expand("1.5e3 + 1"), "1501");
+        assert_eq!(expand("2MiB / 4KiB"), "512");
+        assert_eq!(expand("limit = 4K, limit - 96"), "3904");
+        assert_eq!(expand("250ms * 6"), "1.5");
+        assert_eq!(expand("1e-3"), "0.001");
+        assert_eq!(expand("2.5E+2 - 50"), "200");
+        assert_eq!(expand(":decimal 1.5e-3 + 100ns"), "0.0015001");
+        assert_eq!(expand(":bigint 2PiB"), "2251799813685248");
+    }
+
+    #[test]
+    fn integers() {
+        let cases = [
+            ("1 + 2 * 3", Some(7)),
+            ("(1 + 2) * 3", Some(9)),
+            ("1 | 2 ^ 3 & 6", Some(1)),
+            ("1 << 4 | 1 << 2", Some(20)),
+            ("0xff >> 4 & 0x3", Some(3)),
+            ("0o755 & ~0o22 | 0b1", Some(0o755)),
+            ("-7 % 3 + 10 % 4", Some(1)),
+            ("2 ** 3 ** 2", Some(512)),
+            ("~0 == -1 && !0", Some(1)),
+            ("3 < 2 || 2 >= 2", Some(1)),
+            ("8 / 2", Some(4)),
+            ("7 / 2", None),
+            ("1 / 0", None),
+            ("1.5 + 1", None),
+            ("1 << 64", None),
+            ("(1 + 2", None),
+            ("4K - 1e3 + 2Ki", Some(5048)),
+            ("1.5e-1 * 10", None),
+        ];
+        for &(expression, expected) in cases.iter() {
+            assert_eq!(numbers(&Integers, expression), expected, "{}", expression);
+        }
+    }
+}
@@ -2,13 +2,17 @@
 extern crate calc;
 extern crate permutate;
 
+mod arithmetic;
 mod braces;
 mod ranges;
 mod words;
 
+pub(crate) use self::arithmetic::evaluate_arithmetic;
+use self::arithmetic::expand_arithmetic;
 use self::braces::BraceToken;
 use self::ranges::parse_range;
-pub(crate) use self::words::{Index, Range, Select, WordIterator, WordToken};
+pub(crate) use self::words::{is_boolean_method, quote, timestamp, Index, Range, Select,
+                             WordIterator, WordToken};
 use glob::glob;
 use std::str;
 use types::*;
@@ -29,24 +33,48 @@ pub(crate) trait Expander {
     /// Expand an array variable with some selection
     fn array(&self, &str, Select) -> Option<Array> { None }
     /// Expand a string variable given if its quoted / unquoted
-    fn variable(&self, &str, bool) -> Option<Value> { None }
+    fn variable(&mut self, &str, bool) -> Option<Value> { None }
     /// Expand a subshell expression
-    fn command(&self, &str) -> Option<Value> { None }
+    fn command(&mut self, &str) -> Option<Value> { None }
+    /// Reports a reference to a variable which could not be expanded
+    fn unset(&self, &str) {}
+    /// Reports a glob which did not match any paths
+    fn unmatched(&self, &str) {}
+    /// Called before a string is expanded. Returns `false` if the expansion should be aborted,
+    /// such as when expansions are nested too deeply.
+    fn enter_expansion(&self) -> bool { true }
+    /// Called after a string has been expanded.
+    fn exit_expansion(&self) {}
+    /// Whether trailing newlines are stripped from the output of a quoted process expansion
+    fn strip_newlines(&self) -> bool { true }
+    /// Whether the output of an unquoted process expansion is split into separate words
+    fn split_words(&self) -> bool { false }
+    /// Whether the given shell option is enabled, or `None` if there is no such option
+    fn option(&self, &str) -> Option<bool> { None }
+    /// Calls the given function with a single argument, returning its output and whether it
+    /// succeeded, or `None` if there is no such function
+    fn function(&mut self, &str, &str) -> Option<(Value, bool)> { None }
+    /// Whether a function of the given name exists
+    fn has_function(&self, &str) -> bool { false }
+    /// Assigns a value to a string variable, as arithmetic expansions such as `$((i += 1))` do
+    fn assign(&mut self, &str, &str) {}
+    /// Calls the given function as a method, with the method's input followed by its arguments,
+    /// returning its output, or `None` if there is no such function
+    fn method(&mut self, &str, &[&str]) -> Option<Value> { None }
 }
 
 fn expand_process<E: Expander>(
     current: &mut String,
     command: &str,
     selection: Select,
-    expander: &E,
+    expander: &mut E,
     quoted: bool,
 ) {
     if let Some(output) = expander.command(command) {
         if quoted {
-            let output: &str = if let Some(pos) = output.rfind(|x| x != '\n') {
-                &output[..pos + 1]
-            } else {
-                &output
+            let output: &str = match output.rfind(|x| x != '\n') {
+                Some(pos) if expander.strip_newlines() => &output[..pos + 1],
+                _ => &output,
             };
             slice(current, output, selection)
         } else {
@@ -68,7 +96,7 @@ fn expand_brace<E: Expander>(
     expanders: &mut Vec<Vec<String>>,
     tokens: &mut Vec<BraceToken>,
     nodes: &[&str],
-    expand_func: &E,
+    expand_func: &mut E,
     reverse_quoting: bool,
 ) {
     let mut temp = Vec::new();
@@ -94,7 +122,7 @@ fn expand_brace<E: Expander>(
     }
 }
 
-fn array_expand<E: Expander>(elements: &[&str], expand_func: &E, selection: Select) -> Array {
+fn array_expand<E: Expander>(elements: &[&str], expand_func: &mut E, selection: Select) -> Array {
     match selection {
         Select::None => Array::new(),
         Select::All => elements.iter().flat_map(|e| expand_string(e, expand_func, false)).collect(),
@@ -104,7 +132,7 @@ fn array_expand<E: Expander>(elements: &[&str], expand_func: &E, selection: Sele
     }
 }
 
-fn array_nth<E: Expander>(elements: &[&str], expand_func: &E, index: Index) -> Option<Value> {
+fn array_nth<E: Expander>(elements: &[&str], expand_func: &mut E, index: Index) -> Option<Value> {
     let mut expanded = elements.iter().flat_map(|e| expand_string(e, expand_func, false));
     match index {
         Index::Forward(n) => expanded.nth(n),
@@ -112,12 +140,12 @@ fn array_nth<E: Expander>(elements: &[&str], expand_func: &E, index: Index) -> O
     }
 }
 
-fn array_range<E: Expander>(elements: &[&str], expand_func: &E, range: Range) -> Array {
+fn array_range<E: Expander>(elements: &[&str], expand_func: &mut E, range: Range) -> Array {
     let expanded =
         elements.iter().flat_map(|e| expand_string(e, expand_func, false)).collect::<Array>();
     let len = expanded.len();
-    if let Some((start, length)) = range.bounds(len) {
-        expanded.into_iter().skip(start).take(length).collect()
+    if let Some(elements) = range.elements(expanded.into_iter(), len) {
+        elements.collect()
     } else {
         Array::new()
     }
@@ -139,8 +167,9 @@ fn slice<S: AsRef<str>>(output: &mut String, expanded: S, selection: Select) {
         },
         Select::Range(range) => {
             let graphemes = UnicodeSegmentation::graphemes(expanded.as_ref(), true);
-            if let Some((start, length)) = range.bounds(graphemes.clone().count()) {
-                let substring = graphemes.skip(start).take(length).collect::<Vec<&str>>().join("");
+            let len = graphemes.clone().count();
+            if let Some(graphemes) = range.elements(graphemes, len) {
+                let substring = graphemes.collect::<Vec<&str>>().join("");
                 output.push_str(&substring);
             }
         }
@@ -153,9 +182,13 @@ fn slice<S: AsRef<str>>(output: &mut String, expanded: S, selection: Select) {
 /// and variable words.
 pub(crate) fn expand_string<E: Expander>(
     original: &str,
-    expand_func: &E,
+    expand_func: &mut E,
     reverse_quoting: bool,
 ) -> Array {
+    if !expand_func.enter_expansion() {
+        return Array::new();
+    }
+
     let mut token_buffer = Vec::new();
     let mut contains_brace = false;
 
@@ -166,12 +199,14 @@ pub(crate) fn expand_string<E: Expander>(
         token_buffer.push(word);
     }
 
-    expand_tokens(&token_buffer, expand_func, reverse_quoting, contains_brace)
+    let expanded = expand_tokens(&token_buffer, expand_func, reverse_quoting, contains_brace);
+    expand_func.exit_expansion();
+    expanded
 }
 
 fn expand_braces<E: Expander>(
     word_tokens: &[WordToken],
-    expand_func: &E,
+    expand_func: &mut E,
     reverse_quoting: bool,
 ) -> Array {
     let mut expanded_words = Array::new();
@@ -185,8 +220,9 @@ fn expand_braces<E: Expander>(
                 output.push_str(&array_expand(elements, expand_func, index.clone()).join(" "));
             }
             WordToken::ArrayVariable(array, _, ref index) => {
-                if let Some(array) = expand_func.array(array, index.clone()) {
-                    output.push_str(&array.join(" "));
+                match expand_func.array(array, index.clone()) {
+                    Some(array) => output.push_str(&array.join(" ")),
+                    None => expand_func.unset(array),
                 }
             }
             WordToken::ArrayProcess(command, _, ref index) => match *index {
@@ -211,10 +247,9 @@ fn expand_braces<E: Expander>(
                     let mut temp = String::new();
                     expand_process(&mut temp, command, Select::All, expand_func, false);
                     let len = temp.split_whitespace().count();
-                    if let Some((start, length)) = range.bounds(len) {
-                        let res =
-                            temp.split_whitespace().skip(start).take(length).collect::<Vec<&str>>();
-                        output.push_str(&res.join(" "));
+                    let selection = range.elements(temp.split_whitespace(), len);
+                    if let Some(words) = selection {
+                        output.push_str(&words.collect::<Vec<&str>>().join(" "));
                     }
                 }
                 Select::Key(_) => (),
@@ -242,7 +277,10 @@ fn expand_braces<E: Expander>(
                 let quoted = if reverse_quoting { !quoted } else { quoted };
                 let expanded = match expand_func.variable(text, quoted) {
                     Some(var) => var,
-                    None => continue,
+                    None => {
+                        expand_func.unset(text);
+                        continue;
+                    }
                 };
 
                 slice(&mut output, expanded, index.clone());
@@ -268,7 +306,7 @@ fn expand_braces<E: Expander>(
     expanded_words
 }
 
-fn expand_single_array_token<E: Expander>(token: &WordToken, expand_func: &E) -> Option<Array> {
+fn expand_single_array_token<E: Expander>(token: &WordToken, expand_func: &mut E) -> Option<Array> {
     let mut output = String::new();
     match *token {
         WordToken::Array(ref elements, ref index) => {
@@ -278,7 +316,10 @@ fn expand_single_array_token<E: Expander>(token: &WordToken, expand_func: &E) ->
             match expand_func.array(array, index.clone()) {
                 Some(ref array) if quoted => Some(array.join(" ").into()).into_iter().collect(),
                 Some(array) => Some(array),
-                None => Some(Array::new()),
+                None => {
+                    expand_func.unset(array);
+                    Some(Array::new())
+                }
             }
         }
         WordToken::ArrayProcess(command, _, ref index) => match *index {
@@ -297,15 +338,9 @@ fn expand_single_array_token<E: Expander>(token: &WordToken, expand_func: &E) ->
             }
             Select::Range(range) => {
                 expand_process(&mut output, command, Select::All, expand_func, false);
-                if let Some((start, length)) = range.bounds(output.split_whitespace().count()) {
-                    Some(
-                        output
-                            .split_whitespace()
-                            .skip(start)
-                            .take(length)
-                            .map(From::from)
-                            .collect(),
-                    )
+                let len = output.split_whitespace().count();
+                if let Some(words) = range.elements(output.split_whitespace(), len) {
+                    Some(words.map(From::from).collect())
                 } else {
                     Some(Array::new())
                 }
@@ -319,7 +354,7 @@ fn expand_single_array_token<E: Expander>(token: &WordToken, expand_func: &E) ->
 
 fn expand_single_string_token<E: Expander>(
     token: &WordToken,
-    expand_func: &E,
+    expand_func: &mut E,
     reverse_quoting: bool,
 ) -> Array {
     let mut output = String::new();
@@ -334,12 +369,16 @@ fn expand_single_string_token<E: Expander>(
         WordToken::Process(command, quoted, ref index) => {
             let quoted = if reverse_quoting { !quoted } else { quoted };
             expand_process(&mut output, command, index.clone(), expand_func, quoted);
+            if !quoted && expand_func.split_words() {
+                return output.split_whitespace().map(Into::into).collect();
+            }
         }
         WordToken::Variable(text, quoted, ref index) => {
             let quoted = if reverse_quoting { !quoted } else { quoted };
             let expanded = match expand_func.variable(text, quoted) {
                 Some(var) => var,
                 None => {
+                    expand_func.unset(text);
                     if output != "" {
                         expanded_words.push(output.into());
                     }
@@ -362,7 +401,7 @@ fn expand_single_string_token<E: Expander>(
 fn expand<E: Expander>(
     output: &mut String,
     expanded_words: &mut Array,
-    expand_func: &E,
+    expand_func: &mut E,
     text: &str,
     do_glob: bool,
     tilde: bool,
@@ -385,6 +424,7 @@ fn expand<E: Expander>(
                     expanded_words.push(path.to_string_lossy().into_owned());
                 }
                 if !globs_found {
+                    expand_func.unmatched(&expanded);
                     expanded_words.push(expanded);
                 }
             }
@@ -397,7 +437,7 @@ fn expand<E: Expander>(
 
 pub(crate) fn expand_tokens<E: Expander>(
     token_buffer: &[WordToken],
-    expand_func: &E,
+    expand_func: &mut E,
     reverse_quoting: bool,
     contains_brace: bool,
 ) -> Array {
@@ -421,8 +461,9 @@ pub(crate) fn expand_tokens<E: Expander>(
                     output.push_str(&array_expand(elements, expand_func, index.clone()).join(" "));
                 }
                 WordToken::ArrayVariable(array, _, ref index) => {
-                    if let Some(array) = expand_func.array(array, index.clone()) {
-                        output.push_str(&array.join(" "));
+                    match expand_func.array(array, index.clone()) {
+                        Some(array) => output.push_str(&array.join(" ")),
+                        None => expand_func.unset(array),
                     }
                 }
                 WordToken::ArrayProcess(command, _, ref index) => match index.clone() {
@@ -446,13 +487,10 @@ pub(crate) fn expand_tokens<E: Expander>(
                     Select::Range(range) => {
                         let mut temp = String::new();
                         expand_process(&mut temp, command, Select::All, expand_func, false);
-                        if let Some((start, length)) = range.bounds(temp.split_whitespace().count())
-                        {
-                            let temp = temp.split_whitespace()
-                                .skip(start)
-                                .take(length)
-                                .collect::<Vec<_>>();
-                            output.push_str(&temp.join(" "))
+                        let len = temp.split_whitespace().count();
+                        let selection = range.elements(temp.split_whitespace(), len);
+                        if let Some(words) = selection {
+                            output.push_str(&words.collect::<Vec<_>>().join(" "))
                         }
                     }
                     Select::Key(_) => (),
@@ -478,7 +516,10 @@ pub(crate) fn expand_tokens<E: Expander>(
                     let quoted = if reverse_quoting { !quoted } else { quoted };
                     let expanded = match expand_func.variable(text, quoted) {
                         Some(var) => var,
-                        None => continue,
+                        None => {
+                            expand_func.unset(text);
+                            continue;
+                        }
                     };
 
                     slice(&mut output, expanded, index.clone());
@@ -496,47 +537,6 @@ pub(crate) fn expand_tokens<E: Expander>(
     }
 }
 
-/// Expand a string inside an arithmetic expression, for example:
-/// ```ignore
-/// x * 5 + y => 22
-/// ```
-/// if `x=5` and `y=7`
-fn expand_arithmetic<E: Expander>(output: &mut String, input: &str, expander: &E) {
-    let mut intermediate = String::with_capacity(input.as_bytes().len());
-    let mut varbuf = String::new();
-    let flush = |var: &mut String, out: &mut String| {
-        if !var.is_empty() {
-            // We have reached the end of a potential variable, so we expand it and push
-            // it onto the result
-            let res = expander.variable(&var, false);
-            match res {
-                Some(v) => out.push_str(&v),
-                None => out.push_str(&var),
-            }
-            var.clear();
-        }
-    };
-    for c in input.bytes() {
-        match c {
-            48...57 | 65...90 | 95 | 97...122 => {
-                varbuf.push(c as char);
-            }
-            _ => {
-                flush(&mut varbuf, &mut intermediate);
-                intermediate.push(c as char);
-            }
-        }
-    }
-    flush(&mut varbuf, &mut intermediate);
-    match calc::eval(&intermediate) {
-        Ok(s) => output.push_str(&(s.to_string())),
-        Err(e) => {
-            let err_string: String = e.into();
-            output.push_str(&err_string);
-        }
-    }
-}
-
 // TODO: Write Nested Brace Tests
 
 #[cfg(test)]
@@ -546,7 +546,7 @@ mod test {
     struct VariableExpander;
 
     impl Expander for VariableExpander {
-        fn variable(&self, variable: &str, _: bool) -> Option<Value> {
+        fn variable(&mut self, variable: &str, _: bool) -> Option<Value> {
             match variable {
                 "A" => Some("1".to_owned()),
                 "B" => Some("test".to_owned()),
@@ -563,7 +563,7 @@ mod test {
     fn expand_variable_normal_variable() {
         let input = "$FOO:NOT:$BAR";
         let expected = "FOO:NOT:BAR";
-        let expanded = expand_string(input, &VariableExpander, false);
+        let expanded = expand_string(input, &mut VariableExpander, false);
         assert_eq!(array![expected], expanded);
     }
 
@@ -572,19 +572,19 @@ mod test {
         let line = "pro{digal,grammer,cessed,totype,cedures,ficiently,ving,spective,jections}";
         let expected = "prodigal programmer processed prototype procedures proficiently proving \
                         prospective projections";
-        let expanded = expand_string(line, &VariableExpander, false);
+        let expanded = expand_string(line, &mut VariableExpander, false);
         assert_eq!(expected.split_whitespace().map(|x| x.to_owned()).collect::<Array>(), expanded);
     }
 
     #[test]
     fn expand_variables_with_colons() {
-        let expanded = expand_string("$FOO:$BAR", &VariableExpander, false);
+        let expanded = expand_string("$FOO:$BAR", &mut VariableExpander, false);
         assert_eq!(array!["FOO:BAR"], expanded);
     }
 
     #[test]
     fn expand_multiple_variables() {
-        let expanded = expand_string("${B}${C}...${D}", &VariableExpander, false);
+        let expanded = expand_string("${B}${C}...${D}", &mut VariableExpander, false);
         assert_eq!(array!["testing...1 2 3"], expanded);
     }
 
@@ -592,7 +592,7 @@ mod test {
     fn expand_variable_alongside_braces() {
         let line = "$A{1,2}";
         let expected = array!["11", "12"];
-        let expanded = expand_string(line, &VariableExpander, false);
+        let expanded = expand_string(line, &mut VariableExpander, false);
         assert_eq!(expected, expanded);
     }
 
@@ -600,33 +600,33 @@ mod test {
     fn expand_variable_within_braces() {
         let line = "1{$A,2}";
         let expected = array!["11", "12"];
-        let expanded = expand_string(line, &VariableExpander, false);
+        let expanded = expand_string(line, &mut VariableExpander, false);
         assert_eq!(&expected, &expanded);
     }
 
     #[test]
     fn array_indexing() {
         let base = |idx: &str| format!("[1 2 3][{}]", idx);
-        let expander = VariableExpander;
+        let mut expander = VariableExpander;
         {
             let expected = array!["1"];
             let idxs = vec!["-3", "0", "..-2"];
             for idx in idxs {
-                assert_eq!(expected, expand_string(&base(idx), &expander, false));
+                assert_eq!(expected, expand_string(&base(idx), &mut expander, false));
             }
         }
         {
             let expected = array!["2", "3"];
             let idxs = vec!["1...2", "1...-1"];
             for idx in idxs {
-                assert_eq!(expected, expand_string(&base(idx), &expander, false));
+                assert_eq!(expected, expand_string(&base(idx), &mut expander, false));
             }
         }
         {
             let expected = Array::new();
             let idxs = vec!["-17", "4..-4"];
             for idx in idxs {
-                assert_eq!(expected, expand_string(&base(idx), &expander, false));
+                assert_eq!(expected, expand_string(&base(idx), &mut expander, false));
             }
         }
     }
@@ -634,7 +634,7 @@ mod test {
     #[test]
     fn embedded_array_expansion() {
         let line = |idx: &str| format!("[[foo bar] [baz bat] [bing crosby]][{}]", idx);
-        let expander = VariableExpander;
+        let mut expander = VariableExpander;
         let cases = vec![
             (array!["foo"], "0"),
             (array!["baz"], "2"),
@@ -642,7 +642,7 @@ mod test {
             (array!["bar", "baz", "bat"], "1...3"),
         ];
         for (expected, idx) in cases {
-            assert_eq!(expected, expand_string(&line(idx), &expander, false));
+            assert_eq!(expected, expand_string(&line(idx), &mut expander, false));
         }
     }
 
@@ -650,10 +650,10 @@ mod test {
     fn arith_expression() {
         let line = "$((A * A - (A + A)))";
         let expected = array!["-1"];
-        assert_eq!(expected, expand_string(line, &VariableExpander, false));
+        assert_eq!(expected, expand_string(line, &mut VariableExpander, false));
         let line = "$((3 * 10 - 27))";
         let expected = array!["3"];
-        assert_eq!(expected, expand_string(line, &VariableExpander, false));
+        assert_eq!(expected, expand_string(line, &mut VariableExpander, false));
     }
 
     #[test]
@@ -663,7 +663,29 @@ mod test {
             (array!["FxOxO"], "$join(@chars(FOO), 'x')"),
         ];
         for (expected, input) in cases {
-            assert_eq!(expected, expand_string(input, &VariableExpander, false));
+            assert_eq!(expected, expand_string(input, &mut VariableExpander, false));
         }
     }
+
+    struct CommandExpander {
+        strip_newlines: bool,
+        split_words:    bool,
+    }
+
+    impl Expander for CommandExpander {
+        fn command(&mut self, _: &str) -> Option<Value> { Some("one two\nthree\n\n".to_owned()) }
+        fn strip_newlines(&self) -> bool { self.strip_newlines }
+        fn split_words(&self) -> bool { self.split_words }
+    }
+
+    #[test]
+    fn process_expansion_options() {
+        let mut expander = CommandExpander { strip_newlines: true, split_words: false };
+        assert_eq!(array!["one two\nthree"], expand_string("\"$(cmd)\"", &mut expander, false));
+        assert_eq!(array!["one two three"], expand_string("$(cmd)", &mut expander, false));
+
+        let mut expander = CommandExpander { strip_newlines: false, split_words: true };
+        assert_eq!(array!["one two\nthree\n\n"], expand_string("\"$(cmd)\"", &mut expander, false));
+        assert_eq!(array!["one", "two", "three"], expand_string("$(cmd)", &mut expander, false));
+    }
 }
@@ -223,7 +223,24 @@ pub(crate) fn parse_range(input: &str) -> Option<Vec<String>> {
     None
 }
 
+// An index range may be followed by a step, as in `[start..end..step]`, which selects every
+// `step`th element of the range.
 pub(crate) fn parse_index_range(input: &str) -> Option<Range> {
+    if let Some(id) = input.rfind("..") {
+        let (range, step) = (&input[..id], &input[id + 2..]);
+        if range.contains("..") && !range.ends_with('.') {
+            if let Some(range) = parse_unstepped_index_range(range) {
+                return match step.parse::<usize>() {
+                    Ok(step) if step > 0 => Some(range.with_step(step)),
+                    _ => None,
+                };
+            }
+        }
+    }
+    parse_unstepped_index_range(input)
+}
+
+fn parse_unstepped_index_range(input: &str) -> Option<Range> {
     let mut bytes_iterator = input.bytes().enumerate();
     while let Some((id, byte)) = bytes_iterator.next() {
         match byte {
@@ -294,13 +311,16 @@ fn index_ranges() {
         (Range::exclusive(Index::Backward(2), Index::Backward(0)), "-3..-1"),
         (Range::from(Index::Backward(2)), "-3.."),
         (Range::to(Index::Forward(5)), "..5"),
+        (Range::to(Index::Forward(10)).with_step(2), "..10..2"),
+        (Range::exclusive(Index::Forward(2), Index::Backward(1)).with_step(3), "2..-2..3"),
+        (Range::inclusive(Index::Forward(0), Index::Forward(4)).with_step(2), "0...4..2"),
     ];
 
     for (range, string) in valid_cases {
         assert_eq!(Some(range), parse_index_range(string));
     }
 
-    let invalid_cases = vec!["0..A", "3-3..42"];
+    let invalid_cases = vec!["0..A", "3-3..42", "..10..0", "..10..-1"];
 
     for range in invalid_cases {
         assert_eq!(None, parse_index_range(range))
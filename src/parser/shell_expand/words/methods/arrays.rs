@@ -1,9 +1,15 @@
-use super::Pattern;
+use super::{files, format, regex_captures, time, MethodArgs, Pattern, WithElement};
 use super::pattern::unescape;
 use super::super::{Index, Select, SelectWithSize};
 use super::super::super::{expand_string, is_expression, Expander};
+use fnv::{FnvHashMap, FnvHashSet};
+use glob::Pattern as GlobPattern;
+use regex::{self, Regex};
 use smallstring::SmallString;
 use std::char;
+use std::cmp::Ordering;
+use std::env;
+use std::fs;
 use std::io::{self, Write};
 use types::Array;
 use unicode_segmentation::UnicodeSegmentation;
@@ -17,7 +23,7 @@ pub(crate) struct ArrayMethod<'a> {
 }
 
 impl<'a> ArrayMethod<'a> {
-    pub(crate) fn handle<E: Expander>(&self, current: &mut String, expand_func: &E) {
+    pub(crate) fn handle<E: Expander>(&self, current: &mut String, expand_func: &mut E) {
         match self.method {
             "split" => {
                 let variable = if let Some(variable) = expand_func.variable(self.variable, false) {
@@ -78,28 +84,31 @@ impl<'a> ArrayMethod<'a> {
                             unescape(expand_string(pattern, expand_func, false).join(" ")),
                         );
                         let iter = variable.split(&expansion);
-                        if let Some((start, length)) = range.bounds(iter.clone().count()) {
-                            let range = iter.skip(start).take(length).collect::<Vec<_>>().join(" ");
-                            current.push_str(&range)
+                        let len = iter.clone().count();
+                        let selection = range.elements(iter, len);
+                        if let Some(elements) = selection {
+                            current.push_str(&elements.collect::<Vec<_>>().join(" "))
                         }
                     }
                     (&Pattern::Whitespace, Select::Range(range)) => {
                         let len =
                             variable.split(char::is_whitespace).filter(|x| !x.is_empty()).count();
-                        if let Some((start, length)) = range.bounds(len) {
-                            let range = variable
-                                .split(char::is_whitespace)
-                                .filter(|x| !x.is_empty())
-                                .skip(start)
-                                .take(length)
-                                .collect::<Vec<&str>>()
-                                .join(" ");
-                            current.push_str(&range);
+                        let words = variable.split(char::is_whitespace).filter(|x| !x.is_empty());
+                        if let Some(words) = range.elements(words, len) {
+                            current.push_str(&words.collect::<Vec<&str>>().join(" "));
                         }
                     }
                     (_, Select::Key(_)) => (),
                 }
             }
+            "chunks" | "difference" | "enumerate" | "env" | "filter" | "find" | "glob"
+            | "intersect" | "map" | "max" | "mean" | "min" | "regex_capture" | "reverse" | "sort"
+            | "sort_by" | "split_multi" | "sum" | "union" | "unique" | "zip" => {
+                current.push_str(&self.handle_as_array(expand_func).join(" "))
+            }
+            method if expand_func.has_function(method) => {
+                current.push_str(&self.handle_as_array(expand_func).join(" "))
+            }
             _ => {
                 let stderr = io::stderr();
                 let mut stderr = stderr.lock();
@@ -108,7 +117,7 @@ impl<'a> ArrayMethod<'a> {
         }
     }
 
-    pub(crate) fn handle_as_array<E: Expander>(&self, expand_func: &E) -> Array {
+    pub(crate) fn handle_as_array<E: Expander>(&self, expand_func: &mut E) -> Array {
         macro_rules! resolve_var {
             () => {
                 if let Some(variable) = expand_func.variable(self.variable, false) {
@@ -121,6 +130,18 @@ impl<'a> ArrayMethod<'a> {
             }
         }
 
+        macro_rules! resolve_array {
+            () => {
+                if let Some(array) = expand_func.array(self.variable, Select::All) {
+                    array
+                } else if is_expression(self.variable) {
+                    expand_string(self.variable, expand_func, false)
+                } else {
+                    Array::new()
+                }
+            }
+        }
+
         match self.method {
             "split" => {
                 let variable = resolve_var!();
@@ -169,8 +190,10 @@ impl<'a> ArrayMethod<'a> {
                         let expansion =
                             unescape(expand_string(pattern, expand_func, false).join(" "));
                         let iter = variable.split(&expansion);
-                        if let Some((start, length)) = range.bounds(iter.clone().count()) {
-                            iter.skip(start).take(length).map(From::from).collect()
+                        let len = iter.clone().count();
+                        let selection = range.elements(iter, len);
+                        if let Some(elements) = selection {
+                            elements.map(From::from).collect()
                         } else {
                             Array::new()
                         }
@@ -178,14 +201,9 @@ impl<'a> ArrayMethod<'a> {
                     (&Pattern::Whitespace, Select::Range(range)) => {
                         let len =
                             variable.split(char::is_whitespace).filter(|x| !x.is_empty()).count();
-                        if let Some((start, length)) = range.bounds(len) {
-                            variable
-                                .split(char::is_whitespace)
-                                .filter(|x| !x.is_empty())
-                                .skip(start)
-                                .take(length)
-                                .map(From::from)
-                                .collect()
+                        let words = variable.split(char::is_whitespace).filter(|x| !x.is_empty());
+                        if let Some(words) = range.elements(words, len) {
+                            words.map(From::from).collect()
                         } else {
                             Array::new()
                         }
@@ -212,6 +230,31 @@ impl<'a> ArrayMethod<'a> {
                     }
                 }
             }
+            "split_multi" => {
+                let variable = resolve_var!();
+                let delimiters = match self.pattern {
+                    Pattern::StringPattern(pattern) => {
+                        MethodArgs::new(pattern, expand_func).raw_array().collect::<Vec<_>>()
+                    }
+                    Pattern::Whitespace => Vec::new(),
+                };
+                if delimiters.is_empty() {
+                    let fields = variable.split_whitespace().map(String::from).collect::<Vec<_>>();
+                    let len = fields.len();
+                    return fields.into_iter().select(self.selection.clone(), len);
+                }
+                return match delimiter_regex(delimiters) {
+                    Ok(re) => {
+                        let fields = re.split(&variable).map(String::from).collect::<Vec<_>>();
+                        let len = fields.len();
+                        fields.into_iter().select(self.selection.clone(), len)
+                    }
+                    Err(why) => {
+                        eprintln!("ion: split_multi: {}", why);
+                        array![]
+                    }
+                };
+            }
             "graphemes" => {
                 let variable = resolve_var!();
                 let graphemes = UnicodeSegmentation::graphemes(variable.as_str(), true);
@@ -228,6 +271,263 @@ impl<'a> ArrayMethod<'a> {
                 let len = variable.chars().count();
                 return variable.chars().map(|c| c.to_string()).select(self.selection.clone(), len);
             }
+            "regex_capture" => {
+                let variable = resolve_var!();
+                let expression = match self.pattern {
+                    Pattern::StringPattern(pattern) => {
+                        expand_string(pattern, expand_func, false).join(" ")
+                    }
+                    Pattern::Whitespace => {
+                        eprintln!("ion: regex_capture: a regular expression is required");
+                        return array![];
+                    }
+                };
+                return match Regex::new(&expression) {
+                    Ok(re) => {
+                        let groups = regex_captures(&re, &variable);
+                        let len = groups.len();
+                        groups.into_iter().select(self.selection.clone(), len)
+                    }
+                    Err(_) => {
+                        eprintln!(
+                            "ion: regex_capture: error in regular expression {}",
+                            &expression
+                        );
+                        array![]
+                    }
+                };
+            }
+            "reverse" => {
+                let mut array = resolve_array!();
+                array.reverse();
+                let len = array.len();
+                return array.into_iter().select(self.selection.clone(), len);
+            }
+            "sort" => {
+                let mut array = resolve_array!();
+                match self.pattern {
+                    Pattern::Whitespace => array.sort(),
+                    Pattern::StringPattern(pattern) => {
+                        match expand_string(pattern, expand_func, false).join(" ").as_str() {
+                            "numeric" => array.sort_by(|a, b| compare_numeric(a, b)),
+                            "lexical" => array.sort(),
+                            mode => {
+                                eprintln!("ion: sort: unknown sorting mode: {}", mode);
+                                return array![];
+                            }
+                        }
+                    }
+                }
+                let len = array.len();
+                return array.into_iter().select(self.selection.clone(), len);
+            }
+            "sort_by" => {
+                let expression = match self.pattern {
+                    Pattern::StringPattern(pattern) => pattern,
+                    Pattern::Whitespace => {
+                        eprintln!("ion: sort_by: an expression is required");
+                        return array![];
+                    }
+                };
+                let expression = unquote(expression);
+                let mut keyed = resolve_array!()
+                    .into_iter()
+                    .map(|element| {
+                        let key = {
+                            let mut expand = WithElement::new(expand_func, &element);
+                            expand_string(expression, &mut expand, false).join(" ")
+                        };
+                        (key, element)
+                    })
+                    .collect::<Vec<(String, String)>>();
+                // Keys are compared numerically only if every key is a number.
+                if keyed.iter().all(|&(ref key, _)| key.parse::<f64>().is_ok()) {
+                    keyed.sort_by(|a, b| compare_numeric(&a.0, &b.0));
+                } else {
+                    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+                }
+                let len = keyed.len();
+                return keyed
+                    .into_iter()
+                    .map(|(_, element)| element)
+                    .select(self.selection.clone(), len);
+            }
+            "map" | "filter" => {
+                let expression = match self.pattern {
+                    Pattern::StringPattern(pattern) => unquote(pattern),
+                    Pattern::Whitespace => {
+                        eprintln!("ion: {}: a function or an expression is required", self.method);
+                        return array![];
+                    }
+                };
+                let mut output = Vec::new();
+                for element in resolve_array!() {
+                    let (value, is_true) = match evaluate(expression, &element, expand_func) {
+                        Some(result) => result,
+                        None => {
+                            eprintln!("ion: {}: {}: no such function", self.method, expression);
+                            return array![];
+                        }
+                    };
+                    if self.method == "map" {
+                        output.push(value);
+                    } else if is_true {
+                        output.push(element);
+                    }
+                }
+                let len = output.len();
+                return output.into_iter().select(self.selection.clone(), len);
+            }
+            "zip" => {
+                let other = match self.second_array(expand_func) {
+                    Some(other) => other,
+                    None => return array![],
+                };
+                let zipped = resolve_array!()
+                    .into_iter()
+                    .zip(other.into_iter())
+                    .flat_map(|(first, second)| vec![first, second])
+                    .collect::<Vec<String>>();
+                let len = zipped.len();
+                return zipped.into_iter().select(self.selection.clone(), len);
+            }
+            "enumerate" => {
+                let enumerated = resolve_array!()
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(id, element)| vec![id.to_string(), element])
+                    .collect::<Vec<String>>();
+                let len = enumerated.len();
+                return enumerated.into_iter().select(self.selection.clone(), len);
+            }
+            "chunks" => {
+                let size = match self.pattern {
+                    Pattern::StringPattern(pattern) => {
+                        expand_string(pattern, expand_func, false).join(" ").parse::<usize>()
+                    }
+                    Pattern::Whitespace => {
+                        eprintln!("ion: chunks: a chunk size is required");
+                        return array![];
+                    }
+                };
+                let size = match size {
+                    Ok(size) if size > 0 => size,
+                    _ => {
+                        eprintln!("ion: chunks: the chunk size must be a positive integer");
+                        return array![];
+                    }
+                };
+                let array = resolve_array!();
+                let chunks = array.chunks(size).map(|chunk| chunk.join(" ")).collect::<Vec<_>>();
+                let len = chunks.len();
+                return chunks.into_iter().select(self.selection.clone(), len);
+            }
+            "union" | "intersect" | "difference" => {
+                let other = match self.second_array(expand_func) {
+                    Some(other) => other,
+                    None => return array![],
+                };
+                let result = set_operation(self.method, resolve_array!(), other);
+                let len = result.len();
+                return result.into_iter().select(self.selection.clone(), len);
+            }
+            "sum" | "min" | "max" | "mean" => {
+                return match aggregate(self.method, &resolve_array!()) {
+                    Ok(Some(value)) => array![value],
+                    Ok(None) => array![],
+                    Err(why) => {
+                        eprintln!("ion: {}: {}", self.method, why);
+                        array![]
+                    }
+                };
+            }
+            "unique" => {
+                let array = resolve_array!();
+                let mut seen = FnvHashSet::default();
+                let unique = array
+                    .into_iter()
+                    .filter(|element| seen.insert(element.clone()))
+                    .collect::<Vec<String>>();
+                let len = unique.len();
+                return unique.into_iter().select(self.selection.clone(), len);
+            }
+            "glob" => {
+                // The argument is a glob pattern rather than the name of a variable.
+                let pattern = self.literal_argument(expand_func);
+                return match files::glob_paths(&pattern) {
+                    Ok(paths) => {
+                        let len = paths.len();
+                        paths.into_iter().select(self.selection.clone(), len)
+                    }
+                    Err(why) => {
+                        eprintln!("ion: glob: invalid pattern: {}", why);
+                        array![]
+                    }
+                };
+            }
+            "find" => {
+                // The argument is a directory rather than the name of a variable.
+                let mut directory = self.literal_argument(expand_func);
+                if directory.is_empty() {
+                    directory.push('.');
+                }
+                let filter = match self.pattern {
+                    Pattern::StringPattern(pattern) => match find_filter(pattern, expand_func) {
+                        Ok(filter) => filter,
+                        Err(why) => {
+                            eprintln!("ion: find: {}", why);
+                            return array![];
+                        }
+                    },
+                    Pattern::Whitespace => files::Filter::default(),
+                };
+                let found = files::find(&directory, &filter);
+                let len = found.len();
+                return found.into_iter().select(self.selection.clone(), len);
+            }
+            "env" => {
+                // The argument is a glob pattern rather than the name of a variable.
+                let pattern = if self.variable.is_empty() {
+                    "*".to_owned()
+                } else if is_expression(self.variable) {
+                    expand_string(self.variable, expand_func, false).join(" ")
+                } else {
+                    self.variable.to_owned()
+                };
+                let pattern = match GlobPattern::new(&pattern) {
+                    Ok(pattern) => pattern,
+                    Err(why) => {
+                        eprintln!("ion: env: invalid pattern: {}", why);
+                        return array![];
+                    }
+                };
+                let mut keys = env::vars()
+                    .map(|(key, _)| key)
+                    .filter(|key| pattern.matches(key))
+                    .collect::<Vec<String>>();
+                keys.sort();
+                let len = keys.len();
+                return keys.into_iter().select(self.selection.clone(), len);
+            }
+            method if expand_func.has_function(method) => {
+                let input = match expand_func.variable(self.variable, false) {
+                    Some(value) => value,
+                    None => resolve_array!().join(" "),
+                };
+                let arguments = match self.pattern {
+                    Pattern::StringPattern(pattern) => {
+                        MethodArgs::new(pattern, expand_func).array().collect::<Vec<_>>()
+                    }
+                    Pattern::Whitespace => Vec::new(),
+                };
+                let mut args = vec![input.as_str()];
+                args.extend(arguments.iter().map(String::as_str));
+                if let Some(output) = expand_func.method(method, &args) {
+                    let elements = output.split_whitespace().map(String::from).collect::<Vec<_>>();
+                    let len = elements.len();
+                    return elements.into_iter().select(self.selection.clone(), len);
+                }
+            }
             _ => {
                 let stderr = io::stderr();
                 let mut stderr = stderr.lock();
@@ -237,4 +537,234 @@ impl<'a> ArrayMethod<'a> {
 
         array![]
     }
+
+    /// Obtains an argument which is taken literally unless it is an expression, in which case it
+    /// is expanded. A leading tilde is expanded as well.
+    fn literal_argument<E: Expander>(&self, expand_func: &mut E) -> String {
+        if is_expression(self.variable) {
+            expand_string(self.variable, expand_func, false).join(" ")
+        } else {
+            expand_func.tilde(self.variable).unwrap_or_else(|| self.variable.to_owned())
+        }
+    }
+
+    /// Obtains the array given as the second argument, which may be the name of an array
+    /// variable or an expression.
+    fn second_array<E: Expander>(&self, expand_func: &mut E) -> Option<Array> {
+        match self.pattern {
+            Pattern::StringPattern(pattern) if format::is_name(pattern) => {
+                Some(expand_func.array(pattern, Select::All).unwrap_or_else(Array::new))
+            }
+            Pattern::StringPattern(pattern) => Some(expand_string(pattern, expand_func, false)),
+            Pattern::Whitespace => {
+                eprintln!("ion: {}: a second array is required", self.method);
+                None
+            }
+        }
+    }
+}
+
+/// Parses the `type=`, `name=`, and `newer_than=` arguments which filter the paths that `@find`
+/// yields. The time given to `newer_than` may be either a path, whose modification time is used,
+/// or any time which `$parse_time()` accepts.
+fn find_filter<E: Expander>(arguments: &str, expand_func: &mut E) -> Result<files::Filter, String> {
+    let mut filter = files::Filter::default();
+    for argument in format::split_arguments(arguments) {
+        let (key, value) = match format::named_argument(argument) {
+            (Some(key), value) => (key, expand_string(value, expand_func, false).join(" ")),
+            (None, _) => return Err(format!("expected a filter such as `type=f`: {}", argument)),
+        };
+        match key {
+            "type" => {
+                let file_type = files::FileType::parse(&value);
+                filter.file_type =
+                    Some(file_type.ok_or_else(|| format!("unknown file type: {}", value))?);
+            }
+            "name" => {
+                let pattern = GlobPattern::new(&value).map_err(|why| why.to_string())?;
+                filter.name = Some(pattern);
+            }
+            "newer_than" => {
+                let reference = match fs::metadata(&value) {
+                    Ok(metadata) => files::modified(&metadata),
+                    Err(_) => {
+                        let clock = time::Clock::new(expand_func);
+                        time::parse_any(&value, |time| clock.offset(time))
+                    }
+                };
+                let reference = reference.ok_or_else(|| format!("invalid time: {}", value))?;
+                filter.newer_than = Some(reference);
+            }
+            _ => return Err(format!("unknown filter: {}", key)),
+        }
+    }
+    Ok(filter)
+}
+
+/// Treats both arrays as multisets, in which each element may occur more than once. The union
+/// holds each element as many times as the array with the most occurrences of it, the
+/// intersection as many times as the array with the fewest, and the difference as many times as
+/// it occurs in the first array more than in the second. Elements keep the order of the first
+/// array, followed by the elements which are only within the second.
+fn set_operation(method: &str, first: Array, second: Array) -> Vec<String> {
+    let mut counts: FnvHashMap<&str, usize> = FnvHashMap::default();
+    for element in second.iter() {
+        *counts.entry(element.as_str()).or_insert(0) += 1;
+    }
+
+    let mut output = Vec::new();
+    for element in first.iter() {
+        let is_shared = match counts.get_mut(element.as_str()) {
+            Some(ref mut count) if **count > 0 => {
+                **count -= 1;
+                true
+            }
+            _ => false,
+        };
+        match method {
+            "intersect" if is_shared => output.push(element.clone()),
+            "difference" if !is_shared => output.push(element.clone()),
+            "union" => output.push(element.clone()),
+            _ => (),
+        }
+    }
+
+    if method == "union" {
+        for element in second.iter() {
+            if let Some(count) = counts.get_mut(element.as_str()) {
+                if *count > 0 {
+                    *count -= 1;
+                    output.push(element.clone());
+                }
+            }
+        }
+    }
+    output
+}
+
+/// Orders numbers by their values, ahead of any strings which are not numbers, which are
+/// ordered lexicographically.
+fn compare_numeric(first: &str, second: &str) -> Ordering {
+    match (first.parse::<f64>(), second.parse::<f64>()) {
+        (Ok(first), Ok(second)) => first.partial_cmp(&second).unwrap_or(Ordering::Equal),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => first.cmp(second),
+    }
+}
+
+/// Combines the delimiters into a single regular expression which matches any of them. Each
+/// delimiter written between slashes, as in `/\s*,\s*/`, is a regular expression, and any other
+/// is matched literally. Longer literals are preferred over the shorter ones which they begin
+/// with, so that `", "` is not split upon as `","` when both are given.
+fn delimiter_regex(delimiters: Vec<String>) -> Result<Regex, String> {
+    let mut literals = Vec::new();
+    let mut expressions = Vec::new();
+    for delimiter in delimiters {
+        if delimiter.len() > 2 && delimiter.starts_with('/') && delimiter.ends_with('/') {
+            let expression = &delimiter[1..delimiter.len() - 1];
+            Regex::new(expression).map_err(|why| why.to_string())?;
+            expressions.push(format!("(?:{})", expression));
+        } else {
+            literals.push(unescape(delimiter));
+        }
+    }
+    literals.retain(|literal| !literal.is_empty());
+    literals.sort_by(|a, b| b.len().cmp(&a.len()));
+    expressions.extend(literals.iter().map(|literal| regex::escape(literal)));
+    if expressions.is_empty() {
+        return Err("no delimiters were given".into());
+    }
+    Regex::new(&expressions.join("|")).map_err(|why| why.to_string())
+}
+
+/// Computes the sum, minimum, maximum, or mean of the elements, each of which must be a number.
+/// Integers are summed and compared exactly, unless one of the elements is a float, and a sum of
+/// integers which would overflow is an error. The minimum and maximum are given as they were
+/// written, and an empty array has neither these nor a mean.
+fn aggregate(method: &str, elements: &[String]) -> Result<Option<String>, String> {
+    let mut numbers = Vec::with_capacity(elements.len());
+    for element in elements {
+        match element.parse::<f64>() {
+            Ok(number) if number.is_finite() => numbers.push(number),
+            _ => return Err(format!("'{}' is not a number", element)),
+        }
+    }
+    let integers = elements
+        .iter()
+        .map(|element| element.parse::<i64>().ok())
+        .collect::<Option<Vec<i64>>>();
+
+    let extreme = |ordering: Ordering| {
+        let extreme = match integers {
+            Some(ref integers) => position(integers, ordering),
+            None => position(&numbers, ordering),
+        };
+        extreme.map(|id| elements[id].clone())
+    };
+    let sum = || -> Result<String, String> {
+        match integers {
+            Some(ref integers) => {
+                let mut sum = 0i64;
+                for &integer in integers {
+                    sum = sum.checked_add(integer).ok_or("the sum is too large for an integer")?;
+                }
+                Ok(sum.to_string())
+            }
+            None => Ok(numbers.iter().sum::<f64>().to_string()),
+        }
+    };
+
+    Ok(match method {
+        "sum" => Some(sum()?),
+        "min" => extreme(Ordering::Less),
+        "max" => extreme(Ordering::Greater),
+        _ if numbers.is_empty() => None,
+        _ => Some((numbers.iter().sum::<f64>() / numbers.len() as f64).to_string()),
+    })
+}
+
+/// Returns the position of the first number which is ordered before or after all others, as
+/// given by the ordering.
+fn position<T: PartialOrd>(numbers: &[T], ordering: Ordering) -> Option<usize> {
+    let mut extreme: Option<usize> = None;
+    for (id, number) in numbers.iter().enumerate() {
+        match extreme {
+            Some(index) if number.partial_cmp(&numbers[index]) != Some(ordering) => (),
+            _ => extreme = Some(id),
+        }
+    }
+    extreme
+}
+
+/// Strips the single quotes which may surround an expression that is to be evaluated once for
+/// each element, as the expression itself is not expanded beforehand.
+fn unquote(expression: &str) -> &str {
+    if expression.len() > 1 && expression.starts_with('\'') && expression.ends_with('\'') {
+        &expression[1..expression.len() - 1]
+    } else {
+        expression
+    }
+}
+
+/// Evaluates the expression for an element, returning its value and whether it is true. A bare
+/// word is the name of a function to call with the element, which is true if it succeeds, and
+/// otherwise, the expression is expanded with `$it` referring to the element, which is true if
+/// it expands to `1` or `true`. Returns `None` if the function does not exist.
+fn evaluate<E: Expander>(
+    expression: &str,
+    element: &str,
+    expand: &mut E,
+) -> Option<(String, bool)> {
+    let is_function = expression.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    if is_function {
+        expand
+            .function(expression, element)
+            .map(|(output, status)| (output.trim_right_matches('\n').to_owned(), status))
+    } else {
+        let mut expand = WithElement::new(expand, element);
+        let value = expand_string(expression, &mut expand, false).join(" ");
+        let is_true = value == "1" || value == "true";
+        Some((value, is_true))
+    }
 }
@@ -0,0 +1,124 @@
+//! Unicode-aware case conversions, which are used by the `$to_title`, `$snake_case`, and
+//! `$camel_case` methods, along with their `$is_*` tests.
+
+use std::mem;
+
+/// Splits the input into words at each non-alphanumeric character, and at each change from a
+/// lowercase character or digit to an uppercase character. A run of uppercase characters is
+/// kept together as an acronym, so `HTTPServer` becomes `HTTP` and `Server`.
+fn words(input: &str) -> Vec<String> {
+    let chars = input.chars().collect::<Vec<char>>();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (id, &character) in chars.iter().enumerate() {
+        if !character.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(mem::replace(&mut word, String::new()));
+            }
+            continue;
+        }
+        if character.is_uppercase() && !word.is_empty() {
+            let previous = chars[id - 1];
+            let next_is_lower = chars.get(id + 1).map_or(false, |next| next.is_lowercase());
+            if !previous.is_uppercase() || next_is_lower {
+                words.push(mem::replace(&mut word, String::new()));
+            }
+        }
+        word.push(character);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Uppercases the first character of the word, and lowercases the remainder.
+fn capitalize(word: &str) -> String {
+    let mut characters = word.chars();
+    match characters.next() {
+        Some(first) => {
+            first.to_uppercase().chain(characters.flat_map(char::to_lowercase)).collect()
+        }
+        None => String::new(),
+    }
+}
+
+/// Capitalizes the first letter of each word, and lowercases every other letter. Characters
+/// which separate words are left as they are.
+pub(super) fn to_title(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut start_of_word = true;
+    for character in input.chars() {
+        if start_of_word {
+            output.extend(character.to_uppercase());
+        } else {
+            output.extend(character.to_lowercase());
+        }
+        start_of_word = !character.is_alphanumeric();
+    }
+    output
+}
+
+/// Converts the input into lowercase words joined by underscores, such as `snake_case`.
+pub(super) fn snake_case(input: &str) -> String {
+    words(input).iter().map(|word| word.to_lowercase()).collect::<Vec<String>>().join("_")
+}
+
+/// Converts the input into capitalized words joined together, with the first word in
+/// lowercase, such as `camelCase`.
+pub(super) fn camel_case(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for (id, word) in words(input).iter().enumerate() {
+        if id == 0 {
+            output.push_str(&word.to_lowercase());
+        } else {
+            output.push_str(&capitalize(word));
+        }
+    }
+    output
+}
+
+/// Whether the input contains cased characters, and all of them are uppercase.
+pub(super) fn is_upper(input: &str) -> bool {
+    input.chars().any(char::is_uppercase) && !input.chars().any(char::is_lowercase)
+}
+
+/// Whether the input contains cased characters, and all of them are lowercase.
+pub(super) fn is_lower(input: &str) -> bool {
+    input.chars().any(char::is_lowercase) && !input.chars().any(char::is_uppercase)
+}
+
+/// Whether the input is unchanged by the given conversion, and contains letters.
+pub(super) fn is_case(input: &str, convert: fn(&str) -> String) -> bool {
+    input.chars().any(char::is_alphabetic) && convert(input) == input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        assert_eq!(to_title("hello wORLD, o'neil"), "Hello World, O'Neil");
+        assert_eq!(to_title("élan ßtraße"), "Élan SStraße");
+        assert_eq!(snake_case("HTTPServer error-code v2Beta"), "http_server_error_code_v2_beta");
+        assert_eq!(snake_case("ÉtéChaud"), "été_chaud");
+        assert_eq!(camel_case("http_server error"), "httpServerError");
+        assert_eq!(camel_case("Some Title Case"), "someTitleCase");
+    }
+
+    #[test]
+    fn tests() {
+        assert!(is_upper("ÉTÉ 2"));
+        assert!(!is_upper("Été"));
+        assert!(!is_upper("123"));
+        assert!(is_lower("été 2"));
+        assert!(is_case("Hello World", to_title));
+        assert!(!is_case("Hello world", to_title));
+        assert!(is_case("snake_case_2", snake_case));
+        assert!(!is_case("camelCase", snake_case));
+        assert!(is_case("camelCase", camel_case));
+        assert!(!is_case("snake_case", camel_case));
+        assert!(!is_case("", camel_case));
+    }
+}
@@ -0,0 +1,73 @@
+//! Encodings which are used by the `$url_encode`, `$url_decode`, and `$quote` methods.
+
+/// Percent-encodes every byte of the input except for the unreserved characters of RFC 3986.
+pub(super) fn url_encode(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for &byte in input.as_bytes() {
+        match byte {
+            b'A'...b'Z' | b'a'...b'z' | b'0'...b'9' | b'-' | b'_' | b'.' | b'~' => {
+                output.push(byte as char)
+            }
+            _ => output.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    output
+}
+
+/// Decodes each percent-encoded byte within the input. Sequences which are not valid are kept
+/// as they are, and bytes which do not form valid UTF-8 are replaced.
+pub(super) fn url_decode(input: &str) -> String {
+    let hex = |byte: u8| (byte as char).to_digit(16).map(|digit| digit as u8);
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut id = 0;
+    while id < bytes.len() {
+        if bytes[id] == b'%' && id + 2 < bytes.len() {
+            if let (Some(high), Some(low)) = (hex(bytes[id + 1]), hex(bytes[id + 2])) {
+                output.push(high << 4 | low);
+                id += 3;
+                continue;
+            }
+        }
+        output.push(bytes[id]);
+        id += 1;
+    }
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+/// Quotes the input so that it will be read back as a single, unexpanded word, by Ion as well
+/// as by POSIX shells. Inputs which need no quoting are left as they are.
+pub(crate) fn quote(input: &str) -> String {
+    let is_safe = |byte: u8| match byte {
+        b'A'...b'Z' | b'a'...b'z' | b'0'...b'9' => true,
+        b'-' | b'_' | b'.' | b'/' | b'=' | b':' | b',' | b'+' | b'%' => true,
+        _ => false,
+    };
+    if !input.is_empty() && input.bytes().all(is_safe) {
+        return input.to_owned();
+    }
+    format!("'{}'", input.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls() {
+        let decoded = "a b/c?d=é&e~f";
+        let encoded = "a%20b%2Fc%3Fd%3D%C3%A9%26e~f";
+        assert_eq!(url_encode(decoded), encoded);
+        assert_eq!(url_decode(encoded), decoded);
+        assert_eq!(url_decode("100%+%2"), "100%+%2");
+        assert_eq!(url_decode("%ff"), "\u{FFFD}");
+    }
+
+    #[test]
+    fn quotes() {
+        assert_eq!(quote("plain/path-1.0"), "plain/path-1.0");
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("$HOME @args"), "'$HOME @args'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+    }
+}
@@ -0,0 +1,154 @@
+//! File system searches, which are used by the `@glob` and `@find` methods.
+
+use glob::{glob, Pattern};
+use std::fs::{self, Metadata};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Lists the paths which match the glob pattern, in sorted order. Paths which cannot be read are
+/// skipped, and a pattern which matches nothing yields nothing, rather than itself.
+pub(super) fn glob_paths(pattern: &str) -> Result<Vec<String>, String> {
+    let paths = glob(pattern).map_err(|why| why.to_string())?;
+    Ok(paths.filter_map(Result::ok).map(|path| path.to_string_lossy().into_owned()).collect())
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) enum FileType {
+    File,
+    Directory,
+    Symlink,
+}
+
+impl FileType {
+    pub(super) fn parse(kind: &str) -> Option<FileType> {
+        match kind {
+            "f" | "file" => Some(FileType::File),
+            "d" | "dir" | "directory" => Some(FileType::Directory),
+            "l" | "link" | "symlink" => Some(FileType::Symlink),
+            _ => None,
+        }
+    }
+
+    fn matches(self, metadata: &Metadata) -> bool {
+        let file_type = metadata.file_type();
+        match self {
+            FileType::File => file_type.is_file(),
+            FileType::Directory => file_type.is_dir(),
+            FileType::Symlink => file_type.is_symlink(),
+        }
+    }
+}
+
+/// The conditions which each path that is found must satisfy.
+#[derive(Debug, Default)]
+pub(super) struct Filter {
+    pub(super) file_type:  Option<FileType>,
+    /// A glob pattern which is matched against the file name alone.
+    pub(super) name:       Option<Pattern>,
+    /// Seconds since the epoch, after which the path must have been modified.
+    pub(super) newer_than: Option<i64>,
+}
+
+impl Filter {
+    fn matches(&self, path: &Path, metadata: &Metadata) -> bool {
+        if self.file_type.map_or(false, |file_type| !file_type.matches(metadata)) {
+            return false;
+        }
+        if let Some(ref name) = self.name {
+            let file_name = path.file_name().map(|name| name.to_string_lossy());
+            if !file_name.map_or(false, |file_name| name.matches(&file_name)) {
+                return false;
+            }
+        }
+        match self.newer_than {
+            Some(time) => modified(metadata).map_or(false, |modified| modified > time),
+            None => true,
+        }
+    }
+}
+
+/// Obtains the time at which a file was last modified, in seconds since the epoch.
+pub(super) fn modified(metadata: &Metadata) -> Option<i64> {
+    let modified = metadata.modified().ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|duration| duration.as_secs() as i64)
+}
+
+/// Recursively lists each path beneath the directory which satisfies the filter, with the
+/// entries of each directory in sorted order. Symbolic links are not followed, and entries which
+/// cannot be read are skipped.
+pub(super) fn find(directory: &str, filter: &Filter) -> Vec<String> {
+    let mut found = Vec::new();
+    walk(Path::new(directory), filter, &mut found);
+    found
+}
+
+fn walk(directory: &Path, filter: &Filter, found: &mut Vec<String>) {
+    let mut entries = match fs::read_dir(directory) {
+        Ok(entries) => entries.filter_map(Result::ok).map(|entry| entry.path()).collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+    entries.sort();
+    for path in entries {
+        let metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if filter.matches(&path, &metadata) {
+            found.push(path.to_string_lossy().into_owned());
+        }
+        if metadata.is_dir() {
+            walk(&path, filter, found);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs::File;
+    use std::process;
+
+    #[test]
+    fn finding() {
+        let root = env::temp_dir().join(format!("ion-find-{}", process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src/methods")).unwrap();
+        for file in &["README.md", "src/main.rs", "src/methods/files.rs"] {
+            File::create(root.join(file)).unwrap();
+        }
+        let root_str = root.to_str().unwrap().to_owned();
+        let relative = |paths: Vec<String>| {
+            paths.into_iter().map(|path| path[root_str.len() + 1..].to_owned()).collect::<Vec<_>>()
+        };
+
+        let filter = Filter::default();
+        let everything = ["README.md", "src", "src/main.rs", "src/methods", "src/methods/files.rs"];
+        assert_eq!(relative(find(&root_str, &filter)), everything);
+
+        let filter = Filter {
+            file_type: Some(FileType::Directory),
+            ..Filter::default()
+        };
+        assert_eq!(relative(find(&root_str, &filter)), ["src", "src/methods"]);
+
+        let filter = Filter {
+            name: Some(Pattern::new("*.rs").unwrap()),
+            ..Filter::default()
+        };
+        assert_eq!(relative(find(&root_str, &filter)), ["src/main.rs", "src/methods/files.rs"]);
+
+        let filter = Filter {
+            newer_than: Some(i64::max_value()),
+            ..Filter::default()
+        };
+        assert!(find(&root_str, &filter).is_empty());
+
+        let pattern = format!("{}/src/*.rs", root_str);
+        assert_eq!(relative(glob_paths(&pattern).unwrap()), ["src/main.rs"]);
+        assert!(glob_paths(&format!("{}/*.txt", root_str)).unwrap().is_empty());
+        assert!(glob_paths("[").is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
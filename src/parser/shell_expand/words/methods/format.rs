@@ -0,0 +1,182 @@
+//! Placeholder substitution for the `$format` method, which accepts `{}`, `{0}`, and `{name}`
+//! placeholders, each of which may be followed by a `:[[fill]align][width][.precision]` spec.
+
+use parser::quoting::{Class, Quotes};
+use std::iter;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Splits the arguments given to the method at each comma which is neither quoted nor nested
+/// within parentheses or brackets.
+pub(super) fn split_arguments(args: &str) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let (mut start, mut level, mut quotes) = (0, 0, Quotes::new());
+    for (id, byte) in args.bytes().enumerate() {
+        match byte {
+            _ if quotes.scan(byte) != Class::Unquoted => (),
+            b'(' | b'[' => level += 1,
+            b')' | b']' => level -= 1,
+            b',' if level == 0 => {
+                arguments.push(args[start..id].trim());
+                start = id + 1;
+            }
+            _ => (),
+        }
+    }
+    arguments.push(args[start..].trim());
+    arguments.retain(|argument| !argument.is_empty());
+    arguments
+}
+
+/// Whether the argument is a plain variable name, rather than an expression or a literal.
+pub(super) fn is_name(argument: &str) -> bool {
+    argument.chars().next().map_or(false, |c| c.is_alphabetic() || c == '_')
+        && argument.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Separates the name from the value of an argument given as `name=value`.
+pub(super) fn named_argument(argument: &str) -> (Option<&str>, &str) {
+    match argument.find('=') {
+        Some(id) if is_name(&argument[..id]) => (Some(&argument[..id]), &argument[id + 1..]),
+        _ => (None, argument),
+    }
+}
+
+struct Spec {
+    fill:      char,
+    align:     Option<char>,
+    width:     usize,
+    precision: Option<usize>,
+}
+
+fn parse_spec(spec: &str) -> Option<Spec> {
+    let is_align = |c: Option<char>| c == Some('<') || c == Some('>') || c == Some('^');
+    let mut chars = spec.chars();
+    let (first, second) = (chars.next(), chars.next());
+    let (fill, align, rest) = if is_align(second) {
+        (first.unwrap(), second, chars.as_str())
+    } else if is_align(first) {
+        (' ', first, &spec[1..])
+    } else {
+        (' ', None, spec)
+    };
+
+    let (width, precision) = match rest.find('.') {
+        Some(id) => (&rest[..id], Some(&rest[id + 1..])),
+        None => (rest, None),
+    };
+    let width = if width.is_empty() { 0 } else { width.parse::<usize>().ok()? };
+    let precision = match precision {
+        Some(precision) => Some(precision.parse::<usize>().ok()?),
+        None => None,
+    };
+    Some(Spec { fill, align, width, precision })
+}
+
+/// Applies the precision, width, and alignment of the spec to the value. Numbers are rounded to
+/// the precision and aligned to the right by default, whereas other values are truncated to the
+/// precision and aligned to the left.
+fn apply(value: &str, spec: &Spec) -> String {
+    let number = value.parse::<f64>().ok();
+    let value = match (spec.precision, number) {
+        (Some(precision), Some(number)) => format!("{:.*}", precision, number),
+        (Some(precision), None) => value.graphemes(true).take(precision).collect(),
+        (None, _) => value.to_owned(),
+    };
+
+    let padding = spec.width.saturating_sub(value.graphemes(true).count());
+    let align = spec.align.unwrap_or(if number.is_some() { '>' } else { '<' });
+    let (left, right) = match align {
+        '>' => (padding, 0),
+        '^' => (padding / 2, padding - padding / 2),
+        _ => (0, padding),
+    };
+    let mut output = String::with_capacity(value.len() + padding);
+    output.extend(iter::repeat(spec.fill).take(left));
+    output.push_str(&value);
+    output.extend(iter::repeat(spec.fill).take(right));
+    output
+}
+
+/// Substitutes each placeholder within the template with its corresponding argument. Braces
+/// may be written literally as `{{` and `}}`.
+pub(super) fn format(
+    template: &str,
+    positional: &[String],
+    named: &[(String, String)],
+) -> Result<String, String> {
+    let mut output = String::with_capacity(template.len());
+    let mut next_position = 0;
+    let mut rest = template;
+    while let Some(id) = rest.find(|c| c == '{' || c == '}') {
+        output.push_str(&rest[..id]);
+        let (brace, after) = (&rest[id..id + 1], &rest[id + 1..]);
+        if after.starts_with(brace) {
+            output.push_str(brace);
+            rest = &after[1..];
+            continue;
+        } else if brace == "}" {
+            return Err("unmatched `}` in format string".into());
+        }
+
+        let end = after.find('}').ok_or("unterminated placeholder in format string")?;
+        let placeholder = &after[..end];
+        rest = &after[end + 1..];
+        let (key, spec) = match placeholder.find(':') {
+            Some(id) => (&placeholder[..id], &placeholder[id + 1..]),
+            None => (placeholder, ""),
+        };
+
+        let value = if key.is_empty() {
+            next_position += 1;
+            positional.get(next_position - 1)
+        } else if let Ok(position) = key.parse::<usize>() {
+            positional.get(position)
+        } else {
+            named.iter().find(|&&(ref name, _)| name == key).map(|&(_, ref value)| value)
+        };
+        let value = value.ok_or_else(|| format!("no argument for placeholder `{{{}}}`", key))?;
+        let spec = parse_spec(spec).ok_or_else(|| format!("invalid format spec `{}`", spec))?;
+        output.push_str(&apply(value, &spec));
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments() {
+        assert_eq!(
+            split_arguments(r#" count, name=user, "a, b", @split(x, ','), \, "#),
+            vec!["count", "name=user", "\"a, b\"", "@split(x, ',')", "\\,"]
+        );
+        assert_eq!(named_argument("name=user"), (Some("name"), "user"));
+        assert_eq!(named_argument("\"a=b\""), (None, "\"a=b\""));
+        assert_eq!(named_argument("$x=1"), (None, "$x=1"));
+    }
+
+    #[test]
+    fn placeholders() {
+        let positional = vec!["5".to_owned(), "apple".to_owned(), "3.14159".to_owned()];
+        let named = vec![("name".to_owned(), "Ion".to_owned())];
+        let cases = [
+            ("{name} has {0} items", "Ion has 5 items"),
+            ("{} {} {}", "5 apple 3.14159"),
+            ("{{{1}}}", "{apple}"),
+            ("[{1:8}|{0:4}]", "[apple   |   5]"),
+            ("[{1:>8}|{0:<4}]", "[   apple|5   ]"),
+            ("[{name:-^7}]", "[--Ion--]"),
+            ("{2:.2} {1:.3} {0:0>5.1}", "3.14 app 005.0"),
+        ];
+        for &(template, expected) in cases.iter() {
+            assert_eq!(format(template, &positional, &named).unwrap(), expected);
+        }
+        assert!(format("{3}", &positional, &named).is_err());
+        assert!(format("{user}", &positional, &named).is_err());
+        assert!(format("{0:x}", &positional, &named).is_err());
+        assert!(format("{0", &positional, &named).is_err());
+        assert!(format("0}", &positional, &named).is_err());
+    }
+}
@@ -1,14 +1,25 @@
 mod arrays;
+mod case;
+mod encoding;
+mod files;
+mod format;
+mod paths;
 mod pattern;
 mod strings;
+mod time;
 
 pub(crate) use self::arrays::ArrayMethod;
+pub(crate) use self::encoding::quote;
 pub(crate) use self::pattern::Pattern;
-pub(crate) use self::strings::StringMethod;
+pub(crate) use self::strings::{is_boolean_method, StringMethod};
+pub(crate) use self::time::timestamp;
 
 use self::pattern::unescape;
-use super::{expand_string, Expander};
+use super::{expand_string, Expander, Select};
 use super::super::super::ArgumentSplitter;
+use regex::Regex;
+use std::vec;
+use types::{Array, Value};
 
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) struct Key {
@@ -23,11 +34,11 @@ impl Key {
 
 pub(crate) struct MethodArgs<'a, 'b, E: 'b + Expander> {
     args:   &'a str,
-    expand: &'b E,
+    expand: &'b mut E,
 }
 
 impl<'a, 'b, E: 'b + Expander> MethodArgs<'a, 'b, E> {
-    pub(crate) fn new(args: &'a str, expand: &'b E) -> MethodArgs<'a, 'b, E> {
+    pub(crate) fn new(args: &'a str, expand: &'b mut E) -> MethodArgs<'a, 'b, E> {
         MethodArgs { args, expand }
     }
 
@@ -35,9 +46,132 @@ impl<'a, 'b, E: 'b + Expander> MethodArgs<'a, 'b, E> {
         unescape(expand_string(self.args, self.expand, false).join(pattern))
     }
 
-    pub(crate) fn array<'c>(&'c self) -> impl Iterator<Item = String> + 'c {
+    pub(crate) fn array(self) -> vec::IntoIter<String> {
+        self.raw_array().map(unescape).collect::<Vec<_>>().into_iter()
+    }
+
+    /// Like `array`, but backslashes are left intact so that regular expressions may use them.
+    /// Arguments may be separated by commas as well as by spaces. The arguments are expanded
+    /// before they are returned, so that the expander is free to be used again.
+    pub(crate) fn raw_array(self) -> vec::IntoIter<String> {
+        let expand = self.expand;
         ArgumentSplitter::new(self.args)
-            .flat_map(move |x| expand_string(x, self.expand, false).into_iter())
-            .map(unescape)
+            .map(|arg| {
+                if arg.ends_with(',') && !arg.ends_with("\\,") {
+                    &arg[..arg.len() - 1]
+                } else {
+                    arg
+                }
+            })
+            .filter(|arg| !arg.is_empty())
+            .flat_map(|x| expand_string(x, expand, false).into_iter())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+/// Expands expressions on behalf of another expander, in which `$it` refers to a given element,
+/// so that an expression may be evaluated for each element of an array. The expander is a trait
+/// object, as expressions may themselves contain methods which evaluate expressions, and a
+/// generic wrapper would be instantiated once for each level of nesting, without end.
+pub(crate) struct WithElement<'a> {
+    expand:  &'a mut Expander,
+    element: &'a str,
+}
+
+impl<'a> WithElement<'a> {
+    pub(crate) fn new(expand: &'a mut Expander, element: &'a str) -> WithElement<'a> {
+        WithElement { expand, element }
+    }
+}
+
+impl<'a> Expander for WithElement<'a> {
+    fn tilde(&self, input: &str) -> Option<String> { self.expand.tilde(input) }
+
+    fn array(&self, name: &str, selection: Select) -> Option<Array> {
+        self.expand.array(name, selection)
+    }
+
+    fn variable(&mut self, name: &str, quoted: bool) -> Option<Value> {
+        if name == "it" {
+            Some(self.element.into())
+        } else {
+            self.expand.variable(name, quoted)
+        }
+    }
+
+    fn command(&mut self, command: &str) -> Option<Value> { self.expand.command(command) }
+
+    fn unset(&self, name: &str) { self.expand.unset(name) }
+
+    fn unmatched(&self, glob: &str) { self.expand.unmatched(glob) }
+
+    fn enter_expansion(&self) -> bool { self.expand.enter_expansion() }
+
+    fn exit_expansion(&self) { self.expand.exit_expansion() }
+
+    fn strip_newlines(&self) -> bool { self.expand.strip_newlines() }
+
+    fn split_words(&self) -> bool { self.expand.split_words() }
+
+    fn option(&self, name: &str) -> Option<bool> { self.expand.option(name) }
+
+    fn function(&mut self, name: &str, argument: &str) -> Option<(Value, bool)> {
+        self.expand.function(name, argument)
+    }
+
+    fn assign(&mut self, name: &str, value: &str) { self.expand.assign(name, value) }
+
+    fn has_function(&self, name: &str) -> bool { self.expand.has_function(name) }
+
+    fn method(&mut self, name: &str, arguments: &[&str]) -> Option<Value> {
+        self.expand.method(name, arguments)
+    }
+}
+
+/// Converts a replacement for a regular expression into the form that the regex crate expects.
+/// Backreferences may be given as `\1` in addition to `$1`, `\$` is a literal `$`, and the
+/// remaining escapes are handled as `unescape` would handle them.
+pub(crate) fn regex_replacement(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut characters = input.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => match characters.next() {
+                Some(digit @ '0'...'9') => {
+                    output.push_str("${");
+                    output.push(digit);
+                    while let Some(&digit @ '0'...'9') = characters.peek() {
+                        output.push(digit);
+                        let _ = characters.next();
+                    }
+                    output.push('}');
+                }
+                Some('n') => output.push('\n'),
+                Some('t') => output.push('\t'),
+                Some('$') => output.push_str("$$"),
+                Some(other) => output.push(other),
+                None => output.push('\\'),
+            },
+            _ => output.push(character),
+        }
+    }
+    output
+}
+
+/// Obtains each capture group from the first match of the regular expression within the
+/// input, or the whole match if the expression has no groups. Groups which did not
+/// participate in the match are empty.
+pub(crate) fn regex_captures(regex: &Regex, input: &str) -> Array {
+    match regex.captures(input) {
+        Some(captures) => {
+            let groups = if captures.len() > 1 { 1 } else { 0 };
+            captures
+                .iter()
+                .skip(groups)
+                .map(|group| group.map_or("", |group| group.as_str()).to_owned())
+                .collect()
+        }
+        None => Array::new(),
     }
 }
@@ -0,0 +1,175 @@
+//! Lexical path manipulations, which are used by the `$dirname`, `$relative_to`, `$tilde`, and
+//! `$shorten_path` methods. None of these access the file system.
+
+use std::env;
+use std::path::{Component, Path, PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Obtains the directory portion of the path, as the `dirname` command would: trailing slashes
+/// are ignored, a path without a directory yields `.`, and the root yields `/`.
+pub(super) fn dirname(path: &str) -> String {
+    let trimmed = path.trim_right_matches('/');
+    if trimmed.is_empty() {
+        return if path.is_empty() { ".".into() } else { "/".into() };
+    }
+    match trimmed.rfind('/') {
+        Some(id) => {
+            let parent = trimmed[..id].trim_right_matches('/');
+            if parent.is_empty() { "/".into() } else { parent.into() }
+        }
+        None => ".".into(),
+    }
+}
+
+/// Resolves each `.` and `..` within the path without following symbolic links.
+fn normalize(path: &Path) -> Vec<Component> {
+    let mut components: Vec<Component> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => match components.last() {
+                Some(&Component::Normal(_)) => {
+                    components.pop();
+                }
+                Some(&Component::RootDir) | Some(&Component::Prefix(_)) => (),
+                _ => components.push(component),
+            },
+            _ => components.push(component),
+        }
+    }
+    components
+}
+
+/// Obtains the path which leads from the base to the given path. If only one of the two is
+/// absolute, the other is taken to be relative to the current working directory.
+pub(super) fn relative_to(path: &str, base: &str) -> String {
+    let (path, base) = (Path::new(path), Path::new(base));
+    let (path, base) = match (path.is_absolute(), base.is_absolute(), env::current_dir()) {
+        (true, false, Ok(cwd)) => (path.to_path_buf(), cwd.join(base)),
+        (false, true, Ok(cwd)) => (cwd.join(path), base.to_path_buf()),
+        _ => (path.to_path_buf(), base.to_path_buf()),
+    };
+    let (path, base) = (normalize(&path), normalize(&base));
+
+    let common = path.iter().zip(base.iter()).take_while(|&(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in &base[common..] {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component.as_os_str());
+    }
+    match relative.to_str() {
+        Some("") => ".".into(),
+        Some(relative) => relative.into(),
+        None => relative.to_string_lossy().into_owned(),
+    }
+}
+
+/// Replaces the home directory at the start of the path with `~`.
+pub(super) fn tilde(path: &str, home: &str) -> String {
+    let home = home.trim_right_matches('/');
+    if home.is_empty() || !path.starts_with(home) {
+        return path.into();
+    }
+    match &path[home.len()..] {
+        "" => "~".into(),
+        rest if rest.starts_with('/') => ["~", rest].concat(),
+        _ => path.into(),
+    }
+}
+
+/// Abbreviates a directory to its first grapheme, or to its first two if it is hidden.
+fn abbreviate(directory: &str) -> String {
+    let mut graphemes = UnicodeSegmentation::graphemes(directory, true);
+    match graphemes.next() {
+        Some(".") => graphemes.next().map_or(".".into(), |next| [".", next].concat()),
+        Some(first) => first.into(),
+        None => String::new(),
+    }
+}
+
+/// Shortens the path in the same manner that Fish does, by abbreviating each parent directory.
+/// Given a width, the parents are abbreviated from the outermost, and only until the path is no
+/// wider than the width, which it may still exceed once every parent has been abbreviated.
+pub(super) fn shorten(path: &str, width: Option<usize>) -> String {
+    let fits = |directories: &[String]| {
+        width.map_or(false, |width| {
+            UnicodeSegmentation::graphemes(directories.join("/").as_str(), true).count() <= width
+        })
+    };
+    let mut directories = path.split('/').map(String::from).collect::<Vec<String>>();
+    for id in 0..directories.len() - 1 {
+        if fits(&directories) {
+            break;
+        }
+        directories[id] = abbreviate(&directories[id]);
+    }
+    directories.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dirnames() {
+        let cases = [
+            ("/usr/lib/", "/usr"),
+            ("/usr/lib", "/usr"),
+            ("usr//lib", "usr"),
+            ("/usr", "/"),
+            ("usr", "."),
+            ("/", "/"),
+            ("", "."),
+        ];
+        for &(path, expected) in cases.iter() {
+            assert_eq!(dirname(path), expected);
+        }
+    }
+
+    #[test]
+    fn relative_paths() {
+        let cases = [
+            ("/usr/lib/ion", "/usr/lib", "ion"),
+            ("/usr/lib", "/usr/lib/ion", ".."),
+            ("/usr/share/doc", "/usr/lib/ion", "../../share/doc"),
+            ("/usr/./lib/../bin", "/usr", "bin"),
+            ("/usr", "/usr/", "."),
+            ("a/b/c", "a/d", "../b/c"),
+        ];
+        for &(path, base, expected) in cases.iter() {
+            assert_eq!(relative_to(path, base), expected);
+        }
+    }
+
+    #[test]
+    fn tildes() {
+        let cases = [
+            ("/home/ion", "~"),
+            ("/home/ion/src", "~/src"),
+            ("/home/ionic", "/home/ionic"),
+            ("/usr/home/ion", "/usr/home/ion"),
+        ];
+        for &(path, expected) in cases.iter() {
+            assert_eq!(tilde(path, "/home/ion/"), expected);
+        }
+        assert_eq!(tilde("/home/ion", ""), "/home/ion");
+    }
+
+    #[test]
+    fn shortened_paths() {
+        let cases = [
+            ("~/src/ion/src/shell", None, "~/s/i/s/shell"),
+            ("/usr/.local/share", None, "/u/.l/share"),
+            ("~/src/ion/src/shell", Some(15), "~/s/i/src/shell"),
+            ("~/src/ion/src/shell", Some(19), "~/src/ion/src/shell"),
+            ("~/src/ion/src/shell", Some(5), "~/s/i/s/shell"),
+            ("/", None, "/"),
+            ("shell", Some(1), "shell"),
+        ];
+        for &(path, width, expected) in cases.iter() {
+            assert_eq!(shorten(path, width), expected);
+        }
+    }
+}
@@ -1,17 +1,47 @@
-use super::MethodArgs;
+use super::{case, encoding, format, paths, regex_captures, regex_replacement, time, MethodArgs};
 use super::super::Select;
 use super::super::super::{expand_string, is_expression, slice, Expander};
-use parser::assignments::is_array;
+use parser::assignments::{is_array, is_boolean};
+use radix;
 use regex::Regex;
 use shell::plugins::methods::{self, MethodArguments, StringMethodPlugins};
+use std::cmp::Ordering;
+use std::fs;
+use std::iter;
 use std::path::Path;
 use sys;
 use unicode_segmentation::UnicodeSegmentation;
+use units;
+use versions;
 
 lazy_static! {
     static ref STRING_METHODS: StringMethodPlugins = methods::collect();
 }
 
+/// Methods which expand to either `1` or `0`, or to either `true` or `false`, and so may be used
+/// as conditions.
+const BOOLEAN_METHODS: &[&str] = &[
+    "and",
+    "contains",
+    "ends_with",
+    "is_camel_case",
+    "is_lower",
+    "is_snake_case",
+    "is_title",
+    "is_upper",
+    "not",
+    "option",
+    "or",
+    "starts_with",
+];
+
+/// Whether the word consists solely of a call to a method which expands to a boolean,
+/// such as `$contains($line, error)`.
+pub(crate) fn is_boolean_method(word: &str) -> bool {
+    word.starts_with('$') && word.ends_with(')')
+        && word.find('(').map_or(false, |id| BOOLEAN_METHODS.contains(&&word[1..id]))
+}
+
 /// Represents a method that operates on and returns a string
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) struct StringMethod<'a> {
@@ -29,13 +59,15 @@ pub(crate) struct StringMethod<'a> {
 }
 
 impl<'a> StringMethod<'a> {
-    pub(crate) fn handle<E: Expander>(&self, output: &mut String, expand: &E) {
+    pub(crate) fn handle<E: Expander>(&self, output: &mut String, expand: &mut E) {
         let variable = self.variable;
-        let pattern = MethodArgs::new(self.pattern, expand);
+        macro_rules! pattern {
+            () => { MethodArgs::new(self.pattern, &mut *expand) }
+        }
 
         macro_rules! string_eval {
             ($variable:ident $method:tt) => {{
-                let pattern = pattern.join(" ");
+                let pattern = pattern!().join(" ");
                 let is_true = if let Some(value) = expand.variable($variable, false) {
                     value.$method(&pattern)
                 } else if is_expression($variable) {
@@ -81,6 +113,25 @@ impl<'a> StringMethod<'a> {
             }}
         }
 
+        macro_rules! string_test {
+            ($test:expr) => {{
+                let is_true = $test(&get_var!());
+                output.push_str(if is_true { "1" } else { "0" });
+            }}
+        }
+
+        macro_rules! string_trim {
+            ($whitespace:ident $matches:ident) => {{
+                let value = get_var!();
+                match pattern!().array().next() {
+                    Some(ref chars) if !chars.is_empty() => {
+                        output.push_str(value.$matches(|c| chars.contains(c)))
+                    }
+                    _ => output.push_str(value.$whitespace()),
+                }
+            }}
+        }
+
         match self.method {
             "ends_with" => string_eval!(variable ends_with),
             "contains" => string_eval!(variable contains),
@@ -89,16 +140,122 @@ impl<'a> StringMethod<'a> {
             "extension" => path_eval!(extension),
             "filename" => path_eval!(file_stem),
             "parent" => path_eval!(parent),
-            "to_lowercase" => string_case!(to_lowercase),
-            "to_uppercase" => string_case!(to_uppercase),
-            "repeat" => match pattern.join(" ").parse::<usize>() {
+            "dirname" => output.push_str(&paths::dirname(&get_var!())),
+            "realpath" => {
+                let path = get_var!();
+                match fs::canonicalize(&path) {
+                    Ok(real) => output.push_str(&real.to_string_lossy()),
+                    Err(why) => eprintln!("ion: realpath: {}: {}", path, why),
+                }
+            }
+            "relative_to" => {
+                let base = pattern!().array().next().unwrap_or_else(|| ".".into());
+                output.push_str(&paths::relative_to(&get_var!(), &base));
+            }
+            "tilde" => {
+                let home = expand.variable("HOME", false).unwrap_or_default();
+                output.push_str(&paths::tilde(&get_var!(), &home));
+            }
+            "shorten_path" => {
+                let width = match pattern!().array().next() {
+                    Some(width) => match width.parse::<usize>() {
+                        Ok(width) => Some(width),
+                        Err(_) => {
+                            eprintln!("ion: shorten_path: {} is not a valid width", width);
+                            return;
+                        }
+                    },
+                    None => None,
+                };
+                let home = expand.variable("HOME", false).unwrap_or_default();
+                output.push_str(&paths::shorten(&paths::tilde(&get_var!(), &home), width));
+            }
+            "to_lowercase" | "to_lower" => string_case!(to_lowercase),
+            "to_uppercase" | "to_upper" => string_case!(to_uppercase),
+            "to_title" => output.push_str(&case::to_title(&get_var!())),
+            "snake_case" => output.push_str(&case::snake_case(&get_var!())),
+            "camel_case" => output.push_str(&case::camel_case(&get_var!())),
+            "is_upper" => string_test!(case::is_upper),
+            "is_lower" => string_test!(case::is_lower),
+            "is_title" => string_test!(|value| case::is_case(value, case::to_title)),
+            "is_snake_case" => string_test!(|value| case::is_case(value, case::snake_case)),
+            "is_camel_case" => string_test!(|value| case::is_case(value, case::camel_case)),
+            "url_encode" => output.push_str(&encoding::url_encode(&get_var!())),
+            "url_decode" => output.push_str(&encoding::url_decode(&get_var!())),
+            "quote" => output.push_str(&encoding::quote(&get_var!())),
+            "trim" => string_trim!(trim trim_matches),
+            "trim_left" => string_trim!(trim_left trim_left_matches),
+            "trim_right" => string_trim!(trim_right trim_right_matches),
+            "pad" | "pad_left" | "pad_right" => {
+                let mut args = pattern!().array();
+                let width = args.next().and_then(|width| width.parse::<usize>().ok());
+                let fill = args.next().and_then(|fill| fill.chars().next()).unwrap_or(' ');
+                match width {
+                    Some(width) => {
+                        let value = get_var!();
+                        let len = UnicodeSegmentation::graphemes(value.as_str(), true).count();
+                        let padding = width.saturating_sub(len);
+                        let (left, right) = match self.method {
+                            "pad_left" => (padding, 0),
+                            "pad_right" => (0, padding),
+                            _ => (padding / 2, padding - padding / 2),
+                        };
+                        output.extend(iter::repeat(fill).take(left));
+                        output.push_str(&value);
+                        output.extend(iter::repeat(fill).take(right));
+                    }
+                    None => eprintln!("ion: {}: a valid width is required", self.method),
+                }
+            }
+            "format" => {
+                let (mut positional, mut named) = (Vec::new(), Vec::new());
+                for argument in format::split_arguments(self.pattern) {
+                    let (name, value) = format::named_argument(argument);
+                    // Bare words are variable names, except for numbers, which are literals.
+                    let found =
+                        if format::is_name(value) { expand.variable(value, false) } else { None };
+                    let value =
+                        found.unwrap_or_else(|| expand_string(value, expand, false).join(" "));
+                    match name {
+                        Some(name) => named.push((name.to_owned(), value)),
+                        None => positional.push(value),
+                    }
+                }
+                match format::format(&get_var!(), &positional, &named) {
+                    Ok(formatted) => output.push_str(&formatted),
+                    Err(why) => eprintln!("ion: format: {}", why),
+                }
+            }
+            "truncate" => {
+                let mut args = pattern!().array();
+                let width = args.next().and_then(|width| width.parse::<usize>().ok());
+                let ellipsis = args.next().unwrap_or_default();
+                match width {
+                    Some(width) => {
+                        let value = get_var!();
+                        let graphemes = UnicodeSegmentation::graphemes(value.as_str(), true)
+                            .collect::<Vec<&str>>();
+                        if graphemes.len() <= width {
+                            output.push_str(&value);
+                        } else {
+                            let ellipsis = UnicodeSegmentation::graphemes(ellipsis.as_str(), true)
+                                .take(width)
+                                .collect::<Vec<&str>>();
+                            output.extend(graphemes[..width - ellipsis.len()].iter().cloned());
+                            output.extend(ellipsis);
+                        }
+                    }
+                    None => eprintln!("ion: truncate: a valid width is required"),
+                }
+            }
+            "repeat" => match pattern!().join(" ").parse::<usize>() {
                 Ok(repeat) => output.push_str(&get_var!().repeat(repeat)),
                 Err(_) => {
                     eprintln!("ion: value supplied to $repeat() is not a valid number");
                 }
             },
             "replace" => {
-                let mut args = pattern.array();
+                let mut args = pattern!().array();
                 match (args.next(), args.next()) {
                     (Some(replace), Some(with)) => {
                         let res = &get_var!().replace(&replace, &with);
@@ -108,7 +265,7 @@ impl<'a> StringMethod<'a> {
                 }
             }
             "replacen" => {
-                let mut args = pattern.array();
+                let mut args = pattern!().array();
                 match (args.next(), args.next(), args.next()) {
                     (Some(replace), Some(with), Some(nth)) => if let Ok(nth) = nth.parse::<usize>()
                     {
@@ -121,12 +278,12 @@ impl<'a> StringMethod<'a> {
                 }
             }
             "regex_replace" => {
-                let mut args = pattern.array();
+                let mut args = pattern!().raw_array();
                 match (args.next(), args.next()) {
                     (Some(replace), Some(with)) => match Regex::new(&replace) {
                         Ok(re) => {
                             let inp = &get_var!();
-                            let res = re.replace_all(&inp, &with[..]);
+                            let res = re.replace_all(&inp, &regex_replacement(&with)[..]);
                             output.push_str(&res);
                         }
                         Err(_) => eprintln!(
@@ -137,16 +294,50 @@ impl<'a> StringMethod<'a> {
                     _ => eprintln!("ion: regex_replace: two arguments required"),
                 }
             }
+            "regex_capture" => match pattern!().raw_array().next() {
+                Some(expression) => match Regex::new(&expression) {
+                    Ok(re) => {
+                        let groups = regex_captures(&re, &get_var!()).join(" ");
+                        slice(output, groups, self.selection.clone());
+                    }
+                    Err(_) => eprintln!(
+                        "ion: regex_capture: error in regular expression {}",
+                        &expression
+                    ),
+                },
+                None => eprintln!("ion: regex_capture: a regular expression is required"),
+            },
             "join" => {
-                let pattern = pattern.join(" ");
-                if let Some(array) = expand.array(variable, Select::All) {
-                    slice(output, array.join(&pattern), self.selection.clone());
+                // A second argument is a template, in which `{}` is replaced by each element.
+                let arguments = format::split_arguments(self.pattern);
+                let (pattern, template) = if arguments.len() == 2 {
+                    let template = MethodArgs::new(arguments[1], expand).join(" ");
+                    (MethodArgs::new(arguments[0], expand).join(" "), Some(template))
+                } else {
+                    (pattern!().join(" "), None)
+                };
+                let array = if let Some(array) = expand.array(variable, Select::All) {
+                    array
                 } else if is_expression(variable) {
-                    slice(
-                        output,
-                        expand_string(variable, expand, false).join(&pattern),
-                        self.selection.clone(),
-                    );
+                    expand_string(variable, expand, false)
+                } else {
+                    return;
+                };
+                match template {
+                    Some(template) => {
+                        let mut elements = Vec::with_capacity(array.len());
+                        for element in array {
+                            match format::format(&template, &[element], &[]) {
+                                Ok(formatted) => elements.push(formatted),
+                                Err(why) => {
+                                    eprintln!("ion: join: {}", why);
+                                    return;
+                                }
+                            }
+                        }
+                        slice(output, elements.join(&pattern), self.selection.clone());
+                    }
+                    None => slice(output, array.join(&pattern), self.selection.clone()),
                 }
             }
             "len" => if variable.starts_with('@') || is_array(variable) {
@@ -176,13 +367,142 @@ impl<'a> StringMethod<'a> {
             },
             "find" => {
                 let out = if let Some(value) = expand.variable(variable, false) {
-                    value.find(&pattern.join(" "))
+                    value.find(&pattern!().join(" "))
                 } else if is_expression(variable) {
-                    expand_string(variable, expand, false).join(" ").find(&pattern.join(" "))
+                    expand_string(variable, expand, false).join(" ").find(&pattern!().join(" "))
                 } else {
                     None
                 };
-                output.push_str(&out.unwrap_or(0).to_string());
+                match out {
+                    Some(index) => output.push_str(&index.to_string()),
+                    None => output.push_str("-1"),
+                }
+            }
+            "compare_versions" => {
+                let ordering = versions::compare(&get_var!(), &pattern!().join(" "));
+                output.push_str(match ordering {
+                    Ordering::Less => "-1",
+                    Ordering::Equal => "0",
+                    Ordering::Greater => "1",
+                });
+            }
+            "date_format" => {
+                let clock = time::Clock::new(expand);
+                let time = match pattern!().array().next() {
+                    Some(time) => time::parse_any(&time, |time| clock.offset(time)),
+                    None => Some(clock.now()),
+                };
+                match time {
+                    Some(time) => {
+                        let offset = clock.offset(time);
+                        output.push_str(&time::format(time, offset, &get_var!()));
+                    }
+                    None => eprintln!("ion: date_format: unable to parse the given time"),
+                }
+            }
+            "parse_time" => {
+                let input = get_var!();
+                let clock = time::Clock::new(expand);
+                let time = match pattern!().array().next() {
+                    Some(format) => time::parse(&input, &format, |time| clock.offset(time)),
+                    None => time::parse_any(&input, |time| clock.offset(time)),
+                };
+                match time {
+                    Some(time) => output.push_str(&time.to_string()),
+                    None => eprintln!("ion: parse_time: unable to parse time: {}", input),
+                }
+            }
+            "elapsed" => {
+                let clock = time::Clock::new(expand);
+                let start = time::parse_any(&get_var!(), |time| clock.offset(time));
+                let end = match pattern!().array().next() {
+                    Some(end) => time::parse_any(&end, |time| clock.offset(time)),
+                    None => Some(clock.now()),
+                };
+                match (start, end) {
+                    (Some(start), Some(end)) => output.push_str(&time::elapsed(end - start)),
+                    _ => eprintln!("ion: elapsed: unable to parse the given times"),
+                }
+            }
+            "count" => {
+                let pattern = pattern!().join(" ");
+                if pattern.is_empty() {
+                    eprintln!("ion: count: a pattern is required");
+                } else {
+                    output.push_str(&get_var!().matches(pattern.as_str()).count().to_string());
+                }
+            }
+            "to_base" | "from_base" => {
+                let input = get_var!();
+                let base = pattern!().array().next().and_then(|base| base.parse::<u32>().ok());
+                let converted = match base {
+                    Some(base) if self.method == "to_base" => match radix::parse(&input) {
+                        Some(value) => radix::to_base(value, base),
+                        None => Err(format!("'{}' is not an integer", input)),
+                    },
+                    Some(base) => radix::from_base(&input, base).map(|value| value.to_string()),
+                    None => Err("a valid base is required".into()),
+                };
+                match converted {
+                    Ok(converted) => output.push_str(&converted),
+                    Err(why) => eprintln!("ion: {}: {}", self.method, why),
+                }
+            }
+            "to_unit" | "from_unit" => {
+                let input = get_var!();
+                let converted = if self.method == "from_unit" {
+                    units::parse_decimal(&input)
+                        .map(|number| number.to_string())
+                        .ok_or_else(|| format!("'{}' is not a number", input))
+                } else {
+                    match pattern!().array().next() {
+                        Some(unit) => units::to_unit(&input, &unit),
+                        None => Err("a unit is required".into()),
+                    }
+                };
+                match converted {
+                    Ok(converted) => output.push_str(&converted),
+                    Err(why) => eprintln!("ion: {}: {}", self.method, why),
+                }
+            }
+            "and" | "or" | "not" => match logical(self.method, &get_var!(), pattern!().array()) {
+                Ok(is_true) => output.push_str(if is_true { "true" } else { "false" }),
+                Err(why) => eprintln!("ion: {}: {}", self.method, why),
+            },
+            "select" => {
+                // Only the value which is selected is expanded, so that the command substitutions
+                // of the other are never executed.
+                let arguments = format::split_arguments(self.pattern);
+                let condition = get_var!();
+                match (is_boolean(&condition), arguments.len()) {
+                    (Ok(boolean), 2) => {
+                        let selected = if boolean == "true" { arguments[0] } else { arguments[1] };
+                        output.push_str(&MethodArgs::new(selected, expand).join(" "));
+                    }
+                    (Err(()), _) => eprintln!("ion: select: '{}' is not a boolean", condition),
+                    _ => eprintln!("ion: select: a value for each outcome is required"),
+                }
+            }
+            "option" => {
+                // The argument is the name of an option rather than a variable.
+                let name = if is_expression(variable) {
+                    expand_string(variable, expand, false).join(" ")
+                } else {
+                    variable.to_owned()
+                };
+                match expand.option(&name) {
+                    Some(enabled) => output.push_str(if enabled { "1" } else { "0" }),
+                    None => eprintln!("ion: option: {}: no such option", name),
+                }
+            }
+            method if expand.has_function(method) => {
+                let input = get_var!();
+                let arguments = pattern!().array().collect::<Vec<_>>();
+                let mut args = vec![input.as_str()];
+                args.extend(arguments.iter().map(String::as_str));
+                if let Some(value) = expand.method(method, &args) {
+                    output.push_str(&value);
+                }
             }
             method @ _ => {
                 if sys::is_root() {
@@ -190,7 +510,7 @@ impl<'a> StringMethod<'a> {
                     return;
                 }
 
-                let pattern = pattern.array().collect::<Vec<_>>();
+                let pattern = pattern!().array().collect::<Vec<_>>();
                 let args = if variable.starts_with('@') || is_array(variable) {
                     MethodArguments::Array(
                         expand_string(variable, expand, false).into_vec(),
@@ -218,3 +538,26 @@ impl<'a> StringMethod<'a> {
         }
     }
 }
+
+/// Evaluates a logical method upon its input and arguments, each of which must be a boolean:
+/// either `true`, `1`, or `y`, or `false`, `0`, or `n`.
+fn logical<I: Iterator<Item = String>>(
+    method: &str,
+    input: &str,
+    arguments: I,
+) -> Result<bool, String> {
+    let parse = |value: &str| match is_boolean(value) {
+        Ok(boolean) => Ok(boolean == "true"),
+        Err(()) => Err(format!("'{}' is not a boolean", value)),
+    };
+    let mut is_true = parse(input)?;
+    for argument in arguments {
+        let argument = parse(&argument)?;
+        match method {
+            "and" => is_true &= argument,
+            "or" => is_true |= argument,
+            _ => return Err("only one value may be negated".into()),
+        }
+    }
+    Ok(if method == "not" { !is_true } else { is_true })
+}
@@ -0,0 +1,461 @@
+//! Conversions between seconds since the Unix epoch and calendar dates, which are used by the
+//! `$date_format`, `$parse_time`, and `$elapsed` methods.
+
+use super::super::super::Expander;
+use std::time::{SystemTime, UNIX_EPOCH};
+use sys;
+
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September",
+    "October", "November", "December",
+];
+
+const WEEKDAYS: [&str; 7] =
+    ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+
+/// Patterns which are attempted, in order, when parsing a time without a given pattern.
+const ISO_8601: [&str; 7] = [
+    "%Y-%m-%dT%H:%M:%S%z",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M:%S%z",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%d",
+];
+
+/// The time at which every script is executed in sandbox mode: 2000-01-01T00:00:00Z.
+const SANDBOX_EPOCH: i64 = 946_684_800;
+
+/// The number of seconds since the Unix epoch.
+fn now() -> i64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(why) => -(why.duration().as_secs() as i64),
+    }
+}
+
+/// The source of the current time and of the local time zone, which are fixed to
+/// `SANDBOX_EPOCH` and UTC when the `sandbox` option is enabled.
+pub(super) struct Clock {
+    sandboxed: bool,
+}
+
+impl Clock {
+    pub(super) fn new<E: Expander>(expand: &mut E) -> Clock {
+        Clock {
+            sandboxed: expand.option("sandbox") == Some(true),
+        }
+    }
+
+    pub(super) fn now(&self) -> i64 { if self.sandboxed { SANDBOX_EPOCH } else { now() } }
+
+    /// The offset of the local time zone from UTC at the given time, in seconds.
+    pub(super) fn offset(&self, time: i64) -> i64 {
+        if self.sandboxed { 0 } else { sys::utc_offset(time) }
+    }
+}
+
+/// The number of days since the Unix epoch at which the given date occurs.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date which occurs at the given number of days since the Unix epoch.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = (if days >= 0 { days } else { days - 146_096 }) / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn is_leap_year(year: i64) -> bool { year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) }
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// A point in time as seen from a time zone which is `offset` seconds ahead of UTC.
+struct DateTime {
+    year:   i64,
+    month:  i64,
+    day:    i64,
+    hour:   i64,
+    minute: i64,
+    second: i64,
+    offset: i64,
+}
+
+impl DateTime {
+    fn from_epoch(epoch: i64, offset: i64) -> DateTime {
+        let days = div_floor(epoch + offset, 86_400);
+        let seconds = epoch + offset - days * 86_400;
+        let (year, month, day) = civil_from_days(days);
+        DateTime {
+            year,
+            month,
+            day,
+            hour:   seconds / 3600,
+            minute: seconds / 60 % 60,
+            second: seconds % 60,
+            offset,
+        }
+    }
+
+    fn epoch(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * 86_400 + self.hour * 3600
+            + self.minute * 60 + self.second - self.offset
+    }
+
+    fn weekday(&self) -> i64 {
+        (days_from_civil(self.year, self.month, self.day) % 7 + 11) % 7
+    }
+
+    fn day_of_year(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) - days_from_civil(self.year, 1, 1) + 1
+    }
+}
+
+fn is_digit(byte: u8) -> bool { byte >= b'0' && byte <= b'9' }
+
+fn div_floor(value: i64, divisor: i64) -> i64 {
+    let quotient = value / divisor;
+    if value % divisor < 0 { quotient - 1 } else { quotient }
+}
+
+/// The current time in the local time zone, as an ISO 8601 timestamp such as
+/// `2018-02-05T14:03:09+0100`.
+pub(crate) fn timestamp() -> String {
+    let now = now();
+    format(now, sys::utc_offset(now), "%Y-%m-%dT%H:%M:%S%z")
+}
+
+/// Formats the time given in seconds since the Unix epoch with a `strftime` pattern, in which
+/// unsupported conversions are left as they are.
+pub(super) fn format(epoch: i64, offset: i64, pattern: &str) -> String {
+    let time = DateTime::from_epoch(epoch, offset);
+    let mut output = String::with_capacity(pattern.len() * 2);
+    let mut characters = pattern.chars();
+    while let Some(character) = characters.next() {
+        if character != '%' {
+            output.push(character);
+            continue;
+        }
+        let hour12 = if time.hour % 12 == 0 { 12 } else { time.hour % 12 };
+        match characters.next() {
+            Some('a') => output.push_str(&WEEKDAYS[time.weekday() as usize][..3]),
+            Some('A') => output.push_str(WEEKDAYS[time.weekday() as usize]),
+            Some('b') | Some('h') => output.push_str(&MONTHS[time.month as usize - 1][..3]),
+            Some('B') => output.push_str(MONTHS[time.month as usize - 1]),
+            Some('c') => output.push_str(&format(epoch, offset, "%a %b %e %H:%M:%S %Y")),
+            Some('C') => output.push_str(&format!("{:02}", div_floor(time.year, 100))),
+            Some('d') => output.push_str(&format!("{:02}", time.day)),
+            Some('D') => output.push_str(&format(epoch, offset, "%m/%d/%y")),
+            Some('e') => output.push_str(&format!("{:2}", time.day)),
+            Some('F') => output.push_str(&format(epoch, offset, "%Y-%m-%d")),
+            Some('H') => output.push_str(&format!("{:02}", time.hour)),
+            Some('I') => output.push_str(&format!("{:02}", hour12)),
+            Some('j') => output.push_str(&format!("{:03}", time.day_of_year())),
+            Some('k') => output.push_str(&format!("{:2}", time.hour)),
+            Some('l') => output.push_str(&format!("{:2}", hour12)),
+            Some('m') => output.push_str(&format!("{:02}", time.month)),
+            Some('M') => output.push_str(&format!("{:02}", time.minute)),
+            Some('n') => output.push('\n'),
+            Some('p') => output.push_str(if time.hour < 12 { "AM" } else { "PM" }),
+            Some('P') => output.push_str(if time.hour < 12 { "am" } else { "pm" }),
+            Some('r') => output.push_str(&format(epoch, offset, "%I:%M:%S %p")),
+            Some('R') => output.push_str(&format(epoch, offset, "%H:%M")),
+            Some('s') => output.push_str(&epoch.to_string()),
+            Some('S') => output.push_str(&format!("{:02}", time.second)),
+            Some('t') => output.push('\t'),
+            Some('T') => output.push_str(&format(epoch, offset, "%H:%M:%S")),
+            Some('u') => output.push_str(&((time.weekday() + 6) % 7 + 1).to_string()),
+            Some('w') => output.push_str(&time.weekday().to_string()),
+            Some('y') => output.push_str(&format!("{:02}", time.year.abs() % 100)),
+            Some('Y') => output.push_str(&time.year.to_string()),
+            Some('z') => {
+                let sign = if time.offset < 0 { '-' } else { '+' };
+                let offset = time.offset.abs() / 60;
+                output.push_str(&format!("{}{:02}{:02}", sign, offset / 60, offset % 60));
+            }
+            Some('%') => output.push('%'),
+            Some(other) => {
+                output.push('%');
+                output.push(other);
+            }
+            None => output.push('%'),
+        }
+    }
+    output
+}
+
+/// Reads up to `width` digits, along with a sign if `signed` is set.
+fn parse_number(input: &mut &str, width: usize, signed: bool) -> Option<i64> {
+    let negative = signed && input.starts_with('-');
+    let sign = if negative || (signed && input.starts_with('+')) { 1 } else { 0 };
+    let digits = input[sign..].bytes().take(width).take_while(|&b| is_digit(b)).count();
+    if digits == 0 {
+        return None;
+    }
+    let value = input[sign..sign + digits].parse::<i64>().ok()?;
+    *input = &input[sign + digits..];
+    Some(if negative { -value } else { value })
+}
+
+/// Reads the name, or the abbreviated name, of one of the given months or weekdays.
+fn parse_name(input: &mut &str, names: &[&str]) -> Option<i64> {
+    let lowercase = input.to_lowercase();
+    for (id, name) in names.iter().enumerate() {
+        let name = name.to_lowercase();
+        for prefix in &[&name[..], &name[..3]] {
+            if lowercase.starts_with(prefix) {
+                *input = &input[prefix.len()..];
+                return Some(id as i64);
+            }
+        }
+    }
+    None
+}
+
+/// Reads an offset from UTC such as `Z`, `+02`, `-0530`, or `+05:30`, in seconds.
+fn parse_offset(input: &mut &str) -> Option<i64> {
+    *input = input.trim_left();
+    if input.starts_with('Z') || input.starts_with('z') {
+        *input = &input[1..];
+        return Some(0);
+    }
+    let negative = match input.chars().next() {
+        Some('+') => false,
+        Some('-') => true,
+        _ => return None,
+    };
+    *input = &input[1..];
+    let hours = parse_number(input, 2, false)?;
+    if input.starts_with(':') {
+        *input = &input[1..];
+    }
+    let minutes = parse_number(input, 2, false).unwrap_or(0);
+    let offset = hours * 3600 + minutes * 60;
+    Some(if negative { -offset } else { offset })
+}
+
+/// Parses a time according to a `strptime` pattern, into seconds since the Unix epoch. Times
+/// without an offset are taken to be in the local time zone, whose offset from UTC at a given
+/// time is obtained from `local_offset`.
+pub(super) fn parse<F: Fn(i64) -> i64>(input: &str, pattern: &str, local_offset: F) -> Option<i64> {
+    let mut time = DateTime {
+        year:   1970,
+        month:  1,
+        day:    1,
+        hour:   0,
+        minute: 0,
+        second: 0,
+        offset: 0,
+    };
+    let (mut epoch, mut offset, mut pm) = (None, None, None);
+    let mut input = input.trim();
+    let mut characters = pattern.trim().chars();
+    while let Some(character) = characters.next() {
+        if character.is_whitespace() {
+            input = input.trim_left();
+            continue;
+        } else if character != '%' {
+            if !input.starts_with(character) {
+                return None;
+            }
+            input = &input[character.len_utf8()..];
+            continue;
+        }
+
+        let input = &mut input;
+        match characters.next()? {
+            'Y' => time.year = parse_number(input, 4, true)?,
+            'y' => {
+                let year = parse_number(input, 2, false)?;
+                time.year = if year < 69 { 2000 + year } else { 1900 + year };
+            }
+            'm' => time.month = parse_number(input, 2, false)?,
+            'd' | 'e' => {
+                *input = input.trim_left();
+                time.day = parse_number(input, 2, false)?;
+            }
+            'H' | 'k' => {
+                *input = input.trim_left();
+                time.hour = parse_number(input, 2, false)?;
+            }
+            'I' | 'l' => {
+                *input = input.trim_left();
+                time.hour = parse_number(input, 2, false)? % 12;
+            }
+            'M' => time.minute = parse_number(input, 2, false)?,
+            'S' => {
+                time.second = parse_number(input, 2, false)?;
+                // Fractions of a second are accepted, but discarded.
+                if input.starts_with('.') || input.starts_with(',') {
+                    let digits = input[1..].bytes().take_while(|&b| is_digit(b)).count();
+                    *input = &input[1 + digits..];
+                }
+            }
+            'b' | 'B' | 'h' => time.month = parse_name(input, &MONTHS)? + 1,
+            'a' | 'A' => {
+                parse_name(input, &WEEKDAYS)?;
+            }
+            'p' | 'P' => {
+                let lowercase = input.get(..2)?.to_lowercase();
+                pm = Some(match lowercase.as_str() {
+                    "am" => false,
+                    "pm" => true,
+                    _ => return None,
+                });
+                *input = &input[2..];
+            }
+            's' => epoch = Some(parse_number(input, 20, true)?),
+            'z' => offset = Some(parse_offset(input)?),
+            'n' | 't' => *input = input.trim_left(),
+            '%' if input.starts_with('%') => *input = &input[1..],
+            _ => return None,
+        }
+    }
+
+    if !input.is_empty() {
+        return None;
+    } else if let Some(epoch) = epoch {
+        return Some(epoch);
+    }
+    if let Some(pm) = pm {
+        time.hour = time.hour % 12 + if pm { 12 } else { 0 };
+    }
+    let valid_day = time.month >= 1 && time.month <= 12 && time.day >= 1
+        && time.day <= days_in_month(time.year, time.month);
+    if !valid_day || time.hour > 23 || time.minute > 59 || time.second > 60 {
+        return None;
+    }
+
+    Some(match offset {
+        Some(offset) => {
+            time.offset = offset;
+            time.epoch()
+        }
+        None => {
+            // The offset is found from the time as though it were UTC, and then refined from
+            // the resulting time, so that a change in the offset between the two is noticed.
+            let naive = time.epoch();
+            time.offset = local_offset(naive);
+            time.offset = local_offset(time.epoch());
+            time.epoch()
+        }
+    })
+}
+
+/// Parses a time given as seconds since the Unix epoch, optionally prefixed with `@`, or as an
+/// ISO 8601 timestamp such as `2018-01-30T18:25:43+01:00`.
+pub(super) fn parse_any<F: Fn(i64) -> i64>(input: &str, local_offset: F) -> Option<i64> {
+    let input = input.trim();
+    let seconds = if input.starts_with('@') { &input[1..] } else { input };
+    if let Ok(seconds) = seconds.parse::<i64>() {
+        return Some(seconds);
+    }
+    ISO_8601.iter().filter_map(|pattern| parse(input, pattern, &local_offset)).next()
+}
+
+/// Describes a duration in seconds with the largest units that apply, such as `1d 2h 3m 4s`.
+pub(super) fn elapsed(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.abs();
+    let units = [
+        (seconds / 86_400, "d"),
+        (seconds / 3600 % 24, "h"),
+        (seconds / 60 % 60, "m"),
+        (seconds % 60, "s"),
+    ];
+    let first = units.iter().position(|&(value, _)| value != 0).unwrap_or(3);
+    let units = units[first..]
+        .iter()
+        .map(|&(value, unit)| format!("{}{}", value, unit))
+        .collect::<Vec<String>>();
+    [sign, &units.join(" ")].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting() {
+        // 2017-12-09T21:05:09Z, a Saturday.
+        let epoch = 1_512_853_509;
+        let pattern = "%a %A %b %B %d %e %H %I %j %m %M %p %S %u %w %y %Y %z %% %q";
+        assert_eq!(
+            format(epoch, 0, pattern),
+            "Sat Saturday Dec December 09  9 21 09 343 12 05 PM 09 6 6 17 2017 +0000 % %q"
+        );
+        assert_eq!(
+            format(epoch, 0, "%F %T %R %D %s"),
+            "2017-12-09 21:05:09 21:05 12/09/17 1512853509"
+        );
+        assert_eq!(format(epoch, -5 * 3600, "%F %T %z"), "2017-12-09 16:05:09 -0500");
+        assert_eq!(format(epoch, 5 * 3600 + 1800, "%F %T %z"), "2017-12-10 02:35:09 +0530");
+        assert_eq!(format(-1, 0, "%F %T"), "1969-12-31 23:59:59");
+        assert_eq!(format(951_782_400, 0, "%F %j"), "2000-02-29 060");
+    }
+
+    #[test]
+    fn parsing() {
+        let utc = |_| 0;
+        let epoch = Some(1_512_853_509);
+        assert_eq!(parse("2017-12-09 21:05:09", "%Y-%m-%d %H:%M:%S", utc), epoch);
+        let pattern = "%a, %e %b %Y %I:%M:%S %p";
+        assert_eq!(parse("Sat, 9 Dec 2017 09:05:09 pm", pattern, utc), epoch);
+        assert_eq!(parse("12/09/17 16:05:09 -0500", "%m/%d/%y %H:%M:%S %z", utc), epoch);
+        assert_eq!(parse("1512853509", "%s", utc), epoch);
+        assert_eq!(parse("2017-02-29", "%Y-%m-%d", utc), None);
+        assert_eq!(parse("2017-12-09 extra", "%Y-%m-%d", utc), None);
+
+        assert_eq!(parse_any("2017-12-09T21:05:09Z", utc), epoch);
+        assert_eq!(parse_any("2017-12-09T22:05:09.123+01:00", utc), epoch);
+        assert_eq!(parse_any("2017-12-09 22:05:09", |_| 3600), epoch);
+        assert_eq!(parse_any("@1512853509", utc), epoch);
+        assert_eq!(parse_any("1969-12-31", utc), Some(-86_400));
+        assert_eq!(parse_any("yesterday", utc), None);
+    }
+
+    struct Sandbox;
+
+    impl Expander for Sandbox {
+        fn option(&self, name: &str) -> Option<bool> { Some(name == "sandbox") }
+    }
+
+    #[test]
+    fn sandboxed_clock() {
+        let clock = Clock::new(&mut Sandbox);
+        let now = clock.now();
+        assert_eq!(format(now, clock.offset(now), "%F %T %z"), "2000-01-01 00:00:00 +0000");
+        assert_eq!(parse_any("2000-01-01 01:00:00", |time| clock.offset(time)), Some(946_688_400));
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(elapsed(0), "0s");
+        assert_eq!(elapsed(59), "59s");
+        assert_eq!(elapsed(3600), "1h 0m 0s");
+        assert_eq!(elapsed(93_784), "1d 2h 3m 4s");
+        assert_eq!(elapsed(-61), "-1m 1s");
+    }
+}
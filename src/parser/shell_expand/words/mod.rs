@@ -6,28 +6,15 @@ mod range;
 mod select;
 
 pub(crate) use self::index::Index;
-pub(crate) use self::methods::{ArrayMethod, Pattern, StringMethod};
+pub(crate) use self::methods::{is_boolean_method, quote, timestamp, ArrayMethod, Pattern,
+                               StringMethod};
 #[cfg(test)]
 pub(crate) use self::methods::Key;
 pub(crate) use self::range::Range;
 pub(crate) use self::select::{Select, SelectWithSize};
 use super::{expand_string, Expander};
 use super::super::ArgumentSplitter;
-
-// Bit Twiddling Guide:
-// var & FLAG != 0 checks if FLAG is enabled
-// var & FLAG == 0 checks if FLAG is disabled
-// var |= FLAG enables the FLAG
-// var &= 255 ^ FLAG disables the FLAG
-// var ^= FLAG swaps the state of FLAG
-
-bitflags! {
-    pub struct Flags : u8 {
-        const BACKSL = 1;
-        const SQUOTE = 2;
-        const DQUOTE = 4;
-    }
-}
+use super::super::quoting::{Class, Quotes};
 
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) enum WordToken<'a> {
@@ -47,19 +34,19 @@ pub(crate) enum WordToken<'a> {
     Arithmetic(&'a str), // Glob(&'a str)
 }
 
-pub(crate) struct WordIterator<'a, E: Expander + 'a> {
+pub(crate) struct WordIterator<'a, 'e, E: Expander + 'e> {
     data:      &'a str,
     read:      usize,
-    flags:     Flags,
-    expanders: &'a E,
+    quotes:    Quotes,
+    expanders: &'e mut E,
 }
 
-impl<'a, E: Expander + 'a> WordIterator<'a, E> {
-    pub(crate) fn new(data: &'a str, expanders: &'a E) -> WordIterator<'a, E> {
+impl<'a, 'e, E: Expander + 'e> WordIterator<'a, 'e, E> {
+    pub(crate) fn new(data: &'a str, expanders: &'e mut E) -> WordIterator<'a, 'e, E> {
         WordIterator {
             data,
             read: 0,
-            flags: Flags::empty(),
+            quotes: Quotes::new(),
             expanders,
         }
     }
@@ -90,7 +77,7 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
             if character == b'}' {
                 let output = &self.data[start..self.read];
                 self.read += 1;
-                return WordToken::Variable(output, self.flags.contains(DQUOTE), Select::All);
+                return WordToken::Variable(output, self.quotes.is_double(), Select::All);
             }
             self.read += 1;
         }
@@ -111,15 +98,19 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
                     let method = &self.data[start..self.read];
                     self.read += 1;
                     start = self.read;
-                    let mut depth = 0;
+                    let (mut depth, mut quotes) = (0, Quotes::new());
                     while let Some(character) = iterator.next() {
                         match character {
+                            // Commas and parentheses within quotes belong to the variable.
+                            _ if quotes.scan(character) != Class::Unquoted => (),
                             b',' if depth == 0 => {
                                 let variable = &self.data[start..self.read];
                                 self.read += 1;
                                 start = self.read;
                                 while let Some(character) = iterator.next() {
-                                    if character == b')' {
+                                    if quotes.scan(character) != Class::Unquoted {
+                                        // Quoted and escaped bytes belong to the pattern.
+                                    } else if character == b')' {
                                         self.read += 1;
                                         if depth != 0 {
                                             depth -= 1;
@@ -146,9 +137,6 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
                                         };
                                     } else if character == b'(' {
                                         depth += 1;
-                                    } else if character == b'\\' {
-                                        self.read += 1;
-                                        let _ = iterator.next();
                                     }
                                     self.read += 1;
                                 }
@@ -191,11 +179,11 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
                     return if character == b'[' {
                         WordToken::Variable(
                             variable,
-                            self.flags.contains(DQUOTE),
+                            self.quotes.is_double(),
                             self.read_selection(iterator),
                         )
                     } else {
-                        WordToken::Variable(variable, self.flags.contains(DQUOTE), Select::All)
+                        WordToken::Variable(variable, self.quotes.is_double(), Select::All)
                     };
                 }
                 _ => (),
@@ -203,7 +191,7 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
             self.read += 1;
         }
 
-        WordToken::Variable(&self.data[start..], self.flags.contains(DQUOTE), Select::All)
+        WordToken::Variable(&self.data[start..], self.quotes.is_double(), Select::All)
     }
 
     fn read_selection<I>(&mut self, iterator: &mut I) -> Select
@@ -228,6 +216,85 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
         panic!()
     }
 
+    /// Extends an expansion which is followed by `.method(arguments)` into a call of that method
+    /// upon the expansion, so that `$var.to_lower().trim()` is equivalent to
+    /// `$trim($to_lower($var))`. Chained methods are string methods when the expansion began
+    /// with a `$`, and array methods when it began with a `@`.
+    fn method_chain(
+        &mut self,
+        start: usize,
+        mut token: WordToken<'a>,
+        array: bool,
+    ) -> WordToken<'a> {
+        let bytes = self.data.as_bytes();
+        while let Some(&b'.') = bytes.get(self.read) {
+            let name_start = self.read + 1;
+            let name_end = name_start + bytes[name_start..]
+                .iter()
+                .take_while(|&&byte| match byte {
+                    b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' | b'_' => true,
+                    _ => false,
+                })
+                .count();
+            if name_end == name_start || bytes.get(name_end) != Some(&b'(') {
+                break;
+            }
+
+            // Commas and parentheses within quotes belong to the arguments.
+            let (mut depth, mut quotes, mut end) = (0, Quotes::new(), None);
+            let mut id = name_end + 1;
+            while id < bytes.len() {
+                match bytes[id] {
+                    _ if quotes.scan(bytes[id]) != Class::Unquoted => (),
+                    b'(' => depth += 1,
+                    b')' if depth == 0 => {
+                        end = Some(id);
+                        break;
+                    }
+                    b')' => depth -= 1,
+                    _ => (),
+                }
+                id += 1;
+            }
+            let end = match end {
+                Some(end) => end,
+                None => break,
+            };
+
+            let variable = &self.data[start..self.read];
+            let method = &self.data[name_start..name_end];
+            let pattern = self.data[name_end + 1..end].trim();
+            self.read = end + 1;
+            let selection = if let Some(&b'[') = bytes.get(self.read) {
+                let mut iterator = self.data.bytes().skip(self.read + 1);
+                self.read_selection(&mut iterator)
+            } else {
+                Select::All
+            };
+
+            token = if array {
+                WordToken::ArrayMethod(ArrayMethod {
+                    method,
+                    variable,
+                    pattern: if pattern.is_empty() {
+                        Pattern::Whitespace
+                    } else {
+                        Pattern::StringPattern(pattern)
+                    },
+                    selection,
+                })
+            } else {
+                WordToken::StringMethod(StringMethod {
+                    method,
+                    variable,
+                    pattern: if pattern.is_empty() { " " } else { pattern },
+                    selection,
+                })
+            };
+        }
+        token
+    }
+
     /// Contains the logic for parsing array variable syntax
     fn array_variable<I>(&mut self, iterator: &mut I) -> WordToken<'a>
         where I: Iterator<Item = u8>
@@ -240,15 +307,24 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
                     let method = &self.data[start..self.read];
                     self.read += 1;
                     start = self.read;
-                    let mut depth = 0;
+                    let (mut depth, mut quotes) = (0, Quotes::new());
                     while let Some(character) = iterator.next() {
                         match character {
+                            // Commas and parentheses within quotes belong to the variable.
+                            _ if quotes.scan(character) != Class::Unquoted => (),
                             b',' if depth == 0 => {
                                 let variable = &self.data[start..self.read];
                                 self.read += 1;
                                 start = self.read;
                                 while let Some(character) = iterator.next() {
-                                    if character == b')' {
+                                    if quotes.scan(character) != Class::Unquoted {
+                                        // Quoted and escaped bytes belong to the pattern.
+                                    } else if character == b')' {
+                                        if depth != 0 {
+                                            depth -= 1;
+                                            self.read += 1;
+                                            continue;
+                                        }
                                         let pattern = &self.data[start..self.read].trim();
                                         self.read += 1;
                                         return if let Some(&b'[') =
@@ -269,6 +345,8 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
                                                 selection: Select::All,
                                             })
                                         };
+                                    } else if character == b'(' {
+                                        depth += 1;
                                     }
                                     self.read += 1;
                                 }
@@ -307,7 +385,7 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
                 b'[' => {
                     return WordToken::ArrayVariable(
                         &self.data[start..self.read],
-                        self.flags.contains(DQUOTE),
+                        self.quotes.is_double(),
                         self.read_selection(iterator),
                     )
                 }
@@ -315,7 +393,7 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
                 0...47 | 58...64 | 91...94 | 96 | 123...127 => {
                     return WordToken::ArrayVariable(
                         &self.data[start..self.read],
-                        self.flags.contains(DQUOTE),
+                        self.quotes.is_double(),
                         Select::All,
                     )
                 }
@@ -324,7 +402,7 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
             self.read += 1;
         }
 
-        WordToken::ArrayVariable(&self.data[start..], self.flags.contains(DQUOTE), Select::All)
+        WordToken::ArrayVariable(&self.data[start..], self.quotes.is_double(), Select::All)
     }
 
     fn braced_array_variable<I>(&mut self, iterator: &mut I) -> WordToken<'a>
@@ -337,7 +415,7 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
                 b'[' => {
                     let result = WordToken::ArrayVariable(
                         &self.data[start..self.read],
-                        self.flags.contains(DQUOTE),
+                        self.quotes.is_double(),
                         self.read_selection(iterator),
                     );
                     self.read += 1;
@@ -354,7 +432,7 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
                     self.read += 1;
                     return WordToken::ArrayVariable(
                         output,
-                        self.flags.contains(DQUOTE),
+                        self.quotes.is_double(),
                         Select::All,
                     );
                 }
@@ -362,7 +440,7 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
                 0...47 | 58...64 | 91...94 | 96 | 123...127 => {
                     return WordToken::ArrayVariable(
                         &self.data[start..self.read],
-                        self.flags.contains(DQUOTE),
+                        self.quotes.is_double(),
                         Select::All,
                     )
                 }
@@ -370,7 +448,7 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
             }
             self.read += 1;
         }
-        WordToken::ArrayVariable(&self.data[start..], self.flags.contains(DQUOTE), Select::All)
+        WordToken::ArrayVariable(&self.data[start..], self.quotes.is_double(), Select::All)
     }
 
     /// Contains the logic for parsing subshell syntax.
@@ -379,29 +457,28 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
     {
         let start = self.read;
         let mut level = 0;
+        // Quotes begin anew within the process, as in `"$(echo ")")"`.
+        let mut quotes = Quotes::new();
         while let Some(character) = iterator.next() {
+            let class = quotes.scan(character);
             match character {
-                _ if self.flags.contains(BACKSL) => self.flags ^= BACKSL,
-                b'\\' => self.flags ^= BACKSL,
-                b'\'' if !self.flags.contains(DQUOTE) => self.flags ^= SQUOTE,
-                b'"' if !self.flags.contains(SQUOTE) => self.flags ^= DQUOTE,
-                b'$' if !self.flags.contains(SQUOTE) => {
+                b'$' if class.expands() => {
                     if self.data.as_bytes()[self.read + 1] == b'(' {
                         level += 1;
                     }
                 }
-                b')' if !self.flags.contains(SQUOTE) => if level == 0 {
+                b')' if class == Class::Unquoted => if level == 0 {
                     let output = &self.data[start..self.read];
                     self.read += 1;
                     return if let Some(&b'[') = self.data.as_bytes().get(self.read) {
                         let _ = iterator.next();
                         WordToken::Process(
                             output,
-                            self.flags.contains(DQUOTE),
+                            self.quotes.is_double(),
                             self.read_selection(iterator),
                         )
                     } else {
-                        WordToken::Process(output, self.flags.contains(DQUOTE), Select::All)
+                        WordToken::Process(output, self.quotes.is_double(), Select::All)
                     };
                 } else {
                     level -= 1;
@@ -421,31 +498,29 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
     {
         let start = self.read;
         let mut level = 0;
+        let mut quotes = Quotes::new();
         while let Some(character) = iterator.next() {
+            let class = quotes.scan(character);
             match character {
-                _ if self.flags.contains(BACKSL) => self.flags ^= BACKSL,
-                b'\\' => self.flags ^= BACKSL,
-                b'\'' if !self.flags.contains(DQUOTE) => self.flags ^= SQUOTE,
-                b'"' if !self.flags.contains(SQUOTE) => self.flags ^= DQUOTE,
-                b'@' if !self.flags.contains(SQUOTE) => {
+                b'@' if class.expands() => {
                     if self.data.as_bytes()[self.read + 1] == b'(' {
                         level += 1;
                     }
                 }
-                b')' if !self.flags.contains(SQUOTE) => if level == 0 {
+                b')' if class == Class::Unquoted => if level == 0 {
                     let array_process_contents = &self.data[start..self.read];
                     self.read += 1;
                     return if let Some(&b'[') = self.data.as_bytes().get(self.read) {
                         let _ = iterator.next();
                         WordToken::ArrayProcess(
                             array_process_contents,
-                            self.flags.contains(DQUOTE),
+                            self.quotes.is_double(),
                             self.read_selection(iterator),
                         )
                     } else {
                         WordToken::ArrayProcess(
                             array_process_contents,
-                            self.flags.contains(DQUOTE),
+                            self.quotes.is_double(),
                             Select::All,
                         )
                     };
@@ -468,18 +543,16 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
         let mut start = self.read;
         let mut level = 0;
         let mut elements = Vec::new();
+        let mut quotes = Quotes::new();
         while let Some(character) = iterator.next() {
             match character {
-                _ if self.flags.contains(BACKSL) => self.flags ^= BACKSL,
-                b'\\' => self.flags ^= BACKSL,
-                b'\'' if !self.flags.contains(DQUOTE) => self.flags ^= SQUOTE,
-                b'"' if !self.flags.contains(SQUOTE) => self.flags ^= DQUOTE,
-                b',' if !self.flags.intersects(SQUOTE | DQUOTE) && level == 0 => {
+                _ if quotes.scan(character) != Class::Unquoted => (),
+                b',' if level == 0 => {
                     elements.push(&self.data[start..self.read]);
                     start = self.read + 1;
                 }
-                b'{' if !self.flags.intersects(SQUOTE | DQUOTE) => level += 1,
-                b'}' if !self.flags.intersects(SQUOTE | DQUOTE) => if level == 0 {
+                b'{' => level += 1,
+                b'}' => if level == 0 {
                     elements.push(&self.data[start..self.read]);
                     self.read += 1;
                     return WordToken::Brace(elements);
@@ -500,14 +573,12 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
     {
         let start = self.read;
         let mut level = 0;
+        let mut quotes = Quotes::new();
         while let Some(character) = iterator.next() {
             match character {
-                _ if self.flags.contains(BACKSL) => self.flags ^= BACKSL,
-                b'\\' => self.flags ^= BACKSL,
-                b'\'' if !self.flags.contains(DQUOTE) => self.flags ^= SQUOTE,
-                b'"' if !self.flags.contains(SQUOTE) => self.flags ^= DQUOTE,
-                b'[' if !self.flags.intersects(SQUOTE | DQUOTE) => level += 1,
-                b']' if !self.flags.intersects(SQUOTE | DQUOTE) => if level == 0 {
+                _ if quotes.scan(character) != Class::Unquoted => (),
+                b'[' => level += 1,
+                b']' => if level == 0 {
                     let elements =
                         ArgumentSplitter::new(&self.data[start..self.read]).collect::<Vec<&str>>();
                     self.read += 1;
@@ -594,7 +665,7 @@ impl<'a, E: Expander + 'a> WordIterator<'a, E> {
     }
 }
 
-impl<'a, E: Expander + 'a> Iterator for WordIterator<'a, E> {
+impl<'a, 'e, E: Expander + 'e> Iterator for WordIterator<'a, 'e, E> {
     type Item = WordToken<'a>;
 
     fn next(&mut self) -> Option<WordToken<'a>> {
@@ -608,63 +679,57 @@ impl<'a, E: Expander + 'a> Iterator for WordIterator<'a, E> {
         let mut tilde = false;
         loop {
             if let Some(character) = iterator.next() {
+                let class = self.quotes.scan(character);
                 match character {
-                    _ if self.flags.contains(BACKSL) => {
+                    _ if class == Class::Escaped => {
                         self.read += 1;
-                        self.flags ^= BACKSL;
                         break;
                     }
-                    b'\\' => {
-                        if !self.flags.intersects(DQUOTE | SQUOTE) {
+                    _ if class == Class::Escape => {
+                        if !self.quotes.is_quoted() {
                             start += 1;
                         }
                         self.read += 1;
-                        self.flags ^= BACKSL;
                         break;
                     }
-                    b'\'' if !self.flags.contains(DQUOTE) => {
-                        start += 1;
+                    b'"' if class == Class::Close => {
                         self.read += 1;
-                        self.flags ^= SQUOTE;
-                        break;
+                        return self.next();
                     }
-                    b'"' if !self.flags.contains(SQUOTE) => {
+                    _ if class == Class::Open || class == Class::Close => {
                         start += 1;
                         self.read += 1;
-                        if self.flags.contains(DQUOTE) {
-                            self.flags -= DQUOTE;
-                            return self.next();
-                        }
-                        self.flags |= DQUOTE;
                         break;
                     }
-                    b' ' if !self.flags.intersects(DQUOTE | SQUOTE) => {
+                    b' ' if class == Class::Unquoted => {
                         return Some(self.whitespaces(&mut iterator))
                     }
-                    b'~' if !self.flags.intersects(DQUOTE | SQUOTE) => {
+                    b'~' if class == Class::Unquoted => {
                         tilde = true;
                         self.read += 1;
                         break;
                     }
-                    b'{' if !self.flags.intersects(DQUOTE | SQUOTE) => {
+                    b'{' if class == Class::Unquoted => {
                         self.read += 1;
                         return Some(self.braces(&mut iterator));
                     }
-                    b'[' if !self.flags.intersects(SQUOTE | DQUOTE) => {
+                    b'[' if class == Class::Unquoted => {
                         if self.glob_check(&mut iterator) {
                             glob = true;
                         } else {
                             return Some(self.array(&mut iterator));
                         }
                     }
-                    b'@' if !self.flags.contains(SQUOTE) => match iterator.next() {
+                    b'@' if class.expands() => match iterator.next() {
                         Some(b'(') => {
                             self.read += 2;
-                            return Some(self.array_process(&mut iterator));
+                            let token = self.array_process(&mut iterator);
+                            return Some(self.method_chain(start, token, true));
                         }
                         Some(b'{') => {
                             self.read += 2;
-                            return Some(self.braced_array_variable(&mut iterator));
+                            let token = self.braced_array_variable(&mut iterator);
+                            return Some(self.method_chain(start, token, true));
                         }
                         Some(b' ') | None => {
                             self.read += 1;
@@ -673,10 +738,11 @@ impl<'a, E: Expander + 'a> Iterator for WordIterator<'a, E> {
                         }
                         _ => {
                             self.read += 1;
-                            return Some(self.array_variable(&mut iterator));
+                            let token = self.array_variable(&mut iterator);
+                            return Some(self.method_chain(start, token, true));
                         }
                     },
-                    b'$' if !self.flags.contains(SQUOTE) => {
+                    b'$' if class.expands() => {
                         match iterator.next() {
                             Some(b'(') => {
                                 self.read += 2;
@@ -686,12 +752,14 @@ impl<'a, E: Expander + 'a> Iterator for WordIterator<'a, E> {
                                     self.read += 1;
                                     Some(self.arithmetic_expression(&mut iterator))
                                 } else {
-                                    Some(self.process(&mut iterator))
+                                    let token = self.process(&mut iterator);
+                                    Some(self.method_chain(start, token, false))
                                 };
                             }
                             Some(b'{') => {
                                 self.read += 2;
-                                return Some(self.braced_variable(&mut iterator));
+                                let token = self.braced_variable(&mut iterator);
+                                return Some(self.method_chain(start, token, false));
                             }
                             Some(b' ') | None => {
                                 self.read += 1;
@@ -700,11 +768,12 @@ impl<'a, E: Expander + 'a> Iterator for WordIterator<'a, E> {
                             }
                             _ => {
                                 self.read += 1;
-                                return Some(self.variable(&mut iterator));
+                                let token = self.variable(&mut iterator);
+                                return Some(self.method_chain(start, token, false));
                             }
                         }
                     }
-                    b'*' | b'?' => {
+                    b'*' | b'?' if class.expands() => {
                         self.read += 1;
                         glob = true;
                         break;
@@ -720,35 +789,24 @@ impl<'a, E: Expander + 'a> Iterator for WordIterator<'a, E> {
         }
 
         while let Some(character) = iterator.next() {
+            let class = self.quotes.scan(character);
             match character {
-                _ if self.flags.contains(BACKSL) => self.flags ^= BACKSL,
-                b'\\' => {
-                    self.flags ^= BACKSL;
-                    let end = if self.flags.intersects(DQUOTE | SQUOTE) {
-                        self.read + 1
-                    } else {
-                        self.read
-                    };
+                _ if class == Class::Escaped => (),
+                _ if class == Class::Escape => {
+                    let end = if self.quotes.is_quoted() { self.read + 1 } else { self.read };
                     let output = &self.data[start..end];
                     self.read += 1;
                     return Some(WordToken::Normal(output, glob, tilde));
                 }
-                b'\'' if !self.flags.contains(DQUOTE) => {
-                    self.flags ^= SQUOTE;
-                    let output = &self.data[start..self.read];
-                    self.read += 1;
-                    return Some(WordToken::Normal(output, glob, tilde));
-                }
-                b'"' if !self.flags.contains(SQUOTE) => {
-                    self.flags ^= DQUOTE;
+                _ if class == Class::Open || class == Class::Close => {
                     let output = &self.data[start..self.read];
                     self.read += 1;
                     return Some(WordToken::Normal(output, glob, tilde));
                 }
-                b' ' | b'{' if !self.flags.intersects(SQUOTE | DQUOTE) => {
+                b' ' | b'{' if class == Class::Unquoted => {
                     return Some(WordToken::Normal(&self.data[start..self.read], glob, tilde))
                 }
-                b'$' | b'@' if !self.flags.contains(SQUOTE) => {
+                b'$' | b'@' if class.expands() => {
                     if let Some(&character) = self.data.as_bytes().get(self.read) {
                         if character == b' ' {
                             self.read += 1;
@@ -763,17 +821,17 @@ impl<'a, E: Expander + 'a> Iterator for WordIterator<'a, E> {
                         return self.next();
                     };
                 }
-                b'[' if !self.flags.intersects(SQUOTE | DQUOTE) => {
+                b'[' if class == Class::Unquoted => {
                     if self.glob_check(&mut iterator) {
                         glob = true;
                     } else {
                         return Some(WordToken::Normal(&self.data[start..self.read], glob, tilde));
                     }
                 }
-                b'*' | b'?' if !self.flags.contains(SQUOTE) => {
+                b'*' | b'?' if class.expands() => {
                     glob = true;
                 }
-                b'~' if !self.flags.intersects(SQUOTE | DQUOTE) => {
+                b'~' if class == Class::Unquoted => {
                     let output = &self.data[start..self.read];
                     if output != "" {
                         return Some(WordToken::Normal(output, glob, tilde));
@@ -10,6 +10,8 @@ pub(crate) struct Range {
     /// Is this range inclusive? If false, this object represents a half-open
     /// range of [start, end), otherwise [start, end]
     inclusive: bool,
+    /// Distance between each selected element, where `1` selects every element
+    step: usize,
 }
 
 impl Range {
@@ -18,6 +20,7 @@ impl Range {
             start: Index::new(0),
             end,
             inclusive: false,
+            step: 1,
         }
     }
 
@@ -26,6 +29,7 @@ impl Range {
             start,
             end: Index::new(-1),
             inclusive: true,
+            step: 1,
         }
     }
 
@@ -34,6 +38,7 @@ impl Range {
             start,
             end,
             inclusive: true,
+            step: 1,
         }
     }
 
@@ -42,9 +47,36 @@ impl Range {
             start,
             end,
             inclusive: false,
+            step: 1,
         }
     }
 
+    /// Selects only every `step`th element of this range, beginning with the first.
+    pub(crate) fn with_step(mut self, step: usize) -> Range {
+        self.step = step;
+        self
+    }
+
+    pub(crate) fn step(&self) -> usize { self.step }
+
+    /// Selects the elements of this range from an iterator which yields `vector_length`
+    /// elements, or returns `None` if the range lies outside of those elements.
+    pub(crate) fn elements<I: Iterator>(
+        &self,
+        iterator: I,
+        vector_length: usize,
+    ) -> Option<impl Iterator<Item = I::Item>> {
+        let step = self.step;
+        self.bounds(vector_length).map(move |(start, length)| {
+            iterator
+                .skip(start)
+                .take(length)
+                .enumerate()
+                .filter(move |&(id, _)| id % step == 0)
+                .map(|(_, element)| element)
+        })
+    }
+
     /// Returns the bounds of this range as a tuple containing:
     /// - The starting point of the range
     /// - The length of the range
@@ -39,8 +39,8 @@ impl<I, T> SelectWithSize for I
             Select::Index(idx) => {
                 idx.resolve(size).and_then(|idx| self.nth(idx)).into_iter().collect()
             }
-            Select::Range(range) => if let Some((start, length)) = range.bounds(size) {
-                self.skip(start).take(length).collect()
+            Select::Range(range) => if let Some(elements) = range.elements(self, size) {
+                elements.collect()
             } else {
                 empty().collect()
             },
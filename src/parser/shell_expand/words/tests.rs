@@ -1,4 +1,5 @@
 use super::*;
+use std::env;
 use types::{Array, Value};
 
 struct Empty;
@@ -7,7 +8,7 @@ impl Expander for Empty {}
 
 fn compare(input: &str, expected: Vec<WordToken>) {
     let mut correct = 0;
-    for (actual, expected) in WordIterator::new(input, &Empty).zip(expected.iter()) {
+    for (actual, expected) in WordIterator::new(input, &mut Empty).zip(expected.iter()) {
         assert_eq!(actual, *expected, "{:?} != {:?}", actual, expected);
         correct += 1;
     }
@@ -163,6 +164,34 @@ fn words_process_with_quotes() {
         WordToken::Process("git branch | rg \"[*]\" | awk '{print $2}'", false, Select::All),
     ];
     compare(input, expected);
+
+    let input = "echo \"$(echo \")\")\" $join(@a, ')')";
+    let expected = vec![
+        WordToken::Normal("echo", false, false),
+        WordToken::Whitespace(" "),
+        WordToken::Process("echo \")\"", true, Select::All),
+        WordToken::Whitespace(" "),
+        WordToken::StringMethod(StringMethod {
+            method:    "join",
+            variable:  "@a",
+            pattern:   "')'",
+            selection: Select::All,
+        }),
+    ];
+    compare(input, expected);
+}
+
+#[test]
+fn backslashes_within_single_quotes() {
+    let input = r"echo 'a\' b";
+    let expected = vec![
+        WordToken::Normal("echo", false, false),
+        WordToken::Whitespace(" "),
+        WordToken::Normal(r"a\", false, false),
+        WordToken::Whitespace(" "),
+        WordToken::Normal("b", false, false),
+    ];
+    compare(input, expected);
 }
 
 #[test]
@@ -244,10 +273,11 @@ fn test_empty_strings() {
 struct WithVars;
 
 impl Expander for WithVars {
-    fn variable(&self, var: &str, _: bool) -> Option<Value> {
+    fn variable(&mut self, var: &str, _: bool) -> Option<Value> {
         match var {
             "pkmn1" => "Pokémon".to_owned().into(),
             "pkmn2" => "Poke\u{0301}mon".to_owned().into(),
+            "HOME" => "/home/ion".to_owned().into(),
             _ => None,
         }
     }
@@ -255,7 +285,7 @@ impl Expander for WithVars {
 
 #[test]
 fn array_methods() {
-    let expanders = WithVars;
+    let mut expanders = WithVars;
     let method = ArrayMethod {
         method:    "graphemes",
         variable:  "pkmn1",
@@ -263,7 +293,7 @@ fn array_methods() {
         selection: Select::Index(Index::Forward(3)),
     };
     let expected = array!["é"];
-    assert_eq!(method.handle_as_array(&expanders), expected);
+    assert_eq!(method.handle_as_array(&mut expanders), expected);
     let method = ArrayMethod {
         method:    "chars",
         variable:  "pkmn2",
@@ -271,7 +301,7 @@ fn array_methods() {
         selection: Select::Index(Index::Forward(3)),
     };
     let expected = array!["e"];
-    assert_eq!(method.handle_as_array(&expanders), expected);
+    assert_eq!(method.handle_as_array(&mut expanders), expected);
     let method = ArrayMethod {
         method:    "bytes",
         variable:  "pkmn2",
@@ -279,5 +309,469 @@ fn array_methods() {
         selection: Select::Index(Index::Forward(1)),
     };
     let expected = array!["111"];
-    assert_eq!(method.handle_as_array(&expanders), expected);
+    assert_eq!(method.handle_as_array(&mut expanders), expected);
+}
+
+#[test]
+fn env_method() {
+    env::set_var("ION_TEST_ENV_METHOD_B", "1");
+    env::set_var("ION_TEST_ENV_METHOD_A", "1");
+    let method = ArrayMethod {
+        method:    "env",
+        variable:  "ION_TEST_ENV_METHOD_*",
+        pattern:   Pattern::Whitespace,
+        selection: Select::All,
+    };
+    let expected = array!["ION_TEST_ENV_METHOD_A", "ION_TEST_ENV_METHOD_B"];
+    assert_eq!(method.handle_as_array(&mut WithVars), expected);
+}
+
+#[test]
+fn array_method_with_nested_parentheses() {
+    let input = r"@regex_capture(input, '(\d+)-(\d)\)')";
+    let expected = vec![WordToken::ArrayMethod(ArrayMethod {
+        method:    "regex_capture",
+        variable:  "input",
+        pattern:   Pattern::StringPattern(r"'(\d+)-(\d)\)'"),
+        selection: Select::All,
+    })];
+    compare(input, expected);
+}
+
+#[test]
+fn regex_methods() {
+    use super::methods::{regex_captures, regex_replacement};
+    use regex::Regex;
+
+    assert_eq!(regex_replacement(r"\2-\1 \$1 $1 \n"), "${2}-${1} $$1 $1 \n");
+    let re = Regex::new(r"(\w+)@(\w+)?").unwrap();
+    assert_eq!(regex_captures(&re, "mail user@ here"), array!["user", ""]);
+    let re = Regex::new(r"\d+").unwrap();
+    assert_eq!(regex_captures(&re, "abc 123 456"), array!["123"]);
+    assert_eq!(regex_captures(&re, "abc"), Array::new());
+}
+
+#[test]
+fn trim_and_pad_methods() {
+    let cases = [
+        ("trim", "\"  ab  \"", " "),
+        ("trim", "\"--ab-\"", "'-'"),
+        ("trim_left", "\"  ab  \"", " "),
+        ("trim_right", "\"  ab  \"", " "),
+        ("pad_left", "\"ab\"", "5 '-'"),
+        ("pad_right", "\"ab\"", "5"),
+        ("pad", "\"ab\"", "5 '*'"),
+        ("pad", "\"abcdef\"", "5"),
+    ];
+    let expected = ["ab", "ab", "ab  ", "  ab", "---ab", "ab   ", "*ab**", "abcdef"];
+    for (&(method, variable, pattern), expected) in cases.iter().zip(expected.iter()) {
+        let method = StringMethod {
+            method,
+            variable,
+            pattern,
+            selection: Select::All,
+        };
+        let mut output = String::new();
+        method.handle(&mut output, &mut WithVars);
+        assert_eq!(&output, expected);
+    }
+}
+
+#[test]
+fn format_method() {
+    let input = r#"$format("{0}, {name:>6}", pkmn1, name="a, b")"#;
+    let method = StringMethod {
+        method:    "format",
+        variable:  r#""{0}, {name:>6}""#,
+        pattern:   r#"pkmn1, name="a, b""#,
+        selection: Select::All,
+    };
+    compare(input, vec![WordToken::StringMethod(method.clone())]);
+
+    let mut output = String::new();
+    method.handle(&mut output, &mut WithVars);
+    assert_eq!(output, "Pokémon,   a, b");
+}
+
+#[test]
+fn search_methods() {
+    let cases = [
+        ("count", "\"one two one\"", "one", "2"),
+        ("find", "\"one two\"", "two", "4"),
+        ("find", "\"one two\"", "three", "-1"),
+        ("contains", "pkmn1", "kém", "1"),
+        ("starts_with", "pkmn1", "Pok", "1"),
+        ("ends_with", "pkmn1", "Pok", "0"),
+    ];
+    for &(method, variable, pattern, expected) in cases.iter() {
+        let method = StringMethod {
+            method,
+            variable,
+            pattern,
+            selection: Select::All,
+        };
+        let mut output = String::new();
+        method.handle(&mut output, &mut WithVars);
+        assert_eq!(output, expected);
+    }
+
+    assert!(is_boolean_method("$contains($line, error)"));
+    assert!(is_boolean_method("$is_upper(name)"));
+    assert!(!is_boolean_method("$count($line, error)"));
+    assert!(!is_boolean_method("$contains($line, error)[0]"));
+}
+
+#[test]
+fn truncate_method() {
+    let cases = [
+        ("5", "Pokémon", "Pokém"),
+        ("5 …", "Pokémon", "Poké…"),
+        ("5, '…'", "Pokémon", "Poké…"),
+        ("7 …", "Pokémon", "Pokémon"),
+        ("2 ...", "Pokémon", ".."),
+    ];
+    for &(pattern, value, expected) in cases.iter() {
+        let variable = format!("\"{}\"", value);
+        let method = StringMethod {
+            method:    "truncate",
+            variable:  &variable,
+            pattern,
+            selection: Select::All,
+        };
+        let mut output = String::new();
+        method.handle(&mut output, &mut WithVars);
+        assert_eq!(output, expected);
+    }
+}
+
+#[test]
+fn path_shortening_methods() {
+    let cases = [
+        ("tilde", "/home/ion/src", "", "~/src"),
+        ("shorten_path", "/home/ion/src/ion/src", "", "~/s/i/src"),
+        ("shorten_path", "/home/ion/src/ion/src", "11", "~/s/ion/src"),
+        ("shorten_path", "/home/ion/src", "wide", ""),
+    ];
+    for &(method, value, pattern, expected) in cases.iter() {
+        let variable = format!("\"{}\"", value);
+        let method = StringMethod {
+            method,
+            variable: &variable,
+            pattern,
+            selection: Select::All,
+        };
+        let mut output = String::new();
+        method.handle(&mut output, &mut WithVars);
+        assert_eq!(output, expected);
+    }
+}
+
+#[test]
+fn sorting_methods() {
+    let sort = |method, variable, pattern| {
+        let method = ArrayMethod {
+            method,
+            variable,
+            pattern,
+            selection: Select::All,
+        };
+        method.handle_as_array(&mut WithVars)
+    };
+    let fruits = "[pear apple fig]";
+    let numeric = Pattern::StringPattern("numeric");
+    let by_length = Pattern::StringPattern("$len($it)");
+
+    assert_eq!(sort("sort", fruits, Pattern::Whitespace), array!["apple", "fig", "pear"]);
+    assert_eq!(sort("sort", "[10 9 b 1.5 a]", numeric), array!["1.5", "9", "10", "a", "b"]);
+    assert_eq!(sort("sort_by", fruits, by_length), array!["fig", "pear", "apple"]);
+    assert_eq!(sort("unique", "[a b a c b]", Pattern::Whitespace), array!["a", "b", "c"]);
+    assert_eq!(sort("reverse", "[a b c]", Pattern::Whitespace), array!["c", "b", "a"]);
+}
+
+#[test]
+fn map_and_filter_methods() {
+    let apply = |method, pattern| {
+        let method = ArrayMethod {
+            method,
+            variable:  "[a.rs b.txt c.rs]",
+            pattern:   Pattern::StringPattern(pattern),
+            selection: Select::All,
+        };
+        method.handle_as_array(&mut WithVars)
+    };
+
+    assert_eq!(apply("map", "'$to_upper($it)'"), array!["A.RS", "B.TXT", "C.RS"]);
+    assert_eq!(apply("map", "$len($it)"), array!["4", "5", "4"]);
+    assert_eq!(apply("filter", "'$ends_with($it, .rs)'"), array!["a.rs", "c.rs"]);
+    assert_eq!(apply("filter", "undefined_function"), Array::new());
+}
+
+#[test]
+fn structural_methods() {
+    let apply = |method, variable, pattern| {
+        let method = ArrayMethod {
+            method,
+            variable,
+            pattern,
+            selection: Select::All,
+        };
+        method.handle_as_array(&mut WithVars)
+    };
+    let letters = "[a b c d e]";
+    let numbers = Pattern::StringPattern("[1 2 3]");
+
+    assert_eq!(apply("zip", letters, numbers), array!["a", "1", "b", "2", "c", "3"]);
+    assert_eq!(apply("enumerate", "[a b]", Pattern::Whitespace), array!["0", "a", "1", "b"]);
+    assert_eq!(apply("chunks", letters, Pattern::StringPattern("2")), array!["a b", "c d", "e"]);
+    assert_eq!(apply("chunks", letters, Pattern::StringPattern("0")), Array::new());
+}
+
+#[test]
+fn aggregate_methods() {
+    let apply = |method, variable| {
+        let method = ArrayMethod {
+            method,
+            variable,
+            pattern:   Pattern::Whitespace,
+            selection: Select::All,
+        };
+        method.handle_as_array(&mut WithVars)
+    };
+
+    assert_eq!(apply("sum", "[1 2 3]"), array!["6"]);
+    assert_eq!(apply("sum", "[1 2.5]"), array!["3.5"]);
+    assert_eq!(apply("min", "[3 1.50 2]"), array!["1.50"]);
+    assert_eq!(apply("max", "[3 -7 2]"), array!["3"]);
+    assert_eq!(apply("mean", "[1 2 3 4]"), array!["2.5"]);
+    assert_eq!(apply("mean", "[]"), Array::new());
+    assert_eq!(apply("sum", "[1 two]"), Array::new());
+    assert_eq!(apply("sum", "[9223372036854775807 1]"), Array::new());
+    assert_eq!(
+        apply("max", "[9007199254740993 9007199254740992]"),
+        array!["9007199254740993"]
+    );
+    assert_eq!(
+        apply("min", "[9007199254740993 9007199254740992]"),
+        array!["9007199254740992"]
+    );
+}
+
+#[test]
+fn join_and_split_multi_methods() {
+    let cases = [
+        ("', '", "a, b, c"),
+        ("', ', '<{}>'", "<a>, <b>, <c>"),
+        ("'', '{:>2}'", " a b c"),
+    ];
+    for &(pattern, expected) in cases.iter() {
+        let method = StringMethod {
+            method:    "join",
+            variable:  "[a b c]",
+            pattern,
+            selection: Select::All,
+        };
+        let mut output = String::new();
+        method.handle(&mut output, &mut WithVars);
+        assert_eq!(output, expected);
+    }
+
+    let split = |pattern| {
+        let method = ArrayMethod {
+            method:    "split_multi",
+            variable:  "\"a, b;c ; d\"",
+            pattern:   Pattern::StringPattern(pattern),
+            selection: Select::All,
+        };
+        method.handle_as_array(&mut WithVars)
+    };
+    assert_eq!(split("',' ';'"), array!["a", " b", "c ", " d"]);
+    assert_eq!(split("', ' ',' ';'"), array!["a", "b", "c ", " d"]);
+    assert_eq!(split(r"'/\s*[,;]\s*/'"), array!["a", "b", "c", "d"]);
+}
+
+#[test]
+fn set_methods() {
+    let apply = |method| {
+        let method = ArrayMethod {
+            method,
+            variable:  "[a b a c d]",
+            pattern:   Pattern::StringPattern("[b a e b]"),
+            selection: Select::All,
+        };
+        method.handle_as_array(&mut WithVars)
+    };
+
+    assert_eq!(apply("union"), array!["a", "b", "a", "c", "d", "b", "e"]);
+    assert_eq!(apply("intersect"), array!["a", "b"]);
+    assert_eq!(apply("difference"), array!["a", "c", "d"]);
+}
+
+#[test]
+fn method_chains() {
+    let input = "$var.to_lower().replace(a, b)[..2] @array.reverse() $file.txt";
+    let expected = vec![
+        WordToken::StringMethod(StringMethod {
+            method:    "replace",
+            variable:  "$var.to_lower()",
+            pattern:   "a, b",
+            selection: Select::Range(Range::to(Index::new(2))),
+        }),
+        WordToken::Whitespace(" "),
+        WordToken::ArrayMethod(ArrayMethod {
+            method:    "reverse",
+            variable:  "@array",
+            pattern:   Pattern::Whitespace,
+            selection: Select::All,
+        }),
+        WordToken::Whitespace(" "),
+        WordToken::Variable("file", false, Select::All),
+        WordToken::Normal(".txt", false, false),
+    ];
+    compare(input, expected);
+
+    let method = StringMethod {
+        method:    "to_upper",
+        variable:  "$trim(\" $pkmn1 \").replace('é', e)",
+        pattern:   " ",
+        selection: Select::All,
+    };
+    let mut output = String::new();
+    method.handle(&mut output, &mut WithVars);
+    assert_eq!(output, "POKEMON");
+}
+
+struct WithFunctions;
+
+impl Expander for WithFunctions {
+    fn has_function(&self, name: &str) -> bool { name == "shout" }
+
+    fn method(&mut self, name: &str, arguments: &[&str]) -> Option<Value> {
+        match name {
+            "shout" => Some(format!("{}{}", arguments[0].to_uppercase(), arguments[1..].concat())),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn function_methods() {
+    let method = StringMethod {
+        method:    "shout",
+        variable:  "\"hello\"",
+        pattern:   "'!', '?'",
+        selection: Select::All,
+    };
+    let mut output = String::new();
+    method.handle(&mut output, &mut WithFunctions);
+    assert_eq!(output, "HELLO!?");
+
+    let method = ArrayMethod {
+        method:    "shout",
+        variable:  "[one two three]",
+        pattern:   Pattern::Whitespace,
+        selection: Select::Index(Index::Forward(1)),
+    };
+    assert_eq!(method.handle_as_array(&mut WithFunctions), array!["TWO"]);
+}
+
+#[test]
+fn logical_methods() {
+    let cases = [
+        ("and", "true", "1, y", "true"),
+        ("and", "true", "1 false", "false"),
+        ("or", "false", "0, n", "false"),
+        ("or", "0", "n, true", "true"),
+        ("not", "y", " ", "false"),
+        ("not", "false", " ", "true"),
+        ("and", "maybe", "true", ""),
+        ("not", "true", "false", ""),
+    ];
+    for &(method, variable, pattern, expected) in cases.iter() {
+        let method = StringMethod {
+            method,
+            variable,
+            pattern,
+            selection: Select::All,
+        };
+        let mut output = String::new();
+        method.handle(&mut output, &mut WithVars);
+        assert_eq!(output, expected);
+    }
+    assert!(is_boolean_method("$and($a, $b)"));
+    assert!(is_boolean_method("$not($a)"));
+}
+
+#[test]
+fn select_method() {
+    let cases = [
+        ("true", "yes, no", "yes"),
+        ("0", "yes, no", "no"),
+        ("$not(y)", "'one, two', \"three four\"", "three four"),
+        ("$pkmn1", "yes, no", ""),
+        ("y", "yes", ""),
+    ];
+    for &(variable, pattern, expected) in cases.iter() {
+        let method = StringMethod {
+            method: "select",
+            variable,
+            pattern,
+            selection: Select::All,
+        };
+        let mut output = String::new();
+        method.handle(&mut output, &mut WithVars);
+        assert_eq!(output, expected);
+    }
+}
+
+#[test]
+fn base_methods() {
+    let cases = [
+        ("to_base", "255", "16", "ff"),
+        ("to_base", "0o755", "2", "111101101"),
+        ("to_base", "-35", "36", "-z"),
+        ("from_base", "ff", "16", "255"),
+        ("from_base", "0b1010", "2", "10"),
+        ("from_base", "755", "8", "493"),
+        ("to_base", "ten", "2", ""),
+        ("to_base", "10", "37", ""),
+        ("from_base", "12", "2", ""),
+        ("from_base", "12", " ", ""),
+    ];
+    for &(method, variable, pattern, expected) in cases.iter() {
+        let method = StringMethod {
+            method,
+            variable,
+            pattern,
+            selection: Select::All,
+        };
+        let mut output = String::new();
+        method.handle(&mut output, &mut WithVars);
+        assert_eq!(output, expected);
+    }
+}
+
+#[test]
+fn unit_methods() {
+    let cases = [
+        ("to_unit", "2MiB", "KiB", "2048"),
+        ("to_unit", "1536", "Ki", "1.5"),
+        ("to_unit", "90s", "min", "1.5"),
+        ("from_unit", "250ms", "", "0.25"),
+        ("from_unit", "1.5e3", "", "1500"),
+        ("from_unit", "4K", "", "4000"),
+        ("to_unit", "1h", "MB", ""),
+        ("to_unit", "1h", "", ""),
+        ("from_unit", "4X", "", ""),
+    ];
+    for &(method, variable, pattern, expected) in cases.iter() {
+        let method = StringMethod {
+            method,
+            variable,
+            pattern,
+            selection: Select::All,
+        };
+        let mut output = String::new();
+        method.handle(&mut output, &mut WithVars);
+        assert_eq!(output, expected);
+    }
 }
@@ -0,0 +1,90 @@
+/// Rewrites each backtick command substitution within the given command into the equivalent
+/// `$(...)` form, so that the rest of the parser does not need to be aware of backticks.
+/// Backticks within single quotes or comments, and escaped backticks, are left as they are.
+/// Returns `None` if the command did not contain any backtick substitutions.
+pub(crate) fn replace_backticks(command: &str) -> Option<String> {
+    if !command.contains('`') {
+        return None;
+    }
+
+    let mut output = String::with_capacity(command.len() + 8);
+    let mut replaced = false;
+    let mut single_quote = false;
+    let mut double_quote = false;
+    let mut chars = command.chars().peekable();
+    let mut previous = ' ';
+
+    while let Some(character) = chars.next() {
+        match character {
+            '\\' if !single_quote => {
+                output.push(character);
+                if let Some(escaped) = chars.next() {
+                    output.push(escaped);
+                }
+                previous = '\\';
+                continue;
+            }
+            '\'' if !double_quote => single_quote = !single_quote,
+            '"' if !single_quote => double_quote = !double_quote,
+            '#' if !single_quote && !double_quote && previous.is_whitespace() => {
+                output.push(character);
+                output.extend(chars.by_ref());
+                break;
+            }
+            '`' if !single_quote => {
+                // Within backticks, nested backticks must be escaped.
+                let mut inner = String::new();
+                while let Some(character) = chars.next() {
+                    match character {
+                        '`' => break,
+                        '\\' if chars.peek() == Some(&'`') => inner.push(chars.next().unwrap()),
+                        _ => inner.push(character),
+                    }
+                }
+                output.push_str("$(");
+                match replace_backticks(&inner) {
+                    Some(inner) => output.push_str(&inner),
+                    None => output.push_str(&inner),
+                }
+                output.push(')');
+                replaced = true;
+                previous = ')';
+                continue;
+            }
+            _ => (),
+        }
+        output.push(character);
+        previous = character;
+    }
+
+    if replaced {
+        Some(output)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backticks() {
+        assert_eq!(replace_backticks("echo $(date)"), None);
+        assert_eq!(replace_backticks("echo `date`"), Some("echo $(date)".to_owned()));
+        assert_eq!(
+            replace_backticks("echo \"`echo a; echo b`\" `pwd`"),
+            Some("echo \"$(echo a; echo b)\" $(pwd)".to_owned())
+        );
+        assert_eq!(
+            replace_backticks("echo `echo \\`date\\``"),
+            Some("echo $(echo $(date))".to_owned())
+        );
+        assert_eq!(replace_backticks("echo '`date`' \\`"), None);
+        assert_eq!(replace_backticks("echo a # `date`"), None);
+        assert_eq!(
+            replace_backticks("echo \"it's `date`\""),
+            Some("echo \"it's $(date)\"".to_owned())
+        );
+    }
+}
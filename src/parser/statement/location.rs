@@ -0,0 +1,103 @@
+//! Locations within scripts, by which errors are reported as `script.ion:42:7: error: ...`,
+//! followed by the offending line and a caret beneath its column.
+
+use shell::errors::{ErrorKind, IonError};
+use std::fmt::{self, Display, Formatter};
+
+/// The script which is being executed, and the line on which the current command begins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub script: String,
+    /// Lines are counted from one.
+    pub line:   usize,
+    /// The column of the character at which a syntax error occurred, counting from one.
+    pub column: Option<usize>,
+}
+
+impl Location {
+    pub(crate) fn new<S: Into<String>>(script: S, line: usize) -> Location {
+        Location {
+            script: script.into(),
+            line,
+            column: None,
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "{}:{}:{}", self.script, self.line, column),
+            None => write!(f, "{}:{}", self.script, self.line),
+        }
+    }
+}
+
+/// Describes a syntax error at the given byte offset within a command, along with the line of
+/// the command which contains it, and a caret beneath the offending character.
+pub(crate) fn syntax_error(
+    location: Option<&Location>,
+    command: &str,
+    offset: Option<usize>,
+    message: &str,
+) -> IonError {
+    let offset = offset.map(|offset| offset.min(command.len()));
+    let offset = offset.and_then(|offset| {
+        if command.is_char_boundary(offset) { Some(offset) } else { None }
+    });
+    let mut error = IonError::new(ErrorKind::Syntax, message);
+    error.location = location.map(|location| match offset {
+        Some(offset) => Location {
+            script: location.script.clone(),
+            line:   location.line + command[..offset].matches('\n').count(),
+            column: Some(column(command, offset)),
+        },
+        None => location.clone(),
+    });
+    error.snippet = offset.map(|offset| {
+        let start = command[..offset].rfind('\n').map_or(0, |id| id + 1);
+        let end = command[offset..].find('\n').map_or(command.len(), |id| offset + id);
+        // Tabs are kept, so that the caret lines up with the text above it.
+        let padding = command[start..offset]
+            .chars()
+            .map(|character| if character == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        format!("{}\n{}^", &command[start..end], padding)
+    });
+    error
+}
+
+/// The column of the character at the given byte offset, counting from one.
+fn column(command: &str, offset: usize) -> usize {
+    let start = command[..offset].rfind('\n').map_or(0, |id| id + 1);
+    command[start..offset].chars().count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syntax_errors() {
+        let location = Location::new("script.ion", 42);
+        let error = syntax_error(Some(&location), "echo {a,b", Some(5), "unterminated brace");
+        assert_eq!(error.location.as_ref().and_then(|location| location.column), Some(6));
+        assert_eq!(
+            error.to_string(),
+            "script.ion:42:6: error: unterminated brace\necho {a,b\n     ^"
+        );
+
+        let command = "echo 'one\n\ttwo' }";
+        let error = syntax_error(Some(&location), command, Some(16), "'}' is out of place");
+        assert_eq!(
+            error.to_string(),
+            "script.ion:43:7: error: '}' is out of place\n\ttwo' }\n\t     ^"
+        );
+
+        let error = syntax_error(None, "echo }", Some(5), "'}' is out of place");
+        assert_eq!(error.to_string(), "ion: syntax error: '}' is out of place\necho }\n     ^");
+
+        let error = syntax_error(Some(&location), "|", None, "expected command, but found pipe");
+        assert_eq!(error.to_string(), "script.ion:42: error: expected command, but found pipe");
+    }
+}
@@ -1,20 +1,85 @@
+mod backticks;
 mod case;
 mod functions;
+mod location;
 mod parse;
 mod splitter;
 
+pub(crate) use self::backticks::replace_backticks;
+pub use self::location::Location;
+use self::location::syntax_error;
 pub(crate) use self::parse::parse;
-pub(crate) use self::splitter::{StatementError, StatementSplitter};
-use shell::flow_control::Statement;
-
-/// Parses a given statement string and return's the corresponding mapped `Statement`
-pub(crate) fn parse_and_validate<'a>(statement: Result<&str, StatementError<'a>>) -> Statement {
-    match statement {
-        Ok(statement) => parse(statement),
-        Err(err) => {
-            eprintln!("ion: {}", err);
-            Statement::Error(-1)
+pub(crate) use self::splitter::StatementSplitter;
+use shell::errors::Reporter;
+use shell::flow_control::{ElseIf, Statement};
+
+/// Splits a command into statements, and parses each of them into its corresponding
+/// `Statement`. Syntax errors are reported at their location within the script, if the command
+/// belongs to one.
+pub(crate) struct StatementParser<'a> {
+    splitter: StatementSplitter<'a>,
+    command:  &'a str,
+    location: Option<&'a Location>,
+    reporter: Reporter,
+}
+
+impl<'a> StatementParser<'a> {
+    pub(crate) fn new(
+        command: &'a str,
+        location: Option<&'a Location>,
+        reporter: Reporter,
+    ) -> StatementParser<'a> {
+        StatementParser {
+            splitter: StatementSplitter::new(command),
+            command,
+            location,
+            reporter,
+        }
+    }
+}
+
+impl<'a> Iterator for StatementParser<'a> {
+    type Item = Statement;
+
+    fn next(&mut self) -> Option<Statement> {
+        match self.splitter.next()? {
+            Ok(statement) => {
+                let mut statement = parse(statement);
+                if let Some(location) = self.location {
+                    locate(&mut statement, location.line);
+                }
+                Some(statement)
+            }
+            Err(err) => {
+                let offset = self.splitter.error_offset(&err);
+                let message = err.to_string();
+                let error = syntax_error(self.location, self.command, Some(offset), &message);
+                self.reporter.report(error);
+                Some(Statement::Error(-1))
+            }
+        }
+    }
+}
+
+/// Records the line at which the pipelines of a statement were written, so that the functions
+/// which they call may be traced back to them.
+fn locate(statement: &mut Statement, line: usize) {
+    match *statement {
+        Statement::Pipeline(ref mut pipeline)
+        | Statement::If {
+            expression: ref mut pipeline,
+            ..
         }
+        | Statement::ElseIf(ElseIf {
+            expression: ref mut pipeline,
+            ..
+        })
+        | Statement::While {
+            expression: ref mut pipeline,
+            ..
+        } => pipeline.line = Some(line),
+        Statement::Time(ref mut statement) => locate(statement, line),
+        _ => (),
     }
 }
 
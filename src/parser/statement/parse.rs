@@ -2,9 +2,13 @@ use super::case;
 use super::functions::{collect_arguments, parse_function};
 use super::super::{pipelines, ArgumentSplitter};
 use super::super::assignments::{split_assignment, Operator};
-use super::super::pipelines::Pipeline;
-use shell::flow_control::{Case, ElseIf, ExportAction, LocalAction, Statement};
+use super::super::pipelines::{PipeItem, Pipeline};
+use super::super::shell_expand::is_expression;
+use builtins::compare::{OPERATORS, TYPES};
+use shell::{Job, JobKind};
+use shell::flow_control::{Case, ElseIf, ExportAction, LocalAction, Scope, Statement};
 use std::char;
+use units;
 
 fn collect<F>(arguments: &str, statement: F) -> Statement
     where F: Fn(Pipeline) -> Statement
@@ -18,12 +22,49 @@ fn collect<F>(arguments: &str, statement: F) -> Statement
     }
 }
 
+/// Conditions which compare two values, such as `$x > 5` or `$a == $b :int`, are evaluated by
+/// the `compare` builtin, so that their operators are not mistaken for redirections. The left
+/// value must be a variable, expression, or number, and so must the right value of `<` and `>`.
+/// Assertions of comparisons, such as `assert $x > 5`, are likewise given to `assert`.
+fn comparison(command: &[&str], args: &[&str]) -> Option<Pipeline> {
+    let is_value = |arg: &str| is_expression(arg) || units::parse_decimal(arg).is_some();
+    let is_type = |arg: &str| arg.starts_with(':') && TYPES.contains(&&arg[1..]);
+    let is_comparison = (args.len() == 3 || (args.len() == 4 && is_type(args[3])))
+        && OPERATORS.contains(&args[1]) && is_value(args[0])
+        && ((args[1] != "<" && args[1] != ">") || is_value(args[2]));
+    if !is_comparison {
+        return None;
+    }
+    let args = command.iter().chain(args).map(|&arg| arg.into()).collect();
+    Some(Pipeline {
+        items: vec![
+            PipeItem {
+                job:     Job::new(args, JobKind::Last),
+                outputs: Vec::new(),
+                inputs:  Vec::new(),
+            },
+        ],
+        line:  None,
+    })
+}
+
+/// Collects the expression of a conditional statement, which may be a comparison.
+fn condition<F>(arguments: &str, statement: F) -> Statement
+    where F: Fn(Pipeline) -> Statement
+{
+    let args = ArgumentSplitter::new(arguments).collect::<Vec<&str>>();
+    match comparison(&["compare"], &args) {
+        Some(pipeline) => statement(pipeline),
+        None => collect(arguments, statement),
+    }
+}
+
 fn is_valid_name(name: &str) -> bool { !name.chars().any(|c| !(c.is_alphanumeric() || c == '_')) }
 
 pub(crate) fn parse(code: &str) -> Statement {
     let cmd = code.trim();
     match cmd {
-        "end" => return Statement::End,
+        "end" => return Statement::End(None),
         "break" => return Statement::Break,
         "continue" => return Statement::Continue,
         "for" | "match" | "case" => {
@@ -97,7 +138,7 @@ pub(crate) fn parse(code: &str) -> Statement {
             }
         }
         _ if cmd.starts_with("if ") => {
-            return collect(cmd[3..].trim_left(), |pipeline| {
+            return condition(cmd[3..].trim_left(), |pipeline| {
                 Statement::If {
                     expression: pipeline,
                     success:    Vec::new(),
@@ -112,7 +153,7 @@ pub(crate) fn parse(code: &str) -> Statement {
             if cmd.len() == 0 {
                 return Statement::Else;
             } else if cmd.starts_with("if ") {
-                return collect(cmd[3..].trim_left(), |pipeline| {
+                return condition(cmd[3..].trim_left(), |pipeline| {
                     Statement::ElseIf(ElseIf {
                         expression: pipeline,
                         success:    Vec::new(),
@@ -120,35 +161,62 @@ pub(crate) fn parse(code: &str) -> Statement {
                 });
             }
         }
+        _ if cmd.starts_with("end ") => {
+            let input = cmd[4..].trim_left();
+            if !input.starts_with('<') || input.starts_with("<<") {
+                eprintln!("ion: syntax error: expected a file to read from, as in `end < file`");
+                return Statement::Default;
+            }
+            match input[1..].trim_left() {
+                "" => {
+                    eprintln!("ion: syntax error: expected a file to read from after `<`");
+                    return Statement::Default;
+                }
+                file => return Statement::End(Some(file.into())),
+            }
+        }
         _ if cmd.starts_with("while ") => {
-            return collect(cmd[6..].trim_left(), |pipeline| {
+            return condition(cmd[6..].trim_left(), |pipeline| {
                 Statement::While {
                     expression: pipeline,
                     statements: Vec::new(),
+                    input:      None,
                 }
             })
         }
+        _ if cmd.starts_with("repeat ") => {
+            return Statement::Repeat {
+                count:      cmd[7..].trim_left().into(),
+                statements: Vec::new(),
+            }
+        }
+        _ if cmd.starts_with("retry ") => {
+            return Statement::Retry {
+                arguments:  cmd[6..].trim_left().into(),
+                statements: Vec::new(),
+            }
+        }
         _ if cmd.starts_with("for ") => {
             let mut cmd = cmd[4..].trim_left();
-            let pos = match cmd.find(char::is_whitespace) {
-                Some(pos) => pos,
-                None => {
-                    eprintln!("ion: syntax error: incorrect for loop syntax");
-                    return Statement::Default;
+            let mut variables = Vec::new();
+            loop {
+                let pos = match cmd.find(char::is_whitespace) {
+                    Some(pos) => pos,
+                    None => {
+                        eprintln!("ion: syntax error: incorrect for loop syntax");
+                        return Statement::Default;
+                    }
+                };
+                if &cmd[..pos] == "in" && !variables.is_empty() {
+                    break;
                 }
-            };
-
-            let variable = &cmd[..pos];
-            cmd = &cmd[pos..].trim_left();
-
-            if !cmd.starts_with("in ") {
-                eprintln!("ion: syntax error: incorrect for loop syntax");
-                return Statement::Default;
+                variables.push(cmd[..pos].into());
+                cmd = &cmd[pos..].trim_left();
             }
 
             return Statement::For {
-                variable:   variable.into(),
-                values:     ArgumentSplitter::new(cmd[3..].trim_left()).map(String::from).collect(),
+                variables:  variables,
+                values:     ArgumentSplitter::new(cmd[2..].trim_left()).map(String::from).collect(),
                 statements: Vec::new(),
             };
         }
@@ -185,7 +253,27 @@ pub(crate) fn parse(code: &str) -> Statement {
                 cases:      Vec::new(),
             }
         }
-        _ if cmd.starts_with("fn ") => {
+        _ if cmd.starts_with("with ") => {
+            let cmd = cmd[5..].trim_left();
+            let pos = cmd.find(char::is_whitespace).unwrap_or(cmd.len());
+            let value = cmd[pos..].trim_left();
+            let scope = match &cmd[..pos] {
+                "umask" if !value.is_empty() => Scope::Umask(value.into()),
+                "cd" if !value.is_empty() => Scope::Directory(value.into()),
+                _ => {
+                    eprintln!(
+                        "ion: syntax error: expected `with umask MODE` or `with cd DIRECTORY`"
+                    );
+                    return Statement::Default;
+                }
+            };
+            return Statement::With {
+                scope,
+                statements: Vec::new(),
+            };
+        }
+        // Options, as in `fn --list`, are given to the `fn` builtin.
+        _ if cmd.starts_with("fn ") && !cmd[3..].trim_left().starts_with('-') => {
             let cmd = cmd[3..].trim_left();
             let pos = cmd.find(char::is_whitespace).unwrap_or(cmd.len());
             let name = &cmd[..pos];
@@ -218,6 +306,23 @@ pub(crate) fn parse(code: &str) -> Statement {
             return Statement::Time(Box::new(parse(cmd[4..].trim_left())))
         }
         _ if cmd.eq("time") => return Statement::Time(Box::new(Statement::Default)),
+        _ if cmd.starts_with("assert ") => {
+            let args = ArgumentSplitter::new(&cmd[7..]).collect::<Vec<&str>>();
+            let (command, args) = match args.first() {
+                Some(&"-m") if args.len() > 1 => (vec!["assert", "-m", args[1]], &args[2..]),
+                _ => (vec!["assert"], &args[..]),
+            };
+            if let Some(pipeline) = comparison(&command, args) {
+                return Statement::Pipeline(pipeline);
+            }
+        }
+        _ if cmd.starts_with('(') => {
+            if !cmd.ends_with(')') {
+                eprintln!("ion: syntax error: a group may not be followed by other commands");
+                return Statement::Default;
+            }
+            return Statement::Subshell(cmd[1..cmd.len() - 1].trim().into());
+        }
         _ => (),
     }
 
@@ -231,12 +336,19 @@ pub(crate) fn parse(code: &str) -> Statement {
 
 #[cfg(test)]
 mod tests {
-    use self::pipelines::PipeItem;
     use super::*;
     use parser::assignments::{KeyBuf, Primitive};
-    use shell::{Job, JobKind};
     use shell::flow_control::Statement;
 
+    #[test]
+    fn parsing_groups() {
+        let parsed = parse("(cd /tmp; echo $PWD)");
+        assert_eq!(Statement::Subshell("cd /tmp; echo $PWD".into()), parsed);
+
+        let parsed = parse("(echo one) | cat");
+        assert_eq!(Statement::Default, parsed);
+    }
+
     #[test]
     fn parsing_ifs() {
         // Default case where spaced normally
@@ -259,6 +371,7 @@ mod tests {
                         inputs:  Vec::new(),
                     },
                 ],
+                line:  None,
             },
             success:    vec![],
             else_if:    vec![],
@@ -271,6 +384,74 @@ mod tests {
         assert_eq!(correct_parse, parsed_if);
     }
 
+    #[test]
+    fn parsing_comparisons() {
+        let expression = |args: &[&str]| Pipeline {
+            items: vec![
+                PipeItem {
+                    job:     Job::new(args.iter().map(|&arg| arg.into()).collect(), JobKind::Last),
+                    outputs: Vec::new(),
+                    inputs:  Vec::new(),
+                },
+            ],
+            line:  None,
+        };
+
+        let parsed = parse("if $x > 5");
+        let correct_parse = Statement::If {
+            expression: expression(&["compare", "$x", ">", "5"]),
+            success:    vec![],
+            else_if:    vec![],
+            failure:    vec![],
+        };
+        assert_eq!(correct_parse, parsed);
+
+        let parsed = parse("while $a == $b :int");
+        let correct_parse = Statement::While {
+            expression: expression(&["compare", "$a", "==", "$b", ":int"]),
+            statements: vec![],
+            input:      None,
+        };
+        assert_eq!(correct_parse, parsed);
+
+        let parsed = parse("else if $((x * 2)) <= 10");
+        let correct_parse = Statement::ElseIf(ElseIf {
+            expression: expression(&["compare", "$((x * 2))", "<=", "10"]),
+            success:    vec![],
+        });
+        assert_eq!(correct_parse, parsed);
+
+        // Commands, and variables which are redirected to files, are not comparisons.
+        let parsed = parse("if $size >= 1.5MiB");
+        let correct_parse = Statement::If {
+            expression: expression(&["compare", "$size", ">=", "1.5MiB"]),
+            success:    vec![],
+            else_if:    vec![],
+            failure:    vec![],
+        };
+        assert_eq!(correct_parse, parsed);
+
+        let parsed = parse("assert -m 'too few' $count > 5");
+        let correct_parse = Statement::Pipeline(expression(&[
+            "assert",
+            "-m",
+            "'too few'",
+            "$count",
+            ">",
+            "5",
+        ]));
+        assert_eq!(correct_parse, parsed);
+
+        for &code in &["if test 1 == 1", "if $cmd > file", "if $a == $b :char"] {
+            match parse(code) {
+                Statement::If { expression, .. } => {
+                    assert_ne!("compare", expression.items[0].job.command)
+                }
+                statement => panic!("expected an if statement: {:?}", statement),
+            }
+        }
+    }
+
     #[test]
     fn parsing_elses() {
         // Default case where spaced normally
@@ -291,18 +472,96 @@ mod tests {
     fn parsing_ends() {
         // Default case where spaced normally
         let parsed_if = parse("end");
-        let correct_parse = Statement::End;
+        let correct_parse = Statement::End(None);
         assert_eq!(correct_parse, parsed_if);
 
         // Trailing spaces after final value
         let parsed_if = parse("end         ");
-        let correct_parse = Statement::End;
+        let correct_parse = Statement::End(None);
         assert_eq!(correct_parse, parsed_if);
 
         // Leading spaces after final value
         let parsed_if = parse("         end");
-        let correct_parse = Statement::End;
+        let correct_parse = Statement::End(None);
         assert_eq!(correct_parse, parsed_if);
+
+        // The lines of a file may be given to the block which is ended
+        let correct_parse = Statement::End(Some("$log".into()));
+        assert_eq!(correct_parse, parse("end < $log"));
+        assert_eq!(correct_parse, parse("end <$log"));
+        assert_eq!(Statement::Default, parse("end <"));
+        assert_eq!(Statement::Default, parse("end << EOF"));
+        assert_eq!(Statement::Default, parse("end file"));
+    }
+
+    #[test]
+    fn parsing_fors() {
+        let parsed = parse("for i x in @enumerate(array)");
+        let correct_parse = Statement::For {
+            variables:  vec!["i".into(), "x".into()],
+            values:     vec!["@enumerate(array)".into()],
+            statements: Vec::new(),
+        };
+        assert_eq!(correct_parse, parsed);
+
+        // A variable may itself be named `in`
+        let parsed = parse("for in in 1..3");
+        let correct_parse = Statement::For {
+            variables:  vec!["in".into()],
+            values:     vec!["1..3".into()],
+            statements: Vec::new(),
+        };
+        assert_eq!(correct_parse, parsed);
+
+        assert_eq!(Statement::Default, parse("for i in"));
+        assert_eq!(Statement::Default, parse("for i j"));
+    }
+
+    #[test]
+    fn parsing_repeats() {
+        let parsed = parse("repeat 5");
+        let correct_parse = Statement::Repeat {
+            count:      "5".into(),
+            statements: Vec::new(),
+        };
+        assert_eq!(correct_parse, parsed);
+
+        let parsed = parse("repeat   $((n * 2))");
+        let correct_parse = Statement::Repeat {
+            count:      "$((n * 2))".into(),
+            statements: Vec::new(),
+        };
+        assert_eq!(correct_parse, parsed);
+    }
+
+    #[test]
+    fn parsing_retries() {
+        let parsed = parse("retry  5 --delay 2s --backoff 2x");
+        let correct_parse = Statement::Retry {
+            arguments:  "5 --delay 2s --backoff 2x".into(),
+            statements: Vec::new(),
+        };
+        assert_eq!(correct_parse, parsed);
+    }
+
+    #[test]
+    fn parsing_withs() {
+        let parsed = parse("with umask 077");
+        let correct_parse = Statement::With {
+            scope:      Scope::Umask("077".into()),
+            statements: Vec::new(),
+        };
+        assert_eq!(correct_parse, parsed);
+
+        let parsed = parse("with   cd   $HOME/build dir");
+        let correct_parse = Statement::With {
+            scope:      Scope::Directory("$HOME/build dir".into()),
+            statements: Vec::new(),
+        };
+        assert_eq!(correct_parse, parsed);
+
+        assert_eq!(Statement::Default, parse("with umask"));
+        assert_eq!(Statement::Default, parse("with path /usr/bin"));
     }
 
     #[test]
@@ -369,5 +628,13 @@ mod tests {
         assert_eq!(correct_parse, parsed_if);
         let parsed_if = parse("fn bob a b      --bob is a nice function");
         assert_eq!(correct_parse, parsed_if);
+
+        // Options are given to the `fn` builtin
+        match parse("fn --source bob") {
+            Statement::Pipeline(pipeline) => {
+                assert_eq!(pipeline.items[0].job.args.as_slice(), ["fn", "--source", "bob"])
+            }
+            statement => panic!("expected a pipeline, found {:?}", statement),
+        }
     }
 }
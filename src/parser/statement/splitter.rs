@@ -1,25 +1,8 @@
 // TODO:
-// - Rewrite this in the same style as shell_expand::words.
 // - Validate syntax in methods
 
+use super::super::quoting::{Class, Quotes};
 use std::fmt::{self, Display, Formatter};
-use std::u16;
-
-bitflags! {
-    pub struct Flags : u16 {
-        const DQUOTE = 1;
-        const COMM_1 = 2;
-        const COMM_2 = 4;
-        const VBRACE = 8;
-        const ARRAY  = 16;
-        const VARIAB = 32;
-        const METHOD = 64;
-        /// Set while parsing through an inline arithmetic expression, e.g. $((foo * bar / baz))
-        const MATHEXPR = 128;
-        const POST_MATHEXPR = 256;
-    }
-}
-
 
 #[derive(Debug, PartialEq)]
 pub(crate) enum StatementError<'a> {
@@ -37,27 +20,20 @@ impl<'a> Display for StatementError<'a> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match *self {
             StatementError::IllegalCommandName(command) => {
-                writeln!(f, "illegal command name: {}", command)
-            }
-            StatementError::InvalidCharacter(character, position) => writeln!(
-                f,
-                "syntax error: '{}' at position {} is out of place",
-                character,
-                position
-            ),
-            StatementError::UnterminatedSubshell => {
-                writeln!(f, "syntax error: unterminated subshell")
+                write!(f, "illegal command name: {}", command)
             }
-            StatementError::UnterminatedBrace => writeln!(f, "syntax error: unterminated brace"),
-            StatementError::UnterminatedBracedVar => {
-                writeln!(f, "syntax error: unterminated braced var")
+            StatementError::InvalidCharacter(character, _) => {
+                write!(f, "'{}' is out of place", character)
             }
-            StatementError::UnterminatedMethod => writeln!(f, "syntax error: unterminated method"),
+            StatementError::UnterminatedSubshell => write!(f, "unterminated subshell"),
+            StatementError::UnterminatedBrace => write!(f, "unterminated brace"),
+            StatementError::UnterminatedBracedVar => write!(f, "unterminated braced var"),
+            StatementError::UnterminatedMethod => write!(f, "unterminated method"),
             StatementError::UnterminatedArithmetic => {
-                writeln!(f, "syntax error: unterminated arithmetic subexpression")
+                write!(f, "unterminated arithmetic subexpression")
             }
             StatementError::ExpectedCommandButFound(element) => {
-                writeln!(f, "expected command, but found {}", element)
+                write!(f, "expected command, but found {}", element)
             }
         }
     }
@@ -69,238 +45,288 @@ fn is_invalid(byte: u8) -> bool {
         || (byte >= 123 && byte <= 127)
 }
 
+/// A group which has been opened within a statement, and which must be closed before the
+/// statement may end.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Group {
+    /// `$(`, a process expansion.
+    Process,
+    /// `@(`, an array process expansion.
+    ArrayProcess,
+    /// `(`, a group of statements which may only begin a statement.
+    Subshell,
+    /// `(`, the arguments of a method, as in `$join(`.
+    Method,
+    /// `${` or `@{`, a braced variable.
+    BracedVariable,
+    /// `{`, a brace expansion.
+    Brace,
+    /// `$((`, an arithmetic expression.
+    Arithmetic,
+    /// `(`, which nests within an arithmetic expression.
+    Parens,
+}
+
+/// Records the first error which is found within a statement. The statement is still read
+/// until its end, so that the statements which follow it may be split.
+fn fail<'a>(error: &mut Option<StatementError<'a>>, why: StatementError<'a>) {
+    if error.is_none() {
+        *error = Some(why);
+    }
+}
+
 pub(crate) struct StatementSplitter<'a> {
-    data:             &'a str,
-    read:             usize,
-    flags:            Flags,
-    a_level:          u8,
-    ap_level:         u8,
-    p_level:          u8,
-    brace_level:      u8,
-    math_paren_level: i8,
+    data:            &'a str,
+    read:            usize,
+    /// The groups which are open within the current statement, from the outermost, along with
+    /// the offsets at which they were opened, and the quotes which surround them.
+    groups:          Vec<(Group, usize, Quotes)>,
+    /// The quotes which are open within the innermost group.
+    quotes:          Quotes,
+    /// The offsets at which the current statement, and the group which it left unterminated,
+    /// begin, so that an error may be reported where its group was opened.
+    statement_start: usize,
+    group_start:     usize,
 }
 
 impl<'a> StatementSplitter<'a> {
     pub(crate) fn new(data: &'a str) -> StatementSplitter<'a> {
         StatementSplitter {
-            data:             data,
-            read:             0,
-            flags:            Flags::empty(),
-            a_level:          0,
-            ap_level:         0,
-            p_level:          0,
-            brace_level:      0,
-            math_paren_level: 0,
+            data:            data,
+            read:            0,
+            groups:          Vec::new(),
+            quotes:          Quotes::new(),
+            statement_start: 0,
+            group_start:     0,
+        }
+    }
+
+    /// The offset within the data of the character at which the error occurred, or else of the
+    /// group which it left unterminated.
+    pub(crate) fn error_offset(&self, error: &StatementError) -> usize {
+        match *error {
+            StatementError::InvalidCharacter(_, position) => position - 1,
+            StatementError::IllegalCommandName(_) | StatementError::ExpectedCommandButFound(_) => {
+                self.statement_start
+            }
+            _ => self.group_start,
         }
     }
 
-    fn single_quote<B: Iterator<Item = u8>>(&mut self, bytes: &mut B) -> usize {
-        let mut read = 0;
-        while let Some(character) = bytes.next() {
-            read += 1;
-            if character == b'\\' {
-                read += 1;
-                bytes.next();
-            } else if character == b'\'' {
-                break;
+    /// The innermost group which is open, if any.
+    fn group(&self) -> Option<Group> { self.groups.last().map(|&(group, ..)| group) }
+
+    /// Opens a group, within which quotes begin anew, as in `"$(echo ")")"`.
+    fn open(&mut self, group: Group, start: usize) {
+        self.groups.push((group, start, self.quotes));
+        self.quotes = Quotes::new();
+    }
+
+    /// Closes the innermost group, restoring the quotes which surround it.
+    fn close(&mut self) {
+        if let Some((_, _, quotes)) = self.groups.pop() {
+            self.quotes = quotes;
+        }
+    }
+
+    /// Contains the logic for the expansion which follows a `$` or `@`, if any.
+    fn sigil(&mut self, sigil: u8) {
+        let bytes = self.data.as_bytes();
+        let start = self.read - 1;
+        match bytes.get(self.read) {
+            Some(&b'(') if sigil == b'$' && bytes.get(self.read + 1) == Some(&b'(') => {
+                self.open(Group::Arithmetic, start);
+                self.read += 2;
+            }
+            Some(&b'(') => {
+                let group = if sigil == b'$' { Group::Process } else { Group::ArrayProcess };
+                let paren = self.read;
+                self.open(group, paren);
+                self.read += 1;
             }
+            Some(&b'{') => {
+                self.open(Group::BracedVariable, start);
+                self.read += 1;
+            }
+            Some(&byte) if !is_invalid(byte) => self.name(),
+            _ => (),
+        }
+    }
+
+    /// Contains the logic for the name of a variable, or of a method which is chained onto a
+    /// value, as in `$var.trim()`. If a method is named, its arguments are opened.
+    fn name(&mut self) {
+        let bytes = self.data.as_bytes();
+        loop {
+            while bytes.get(self.read).map_or(false, |&byte| !is_invalid(byte)) {
+                self.read += 1;
+            }
+            match bytes.get(self.read) {
+                Some(&b'(') => {
+                    let paren = self.read;
+                    self.open(Group::Method, paren);
+                    self.read += 1;
+                    return;
+                }
+                Some(&b'.') => self.read += 1,
+                _ => return,
+            }
+        }
+    }
+
+    /// Contains the logic for a `)` which closes an arithmetic expression, which must be
+    /// followed by another `)`.
+    fn close_arithmetic(&mut self, error: &mut Option<StatementError<'a>>) {
+        match self.data.as_bytes().get(self.read) {
+            Some(&b')') => {
+                self.close();
+                self.read += 1;
+            }
+            Some(&next) => {
+                fail(error, StatementError::InvalidCharacter(next as char, self.read + 1))
+            }
+            None => {
+                self.group_start = self.groups.last().map_or(0, |&(_, start, _)| start);
+                fail(error, StatementError::UnterminatedArithmetic);
+            }
+        }
+    }
+
+    /// Whether the `else` which was just read is followed by a command, rather than by `if`,
+    /// so that it should be split from that command.
+    fn else_precedes_command(&self, start: usize) -> bool {
+        if self.data[start..self.read - 1].trim() != "else" {
+            return false;
+        }
+        let rest = self.data[self.read..].trim_left();
+        let word = rest.split(|c| c == ' ' || c == '\t' || c == ';').next().unwrap_or("");
+        !word.is_empty() && word != "if"
+    }
+
+    /// The statement which spans from `start` to `end`, unless an error was found within it,
+    /// or it does not begin with a command.
+    fn statement(
+        &self,
+        start: usize,
+        end: usize,
+        error: Option<StatementError<'a>>,
+    ) -> Result<&'a str, StatementError<'a>> {
+        if let Some(error) = error {
+            return Err(error);
+        }
+        let output = self.data[start..end].trim();
+        match output.bytes().next() {
+            Some(b'>') | Some(b'<') | Some(b'^') => {
+                Err(StatementError::ExpectedCommandButFound("redirection"))
+            }
+            Some(b'|') => Err(StatementError::ExpectedCommandButFound("pipe")),
+            Some(b'&') => Err(StatementError::ExpectedCommandButFound("&")),
+            Some(b'*') | Some(b'%') | Some(b'?') | Some(b'{') | Some(b'}') => {
+                Err(StatementError::IllegalCommandName(output))
+            }
+            _ => Ok(output),
         }
-        read
     }
 }
 
 impl<'a> Iterator for StatementSplitter<'a> {
     type Item = Result<&'a str, StatementError<'a>>;
     fn next(&mut self) -> Option<Result<&'a str, StatementError<'a>>> {
+        let data = self.data;
+        let bytes = data.as_bytes();
+        if self.read >= data.len() {
+            return None;
+        }
+
         let start = self.read;
-        let mut first_arg_found = false;
-        let mut else_found = false;
-        let mut else_pos = 0;
+        self.statement_start = start + data[start..].len() - data[start..].trim_left().len();
+        self.groups.clear();
+        self.quotes = Quotes::new();
         let mut error = None;
-        let mut bytes = self.data.bytes().skip(self.read);
-        while let Some(character) = bytes.next() {
+        while let Some(&character) = bytes.get(self.read) {
             self.read += 1;
-            match character {
-                b'\\' => {
-                    self.read += 1;
-                    bytes.next();
-                }
-                _ if self.flags.contains(POST_MATHEXPR) => {
-                    self.flags -= POST_MATHEXPR;
-                }
+            let group = self.group();
+            if group == Some(Group::BracedVariable) {
                 // [^A-Za-z0-9_:,}]
-                0...43 | 45...47 | 59...64 | 91...94 | 96 | 123...124 | 126...127
-                    if self.flags.contains(VBRACE) =>
-                {
-                    // If we are just ending the braced section continue as normal
-                    if error.is_none() {
-                        error = Some(StatementError::InvalidCharacter(character as char, self.read))
-                    }
+                match character {
+                    b'}' => self.close(),
+                    b'\\' => self.read += 1,
+                    b':' | b',' => (),
+                    _ if is_invalid(character) => fail(
+                        &mut error,
+                        StatementError::InvalidCharacter(character as char, self.read),
+                    ),
+                    _ => (),
                 }
-                b'\'' if !self.flags.contains(DQUOTE) => {
-                    self.flags -= VARIAB | ARRAY;
-                    self.read += self.single_quote(&mut bytes);
-                }
-                // Toggle DQUOTE and disable VARIAB + ARRAY.
-                b'"' => self.flags = (self.flags ^ DQUOTE) - (VARIAB | ARRAY),
-                // Disable COMM_1 and enable COMM_2 + ARRAY.
-                b'@' => {
-                    self.flags = (self.flags - COMM_1) | (COMM_2 | ARRAY);
-                    continue;
-                }
-                b'$' => {
-                    self.flags = (self.flags - COMM_2) | (COMM_1 | VARIAB);
-                    continue;
-                }
-                b'{' if self.flags.intersects(COMM_1 | COMM_2) => self.flags |= VBRACE,
-                b'{' if !self.flags.contains(DQUOTE) => self.brace_level += 1,
-                b'}' if self.flags.contains(VBRACE) => self.flags.toggle(VBRACE),
-                b'}' if !self.flags.contains(DQUOTE) => if self.brace_level == 0 {
-                    if error.is_none() {
-                        error = Some(StatementError::InvalidCharacter(character as char, self.read))
-                    }
-                } else {
-                    self.brace_level -= 1;
-                },
-                b'(' if self.flags.contains(MATHEXPR) => {
-                    self.math_paren_level += 1;
-                }
-                b'(' if !self.flags.intersects(COMM_1 | VARIAB | ARRAY) => {
-                    if error.is_none() && !self.flags.contains(DQUOTE) {
-                        error = Some(StatementError::InvalidCharacter(character as char, self.read))
-                    }
-                }
-                b'(' if self.flags.intersects(COMM_1 | METHOD) => {
-                    self.flags -= VARIAB | ARRAY;
-                    if self.data.as_bytes()[self.read] == b'(' {
-                        self.flags = (self.flags - COMM_1) | MATHEXPR;
-                        // The next character will always be a left paren in this branch;
-                        self.math_paren_level = -1;
-                    } else {
-                        self.p_level += 1;
-                    }
-                }
-                b'(' if self.flags.contains(COMM_2) => {
-                    self.ap_level += 1;
+                continue;
+            }
+
+            let class = self.quotes.scan(character);
+            if !class.expands() {
+                continue;
+            }
+            let arithmetic = group == Some(Group::Arithmetic) || group == Some(Group::Parens);
+            match character {
+                b'$' | b'@' => self.sigil(character),
+                // A method may be chained onto a process, selection, or braced variable.
+                b'.' if self.read > 1 && match bytes[self.read - 2] {
+                    b')' | b']' | b'}' => true,
+                    _ => false,
+                } =>
+                {
+                    self.name()
                 }
-                b'(' if self.flags.intersects(VARIAB | ARRAY) => {
-                    self.flags = (self.flags - (VARIAB | ARRAY)) | METHOD;
+                // Within double quotes, only expansions are recognized.
+                _ if class == Class::Double => (),
+                b'(' if arithmetic => self.open(Group::Parens, self.read - 1),
+                // A group is only permitted at the beginning of a statement.
+                b'(' if self.groups.is_empty() && data[start..self.read - 1].trim().is_empty() => {
+                    self.open(Group::Subshell, self.read - 1)
                 }
-                b')' if self.flags.contains(MATHEXPR) => if self.math_paren_level == 0 {
-                    if self.data.as_bytes().len() <= self.read {
-                        if error.is_none() {
-                            error = Some(StatementError::UnterminatedArithmetic)
-                        }
-                    } else {
-                        let next_character = self.data.as_bytes()[self.read] as char;
-                        if next_character == ')' {
-                            self.flags = (self.flags - MATHEXPR) | POST_MATHEXPR;
-                        } else if error.is_none() {
-                            error =
-                                Some(StatementError::InvalidCharacter(next_character, self.read));
-                        }
+                b'(' => fail(&mut error, StatementError::InvalidCharacter('(', self.read)),
+                b')' => match group {
+                    Some(Group::Arithmetic) => self.close_arithmetic(&mut error),
+                    Some(Group::Brace) | None => {
+                        fail(&mut error, StatementError::InvalidCharacter(')', self.read))
                     }
-                } else {
-                    self.math_paren_level -= 1;
+                    Some(_) => self.close(),
                 },
-                b')' if self.flags.contains(METHOD) && self.p_level == 0 => {
-                    self.flags ^= METHOD;
-                }
-                b')' if self.p_level + self.ap_level == 0 => {
-                    if error.is_none() && !self.flags.contains(DQUOTE) {
-                        error = Some(StatementError::InvalidCharacter(character as char, self.read))
-                    }
-                }
-                b')' if self.p_level != 0 => self.p_level -= 1,
-                b')' => self.ap_level -= 1,
-                b';' if !self.flags.contains(DQUOTE) && self.p_level == 0 && self.ap_level == 0 => {
-                    return match error {
-                        Some(error) => Some(Err(error)),
-                        None => Some(Ok(self.data[start..self.read - 1].trim())),
-                    }
+                b'{' => self.open(Group::Brace, self.read - 1),
+                b'}' if group == Some(Group::Brace) => self.close(),
+                b'}' => fail(&mut error, StatementError::InvalidCharacter('}', self.read)),
+                b';' if self.groups.is_empty() => {
+                    return Some(self.statement(start, self.read - 1, error));
                 }
-                b'#' if self.read == 1
-                    || (!self.flags.contains(DQUOTE) && self.p_level + self.ap_level == 0
-                        && match self.data.as_bytes()[self.read - 2] {
-                            b' ' | b'\t' => true,
-                            _ => false,
-                        }) =>
+                b'#' if self.groups.is_empty() && (self.read == 1 || match bytes[self.read - 2] {
+                    b' ' | b'\t' => true,
+                    _ => false,
+                }) =>
                 {
-                    let output = self.data[start..self.read - 1].trim();
-                    self.read = self.data.len();
-                    return match error {
-                        Some(error) => Some(Err(error)),
-                        None => Some(Ok(output)),
-                    };
+                    let end = self.read - 1;
+                    self.read = data.len();
+                    return Some(self.statement(start, end, error));
                 }
-                b' ' if else_found => {
-                    let output = &self.data[else_pos..self.read - 1].trim();
-                    if !output.is_empty() {
-                        if "if" != *output {
-                            self.read = else_pos;
-                            return Some(Ok("else"));
-                        }
-                    }
-                    else_found = false;
-                }
-                b' ' if !first_arg_found => {
-                    let output = &self.data[start..self.read - 1].trim();
-                    if !output.is_empty() {
-                        match *output {
-                            "else" => {
-                                else_found = true;
-                                else_pos = self.read;
-                            }
-                            _ => first_arg_found = true,
-                        }
-                    }
+                b' ' | b'\t' if self.groups.is_empty() && self.else_precedes_command(start) => {
+                    return Some(Ok("else"));
                 }
-                // [^A-Za-z0-9_]
-                byte => if self.flags.intersects(VARIAB | ARRAY) {
-                    self.flags -= if is_invalid(byte) { VARIAB | ARRAY } else { Flags::empty() };
-                },
+                _ => (),
             }
-            self.flags -= COMM_1 | COMM_2;
         }
 
-        if start == self.read {
-            None
-        } else {
-            self.read = self.data.len();
-            match error {
-                Some(error) => Some(Err(error)),
-                None if self.p_level != 0 || self.ap_level != 0 || self.a_level != 0 => {
-                    Some(Err(StatementError::UnterminatedSubshell))
-                }
-                None if self.flags.contains(METHOD) => {
-                    Some(Err(StatementError::UnterminatedMethod))
-                }
-                None if self.flags.contains(VBRACE) => {
-                    Some(Err(StatementError::UnterminatedBracedVar))
-                }
-                None if self.brace_level != 0 => Some(Err(StatementError::UnterminatedBrace)),
-                None if self.flags.contains(MATHEXPR) => {
-                    Some(Err(StatementError::UnterminatedArithmetic))
-                }
-                None => {
-                    let output = self.data[start..].trim();
-                    if output.is_empty() {
-                        return Some(Ok(output));
-                    }
-                    match output.as_bytes()[0] {
-                        b'>' | b'<' | b'^' => {
-                            Some(Err(StatementError::ExpectedCommandButFound("redirection")))
-                        }
-                        b'|' => Some(Err(StatementError::ExpectedCommandButFound("pipe"))),
-                        b'&' => Some(Err(StatementError::ExpectedCommandButFound("&"))),
-                        b'*' | b'%' | b'?' | b'{' | b'}' => {
-                            Some(Err(StatementError::IllegalCommandName(output)))
-                        }
-                        _ => Some(Ok(output)),
-                    }
-                }
+        self.read = data.len();
+        if error.is_none() {
+            if let Some(&(group, start, _)) = self.groups.first() {
+                self.group_start = start;
+                error = Some(match group {
+                    Group::Method => StatementError::UnterminatedMethod,
+                    Group::BracedVariable => StatementError::UnterminatedBracedVar,
+                    Group::Brace => StatementError::UnterminatedBrace,
+                    Group::Arithmetic | Group::Parens => StatementError::UnterminatedArithmetic,
+                    _ => StatementError::UnterminatedSubshell,
+                });
             }
         }
+        Some(self.statement(start, data.len(), error))
     }
 }
 
@@ -325,6 +351,31 @@ fn syntax_errors() {
     assert_eq!(results.len(), 1);
 }
 
+#[test]
+fn error_offsets() {
+    let cases = [
+        ("echo one; echo two)", 18),
+        ("echo {a,b", 5),
+        ("echo {a,{b,c}", 5),
+        ("echo one $(echo two", 10),
+        ("echo $((1 + 2)", 5),
+        ("echo $join(a, b", 10),
+        ("echo ${foo", 5),
+        ("echo one;  | cat", 11),
+    ];
+    for &(command, offset) in cases.iter() {
+        let mut splitter = StatementSplitter::new(command);
+        let error = loop {
+            match splitter.next() {
+                Some(Ok(_)) => continue,
+                Some(Err(error)) => break error,
+                None => panic!("expected an error in {}", command),
+            }
+        };
+        assert_eq!(splitter.error_offset(&error), offset, "{}", command);
+    }
+}
+
 #[test]
 fn methods() {
     let command = "echo $join(array, ', '); echo @join(var, ', ')";
@@ -334,6 +385,16 @@ fn methods() {
     assert_eq!(statements.len(), 2);
 }
 
+#[test]
+fn method_chains() {
+    let command = "echo $var.trim().replace(a, b); echo @array[..2].reverse(); echo $(pwd).len()";
+    let statements = StatementSplitter::new(command).collect::<Vec<_>>();
+    assert_eq!(statements[0], Ok("echo $var.trim().replace(a, b)"));
+    assert_eq!(statements[1], Ok("echo @array[..2].reverse()"));
+    assert_eq!(statements[2], Ok("echo $(pwd).len()"));
+    assert_eq!(statements.len(), 3);
+}
+
 #[test]
 fn processes() {
     let command = "echo $(seq 1 10); echo $(seq 1 10)";
@@ -358,6 +419,19 @@ fn process_with_statements() {
     }
 }
 
+#[test]
+fn groups() {
+    let command = "(cd /tmp; echo $len(@(ls))); echo one";
+    let results = StatementSplitter::new(command).collect::<Vec<Result<&str, StatementError>>>();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0], Ok("(cd /tmp; echo $len(@(ls)))"));
+    assert_eq!(results[1], Ok("echo one"));
+
+    let command = "(echo one";
+    let results = StatementSplitter::new(command).collect::<Vec<Result<&str, StatementError>>>();
+    assert_eq!(results[0], Err(StatementError::UnterminatedSubshell));
+}
+
 #[test]
 fn quotes() {
     let command = "echo \"This ;'is a test\"; echo 'This ;\" is also a test'";
@@ -409,3 +483,80 @@ fn braced_variables() {
     assert_eq!(results.len(), 1);
     assert_eq!(results, vec![Ok(command)]);
 }
+
+#[test]
+fn corner_cases() {
+    use self::StatementError::*;
+    let cases: &[(&str, &[Result<&str, StatementError>])] = &[
+        // Separators and comments
+        ("", &[]),
+        ("echo one;", &[Ok("echo one")]),
+        ("echo one;;echo two", &[Ok("echo one"), Ok(""), Ok("echo two")]),
+        ("  # a comment", &[Ok("")]),
+        ("#!/usr/bin/env ion", &[Ok("")]),
+        ("echo one # two; three", &[Ok("echo one")]),
+        ("echo one\t# two", &[Ok("echo one")]),
+        ("echo one#two", &[Ok("echo one#two")]),
+        ("echo $#", &[Ok("echo $#")]),
+        ("echo \"a # b\" '# c'", &[Ok("echo \"a # b\" '# c'")]),
+        ("echo {a #b}", &[Ok("echo {a #b}")]),
+        // Quotes and escapes
+        ("echo \"}\"; echo \"{\"", &[Ok("echo \"}\""), Ok("echo \"{\"")]),
+        ("echo \"(\" \")\"; echo", &[Ok("echo \"(\" \")\""), Ok("echo")]),
+        ("echo 'a;b'c; d", &[Ok("echo 'a;b'c"), Ok("d")]),
+        (r"echo 'it'\''s'; d", &[Ok(r"echo 'it'\''s'"), Ok("d")]),
+        (r"echo 'a\'; d", &[Ok(r"echo 'a\'"), Ok("d")]),
+        ("echo \"a\\\"; b\"", &[Ok("echo \"a\\\"; b\"")]),
+        (r"echo \; \(; d", &[Ok(r"echo \; \("), Ok("d")]),
+        ("echo \\", &[Ok("echo \\")]),
+        ("echo 'a", &[Ok("echo 'a")]),
+        // Expansions within quotes
+        ("echo \"$(echo \")\")\"; d", &[Ok("echo \"$(echo \")\")\""), Ok("d")]),
+        ("echo \"$(echo 'a)')\"", &[Ok("echo \"$(echo 'a)')\"")]),
+        ("echo \"$((1 + 2))\"; d", &[Ok("echo \"$((1 + 2))\""), Ok("d")]),
+        ("echo \"${a}}\"", &[Ok("echo \"${a}}\"")]),
+        // Processes, methods, and arithmetic
+        ("echo $(a; b) @(c; d); e", &[Ok("echo $(a; b) @(c; d)"), Ok("e")]),
+        ("echo $join(@a \")\"); e", &[Ok("echo $join(@a \")\")"), Ok("e")]),
+        ("echo $join(@a ')'); e", &[Ok("echo $join(@a ')')"), Ok("e")]),
+        ("echo @(ls)[0].len(); e", &[Ok("echo @(ls)[0].len()"), Ok("e")]),
+        ("echo $((1 + (2 * 3))); e", &[Ok("echo $((1 + (2 * 3)))"), Ok("e")]),
+        ("echo $(($(echo 1) + 2))", &[Ok("echo $(($(echo 1) + 2))")]),
+        ("echo $(( 1 ) )", &[Err(InvalidCharacter(' ', 13))]),
+        ("echo a.b(c)", &[Err(InvalidCharacter('(', 9))]),
+        // Braces
+        ("echo {a,b}; echo {c;d}", &[Ok("echo {a,b}"), Ok("echo {c;d}")]),
+        ("echo ${a}}", &[Err(InvalidCharacter('}', 10))]),
+        ("echo ${a:-b}", &[Err(InvalidCharacter('-', 10))]),
+        ("echo @{a}; e", &[Ok("echo @{a}"), Ok("e")]),
+        // Groups
+        ("(a; b); c", &[Ok("(a; b)"), Ok("c")]),
+        ("  (a)", &[Ok("(a)")]),
+        ("echo (a)", &[Err(InvalidCharacter('(', 6))]),
+        ("((a))", &[Err(InvalidCharacter('(', 2))]),
+        // The else keyword is split from the command which follows it
+        ("else echo a", &[Ok("else"), Ok("echo a")]),
+        ("else\techo a", &[Ok("else"), Ok("echo a")]),
+        ("else if test", &[Ok("else if test")]),
+        ("else\tif test", &[Ok("else\tif test")]),
+        ("else ; end", &[Ok("else"), Ok("end")]),
+        ("elsewhere a", &[Ok("elsewhere a")]),
+        ("echo else a", &[Ok("echo else a")]),
+        // Statements which do not begin with commands
+        ("| cat; echo", &[Err(ExpectedCommandButFound("pipe")), Ok("echo")]),
+        ("echo; > file", &[Ok("echo"), Err(ExpectedCommandButFound("redirection"))]),
+        ("echo; & a", &[Ok("echo"), Err(ExpectedCommandButFound("&"))]),
+        ("* a", &[Err(IllegalCommandName("* a"))]),
+        // Unterminated groups
+        ("echo \"$(a", &[Err(UnterminatedSubshell)]),
+        ("echo @(a; b", &[Err(UnterminatedSubshell)]),
+        ("echo $join(a", &[Err(UnterminatedMethod)]),
+        ("echo {a, $(b", &[Err(UnterminatedBrace)]),
+        ("echo @{a", &[Err(UnterminatedBracedVar)]),
+        ("echo $((1 + (2)", &[Err(UnterminatedArithmetic)]),
+    ];
+    for &(command, expected) in cases {
+        let results = StatementSplitter::new(command).collect::<Vec<_>>();
+        assert_eq!(&results[..], expected, "{}", command);
+    }
+}
@@ -0,0 +1,125 @@
+//! Integers written in bases other than ten, such as the hexadecimal `0xff`, the octal `0o755`,
+//! and the binary `0b1010`, which are accepted wherever an integer is expected.
+
+/// Parses an integer, which may be preceded by a sign, and written in hexadecimal, octal, or
+/// binary when it begins with `0x`, `0o`, or `0b`.
+pub(crate) fn parse(literal: &str) -> Option<i64> {
+    let (negative, digits) = match literal.as_bytes().first() {
+        Some(&b'-') => (true, &literal[1..]),
+        Some(&b'+') => (false, &literal[1..]),
+        _ => (false, literal),
+    };
+    let (base, digits) = split_prefix(digits);
+    from_digits(negative, digits, base)
+}
+
+/// Splits the prefix which denotes the base of an integer from its digits.
+fn split_prefix(digits: &str) -> (u32, &str) {
+    let prefix = if digits.len() > 2 { digits[..2].to_lowercase() } else { String::new() };
+    match prefix.as_str() {
+        "0x" => (16, &digits[2..]),
+        "0o" => (8, &digits[2..]),
+        "0b" => (2, &digits[2..]),
+        _ => (10, digits),
+    }
+}
+
+fn from_digits(negative: bool, digits: &str, base: u32) -> Option<i64> {
+    // Signs have already been removed, and so may not appear again among the digits.
+    if digits.is_empty() || !digits.chars().all(|digit| digit.is_digit(base)) {
+        return None;
+    }
+    let magnitude = u64::from_str_radix(digits, base).ok()?;
+    if negative {
+        if magnitude <= i64::max_value() as u64 + 1 {
+            Some((magnitude as i64).wrapping_neg())
+        } else {
+            None
+        }
+    } else if magnitude <= i64::max_value() as u64 {
+        Some(magnitude as i64)
+    } else {
+        None
+    }
+}
+
+/// Parses the digits of an integer in the given base, from 2 to 36. The prefix of that base,
+/// such as the `0x` of hexadecimal, may also be given.
+pub(crate) fn from_base(literal: &str, base: u32) -> Result<i64, String> {
+    if base < 2 || base > 36 {
+        return Err(format!("{} is not a base from 2 to 36", base));
+    }
+    let (negative, digits) = match literal.as_bytes().first() {
+        Some(&b'-') => (true, &literal[1..]),
+        Some(&b'+') => (false, &literal[1..]),
+        _ => (false, literal),
+    };
+    let digits = match split_prefix(digits) {
+        (prefix, rest) if prefix == base => rest,
+        _ => digits,
+    };
+    from_digits(negative, digits, base)
+        .ok_or_else(|| format!("'{}' is not an integer in base {}", literal, base))
+}
+
+/// Writes an integer in the given base, from 2 to 36, with lowercase letters for digits above
+/// nine.
+pub(crate) fn to_base(value: i64, base: u32) -> Result<String, String> {
+    if base < 2 || base > 36 {
+        return Err(format!("{} is not a base from 2 to 36", base));
+    }
+    let mut magnitude = if value < 0 { (value as u64).wrapping_neg() } else { value as u64 };
+    let mut digits = Vec::new();
+    loop {
+        let digit = (magnitude % base as u64) as u8;
+        digits.push(if digit < 10 { b'0' + digit } else { b'a' + digit - 10 });
+        magnitude /= base as u64;
+        if magnitude == 0 {
+            break;
+        }
+    }
+    if value < 0 {
+        digits.push(b'-');
+    }
+    digits.reverse();
+    Ok(String::from_utf8(digits).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        assert_eq!(parse("255"), Some(255));
+        assert_eq!(parse("0xff"), Some(255));
+        assert_eq!(parse("0XFF"), Some(255));
+        assert_eq!(parse("0o755"), Some(493));
+        assert_eq!(parse("-0b1010"), Some(-10));
+        assert_eq!(parse("-0x8000000000000000"), Some(i64::min_value()));
+        assert_eq!(parse("0x8000000000000000"), None);
+        assert_eq!(parse("0o8"), None);
+        assert_eq!(parse("0x"), None);
+        assert_eq!(parse("--1"), None);
+        assert_eq!(parse("1.5"), None);
+    }
+
+    #[test]
+    fn bases() {
+        assert_eq!(to_base(255, 16), Ok("ff".into()));
+        assert_eq!(to_base(493, 8), Ok("755".into()));
+        assert_eq!(to_base(-10, 2), Ok("-1010".into()));
+        assert_eq!(to_base(0, 36), Ok("0".into()));
+        assert_eq!(to_base(i64::min_value(), 16), Ok("-8000000000000000".into()));
+        assert!(to_base(10, 1).is_err());
+
+        assert_eq!(from_base("ff", 16), Ok(255));
+        assert_eq!(from_base("0xFF", 16), Ok(255));
+        assert_eq!(from_base("zz", 36), Ok(1295));
+        assert_eq!(from_base("-1010", 2), Ok(-10));
+        assert!(from_base("12", 2).is_err());
+        // Only the prefix of the given base is removed.
+        assert_eq!(from_base("0b1", 16), Ok(0xb1));
+        assert!(from_base("1", 37).is_err());
+    }
+}
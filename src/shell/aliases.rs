@@ -0,0 +1,209 @@
+//! Expansion of the aliases of each job within a pipeline, which is performed before the words of
+//! the job are expanded. An alias replaces the command of a job, and may refer to the arguments
+//! which follow the command as `$1` through `$9`, or as `@args`, much like a function. Global
+//! aliases, which are defined with `alias -g`, replace any word of a job, and suffix aliases,
+//! which are defined with `alias -s`, give the command that opens a file which is given as the
+//! command, according to the suffix of its name.
+
+use parser::{parse, ArgumentSplitter, StatementSplitter};
+use parser::pipelines::{Collector, Pipeline};
+use parser::quoting::Quotes;
+use shell::flow_control::Statement;
+use shell::variables::Variables;
+use types::Array;
+
+/// Substitutes the arguments to which the words of an alias refer, as they were written. Returns
+/// `None` if the alias does not refer to any of its arguments, which are then appended to it.
+fn substitute(alias: &str, args: &[String]) -> Option<Array> {
+    let mut words = Array::new();
+    let mut refers = false;
+    for word in ArgumentSplitter::new(alias) {
+        if word == "@args" {
+            words.extend(args.iter().skip(1).cloned());
+            refers = true;
+            continue;
+        }
+
+        let mut substituted = String::with_capacity(word.len());
+        let mut quotes = Quotes::new();
+        let (bytes, mut start, mut id) = (word.as_bytes(), 0, 0);
+        while id < bytes.len() {
+            let class = quotes.scan(bytes[id]);
+            id += 1;
+            match bytes.get(id) {
+                Some(&position @ b'1'...b'9') if bytes[id - 1] == b'$' && class.expands() => {
+                    substituted.push_str(&word[start..id - 1]);
+                    if let Some(arg) = args.get((position - b'0') as usize) {
+                        substituted.push_str(arg);
+                    }
+                    refers = true;
+                    id += 1;
+                    start = id;
+                }
+                _ => (),
+            }
+        }
+        substituted.push_str(&word[start..]);
+        // A word which only referred to an argument that was not supplied is removed.
+        if !substituted.is_empty() {
+            words.push(substituted);
+        }
+    }
+    if refers {
+        Some(words)
+    } else {
+        None
+    }
+}
+
+/// The suffix alias for the longest suffix of the command, if any.
+fn suffix_alias<'a>(variables: &'a Variables, command: &str) -> Option<&'a str> {
+    variables
+        .suffix_aliases
+        .iter()
+        .filter(|&(suffix, _)| command.len() > suffix.len() && command.ends_with(&**suffix))
+        .max_by_key(|&(suffix, _)| suffix.len())
+        .map(|(_, alias)| alias.as_str())
+}
+
+/// Expands the aliases of the arguments of a job, returning whether any were expanded. Global
+/// aliases are expanded first, so that the command which one of them gives may be an alias.
+fn expand_job(variables: &Variables, args: &mut Array) -> bool {
+    let mut expanded = false;
+    if args.iter().any(|arg| variables.global_aliases.contains_key(arg.as_str())) {
+        let mut words = Array::new();
+        for arg in args.drain() {
+            match variables.global_aliases.get(arg.as_str()) {
+                Some(alias) => words.extend(ArgumentSplitter::new(alias).map(String::from)),
+                None => words.push(arg),
+            }
+        }
+        *args = words;
+        expanded = true;
+    }
+
+    let words = match args.first() {
+        Some(command) => match variables.aliases.get(command.as_str()) {
+            Some(alias) => substitute(alias, args).unwrap_or_else(|| {
+                ArgumentSplitter::new(alias)
+                    .map(String::from)
+                    .chain(args.iter().skip(1).cloned())
+                    .collect()
+            }),
+            None => match suffix_alias(variables, command) {
+                Some(alias) => ArgumentSplitter::new(alias)
+                    .map(String::from)
+                    .chain(args.iter().cloned())
+                    .collect(),
+                None => return expanded,
+            },
+        },
+        None => return expanded,
+    };
+    // The command is kept if its alias only referred to arguments which were not supplied.
+    if words.is_empty() {
+        return expanded;
+    }
+    *args = words;
+    true
+}
+
+/// Expands the aliases of each job within the pipeline, returning whether any were expanded.
+/// Aliases are not expanded recursively, so that an alias may refer to the command it replaces.
+pub(crate) fn expand_aliases(variables: &Variables, pipeline: &mut Pipeline) -> bool {
+    let mut expanded = false;
+    for item in &mut pipeline.items {
+        if expand_job(variables, &mut item.job.args) {
+            item.job.command = item.job.args[0].as_str().into();
+            expanded = true;
+        }
+    }
+    expanded
+}
+
+/// Expands the aliases of each pipeline within a command line, so that it may be recorded in the
+/// history as it was executed. Other statements are kept as they were written. Returns `None` if
+/// no aliases were expanded, or if the line could not be parsed.
+pub(crate) fn expand_line(variables: &Variables, line: &str) -> Option<String> {
+    let mut statements = Vec::new();
+    let mut expanded = false;
+    for statement in StatementSplitter::new(line) {
+        let statement = statement.ok()?;
+        // Statements which are not valid pipelines are not parsed again, so that their syntax
+        // errors are not reported twice.
+        if Collector::run(statement).is_ok() {
+            if let Statement::Pipeline(mut pipeline) = parse(statement) {
+                if expand_aliases(variables, &mut pipeline) {
+                    statements.push(pipeline.to_string());
+                    expanded = true;
+                    continue;
+                }
+            }
+        }
+        statements.push(statement.trim().to_owned());
+    }
+    if expanded {
+        Some(statements.join("; "))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Array { args.iter().map(|&arg| arg.to_owned()).collect() }
+
+    fn expand(variables: &Variables, line: &[&str]) -> Array {
+        let mut line = args(line);
+        expand_job(variables, &mut line);
+        line
+    }
+
+    #[test]
+    fn positional_aliases() {
+        let mut variables = Variables::default();
+        variables.aliases.insert("ll".into(), "ls -l".into());
+        variables.aliases.insert("gc".into(), "git commit -m $1".into());
+        variables.aliases.insert("mkcd".into(), "mkdir -p @args".into());
+        variables.aliases.insert("swap".into(), "echo --first=$2 $1".into());
+        variables.aliases.insert("col".into(), "awk '{print $1}' $1".into());
+        variables.aliases.insert("sep".into(), r"printf '\' $1".into());
+
+        assert_eq!(expand(&variables, &["ll", "/tmp"]), args(&["ls", "-l", "/tmp"]));
+        assert_eq!(
+            expand(&variables, &["gc", "'fix it'", "ignored"]),
+            args(&["git", "commit", "-m", "'fix it'"])
+        );
+        assert_eq!(expand(&variables, &["gc"]), args(&["git", "commit", "-m"]));
+        assert_eq!(expand(&variables, &["mkcd", "a", "b"]), args(&["mkdir", "-p", "a", "b"]));
+        assert_eq!(expand(&variables, &["swap", "a", "b"]), args(&["echo", "--first=b", "a"]));
+        assert_eq!(
+            expand(&variables, &["col", "file"]),
+            args(&["awk", "'{print $1}'", "file"])
+        );
+        assert_eq!(expand(&variables, &["sep", "a"]), args(&["printf", r"'\'", "a"]));
+    }
+
+    #[test]
+    fn global_and_suffix_aliases() {
+        let mut variables = Variables::default();
+        variables.global_aliases.insert("...".into(), "../..".into());
+        variables.aliases.insert("up".into(), "cd".into());
+        variables.suffix_aliases.insert(".py".into(), "python3".into());
+        variables.suffix_aliases.insert(".gz".into(), "zcat".into());
+        variables.suffix_aliases.insert(".tar.gz".into(), "tar -xzf".into());
+
+        assert_eq!(expand(&variables, &["up", "..."]), args(&["cd", "../.."]));
+        assert_eq!(expand(&variables, &["ls", "...", "..."]), args(&["ls", "../..", "../.."]));
+        assert_eq!(
+            expand(&variables, &["./setup.py", "install"]),
+            args(&["python3", "./setup.py", "install"])
+        );
+        assert_eq!(expand(&variables, &["a.tar.gz"]), args(&["tar", "-xzf", "a.tar.gz"]));
+        assert_eq!(expand(&variables, &["log.gz"]), args(&["zcat", "log.gz"]));
+        assert_eq!(expand(&variables, &[".py"]), args(&[".py"]));
+        assert_eq!(expand(&variables, &["python3", "a.py"]), args(&["python3", "a.py"]));
+    }
+}
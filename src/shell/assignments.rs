@@ -1,6 +1,10 @@
 use super::Shell;
+use super::errors::{ErrorKind, IonError};
 use super::flow_control::{ExportAction, LocalAction};
 use super::status::*;
+use super::variables::MASK;
+use decimal::DIVISION_SCALE;
+use parser::{expand_string, Select};
 use parser::assignments::*;
 use shell::history::ShellHistory;
 use std::borrow::Cow;
@@ -9,6 +13,12 @@ use std::ffi::OsStr;
 use std::fmt::{self, Display};
 use std::io::{self, BufWriter, Write};
 use std::os::unix::ffi::OsStrExt;
+use units;
+
+/// Describes an error which prevented a variable from being assigned.
+fn assignment_error<E: Display>(why: E) -> IonError {
+    IonError::new(ErrorKind::Assignment, why.to_string())
+}
 
 fn list_vars(shell: &Shell) {
     let stdout = io::stdout();
@@ -33,13 +43,21 @@ fn list_vars(shell: &Shell) {
 
     // Write all the string variables to the buffer.
     let _ = buffer.write(b"# String Variables\n");
+    // The values of secret variables are masked.
     shell.variables.variables.iter().for_each(|(key, val)| {
-        let _ = buffer.write([key, " = ", val.as_str(), "\n"].concat().as_bytes());
+        let val = if shell.variables.is_secret(key) { MASK } else { val.as_str() };
+        let _ = buffer.write([key, " = ", val, "\n"].concat().as_bytes());
     });
 
     // Then immediately follow that with a list of array variables.
     let _ = buffer.write(b"\n# Array Variables\n");
-    shell.variables.arrays.iter().for_each(|(key, val)| print_array(&mut buffer, &key, &val));
+    shell.variables.arrays.iter().for_each(|(key, val)| {
+        if shell.variables.is_secret(key) {
+            let _ = buffer.write([key, " = [ ", MASK, " ]\n"].concat().as_bytes());
+        } else {
+            print_array(&mut buffer, &key, &val)
+        }
+    });
 }
 
 /// Represents: A variable store capable of setting local variables or
@@ -63,8 +81,29 @@ impl VariableStore for Shell {
         };
         for action in actions {
             match action {
+                Ok(Action::UpdateArray(ref key, operator, ref expression))
+                | Ok(Action::UpdateString(ref key, operator, ref expression))
+                    if split_selection(key.name).is_some() =>
+                {
+                    let (name, selection) = split_selection(key.name).unwrap();
+                    let value = value_check(self, expression, key.kind);
+                    if self.expansion_failed() {
+                        return FAILURE;
+                    }
+                    let result = value
+                        .map_err(|why| why.to_string())
+                        .and_then(|value| assign_selection(self, name, selection, operator, value));
+                    if let Err(why) = result {
+                        self.report(assignment_error(why).context(key.name));
+                        return FAILURE;
+                    }
+                }
                 Ok(Action::UpdateArray(key, Operator::Equal, expression)) => {
-                    match value_check(self, &expression, key.kind) {
+                    let value = value_check(self, &expression, key.kind);
+                    if self.expansion_failed() {
+                        return FAILURE;
+                    }
+                    match value {
                         Ok(ReturnValue::Vector(values)) => {
                             // When we changed the HISTORY_IGNORE variable, update the
                             // ignore patterns. This happens first because `set_array`
@@ -75,45 +114,84 @@ impl VariableStore for Shell {
                             self.variables.set_array(key.name, values)
                         }
                         Err(why) => {
-                            eprintln!("ion: assignment error: {}: {}", key.name, why);
+                            self.report(assignment_error(why).context(key.name));
                             return FAILURE;
                         }
                         _ => unreachable!(),
                     }
                 }
                 Ok(Action::UpdateArray(..)) => {
-                    eprintln!(
-                        "ion: arithmetic operators on array expressions aren't supported \
-                            yet."
-                    );
+                    let why = "arithmetic operators on array expressions aren't supported yet";
+                    self.report(assignment_error(why));
                     return FAILURE;
                 }
+                Ok(Action::Destructure(keys, operator, expression)) => {
+                    let values = expand_string(expression, self, false);
+                    if self.expansion_failed() {
+                        return FAILURE;
+                    }
+                    let pairs = match destructure(&keys, values) {
+                        Ok(pairs) => pairs,
+                        Err(why) => {
+                            self.report(assignment_error(why));
+                            return FAILURE;
+                        }
+                    };
+                    for (key, value) in pairs {
+                        if ["HOME", "PWD", "MWD", "SWD", "?"].contains(&key.name) {
+                            let error = assignment_error("variable is read-only");
+                            self.report(error.context(key.name));
+                            return FAILURE;
+                        }
+                        match string_check(ReturnValue::Str(value), key.kind) {
+                            Ok(ReturnValue::Str(value)) => {
+                                let lhs = self.variables.get_var_or_empty(key.name);
+                                match math(&lhs, key.kind, operator, &value) {
+                                    Ok(value) => self.variables.set_var(key.name, &value),
+                                    Err(why) => {
+                                        self.report(assignment_error(why));
+                                        return FAILURE;
+                                    }
+                                }
+                            }
+                            Err(why) => {
+                                self.report(assignment_error(why).context(key.name));
+                                return FAILURE;
+                            }
+                            _ => unreachable!(),
+                        }
+                    }
+                }
                 Ok(Action::UpdateString(key, operator, expression)) => {
                     if ["HOME", "PWD", "MWD", "SWD", "?"].contains(&key.name) {
-                        eprintln!("ion: not allowed to set {}", key.name);
+                        self.report(assignment_error("variable is read-only").context(key.name));
                         return FAILURE;
                     }
 
-                    match value_check(self, &expression, key.kind) {
+                    let value = value_check(self, &expression, key.kind);
+                    if self.expansion_failed() {
+                        return FAILURE;
+                    }
+                    match value {
                         Ok(ReturnValue::Str(value)) => {
                             let lhs = self.variables.get_var_or_empty(&key.name);
                             match math(&lhs, key.kind, operator, &value) {
                                 Ok(value) => self.variables.set_var(&key.name, &value),
                                 Err(why) => {
-                                    eprintln!("ion: assignment error: {}", why);
+                                    self.report(assignment_error(why));
                                     return FAILURE;
                                 }
                             }
                         }
                         Err(why) => {
-                            eprintln!("ion: assignment error: {}: {}", key.name, why);
+                            self.report(assignment_error(why).context(key.name));
                             return FAILURE;
                         }
                         _ => unreachable!(),
                     }
                 }
                 Err(why) => {
-                    eprintln!("ion: assignment error: {}", why);
+                    self.report(assignment_error(why));
                     return FAILURE;
                 }
             }
@@ -131,7 +209,8 @@ impl VariableStore for Shell {
                     return SUCCESS;
                 }
                 None => {
-                    eprintln!("ion: cannot export {} because it does not exist.", key);
+                    let why = "cannot export a variable which does not exist";
+                    self.report(assignment_error(why).context(key.clone()));
                     return FAILURE;
                 }
             },
@@ -148,23 +227,65 @@ impl VariableStore for Shell {
         for action in actions {
             match action {
                 Ok(Action::UpdateArray(key, Operator::Equal, expression)) => {
-                    match value_check(self, &expression, key.kind) {
+                    let value = value_check(self, &expression, key.kind);
+                    if self.expansion_failed() {
+                        return FAILURE;
+                    }
+                    match value {
                         Ok(ReturnValue::Vector(values)) => env::set_var(key.name, values.join(" ")),
                         Err(why) => {
-                            eprintln!("ion: assignment error: {}: {}", key.name, why);
+                            self.report(assignment_error(why).context(key.name));
                             return FAILURE;
                         }
                         _ => unreachable!(),
                     }
                 }
                 Ok(Action::UpdateArray(..)) => {
-                    eprintln!(
-                        "ion: arithmetic operators on array expressions aren't supported yet."
-                    );
+                    let why = "arithmetic operators on array expressions aren't supported yet";
+                    self.report(assignment_error(why));
                     return FAILURE;
                 }
+                Ok(Action::Destructure(keys, operator, expression)) => {
+                    let values = expand_string(expression, self, false);
+                    if self.expansion_failed() {
+                        return FAILURE;
+                    }
+                    let pairs = match destructure(&keys, values) {
+                        Ok(pairs) => pairs,
+                        Err(why) => {
+                            self.report(assignment_error(why));
+                            return FAILURE;
+                        }
+                    };
+                    for (key, value) in pairs {
+                        match string_check(ReturnValue::Str(value), key.kind) {
+                            Ok(ReturnValue::Str(value)) => {
+                                let lhs = self.variables.get_var_or_empty(key.name);
+                                match math(&lhs, key.kind, operator, &value) {
+                                    Ok(value) => {
+                                        let value = OsStr::from_bytes(&value.as_bytes());
+                                        env::set_var(key.name, &value)
+                                    }
+                                    Err(why) => {
+                                        self.report(assignment_error(why));
+                                        return FAILURE;
+                                    }
+                                }
+                            }
+                            Err(why) => {
+                                self.report(assignment_error(why).context(key.name));
+                                return FAILURE;
+                            }
+                            _ => unreachable!(),
+                        }
+                    }
+                }
                 Ok(Action::UpdateString(key, operator, expression)) => {
-                    match value_check(self, &expression, key.kind) {
+                    let value = value_check(self, &expression, key.kind);
+                    if self.expansion_failed() {
+                        return FAILURE;
+                    }
+                    match value {
                         Ok(ReturnValue::Str(value)) => {
                             let lhs = self.variables.get_var_or_empty(&key.name);
                             match math(&lhs, key.kind, operator, &value) {
@@ -173,20 +294,20 @@ impl VariableStore for Shell {
                                     env::set_var(&key.name, &value)
                                 }
                                 Err(why) => {
-                                    eprintln!("ion: assignment error: {}", why);
+                                    self.report(assignment_error(why));
                                     return FAILURE;
                                 }
                             }
                         }
                         Err(why) => {
-                            eprintln!("ion: assignment error: {}: {}", key.name, why);
+                            self.report(assignment_error(why).context(key.name));
                             return FAILURE;
                         }
                         _ => unreachable!(),
                     }
                 }
                 Err(why) => {
-                    eprintln!("ion: assignment error: {}", why);
+                    self.report(assignment_error(why));
                     return FAILURE;
                 }
             }
@@ -200,6 +321,9 @@ enum MathError {
     RHS,
     LHS,
     Unsupported,
+    DivideByZero,
+    Overflow,
+    NegativeExponent,
 }
 
 impl Display for MathError {
@@ -208,66 +332,234 @@ impl Display for MathError {
             MathError::RHS => write!(fmt, "right hand side has invalid type"),
             MathError::LHS => write!(fmt, "left hand side has invalid type"),
             MathError::Unsupported => write!(fmt, "type does not support operation"),
+            MathError::DivideByZero => write!(fmt, "division by zero"),
+            MathError::Overflow => write!(fmt, "result is too large for the type"),
+            MathError::NegativeExponent => {
+                write!(fmt, "integers may not be raised to a negative power")
+            }
         }
     }
 }
 
-fn parse_f64<F: Fn(f64, f64) -> f64>(lhs: &str, rhs: &str, operation: F) -> Result<f64, MathError> {
-    lhs.parse::<f64>().map_err(|_| MathError::LHS).and_then(
-        |lhs| rhs.parse::<f64>().map_err(|_| MathError::RHS).map(|rhs| operation(lhs, rhs)),
-    )
+/// Separates the name of an array from a selection of its elements, as in `array[-1]`.
+fn split_selection(key: &str) -> Option<(&str, &str)> {
+    if !key.ends_with(']') {
+        return None;
+    }
+    key.find('[').map(|id| (&key[..id], &key[id + 1..key.len() - 1]))
 }
 
-fn parse_i64<F: Fn(i64, i64) -> i64>(lhs: &str, rhs: &str, operation: F) -> Result<i64, MathError> {
-    lhs.parse::<i64>().map_err(|_| MathError::LHS).and_then(
-        |lhs| rhs.parse::<i64>().map_err(|_| MathError::RHS).map(|rhs| operation(lhs, rhs)),
-    )
+/// Assigns to the elements of an existing array which are selected by an index or a range. An
+/// index is assigned a single value, and may be updated by an arithmetic operator. A range is
+/// replaced by the values, unless it has a step, in which case each element that it selects is
+/// assigned one value in turn.
+fn assign_selection(
+    shell: &mut Shell,
+    name: &str,
+    selection: &str,
+    operator: Operator,
+    value: ReturnValue,
+) -> Result<(), String> {
+    let mut array = match shell.variables.get_array(name) {
+        Some(array) => array.clone(),
+        None => return Err(format!("{} is not an array", name)),
+    };
+    let len = array.len();
+    match selection.parse::<Select>() {
+        Ok(Select::Index(index)) => {
+            let id = match index.resolve(len) {
+                Some(id) if id < len => id,
+                _ => return Err(format!("index {} is out of bounds", selection)),
+            };
+            let value = match value {
+                ReturnValue::Str(value) => value,
+                ReturnValue::Vector(_) => return Err("cannot assign an array to an element".into()),
+            };
+            let value = math(&array[id], Primitive::Any, operator, &value)
+                .map_err(|why| why.to_string())?
+                .into_owned();
+            array[id] = value;
+        }
+        Ok(Select::Range(range)) => {
+            if operator != Operator::Equal {
+                return Err("arithmetic operators on ranges aren't supported".into());
+            }
+            let values = match value {
+                ReturnValue::Str(value) => vec![value],
+                ReturnValue::Vector(values) => values.into_iter().collect(),
+            };
+            let (start, length) = match range.bounds(len) {
+                Some((start, length)) if start <= len => (start, length.min(len - start)),
+                _ => return Err(format!("range {} is out of bounds", selection)),
+            };
+            if range.step() == 1 {
+                let end = start + length;
+                let replaced = array[..start]
+                    .iter()
+                    .cloned()
+                    .chain(values)
+                    .chain(array[end..].iter().cloned())
+                    .collect();
+                array = replaced;
+            } else {
+                let ids = (start..start + length)
+                    .filter(|id| (id - start) % range.step() == 0)
+                    .collect::<Vec<usize>>();
+                if ids.len() != values.len() {
+                    return Err(format!(
+                        "range {} selects {} elements, but {} values were given",
+                        selection,
+                        ids.len(),
+                        values.len()
+                    ));
+                }
+                for (id, value) in ids.into_iter().zip(values) {
+                    array[id] = value;
+                }
+            }
+        }
+        _ => return Err(format!("invalid index: {}", selection)),
+    }
+    shell.variables.set_array(name, array);
+    Ok(())
+}
+
+fn parse_f64<F>(lhs: &str, rhs: &str, operation: F) -> Result<f64, MathError>
+    where F: Fn(f64, f64) -> Result<f64, MathError>
+{
+    let lhs = units::parse_float(lhs).ok_or(MathError::LHS)?;
+    let rhs = units::parse_float(rhs).ok_or(MathError::RHS)?;
+    // Results which are too large to be represented become infinite.
+    match operation(lhs, rhs)? {
+        value if value.is_finite() => Ok(value),
+        _ => Err(MathError::Overflow),
+    }
 }
 
+fn parse_i64<F>(lhs: &str, rhs: &str, operation: F) -> Result<i64, MathError>
+    where F: Fn(i64, i64) -> Option<i64>
+{
+    let lhs = units::parse_integer(lhs).ok_or(MathError::LHS)?;
+    let rhs = units::parse_integer(rhs).ok_or(MathError::RHS)?;
+    operation(lhs, rhs).ok_or(MathError::Overflow)
+}
+
+/// Divides by a nonzero number, truncating the quotient towards zero for `//=`, while the
+/// remainder of `%=` takes the sign of the dividend. Integers are divided as integers, so that
+/// they never lose precision, and all other numbers as floats.
+fn divide(lhs: &str, key: Primitive, operator: Operator, rhs: &str) -> Result<String, MathError> {
+    if units::parse_float(rhs) == Some(0.0) {
+        return Err(MathError::DivideByZero);
+    }
+    let is_modulo = operator == Operator::Modulo;
+    let integers = || {
+        parse_i64(lhs, rhs, |lhs, rhs| {
+            if is_modulo { lhs.checked_rem(rhs) } else { lhs.checked_div(rhs) }
+        }).map(|value| value.to_string())
+    };
+    match key {
+        Primitive::Integer => integers(),
+        Primitive::Any
+            if units::parse_integer(lhs).is_some() && units::parse_integer(rhs).is_some() =>
+        {
+            integers()
+        }
+        Primitive::Any | Primitive::Float => {
+            let value = parse_f64(lhs, rhs, |lhs, rhs| {
+                Ok(if is_modulo { lhs % rhs } else { (lhs / rhs).trunc() })
+            })?;
+            Ok(value.to_string())
+        }
+        _ => Err(MathError::Unsupported),
+    }
+}
+
+/// Applies an operator to numbers of arbitrary precision. Quotients of the `bigint` type are
+/// truncated to integers, and those of the `decimal` type to `DIVISION_SCALE` decimal places.
+fn precise_math<'a>(
+    lhs: &str,
+    integer: bool,
+    operator: Operator,
+    value: &'a str,
+) -> Result<Cow<'a, str>, MathError> {
+    if operator == Operator::Equal {
+        return Ok(Cow::Borrowed(value));
+    }
+    let lhs = units::parse_decimal(lhs).ok_or(MathError::LHS)?;
+    let rhs = units::parse_decimal(value).ok_or(MathError::RHS)?;
+    let value = match operator {
+        Operator::Add => lhs.add(&rhs),
+        Operator::Subtract => lhs.subtract(&rhs),
+        Operator::Multiply => lhs.multiply(&rhs),
+        Operator::Divide if !integer => {
+            lhs.divide(&rhs, DIVISION_SCALE).ok_or(MathError::DivideByZero)?
+        }
+        Operator::Divide | Operator::IntegerDivide => {
+            lhs.divide(&rhs, 0).ok_or(MathError::DivideByZero)?
+        }
+        Operator::Modulo => lhs.remainder(&rhs).ok_or(MathError::DivideByZero)?,
+        Operator::Exponent if rhs.is_negative() => {
+            return Err(MathError::NegativeExponent)
+        }
+        Operator::Exponent => lhs.power(&rhs).ok_or(MathError::Overflow)?,
+        Operator::Equal => unreachable!(),
+    };
+    Ok(Cow::Owned(value.to_string()))
+}
+
+/// Applies an operator to the current value of a variable. Operations which would divide by
+/// zero, or whose results are too large for the type of the variable, are errors.
 fn math<'a>(
     lhs: &str,
     key: Primitive,
     operator: Operator,
     value: &'a str,
 ) -> Result<Cow<'a, str>, MathError> {
+    if key == Primitive::BigInt || key == Primitive::Decimal {
+        return precise_math(lhs, key == Primitive::BigInt, operator, value);
+    }
+    let is_float = Primitive::Any == key || Primitive::Float == key;
     let value: String = match operator {
-        Operator::Add => if Primitive::Any == key || Primitive::Float == key {
-            parse_f64(lhs, value, |lhs, rhs| lhs + rhs)?.to_string()
+        Operator::Add => if is_float {
+            parse_f64(lhs, value, |lhs, rhs| Ok(lhs + rhs))?.to_string()
         } else if let Primitive::Integer = key {
-            parse_i64(lhs, value, |lhs, rhs| lhs + rhs)?.to_string()
+            parse_i64(lhs, value, |lhs, rhs| lhs.checked_add(rhs))?.to_string()
         } else {
             return Err(MathError::Unsupported);
         },
         Operator::Divide => {
-            if Primitive::Any == key || Primitive::Float == key || Primitive::Integer == key {
-                parse_f64(lhs, value, |lhs, rhs| lhs / rhs)?.to_string()
+            if is_float || Primitive::Integer == key {
+                parse_f64(lhs, value, |lhs, rhs| {
+                    if rhs == 0.0 { Err(MathError::DivideByZero) } else { Ok(lhs / rhs) }
+                })?.to_string()
             } else {
                 return Err(MathError::Unsupported);
             }
         }
-        Operator::IntegerDivide => if Primitive::Any == key || Primitive::Float == key {
-            parse_i64(lhs, value, |lhs, rhs| lhs / rhs)?.to_string()
-        } else {
-            return Err(MathError::Unsupported);
-        },
-        Operator::Subtract => if Primitive::Any == key || Primitive::Float == key {
-            parse_f64(lhs, value, |lhs, rhs| lhs - rhs)?.to_string()
+        Operator::IntegerDivide | Operator::Modulo => divide(lhs, key, operator, value)?,
+        Operator::Subtract => if is_float {
+            parse_f64(lhs, value, |lhs, rhs| Ok(lhs - rhs))?.to_string()
         } else if let Primitive::Integer = key {
-            parse_i64(lhs, value, |lhs, rhs| lhs - rhs)?.to_string()
+            parse_i64(lhs, value, |lhs, rhs| lhs.checked_sub(rhs))?.to_string()
         } else {
             return Err(MathError::Unsupported);
         },
-        Operator::Multiply => if Primitive::Any == key || Primitive::Float == key {
-            parse_f64(lhs, value, |lhs, rhs| lhs * rhs)?.to_string()
+        Operator::Multiply => if is_float {
+            parse_f64(lhs, value, |lhs, rhs| Ok(lhs * rhs))?.to_string()
         } else if let Primitive::Integer = key {
-            parse_i64(lhs, value, |lhs, rhs| lhs * rhs)?.to_string()
+            parse_i64(lhs, value, |lhs, rhs| lhs.checked_mul(rhs))?.to_string()
         } else {
             return Err(MathError::Unsupported);
         },
-        Operator::Exponent => if Primitive::Any == key || Primitive::Float == key {
-            parse_f64(lhs, value, |lhs, rhs| lhs.powf(rhs))?.to_string()
+        Operator::Exponent => if is_float {
+            parse_f64(lhs, value, |lhs, rhs| Ok(lhs.powf(rhs)))?.to_string()
         } else if let Primitive::Integer = key {
-            parse_i64(lhs, value, |lhs, rhs| lhs.pow(rhs as u32))?.to_string()
+            if units::parse_integer(value).map_or(false, |rhs| rhs < 0) {
+                return Err(MathError::NegativeExponent);
+            }
+            parse_i64(lhs, value, |lhs, rhs| {
+                if rhs > u32::max_value() as i64 { None } else { lhs.checked_pow(rhs as u32) }
+            })?.to_string()
         } else {
             return Err(MathError::Unsupported);
         },
@@ -0,0 +1,103 @@
+//! An audit log of the commands which the shell executes, for shared servers which must keep a
+//! record of them. While the `audit` option is enabled, each command is appended to the file
+//! given by the `AUDIT_FILE` variable once it has been executed. Unlike the history, commands
+//! which are executed by scripts and functions are also recorded.
+
+use super::Shell;
+use super::errors::{ErrorKind, IonError};
+use parser::timestamp;
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use sys;
+use sys::privileges;
+
+/// Escapes backslashes, tabs, and newlines, so that each field of an entry is kept within its
+/// column, and each entry within its line.
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for character in field.chars() {
+        match character {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// An entry of the audit log, whose fields are separated by tabs: the time, the user, the PID of
+/// the shell, the working directory, the exit status, and the command.
+fn entry(time: &str, user: &str, pid: u32, directory: &str, status: i32, command: &str) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\n",
+        time,
+        user,
+        pid,
+        escape(directory),
+        status,
+        escape(command)
+    )
+}
+
+/// Records executed commands in the audit log.
+pub(crate) trait Audit {
+    /// Appends the command which was executed, and its exit status, to the audit log.
+    fn audit(&self, command: &str, status: i32);
+}
+
+impl Audit for Shell {
+    fn audit(&self, command: &str, status: i32) {
+        let path = match self.variables.get_var("AUDIT_FILE") {
+            Some(path) if !path.is_empty() => path,
+            _ => {
+                let error = IonError::new(ErrorKind::Audit, "AUDIT_FILE is not set");
+                self.report(error.context("audit"));
+                return;
+            }
+        };
+
+        // The effective user is recorded, as it determines what the command was permitted to do.
+        let (_, euid, ..) = privileges::ids();
+        let user = privileges::user_name(euid).unwrap_or_else(|| euid.to_string());
+        let directory = env::current_dir()
+            .map(|directory| directory.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| "?".into());
+        let pid = sys::getpid().unwrap_or(0);
+        let command = self.variables.mask(command);
+        let entry = entry(&timestamp(), &user, pid, &directory, status, &command);
+
+        // The log is opened for each entry, so that it may be rotated while the shell is running,
+        // and each entry is written at once, so that those of concurrent shells are not mixed.
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(&path)
+            .and_then(|mut log| log.write_all(entry.as_bytes()));
+        if let Err(why) = result {
+            let message = format!("unable to write to {}: {}", path, why);
+            self.report(IonError::new(ErrorKind::Audit, message).context("audit"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries() {
+        let time = "2018-02-05T14:03:09+0100";
+        assert_eq!(
+            entry(time, "deploy", 4242, "/srv/app", 0, "git pull"),
+            "2018-02-05T14:03:09+0100\tdeploy\t4242\t/srv/app\t0\tgit pull\n"
+        );
+        assert_eq!(
+            entry(time, "root", 1, "/tmp/a\tb", 127, "echo 'one\ntwo' \\"),
+            "2018-02-05T14:03:09+0100\troot\t1\t/tmp/a\\tb\t127\techo 'one\\ntwo' \\\\\n"
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Functions which are loaded on demand from the directories of `FPATH`, so that large libraries
+//! of functions do not slow the startup of the shell. Each file named `NAME.ion` within those
+//! directories defines the function `NAME`, and is sourced the first time that the function is
+//! called, or when it is loaded with the `autoload` builtin.
+
+use super::Shell;
+use super::errors::{ErrorKind, IonError};
+use builtins::source::source;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Finds the functions which may be autoloaded from the directories of `FPATH`, which are
+/// separated by colons. When a function is defined within several directories, the first is used.
+fn index(fpath: &str) -> BTreeMap<String, PathBuf> {
+    let mut files = BTreeMap::new();
+    for directory in fpath.split(':').filter(|directory| !directory.is_empty()) {
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if let Some(name) = function_name(&path) {
+                files.entry(name).or_insert(path);
+            }
+        }
+    }
+    files
+}
+
+/// The name of the function which a file defines, if it is named as `NAME.ion`.
+fn function_name(path: &Path) -> Option<String> {
+    if path.extension()? != "ion" || !path.is_file() {
+        return None;
+    }
+    let name = path.file_stem()?.to_str()?;
+    if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        Some(name.into())
+    } else {
+        None
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Autoloads {
+    /// The value of `FPATH` from which the functions were indexed.
+    fpath:   String,
+    files:   BTreeMap<String, PathBuf>,
+    /// The functions which are being loaded, so that a file which calls its own function as it
+    /// is sourced does not load itself again.
+    loading: Vec<String>,
+}
+
+impl Autoloads {
+    /// Indexes the directories of `FPATH` again if it has changed since they were indexed, or if
+    /// the index is forced to be rebuilt, such as after a file was added to a directory.
+    pub(crate) fn refresh(&mut self, fpath: &str, force: bool) {
+        if force || self.fpath != fpath {
+            self.files = index(fpath);
+            self.fpath = fpath.into();
+        }
+    }
+
+    /// The functions which may be autoloaded, and the files which define them, by name.
+    pub(crate) fn files(&self) -> &BTreeMap<String, PathBuf> { &self.files }
+}
+
+/// Loads functions from the directories of `FPATH`.
+pub(crate) trait Autoload {
+    /// Sources the file which defines the function, if the function is not yet defined, and is
+    /// neither a builtin nor a function which is already being loaded. Returns whether the
+    /// function is defined.
+    fn autoload(&mut self, name: &str) -> bool;
+}
+
+impl Autoload for Shell {
+    fn autoload(&mut self, name: &str) -> bool {
+        if self.functions.contains_key(name) {
+            return true;
+        }
+        if self.builtins.contains_key(name) || self.autoloads.loading.iter().any(|n| n == name) {
+            return false;
+        }
+        let fpath = self.variables.get_var_or_empty("FPATH");
+        if fpath.is_empty() {
+            return false;
+        }
+        self.autoloads.refresh(&fpath, false);
+        let path = match self.autoloads.files.get(name) {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None => return false,
+        };
+
+        // The status of the command which called the function is not changed by its file.
+        let previous_status = self.previous_status;
+        self.autoloads.loading.push(name.into());
+        let result = source(self, &["source", path.as_str()]);
+        self.autoloads.loading.pop();
+        self.previous_status = previous_status;
+
+        let message = match result {
+            Ok(()) if self.functions.contains_key(name) => return true,
+            Ok(()) => format!("{} does not define the function", path),
+            Err(why) => why.trim_right().trim_left_matches("ion: ").into(),
+        };
+        let context = format!("autoload: {}", name);
+        self.report(IonError::new(ErrorKind::Function, message).context(context));
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs::File;
+
+    #[test]
+    fn indexing() {
+        let root = env::temp_dir().join(format!("ion-autoload-{}", ::std::process::id()));
+        let (first, second) = (root.join("first"), root.join("second"));
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        for path in &[
+            first.join("greet.ion"),
+            first.join("notes.txt"),
+            second.join("greet.ion"),
+            second.join("git_root.ion"),
+            second.join("bad-name.ion"),
+        ] {
+            File::create(path).unwrap();
+        }
+        fs::create_dir_all(second.join("directory.ion")).unwrap();
+
+        let fpath = format!("{}::{}:/nonexistent", first.display(), second.display());
+        let files = index(&fpath);
+        fs::remove_dir_all(&root).unwrap();
+
+        let names = files.keys().map(String::as_str).collect::<Vec<&str>>();
+        assert_eq!(names, vec!["git_root", "greet"]);
+        assert_eq!(files["greet"], first.join("greet.ion"));
+        assert_eq!(files["git_root"], second.join("git_root.ion"));
+    }
+}
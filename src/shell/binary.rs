@@ -1,24 +1,35 @@
 //! Contains the binary logic of Ion.
 use super::{DirectoryStack, FlowLogic, JobControl, Shell, ShellHistory, Variables};
 use super::completer::*;
+use super::debugger::Debugger;
+use super::diagnostics::diagnose;
+use super::editor::{self, Completer, CursorPosition, Line};
+use super::environment;
+use super::errors::{self, IonError};
 use super::flags::*;
 use super::flow_control::Statement;
+use super::frecency::{Frecency, FrecencyCompleter};
 use super::library::IonLibrary;
+use super::profiler::{Format, Profiler};
+use super::recovery::{self, TerminalState};
+use super::sandbox::read_fake_commands;
+use super::segments::Segments;
 use super::status::*;
-use liner::{BasicCompleter, Buffer, Context, CursorPosition, Event, EventKind};
+use super::testing::run_tests;
+use super::translate::translate_script;
+use liner::BasicCompleter;
 use parser::*;
 use parser::QuoteTerminator;
 use smallstring::SmallString;
-use smallvec::SmallVec;
 use std::env;
 use std::fs::File;
-use std::io::{self, ErrorKind, Read, Write};
-use std::iter::{self, FromIterator};
-use std::mem;
+use std::io::{self, BufRead, ErrorKind, Read, Write};
+use std::iter;
 use std::os::unix::io::FromRawFd;
 use std::path::{Path, PathBuf};
 use std::process;
 use std::process::exit;
+use std::time::{Duration, Instant};
 use sys;
 use types::*;
 
@@ -26,23 +37,40 @@ pub(crate) trait Binary {
     /// Launches the shell, parses arguments, and then diverges into one of the `execution`
     /// paths.
     fn main(self);
-    /// Parses and executes the arguments that were supplied to the shell.
-    fn execute_arguments<A: Iterator<Item = String>>(&mut self, args: A);
-    /// Creates an interactive session that reads from a prompt provided by Liner.
+    /// Executes the command that was supplied to the shell, followed by the name to give it and
+    /// its positional arguments.
+    fn execute_arguments<A: Iterator<Item = String>>(&mut self, name: String, args: A);
+    /// Executes the script at the given path, with the remaining arguments as its positional
+    /// arguments.
+    fn execute_file<A: Iterator<Item = String>>(&mut self, path: String, args: A);
+    /// Executes a script read from standard input, with the remaining arguments as its
+    /// positional arguments.
+    fn execute_stdin<A: Iterator<Item = String>>(&mut self, name: String, args: A);
+    /// Creates an interactive session that reads from a prompt provided by the line editor,
+    /// which falls back to plain prompts for dumb terminals and pipes.
     fn execute_interactive(self);
-    /// Ensures that read statements from a script are terminated.
-    fn terminate_script_quotes<I: Iterator<Item = String>>(&mut self, lines: I) -> i32;
+    /// Ensures that read statements from a script are terminated, and executes them. Errors are
+    /// reported at their lines within the named script.
+    fn terminate_script_quotes<I: Iterator<Item = String>>(
+        &mut self,
+        script: &str,
+        lines: I,
+    ) -> i32;
     /// Ensures that read statements from the interactive prompt is terminated.
     fn terminate_quotes(&mut self, command: String) -> Result<String, ()>;
-    /// Ion's interface to Liner's `read_line` method, which handles everything related to
-    /// rendering, controlling, and getting input from the prompt.
+    /// Ion's interface to the line editor's `read_line` method, which handles everything related
+    /// to rendering, controlling, and getting input from the prompt.
     fn readln(&mut self) -> Option<String>;
-    /// Generates the prompt that will be used by Liner.
+    /// Generates the prompt that will be used by the line editor, within which asynchronous
+    /// segments are yet to be filled by `render_prompt`.
     fn prompt(&mut self) -> String;
     /// Display version information and exit
     fn display_version(&self);
     // Executes the PROMPT function, if it exists, and returns the output.
     fn prompt_fn(&mut self) -> Option<String>;
+    /// Sets the variables which describe the previous command, and executes the PRECMD
+    /// function, if it exists, before the prompt is displayed.
+    fn precmd(&mut self, duration: Duration);
 }
 
 impl Binary for Shell {
@@ -52,7 +80,7 @@ impl Binary for Shell {
                 Some(prompt) => prompt,
                 None => self.variables.get_var_or_empty("PROMPT"),
             };
-            expand_string(&rprompt, self, false).join(" ")
+            self.expand_prompt(&rprompt)
         } else {
             "    ".repeat(self.flow_control.level as usize)
         }
@@ -64,7 +92,7 @@ impl Binary for Shell {
             None => return None,
         };
 
-        let (read_fd, write_fd) = match sys::pipe2(0) {
+        let (read_fd, write_fd) = match sys::pipe2(sys::O_CLOEXEC) {
             Ok(fds) => fds,
             Err(why) => {
                 eprintln!("ion: unable to create pipe: {}", why);
@@ -80,11 +108,12 @@ impl Binary for Shell {
                 let _ = function.execute(self, &["ion"]);
                 exit(0);
             }
-            Ok(_) => {
+            Ok(pid) => {
                 let _ = sys::close(write_fd);
                 let mut child_stdout = unsafe { File::from_raw_fd(read_fd) };
                 let mut output = String::new();
                 let _ = child_stdout.read_to_string(&mut output);
+                let _ = sys::waitpid(pid);
                 Some(output)
             }
             Err(why) => {
@@ -96,15 +125,37 @@ impl Binary for Shell {
         }
     }
 
+    fn precmd(&mut self, duration: Duration) {
+        let status = self.previous_status;
+        let jobs = self.background.lock().unwrap().iter().filter(|job| !job.is_finished()).count();
+        let duration = format!("{}.{:03}", duration.as_secs(), duration.subsec_nanos() / 1_000_000);
+        self.variables.set_var("CMD_STATUS", &status.to_string());
+        self.variables.set_var("CMD_DURATION", &duration);
+        self.variables.set_var("JOB_COUNT", &jobs.to_string());
+
+        let function = match self.functions.get("PRECMD") {
+            Some(func) => func.clone(),
+            None => return,
+        };
+        // The hook is not a command of the user, and so its statuses are not recorded.
+        let history = self.variables.get_array("status_history").cloned();
+        if let Err(why) = function.execute(self, &["PRECMD"]) {
+            self.report(why.describe("PRECMD"));
+        }
+        self.variables.set_array("status_history", history.unwrap_or_default());
+        self.previous_status = status;
+        self.variables.set_var("?", &status.to_string());
+    }
+
     fn readln(&mut self) -> Option<String> {
         {
             let vars_ptr = &self.variables as *const Variables;
             let dirs_ptr = &self.directory_stack as *const DirectoryStack;
 
             // Collects the current list of values from history for completion.
-            let history = &self.context.as_ref().unwrap().history.buffers.iter()
-                // Map each underlying `liner::Buffer` into a `String`.
-                .map(|x| x.chars().cloned().collect())
+            let history = &self.editor.as_ref().unwrap().history().into_iter()
+                // Map each command into a `SmallString`.
+                .map(SmallString::from)
                 // Collect each result into a vector to avoid borrowing issues.
                 .collect::<Vec<SmallString>>();
 
@@ -113,95 +164,105 @@ impl Binary for Shell {
                 let funcs = &self.functions;
                 let vars = &self.variables;
                 let builtins = &self.builtins;
-
-                let line = self.context.as_mut().unwrap().read_line(
-                    prompt,
-                    &mut move |Event { editor, kind }| {
-                        if let EventKind::BeforeComplete = kind {
-                            let (words, pos) = editor.get_words_and_cursor_position();
-
-                            let filename = match pos {
-                                CursorPosition::InWord(index) => index > 0,
-                                CursorPosition::InSpace(Some(_), _) => true,
-                                CursorPosition::InSpace(None, _) => false,
-                                CursorPosition::OnWordLeftEdge(index) => index >= 1,
-                                CursorPosition::OnWordRightEdge(index) => {
-                                    match (words.into_iter().nth(index), env::current_dir()) {
-                                        (Some((start, end)), Ok(file)) => {
-                                            let filename =
-                                                editor.current_buffer().range(start, end);
-                                            complete_as_file(file, filename, index)
-                                        }
-                                        _ => false,
+                let segments = &self.segments;
+                let frecency_file = self.frecency_file();
+
+                let line = self.editor.as_mut().unwrap().read_line(
+                    render_prompt(&prompt, segments, vars),
+                    &mut move |line: &Line| -> Option<Box<Completer>> {
+                        // The arguments of `j` are completed with the directories which they
+                        // match.
+                        let jump_database = match (line.words.first(), frecency_file.as_ref()) {
+                            (Some(&(start, end)), Some(database))
+                                if line.range(start, end) == "j" =>
+                            {
+                                Some(database.clone())
+                            }
+                            _ => None,
+                        };
+
+                        let filename = match line.position {
+                            CursorPosition::InWord(index) => index > 0,
+                            CursorPosition::InSpace(Some(_), _) => true,
+                            CursorPosition::InSpace(None, _) => false,
+                            CursorPosition::OnWordLeftEdge(index) => index >= 1,
+                            CursorPosition::OnWordRightEdge(index) => {
+                                match (line.words.get(index), env::current_dir()) {
+                                    (Some(&(start, end)), Ok(file)) => {
+                                        let filename = line.range(start, end);
+                                        complete_as_file(file, filename, index)
                                     }
+                                    _ => false,
                                 }
-                            };
+                            }
+                        };
 
-                            if filename {
-                                if let Ok(current_dir) = env::current_dir() {
-                                    if let Some(url) = current_dir.to_str() {
-                                        let completer =
-                                            IonFileCompleter::new(Some(url), dirs_ptr, vars_ptr);
-                                        mem::replace(
-                                            &mut editor.context().completer,
-                                            Some(Box::new(completer)),
-                                        );
-                                    }
-                                }
+                        if let (true, Some(database)) = (filename, jump_database) {
+                            Some(Box::new(FrecencyCompleter::new(database)))
+                        } else if filename {
+                            let current_dir = env::current_dir().ok();
+                            current_dir.as_ref().and_then(|dir| dir.to_str()).map(|url| {
+                                let completer =
+                                    IonFileCompleter::new(Some(url), dirs_ptr, vars_ptr);
+                                Box::new(completer) as Box<Completer>
+                            })
+                        } else {
+                            // Creates a list of definitions from the shell environment that
+                            // will be used
+                            // in the creation of a custom completer.
+                            let words = builtins.keys().iter()
+                            // Add built-in commands to the completer's definitions.
+                            .map(|&s| Identifier::from(s))
+                            // Add the history list to the completer's definitions.
+                            .chain(history.iter().cloned())
+                            // Add the aliases to the completer's definitions.
+                            .chain(vars.aliases.keys().cloned())
+                            // Add the list of available functions to the completer's definitions.
+                            .chain(funcs.keys().cloned())
+                            // Add the list of available variables to the completer's definitions.
+                            // TODO: We should make it free to do String->SmallString
+                            //       and mostly free to go back (free if allocated)
+                            .chain(vars.get_vars().map(|s| ["$", &s].concat().into()))
+                            .collect();
+
+                            // Initialize a new completer from the definitions collected.
+                            let custom_completer = BasicCompleter::new(words);
+
+                            // Creates completers containing definitions from all directories
+                            // listed
+                            // in the environment's **$PATH** variable.
+                            let mut file_completers = if let Ok(val) = env::var("PATH") {
+                                val.split(sys::PATH_SEPARATOR)
+                                    .map(|s| IonFileCompleter::new(Some(s), dirs_ptr, vars_ptr))
+                                    .collect()
                             } else {
-                                // Creates a list of definitions from the shell environment that
-                                // will be used
-                                // in the creation of a custom completer.
-                                let words = builtins.keys().iter()
-                                // Add built-in commands to the completer's definitions.
-                                .map(|&s| Identifier::from(s))
-                                // Add the history list to the completer's definitions.
-                                .chain(history.iter().cloned())
-                                // Add the aliases to the completer's definitions.
-                                .chain(vars.aliases.keys().cloned())
-                                // Add the list of available functions to the completer's definitions.
-                                .chain(funcs.keys().cloned())
-                                // Add the list of available variables to the completer's definitions.
-                                // TODO: We should make it free to do String->SmallString
-                                //       and mostly free to go back (free if allocated)
-                                .chain(vars.get_vars().map(|s| ["$", &s].concat().into()))
-                                .collect();
-
-                                // Initialize a new completer from the definitions collected.
-                                let custom_completer = BasicCompleter::new(words);
-
-                                // Creates completers containing definitions from all directories
-                                // listed
-                                // in the environment's **$PATH** variable.
-                                let mut file_completers = if let Ok(val) = env::var("PATH") {
-                                    val.split(sys::PATH_SEPARATOR)
-                                        .map(|s| IonFileCompleter::new(Some(s), dirs_ptr, vars_ptr))
-                                        .collect()
-                                } else {
-                                    vec![IonFileCompleter::new(Some("/bin/"), dirs_ptr, vars_ptr)]
-                                };
-
-                                // Also add files/directories in the current directory to the
-                                // completion list.
-                                if let Ok(current_dir) = env::current_dir() {
-                                    if let Some(url) = current_dir.to_str() {
-                                        file_completers.push(
-                                            IonFileCompleter::new(Some(url), dirs_ptr, vars_ptr),
-                                        );
-                                    }
-                                }
+                                vec![IonFileCompleter::new(Some("/bin/"), dirs_ptr, vars_ptr)]
+                            };
 
-                                // Merge the collected definitions with the file path definitions.
-                                let completer =
-                                    MultiCompleter::new(file_completers, custom_completer);
-
-                                // Replace the shell's current completer with the newly-created
-                                // completer.
-                                mem::replace(
-                                    &mut editor.context().completer,
-                                    Some(Box::new(completer)),
-                                );
+                            // Also add files/directories in the current directory to the
+                            // completion list.
+                            if let Ok(current_dir) = env::current_dir() {
+                                if let Some(url) = current_dir.to_str() {
+                                    file_completers.push(
+                                        IonFileCompleter::new(Some(url), dirs_ptr, vars_ptr),
+                                    );
+                                }
                             }
+
+                            // Merge the collected definitions with the file path definitions.
+                            let completer =
+                                MultiCompleter::new(file_completers, custom_completer);
+
+                            // Replace the editor's current completer with the newly-created
+                            // completer.
+                            Some(Box::new(completer))
+                        }
+                    },
+                    &mut || {
+                        if segments.collect() {
+                            Some(render_prompt(&prompt, segments, vars))
+                        } else {
+                            None
                         }
                     },
                 );
@@ -215,7 +276,7 @@ impl Binary for Shell {
                     Err(err) => {
                         let stderr = io::stderr();
                         let mut stderr = stderr.lock();
-                        let _ = writeln!(stderr, "ion: liner: {}", err);
+                        let _ = writeln!(stderr, "ion: line editor: {}", err);
                         return None;
                     }
                 }
@@ -226,31 +287,49 @@ impl Binary for Shell {
         self.exit(previous_status);
     }
 
-    fn terminate_script_quotes<I: Iterator<Item = String>>(&mut self, mut lines: I) -> i32 {
-        while let Some(command) = lines.next() {
+    fn terminate_script_quotes<I: Iterator<Item = String>>(
+        &mut self,
+        script: &str,
+        lines: I,
+    ) -> i32 {
+        // Lines are counted from one, so that errors may be reported on the line at which their
+        // command begins, and so that a block which is never ended is reported where it began.
+        let mut lines = lines.enumerate().map(|(id, line)| (id + 1, line));
+        let mut block_line = 0;
+        while let Some((line, command)) = lines.next() {
             let mut buffer = QuoteTerminator::new(command);
             while !buffer.check_termination() {
                 loop {
-                    if let Some(command) = lines.next() {
+                    if let Some((_, command)) = lines.next() {
                         buffer.append(command);
                         break;
                     } else {
-                        let stderr = io::stderr();
-                        let _ = writeln!(stderr.lock(), "ion: unterminated quote in script");
+                        self.location = None;
+                        let error = IonError::new(errors::ErrorKind::Syntax, "unterminated quote");
+                        self.report(error.at(Some(Location::new(script, line))));
                         return FAILURE;
                     }
                 }
             }
+            self.break_flow = false;
+            let level = self.flow_control.level;
+            self.location = Some(Location::new(script, line));
             self.on_command(&buffer.consume());
+            if level == 0 && self.flow_control.level != 0 {
+                block_line = line;
+            }
         }
+        self.location = None;
 
         // The flow control level being non zero means that we have a statement that has
         // only been partially parsed.
         if self.flow_control.level != 0 {
-            eprintln!(
-                "ion: unexpected end of script: expected end block for `{}`",
+            let message = format!(
+                "unexpected end of script: expected end block for `{}`",
                 self.flow_control.current_statement.short()
             );
+            let error = IonError::new(errors::ErrorKind::Syntax, message);
+            self.report(error.at(Some(Location::new(script, block_line))));
             return FAILURE;
         }
 
@@ -272,17 +351,14 @@ impl Binary for Shell {
         Ok(terminated)
     }
 
-    fn execute_arguments<A: Iterator<Item = String>>(&mut self, mut args: A) {
-        if let Some(mut arg) = args.next() {
-            for argument in args {
-                arg.push(' ');
-                if argument == "" {
-                    arg.push_str("''");
-                } else {
-                    arg.push_str(&argument);
-                }
-            }
-            self.on_command(&arg);
+    fn execute_arguments<A: Iterator<Item = String>>(&mut self, name: String, mut args: A) {
+        if let Some(command) = args.next() {
+            // As with `sh -c`, the first argument after the command is its name, and the
+            // remaining arguments are its positional arguments.
+            let name = args.next().unwrap_or(name);
+            let array = iter::once(name).chain(args).collect();
+            self.variables.set_array("args", array);
+            self.on_command(&command);
         } else {
             let stderr = io::stderr();
             let mut stderr = stderr.lock();
@@ -299,20 +375,38 @@ impl Binary for Shell {
         }
     }
 
+    fn execute_file<A: Iterator<Item = String>>(&mut self, path: String, args: A) {
+        let array = iter::once(path.clone()).chain(args).collect();
+        self.variables.set_array("args", array);
+        if let Err(err) = self.execute_script(&path) {
+            eprintln!("ion: {}: {}", path, err);
+            self.previous_status = FAILURE;
+        }
+    }
+
+    fn execute_stdin<A: Iterator<Item = String>>(&mut self, name: String, args: A) {
+        let array = iter::once(name.clone()).chain(args).collect();
+        self.variables.set_array("args", array);
+        let stdin = io::stdin();
+        let lines = stdin.lock().lines().filter_map(Result::ok);
+        if FAILURE == self.terminate_script_quotes(&name, lines) {
+            self.previous_status = FAILURE;
+        }
+    }
+
     fn execute_interactive(mut self) {
-        self.context = Some({
-            let mut context = Context::new();
-            context.word_divider_fn = Box::new(word_divide);
+        self.editor = Some({
+            let mut editor = editor::new();
             if "1" == self.variables.get_var_or_empty("HISTFILE_ENABLED") {
                 let path = self.variables.get_var("HISTFILE").expect("shell didn't set HISTFILE");
-                context.history.set_file_name(Some(path.clone()));
+                editor.set_history_file(Some(path.clone()));
                 if !Path::new(path.as_str()).exists() {
                     eprintln!("ion: creating history file at \"{}\"", path);
                     if let Err(why) = File::create(path) {
                         eprintln!("ion: could not create history file: {}", why);
                     }
                 }
-                match context.history.load_history() {
+                match editor.load_history() {
                     Ok(()) => {
                         // pass
                     }
@@ -325,19 +419,49 @@ impl Binary for Shell {
                     }
                 }
             }
-            context
+            editor
         });
 
         self.evaluate_init_file();
 
         self.variables.set_array("args", iter::once(env::args().next().unwrap()).collect());
+        // Panics within commands are reported, and the session continues.
+        recovery::install_hook();
 
+        let mut duration = Duration::from_secs(0);
+        let mut finished = None;
         loop {
+            // Background jobs that have finished are reported before each new prompt.
+            self.report_finished_jobs();
+            self.precmd(duration);
+            self.osc_prompt(finished.take());
+            duration = Duration::from_secs(0);
             if let Some(command) = self.readln() {
                 if !command.is_empty() {
                     if let Ok(command) = self.terminate_quotes(command.replace("\\\n", "")) {
                         let cmd = command.trim();
-                        self.on_command(cmd);
+                        // Expose the command being executed, and the one before it, to
+                        // external tools that are run by it.
+                        if let Some(last) = self.last_command() {
+                            env::set_var("ION_LAST_COMMAND", last);
+                        }
+                        env::set_var("ION_COMMAND", cmd);
+                        self.break_flow = false;
+                        let terminal = TerminalState::save();
+                        self.osc_command(cmd);
+                        let start = Instant::now();
+                        let result = recovery::catch(|| self.on_command(cmd));
+                        duration = start.elapsed();
+                        finished = Some(self.previous_status);
+                        if let Err(why) = result {
+                            terminal.restore();
+                            recovery::reset(&mut self);
+                            eprintln!("ion: internal error: {}", why);
+                            eprintln!(
+                                "ion: this is a bug, which may be reported at \
+                                 https://github.com/redox-os/ion/issues"
+                            );
+                        }
 
                         if cmd.starts_with('~') {
                             if !cmd.ends_with('/')
@@ -374,25 +498,125 @@ impl Binary for Shell {
     }
 
     fn main(mut self) {
-        let mut args = env::args().skip(1);
-        while let Some(path) = args.next() {
-            match path.as_str() {
+        let mut args = env::args().collect::<Vec<String>>();
+        // The kernel supplies every option on a shebang line, such as `#!/bin/ion -n -l`, as a
+        // single argument.
+        if args.len() > 2 && args[1].starts_with('-') && args[1].contains(' ') {
+            let options = args.remove(1);
+            for (id, option) in options.split_whitespace().enumerate() {
+                args.insert(1 + id, option.into());
+            }
+        }
+
+        // A command given to `-c` is executed within the process group of the program which
+        // invoked the shell, so that the terminal is left to that program.
+        let command = args.iter()
+            .skip(1)
+            .take_while(|arg| arg.starts_with('-') && *arg != "--")
+            .any(|arg| arg == "-c");
+        if !command {
+            if let Ok(pid) = sys::getpid() {
+                if sys::setpgid(0, pid).is_ok() {
+                    let _ = sys::tcsetpgrp(0, pid);
+                }
+            }
+        }
+
+        let mut args = args.into_iter();
+        let name = args.next().unwrap_or_else(|| "ion".into());
+        // A login shell is invoked with a leading dash in its name, such as `-ion`.
+        let mut login = name.starts_with('-');
+        if login {
+            self.evaluate_profile_files();
+        }
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
                 "-n" => {
                     self.flags |= NO_EXEC;
                     continue;
                 }
-                "-c" => self.execute_arguments(args),
-                "--version" => self.display_version(),
-                _ => {
-                    let mut array = SmallVec::from_iter(Some(path.clone().into()));
-                    for arg in args {
-                        array.push(arg.into());
+                "--dry-run" => {
+                    self.flags |= DRY_RUN;
+                    continue;
+                }
+                "--clean-env" => {
+                    environment::sanitize();
+                    self.flags |= CLEAN_ENV;
+                    continue;
+                }
+                _ if arg.starts_with("--sandbox") => {
+                    let table = match &arg[9..] {
+                        "" => None,
+                        table if table.starts_with('=') => Some(&table[1..]),
+                        _ => {
+                            eprintln!("ion: invalid option: {}", arg);
+                            self.exit(BAD_ARG);
+                        }
+                    };
+                    if let Some(table) = table {
+                        match read_fake_commands(table) {
+                            Ok(commands) => self.fake_commands = commands,
+                            Err(why) => {
+                                eprintln!("ion: sandbox: {}", why);
+                                self.exit(BAD_ARG);
+                            }
+                        }
+                    }
+                    self.set_option("sandbox", true);
+                    continue;
+                }
+                "--confirm" => {
+                    self.confirming = true;
+                    continue;
+                }
+                "--debug" => {
+                    self.debugger = Some(Debugger::new());
+                    continue;
+                }
+                _ if arg.starts_with("--warn=") => match self.warnings.configure(&arg[7..]) {
+                    Ok(()) => continue,
+                    Err(why) => {
+                        eprintln!("ion: warn: {}", why);
+                        self.exit(BAD_ARG);
+                    }
+                },
+                _ if arg.starts_with("--profile") => match Format::parse(&arg[9..]) {
+                    Some(format) => {
+                        self.profiler = Some(Profiler::new(format));
+                        continue;
+                    }
+                    None => {
+                        eprintln!("ion: invalid option: {}", arg);
+                        self.exit(BAD_ARG);
                     }
-                    self.variables.set_array("args", array);
-                    if let Err(err) = self.execute_script(&path) {
-                        eprintln!("ion: {}", err);
+                },
+                "-l" | "--login" => {
+                    if !login {
+                        login = true;
+                        self.evaluate_profile_files();
                     }
+                    continue;
+                }
+                "-c" => self.execute_arguments(name, args),
+                "-s" => self.execute_stdin(name, args),
+                "--version" => self.display_version(),
+                "--diagnose" => process::exit(diagnose(&mut self)),
+                "translate" => {
+                    let script = args.next();
+                    process::exit(translate_script(script.as_ref().map(String::as_str)));
                 }
+                "test" => process::exit(run_tests(&args.collect::<Vec<String>>(), &self)),
+                // Every argument after `--` is a script and its arguments, even if it begins
+                // with a dash.
+                "--" => match args.next() {
+                    Some(path) => self.execute_file(path, args),
+                    None => break,
+                },
+                _ if arg.starts_with('-') && arg.len() > 1 => {
+                    eprintln!("ion: invalid option: {}", arg);
+                    self.exit(BAD_ARG);
+                }
+                _ => self.execute_file(arg, args),
             }
 
             self.wait_for_background();
@@ -409,42 +633,17 @@ impl Binary for Shell {
     }
 }
 
-fn word_divide(buf: &Buffer) -> Vec<(usize, usize)> {
-    let mut res = Vec::new();
-    let mut word_start = None;
-
-    macro_rules! check_boundary {
-        ($c:expr, $index:expr, $escaped:expr) => {{
-            if let Some(start) = word_start {
-                if $c == ' ' && !$escaped {
-                    res.push((start, $index));
-                    word_start = None;
-                }
-            } else {
-                if $c != ' ' {
-                    word_start = Some($index);
-                }
-            }
-        }}
-    }
-
-    let mut iter = buf.chars().enumerate();
-    while let Some((i, &c)) = iter.next() {
-        match c {
-            '\\' => {
-                if let Some((_, &cnext)) = iter.next() {
-                    // We use `i` in order to include the backslash as part of the word
-                    check_boundary!(cnext, i, true);
-                }
-            }
-            c => check_boundary!(c, i, false),
+/// Fills the asynchronous segments of the prompt, and then shortens it if it would leave too
+/// few columns for the command.
+fn render_prompt(prompt: &str, segments: &Segments, vars: &Variables) -> String {
+    let prompt = segments.fill(prompt, &vars.get_var_or_empty("PROMPT_PLACEHOLDER"));
+    let reserved = vars.get_var("PROMPT_INPUT_WIDTH").and_then(|width| width.parse::<usize>().ok());
+    match (reserved, sys::terminal_width(sys::STDOUT_FILENO)) {
+        (Some(reserved), Ok(columns)) if columns > reserved => {
+            editor::truncate_prompt(&prompt, columns - reserved)
         }
+        _ => prompt,
     }
-    if let Some(start) = word_start {
-        // When start has been set, that means we have encountered a full word.
-        res.push((start, buf.num_chars()));
-    }
-    res
 }
 
 /// Infer if the given filename is actually a partial filename
@@ -0,0 +1,49 @@
+//! Captures the output which is written to one of the shell's own streams, such as its standard
+//! output, while commands are executed within the shell itself, rather than within a subshell.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::thread::{self, JoinHandle};
+use sys;
+
+/// A stream of the shell which is redirected into a pipe until the capture is finished.
+pub(crate) struct Capture {
+    fd:     RawFd,
+    backup: File,
+    reader: JoinHandle<String>,
+}
+
+impl Capture {
+    /// Redirects the stream into a pipe, which is read from another thread, so that output
+    /// which does not fit within the pipe's buffer cannot block the shell.
+    pub(crate) fn start(fd: RawFd) -> Result<Capture, String> {
+        let (mut read, write) = match sys::pipe2(sys::O_CLOEXEC) {
+            Ok(fds) => unsafe { (File::from_raw_fd(fds.0), File::from_raw_fd(fds.1)) },
+            Err(why) => return Err(format!("unable to create pipe: {}", why)),
+        };
+        let backup = match sys::dup(fd) {
+            Ok(backup) => unsafe { File::from_raw_fd(backup) },
+            Err(why) => return Err(format!("failed to `dup` file descriptor {}: {}", fd, why)),
+        };
+
+        let reader = thread::spawn(move || {
+            let mut output = String::new();
+            let _ = read.read_to_string(&mut output);
+            output
+        });
+        let _ = sys::dup2(write.as_raw_fd(), fd);
+        Ok(Capture { fd, backup, reader })
+    }
+
+    /// Restores the stream, and collects everything which was written to it in the meantime.
+    pub(crate) fn finish(self) -> String {
+        if self.fd == sys::STDOUT_FILENO {
+            let _ = io::stdout().flush();
+        }
+        // Restoring the stream closes the last write end of the pipe, ending the output.
+        let _ = sys::dup2(self.backup.as_raw_fd(), self.fd);
+        drop(self.backup);
+        self.reader.join().unwrap_or_default()
+    }
+}
@@ -0,0 +1,104 @@
+//! Interactive confirmation of the external commands of a script, which is enabled with
+//! `ion --confirm`, so that a script which is not yet trusted, such as one that was downloaded to
+//! provision a machine, may be reviewed as it runs. Each pipeline that would execute an external
+//! command is printed once it has been expanded, and is only executed if the user confirms it.
+
+use super::Shell;
+use super::errors::{ErrorKind, IonError};
+use super::flags::{DRY_RUN, NO_EXEC};
+use super::pipe_exec::is_external;
+use super::status::*;
+use parser::pipelines::Pipeline;
+use std::io::{self, Write};
+
+const HELP: &'static str = "\
+answers:
+    yes, y     execute the command
+    no, n      skip the command, which fails (the default)
+    all, a     execute this command and every following command
+    quit, q    exit the shell";
+
+#[derive(Debug, PartialEq)]
+enum Answer {
+    Yes,
+    No,
+    All,
+    Quit,
+}
+
+fn parse_answer(answer: &str) -> Option<Answer> {
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(Answer::Yes),
+        "" | "n" | "no" => Some(Answer::No),
+        "a" | "all" => Some(Answer::All),
+        "q" | "quit" => Some(Answer::Quit),
+        _ => None,
+    }
+}
+
+/// Asks the user to confirm external commands before they are executed.
+pub(crate) trait Ask {
+    /// Prints the pipeline and reads the answer of the user from stdin, if the pipeline would
+    /// execute an external command. Returns whether the pipeline may be executed.
+    fn ask(&mut self, pipeline: &Pipeline) -> bool;
+}
+
+impl Ask for Shell {
+    fn ask(&mut self, pipeline: &Pipeline) -> bool {
+        if !self.confirming || self.flags & (NO_EXEC | DRY_RUN) != 0 {
+            return true;
+        }
+        let external = pipeline.items.iter().any(|item| {
+            is_external(self, item.job.args.first().map_or("", |arg| arg.as_str()))
+        });
+        if !external {
+            return true;
+        }
+
+        let command = self.variables.mask(&pipeline.to_string());
+        let stdin = io::stdin();
+        let answer = loop {
+            let stderr = io::stderr();
+            let _ = write!(stderr.lock(), "ion: confirm: {} [y/n/a/q] ", command);
+            let mut answer = String::new();
+            match stdin.read_line(&mut answer) {
+                // A script which can no longer be confirmed may not continue.
+                Ok(0) | Err(_) => break Answer::Quit,
+                Ok(_) => match parse_answer(&answer) {
+                    Some(answer) => break answer,
+                    None => eprintln!("{}", HELP),
+                },
+            }
+        };
+
+        match answer {
+            Answer::Yes => true,
+            Answer::No => {
+                let error = IonError::new(ErrorKind::Pipeline, "command was skipped");
+                self.report(error.context("confirm"));
+                false
+            }
+            Answer::All => {
+                self.confirming = false;
+                true
+            }
+            Answer::Quit => self.exit(FAILURE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers() {
+        assert_eq!(parse_answer("y\n"), Some(Answer::Yes));
+        assert_eq!(parse_answer("Yes\n"), Some(Answer::Yes));
+        assert_eq!(parse_answer("\n"), Some(Answer::No));
+        assert_eq!(parse_answer("no"), Some(Answer::No));
+        assert_eq!(parse_answer(" a \n"), Some(Answer::All));
+        assert_eq!(parse_answer("quit\n"), Some(Answer::Quit));
+        assert_eq!(parse_answer("maybe\n"), None);
+    }
+}
@@ -0,0 +1,240 @@
+//! An interactive debugger for scripts, which is enabled with `ion --debug`. The shell pauses
+//! before the first statement, at breakpoints, and after each step, so that the variables of the
+//! script may be inspected before it continues.
+
+use super::Shell;
+use super::flow_control::Statement;
+use super::status::*;
+use parser::{expand_string, Location};
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+use std::path::Path;
+
+const HELP: &'static str = "\
+commands:
+    step, s                step to the next statement (the default)
+    continue, c            continue until the next breakpoint
+    break, b [LOCATION]    pause at SCRIPT:LINE or at a function, or list breakpoints
+    delete, d LOCATION     remove a breakpoint
+    print, p WORDS...      expand and print words, such as $var or @array
+    backtrace, bt          print the functions which are being called
+    quit, q                exit the shell
+    help, h                print this help";
+
+/// Where the debugger should pause: at a line of a script, or when a function is called.
+#[derive(Debug, PartialEq)]
+enum Breakpoint {
+    Line(String, usize),
+    Function(String),
+}
+
+impl Breakpoint {
+    fn parse(spec: &str) -> Breakpoint {
+        match spec.rfind(':').map(|id| (&spec[..id], spec[id + 1..].parse::<usize>())) {
+            Some((script, Ok(line))) => Breakpoint::Line(script.into(), line),
+            _ => Breakpoint::Function(spec.into()),
+        }
+    }
+
+    /// A script may be given by any suffix of its path, such as its file name.
+    fn is_at(&self, location: &Location) -> bool {
+        match *self {
+            Breakpoint::Line(ref script, line) => {
+                line == location.line && Path::new(&location.script).ends_with(script)
+            }
+            Breakpoint::Function(_) => false,
+        }
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Breakpoint::Line(ref script, line) => write!(f, "{}:{}", script, line),
+            Breakpoint::Function(ref name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// The state of the debugger, which persists between pauses.
+#[derive(Debug, Default)]
+pub(crate) struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    /// Set when the shell should pause before the next statement.
+    stepping:    bool,
+    /// The location of the last command, so that a line with several statements pauses once.
+    last:        Option<Location>,
+}
+
+impl Debugger {
+    /// Creates a debugger which pauses before the first statement.
+    pub(crate) fn new() -> Debugger {
+        Debugger {
+            stepping: true,
+            ..Debugger::default()
+        }
+    }
+
+    /// Pauses before the first statement of a function which has a breakpoint.
+    pub(crate) fn call(&mut self, function: &str) {
+        let is_breakpoint = |breakpoint: &Breakpoint| match *breakpoint {
+            Breakpoint::Function(ref name) => name == function,
+            Breakpoint::Line(..) => false,
+        };
+        if self.breakpoints.iter().any(is_breakpoint) {
+            self.stepping = true;
+        }
+    }
+
+    /// Executes a command which only concerns the debugger itself, returning whether the
+    /// shell should resume execution.
+    fn execute(&mut self, command: Option<&str>, argument: Option<&str>) -> bool {
+        match command {
+            None | Some("s") | Some("step") => {
+                self.stepping = true;
+                return true;
+            }
+            Some("c") | Some("continue") => {
+                self.stepping = false;
+                return true;
+            }
+            Some("b") | Some("break") => match argument {
+                Some(spec) => self.breakpoints.push(Breakpoint::parse(spec)),
+                None => for (id, breakpoint) in self.breakpoints.iter().enumerate() {
+                    eprintln!("{}: {}", id, breakpoint);
+                },
+            },
+            Some("d") | Some("delete") => match argument.map(Breakpoint::parse) {
+                Some(breakpoint) => self.breakpoints.retain(|other| *other != breakpoint),
+                None => eprintln!("ion: debug: delete: expected a breakpoint"),
+            },
+            Some("h") | Some("help") => eprintln!("{}", HELP),
+            Some(command) => eprintln!("ion: debug: unknown command: {}", command),
+        }
+        false
+    }
+
+    /// Decides whether to pause before a statement, which is located if it is executed directly
+    /// from a script.
+    fn should_pause(&mut self, location: Option<&Location>) -> bool {
+        if let Some(location) = location {
+            if self.last.as_ref() != Some(location) {
+                self.last = Some(location.clone());
+                if self.breakpoints.iter().any(|breakpoint| breakpoint.is_at(location)) {
+                    self.stepping = true;
+                }
+            }
+        }
+        self.stepping
+    }
+}
+
+/// Pauses the shell for the debugger, before statements are executed.
+pub(crate) trait Debugging {
+    /// Pauses before the given statement if a breakpoint was reached, or if the debugger is
+    /// stepping through statements, and reads debugger commands until execution resumes.
+    /// Statements at the top level are located at the command of the script which contains them.
+    fn debug_statement(&mut self, statement: &Statement, toplevel: bool);
+}
+
+impl Debugging for Shell {
+    fn debug_statement(&mut self, statement: &Statement, toplevel: bool) {
+        let location = if toplevel { self.location.clone() } else { None };
+        let location = location.as_ref();
+        match self.debugger {
+            Some(ref mut debugger) => if !debugger.should_pause(location) {
+                return;
+            },
+            None => return,
+        }
+
+        let description = statement.describe();
+        let function = self.flow_control.call_stack.last().map(|frame| frame.function.clone());
+        match (location, function) {
+            (_, Some(function)) => eprintln!("ion: debug: in {}: {}", function, description),
+            (Some(location), None) => eprintln!("ion: debug: at {}: {}", location, description),
+            (None, None) => eprintln!("ion: debug: {}", description),
+        }
+
+        let stdin = io::stdin();
+        loop {
+            let stderr = io::stderr();
+            let _ = write!(stderr.lock(), "(debug) ");
+            let mut command = String::new();
+            match stdin.read_line(&mut command) {
+                Ok(0) | Err(_) => {
+                    // The script runs to completion once there are no more commands to read.
+                    self.debugger = None;
+                    return;
+                }
+                Ok(_) => (),
+            }
+
+            let mut words = command.split_whitespace();
+            match words.next() {
+                Some("p") | Some("print") => {
+                    let words = words.collect::<Vec<&str>>().join(" ");
+                    eprintln!("{}", expand_string(&words, self, false).join(" "));
+                }
+                Some("bt") | Some("backtrace") => {
+                    for (id, frame) in self.flow_control.call_stack.iter().rev().enumerate() {
+                        eprintln!("{}: {}", id, frame);
+                    }
+                }
+                Some("q") | Some("quit") => self.exit(FAILURE),
+                command => if self.debugger.as_mut().unwrap().execute(command, words.next()) {
+                    return;
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breakpoints() {
+        assert_eq!(Breakpoint::parse("script.ion:4"), Breakpoint::Line("script.ion".into(), 4));
+        assert_eq!(Breakpoint::parse("greet"), Breakpoint::Function("greet".into()));
+        assert_eq!(Breakpoint::parse("a:b"), Breakpoint::Function("a:b".into()));
+        let breakpoint = Breakpoint::parse("script.ion:4");
+        assert!(breakpoint.is_at(&Location::new("tests/script.ion", 4)));
+        assert!(!breakpoint.is_at(&Location::new("tests/script.ion", 5)));
+        assert!(!breakpoint.is_at(&Location::new("tests/other.ion", 4)));
+    }
+
+    #[test]
+    fn commands() {
+        let mut debugger = Debugger::new();
+        assert!(!debugger.execute(Some("break"), Some("script.ion:3")));
+        assert!(!debugger.execute(Some("b"), Some("greet")));
+        assert_eq!(debugger.breakpoints.len(), 2);
+        assert!(!debugger.execute(Some("delete"), Some("greet")));
+        assert_eq!(debugger.breakpoints, vec![Breakpoint::Line("script.ion".into(), 3)]);
+        assert!(debugger.execute(Some("continue"), None));
+        assert!(!debugger.stepping);
+        assert!(debugger.execute(None, None));
+        assert!(debugger.stepping);
+    }
+
+    #[test]
+    fn pausing() {
+        let mut debugger = Debugger::new();
+        assert!(debugger.should_pause(None));
+        debugger.stepping = false;
+        debugger.breakpoints.push(Breakpoint::parse("script.ion:2"));
+        debugger.breakpoints.push(Breakpoint::parse("greet"));
+        assert!(!debugger.should_pause(Some(&Location::new("script.ion", 1))));
+        assert!(debugger.should_pause(Some(&Location::new("script.ion", 2))));
+
+        // Each line only reaches its breakpoint once, however many statements it contains.
+        debugger.stepping = false;
+        assert!(!debugger.should_pause(Some(&Location::new("script.ion", 2))));
+        debugger.call("other");
+        assert!(!debugger.should_pause(None));
+        debugger.call("greet");
+        assert!(debugger.should_pause(None));
+    }
+}
@@ -0,0 +1,188 @@
+//! Implements `ion --diagnose`, which prints where the shell looks for its configuration, and
+//! what it found there, so that problems such as a plugin or an init file which silently fails
+//! to load may be triaged.
+
+use super::Shell;
+use super::editor;
+use super::options::OPTIONS;
+use super::plugins::{self, methods, namespaces};
+use super::status::*;
+use super::xdg;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use sys;
+
+/// Describes a file or directory which the shell may read.
+fn describe(path: &Path) -> String {
+    match fs::metadata(path) {
+        Ok(ref metadata) if metadata.permissions().readonly() => "found, read-only".into(),
+        Ok(_) => "found".into(),
+        Err(ref why) if why.kind() == io::ErrorKind::NotFound => "missing".into(),
+        Err(why) => format!("unreadable: {}", why),
+    }
+}
+
+/// Describes an init file, which is also marked if it was evaluated by the shell.
+fn describe_init_file(shell: &Shell, path: &Path) -> String {
+    let loaded = shell.variables.get_array("INIT_FILES").map_or(false, |files| {
+        files.iter().any(|file| Path::new(file.as_str()) == path)
+    });
+    if loaded { "loaded".into() } else { describe(path) }
+}
+
+/// Lists the libraries within a plugin directory, along with the number of functions which each
+/// provides, or the reason that it could not be loaded.
+fn plugin_libraries<W: Write>(
+    out: &mut W,
+    directory: &Path,
+    inspect: fn(&Path) -> Result<usize, String>,
+) -> io::Result<()> {
+    let mut libraries = match fs::read_dir(directory) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map_or(false, |ext| ext == "so"))
+            .collect::<Vec<PathBuf>>(),
+        Err(why) => return writeln!(out, "  {}: {}", directory.display(), why),
+    };
+    libraries.sort();
+    writeln!(out, "  {}: {} libraries", directory.display(), libraries.len())?;
+    for library in libraries {
+        match inspect(&library) {
+            Ok(functions) => writeln!(out, "    {}: {} functions", library.display(), functions)?,
+            Err(why) => writeln!(out, "    {}: failed to load: {}", library.display(), why)?,
+        }
+    }
+    Ok(())
+}
+
+/// Counts the lines of the history file.
+fn history_entries(path: &Path) -> io::Result<usize> {
+    Ok(BufReader::new(File::open(path)?).lines().count())
+}
+
+fn report<W: Write>(shell: &Shell, out: &mut W) -> io::Result<()> {
+    let vars = &shell.variables;
+    writeln!(out, "{}", include!(concat!(env!("OUT_DIR"), "/version_string")))?;
+
+    writeln!(out, "\nconfiguration:")?;
+    match xdg::config_dir() {
+        Some(dir) => writeln!(out, "  config directory: {} ({})", dir.display(), describe(dir))?,
+        None => writeln!(out, "  config directory: unknown, as no home directory was found")?,
+    }
+    match xdg::data_dir() {
+        Some(dir) => writeln!(out, "  data directory: {} ({})", dir.display(), describe(dir))?,
+        None => writeln!(out, "  data directory: unknown, as no home directory was found")?,
+    }
+    let mut init_files = vec![PathBuf::from("/etc/ion/profile")];
+    if let Some(dir) = xdg::config_dir() {
+        init_files.push(dir.join("profile"));
+    }
+    init_files.push(PathBuf::from("/etc/ion/initrc"));
+    if let Some(dir) = xdg::config_dir() {
+        init_files.push(dir.join("initrc"));
+    }
+    if let Some(session) = env::var_os("ION_INITRC") {
+        init_files.push(PathBuf::from(session));
+    }
+    for path in init_files {
+        writeln!(out, "  init file {}: {}", path.display(), describe_init_file(shell, &path))?;
+    }
+    writeln!(out, "  FPATH: {}", vars.get_var_or_empty("FPATH"))?;
+    writeln!(out, "  IMPORT_PATH: {}", vars.get_var_or_empty("IMPORT_PATH"))?;
+    let hooks = ["PROMPT", "PRECMD"];
+    for hook in &hooks {
+        let defined = shell.functions.contains_key(*hook);
+        writeln!(out, "  {} function: {}", hook, if defined { "defined" } else { "undefined" })?;
+    }
+
+    writeln!(out, "\nplugins:")?;
+    let enabled = vars.has_plugin_support();
+    writeln!(out, "  NS_PLUGINS: {}", if enabled { "enabled" } else { "disabled" })?;
+    match plugins::config_dir() {
+        Some(dir) => {
+            let namespaces_dir = dir.join("namespaces").join("strings");
+            plugin_libraries(out, &namespaces_dir, namespaces::inspect)?;
+            let methods_dir = dir.join("methods").join("strings");
+            plugin_libraries(out, &methods_dir, methods::inspect)?;
+        }
+        None => writeln!(out, "  plugin directory: unknown, as no home directory was found")?,
+    }
+
+    writeln!(out, "\nterminal:")?;
+    let streams = [
+        ("stdin", sys::STDIN_FILENO),
+        ("stdout", sys::STDOUT_FILENO),
+        ("stderr", sys::STDERR_FILENO),
+    ];
+    for &(name, fd) in &streams {
+        let kind = if sys::isatty(fd) { "terminal" } else { "not a terminal" };
+        writeln!(out, "  {}: {}", name, kind)?;
+    }
+    let owned = match (sys::tcgetpgrp(sys::STDIN_FILENO), sys::getpgrp()) {
+        (Ok(foreground), Ok(pgid)) => foreground == pgid,
+        _ => false,
+    };
+    writeln!(out, "  foreground: {}", if owned { "yes" } else { "no" })?;
+    writeln!(out, "  line editor: {}", editor::name())?;
+    for name in &["TERM", "COLORTERM", "LANG"] {
+        let value = env::var(name).unwrap_or_default();
+        writeln!(out, "  {}: {}", name, if value.is_empty() { "unset" } else { value.as_str() })?;
+    }
+
+    writeln!(out, "\nhistory:")?;
+    let histfile = vars.get_var_or_empty("HISTFILE");
+    if "1" == vars.get_var_or_empty("HISTFILE_ENABLED") && !histfile.is_empty() {
+        let path = Path::new(histfile.as_str());
+        match history_entries(path) {
+            Ok(entries) => writeln!(
+                out,
+                "  HISTFILE: {} ({}, {} entries)",
+                path.display(),
+                describe(path),
+                entries
+            )?,
+            Err(_) => writeln!(out, "  HISTFILE: {} ({})", path.display(), describe(path))?,
+        }
+    } else {
+        writeln!(out, "  HISTFILE: disabled")?;
+    }
+    writeln!(out, "  HISTORY_SIZE: {}", vars.get_var_or_empty("HISTORY_SIZE"))?;
+    writeln!(out, "  HISTFILE_SIZE: {}", vars.get_var_or_empty("HISTFILE_SIZE"))?;
+
+    writeln!(out, "\noptions:")?;
+    for option in OPTIONS.iter() {
+        let enabled = shell.flags & option.flag != 0;
+        writeln!(out, "  {}: {}", option.name, if enabled { "on" } else { "off" })?;
+    }
+    Ok(())
+}
+
+/// Evaluates the init files, as an interactive shell would, and then prints the state of the
+/// shell's configuration to stdout.
+pub(crate) fn diagnose(shell: &mut Shell) -> i32 {
+    shell.evaluate_init_file();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    match report(shell, &mut stdout) {
+        Ok(()) => SUCCESS,
+        Err(why) => {
+            eprintln!("ion: diagnose: {}", why);
+            FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files() {
+        let dir = env::temp_dir();
+        assert_eq!(describe(&dir), "found");
+        assert_eq!(describe(&dir.join("ion-diagnose-missing")), "missing");
+    }
+}
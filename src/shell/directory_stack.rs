@@ -3,7 +3,9 @@ use super::variables::Variables;
 use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::env::{current_dir, home_dir, set_current_dir};
-use std::path::PathBuf;
+use std::fs;
+use std::iter;
+use std::path::{Path, PathBuf};
 
 pub struct DirectoryStack {
     dirs: VecDeque<PathBuf>, // The top is always the current directory
@@ -167,10 +169,10 @@ impl DirectoryStack {
         match args.into_iter().nth(1) {
             Some(dir) => {
                 let dir = dir.as_ref();
-                if dir == "-" {
-                    self.switch_to_previous_directory(variables)
-                } else {
-                    self.change_and_push_dir(dir, variables)
+                match parse_numeric_arg(dir) {
+                    Some((false, index)) => self.switch_to_previous_directory(index, variables),
+                    _ if dir == "-" => self.switch_to_previous_directory(1, variables),
+                    _ => self.change_and_push_dir(&resolve(dir, variables), variables),
                 }
             }
             None => self.switch_to_home_directory(variables),
@@ -186,27 +188,24 @@ impl DirectoryStack {
         })
     }
 
+    /// Switches to a directory which was previously visited, by its index within the stack, as
+    /// is listed by `dirs -v`. `cd -` switches to the most recent, and `cd -N` to the Nth.
     fn switch_to_previous_directory(
         &mut self,
+        index: usize,
         variables: &Variables,
     ) -> Result<(), Cow<'static, str>> {
-        self.get_previous_dir().cloned().map_or_else(
-            || Err(Cow::Borrowed("ion: no previous directory to switch to")),
-            |prev| {
-                self.dirs.remove(1);
-                let prev = prev.to_string_lossy().to_string();
-                println!("{}", prev);
-                self.change_and_push_dir(&prev, variables)
-            },
-        )
-    }
-
-    fn get_previous_dir(&self) -> Option<&PathBuf> {
-        if self.dirs.len() < 2 {
-            None
-        } else {
-            self.dirs.get(1)
-        }
+        let prev = match self.dirs.get(index) {
+            Some(prev) if index != 0 => prev.to_string_lossy().to_string(),
+            _ if index == 1 => return Err(Cow::Borrowed("ion: no previous directory to switch to")),
+            _ => {
+                let why = format!("ion: cd: -{}: directory history out of range\n", index);
+                return Err(Cow::Owned(why));
+            }
+        };
+        self.dirs.remove(index);
+        println!("{}", prev);
+        self.change_and_push_dir(&prev, variables)
     }
 
     pub(crate) fn change_and_push_dir(
@@ -351,8 +350,108 @@ impl DirectoryStack {
     }
 }
 
+/// Resolves the directory which was given to `cd`. A directory which is not relative to the
+/// current directory is searched for within the directories of `CDPATH`, which are separated by
+/// colons, unless it begins with `/`, `./`, or `../`. If it is not found, and `CD_FUZZY` is set to
+/// `1`, each of its components is matched against the closest subdirectory instead. The directory
+/// which was found is printed, as it is not the one which was given.
+fn resolve<'a>(dir: &'a str, variables: &Variables) -> Cow<'a, str> {
+    if Path::new(dir).is_dir() {
+        return Cow::Borrowed(dir);
+    }
+    let cdpath = variables.get_var_or_empty("CDPATH");
+    let explicit = dir.starts_with('/') || dir == "." || dir == ".." || dir.starts_with("./")
+        || dir.starts_with("../");
+    let directories: Vec<&str> = if explicit {
+        Vec::new()
+    } else {
+        cdpath.split(':').filter(|directory| !directory.is_empty()).collect()
+    };
+
+    let mut found = directories
+        .iter()
+        .map(|directory| Path::new(directory).join(dir))
+        .find(|path| path.is_dir());
+    if found.is_none() && variables.get_var_or_empty("CD_FUZZY") == "1" {
+        let base = if dir.starts_with('/') { "/" } else { "." };
+        found = iter::once(base)
+            .chain(directories.iter().cloned())
+            .filter_map(|base| fuzzy_find(Path::new(base), dir))
+            .next();
+    }
+
+    match found {
+        Some(path) => {
+            let path = path.to_string_lossy().into_owned();
+            println!("{}", path);
+            Cow::Owned(path)
+        }
+        None => Cow::Borrowed(dir),
+    }
+}
+
+/// How closely the name of a directory matches a component of a path which is searched for
+/// fuzzily, from the closest: the same name, a name which begins with the component, a name which
+/// contains it, and a name which contains each of its characters in order, ignoring case.
+fn closeness(name: &str, component: &str) -> Option<u8> {
+    if name == component {
+        return Some(0);
+    }
+    let (name, component) = (name.to_lowercase(), component.to_lowercase());
+    if name.starts_with(&component) {
+        Some(1)
+    } else if name.contains(&component) {
+        Some(2)
+    } else {
+        let mut chars = name.chars();
+        if component.chars().all(|character| chars.any(|c| c == character)) {
+            Some(3)
+        } else {
+            None
+        }
+    }
+}
+
+/// Searches for a directory within the base directory, matching each component of the path
+/// against the closest subdirectory, so that `proj/ion` may match `projects/ion-shell`. Shorter
+/// names are preferred among those which are as close, and hidden directories are only matched
+/// by components which begin with `.`.
+fn fuzzy_find(base: &Path, path: &str) -> Option<PathBuf> {
+    let mut directory = base.to_path_buf();
+    for component in path.split('/').filter(|component| !component.is_empty()) {
+        if component == "." || component == ".." || directory.join(component).is_dir() {
+            directory.push(component);
+            continue;
+        }
+
+        let mut closest: Option<(u8, String)> = None;
+        for entry in fs::read_dir(&directory).ok()?.filter_map(Result::ok) {
+            let name = match entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            if (name.starts_with('.') && !component.starts_with('.')) || !entry.path().is_dir() {
+                continue;
+            }
+            if let Some(rank) = closeness(&name, component) {
+                let closer = match closest {
+                    Some((best, ref best_name)) => {
+                        (rank, name.len(), &name) < (best, best_name.len(), best_name)
+                    }
+                    None => true,
+                };
+                if closer {
+                    closest = Some((rank, name));
+                }
+            }
+        }
+        directory.push(closest?.1);
+    }
+    Some(directory)
+}
+
 // parses -N or +N patterns
-// required for popd, pushd, dirs
+// required for popd, pushd, dirs, cd
 fn parse_numeric_arg(arg: &str) -> Option<(bool, usize)> {
     match arg.chars().nth(0) {
         Some('+') => Some(true),
@@ -365,3 +464,44 @@ fn parse_numeric_arg(arg: &str) -> Option<(bool, usize)> {
 fn try_abs_path(pbuf: &PathBuf) -> Cow<str> {
     Cow::Owned(pbuf.canonicalize().unwrap_or_else(|_| pbuf.clone()).to_string_lossy().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn closeness_of_names() {
+        assert_eq!(closeness("ion", "ion"), Some(0));
+        assert_eq!(closeness("ion-shell", "ion"), Some(1));
+        assert_eq!(closeness("Projects", "proj"), Some(1));
+        assert_eq!(closeness("my-ion", "ion"), Some(2));
+        assert_eq!(closeness("documents", "dcmt"), Some(3));
+        assert_eq!(closeness("downloads", "ion"), None);
+    }
+
+    #[test]
+    fn fuzzy_finding() {
+        let root = env::temp_dir().join(format!("ion-cd-{}", ::std::process::id()));
+        for dir in &["projects/ion-shell", "projects/ion", "projects/my-ion", "Pictures", ".proj"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        let found = (
+            fuzzy_find(&root, "proj/ion-sh"),
+            fuzzy_find(&root, "proj/ion"),
+            fuzzy_find(&root, "pic"),
+            fuzzy_find(&root, ".proj"),
+            fuzzy_find(&root, "projects/../pctrs"),
+            fuzzy_find(&root, "music"),
+        );
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(found.0, Some(root.join("projects/ion-shell")));
+        assert_eq!(found.1, Some(root.join("projects/ion")));
+        assert_eq!(found.2, Some(root.join("Pictures")));
+        assert_eq!(found.3, Some(root.join(".proj")));
+        assert_eq!(found.4, Some(root.join("projects/../Pictures")));
+        assert_eq!(found.5, None);
+    }
+}
@@ -0,0 +1,142 @@
+use super::super::input;
+use super::{escape_len, CompletionHandler, KeyBindings, LineEditor, RedrawHandler};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+
+/// A fallback editor for terminals which are unable to support Liner, such as dumb terminals
+/// and pipes. Lines are read as they were typed, relying on the terminal to edit them, and so
+/// there are neither completions nor key bindings. Escape sequences, such as the colors of the
+/// default prompt, are removed from prompts, as the terminal would print them verbatim.
+pub(crate) struct BasicEditor {
+    history:   VecDeque<String>,
+    size:      usize,
+    file:      Option<String>,
+    file_size: usize,
+}
+
+impl BasicEditor {
+    pub(crate) fn new() -> BasicEditor {
+        BasicEditor {
+            history:   VecDeque::new(),
+            size:      1000,
+            file:      None,
+            file_size: 1000,
+        }
+    }
+
+    fn truncate(&mut self) {
+        while self.history.len() > self.size {
+            self.history.pop_front();
+        }
+    }
+}
+
+impl LineEditor for BasicEditor {
+    fn read_line(
+        &mut self,
+        prompt: String,
+        _: &mut CompletionHandler,
+        _: &mut RedrawHandler,
+    ) -> io::Result<String> {
+        {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            stdout.write_all(strip_escapes(&prompt).as_bytes())?;
+            stdout.flush()?;
+        }
+        match input::read_line()? {
+            Some(line) => Ok(line),
+            None => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "end of input")),
+        }
+    }
+
+    fn set_key_bindings(&mut self, _: KeyBindings) {}
+
+    fn history(&self) -> Vec<String> { self.history.iter().cloned().collect() }
+
+    fn last_history(&self) -> Option<String> { self.history.back().cloned() }
+
+    fn push_history(&mut self, command: &str) -> io::Result<()> {
+        self.history.push_back(command.into());
+        self.truncate();
+        Ok(())
+    }
+
+    fn set_history_size(&mut self, size: usize) {
+        self.size = size;
+        self.truncate();
+    }
+
+    fn set_history_file(&mut self, path: Option<String>) { self.file = path; }
+
+    fn set_history_file_size(&mut self, size: usize) { self.file_size = size; }
+
+    fn load_history(&mut self) -> io::Result<()> {
+        let path = match self.file {
+            Some(ref path) => path.clone(),
+            None => return Ok(()),
+        };
+        for line in BufReader::new(File::open(path)?).lines() {
+            self.history.push_back(line?);
+        }
+        self.truncate();
+        Ok(())
+    }
+
+    /// Replaces the history file with the most recent commands of the history.
+    fn commit_history(&mut self) {
+        let path = match self.file {
+            Some(ref path) => path,
+            None => return,
+        };
+        let skip = self.history.len().saturating_sub(self.file_size);
+        let result = File::create(path).and_then(|mut file| {
+            for command in self.history.iter().skip(skip) {
+                writeln!(file, "{}", command)?;
+            }
+            Ok(())
+        });
+        if let Err(why) = result {
+            eprintln!("ion: unable to save the history to {}: {}", path, why);
+        }
+    }
+}
+
+/// Removes the escape sequences from the text.
+fn strip_escapes(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut text = text;
+    while let Some(start) = text.find('\x1b') {
+        output.push_str(&text[..start]);
+        text = &text[start + escape_len(&text[start..])..];
+    }
+    output.push_str(text);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_size() {
+        let mut editor = BasicEditor::new();
+        editor.set_history_size(2);
+        for command in &["one", "two", "three"] {
+            editor.push_history(command).unwrap();
+        }
+        assert_eq!(editor.history(), vec!["two".to_owned(), "three".to_owned()]);
+        assert_eq!(editor.last_history(), Some("three".into()));
+        editor.set_history_size(1);
+        assert_eq!(editor.history(), vec!["three".to_owned()]);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(strip_escapes("\x1b[38;5;85;1mion\x1b[0m:~# "), "ion:~# ");
+        assert_eq!(strip_escapes("\x1b]0;title\x07# "), "# ");
+        assert_eq!(strip_escapes("\x1b]0;title\x1b\\# "), "# ");
+        assert_eq!(strip_escapes("\x1b7dóc\x1b8# "), "dóc# ");
+    }
+}
@@ -0,0 +1,225 @@
+//! Contains the line editors which read the commands of an interactive shell. The shell only
+//! depends upon the `LineEditor` trait, so that the editor may be chosen at runtime: Liner is
+//! used by default, whereas a basic editor, which only reads lines, is used for terminals which
+//! are unable to support Liner.
+
+mod basic;
+mod readline;
+
+pub(crate) use self::basic::BasicEditor;
+pub(crate) use self::readline::Readline;
+pub(crate) use liner::Completer;
+
+use std::env;
+use std::io;
+use sys;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The position of the cursor relative to the words of the line which is being edited.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CursorPosition {
+    /// The cursor is within the word of the given index.
+    InWord(usize),
+    /// The cursor is at the start of the word of the given index.
+    OnWordLeftEdge(usize),
+    /// The cursor is at the end of the word of the given index.
+    OnWordRightEdge(usize),
+    /// The cursor is between the words of the given indexes, if there are any.
+    InSpace(Option<usize>, Option<usize>),
+}
+
+/// The line which is being edited, at the moment that completions were requested.
+pub(crate) struct Line {
+    pub(crate) text:     String,
+    /// The start and end of each word of the text, counted in characters.
+    pub(crate) words:    Vec<(usize, usize)>,
+    pub(crate) position: CursorPosition,
+}
+
+impl Line {
+    /// Obtains the text from the start to the end character.
+    pub(crate) fn range(&self, start: usize, end: usize) -> String {
+        self.text.chars().skip(start).take(end - start).collect()
+    }
+}
+
+/// Chooses the completer with which the given line will be completed, if it should be completed
+/// at all.
+pub(crate) type CompletionHandler<'a> = FnMut(&Line) -> Option<Box<Completer>> + 'a;
+
+/// Generates the prompt again if it has changed since it was drawn, such as once an
+/// asynchronous segment has finished, so that the line may be redrawn with it.
+pub(crate) type RedrawHandler<'a> = FnMut() -> Option<String> + 'a;
+
+/// The key bindings of the editor, as set with `set -o vi` and `set -o emacs`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum KeyBindings {
+    Emacs,
+    Vi,
+}
+
+pub(crate) trait LineEditor {
+    /// Reads a line after displaying the given prompt. An error of the `Interrupted` kind is
+    /// returned if the line was cancelled, and one of the `UnexpectedEof` kind once the input
+    /// has ended. Editors which are able to redraw the line should call `redraw` while the line
+    /// is being edited, and replace the prompt with any that it returns.
+    fn read_line(
+        &mut self,
+        prompt: String,
+        complete: &mut CompletionHandler,
+        redraw: &mut RedrawHandler,
+    ) -> io::Result<String>;
+
+    /// Sets the key bindings, if the editor supports them.
+    fn set_key_bindings(&mut self, bindings: KeyBindings);
+
+    /// Obtains each command within the history, from the oldest to the most recent.
+    fn history(&self) -> Vec<String>;
+
+    /// Obtains the most recent command within the history.
+    fn last_history(&self) -> Option<String>;
+
+    /// Appends a command to the history.
+    fn push_history(&mut self, command: &str) -> io::Result<()>;
+
+    /// Sets the number of commands which are kept within the history.
+    fn set_history_size(&mut self, size: usize);
+
+    /// Sets the file which the history is loaded from and saved to, if any.
+    fn set_history_file(&mut self, path: Option<String>);
+
+    /// Sets the number of commands which are kept within the history file.
+    fn set_history_file_size(&mut self, size: usize);
+
+    /// Loads the history from the history file.
+    fn load_history(&mut self) -> io::Result<()>;
+
+    /// Saves the history to the history file.
+    fn commit_history(&mut self);
+}
+
+/// Whether the standard input is unable to support Liner, which is the case if it is not a
+/// terminal, such as when commands are piped to the shell, or if it is a dumb terminal, which
+/// cannot interpret escape sequences.
+pub(crate) fn is_dumb_terminal() -> bool {
+    env::var("TERM").map_or(false, |term| term == "dumb") || !sys::isatty(sys::STDIN_FILENO)
+}
+
+/// Obtains the name of the line editor given by the `ION_LINE_EDITOR` environment variable,
+/// which may be either `liner` or `basic`. By default, the basic editor is used for dumb
+/// terminals, and Liner is used otherwise.
+pub(crate) fn name() -> &'static str {
+    match env::var("ION_LINE_EDITOR").as_ref().map(String::as_str) {
+        Ok("liner") => "liner",
+        Ok("basic") => "basic",
+        _ if is_dumb_terminal() => "basic",
+        _ => "liner",
+    }
+}
+
+/// Creates the line editor given by `name`.
+pub(crate) fn new() -> Box<LineEditor> {
+    match env::var("ION_LINE_EDITOR").unwrap_or_default().as_str() {
+        "" | "liner" | "basic" => (),
+        unknown => eprintln!("ion: ION_LINE_EDITOR: unknown line editor: {}", unknown),
+    }
+    match name() {
+        "basic" => Box::new(BasicEditor::new()),
+        _ => Box::new(Readline::new()),
+    }
+}
+
+/// Obtains the length, in bytes, of the escape sequence at the start of the text, which must
+/// begin with `ESC`. Control sequences, such as colors, end with a character from `@` to `~`,
+/// operating system commands, such as those which set the title of the window, end with a bell
+/// or with `ESC \`, and the remaining escapes are a single character long.
+pub(crate) fn escape_len(text: &str) -> usize {
+    let mut chars = text.char_indices().skip(1);
+    let end = match chars.next() {
+        Some((_, '[')) => chars.find(|&(_, c)| '@' <= c && c <= '~'),
+        Some((_, ']')) => match chars.find(|&(_, c)| c == '\x07' || c == '\x1b') {
+            Some((id, '\x1b')) if text[id + 1..].starts_with('\\') => Some((id + 1, '\\')),
+            end => end,
+        },
+        next => next,
+    };
+    end.map_or(text.len(), |(id, c)| id + c.len_utf8())
+}
+
+/// Shortens the last line of the prompt to the given number of columns, if it is wider, by
+/// replacing its start with an ellipsis. Escape sequences are kept, so that the colors of the
+/// text which remains are unchanged.
+pub(crate) fn truncate_prompt(prompt: &str, width: usize) -> String {
+    let (head, line) = match prompt.rfind('\n') {
+        Some(id) => prompt.split_at(id + 1),
+        None => ("", prompt),
+    };
+
+    // The line is divided into escapes and graphemes, the latter of which are visible.
+    let mut segments = Vec::new();
+    let mut text = line;
+    while let Some(start) = text.find('\x1b') {
+        let len = escape_len(&text[start..]);
+        segments.extend(UnicodeSegmentation::graphemes(&text[..start], true).map(|g| (g, true)));
+        segments.push((&text[start..start + len], false));
+        text = &text[start + len..];
+    }
+    segments.extend(UnicodeSegmentation::graphemes(text, true).map(|g| (g, true)));
+
+    let visible = segments.iter().filter(|&&(_, visible)| visible).count();
+    if width == 0 || visible <= width {
+        return prompt.into();
+    }
+    let mut hidden = visible - (width - 1);
+    let mut output = String::from(head);
+    for (segment, visible) in segments {
+        if !visible {
+            output.push_str(segment);
+        } else if hidden > 0 {
+            hidden -= 1;
+            if hidden == 0 {
+                output.push('…');
+            }
+        } else {
+            output.push_str(segment);
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_range() {
+        let line = Line {
+            text:     "ls ~/dóc".into(),
+            words:    vec![(0, 2), (3, 8)],
+            position: CursorPosition::OnWordRightEdge(1),
+        };
+        assert_eq!(line.range(3, 8), "~/dóc");
+        assert_eq!(line.range(0, 2), "ls");
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(escape_len("\x1b[38;5;85mion"), 10);
+        assert_eq!(escape_len("\x1b]0;title\x07#"), 10);
+        assert_eq!(escape_len("\x1b]0;title\x1b\\#"), 11);
+        assert_eq!(escape_len("\x1b7#"), 2);
+        assert_eq!(escape_len("\x1b[38"), 4);
+    }
+
+    #[test]
+    fn truncated_prompts() {
+        assert_eq!(truncate_prompt("ion:~/src# ", 20), "ion:~/src# ");
+        assert_eq!(truncate_prompt("ion:~/src# ", 8), "…~/src# ");
+        assert_eq!(
+            truncate_prompt("\x1b[1mion\x1b[0m:~/src# ", 8),
+            "\x1b[1m\x1b[0m…~/src# "
+        );
+        assert_eq!(truncate_prompt("ion:~/src\n# ", 2), "ion:~/src\n# ");
+        assert_eq!(truncate_prompt("dóc/ion# ", 6), "…ion# ");
+    }
+}
@@ -0,0 +1,132 @@
+use super::{CompletionHandler, CursorPosition, KeyBindings, Line, LineEditor, RedrawHandler};
+use liner::{self, Buffer, Context, Event, EventKind};
+use std::io;
+use std::mem;
+
+/// The editor provided by Liner, which supports completions, history searches, and the key
+/// bindings of both Emacs and Vi.
+pub(crate) struct Readline {
+    context: Context,
+}
+
+impl Readline {
+    pub(crate) fn new() -> Readline {
+        let mut context = Context::new();
+        context.word_divider_fn = Box::new(word_divide);
+        Readline { context }
+    }
+}
+
+impl LineEditor for Readline {
+    fn read_line(
+        &mut self,
+        prompt: String,
+        complete: &mut CompletionHandler,
+        redraw: &mut RedrawHandler,
+    ) -> io::Result<String> {
+        self.context.read_line(prompt, &mut |Event { editor, kind }| match kind {
+            // Liner blocks while it waits for a key, and so the prompt is redrawn before the
+            // next key is handled.
+            EventKind::BeforeKey(_) => if let Some(prompt) = redraw() {
+                editor.set_prompt(prompt);
+                let _ = editor.display();
+            },
+            EventKind::BeforeComplete => {
+                let (words, position) = editor.get_words_and_cursor_position();
+                let line = Line {
+                    text:     editor.current_buffer().to_string(),
+                    words:    words,
+                    position: match position {
+                        liner::CursorPosition::InWord(index) => CursorPosition::InWord(index),
+                        liner::CursorPosition::OnWordLeftEdge(index) => {
+                            CursorPosition::OnWordLeftEdge(index)
+                        }
+                        liner::CursorPosition::OnWordRightEdge(index) => {
+                            CursorPosition::OnWordRightEdge(index)
+                        }
+                        liner::CursorPosition::InSpace(before, after) => {
+                            CursorPosition::InSpace(before, after)
+                        }
+                    },
+                };
+                if let Some(completer) = complete(&line) {
+                    mem::replace(&mut editor.context().completer, Some(completer));
+                }
+            }
+            _ => (),
+        })
+    }
+
+    fn set_key_bindings(&mut self, bindings: KeyBindings) {
+        self.context.key_bindings = match bindings {
+            KeyBindings::Emacs => liner::KeyBindings::Emacs,
+            KeyBindings::Vi => liner::KeyBindings::Vi,
+        };
+    }
+
+    fn history(&self) -> Vec<String> {
+        self.context.history.buffers.iter().map(|buffer| buffer.to_string()).collect()
+    }
+
+    fn last_history(&self) -> Option<String> {
+        self.context.history.buffers.back().map(|buffer| buffer.to_string())
+    }
+
+    fn push_history(&mut self, command: &str) -> io::Result<()> {
+        self.context.history.push(command.into())
+    }
+
+    fn set_history_size(&mut self, size: usize) { self.context.history.set_max_size(size); }
+
+    fn set_history_file(&mut self, path: Option<String>) {
+        self.context.history.set_file_name(path);
+    }
+
+    fn set_history_file_size(&mut self, size: usize) {
+        self.context.history.set_max_file_size(size);
+    }
+
+    fn load_history(&mut self) -> io::Result<()> { self.context.history.load_history() }
+
+    fn commit_history(&mut self) { self.context.history.commit_history(); }
+}
+
+/// Divides the line into words at each space which is not escaped, so that completions may be
+/// given for filenames which contain spaces.
+fn word_divide(buf: &Buffer) -> Vec<(usize, usize)> {
+    let mut res = Vec::new();
+    let mut word_start = None;
+
+    macro_rules! check_boundary {
+        ($c:expr, $index:expr, $escaped:expr) => {{
+            if let Some(start) = word_start {
+                if $c == ' ' && !$escaped {
+                    res.push((start, $index));
+                    word_start = None;
+                }
+            } else {
+                if $c != ' ' {
+                    word_start = Some($index);
+                }
+            }
+        }}
+    }
+
+    let mut iter = buf.chars().enumerate();
+    while let Some((i, &c)) = iter.next() {
+        match c {
+            '\\' => {
+                if let Some((_, &cnext)) = iter.next() {
+                    // We use `i` in order to include the backslash as part of the word
+                    check_boundary!(cnext, i, true);
+                }
+            }
+            c => check_boundary!(c, i, false),
+        }
+    }
+    if let Some(start) = word_start {
+        // When start has been set, that means we have encountered a full word.
+        res.push((start, buf.num_chars()));
+    }
+    res
+}
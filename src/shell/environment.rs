@@ -0,0 +1,110 @@
+//! Sanitization of the environment which is given to external commands, for scripts which run
+//! setuid-adjacent tooling. While the `cleanenv` option is enabled, and for commands which are
+//! executed with `env --clean`, external commands only receive the variables which are allowed,
+//! so that variables such as `LD_PRELOAD` and `IFS` cannot be injected into them.
+
+use super::Shell;
+use std::env;
+use std::process::Command;
+use types::Value;
+
+/// The variables which are allowed unless the `ENV_ALLOW` array gives others. Patterns which end
+/// with `*` allow every variable whose name begins with them.
+pub(crate) const ALLOWED: [&str; 10] =
+    ["HOME", "LANG", "LC_*", "LOGNAME", "PATH", "PWD", "SHELL", "TERM", "TZ", "USER"];
+
+fn is_allowed<S: AsRef<str>>(name: &str, allowed: &[S]) -> bool {
+    allowed.iter().map(|pattern| pattern.as_ref()).any(|pattern| {
+        if pattern.ends_with('*') {
+            name.starts_with(&pattern[..pattern.len() - 1])
+        } else {
+            name == pattern
+        }
+    })
+}
+
+/// Removes the variables which are not allowed by default from the environment of the shell,
+/// as is done on startup by `ion --clean-env`.
+pub(crate) fn sanitize() {
+    for (name, _) in env::vars_os() {
+        if !name.to_str().map_or(false, |name| is_allowed(name, &ALLOWED)) {
+            env::remove_var(name);
+        }
+    }
+}
+
+/// Splits the arguments which follow `env --clean` into the assignments of the form
+/// `NAME=VALUE` which come first, and the command which follows them.
+fn split_assignments(args: &[Value]) -> (Vec<(&str, &str)>, &[Value]) {
+    let mut assignments = Vec::new();
+    for (id, arg) in args.iter().enumerate() {
+        match arg.find('=') {
+            Some(pos) if pos != 0 => assignments.push((&arg[..pos], &arg[pos + 1..])),
+            _ => return (assignments, &args[id..]),
+        }
+    }
+    (assignments, &[])
+}
+
+/// Gives external commands a clean environment.
+pub(crate) trait CleanEnv {
+    /// Replaces the environment of the command with the allowed variables of the environment
+    /// of the shell.
+    fn clean(&self, command: &mut Command);
+
+    /// Creates the command which is given to `env --clean [NAME=VALUE]... COMMAND [ARG]...`,
+    /// whose clean environment also contains the assigned variables, along with the program and
+    /// arguments that follow the assignments. Returns `None` if no command was given.
+    fn clean_command<'a>(&self, args: &'a [Value]) -> Option<(Command, &'a [Value])>;
+}
+
+impl CleanEnv for Shell {
+    fn clean(&self, command: &mut Command) {
+        let allowed = |name: &str| match self.variables.get_array("ENV_ALLOW") {
+            Some(allowed) => is_allowed(name, allowed),
+            None => is_allowed(name, &ALLOWED),
+        };
+        command.env_clear();
+        for (name, value) in env::vars_os() {
+            if name.to_str().map_or(false, |name| allowed(name)) {
+                command.env(name, value);
+            }
+        }
+    }
+
+    fn clean_command<'a>(&self, args: &'a [Value]) -> Option<(Command, &'a [Value])> {
+        let (assignments, args) = split_assignments(args);
+        let mut command = Command::new(args.first()?);
+        command.args(&args[1..]);
+        self.clean(&mut command);
+        command.envs(assignments);
+        Some((command, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowed_variables() {
+        assert!(is_allowed("PATH", &ALLOWED));
+        assert!(is_allowed("LC_TIME", &ALLOWED));
+        assert!(!is_allowed("LD_PRELOAD", &ALLOWED));
+        assert!(!is_allowed("IFS", &ALLOWED));
+        assert!(!is_allowed("PATHS", &ALLOWED));
+        assert!(is_allowed("GIT_DIR", &["HOME", "GIT_*"]));
+    }
+
+    #[test]
+    fn assignments() {
+        let args = ["TERM=dumb", "A=b=c", "make", "-j", "X=1"]
+            .iter()
+            .map(|&arg| arg.to_owned())
+            .collect::<Vec<Value>>();
+        let (assignments, command) = split_assignments(&args);
+        assert_eq!(assignments, vec![("TERM", "dumb"), ("A", "b=c")]);
+        assert_eq!(command, &args[2..]);
+        assert_eq!(split_assignments(&args[..2]).1.len(), 0);
+    }
+}
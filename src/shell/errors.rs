@@ -0,0 +1,252 @@
+//! Errors which are reported by the shell. Rather than being printed where they occur, errors are
+//! described by an `IonError`, and handed to the shell's `Reporter`, which renders them to stderr.
+//! A program which embeds the shell may instead have them collected, so that it can inspect them.
+
+pub use parser::Location;
+use std::cell::RefCell;
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+
+/// The stage of execution at which an error occurred.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    /// A command could not be parsed, or its blocks are not properly nested.
+    Syntax,
+    /// A variable could not be assigned or exported.
+    Assignment,
+    /// A word could not be expanded, such as when a variable is unset in strict mode.
+    Expansion,
+    /// A function or method could not be called with the arguments that were supplied.
+    Function,
+    /// A pipeline could not be executed, such as when a command does not exist.
+    Pipeline,
+    /// An assertion which was made with the `assert` builtin did not hold.
+    Assertion,
+    /// A warning which was raised as an error, because its level is `error`.
+    Warning,
+    /// The umask or working directory of a `with` block could not be changed or restored.
+    Scope,
+    /// An executed command could not be recorded in the audit log.
+    Audit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IonError {
+    pub kind:      ErrorKind,
+    /// The script, line, and column at which the error occurred, if it belongs to a script.
+    pub location:  Option<Location>,
+    /// The name of the variable, function, or command to which the error belongs, if any.
+    pub context:   Option<String>,
+    pub message:   String,
+    /// The line which contains a syntax error, with a caret beneath the offending character, or
+    /// the values which a failed assertion compared.
+    pub snippet:   Option<String>,
+    /// The functions which were being called when the error occurred, from the most recent call.
+    pub backtrace: Vec<Frame>,
+}
+
+/// The number of frames which are rendered with an error, beyond which the rest of the backtrace
+/// is only counted.
+const MAX_FRAMES: usize = 32;
+
+/// A call to a function which had yet to return.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function:  String,
+    /// Where the function was defined, if it was defined within a script.
+    pub location:  Option<Location>,
+    /// Where the function was called, if it was called from a script.
+    pub call_site: Option<Location>,
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.function)?;
+        if let Some(ref call_site) = self.call_site {
+            write!(f, ", called at {}", call_site)?;
+        }
+        if let Some(ref location) = self.location {
+            write!(f, ", defined at {}", location)?;
+        }
+        Ok(())
+    }
+}
+
+impl IonError {
+    pub(crate) fn new<S: Into<String>>(kind: ErrorKind, message: S) -> IonError {
+        IonError {
+            kind,
+            location:  None,
+            context:   None,
+            message:   message.into(),
+            snippet:   None,
+            backtrace: Vec::new(),
+        }
+    }
+
+    pub(crate) fn context<S: Into<String>>(mut self, context: S) -> IonError {
+        self.context = Some(context.into());
+        self
+    }
+
+    pub(crate) fn at(mut self, location: Option<Location>) -> IonError {
+        self.location = location;
+        self
+    }
+}
+
+impl Display for IonError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.location {
+            Some(ref location) => write!(f, "{}: error: ", location)?,
+            None => match self.kind {
+                ErrorKind::Syntax => write!(f, "ion: syntax error: ")?,
+                ErrorKind::Assignment => write!(f, "ion: assignment error: ")?,
+                _ => write!(f, "ion: ")?,
+            },
+        }
+        if let Some(ref context) = self.context {
+            write!(f, "{}: ", context)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(ref snippet) = self.snippet {
+            write!(f, "\n{}", snippet)?;
+        }
+        if !self.backtrace.is_empty() {
+            write!(f, "\nbacktrace (most recent call first):")?;
+        }
+        // Recursive calls, which are made from the same place, are collapsed into one frame.
+        let mut id = 0;
+        let mut rendered = 0;
+        while id < self.backtrace.len() {
+            if rendered == MAX_FRAMES {
+                let remaining = self.backtrace.len() - id;
+                return write!(f, "\n    … {} more frames", remaining);
+            }
+            let frame = &self.backtrace[id];
+            write!(f, "\n    {}: {}", id, frame)?;
+            let repeated = self.backtrace[id + 1..]
+                .iter()
+                .take_while(|&next| next == frame)
+                .count();
+            if repeated != 0 {
+                write!(f, "\n    … repeated {} times", repeated)?;
+            }
+            id += repeated + 1;
+            rendered += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Renders each error that it is given to stderr, unless errors are being collected. Clones of a
+/// reporter share their collected errors, so that the statement parser may report to the shell.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Reporter {
+    collected: Rc<RefCell<Option<Vec<IonError>>>>,
+}
+
+impl Reporter {
+    pub(crate) fn report(&self, error: IonError) {
+        match *self.collected.borrow_mut() {
+            Some(ref mut errors) => errors.push(error),
+            None => eprintln!("{}", error),
+        }
+    }
+
+    /// Collects errors rather than rendering them, or resumes rendering them.
+    pub(crate) fn collect(&self, enable: bool) {
+        let mut collected = self.collected.borrow_mut();
+        if !enable {
+            *collected = None;
+        } else if collected.is_none() {
+            *collected = Some(Vec::new());
+        }
+    }
+
+    /// Removes and returns the errors that have been collected.
+    pub(crate) fn take(&self) -> Vec<IonError> {
+        let mut collected = self.collected.borrow_mut();
+        collected.as_mut().map_or_else(Vec::new, |errors| errors.drain(..).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendering() {
+        let error = IonError::new(ErrorKind::Assignment, "not a valid int").context("x");
+        assert_eq!(error.to_string(), "ion: assignment error: x: not a valid int");
+        let error = error.at(Some(Location::new("script.ion", 3)));
+        assert_eq!(error.to_string(), "script.ion:3: error: x: not a valid int");
+        let error = IonError::new(ErrorKind::Expansion, "unbound variable").context("y");
+        assert_eq!(error.to_string(), "ion: y: unbound variable");
+
+        let mut error = IonError::new(ErrorKind::Pipeline, "command not found").context("z");
+        error.backtrace = vec![
+            Frame {
+                function:  "inner".into(),
+                location:  Some(Location::new("script.ion", 1)),
+                call_site: Some(Location::new("script.ion", 5)),
+            },
+            Frame {
+                function:  "outer".into(),
+                location:  None,
+                call_site: None,
+            },
+        ];
+        assert_eq!(
+            error.to_string(),
+            "ion: z: command not found\nbacktrace (most recent call first):\n    0: inner, \
+             called at script.ion:5, defined at script.ion:1\n    1: outer"
+        );
+    }
+
+    #[test]
+    fn long_backtraces() {
+        let recursive = Frame {
+            function:  "f".into(),
+            location:  Some(Location::new("script.ion", 1)),
+            call_site: Some(Location::new("script.ion", 2)),
+        };
+        let first = Frame {
+            call_site: Some(Location::new("script.ion", 4)),
+            ..recursive.clone()
+        };
+        let mut error = IonError::new(ErrorKind::Function, "recursion limit reached");
+        error.backtrace = vec![recursive; 255];
+        error.backtrace.push(first);
+        assert_eq!(
+            error.to_string(),
+            "ion: recursion limit reached\nbacktrace (most recent call first):\n    0: f, called \
+             at script.ion:2, defined at script.ion:1\n    … repeated 254 times\n    255: f, \
+             called at script.ion:4, defined at script.ion:1"
+        );
+
+        let mut error = IonError::new(ErrorKind::Function, "recursion limit reached");
+        error.backtrace = (0..40)
+            .map(|id| Frame {
+                function:  if id % 2 == 0 { "even" } else { "odd" }.into(),
+                location:  None,
+                call_site: None,
+            })
+            .collect();
+        let rendered = error.to_string();
+        assert_eq!(rendered.lines().count(), 2 + MAX_FRAMES + 1);
+        assert!(rendered.ends_with("\n    31: odd\n    … 8 more frames"));
+    }
+
+    #[test]
+    fn collecting() {
+        let reporter = Reporter::default();
+        reporter.collect(true);
+        let error = IonError::new(ErrorKind::Pipeline, "command not found").context("x");
+        reporter.clone().report(error);
+        let errors = reporter.take();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), "ion: x: command not found");
+        assert!(reporter.take().is_empty());
+    }
+}
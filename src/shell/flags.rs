@@ -1,3 +1,20 @@
-pub const ERR_EXIT: u8 = 1;
-pub const PRINT_COMMS: u8 = 2;
-pub const NO_EXEC: u8 = 4;
+pub const ERR_EXIT: u32 = 1;
+pub const PRINT_COMMS: u32 = 2;
+pub const NO_EXEC: u32 = 4;
+pub const NO_UNSET: u32 = 8;
+pub const FAIL_GLOB: u32 = 16;
+pub const WARN_DEPRECATED: u32 = 32;
+pub const KEEP_NEWLINES: u32 = 64;
+pub const SPLIT_WORDS: u32 = 128;
+pub const IMPORT_ENV: u32 = 256;
+pub const PIPE_FAIL: u32 = 512;
+pub const NO_CLOBBER: u32 = 1024;
+pub const SAVE_HISTORY: u32 = 2048;
+pub const DRY_RUN: u32 = 4096;
+pub const SANDBOX: u32 = 8192;
+pub const CLEAN_ENV: u32 = 16384;
+pub const AUDIT: u32 = 32768;
+pub const TITLE: u32 = 65536;
+pub const REPORT_CWD: u32 = 131_072;
+pub const MARKS: u32 = 262_144;
+pub const AUTO_CD: u32 = 524_288;
@@ -1,15 +1,32 @@
 use super::Shell;
-use super::flags::*;
-use super::flow_control::{collect_cases, collect_if, collect_loops, Case, ElseIf, Function, Statement};
-use super::job_control::JobControl;
+use super::debugger::Debugging;
+use super::errors::{ErrorKind, IonError, Reporter};
+use super::flags::WARN_DEPRECATED;
+use super::flow_control::{collect_cases, collect_if, collect_loops, Case, ElseIf, Function, Scope,
+                          Statement};
+use super::input::Input;
+use super::pipe_exec::fork_subshell;
+use super::profiler::Profiling;
+use super::retry::Retry;
+use super::scopes::Scopes;
 use super::status::*;
-use parser::{expand_string, parse_and_validate, ForExpression, StatementSplitter};
+use super::warnings::{Warn, Warning};
+use parser::{expand_string, replace_backticks, ArgumentSplitter, ForExpression, Location,
+             StatementParser};
 use parser::assignments::{is_array, ReturnValue};
 use parser::pipelines::Pipeline;
 use shell::assignments::VariableStore;
-use std::io::{self, stdout, Write};
+use std::io::{stdout, Write};
 use std::mem;
-use types::Array;
+use std::thread;
+use types::{Array, Identifier, Value};
+
+/// Describes an error which occurred while collecting the statements of a command, located at
+/// that command within its script, if it belongs to one.
+fn flow_error(location: Option<&Location>, why: &str) -> IonError {
+    IonError::new(ErrorKind::Syntax, why.trim_left_matches("ion: syntax error: "))
+        .at(location.cloned())
+}
 
 pub(crate) enum Condition {
     Continue,
@@ -31,13 +48,26 @@ pub(crate) trait FlowLogic {
     ) -> Result<(), &'static str>
         where I: Iterator<Item = Statement>;
 
-    /// Executes all of the statements within a while block until a certain condition is met.
-    fn execute_while(&mut self, expression: Pipeline, statements: Vec<Statement>) -> Condition;
+    /// Executes all of the statements within a while block until a certain condition is met,
+    /// reading the standard input from the given file, if one was given to its `end`.
+    fn execute_while(
+        &mut self,
+        expression: Pipeline,
+        statements: Vec<Statement>,
+        input: Option<String>,
+    ) -> Condition;
+
+    /// Executes all of the statements within a repeat block as many times as its count.
+    fn execute_repeat(&mut self, count: &str, statements: Vec<Statement>) -> Condition;
+
+    /// Executes all of the statements within a retry block until they succeed, or until every
+    /// attempt has been made.
+    fn execute_retry(&mut self, arguments: &str, statements: Vec<Statement>) -> Condition;
 
     /// Executes all of the statements within a for block for each value specified in the range.
     fn execute_for(
         &mut self,
-        variable: &str,
+        variables: &[Identifier],
         values: &[String],
         statements: Vec<Statement>,
     ) -> Condition;
@@ -60,38 +90,81 @@ pub(crate) trait FlowLogic {
 
     /// Expand an expression and run a branch based on the value of the expanded expression
     fn execute_match(&mut self, expression: String, cases: Vec<Case>) -> Condition;
+
+    /// Executes the statements of a `with` block, restoring the setting which the block
+    /// changed once they have been executed.
+    fn execute_with(&mut self, scope: Scope, statements: Vec<Statement>) -> Condition;
 }
 
 impl FlowLogic for Shell {
     fn on_command(&mut self, command_string: &str) {
-        self.break_flow = false;
-        let mut iterator = StatementSplitter::new(command_string).map(parse_and_validate);
+        // Backtick command substitutions are deprecated, but are still accepted as `$(...)`.
+        let replaced = replace_backticks(command_string);
+        if replaced.is_some() && self.flags & WARN_DEPRECATED != 0 {
+            let message = "backtick command substitutions are deprecated; use `$(...)` instead";
+            if self.warn(Warning::Deprecated, message) {
+                self.previous_status = FAILURE;
+                return;
+            }
+        }
+        let command_string = replaced.as_ref().map_or(command_string, String::as_str);
+        // Syntax errors are only located within commands which are executed directly from a
+        // script, and not within those which they execute in turn, such as through `eval`.
+        let location = if self.depth == 0 { self.location.clone() } else { None };
+        let reporter = self.reporter.clone();
+        let mut iterator = StatementParser::new(command_string, location.as_ref(), reporter);
 
         // If the value is set to `0`, this means that we don't need to append to an existing
         // partial statement block in memory, but can read and execute new statements.
         if self.flow_control.level == 0 {
+            self.flow_control.text.clear();
+            self.flow_control.text.push_str(command_string);
             while let Some(statement) = iterator.next() {
                 // Executes all statements that it can, and stores the last remaining partial
                 // statement in memory if needed. We can tell if there is a partial statement
                 // later if the value of `level` is not set to `0`.
-                if let Err(why) = self.execute_toplevel(&mut iterator, statement) {
-                    let stderr = io::stderr();
-                    let mut stderr = stderr.lock();
-                    let _ = writeln!(stderr, "{}", why);
+                let profile = self.start_profile(&statement, location.as_ref());
+                let result = self.execute_toplevel(&mut iterator, statement);
+                self.finish_profile(profile);
+                if let Err(why) = result {
+                    self.report(flow_error(location.as_ref(), why));
                     self.flow_control.level = 0;
                     self.flow_control.current_if_mode = 0;
                     return;
                 }
+                // The remaining statements are discarded if the user interrupted the shell.
+                if self.was_interrupted() {
+                    return;
+                }
+            }
+            // Remember where a block which was left open began, so that it may be located.
+            if self.flow_control.level != 0 {
+                self.flow_control.location = self.location.clone();
             }
         } else {
+            self.flow_control.text.push('\n');
+            self.flow_control.text.push_str(command_string);
+
             fn append_new_commands<I: Iterator<Item = Statement>>(
                 mut iterator: &mut I,
                 current_statement: &mut Statement,
                 level: &mut usize,
                 current_if_mode: &mut u8,
+                location: Option<&Location>,
+                reporter: &Reporter,
             ) {
                 match current_statement {
                     &mut Statement::While {
+                        ref mut statements,
+                        ref mut input,
+                        ..
+                    } => {
+                        *input = collect_loops(&mut iterator, statements, level);
+                    }
+                    &mut Statement::Repeat {
+                        ref mut statements, ..
+                    }
+                    | &mut Statement::Retry {
                         ref mut statements, ..
                     }
                     | &mut Statement::For {
@@ -99,6 +172,9 @@ impl FlowLogic for Shell {
                     }
                     | &mut Statement::Function {
                         ref mut statements, ..
+                    }
+                    | &mut Statement::With {
+                        ref mut statements, ..
                     } => {
                         collect_loops(&mut iterator, statements, level);
                     }
@@ -118,22 +194,26 @@ impl FlowLogic for Shell {
                         ) {
                             Ok(mode) => mode,
                             Err(why) => {
-                                let stderr = io::stderr();
-                                let mut stderr = stderr.lock();
-                                let _ = writeln!(stderr, "{}", why);
+                                reporter.report(flow_error(location, why));
                                 4
                             }
                         };
                     }
                     &mut Statement::Match { ref mut cases, .. } => {
                         if let Err(why) = collect_cases(&mut iterator, cases, level) {
-                            let stderr = io::stderr();
-                            let mut stderr = stderr.lock();
-                            let _ = writeln!(stderr, "{}", why);
+                            reporter.report(flow_error(location, &why));
                         }
                     }
                     &mut Statement::Time(ref mut box_stmt) => {
-                        append_new_commands(iterator, box_stmt.as_mut(), level, current_if_mode);
+                        let statement = box_stmt.as_mut();
+                        append_new_commands(
+                            iterator,
+                            statement,
+                            level,
+                            current_if_mode,
+                            location,
+                            reporter,
+                        );
                     }
                     _ => (),
                 }
@@ -144,6 +224,8 @@ impl FlowLogic for Shell {
                 &mut self.flow_control.current_statement,
                 &mut self.flow_control.level,
                 &mut self.flow_control.current_if_mode,
+                location.as_ref(),
+                &self.reporter,
             );
 
             // If this is true, an error occurred during the if statement
@@ -162,7 +244,12 @@ impl FlowLogic for Shell {
                 let mut replacement = Statement::Default;
                 mem::swap(&mut self.flow_control.current_statement, &mut replacement);
 
-                fn execute_final(shell: &mut Shell, statement: Statement) -> Condition {
+                fn execute_final(
+                    shell: &mut Shell,
+                    statement: Statement,
+                    location: Option<Location>,
+                ) -> Condition {
+                    shell.debug_statement(&statement, false);
                     match statement {
                         Statement::Error(number) => shell.previous_status = number,
                         Statement::Let(action) => {
@@ -174,16 +261,30 @@ impl FlowLogic for Shell {
                         Statement::While {
                             expression,
                             statements,
-                        } => if let Condition::SigInt = shell.execute_while(expression, statements)
+                            input,
+                        } => if let Condition::SigInt =
+                            shell.execute_while(expression, statements, input)
+                        {
+                            return Condition::SigInt;
+                        },
+                        Statement::Repeat { count, statements } => {
+                            if let Condition::SigInt = shell.execute_repeat(&count, statements) {
+                                return Condition::SigInt;
+                            }
+                        }
+                        Statement::Retry {
+                            arguments,
+                            statements,
+                        } => if let Condition::SigInt = shell.execute_retry(&arguments, statements)
                         {
                             return Condition::SigInt;
                         },
                         Statement::For {
-                            variable,
+                            variables,
                             values,
                             statements,
                         } => if let Condition::SigInt =
-                            shell.execute_for(&variable, &values, statements)
+                            shell.execute_for(&variables, &values, statements)
                         {
                             return Condition::SigInt;
                         },
@@ -192,12 +293,20 @@ impl FlowLogic for Shell {
                             args,
                             statements,
                             description,
-                        } => {
-                            shell.functions.insert(
+                        } => if shell.may_define(&name) {
+                            let source = Some(shell.flow_control.text.clone());
+                            let function = Function::new(
+                                description,
                                 name.clone(),
-                                Function::new(description, name, args, statements),
+                                args,
+                                statements,
+                                location,
+                                source,
                             );
-                        }
+                            shell.functions.insert(name, function);
+                        } else {
+                            shell.previous_status = FAILURE;
+                        },
                         Statement::If {
                             expression,
                             success,
@@ -209,10 +318,15 @@ impl FlowLogic for Shell {
                         Statement::Match { expression, cases } => {
                             shell.execute_match(expression, cases);
                         }
+                        Statement::With { scope, statements } => {
+                            if let Condition::SigInt = shell.execute_with(scope, statements) {
+                                return Condition::SigInt;
+                            }
+                        }
                         Statement::Time(box_stmt) => {
                             let time = ::std::time::Instant::now();
 
-                            let condition = execute_final(shell, *box_stmt);
+                            let condition = execute_final(shell, *box_stmt, location);
 
                             let duration = time.elapsed();
                             let seconds = duration.as_secs();
@@ -238,20 +352,33 @@ impl FlowLogic for Shell {
                     Condition::NoOp
                 }
 
-                if let Condition::SigInt = execute_final(self, replacement) {
+                // The block is located at the line on which it began.
+                let start = self.flow_control.location.take();
+                let profile = self.start_profile(&replacement, start.as_ref());
+                let condition = execute_final(self, replacement, start);
+                self.finish_profile(profile);
+                if let Condition::SigInt = condition {
                     return;
                 }
 
                 // Capture any leftover statements.
                 while let Some(statement) = iterator.next() {
-                    if let Err(why) = self.execute_toplevel(&mut iterator, statement) {
-                        let stderr = io::stderr();
-                        let mut stderr = stderr.lock();
-                        let _ = writeln!(stderr, "{}", why);
+                    let profile = self.start_profile(&statement, location.as_ref());
+                    let result = self.execute_toplevel(&mut iterator, statement);
+                    self.finish_profile(profile);
+                    if let Err(why) = result {
+                        self.report(flow_error(location.as_ref(), why));
                         self.flow_control.level = 0;
                         self.flow_control.current_if_mode = 0;
                         return;
                     }
+                    if self.was_interrupted() {
+                        return;
+                    }
+                }
+                // Remember where a block which was left open began, so that it may be located.
+                if self.flow_control.level != 0 {
+                    self.flow_control.location = self.location.clone();
                 }
             }
         }
@@ -276,6 +403,10 @@ impl FlowLogic for Shell {
 
         let is_array = is_array(&expression);
         let value = expand_string(&expression, self, false);
+        if self.expansion_failed() {
+            self.previous_status = FAILURE;
+            return Condition::NoOp;
+        }
         let mut condition = Condition::NoOp;
         for case in cases {
             // let pattern_is_array = is_array(&value);
@@ -364,7 +495,10 @@ impl FlowLogic for Shell {
     fn execute_statements(&mut self, mut statements: Vec<Statement>) -> Condition {
         let mut iterator = statements.drain(..);
         while let Some(statement) = iterator.next() {
-            match self.execute_statement(&mut iterator, statement) {
+            let profile = self.start_profile(&statement, None);
+            let condition = self.execute_statement(&mut iterator, statement);
+            self.finish_profile(profile);
+            match condition {
                 Condition::NoOp => {}
                 cond => return cond,
             }
@@ -375,6 +509,7 @@ impl FlowLogic for Shell {
     fn execute_statement<I>(&mut self, mut iterator: &mut I, statement: Statement) -> Condition
         where I: Iterator<Item = Statement>
     {
+        self.debug_statement(&statement, false);
         match statement {
             Statement::Error(number) => self.previous_status = number,
             Statement::Let(action) => {
@@ -386,21 +521,43 @@ impl FlowLogic for Shell {
             Statement::While {
                 expression,
                 mut statements,
+                ..
+            } => {
+                self.flow_control.level += 1;
+                let input =
+                    collect_loops(&mut iterator, &mut statements, &mut self.flow_control.level);
+                if let Condition::SigInt = self.execute_while(expression, statements, input) {
+                    return Condition::SigInt;
+                }
+            }
+            Statement::Repeat {
+                count,
+                mut statements,
+            } => {
+                self.flow_control.level += 1;
+                collect_loops(&mut iterator, &mut statements, &mut self.flow_control.level);
+                if let Condition::SigInt = self.execute_repeat(&count, statements) {
+                    return Condition::SigInt;
+                }
+            }
+            Statement::Retry {
+                arguments,
+                mut statements,
             } => {
                 self.flow_control.level += 1;
                 collect_loops(&mut iterator, &mut statements, &mut self.flow_control.level);
-                if let Condition::SigInt = self.execute_while(expression, statements) {
+                if let Condition::SigInt = self.execute_retry(&arguments, statements) {
                     return Condition::SigInt;
                 }
             }
             Statement::For {
-                variable,
+                variables,
                 values,
                 mut statements,
             } => {
                 self.flow_control.level += 1;
                 collect_loops(&mut iterator, &mut statements, &mut self.flow_control.level);
-                if let Condition::SigInt = self.execute_for(&variable, &values, statements) {
+                if let Condition::SigInt = self.execute_for(&variables, &values, statements) {
                     return Condition::SigInt;
                 }
             }
@@ -419,9 +576,7 @@ impl FlowLogic for Shell {
                     &mut self.flow_control.level,
                     0,
                 ) {
-                    let stderr = io::stderr();
-                    let mut stderr = stderr.lock();
-                    let _ = writeln!(stderr, "{}", why);
+                    self.report(flow_error(None, &why));
                     self.flow_control.level = 0;
                     self.flow_control.current_if_mode = 0;
                     return Condition::Break;
@@ -442,15 +597,29 @@ impl FlowLogic for Shell {
             } => {
                 self.flow_control.level += 1;
                 collect_loops(&mut iterator, &mut statements, &mut self.flow_control.level);
-                self.functions
-                    .insert(name.clone(), Function::new(description, name, args, statements));
+                let location = self.location.clone();
+                if self.may_define(&name) {
+                    let function =
+                        Function::new(description, name.clone(), args, statements, location, None);
+                    // Functions which are defined within a function only exist during its call.
+                    let previous = self.functions.insert(name.clone(), function);
+                    let calls = self.flow_control.call_stack.len();
+                    if calls != 0 {
+                        self.flow_control.shadowed.push((calls, name, previous));
+                    }
+                } else {
+                    self.previous_status = FAILURE;
+                }
             }
             Statement::Pipeline(mut pipeline) => {
                 self.run_pipeline(&mut pipeline);
-                if self.flags & ERR_EXIT != 0 && self.previous_status != SUCCESS {
-                    let status = self.previous_status;
-                    self.exit(status);
-                }
+                self.check_failure();
+            }
+            Statement::Subshell(body) => {
+                let status = fork_subshell(self, &body);
+                self.variables.set_var("?", &status.to_string());
+                self.previous_status = status;
+                self.check_failure();
             }
             Statement::Time(box_statement) => {
                 let time = ::std::time::Instant::now();
@@ -492,9 +661,7 @@ impl FlowLogic for Shell {
                 if let Err(why) =
                     collect_cases(&mut iterator, &mut cases, &mut self.flow_control.level)
                 {
-                    let stderr = io::stderr();
-                    let mut stderr = stderr.lock();
-                    let _ = writeln!(stderr, "{}", why);
+                    self.report(flow_error(None, &why));
                     self.flow_control.level = 0;
                     self.flow_control.current_if_mode = 0;
                     return Condition::Break;
@@ -506,23 +673,64 @@ impl FlowLogic for Shell {
                     Condition::SigInt => return Condition::SigInt,
                 }
             }
+            Statement::With {
+                scope,
+                mut statements,
+            } => {
+                self.flow_control.level += 1;
+                collect_loops(&mut iterator, &mut statements, &mut self.flow_control.level);
+                match self.execute_with(scope, statements) {
+                    Condition::Break => return Condition::Break,
+                    Condition::Continue => return Condition::Continue,
+                    Condition::NoOp => (),
+                    Condition::SigInt => return Condition::SigInt,
+                }
+            }
             _ => {}
         }
-        if let Some(signal) = self.next_signal() {
-            if self.handle_signal(signal) {
-                self.exit(get_signal_code(signal));
-            }
-            Condition::SigInt
-        } else if self.break_flow {
-            self.break_flow = false;
+        // The interrupt is left in effect so that every enclosing block, function, and builtin
+        // will also abort, until the shell reads its next command.
+        if self.was_interrupted() {
             Condition::SigInt
         } else {
             Condition::NoOp
         }
     }
 
-    fn execute_while(&mut self, expression: Pipeline, statements: Vec<Statement>) -> Condition {
-        while self.run_pipeline(&mut expression.clone()) == Some(SUCCESS) {
+    fn execute_while(
+        &mut self,
+        expression: Pipeline,
+        statements: Vec<Statement>,
+        input: Option<String>,
+    ) -> Condition {
+        // The standard input is restored once the guard is dropped, when the loop exits.
+        let _input = match input {
+            Some(path) => {
+                let path = expand_string(&path, self, false).join(" ");
+                if self.expansion_failed() {
+                    self.previous_status = FAILURE;
+                    return Condition::NoOp;
+                }
+                match Input::redirect(&path) {
+                    Ok(input) => Some(input),
+                    Err(why) => {
+                        self.report(IonError::new(ErrorKind::Pipeline, why).context("while"));
+                        self.previous_status = FAILURE;
+                        self.check_failure();
+                        return Condition::NoOp;
+                    }
+                }
+            }
+            None => None,
+        };
+        loop {
+            let status = self.run_condition(&mut expression.clone());
+            // The expression itself may have been interrupted.
+            if self.was_interrupted() {
+                return Condition::SigInt;
+            } else if status != Some(SUCCESS) {
+                break;
+            }
             // Cloning is needed so the statement can be re-iterated again if needed.
             match self.execute_statements(statements.clone()) {
                 Condition::Break => break,
@@ -533,14 +741,120 @@ impl FlowLogic for Shell {
         Condition::NoOp
     }
 
+    fn execute_repeat(&mut self, count: &str, statements: Vec<Statement>) -> Condition {
+        let expanded = expand_string(count, self, false).join(" ");
+        if self.expansion_failed() {
+            self.previous_status = FAILURE;
+            return Condition::NoOp;
+        }
+        let count = match expanded.parse::<usize>() {
+            Ok(count) => count,
+            Err(_) => {
+                let message = format!("{}: expected a count of zero or more", expanded);
+                self.report(IonError::new(ErrorKind::Expansion, message).context("repeat"));
+                self.previous_status = FAILURE;
+                self.check_failure();
+                return Condition::NoOp;
+            }
+        };
+
+        for _ in 0..count {
+            // Cloning is needed so the statements can be executed again.
+            match self.execute_statements(statements.clone()) {
+                Condition::Break => break,
+                Condition::SigInt => return Condition::SigInt,
+                _ => (),
+            }
+        }
+        Condition::NoOp
+    }
+
+    fn execute_retry(&mut self, arguments: &str, statements: Vec<Statement>) -> Condition {
+        let mut expanded = Vec::new();
+        for argument in ArgumentSplitter::new(arguments) {
+            expanded.extend(expand_string(argument, self, false));
+        }
+        if self.expansion_failed() {
+            self.previous_status = FAILURE;
+            return Condition::NoOp;
+        }
+        let retry = match Retry::parse(&expanded) {
+            Ok(retry) => retry,
+            Err(why) => {
+                self.report(IonError::new(ErrorKind::Expansion, why).context("retry"));
+                self.previous_status = FAILURE;
+                self.check_failure();
+                return Condition::NoOp;
+            }
+        };
+
+        for attempt in 1..retry.attempts + 1 {
+            self.variables.set_var("attempt", &attempt.to_string());
+            // Attempts which fail are not failures of the script, unless they are the last.
+            let last = attempt == retry.attempts;
+            if !last {
+                self.conditions += 1;
+            }
+            let condition = self.execute_statements(statements.clone());
+            if !last {
+                self.conditions -= 1;
+            }
+            match condition {
+                Condition::Break => break,
+                Condition::SigInt => return Condition::SigInt,
+                _ => (),
+            }
+            if self.previous_status == SUCCESS || last {
+                break;
+            }
+
+            thread::sleep(retry.delay(attempt));
+            if self.was_interrupted() {
+                return Condition::SigInt;
+            }
+        }
+        Condition::NoOp
+    }
+
     fn execute_for(
         &mut self,
-        variable: &str,
+        variables: &[Identifier],
         values: &[String],
         statements: Vec<Statement>,
     ) -> Condition {
+        let expression = ForExpression::new(values, self);
+        if self.expansion_failed() {
+            self.previous_status = FAILURE;
+            return Condition::NoOp;
+        }
+
+        // With multiple variables, each iteration consumes as many values as there are
+        // variables, and the variables left over by the final iteration are set to be empty.
+        if variables.len() > 1 {
+            let values: Vec<Value> = match expression {
+                ForExpression::Multiple(values) => values,
+                ForExpression::Normal(values) => values.lines().map(Into::into).collect(),
+                ForExpression::Range(start, end) => (start..end).map(|x| x.to_string()).collect(),
+            };
+            for group in values.chunks(variables.len()) {
+                for (id, variable) in variables.iter().enumerate() {
+                    if &**variable != "_" {
+                        let value = group.get(id).map_or("", String::as_str);
+                        self.variables.set_var(variable, value);
+                    }
+                }
+                match self.execute_statements(statements.clone()) {
+                    Condition::Break => break,
+                    Condition::SigInt => return Condition::SigInt,
+                    _ => (),
+                }
+            }
+            return Condition::NoOp;
+        }
+
+        let variable: &str = &variables[0];
         let ignore_variable = variable == "_";
-        match ForExpression::new(values, self) {
+        match expression {
             ForExpression::Multiple(ref values) if ignore_variable => for _ in values.iter() {
                 match self.execute_statements(statements.clone()) {
                     Condition::Break => break,
@@ -597,11 +911,18 @@ impl FlowLogic for Shell {
         else_if: Vec<ElseIf>,
         failure: Vec<Statement>,
     ) -> Condition {
-        match self.run_pipeline(&mut expression) {
+        let status = self.run_condition(&mut expression);
+        if self.was_interrupted() {
+            return Condition::SigInt;
+        }
+        match status {
             Some(SUCCESS) => self.execute_statements(success),
             _ => {
                 for mut elseif in else_if {
-                    if self.run_pipeline(&mut elseif.expression) == Some(SUCCESS) {
+                    let status = self.run_condition(&mut elseif.expression);
+                    if self.was_interrupted() {
+                        return Condition::SigInt;
+                    } else if status == Some(SUCCESS) {
                         return self.execute_statements(elseif.success);
                     }
                 }
@@ -610,6 +931,18 @@ impl FlowLogic for Shell {
         }
     }
 
+    fn execute_with(&mut self, scope: Scope, statements: Vec<Statement>) -> Condition {
+        let depth = self.flow_control.scopes.len();
+        if !self.enter_scope(&scope) {
+            self.previous_status = FAILURE;
+            self.check_failure();
+            return Condition::NoOp;
+        }
+        let condition = self.execute_statements(statements);
+        self.leave_scopes(depth);
+        condition
+    }
+
     fn execute_toplevel<I>(
         &mut self,
         iterator: &mut I,
@@ -617,6 +950,7 @@ impl FlowLogic for Shell {
     ) -> Result<(), &'static str>
         where I: Iterator<Item = Statement>
     {
+        self.debug_statement(&statement, true);
         match statement {
             Statement::Error(number) => self.previous_status = number,
             // Execute a Let Statement
@@ -631,27 +965,58 @@ impl FlowLogic for Shell {
             Statement::While {
                 expression,
                 mut statements,
+                ..
             } => {
                 self.flow_control.level += 1;
 
                 // Collect all of the statements contained within the while block.
-                collect_loops(iterator, &mut statements, &mut self.flow_control.level);
+                let input = collect_loops(iterator, &mut statements, &mut self.flow_control.level);
 
                 if self.flow_control.level == 0 {
                     // All blocks were read, thus we can immediately execute now
-                    self.execute_while(expression, statements);
+                    self.execute_while(expression, statements, input);
                 } else {
                     // Store the partial `Statement::While` to memory
                     self.flow_control.current_statement = Statement::While {
                         expression: expression,
                         statements: statements,
+                        input:      None,
                     }
                 }
             }
+            // Collect the statements for the repeat loop, and if the loop is complete,
+            // execute it as many times as its count.
+            Statement::Repeat {
+                count,
+                mut statements,
+            } => {
+                self.flow_control.level += 1;
+                collect_loops(iterator, &mut statements, &mut self.flow_control.level);
+                if self.flow_control.level == 0 {
+                    self.execute_repeat(&count, statements);
+                } else {
+                    self.flow_control.current_statement = Statement::Repeat { count, statements };
+                }
+            }
+            // Collect the statements for the retry block, and if the block is complete,
+            // execute it until it succeeds.
+            Statement::Retry {
+                arguments,
+                mut statements,
+            } => {
+                self.flow_control.level += 1;
+                collect_loops(iterator, &mut statements, &mut self.flow_control.level);
+                if self.flow_control.level == 0 {
+                    self.execute_retry(&arguments, statements);
+                } else {
+                    self.flow_control.current_statement =
+                        Statement::Retry { arguments, statements };
+                }
+            }
             // Collect the statements for the for loop, and if the loop is complete,
             // execute the for loop with the provided expression.
             Statement::For {
-                variable,
+                variables,
                 values,
                 mut statements,
             } => {
@@ -662,11 +1027,11 @@ impl FlowLogic for Shell {
 
                 if self.flow_control.level == 0 {
                     // All blocks were read, thus we can immediately execute now
-                    self.execute_for(&variable, &values, statements);
+                    self.execute_for(&variables, &values, statements);
                 } else {
                     // Store the partial `Statement::For` to memory
                     self.flow_control.current_statement = Statement::For {
-                        variable:   variable,
+                        variables:  variables,
                         values:     values,
                         statements: statements,
                     }
@@ -723,8 +1088,21 @@ impl FlowLogic for Shell {
 
                 if self.flow_control.level == 0 {
                     // All blocks were read, thus we can add it to the list
-                    self.functions
-                        .insert(name.clone(), Function::new(description, name, args, statements));
+                    let location = self.location.clone();
+                    let source = Some(self.flow_control.text.clone());
+                    if self.may_define(&name) {
+                        let function = Function::new(
+                            description,
+                            name.clone(),
+                            args,
+                            statements,
+                            location,
+                            source,
+                        );
+                        self.functions.insert(name, function);
+                    } else {
+                        self.previous_status = FAILURE;
+                    }
                 } else {
                     // Store the partial function declaration in memory.
                     self.flow_control.current_statement = Statement::Function {
@@ -738,18 +1116,19 @@ impl FlowLogic for Shell {
             // Simply executes a provided pipeline, immediately.
             Statement::Pipeline(mut pipeline) => {
                 self.run_pipeline(&mut pipeline);
-                if self.flags & ERR_EXIT != 0 && self.previous_status != SUCCESS {
-                    let status = self.previous_status;
-                    self.exit(status);
-                }
+                self.check_failure();
+            }
+            Statement::Subshell(body) => {
+                let status = fork_subshell(self, &body);
+                self.variables.set_var("?", &status.to_string());
+                self.previous_status = status;
+                self.check_failure();
             }
             Statement::Time(box_statement) => {
                 let time = ::std::time::Instant::now();
 
                 if let Err(why) = self.execute_toplevel(iterator, *box_statement) {
-                    let stderr = io::stderr();
-                    let mut stderr = stderr.lock();
-                    let _ = writeln!(stderr, "{}", why);
+                    self.report(flow_error(None, &why));
                     self.flow_control.level = 0;
                     self.flow_control.current_if_mode = 0;
                 }
@@ -782,15 +1161,11 @@ impl FlowLogic for Shell {
             }
             // At this level, else and else if keywords are forbidden.
             Statement::ElseIf { .. } | Statement::Else => {
-                let stderr = io::stderr();
-                let mut stderr = stderr.lock();
-                let _ = writeln!(stderr, "ion: syntax error: not an if statement");
+                self.report(IonError::new(ErrorKind::Syntax, "not an if statement"));
             }
             // Likewise to else and else if, the end keyword does nothing here.
-            Statement::End => {
-                let stderr = io::stderr();
-                let mut stderr = stderr.lock();
-                let _ = writeln!(stderr, "ion: syntax error: no block to end");
+            Statement::End(_) => {
+                self.report(IonError::new(ErrorKind::Syntax, "no block to end"));
             }
             // Collect all cases that are being used by a match construct
             Statement::Match {
@@ -800,9 +1175,7 @@ impl FlowLogic for Shell {
                 self.flow_control.level += 1;
                 if let Err(why) = collect_cases(iterator, &mut cases, &mut self.flow_control.level)
                 {
-                    let stderr = io::stderr();
-                    let mut stderr = stderr.lock();
-                    let _ = writeln!(stderr, "{}", why);
+                    self.report(flow_error(None, &why));
                 }
                 if self.flow_control.level == 0 {
                     // If all blocks were read we execute the statement
@@ -812,6 +1185,19 @@ impl FlowLogic for Shell {
                     self.flow_control.current_statement = Statement::Match { expression, cases };
                 }
             }
+            // Collect the statements of a `with` block, and execute them if it is complete.
+            Statement::With {
+                scope,
+                mut statements,
+            } => {
+                self.flow_control.level += 1;
+                collect_loops(iterator, &mut statements, &mut self.flow_control.level);
+                if self.flow_control.level == 0 {
+                    self.execute_with(scope, statements);
+                } else {
+                    self.flow_control.current_statement = Statement::With { scope, statements };
+                }
+            }
             _ => {}
         }
         Ok(())
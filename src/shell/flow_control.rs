@@ -1,8 +1,12 @@
 use super::Shell;
+use super::errors::{ErrorKind, Frame, IonError};
 use super::flow::FlowLogic;
+use super::scopes::Saved;
 use fnv::*;
+use parser::Location;
 use parser::assignments::*;
 use parser::pipelines::Pipeline;
+use std::fmt::{self, Display, Formatter};
 use types::*;
 use types::Identifier;
 
@@ -40,6 +44,34 @@ pub(crate) struct Case {
     pub statements:  Vec<Statement>,
 }
 
+/// A setting of the shell process which a `with` block changes for the statements within it.
+/// The value is expanded when the block is executed.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Scope {
+    /// `with umask MODE`
+    Umask(String),
+    /// `with cd DIRECTORY`
+    Directory(String),
+}
+
+impl Scope {
+    pub(crate) fn value(&self) -> &str {
+        match *self {
+            Scope::Umask(ref mode) => mode,
+            Scope::Directory(ref directory) => directory,
+        }
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Scope::Umask(ref mode) => write!(f, "umask {}", mode),
+            Scope::Directory(ref directory) => write!(f, "cd {}", directory),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) enum LocalAction {
     List,
@@ -73,24 +105,47 @@ pub(crate) enum Statement {
         statements:  Vec<Statement>,
     },
     For {
-        variable:   Identifier,
+        variables:  Vec<Identifier>,
         values:     Vec<String>,
         statements: Vec<Statement>,
     },
+    /// A loop which is executed while its expression succeeds. If a file was given to its `end`,
+    /// as in `end < file`, the loop reads its standard input from that file.
     While {
         expression: Pipeline,
         statements: Vec<Statement>,
+        input:      Option<String>,
+    },
+    /// A block which is executed as many times as its count, which is expanded once.
+    Repeat {
+        count:      String,
+        statements: Vec<Statement>,
+    },
+    /// A block which is executed again until it succeeds, as configured by its arguments, which
+    /// are expanded once.
+    Retry {
+        arguments:  String,
+        statements: Vec<Statement>,
     },
     Match {
         expression: String,
         cases:      Vec<Case>,
     },
+    /// A block which is executed with a different umask or working directory, which is
+    /// restored when the block exits.
+    With {
+        scope:      Scope,
+        statements: Vec<Statement>,
+    },
     Else,
-    End,
+    /// The end of a block, which may give a file to read from, as in `end < file`.
+    End(Option<String>),
     Error(i32),
     Break,
     Continue,
     Pipeline(Pipeline),
+    /// The body of a `( ... )` group, which is evaluated in a forked child shell.
+    Subshell(String),
     Time(Box<Statement>),
     Default,
 }
@@ -106,23 +161,73 @@ impl Statement {
             Statement::Function { .. } => "Function { .. }",
             Statement::For { .. } => "For { .. }",
             Statement::While { .. } => "While { .. }",
+            Statement::Repeat { .. } => "Repeat { .. }",
+            Statement::Retry { .. } => "Retry { .. }",
             Statement::Match { .. } => "Match { .. }",
+            Statement::With { .. } => "With { .. }",
             Statement::Else => "Else",
-            Statement::End => "End",
+            Statement::End(_) => "End",
             Statement::Error(_) => "Error { .. }",
             Statement::Break => "Break",
             Statement::Continue => "Continue",
             Statement::Pipeline(_) => "Pipeline { .. }",
+            Statement::Subshell(_) => "Subshell { .. }",
             Statement::Time(_) => "Time { .. }",
             Statement::Default => "Default",
         }
     }
+
+    /// Describes a statement by the command which it was parsed from, or by its kind.
+    pub(crate) fn describe(&self) -> String {
+        match *self {
+            Statement::Pipeline(ref pipeline) => pipeline.to_string(),
+            Statement::Let(LocalAction::Assign(ref keys, operator, ref values))
+            | Statement::Export(ExportAction::Assign(ref keys, operator, ref values)) => {
+                let keyword = if let Statement::Let(_) = *self { "let" } else { "export" };
+                format!("{} {} {} {}", keyword, keys, operator, values)
+            }
+            Statement::If { ref expression, .. } => format!("if {}", expression),
+            Statement::While { ref expression, .. } => format!("while {}", expression),
+            Statement::Repeat { ref count, .. } => format!("repeat {}", count),
+            Statement::Retry { ref arguments, .. } => format!("retry {}", arguments),
+            Statement::For {
+                ref variables,
+                ref values,
+                ..
+            } => {
+                let variables = variables.iter().map(|variable| &**variable).collect::<Vec<&str>>();
+                format!("for {} in {}", variables.join(" "), values.join(" "))
+            }
+            Statement::Function { ref name, .. } => format!("fn {}", name),
+            Statement::Match { ref expression, .. } => format!("match {}", expression),
+            Statement::With { ref scope, .. } => format!("with {}", scope),
+            Statement::Subshell(ref body) => format!("({})", body),
+            Statement::Time(ref statement) => format!("time {}", statement.describe()),
+            ref statement => statement.short().into(),
+        }
+    }
 }
 
 pub(crate) struct FlowControl {
     pub level:             usize,
     pub current_statement: Statement,
     pub current_if_mode:   u8, // { 0 = SUCCESS; 1 = FAILURE }
+    /// The location at which the block in memory began to be defined.
+    pub location:          Option<Location>,
+    /// The lines of the block in memory, as they were written, so that the source of a function
+    /// may be printed once it has been defined.
+    pub text:              String,
+    /// The functions which are being called, from the first call to the most recent.
+    pub call_stack:        Vec<Frame>,
+    /// The line of the pipeline which is being executed, at which the functions that it calls
+    /// are located.
+    pub line:              Option<usize>,
+    /// The settings which were replaced by the `with` blocks that are being executed, from the
+    /// outermost block to the innermost.
+    pub scopes:            Vec<Saved>,
+    /// The functions which were replaced by those defined within the functions that are being
+    /// called, each with the number of calls that were being made when it was replaced.
+    pub shadowed:          Vec<(usize, Identifier, Option<Function>)>,
 }
 
 impl Default for FlowControl {
@@ -131,6 +236,12 @@ impl Default for FlowControl {
             level:             0,
             current_statement: Statement::Default,
             current_if_mode:   0,
+            location:          None,
+            text:              String::new(),
+            call_stack:        Vec::new(),
+            line:              None,
+            scopes:            Vec::new(),
+            shadowed:          Vec::new(),
         }
     }
 }
@@ -141,35 +252,74 @@ pub struct Function {
     name:        Identifier,
     args:        Vec<KeyBuf>,
     statements:  Vec<Statement>,
+    location:    Option<Location>,
+    /// The lines which defined the function, as they were written.
+    source:      Option<String>,
 }
 
 pub(crate) enum FunctionError {
+    RecursionLimit,
     InvalidArgumentCount,
     InvalidArgumentType(Primitive, String),
 }
 
+impl FunctionError {
+    /// Describes the error which occurred when the named function was called.
+    pub(crate) fn describe(self, name: &str) -> IonError {
+        let message = match self {
+            FunctionError::RecursionLimit => "maximum recursion depth exceeded".into(),
+            FunctionError::InvalidArgumentCount => "invalid number of arguments supplied".into(),
+            FunctionError::InvalidArgumentType(expected_type, value) => format!(
+                "argument has invalid type: expected {}, found value '{}'",
+                expected_type, value
+            ),
+        };
+        IonError::new(ErrorKind::Function, message).context(name)
+    }
+}
+
 impl Function {
     pub(crate) fn new(
         description: Option<String>,
         name: Identifier,
         args: Vec<KeyBuf>,
         statements: Vec<Statement>,
+        location: Option<Location>,
+        source: Option<String>,
     ) -> Function {
         Function {
             description,
             name,
             args,
             statements,
+            location,
+            source,
         }
     }
 
     pub(crate) fn get_description<'a>(&'a self) -> Option<&'a String> { self.description.as_ref() }
 
+    pub(crate) fn get_args(&self) -> &[KeyBuf] { &self.args }
+
+    pub(crate) fn get_source(&self) -> Option<&str> { self.source.as_ref().map(String::as_str) }
+
+    /// The script within which the function was defined, if any.
+    pub(crate) fn script(&self) -> Option<&str> {
+        self.location.as_ref().map(|location| location.script.as_str())
+    }
+
+    /// Renames the function, such as when it is moved into the namespace of a library.
+    pub(crate) fn rename(&mut self, name: Identifier) { self.name = name; }
+
     pub(crate) fn execute(self, shell: &mut Shell, args: &[&str]) -> Result<(), FunctionError> {
         if args.len() - 1 != self.args.len() {
             return Err(FunctionError::InvalidArgumentCount);
         }
 
+        if shell.depth >= shell.recursion_limit() {
+            return Err(FunctionError::RecursionLimit);
+        }
+
         let mut variables_backup: FnvHashMap<&str, Option<Value>> =
             FnvHashMap::with_capacity_and_hasher(64, Default::default());
 
@@ -197,7 +347,37 @@ impl Function {
             }
         }
 
+        // The call is located within the script of the function which made it, or within the
+        // script which is being executed if it was not made by a function.
+        let line = shell.flow_control.line;
+        let call_site = line.and_then(|line| {
+            let caller = match shell.flow_control.call_stack.last() {
+                Some(frame) => frame.location.as_ref(),
+                None => shell.location.as_ref(),
+            };
+            caller.map(|caller| Location::new(caller.script.as_str(), line))
+        });
+        shell.flow_control.call_stack.push(Frame {
+            function: self.name.to_string(),
+            location: self.location,
+            call_site,
+        });
+        if let Some(ref mut debugger) = shell.debugger {
+            debugger.call(&self.name);
+        }
+        if let Some(ref mut profiler) = shell.profiler {
+            profiler.enter();
+        }
+        shell.depth += 1;
         shell.execute_statements(self.statements);
+        shell.depth -= 1;
+        if let Some(ref mut profiler) = shell.profiler {
+            profiler.leave(&shell.flow_control.call_stack);
+        }
+        shell.flow_control.call_stack.pop();
+        shell.flow_control.line = line;
+        let calls = shell.flow_control.call_stack.len();
+        restore_functions(shell, calls);
 
         for (name, value_option) in &variables_backup {
             match *value_option {
@@ -221,6 +401,18 @@ impl Function {
     }
 }
 
+/// Restores the functions which were replaced by those defined within calls that have returned,
+/// keeping the functions which were defined within the first given number of calls.
+pub(crate) fn restore_functions(shell: &mut Shell, calls: usize) {
+    while shell.flow_control.shadowed.last().map_or(false, |&(depth, ..)| depth > calls) {
+        let (_, name, previous) = shell.flow_control.shadowed.pop().unwrap();
+        match previous {
+            Some(function) => shell.functions.insert(name, function),
+            None => shell.functions.remove(&name),
+        };
+    }
+}
+
 pub(crate) fn collect_cases<I>(
     iterator: &mut I,
     cases: &mut Vec<Case>,
@@ -252,16 +444,19 @@ pub(crate) fn collect_cases<I>(
                     add_to_case!(Statement::Case(case));
                 }
             }
-            Statement::End => {
+            Statement::End(_) => {
                 *level -= 1;
                 if *level == 0 {
                     return Ok(());
                 }
             }
             Statement::While { .. }
+            | Statement::Repeat { .. }
+            | Statement::Retry { .. }
             | Statement::For { .. }
             | Statement::If { .. }
             | Statement::Match { .. }
+            | Statement::With { .. }
             | Statement::Function { .. } => {
                 *level += 1;
                 add_to_case!(statement);
@@ -274,6 +469,7 @@ pub(crate) fn collect_cases<I>(
             | Statement::Continue
             | Statement::Let { .. }
             | Statement::Pipeline(_)
+            | Statement::Subshell(_)
             | Statement::Time(_)
             | Statement::Break => {
                 // This is the default case with all of the other statements explicitly listed
@@ -284,41 +480,50 @@ pub(crate) fn collect_cases<I>(
     return Ok(());
 }
 
+/// Collects the statements of a block until its `end`, returning the file which was given to
+/// that `end`, if the block was ended and given one.
 pub(crate) fn collect_loops<I: Iterator<Item = Statement>>(
     iterator: &mut I,
     statements: &mut Vec<Statement>,
     level: &mut usize,
-) {
+) -> Option<String> {
     #[allow(while_let_on_iterator)]
     while let Some(statement) = iterator.next() {
         match statement {
             Statement::While { .. }
+            | Statement::Repeat { .. }
+            | Statement::Retry { .. }
             | Statement::For { .. }
             | Statement::If { .. }
             | Statement::Function { .. }
-            | Statement::Match { .. } => *level += 1,
+            | Statement::Match { .. }
+            | Statement::With { .. } => *level += 1,
             Statement::Time(ref box_stmt) => match box_stmt.as_ref() {
                 &Statement::While { .. }
+                | &Statement::Repeat { .. }
+                | &Statement::Retry { .. }
                 | &Statement::For { .. }
                 | &Statement::If { .. }
                 | &Statement::Function { .. }
-                | &Statement::Match { .. } => *level += 1,
-                &Statement::End if *level == 1 => {
+                | &Statement::Match { .. }
+                | &Statement::With { .. } => *level += 1,
+                &Statement::End(_) if *level == 1 => {
                     *level = 0;
                     break;
                 }
-                &Statement::End => *level -= 1,
+                &Statement::End(_) => *level -= 1,
                 _ => (),
             },
-            Statement::End if *level == 1 => {
+            Statement::End(ref input) if *level == 1 => {
                 *level = 0;
-                break;
+                return input.clone();
             }
-            Statement::End => *level -= 1,
+            Statement::End(_) => *level -= 1,
             _ => (),
         }
         statements.push(statement);
     }
+    None
 }
 
 pub(crate) fn collect_if<I>(
@@ -335,10 +540,13 @@ pub(crate) fn collect_if<I>(
     while let Some(statement) = iterator.next() {
         match statement {
             Statement::While { .. }
+            | Statement::Repeat { .. }
+            | Statement::Retry { .. }
             | Statement::For { .. }
             | Statement::If { .. }
             | Statement::Function { .. }
-            | Statement::Match { .. } => *level += 1,
+            | Statement::Match { .. }
+            | Statement::With { .. } => *level += 1,
             Statement::ElseIf(ref elseif) if *level == 1 => if current_block == 1 {
                 return Err("ion: syntax error: else block already given");
             } else {
@@ -353,11 +561,11 @@ pub(crate) fn collect_if<I>(
             Statement::Else if *level == 1 && current_block == 1 => {
                 return Err("ion: syntax error: else block already given")
             }
-            Statement::End if *level == 1 => {
+            Statement::End(_) if *level == 1 => {
                 *level = 0;
                 break;
             }
-            Statement::End => *level -= 1,
+            Statement::End(_) => *level -= 1,
             _ => (),
         }
 
@@ -0,0 +1,250 @@
+//! The directories which have been visited within interactive sessions, ranked by their frecency:
+//! how frequently, and how recently, each was visited. Whenever a command changes the current
+//! directory, the visit is recorded within the database given by the `FRECENCY_FILE` variable,
+//! so that the `j` builtin may jump to the best match of a pattern, as in `j ion`.
+
+use super::Shell;
+use liner::Completer;
+use std::cmp::{self, Ordering};
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Once the ranks of the directories add up to more than this, each of them is aged, and those
+/// which are no longer ranked highly enough are forgotten.
+const MAX_RANK: f64 = 9000.0;
+
+/// A directory within the database, along with the number of times that it was visited, which
+/// is aged over time, and the time of its last visit, in seconds since the Unix epoch.
+#[derive(Debug, PartialEq)]
+struct Entry {
+    path: String,
+    rank: f64,
+    time: u64,
+}
+
+impl Entry {
+    /// The rank of the directory, which is weighted by how recently it was visited.
+    fn frecency(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.time);
+        if age < 3600 {
+            self.rank * 4.0
+        } else if age < 86400 {
+            self.rank * 2.0
+        } else if age < 604800 {
+            self.rank / 2.0
+        } else {
+            self.rank / 4.0
+        }
+    }
+}
+
+/// Parses the database, in which each line is a directory followed by its rank and time, which
+/// are separated by `|`. Lines which cannot be parsed are ignored.
+fn parse(database: &str) -> Vec<Entry> {
+    database
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.rsplitn(3, '|');
+            let time = fields.next()?.parse().ok()?;
+            let rank = fields.next()?.parse().ok()?;
+            let path = fields.next()?.to_owned();
+            Some(Entry { path, rank, time })
+        })
+        .collect()
+}
+
+fn serialize(entries: &[Entry]) -> String {
+    entries.iter().map(|entry| format!("{}|{}|{}\n", entry.path, entry.rank, entry.time)).collect()
+}
+
+/// Records a visit to the directory, and ages every directory once their ranks grow too high.
+fn visit(entries: &mut Vec<Entry>, path: &str, now: u64) {
+    match entries.iter().position(|entry| entry.path == path) {
+        Some(pos) => {
+            entries[pos].rank += 1.0;
+            entries[pos].time = now;
+        }
+        None => entries.push(Entry {
+            path: path.to_owned(),
+            rank: 1.0,
+            time: now,
+        }),
+    }
+
+    if entries.iter().map(|entry| entry.rank).sum::<f64>() > MAX_RANK {
+        for entry in entries.iter_mut() {
+            entry.rank *= 0.99;
+        }
+        entries.retain(|entry| entry.rank >= 1.0);
+    }
+}
+
+/// Whether the directory matches each of the patterns, in order, ignoring case. The last pattern
+/// must match the name of the directory itself, so that `j ion` jumps to `~/src/ion`, rather
+/// than to `~/src/ion/examples`.
+fn matches(path: &str, patterns: &[&str]) -> bool {
+    let (last, patterns) = match patterns.split_last() {
+        Some(patterns) => patterns,
+        None => return true,
+    };
+    let path = path.to_lowercase();
+    let mut start = 0;
+    for pattern in patterns {
+        let pattern = pattern.to_lowercase();
+        match path[start..].find(&pattern) {
+            Some(pos) => start += pos + pattern.len(),
+            None => return false,
+        }
+    }
+    let name = path.rfind('/').map_or(0, |pos| pos + 1);
+    path[cmp::max(start, name)..].contains(&last.to_lowercase())
+}
+
+/// The directories which match the patterns, from the most frecent, along with their frecency.
+fn ranked(entries: &[Entry], patterns: &[&str], now: u64) -> Vec<(f64, String)> {
+    let mut ranked: Vec<(f64, String)> = entries
+        .iter()
+        .filter(|entry| matches(&entry.path, patterns))
+        .map(|entry| (entry.frecency(now), entry.path.clone()))
+        .collect();
+    ranked.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    ranked
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs())
+}
+
+fn read(path: &Path) -> Vec<Entry> {
+    let mut database = String::new();
+    match File::open(path).and_then(|mut file| file.read_to_string(&mut database)) {
+        Ok(_) => parse(&database),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// The directories within the database which still exist and match the patterns, from the most
+/// frecent, along with their frecency.
+fn candidates(database: &Path, patterns: &[&str]) -> Vec<(f64, String)> {
+    ranked(&read(database), patterns, now())
+        .into_iter()
+        .filter(|&(_, ref path)| Path::new(path).is_dir())
+        .collect()
+}
+
+/// Completes the patterns given to the `j` builtin with the directories which they match.
+pub(crate) struct FrecencyCompleter {
+    database: PathBuf,
+}
+
+impl FrecencyCompleter {
+    pub(crate) fn new(database: PathBuf) -> FrecencyCompleter { FrecencyCompleter { database } }
+}
+
+impl Completer for FrecencyCompleter {
+    fn completions(&self, start: &str) -> Vec<String> {
+        candidates(&self.database, &[start]).into_iter().map(|(_, path)| path).collect()
+    }
+}
+
+/// Ranks the directories which are visited, so that they may be jumped to.
+pub(crate) trait Frecency {
+    /// The database of directories, unless `FRECENCY_FILE` is empty.
+    fn frecency_file(&self) -> Option<PathBuf>;
+
+    /// Records a visit to the current directory within the database.
+    fn visit_directory(&self);
+
+    /// The directories which match the patterns, from the most frecent, along with their
+    /// frecency.
+    fn frecent_directories(&self, patterns: &[&str]) -> Vec<(f64, String)>;
+}
+
+impl Frecency for Shell {
+    fn frecency_file(&self) -> Option<PathBuf> {
+        match self.variables.get_var("FRECENCY_FILE") {
+            Some(ref path) if !path.is_empty() => Some(PathBuf::from(path.as_str())),
+            _ => None,
+        }
+    }
+
+    fn visit_directory(&self) {
+        let (database, directory) = match (self.frecency_file(), env::current_dir()) {
+            (Some(database), Ok(directory)) => (database, directory),
+            _ => return,
+        };
+        // The database is read and written for each visit, so that the visits of concurrent
+        // sessions are all recorded.
+        let mut entries = read(&database);
+        visit(&mut entries, &directory.to_string_lossy(), now());
+        let temporary = database.with_extension("tmp");
+        let result = File::create(&temporary)
+            .and_then(|mut file| file.write_all(serialize(&entries).as_bytes()))
+            .and_then(|_| fs::rename(&temporary, &database));
+        if let Err(why) = result {
+            eprintln!("ion: unable to write to {}: {}", database.display(), why);
+        }
+    }
+
+    fn frecent_directories(&self, patterns: &[&str]) -> Vec<(f64, String)> {
+        self.frecency_file().map_or_else(Vec::new, |database| candidates(&database, patterns))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, rank: f64, time: u64) -> Entry {
+        Entry {
+            path: path.into(),
+            rank,
+            time,
+        }
+    }
+
+    #[test]
+    fn databases() {
+        let entries = parse("/home/ion|3|100\n/tmp/a|b|0.5|200\ncorrupt\n/srv|x|1\n");
+        assert_eq!(entries, vec![entry("/home/ion", 3.0, 100), entry("/tmp/a|b", 0.5, 200)]);
+        assert_eq!(parse(&serialize(&entries)), entries);
+    }
+
+    #[test]
+    fn visits() {
+        let mut entries = vec![entry("/home", 2.0, 100)];
+        visit(&mut entries, "/home", 200);
+        visit(&mut entries, "/tmp", 300);
+        assert_eq!(entries, vec![entry("/home", 3.0, 200), entry("/tmp", 1.0, 300)]);
+
+        let mut entries = vec![entry("/home", 9000.0, 100), entry("/tmp", 1.0, 100)];
+        visit(&mut entries, "/srv", 200);
+        assert_eq!(entries, vec![entry("/home", 8910.0, 100)]);
+    }
+
+    #[test]
+    fn matching() {
+        assert!(matches("/home/ion/src/ion-shell", &["ion"]));
+        assert!(matches("/home/ion/src/Ion-Shell", &["src", "shell"]));
+        assert!(!matches("/home/ion/src/ion-shell/examples", &["ion"]));
+        assert!(!matches("/home/ion/src/ion-shell", &["shell", "src"]));
+        assert!(matches("/home/ion/src/ion-shell", &[]));
+    }
+
+    #[test]
+    fn ranking() {
+        let now = 1_000_000;
+        let entries = vec![
+            entry("/src/ion", 10.0, now - 1_000_000),
+            entry("/src/ion-shell", 3.0, now - 60),
+            entry("/src/redox", 100.0, now),
+        ];
+        assert_eq!(
+            ranked(&entries, &["ion"], now),
+            vec![(12.0, "/src/ion-shell".into()), (2.5, "/src/ion".into())]
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! Implements the `git` namespace, which describes the repository of the current directory, so
+//! that prompts may show `$git::branch`, `$git::dirty`, and `$git::ahead_behind` without running
+//! `git status` before every prompt. The branch, and whether any tracked file was modified, are
+//! read from the repository's files. Whether changes are staged, and how far the branch is from
+//! its upstream, require git itself, and so are cached for each repository until its index or
+//! the commits which they were computed from change.
+
+use fnv::FnvHashMap;
+use std::cell::RefCell;
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+use types::Value;
+
+/// The mode of a submodule within the index, whose state is left to git.
+const GITLINK: u32 = 0o160_000;
+
+/// A file which is tracked by the index, along with its state when it was last staged.
+#[derive(Debug, PartialEq)]
+struct Entry {
+    path:  String,
+    mtime: u32,
+    size:  u32,
+    mode:  u32,
+    stage: u16,
+}
+
+/// Parses the entries of an index of version 2 or 3. Version 4, which compresses paths, is not
+/// supported, and so `None` is returned for it, as it is for an index which cannot be parsed.
+fn parse_index(data: &[u8]) -> Option<Vec<Entry>> {
+    // Numbers are stored in network byte order.
+    let number = |pos: usize, len: usize| -> Option<u32> {
+        data.get(pos..pos + len)
+            .map(|bytes| bytes.iter().fold(0, |value, &byte| value << 8 | u32::from(byte)))
+    };
+    if !data.starts_with(b"DIRC") {
+        return None;
+    }
+    let version = number(4, 4)?;
+    if version != 2 && version != 3 {
+        return None;
+    }
+
+    let mut entries = Vec::new();
+    let mut pos = 12;
+    for _ in 0..number(8, 4)? {
+        let flags = number(pos + 60, 2)?;
+        // Entries of version 3 may have a second field of flags.
+        let start = if version == 3 && flags & 0x4000 != 0 { pos + 64 } else { pos + 62 };
+        let length = data.get(start..)?.iter().position(|&byte| byte == 0)?;
+        entries.push(Entry {
+            path:  String::from_utf8_lossy(&data[start..start + length]).into_owned(),
+            mtime: number(pos + 8, 4)?,
+            size:  number(pos + 36, 4)?,
+            mode:  number(pos + 24, 4)?,
+            stage: ((flags >> 12) & 0x3) as u16,
+        });
+        // Each entry is padded with at least one null byte to a multiple of eight bytes.
+        pos += (start + length - pos + 8) & !7;
+    }
+    Some(entries)
+}
+
+/// Obtains the branch which is named by the contents of `HEAD`, or the abbreviated commit if
+/// the head is detached.
+fn head_branch(head: &str) -> String {
+    let head = head.trim();
+    if head.starts_with("ref: refs/heads/") {
+        head[16..].into()
+    } else if head.starts_with("ref: ") {
+        head[5..].into()
+    } else {
+        head.chars().take(7).collect()
+    }
+}
+
+/// Obtains the reference to the upstream of the branch, as configured by `branch.<name>.remote`
+/// and `branch.<name>.merge` within the configuration of the repository.
+fn upstream(config: &str, branch: &str) -> Option<String> {
+    let section = format!("[branch \"{}\"]", branch);
+    let (mut remote, mut merge) = (None, None);
+    let mut within = false;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            within = line == section;
+        } else if within {
+            let mut pair = line.splitn(2, '=').map(str::trim);
+            match (pair.next(), pair.next()) {
+                (Some("remote"), Some(value)) => remote = Some(value),
+                (Some("merge"), Some(value)) => merge = Some(value),
+                _ => (),
+            }
+        }
+    }
+    let merge = merge?;
+    match remote? {
+        "." => Some(merge.into()),
+        remote if merge.starts_with("refs/heads/") => {
+            Some(format!("refs/remotes/{}/{}", remote, &merge[11..]))
+        }
+        _ => None,
+    }
+}
+
+fn read_to_string(path: &Path) -> Option<String> {
+    let mut contents = String::new();
+    File::open(path).and_then(|mut file| file.read_to_string(&mut contents)).ok()?;
+    Some(contents)
+}
+
+/// The repository which contains the current directory.
+struct Repository {
+    /// The directory of the repository's files, which is usually `.git`.
+    git_dir:   PathBuf,
+    /// The directory which contains the references and configuration, which differs from the
+    /// former within worktrees.
+    common:    PathBuf,
+    work_tree: PathBuf,
+}
+
+impl Repository {
+    /// Finds the repository which contains the directory, or one of its parents. A `.git` file,
+    /// as is found within worktrees and submodules, gives the location of the repository.
+    fn find(directory: &Path) -> Option<Repository> {
+        let mut directory = Some(directory);
+        while let Some(dir) = directory {
+            let dot_git = dir.join(".git");
+            let git_dir = if dot_git.is_dir() {
+                Some(dot_git)
+            } else if dot_git.is_file() {
+                read_to_string(&dot_git).and_then(|contents| {
+                    let contents = contents.trim();
+                    if contents.starts_with("gitdir:") {
+                        Some(dir.join(contents[7..].trim()))
+                    } else {
+                        None
+                    }
+                })
+            } else {
+                None
+            };
+            if let Some(git_dir) = git_dir {
+                let common = read_to_string(&git_dir.join("commondir"))
+                    .map_or_else(|| git_dir.clone(), |common| git_dir.join(common.trim()));
+                return Some(Repository {
+                    git_dir,
+                    common,
+                    work_tree: dir.to_path_buf(),
+                });
+            }
+            directory = dir.parent();
+        }
+        None
+    }
+
+    fn head(&self) -> Option<String> { read_to_string(&self.git_dir.join("HEAD")) }
+
+    /// Resolves the reference to a commit, from either its file or the packed references.
+    fn resolve(&self, reference: &str) -> Option<String> {
+        if let Some(commit) = read_to_string(&self.common.join(reference)) {
+            return Some(commit.trim().into());
+        }
+        let packed = read_to_string(&self.common.join("packed-refs"))?;
+        let commit = packed.lines().filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some(commit), Some(name)) if name == reference => Some(commit.to_owned()),
+                _ => None,
+            }
+        }).next();
+        commit
+    }
+
+    /// The commit which is checked out.
+    fn head_commit(&self) -> Option<String> {
+        let head = self.head()?;
+        let head = head.trim();
+        if head.starts_with("ref: ") {
+            self.resolve(&head[5..])
+        } else {
+            Some(head.into())
+        }
+    }
+
+    /// The files which are tracked by the index, or `None` if the index could not be read.
+    fn entries(&self) -> Option<Vec<Entry>> {
+        let mut data = Vec::new();
+        let mut index = File::open(self.git_dir.join("index")).ok()?;
+        index.read_to_end(&mut data).ok()?;
+        parse_index(&data)
+    }
+
+    /// Whether any tracked file differs from the index, judging by its size and modification
+    /// time. A file which was touched without being changed is considered to be modified until
+    /// git refreshes the index, as it does upon `git status`.
+    fn modified(&self) -> Option<bool> {
+        let modified = self.entries()?.iter().any(|entry| {
+            if entry.stage != 0 {
+                // An unmerged file is in conflict.
+                return true;
+            }
+            if entry.mode == GITLINK {
+                return false;
+            }
+            match fs::symlink_metadata(self.work_tree.join(&entry.path)) {
+                // The index only records the lower 32 bits of each field.
+                Ok(metadata) => {
+                    metadata.size() as u32 != entry.size || metadata.mtime() as u32 != entry.mtime
+                }
+                Err(_) => true,
+            }
+        });
+        Some(modified)
+    }
+
+    /// Runs git within the work tree, and obtains its status and output.
+    fn git(&self, args: &[&str]) -> Option<(bool, String)> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&self.work_tree)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        Some((output.status.success(), String::from_utf8_lossy(&output.stdout).into_owned()))
+    }
+}
+
+/// The results of git which have been computed for a repository, along with the state of the
+/// repository that they were computed from.
+#[derive(Default)]
+struct Cache {
+    staged:       Option<((Option<SystemTime>, Option<String>), bool)>,
+    ahead_behind: Option<((String, String), String)>,
+}
+
+/// Provides the values of the `git` namespace, caching those which require git.
+#[derive(Default)]
+pub(crate) struct GitStatus {
+    caches: RefCell<FnvHashMap<PathBuf, Cache>>,
+}
+
+impl GitStatus {
+    /// Obtains `branch`, `dirty`, or `ahead_behind` for the repository of the current directory,
+    /// or `None` if the directory is not within a repository.
+    pub(crate) fn get(&self, name: &str) -> Option<Value> {
+        let repository = env::current_dir().ok().and_then(|dir| Repository::find(&dir))?;
+        match name {
+            "branch" => repository.head().map(|head| head_branch(&head)),
+            "dirty" => {
+                let dirty = repository.modified().unwrap_or(false) || self.staged(&repository);
+                Some(if dirty { "1".into() } else { "0".into() })
+            }
+            "ahead_behind" => self.ahead_behind(&repository),
+            _ => None,
+        }
+    }
+
+    /// Whether the index differs from the commit which is checked out.
+    fn staged(&self, repository: &Repository) -> bool {
+        let index = fs::metadata(repository.git_dir.join("index")).and_then(|m| m.modified());
+        let key = (index.ok(), repository.head_commit());
+        let mut caches = self.caches.borrow_mut();
+        let cache = caches.entry(repository.git_dir.clone()).or_insert_with(Cache::default);
+        match cache.staged {
+            Some((ref cached, staged)) if *cached == key => return staged,
+            _ => (),
+        }
+        // A repository without any commits has staged changes if anything was added.
+        let staged = match key.1 {
+            Some(_) => repository.git(&["diff", "--cached", "--quiet"]).map_or(false, |o| !o.0),
+            None => repository.entries().map_or(false, |entries| !entries.is_empty()),
+        };
+        cache.staged = Some((key, staged));
+        staged
+    }
+
+    /// The number of commits by which the branch is ahead of, and behind, its upstream, which
+    /// are separated by a space.
+    fn ahead_behind(&self, repository: &Repository) -> Option<Value> {
+        let head = repository.head()?;
+        let head = head.trim();
+        if !head.starts_with("ref: refs/heads/") {
+            return None;
+        }
+        let config = read_to_string(&repository.common.join("config"))?;
+        let upstream = upstream(&config, &head[16..])?;
+        let key = (repository.resolve(&head[5..])?, repository.resolve(&upstream)?);
+
+        let mut caches = self.caches.borrow_mut();
+        let cache = caches.entry(repository.git_dir.clone()).or_insert_with(Cache::default);
+        match cache.ahead_behind {
+            Some((ref cached, ref counts)) if *cached == key => return Some(counts.clone()),
+            _ => (),
+        }
+        let range = format!("{}...{}", key.0, key.1);
+        let counts = match repository.git(&["rev-list", "--left-right", "--count", &range])? {
+            (true, output) => output.split_whitespace().collect::<Vec<&str>>().join(" "),
+            (false, _) => return None,
+        };
+        cache.ahead_behind = Some((key, counts.clone()));
+        Some(counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branches() {
+        assert_eq!(head_branch("ref: refs/heads/master\n"), "master");
+        assert_eq!(head_branch("ref: refs/heads/feature/git\n"), "feature/git");
+        assert_eq!(head_branch("6184328a1b2c3d4e5f60718293a4b5c6d7e8f901\n"), "6184328");
+    }
+
+    #[test]
+    fn upstreams() {
+        let config = "[core]\n\tbare = false\n[branch \"master\"]\n\tremote = origin\n\tmerge = \
+                      refs/heads/master\n[branch \"local\"]\n\tremote = .\n\tmerge = \
+                      refs/heads/master\n";
+        assert_eq!(upstream(config, "master"), Some("refs/remotes/origin/master".into()));
+        assert_eq!(upstream(config, "local"), Some("refs/heads/master".into()));
+        assert_eq!(upstream(config, "topic"), None);
+    }
+
+    #[test]
+    fn index() {
+        let mut data = b"DIRC\0\0\0\x02\0\0\0\x01".to_vec();
+        let mut entry = vec![0; 62];
+        entry[8..12].copy_from_slice(&[0x5a, 0, 0, 1]);
+        entry[24..28].copy_from_slice(&[0, 0, 0x81, 0xa4]);
+        entry[36..40].copy_from_slice(&[0, 0, 0, 42]);
+        entry[60..62].copy_from_slice(&[0, 10]);
+        entry.extend_from_slice(b"src/lib.rs");
+        entry.extend_from_slice(&[0, 0]);
+        data.extend(entry);
+        assert_eq!(
+            parse_index(&data),
+            Some(vec![
+                Entry {
+                    path:  "src/lib.rs".into(),
+                    mtime: 0x5a00_0001,
+                    size:  42,
+                    mode:  0o100_644,
+                    stage: 0,
+                },
+            ])
+        );
+        data[7] = 4;
+        assert_eq!(parse_index(&data), None);
+    }
+}
@@ -0,0 +1,191 @@
+//! Guards which have been registered with the `guard` builtin, which protect against destructive
+//! commands, such as `rm -rf /*` or `> /etc/*`. Before a builtin or an external command is
+//! executed, its expanded command line, and the files to which it redirects, are matched against
+//! the pattern of each guard. When one matches, the function of that guard is called to confirm
+//! the command, which is only executed if the function succeeds. As the pipeline is matched after
+//! aliases and variables have been expanded, neither may hide the real target of a command.
+
+use super::Shell;
+use super::errors::{ErrorKind, IonError};
+use super::flags::NO_EXEC;
+use super::status::*;
+use glob::Pattern;
+use parser::pipelines::{Input, Pipeline, RedirectFrom};
+
+/// A pattern which requires a function to confirm the commands that it matches.
+pub(crate) struct Guard {
+    pub(crate) pattern:  String,
+    matcher:             Pattern,
+    pub(crate) function: String,
+}
+
+#[derive(Default)]
+pub(crate) struct Guards {
+    guards: Vec<Guard>,
+    /// Set while a function is confirming a command, so that the commands which the function
+    /// executes are not guarded themselves.
+    active: bool,
+}
+
+impl Guards {
+    /// Registers a guard, replacing the guard which had the same pattern, if any.
+    pub(crate) fn set(&mut self, pattern: &str, function: &str) -> Result<(), String> {
+        let matcher =
+            Pattern::new(pattern).map_err(|why| format!("{}: invalid pattern: {}", pattern, why))?;
+        let guard = Guard {
+            pattern: pattern.into(),
+            matcher,
+            function: function.into(),
+        };
+        match self.guards.iter().position(|guard| guard.pattern == pattern) {
+            Some(id) => self.guards[id] = guard,
+            None => self.guards.push(guard),
+        }
+        Ok(())
+    }
+
+    /// Removes the guard with the given pattern, returning whether it existed.
+    pub(crate) fn remove(&mut self, pattern: &str) -> bool {
+        let length = self.guards.len();
+        self.guards.retain(|guard| guard.pattern != pattern);
+        self.guards.len() != length
+    }
+
+    pub(crate) fn list(&self) -> &[Guard] { &self.guards }
+
+    /// The first guard whose pattern matches any of the targets.
+    fn find(&self, targets: &[String]) -> Option<&Guard> {
+        self.guards
+            .iter()
+            .find(|guard| targets.iter().any(|target| guard.matcher.matches(target)))
+    }
+}
+
+/// The strings which guards are matched against: the command line of each job, as in
+/// `rm -rf /tmp`, and each of its redirections, as in `> /etc/passwd` or `< /dev/sda`.
+fn targets(pipeline: &Pipeline) -> Vec<String> {
+    let mut targets = Vec::new();
+    for item in &pipeline.items {
+        targets.push(item.job.args.join(" "));
+        for input in &item.inputs {
+            if let Input::File(ref file) = *input {
+                targets.push(format!("< {}", file));
+            }
+        }
+        for output in &item.outputs {
+            let operator = match (output.from, output.append) {
+                (RedirectFrom::Stdout, false) => ">",
+                (RedirectFrom::Stdout, true) => ">>",
+                (RedirectFrom::Stderr, false) => "^>",
+                (RedirectFrom::Stderr, true) => "^>>",
+                (RedirectFrom::Both, false) => "&>",
+                (RedirectFrom::Both, true) => "&>>",
+            };
+            targets.push(format!("{} {}", operator, output.file));
+        }
+    }
+    targets
+}
+
+/// Confirms pipelines which are matched by a guard before they are executed.
+pub(crate) trait Confirm {
+    /// Calls the function of the first guard which matches the expanded pipeline, with the
+    /// pipeline and the pattern as its arguments. Returns whether the pipeline may be executed,
+    /// which it may not if the function fails or does not exist.
+    fn confirm(&mut self, pipeline: &Pipeline) -> bool;
+}
+
+impl Confirm for Shell {
+    fn confirm(&mut self, pipeline: &Pipeline) -> bool {
+        if self.guards.active || self.flags & NO_EXEC != 0 {
+            return true;
+        }
+        let (pattern, function) = match self.guards.find(&targets(pipeline)) {
+            Some(guard) => (guard.pattern.clone(), guard.function.clone()),
+            None => return true,
+        };
+
+        let command = pipeline.to_string();
+        let confirmed = match self.functions.get(function.as_str()).cloned() {
+            Some(confirmation) => {
+                self.guards.active = true;
+                let args = [function.as_str(), command.as_str(), pattern.as_str()];
+                let result = confirmation.execute(self, &args);
+                self.guards.active = false;
+                match result {
+                    Ok(()) => self.previous_status == SUCCESS,
+                    Err(why) => {
+                        self.report(why.describe(&function));
+                        false
+                    }
+                }
+            }
+            None => {
+                let message = format!("{}: function not found", function);
+                self.report(IonError::new(ErrorKind::Pipeline, message).context("guard"));
+                false
+            }
+        };
+
+        if !confirmed {
+            let message = format!("{}: not confirmed by {}", pattern, function);
+            self.report(IonError::new(ErrorKind::Pipeline, message).context("guard"));
+        }
+        confirmed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::pipelines::{PipeItem, Redirection};
+    use shell::{Job, JobKind};
+
+    fn item(args: &[&str], outputs: Vec<Redirection>, inputs: Vec<Input>) -> PipeItem {
+        let args = args.iter().map(|&arg| arg.to_owned()).collect();
+        PipeItem::new(Job::new(args, JobKind::Last), outputs, inputs)
+    }
+
+    #[test]
+    fn pipeline_targets() {
+        let output = Redirection {
+            from:   RedirectFrom::Stderr,
+            file:   "/etc/hosts".into(),
+            append: true,
+        };
+        let pipeline = Pipeline {
+            items: vec![
+                item(&["rm", "-rf", "/tmp/a"], Vec::new(), vec![Input::HereString("x".into())]),
+                item(&["cat"], vec![output], vec![Input::File("/dev/sda".into())]),
+            ],
+            line:  None,
+        };
+        assert_eq!(
+            targets(&pipeline),
+            vec!["rm -rf /tmp/a", "cat", "< /dev/sda", "^>> /etc/hosts"]
+        );
+    }
+
+    #[test]
+    fn matching() {
+        let mut guards = Guards::default();
+        guards.set("rm -rf /*", "confirm").unwrap();
+        guards.set("> /etc/*", "deny").unwrap();
+        let find = |guards: &Guards, target: &str| {
+            guards.find(&[target.to_owned()]).map(|guard| guard.function.clone())
+        };
+        assert_eq!(find(&guards, "rm -rf /"), Some("confirm".into()));
+        assert_eq!(find(&guards, "rm -rf /home/user"), Some("confirm".into()));
+        assert_eq!(find(&guards, "rm -rf ./build"), None);
+        assert_eq!(find(&guards, "> /etc/passwd"), Some("deny".into()));
+        assert_eq!(find(&guards, ">> /etc/passwd"), None);
+
+        guards.set("> /etc/*", "confirm").unwrap();
+        assert_eq!(guards.list().len(), 2);
+        assert_eq!(find(&guards, "> /etc/passwd"), Some("confirm".into()));
+        assert!(guards.remove("rm -rf /*"));
+        assert!(!guards.remove("rm -rf /*"));
+        assert_eq!(find(&guards, "rm -rf /"), None);
+        assert!(guards.set("[", "confirm").is_err());
+    }
+}
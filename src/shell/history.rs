@@ -1,4 +1,6 @@
 use super::Shell;
+use super::aliases::expand_line;
+use super::flags::SAVE_HISTORY;
 use super::status::*;
 
 use regex::Regex;
@@ -39,26 +41,30 @@ pub(crate) trait ShellHistory {
     /// Prints the commands contained within the history buffers to standard output.
     fn print_history(&self, _arguments: &[&str]) -> i32;
 
-    /// Sets the history size for the shell context equal to the HISTORY_SIZE shell variable if
+    /// Sets the history size for the line editor equal to the HISTORY_SIZE shell variable if
     /// it
     /// is set otherwise to a default value (1000).
     ///
     /// If the HISTFILE_ENABLED shell variable is set to 1, then HISTFILE_SIZE is synced
-    /// with the shell context as well. Otherwise, the history file name is set to None in the
-    /// shell context.
+    /// with the line editor as well. Otherwise, the history file name is set to None in the
+    /// line editor.
     ///
     /// This is called in on_command so that the history length and history file state will be
     /// updated correctly after a command is entered that alters them and just before loading
     /// the
     /// history file so that it will be loaded correctly.
-    fn set_context_history_from_vars(&mut self);
+    fn set_editor_history_from_vars(&mut self);
 
-    /// Saves a command in the history, depending on @HISTORY_IGNORE. Should be called
-    /// immediately after `on_command()`
+    /// Saves a command in the history, depending on @HISTORY_IGNORE, with its aliases expanded
+    /// if HISTORY_EXPAND_ALIASES is set to 1. Should be called immediately after `on_command()`
     fn save_command_in_history(&mut self, command: &str);
 
     /// Updates the history ignore patterns. Call this whenever HISTORY_IGNORE is changed.
     fn update_ignore_patterns(&mut self, patterns: &Array);
+
+    /// Obtains the most recent command recorded in the history, which is exposed to scripts as
+    /// `${history::last}`.
+    fn last_command(&self) -> Option<String>;
 }
 
 trait ShellHistoryPrivate {
@@ -69,9 +75,9 @@ trait ShellHistoryPrivate {
 
 impl ShellHistory for Shell {
     fn print_history(&self, _arguments: &[&str]) -> i32 {
-        if let Some(context) = self.context.as_ref() {
+        if let Some(editor) = self.editor.as_ref() {
             let mut buffer = Vec::with_capacity(8 * 1024);
-            for command in &context.history.buffers {
+            for command in editor.history() {
                 let _ = writeln!(buffer, "{}", command);
             }
             let stdout = io::stdout();
@@ -83,35 +89,45 @@ impl ShellHistory for Shell {
         }
     }
 
-    fn set_context_history_from_vars(&mut self) {
-        let context = self.context.as_mut().unwrap();
+    fn set_editor_history_from_vars(&mut self) {
+        let editor = self.editor.as_mut().unwrap();
         let max_history_size =
             self.variables.get_var_or_empty("HISTORY_SIZE").parse().unwrap_or(1000);
 
-        context.history.set_max_size(max_history_size);
+        editor.set_history_size(max_history_size);
 
         if &*self.variables.get_var_or_empty("HISTFILE_ENABLED") == "1" {
             let file_name = self.variables.get_var("HISTFILE");
-            context.history.set_file_name(file_name.map(|f| f.into()));
+            editor.set_history_file(file_name.map(|f| f.into()));
 
             let max_histfile_size =
                 self.variables.get_var_or_empty("HISTFILE_SIZE").parse().unwrap_or(1000);
-            context.history.set_max_file_size(max_histfile_size);
+            editor.set_history_file_size(max_histfile_size);
         } else {
-            context.history.set_file_name(None);
+            editor.set_history_file(None);
         }
     }
 
     fn save_command_in_history(&mut self, command: &str) {
         if self.should_save_command(command) {
-            // Mark the command in the context history
-            self.set_context_history_from_vars();
-            if let Err(err) = self.context.as_mut().unwrap().history.push(command.into()) {
+            let expanded = if "1" == self.variables.get_var_or_empty("HISTORY_EXPAND_ALIASES") {
+                expand_line(&self.variables, command)
+            } else {
+                None
+            };
+            let command = expanded.as_ref().map_or(command, String::as_str);
+            // Mark the command in the editor's history
+            self.set_editor_history_from_vars();
+            if let Err(err) = self.editor.as_mut().unwrap().push_history(command) {
                 eprintln!("ion: {}", err);
             }
         }
     }
 
+    fn last_command(&self) -> Option<String> {
+        self.editor.as_ref().and_then(|editor| editor.last_history())
+    }
+
     fn update_ignore_patterns(&mut self, patterns: &Array) {
         let mut flags = IgnoreFlags::empty();
         let mut regexes = Vec::new();
@@ -146,6 +162,10 @@ impl ShellHistoryPrivate for Shell {
         let ignore = &self.ignore_setting.flags;
         let regexes = &self.ignore_setting.regexes;
 
+        if self.flags & SAVE_HISTORY == 0 {
+            return false;
+        }
+
         // without the second check the command which sets the local variable would also be
         // ignored. However, this behavior might not be wanted.
         if ignore.contains(IGNORE_ALL) && !command.contains("HISTORY_IGNORE") {
@@ -0,0 +1,236 @@
+//! Libraries of functions which scripts import with the `import` builtin, as in `import math`.
+//! A library named `NAME` is the file `NAME.ion`, which is searched for within the directory of
+//! the script that imports it, and then within the directories of `IMPORT_PATH`. Each library is
+//! only sourced once, however many scripts import it. A library may also be imported into a
+//! namespace, as in `import math as m`, so that its functions are called as `m::mean`.
+
+use super::Shell;
+use super::flow_control::Function;
+use builtins::source::source;
+use fnv::FnvHashMap;
+use parser::pipelines::Pipeline;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use types::Identifier;
+
+/// Whether the name of a library is valid: words of alphanumeric characters and underscores,
+/// which may be separated by slashes to name a library within a subdirectory, as in `net/http`.
+pub(crate) fn is_valid_name(name: &str) -> bool { name.split('/').all(is_valid_namespace) }
+
+/// Whether a namespace is a valid word of alphanumeric characters and underscores.
+pub(crate) fn is_valid_namespace(namespace: &str) -> bool {
+    !namespace.is_empty() && namespace.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Finds the file of a library within the directory of the script which imports it, and then
+/// within the directories of `IMPORT_PATH`, which are separated by colons.
+fn find(name: &str, directory: Option<&Path>, import_path: &str) -> Option<PathBuf> {
+    let file = format!("{}.ion", name);
+    directory
+        .into_iter()
+        .map(Path::to_path_buf)
+        .chain(import_path.split(':').filter(|dir| !dir.is_empty()).map(PathBuf::from))
+        .map(|directory| directory.join(&file))
+        .find(|path| path.is_file())
+}
+
+/// Describes the cycle which importing the library would complete, if it is already being
+/// imported by one of the libraries which it imports.
+fn cycle(importing: &[PathBuf], path: &Path) -> Option<String> {
+    let start = importing.iter().position(|library| library == path)?;
+    let mut libraries: Vec<String> =
+        importing[start..].iter().map(|library| library.display().to_string()).collect();
+    libraries.push(path.display().to_string());
+    Some(format!("cyclic import: {}", libraries.join(" -> ")))
+}
+
+/// Moves the functions which the script defined into the namespace, as `NAMESPACE::NAME`, and
+/// restores the functions of the same names which they replaced.
+fn enter_namespace(
+    functions: &mut FnvHashMap<Identifier, Function>,
+    mut previous: FnvHashMap<Identifier, Function>,
+    script: &str,
+    namespace: &str,
+) {
+    let names: Vec<Identifier> = functions
+        .iter()
+        .filter(|&(name, function)| !name.contains("::") && function.script() == Some(script))
+        .map(|(name, _)| name.clone())
+        .collect();
+    for name in names {
+        let mut function = match functions.remove(&name) {
+            Some(function) => function,
+            None => continue,
+        };
+        if let Some(replaced) = previous.remove(&name) {
+            functions.insert(name.clone(), replaced);
+        }
+        let qualified: Identifier = format!("{}::{}", namespace, name).as_str().into();
+        function.rename(qualified.clone());
+        functions.insert(qualified, function);
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Imports {
+    /// The libraries which have been imported, and the namespaces into which they were imported.
+    imported:  Vec<(PathBuf, Option<String>)>,
+    /// The libraries which are being imported, in the order in which they imported one another,
+    /// so that a library which imports itself, directly or not, may be detected.
+    importing: Vec<PathBuf>,
+}
+
+impl Imports {
+    pub(crate) fn imported(&self) -> &[(PathBuf, Option<String>)] { &self.imported }
+}
+
+/// Imports libraries of functions.
+pub(crate) trait Import {
+    /// Sources the library with the given name, unless it was already imported into the same
+    /// namespace. When a namespace is given, the functions which the library defines are moved
+    /// into it once the library has been sourced.
+    fn import(&mut self, name: &str, namespace: Option<&str>) -> Result<(), String>;
+
+    /// Qualifies the commands of the pipeline which name functions within the namespace of the
+    /// function being executed, so that the functions of a library which was imported into a
+    /// namespace may call one another without it.
+    fn qualify(&self, pipeline: &mut Pipeline);
+}
+
+impl Import for Shell {
+    fn import(&mut self, name: &str, namespace: Option<&str>) -> Result<(), String> {
+        let directory = match self.location {
+            Some(ref location) => Path::new(&location.script).parent().map(Path::to_path_buf),
+            None => env::current_dir().ok(),
+        };
+        let import_path = self.variables.get_var_or_empty("IMPORT_PATH");
+        let path = find(name, directory.as_ref().map(PathBuf::as_path), &import_path)
+            .ok_or_else(|| format!("{}: library not found", name))?;
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        if let Some(cycle) = cycle(&self.imports.importing, &path) {
+            return Err(cycle);
+        }
+        let import = (path, namespace.map(String::from));
+        if self.imports.imported.contains(&import) {
+            return Ok(());
+        }
+
+        // The functions which the library replaces are restored once it has been sourced, if its
+        // own functions are moved into a namespace.
+        let previous = namespace.map(|_| self.functions.clone());
+        let script = import.0.to_string_lossy().into_owned();
+        self.imports.importing.push(import.0.clone());
+        let result = source(self, &["source", script.as_str()]);
+        self.imports.importing.pop();
+        result.map_err(|why| why.trim_right().trim_left_matches("ion: ").to_owned())?;
+
+        if let (Some(namespace), Some(previous)) = (namespace, previous) {
+            enter_namespace(&mut self.functions, previous, &script, namespace);
+        }
+        self.imports.imported.push(import);
+        Ok(())
+    }
+
+    fn qualify(&self, pipeline: &mut Pipeline) {
+        let namespace = match self.flow_control.call_stack.last() {
+            Some(frame) => match frame.function.rfind("::") {
+                Some(pos) => &frame.function[..pos + 2],
+                None => return,
+            },
+            None => return,
+        };
+        for item in &mut pipeline.items {
+            if self.builtins.contains_key(&item.job.args[0]) {
+                continue;
+            }
+            let qualified = [namespace, item.job.args[0].as_str()].concat();
+            if self.functions.contains_key(qualified.as_str()) {
+                item.job.command = qualified.as_str().into();
+                item.job.args[0] = qualified;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::Location;
+    use std::fs::File;
+
+    #[test]
+    fn names() {
+        assert!(is_valid_name("math_utils"));
+        assert!(is_valid_name("net/http"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("../secrets"));
+        assert!(!is_valid_name("/etc/passwd"));
+        assert!(!is_valid_name("math.ion"));
+        assert!(!is_valid_namespace("a::b"));
+    }
+
+    #[test]
+    fn searching() {
+        let root = env::temp_dir().join(format!("ion-imports-{}", ::std::process::id()));
+        let (script, library) = (root.join("script"), root.join("library"));
+        fs::create_dir_all(script.join("net")).unwrap();
+        fs::create_dir_all(&library).unwrap();
+        for path in &[
+            script.join("math.ion"),
+            script.join("net/http.ion"),
+            library.join("math.ion"),
+            library.join("strings.ion"),
+        ] {
+            File::create(path).unwrap();
+        }
+
+        let import_path = format!("/nonexistent::{}", library.display());
+        let search = |name: &str| find(name, Some(&script), &import_path);
+        let found = (search("math"), search("net/http"), search("strings"), search("lists"));
+        let without_directory = find("math", None, &import_path);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(found.0, Some(script.join("math.ion")));
+        assert_eq!(found.1, Some(script.join("net/http.ion")));
+        assert_eq!(found.2, Some(library.join("strings.ion")));
+        assert_eq!(found.3, None);
+        assert_eq!(without_directory, Some(library.join("math.ion")));
+    }
+
+    #[test]
+    fn cycles() {
+        let importing = vec![PathBuf::from("/a.ion"), PathBuf::from("/b.ion")];
+        assert_eq!(cycle(&importing, Path::new("/c.ion")), None);
+        assert_eq!(
+            cycle(&importing, Path::new("/a.ion")),
+            Some("cyclic import: /a.ion -> /b.ion -> /a.ion".into())
+        );
+        assert_eq!(
+            cycle(&importing, Path::new("/b.ion")),
+            Some("cyclic import: /b.ion -> /b.ion".into())
+        );
+    }
+
+    #[test]
+    fn namespaces() {
+        let function = |name: &str, script: Option<&str>| {
+            let location = script.map(|script| Location::new(script, 1));
+            Function::new(None, name.into(), Vec::new(), Vec::new(), location, None)
+        };
+        let mut previous = FnvHashMap::default();
+        previous.insert("mean".into(), function("mean", None));
+        let mut functions = previous.clone();
+        functions.insert("mean".into(), function("mean", Some("/lib/math.ion")));
+        functions.insert("sum".into(), function("sum", Some("/lib/math.ion")));
+        functions.insert("m::sum".into(), function("m::sum", Some("/lib/math.ion")));
+        functions.insert("greet".into(), function("greet", Some("/lib/greet.ion")));
+        enter_namespace(&mut functions, previous, "/lib/math.ion", "math");
+
+        let mut names = functions.keys().map(|name| &**name).collect::<Vec<&str>>();
+        names.sort();
+        assert_eq!(names, vec!["greet", "m::sum", "math::mean", "math::sum", "mean"]);
+        assert_eq!(functions["mean"].script(), None);
+        assert_eq!(functions["math::mean"].script(), Some("/lib/math.ion"));
+    }
+}
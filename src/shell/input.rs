@@ -0,0 +1,98 @@
+//! The standard input of the shell, which a `while` loop may redirect from a file, as in
+//! `while read line; ...; end < file`, and from which the `read` builtin reads one line at a time,
+//! so that a file of any size may be processed without first being read into memory.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
+use sys;
+
+/// The shell's standard input, which is redirected from a file until it is dropped.
+pub(crate) struct Input {
+    backup: File,
+}
+
+impl Input {
+    pub(crate) fn redirect(path: &str) -> Result<Input, String> {
+        let file = File::open(path).map_err(|why| format!("{}: {}", path, why))?;
+        let backup = match sys::dup(sys::STDIN_FILENO) {
+            Ok(backup) => unsafe { File::from_raw_fd(backup) },
+            Err(why) => return Err(format!("failed to `dup` the standard input: {}", why)),
+        };
+        sys::dup2(file.as_raw_fd(), sys::STDIN_FILENO)
+            .map_err(|why| format!("failed to redirect the standard input: {}", why))?;
+        Ok(Input { backup })
+    }
+}
+
+impl Drop for Input {
+    fn drop(&mut self) { let _ = sys::dup2(self.backup.as_raw_fd(), sys::STDIN_FILENO); }
+}
+
+/// Reads the next line from the standard input, without its newline, or `None` if the input
+/// has ended. Bytes which follow the line are left unread, so that the commands which are
+/// executed next, which share the standard input, may read them in turn. Files are read in
+/// chunks, after which the file's offset is moved back to the end of the line, whereas pipes
+/// and terminals, which cannot seek, are read one byte at a time.
+pub(crate) fn read_line() -> io::Result<Option<String>> {
+    // The descriptor is borrowed, and so must not be closed when the file is dropped.
+    let mut stdin = unsafe { File::from_raw_fd(sys::STDIN_FILENO) };
+    let line = read_line_from(&mut stdin);
+    let _ = stdin.into_raw_fd();
+    line
+}
+
+fn read_line_from(file: &mut File) -> io::Result<Option<String>> {
+    let seekable = file.seek(SeekFrom::Current(0)).is_ok();
+    let mut line = Vec::new();
+    let mut buffer = [0; 4096];
+    let mut empty = true;
+    loop {
+        let chunk = if seekable { &mut buffer[..] } else { &mut buffer[..1] };
+        let read = match file.read(chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(ref why) if why.kind() == io::ErrorKind::Interrupted => continue,
+            Err(why) => return Err(why),
+        };
+        empty = false;
+        if let Some(newline) = chunk[..read].iter().position(|&byte| byte == b'\n') {
+            line.extend_from_slice(&chunk[..newline]);
+            if seekable {
+                file.seek(SeekFrom::Current(newline as i64 + 1 - read as i64))?;
+            }
+            return Ok(Some(String::from_utf8_lossy(&line).into_owned()));
+        }
+        line.extend_from_slice(&chunk[..read]);
+    }
+    // The last line of the input need not end with a newline.
+    Ok(if empty { None } else { Some(String::from_utf8_lossy(&line).into_owned()) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::io::Write;
+    use std::process;
+
+    #[test]
+    fn lines() {
+        let path = env::temp_dir().join(format!("ion-input-{}", process::id()));
+        File::create(&path).unwrap().write_all(b"one\n\nthree\nfour").unwrap();
+
+        let mut file = File::open(&path).unwrap();
+        assert_eq!(read_line_from(&mut file).unwrap(), Some("one".into()));
+        assert_eq!(read_line_from(&mut file).unwrap(), Some("".into()));
+        assert_eq!(read_line_from(&mut file).unwrap(), Some("three".into()));
+        // The remainder of the input is left for whichever reads it next.
+        let mut rest = String::new();
+        file.try_clone().unwrap().read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "four");
+        file.seek(SeekFrom::Start(11)).unwrap();
+        assert_eq!(read_line_from(&mut file).unwrap(), Some("four".into()));
+        assert_eq!(read_line_from(&mut file).unwrap(), None);
+
+        let _ = ::std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,97 @@
+//! Isolation of external commands with `isolate`, which executes a command within new Linux
+//! namespaces, or within another root directory, so that scripts may confine the commands which
+//! they execute without depending upon other tools.
+
+use std::ffi::CString;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use sys::namespaces::{self, MOUNT, NETWORK, PID, USER};
+use types::Value;
+
+/// The namespaces, and the root directory, within which an external command is executed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Isolation {
+    namespaces: u8,
+    root:       Option<CString>,
+}
+
+impl Isolation {
+    /// Parses the options which follow `isolate`, returning the isolation which they describe
+    /// and the command which follows them.
+    pub(crate) fn parse(args: &[Value]) -> Result<(Isolation, &[Value]), String> {
+        let mut isolation = Isolation::default();
+        let mut id = 0;
+        while let Some(arg) = args.get(id) {
+            let namespace = match arg.as_str() {
+                "-m" | "--mount" => MOUNT,
+                "-p" | "--pid" => PID,
+                "-n" | "--net" => NETWORK,
+                "-u" | "--user" => USER,
+                "-r" | "--root" => {
+                    let root = args
+                        .get(id + 1)
+                        .ok_or_else(|| format!("{}: expected a directory", arg))?;
+                    let root = CString::new(root.as_str())
+                        .map_err(|_| format!("{}: {}: invalid directory", arg, root))?;
+                    isolation.root = Some(root);
+                    id += 2;
+                    continue;
+                }
+                "--" => {
+                    id += 1;
+                    break;
+                }
+                _ if arg.starts_with('-') => return Err(format!("{}: invalid option", arg)),
+                _ => break,
+            };
+            isolation.namespaces |= namespace;
+            id += 1;
+        }
+
+        if id < args.len() {
+            Ok((isolation, &args[id..]))
+        } else {
+            Err("expected a command".into())
+        }
+    }
+
+    /// Isolates the command once it has been forked. This must follow the closure which moves
+    /// the child into the process group of its pipeline, as entering a PID namespace forks the
+    /// child again.
+    pub(crate) fn apply(&self, command: &mut Command) {
+        let (namespaces, root) = (self.namespaces, self.root.clone());
+        command.before_exec(move || {
+            namespaces::enter(namespaces, root.as_ref().map(|root| &**root))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<Value> { args.iter().map(|&arg| arg.to_owned()).collect() }
+
+    #[test]
+    fn options() {
+        let args = args(&["-u", "--net", "--root", "/srv/jail", "--", "-v", "--pid"]);
+        let (isolation, command) = Isolation::parse(&args).unwrap();
+        assert_eq!(isolation.namespaces, USER | NETWORK);
+        assert_eq!(isolation.root, Some(CString::new("/srv/jail").unwrap()));
+        assert_eq!(command, &args[5..]);
+
+        let args = self::args(&["-m", "-p", "ps", "-e"]);
+        let (isolation, command) = Isolation::parse(&args).unwrap();
+        assert_eq!(isolation.namespaces, MOUNT | PID);
+        assert_eq!(isolation.root, None);
+        assert_eq!(command, &args[2..]);
+    }
+
+    #[test]
+    fn invalid_options() {
+        assert_eq!(Isolation::parse(&args(&["-n"])), Err("expected a command".into()));
+        let error = String::from("--root: expected a directory");
+        assert_eq!(Isolation::parse(&args(&["--root"])), Err(error));
+        assert_eq!(Isolation::parse(&args(&["--ipc", "ls"])), Err("--ipc: invalid option".into()));
+    }
+}
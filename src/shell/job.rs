@@ -4,9 +4,10 @@ use std::process::{Command, Stdio};
 // use glob::glob;
 
 use super::Shell;
+use super::isolation::Isolation;
 use parser::ArgumentSplitter;
 use parser::expand_string;
-use parser::pipelines::RedirectFrom;
+use parser::pipelines::{Input, RedirectFrom, Redirection};
 use smallstring::SmallString;
 use std::str;
 use types::*;
@@ -39,7 +40,7 @@ impl Job {
 
     /// Takes the current job's arguments and expands them, one argument at a
     /// time, returning a new `Job` with the expanded arguments.
-    pub(crate) fn expand(&mut self, shell: &Shell) {
+    pub(crate) fn expand(&mut self, shell: &mut Shell) {
         let mut expanded = Array::new();
         expanded.grow(self.args.len());
         expanded.extend(self.args.drain().flat_map(|arg| match arg.as_str() {
@@ -66,7 +67,7 @@ pub(crate) enum Operation {
 ///
 /// If `last_arg` is set to `true`, then only the last argument of
 /// the last command will be expanded.
-pub(crate) fn expand_last_command(shell: &Shell, operation: Operation) -> Array {
+pub(crate) fn expand_last_command(shell: &mut Shell, operation: Operation) -> Array {
     fn get_last_arg(buffer: &str) -> &str { ArgumentSplitter::new(buffer).last().unwrap_or(buffer) }
 
     fn get_first_arg(buffer: &str) -> &str {
@@ -87,14 +88,13 @@ pub(crate) fn expand_last_command(shell: &Shell, operation: Operation) -> Array
         buffer
     }
 
-    fn expand_args(buffer: &str, shell: &Shell) -> Array {
+    fn expand_args(buffer: &str, shell: &mut Shell) -> Array {
         ArgumentSplitter::new(buffer).flat_map(|b| expand_arg(b, shell)).collect::<Array>()
     }
 
-    if let Some(ref context) = shell.context {
-        if let Some(buffer) = context.history.buffers.iter().last() {
-            let buffer = buffer.as_bytes();
-            let buffer = unsafe { str::from_utf8_unchecked(&buffer) };
+    if let Some(ref editor) = shell.editor {
+        if let Some(buffer) = editor.last_history() {
+            let buffer = buffer.as_str();
             return match operation {
                 Operation::LastArg => expand_arg(get_last_arg(buffer), shell),
                 Operation::FirstArg => expand_arg(get_first_arg(buffer), shell),
@@ -109,7 +109,7 @@ pub(crate) fn expand_last_command(shell: &Shell, operation: Operation) -> Array
 }
 
 /// Expands a given argument and returns it as an `Array`.
-fn expand_arg(arg: &str, shell: &Shell) -> Array {
+fn expand_arg(arg: &str, shell: &mut Shell) -> Array {
     let res = expand_string(&arg, shell, false);
     if res.is_empty() {
         array![""]
@@ -122,7 +122,14 @@ fn expand_arg(arg: &str, shell: &Shell) -> Array {
 /// as part of some pipeline
 pub(crate) enum RefinedJob {
     /// An external program that is executed by this shell
-    External(Command),
+    External {
+        /// The command which executes the program
+        command:   Command,
+        /// The program and its arguments, as they were expanded, which describe the job
+        args:      Array,
+        /// The isolation which `isolate` gave the program
+        isolation: Option<Isolation>,
+    },
     /// A procedure embedded into Ion
     Builtin {
         /// Name of the procedure
@@ -152,6 +159,8 @@ pub(crate) enum RefinedJob {
     /// Represents redirection into stdin from more than one source
     Cat {
         sources: Vec<File>,
+        /// The redirections which opened each of the sources
+        inputs:  Vec<Input>,
         stdin:   Option<File>,
         stdout:  Option<File>,
     },
@@ -169,6 +178,8 @@ pub struct TeeItem {
     /// stdout and stderr.
     pub source: Option<File>,
     pub sinks: Vec<File>,
+    /// The redirections which opened each of the sinks.
+    pub outputs: Vec<Redirection>,
 }
 
 impl TeeItem {
@@ -229,7 +240,7 @@ impl TeeItem {
 macro_rules! set_field {
     ($self:expr, $field:ident, $arg:expr) => {
         match *$self {
-            RefinedJob::External(ref mut command) => {
+            RefinedJob::External { ref mut command, .. } => {
                 command.$field(Stdio::from($arg));
             }
             RefinedJob::Builtin { ref mut $field,  .. } |
@@ -244,6 +255,14 @@ macro_rules! set_field {
 }
 
 impl RefinedJob {
+    pub(crate) fn external(command: Command, args: Array, isolation: Option<Isolation>) -> Self {
+        RefinedJob::External {
+            command,
+            args,
+            isolation,
+        }
+    }
+
     pub(crate) fn builtin(name: Identifier, args: Array) -> Self {
         RefinedJob::Builtin {
             name,
@@ -264,9 +283,10 @@ impl RefinedJob {
         }
     }
 
-    pub(crate) fn cat(sources: Vec<File>) -> Self {
+    pub(crate) fn cat(sources: Vec<File>, inputs: Vec<Input>) -> Self {
         RefinedJob::Cat {
             sources,
+            inputs,
             stdin: None,
             stdout: None,
         }
@@ -302,45 +322,90 @@ impl RefinedJob {
     }
 
     /// Returns a short description of this job: often just the command
-    /// or builtin name
+    /// or builtin name, or the redirections of a job which reads or writes several files
     pub(crate) fn short(&self) -> String {
         match *self {
-            RefinedJob::External(ref cmd) => {
-                format!("{:?}", cmd).split('"').nth(1).unwrap_or("").to_string()
+            RefinedJob::External { ref args, .. } => {
+                args.first().map_or(String::new(), |program| program.to_string())
             }
             RefinedJob::Builtin { ref name, .. } | RefinedJob::Function { ref name, .. } => {
                 name.to_string()
             }
-            // TODO: Print for real
-            RefinedJob::Cat { .. } => "multi-input".into(),
-            RefinedJob::Tee { .. } => "multi-output".into(),
+            RefinedJob::Cat { .. } | RefinedJob::Tee { .. } => self.long(),
         }
     }
 
-    /// Returns a long description of this job: the commands and arguments
+    /// Returns a long description of this job: the commands and arguments, which are quoted
+    /// wherever they would otherwise be split or expanded.
     pub(crate) fn long(&self) -> String {
         match *self {
-            RefinedJob::External(ref cmd) => {
-                let command = format!("{:?}", cmd);
-                let mut arg_iter = command.split_whitespace();
-                let command = arg_iter.next().unwrap();
-                let mut output = String::from(&command[1..command.len() - 1]);
-                for argument in arg_iter {
-                    output.push(' ');
-                    if argument.len() > 2 {
-                        output.push_str(&argument[1..argument.len() - 1]);
-                    } else {
-                        output.push_str(&argument);
+            RefinedJob::External { ref args, .. }
+            | RefinedJob::Builtin { ref args, .. }
+            | RefinedJob::Function { ref args, .. } => quote_all(args),
+            RefinedJob::Cat { ref inputs, .. } => {
+                inputs.iter().map(describe_input).collect::<Vec<String>>().join(" ")
+            }
+            RefinedJob::Tee { items: (ref out, ref err), .. } => {
+                // A redirection of both streams is given to both items, but is described once.
+                let mut outputs: Vec<&Redirection> = Vec::new();
+                for item in out.iter().chain(err.iter()) {
+                    for output in &item.outputs {
+                        if !outputs.contains(&output) {
+                            outputs.push(output);
+                        }
                     }
                 }
-                output
+                outputs.into_iter().map(describe_output).collect::<Vec<String>>().join(" ")
             }
-            RefinedJob::Builtin { ref args, .. } | RefinedJob::Function { ref args, .. } => {
-                format!("{}", args.join(" "))
+        }
+    }
+}
+
+/// Describes an input redirection as it would be written.
+fn describe_input(input: &Input) -> String {
+    match *input {
+        Input::File(ref file) => ["< ", &quote(file)].concat(),
+        Input::HereString(ref string) => ["<<< ", &quote(string.trim_right_matches('\n'))].concat(),
+    }
+}
+
+/// Describes an output redirection as it would be written.
+fn describe_output(output: &Redirection) -> String {
+    let operator = match (output.from, output.append) {
+        (RedirectFrom::Stdout, false) => "> ",
+        (RedirectFrom::Stdout, true) => ">> ",
+        (RedirectFrom::Stderr, false) => "^> ",
+        (RedirectFrom::Stderr, true) => "^>> ",
+        (RedirectFrom::Both, false) => "&> ",
+        (RedirectFrom::Both, true) => "&>> ",
+    };
+    [operator, &quote(&output.file)].concat()
+}
+
+/// Quotes each of the arguments which require it, and joins them with spaces.
+fn quote_all<S: AsRef<str>>(args: &[S]) -> String {
+    args.iter().map(|arg| quote(arg.as_ref())).collect::<Vec<String>>().join(" ")
+}
+
+/// Quotes an argument which would be split, expanded, or interpreted as an operator if it were
+/// written as it is, preferring single quotes, which do not require any escapes.
+fn quote(arg: &str) -> String {
+    let special =
+        |c: char| c.is_whitespace() || c.is_control() || "'\"\\$@|&;<>(){}[]*?#~".contains(c);
+    if !arg.is_empty() && !arg.contains(special) {
+        arg.into()
+    } else if !arg.contains('\'') {
+        format!("'{}'", arg)
+    } else {
+        let mut quoted = String::from("\"");
+        for character in arg.chars() {
+            if "\"\\$@".contains(character) {
+                quoted.push('\\');
             }
-            // TODO: Figure out real printing
-            RefinedJob::Cat { .. } | RefinedJob::Tee { .. } => "".into(),
+            quoted.push(character);
         }
+        quoted.push('"');
+        quoted
     }
 }
 
@@ -350,11 +415,42 @@ mod tests {
 
     #[test]
     fn preserve_empty_arg() {
-        let shell = Shell::new();
+        let mut shell = Shell::new();
         let job = Job::new(array!("rename", "", "0", "a"), JobKind::Last);
         let mut expanded = job.clone();
-        expanded.expand(&shell);
+        expanded.expand(&mut shell);
         assert_eq!(job, expanded);
     }
 
+    #[test]
+    fn long_descriptions() {
+        let args = array!("grep", "-e", "a b", "", "it's", "ü\n");
+        let job = RefinedJob::external(Command::new("grep"), args, None);
+        assert_eq!(job.short(), "grep");
+        assert_eq!(job.long(), "grep -e 'a b' '' \"it's\" 'ü\n'");
+        let job = RefinedJob::builtin("echo".into(), array!("echo", "$HOME", "*"));
+        assert_eq!(job.long(), "echo '$HOME' '*'");
+    }
+
+    #[test]
+    fn redirection_descriptions() {
+        let inputs = vec![Input::File("my file".into()), Input::HereString("text\n".into())];
+        let job = RefinedJob::cat(Vec::new(), inputs);
+        assert_eq!(job.short(), "< 'my file' <<< text");
+
+        let output = |from, file: &str, append| Redirection { from, file: file.into(), append };
+        let both = output(RedirectFrom::Both, "both", false);
+        let out = TeeItem {
+            source:  None,
+            sinks:   Vec::new(),
+            outputs: vec![output(RedirectFrom::Stdout, "out", false), both.clone()],
+        };
+        let err = TeeItem {
+            source:  None,
+            sinks:   Vec::new(),
+            outputs: vec![both, output(RedirectFrom::Stderr, "err", true)],
+        };
+        let job = RefinedJob::tee(Some(out), Some(err));
+        assert_eq!(job.long(), "> out &> both ^>> err");
+    }
 }
@@ -1,4 +1,5 @@
 use super::{Binary, FlowLogic, Shell};
+use super::errors::IonError;
 use super::status::*;
 use std::fs::File;
 use std::io::{self, Read};
@@ -10,10 +11,16 @@ pub trait IonLibrary {
     /// Executes all of the statements contained within a given script,
     /// returning the final exit status.
     fn execute_script<P: AsRef<Path>>(&mut self, path: P) -> io::Result<i32>;
+    /// Collects errors rather than printing them to stderr, so that they may be retrieved with
+    /// `take_errors`. Errors are printed again once collection is disabled.
+    fn collect_errors(&mut self, enable: bool);
+    /// Removes and returns the errors which have been collected.
+    fn take_errors(&mut self) -> Vec<IonError>;
 }
 
 impl IonLibrary for Shell {
     fn execute_command(&mut self, command: &str) -> i32 {
+        self.break_flow = false;
         self.on_command(command);
         self.previous_status
     }
@@ -24,9 +31,15 @@ impl IonLibrary for Shell {
         let capacity = file.metadata().ok().map_or(0, |x| x.len());
         let mut command_list = String::with_capacity(capacity as usize);
         let _ = file.read_to_string(&mut command_list)?;
-        if FAILURE == self.terminate_script_quotes(command_list.lines().map(|x| x.to_owned())) {
+        let script = path.to_string_lossy();
+        let lines = command_list.lines().map(|x| x.to_owned());
+        if FAILURE == self.terminate_script_quotes(&script, lines) {
             self.previous_status = FAILURE;
         }
         Ok(self.previous_status)
     }
+
+    fn collect_errors(&mut self, enable: bool) { self.reporter.collect(enable); }
+
+    fn take_errors(&mut self) -> Vec<IonError> { self.reporter.take() }
 }
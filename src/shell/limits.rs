@@ -0,0 +1,91 @@
+//! Resource limits for external commands, with `limit`, as in `limit cpu=30s mem=512MiB cmd`.
+//! The limits are set within the child before the command is executed, so that they apply to the
+//! command and to the processes which it creates, but not to the shell.
+
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use sys::limits::{self, CPU, FILES, FILE_SIZE, MEMORY, PROCESSES};
+use types::Value;
+use units;
+
+/// The resources which may be limited, by the names which `limit` gives them.
+const RESOURCES: [(&str, u8); 5] =
+    [("cpu", CPU), ("mem", MEMORY), ("fsize", FILE_SIZE), ("nofile", FILES), ("nproc", PROCESSES)];
+
+/// The limits of the resources of an external command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Limits {
+    limits: Vec<(u8, u64)>,
+}
+
+impl Limits {
+    /// Parses the limits of the form `RESOURCE=VALUE` which follow `limit`, returning them and
+    /// the command which follows them. Values may be given with the suffix of a unit, as in
+    /// `30s` or `512MiB`.
+    pub(crate) fn parse(args: &[Value]) -> Result<(Limits, &[Value]), String> {
+        let mut limits = Vec::new();
+        for (id, arg) in args.iter().enumerate() {
+            let pos = match arg.find('=') {
+                Some(pos) => pos,
+                None if limits.is_empty() => return Err("expected a limit, as in cpu=30s".into()),
+                None => return Ok((Limits { limits }, &args[id..])),
+            };
+            let resource = RESOURCES
+                .iter()
+                .find(|&&(name, _)| name == &arg[..pos])
+                .map(|&(_, resource)| resource)
+                .ok_or_else(|| format!("{}: no such resource", &arg[..pos]))?;
+            let value = match units::parse_integer(&arg[pos + 1..]) {
+                Some(value) if value >= 0 => value as u64,
+                _ => return Err(format!("{}: expected a whole number", arg)),
+            };
+            limits.push((resource, value));
+        }
+        Err("expected a command".into())
+    }
+
+    /// Sets the limits within the child of the command, before it is executed. Limits which
+    /// could not be set are reported beforehand, as nothing may be allocated within the child.
+    pub(crate) fn apply(&self, command: &mut Command) -> Result<(), String> {
+        for &(resource, value) in &self.limits {
+            limits::check(resource, value).map_err(|why| why.to_string())?;
+        }
+        let limits = self.limits.clone();
+        command.before_exec(move || {
+            for &(resource, value) in &limits {
+                limits::set(resource, value)?;
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<Value> { args.iter().map(|&arg| arg.to_owned()).collect() }
+
+    #[test]
+    fn limits() {
+        let args = args(&["cpu=30s", "mem=512MiB", "nofile=64", "python3", "x=1"]);
+        let (limits, command) = Limits::parse(&args).unwrap();
+        assert_eq!(limits.limits, vec![(CPU, 30), (MEMORY, 536_870_912), (FILES, 64)]);
+        assert_eq!(command, &args[3..]);
+
+        let args = self::args(&["cpu=2min", "fsize=1K", "./a.out"]);
+        let (limits, _) = Limits::parse(&args).unwrap();
+        assert_eq!(limits.limits, vec![(CPU, 120), (FILE_SIZE, 1000)]);
+    }
+
+    #[test]
+    fn invalid_limits() {
+        let parse = |list: &[&str]| Limits::parse(&args(list)).map(|(limits, _)| limits);
+        assert_eq!(parse(&["ls"]), Err("expected a limit, as in cpu=30s".into()));
+        assert_eq!(parse(&["cpu=1s"]), Err("expected a command".into()));
+        assert_eq!(parse(&["disk=1G", "ls"]), Err("disk: no such resource".into()));
+        assert_eq!(parse(&["cpu=1.5s", "ls"]), Err("cpu=1.5s: expected a whole number".into()));
+        assert_eq!(parse(&["nproc=-1", "ls"]), Err("nproc=-1: expected a whole number".into()));
+    }
+}
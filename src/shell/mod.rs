@@ -1,16 +1,46 @@
+mod aliases;
 mod assignments;
+mod audit;
 mod binary;
 mod completer;
+mod confirmation;
+mod debugger;
+mod diagnostics;
+mod environment;
 mod flow;
+mod git;
+mod guards;
 mod history;
+mod input;
+mod isolation;
 mod job;
+mod osc;
+mod limits;
 mod pipe_exec;
+mod profiler;
+mod recovery;
+mod retry;
+mod sandbox;
+mod scopes;
+mod segments;
+mod testing;
+mod translate;
+mod xdg;
+pub(crate) mod autoload;
+pub(crate) mod capture;
 pub(crate) mod colors;
 pub(crate) mod directory_stack;
+pub(crate) mod editor;
+pub(crate) mod frecency;
+pub(crate) mod imports;
 pub mod flags;
 pub(crate) mod plugins;
 pub(crate) mod flow_control;
+pub(crate) mod options;
 pub(crate) mod signals;
+pub(crate) mod traps;
+pub(crate) mod warnings;
+pub mod errors;
 pub mod status;
 pub mod variables;
 pub mod library;
@@ -21,34 +51,57 @@ pub(crate) use self::history::{IgnoreSetting, ShellHistory};
 pub(crate) use self::job::{Job, JobKind};
 pub(crate) use self::pipe_exec::{foreground, job_control};
 
+use self::aliases::expand_aliases;
+use self::audit::Audit;
+use self::capture::Capture;
+use self::autoload::{Autoload, Autoloads};
+use self::confirmation::Ask;
+use self::debugger::Debugger;
 use self::directory_stack::DirectoryStack;
+use self::editor::LineEditor;
+use self::errors::{ErrorKind, IonError, Reporter};
 use self::flags::*;
-use self::flow_control::{FlowControl, Function, FunctionError};
+use self::flow_control::{FlowControl, Function};
+use self::frecency::Frecency;
+use self::git::GitStatus;
 use self::foreground::ForegroundSignals;
+use self::guards::{Confirm, Guards};
+use self::imports::{Import, Imports};
 use self::job_control::{BackgroundProcess, JobControl};
 use self::library::IonLibrary;
 use self::pipe_exec::PipelineExecution;
+use self::profiler::Profiler;
+use self::sandbox::Random;
+use self::scopes::Scopes;
+use self::segments::Segments;
 use self::status::*;
+use self::traps::Traps;
 use self::variables::Variables;
-use app_dirs::{app_root, AppDataType, AppInfo};
+use self::warnings::{Warn, Warning, Warnings};
 use builtins::{BuiltinMap, BUILTINS};
 use fnv::FnvHashMap;
-use liner::Context;
-use parser::{ArgumentSplitter, Expander, Select};
+use parser::{expand_string, Expander, Location, Select};
+use parser::assignments::is_boolean;
 use parser::pipelines::Pipeline;
 use smallvec::SmallVec;
+use std::cell::Cell;
 use std::env;
 use std::fs::File;
 use std::io::{self, Write};
 use std::ops::Deref;
+use std::path::Path;
 use std::process;
-use std::ptr;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::Ordering;
 use std::time::SystemTime;
 use sys;
 use types::*;
 
+/// The recursion limit that is used when `RECURSION_LIMIT` is not set to a valid number.
+const DEFAULT_RECURSION_LIMIT: usize = 256;
+/// Set in `expansion_errors` when expansions were nested beyond the recursion limit.
+const TOO_DEEP: u32 = 0x8000_0000;
+
 /// The shell structure is a megastructure that manages all of the state of the shell throughout
 /// the entirety of the
 /// program. It is initialized at the beginning of the program, and lives until the end of the
@@ -56,9 +109,9 @@ use types::*;
 pub struct Shell {
     /// Contains a list of built-in commands that were created when the program started.
     pub builtins: &'static BuiltinMap,
-    /// Contains the history, completions, and manages writes to the history file.
-    /// Note that the context is only available in an interactive session.
-    pub context: Option<Context>,
+    /// Reads commands, and contains the history, which it writes to the history file.
+    /// Note that the editor is only available in an interactive session.
+    pub(crate) editor: Option<Box<LineEditor>>,
     /// Contains the aliases, strings, and array variable maps.
     pub variables: Variables,
     /// Contains the current state of flow control parameters.
@@ -72,7 +125,7 @@ pub struct Shell {
     /// The job ID of the previous command sent to the background.
     pub previous_job: u32,
     /// Contains all the boolean flags that control shell behavior.
-    pub flags: u8,
+    pub flags: u32,
     /// A temporary field for storing foreground PIDs used by the pipeline execution.
     foreground: Vec<u32>,
     /// Contains information on all of the active background processes that are being managed
@@ -82,6 +135,12 @@ pub struct Shell {
     pub is_background_shell: bool,
     /// Set when a signal is received, this will tell the flow control logic to abort.
     pub break_flow: bool,
+    /// The number of files which are being sourced within one another, from which `return` may
+    /// return.
+    pub(crate) sourcing: usize,
+    /// Set by `return` to the status with which the file that is being sourced returns, until
+    /// the file stops being sourced.
+    pub(crate) returning: Option<i32>,
     // Useful for disabling the execution of the `tcsetpgrp` call.
     pub is_library: bool,
     /// When the `fg` command is run, this will be used to communicate with the specified
@@ -90,8 +149,47 @@ pub struct Shell {
     /// Stores the patterns used to determine whether a command should be saved in the history
     /// or not
     ignore_setting: IgnoreSetting,
-    /// A pointer to itself which should only be used when performing a subshell expansion.
-    pointer: *mut Shell,
+    /// Contains the handlers that were registered with the `trap` builtin.
+    pub(crate) traps: Traps,
+    /// Contains the patterns that were registered with the `guard` builtin.
+    pub(crate) guards: Guards,
+    /// Set when the shell was started with `--confirm`, until the user confirms all commands.
+    pub(crate) confirming: bool,
+    /// The functions which may be loaded from the directories of `FPATH`.
+    pub(crate) autoloads: Autoloads,
+    /// The libraries which have been imported with the `import` builtin.
+    pub(crate) imports: Imports,
+    /// The number of function calls and `eval` commands that are currently being executed.
+    pub(crate) depth: usize,
+    /// The number of expansions that are currently being evaluated within one another.
+    expansion_depth: Cell<usize>,
+    /// The number of conditions that are currently being evaluated, such as the expression of an
+    /// `if` statement. Failures within a condition will not trigger the `ERR` trap or `errexit`.
+    pub(crate) conditions: u16,
+    /// Set when the previous status was produced by a condition, such as the left-hand side of
+    /// an `&&` operator whose right-hand side was not executed.
+    pub(crate) conditional_status: bool,
+    /// Records the strict mode flags (`NO_UNSET` and `FAIL_GLOB`) whose errors were triggered by
+    /// the last expansion, along with `TOO_DEEP` if expansions were nested beyond the limit.
+    expansion_errors: Cell<u32>,
+    /// The script and line of the next command to be executed, by which its errors are located.
+    pub(crate) location: Option<Location>,
+    /// Renders errors to stderr, or collects them for the program which embeds the shell.
+    pub(crate) reporter: Reporter,
+    /// The state of the debugger, when the shell was started with `--debug`.
+    pub(crate) debugger: Option<Debugger>,
+    /// Records the time spent within statements, when the shell was started with `--profile`.
+    pub(crate) profiler: Option<Profiler>,
+    /// Generates the numbers which `$RANDOM` expands to.
+    random: Random,
+    /// Describes the repository of the current directory to the `git` namespace.
+    git: GitStatus,
+    /// The segments of the `async` namespace, whose functions are executed in the background.
+    segments: Segments,
+    /// The commands which replace external commands of the same name within the sandbox.
+    pub(crate) fake_commands: FnvHashMap<Identifier, Value>,
+    /// The level of each kind of warning, which is set by `ion --warn` and `pragma warn`.
+    pub(crate) warnings: Warnings,
 }
 
 impl<'a> Shell {
@@ -100,22 +198,42 @@ impl<'a> Shell {
     pub(crate) fn new_bin() -> Shell {
         Shell {
             builtins:            BUILTINS,
-            context:             None,
+            editor:              None,
             variables:           Variables::default(),
             flow_control:        FlowControl::default(),
             directory_stack:     DirectoryStack::new(),
             functions:           FnvHashMap::default(),
             previous_job:        !0,
             previous_status:     0,
-            flags:               0,
+            flags:               WARN_DEPRECATED | SAVE_HISTORY,
             foreground:          Vec::new(),
             background:          Arc::new(Mutex::new(Vec::new())),
             is_background_shell: false,
             is_library:          false,
             break_flow:          false,
+            sourcing:            0,
+            returning:           None,
             foreground_signals:  Arc::new(ForegroundSignals::new()),
             ignore_setting:      IgnoreSetting::default(),
-            pointer:             ptr::null_mut(),
+            traps:               Traps::default(),
+            guards:              Guards::default(),
+            confirming:          false,
+            autoloads:           Autoloads::default(),
+            imports:             Imports::default(),
+            depth:               0,
+            expansion_depth:     Cell::new(0),
+            conditions:          0,
+            conditional_status:  false,
+            expansion_errors:    Cell::new(0),
+            location:            None,
+            reporter:            Reporter::default(),
+            debugger:            None,
+            profiler:            None,
+            random:              Random::new(),
+            git:                 GitStatus::default(),
+            segments:            Segments::default(),
+            fake_commands:       FnvHashMap::default(),
+            warnings:            Warnings::default(),
         }
     }
 
@@ -123,7 +241,7 @@ impl<'a> Shell {
     pub fn new() -> Shell {
         Shell {
             builtins:            BUILTINS,
-            context:             None,
+            editor:              None,
             variables:           Variables::default(),
             flow_control:        FlowControl::default(),
             directory_stack:     DirectoryStack::new(),
@@ -136,9 +254,29 @@ impl<'a> Shell {
             is_background_shell: false,
             is_library:          true,
             break_flow:          false,
+            sourcing:            0,
+            returning:           None,
             foreground_signals:  Arc::new(ForegroundSignals::new()),
             ignore_setting:      IgnoreSetting::default(),
-            pointer:             ptr::null_mut(),
+            traps:               Traps::default(),
+            guards:              Guards::default(),
+            confirming:          false,
+            autoloads:           Autoloads::default(),
+            imports:             Imports::default(),
+            depth:               0,
+            expansion_depth:     Cell::new(0),
+            conditions:          0,
+            conditional_status:  false,
+            expansion_errors:    Cell::new(0),
+            location:            None,
+            reporter:            Reporter::default(),
+            debugger:            None,
+            profiler:            None,
+            random:              Random::new(),
+            git:                 GitStatus::default(),
+            segments:            Segments::default(),
+            fake_commands:       FnvHashMap::default(),
+            warnings:            Warnings::default(),
         }
     }
 
@@ -152,13 +290,174 @@ impl<'a> Shell {
         }
     }
 
+    /// Consumes any pending signal, exiting the shell if that signal was meant to terminate it.
+    /// Returns `true` if the current flow of execution was interrupted, either because the shell
+    /// received a SIGINT, or because a foreground job was killed by one. The interruption will
+    /// remain in effect until the next command is read.
+    /// If a trap was registered for the signal, its handler will be executed instead.
+    pub(crate) fn was_interrupted(&mut self) -> bool {
+        if let Some(signal) = self.next_signal() {
+            let trapped =
+                traps::from_signal(signal).map_or(false, |event| self.execute_trap(event));
+            if !trapped {
+                if self.handle_signal(signal) {
+                    self.exit(get_signal_code(signal));
+                }
+                self.break_flow = true;
+            }
+        }
+        self.break_flow
+    }
+
+    /// Executes the handler that was registered for the given trap event, if there is one.
+    /// The exit status of the shell is preserved across the execution of the handler.
+    pub(crate) fn execute_trap(&mut self, event: usize) -> bool {
+        let handler = match self.traps.begin(event) {
+            Some(handler) => handler,
+            None => return false,
+        };
+        let previous_status = self.previous_status;
+        // The handler does not belong to the script, so its syntax errors are not located there.
+        let location = self.location.take();
+        self.on_command(&handler);
+        self.location = location;
+        self.previous_status = previous_status;
+        self.variables.set_var("?", &previous_status.to_string());
+        self.traps.end(event);
+        true
+    }
+
+    /// Triggers the `ERR` trap, and exits the shell if `errexit` is set, when the previous
+    /// statement failed outside of a condition. The status given to `return` is not a failure.
+    pub(crate) fn check_failure(&mut self) {
+        if self.previous_status != SUCCESS && self.conditions == 0 && !self.conditional_status
+            && self.returning.is_none()
+        {
+            self.execute_trap(traps::ERR);
+            if self.flags & ERR_EXIT != 0 {
+                let status = self.previous_status;
+                // Failures within functions are traced back to the command which called them.
+                if !self.flow_control.call_stack.is_empty() {
+                    let message = format!("command failed with status {}", status);
+                    self.report(IonError::new(ErrorKind::Pipeline, message).context("errexit"));
+                }
+                self.exit(status);
+            }
+        }
+    }
+
+    /// The maximum depth that function calls, `eval` commands, and expansions may be nested
+    /// within one another, which is configured with the `RECURSION_LIMIT` variable.
+    pub(crate) fn recursion_limit(&self) -> usize {
+        self.variables
+            .get_var("RECURSION_LIMIT")
+            .and_then(|limit| limit.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_RECURSION_LIMIT)
+    }
+
+    /// Reports whether the option of the given name is enabled, or `None` if there is no
+    /// such option.
+    pub(crate) fn get_option(&self, name: &str) -> Option<bool> {
+        options::find(name).map(|option| self.flags & option.flag != 0)
+    }
+
+    /// Enables or disables the option of the given name, returning `false` if there is no
+    /// such option.
+    pub(crate) fn set_option(&mut self, name: &str, enable: bool) -> bool {
+        let flag = match options::find(name) {
+            Some(option) => option.flag,
+            None => return false,
+        };
+        if enable {
+            // Typed environment variables are imported at the moment the option is enabled.
+            if flag == IMPORT_ENV && self.flags & IMPORT_ENV == 0 {
+                self.variables.import_env();
+            }
+            // Every sandbox yields the same sequence of random numbers.
+            if flag == SANDBOX {
+                self.random.seed(sandbox::SEED);
+            }
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+        true
+    }
+
+    /// Returns `true` if the last expansion referenced an unset variable while `nounset` was
+    /// set, or contained a glob which matched nothing while `failglob` was set. Referencing an
+    /// unset variable will also abort the current flow of execution, and will cause a
+    /// non-interactive shell to exit.
+    pub(crate) fn expansion_failed(&mut self) -> bool {
+        let errors = self.expansion_errors.replace(0);
+        if errors & NO_UNSET != 0 {
+            if self.editor.is_none() && !self.is_library {
+                self.exit(FAILURE);
+            }
+            self.break_flow = true;
+        }
+        errors != 0
+    }
+
+    /// Prints a command to stderr before it is executed, when `set -x` is enabled. Commands are
+    /// prefixed with the expansion of `PS4`, whose first character is repeated once more for
+    /// each function or `eval` that is currently being executed. The values of secret variables
+    /// are masked.
+    pub(crate) fn trace(&mut self, command: &str) {
+        if self.flags & PRINT_COMMS == 0 {
+            return;
+        }
+        let prefix = self.variables.get_var_or_empty("PS4");
+        let prefix = expand_string(&prefix, self, false).join(" ");
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+        if let Some(character) = prefix.chars().next() {
+            for _ in 0..self.depth {
+                let _ = write!(stderr, "{}", character);
+            }
+        }
+        let _ = writeln!(stderr, "{}{}", prefix, self.variables.mask(command));
+    }
+
+    /// Reports an error, which is rendered to stderr unless errors are being collected. Errors
+    /// are located at the command of the script which is being executed, and are given a
+    /// backtrace if they occurred within a function.
+    pub(crate) fn report(&self, mut error: IonError) {
+        if error.location.is_none() {
+            error.location = self.location.clone();
+        }
+        if error.backtrace.is_empty() {
+            error.backtrace = self.flow_control.call_stack.iter().rev().cloned().collect();
+        }
+        self.reporter.report(error);
+    }
+
     pub(crate) fn exit(&mut self, status: i32) -> ! {
-        if let Some(context) = self.context.as_mut() {
-            context.history.commit_history();
+        self.previous_status = status;
+        // The settings of any `with` blocks which are being executed are restored first.
+        self.leave_scopes(0);
+        self.execute_trap(traps::EXIT);
+        if let Some(editor) = self.editor.as_mut() {
+            editor.commit_history();
+        }
+        if let Some(ref profiler) = self.profiler {
+            profiler.print();
         }
         process::exit(status);
     }
 
+    /// Appends an exit status to `@status_history`, which holds the statuses of the last
+    /// `STATUS_HISTORY_SIZE` statements, from the oldest to the most recent.
+    fn record_status(&mut self, status: i32) {
+        let size = self.variables.get_var_or_empty("STATUS_HISTORY_SIZE").parse().unwrap_or(10);
+        let mut history = self.variables.get_array("status_history").cloned().unwrap_or_default();
+        history.push(status.to_string());
+        while history.len() > size {
+            history.remove(0);
+        }
+        self.variables.set_array("status_history", history);
+    }
+
     /// This function updates variables that need to be kept consistent with each iteration
     /// of the prompt. For example, the PWD variable needs to be updated to reflect changes to
     /// the
@@ -175,67 +474,108 @@ impl<'a> Shell {
                 if pwd != current_dir {
                     env::set_var("OLDPWD", pwd);
                     env::set_var("PWD", current_dir);
+                    // The directory is ranked for the `j` builtin.
+                    self.visit_directory();
                 }
             },
         )
     }
 
-    /// Evaluates the source init file in the user's home directory.
+    /// Evaluates each layer of init files in order: the system-wide `/etc/ion/initrc`, the
+    /// initrc within the user's config directory, and then the file named by `ION_INITRC`, which
+    /// may be set to override settings for a single session.
     pub fn evaluate_init_file(&mut self) {
-        match app_root(
-            AppDataType::UserConfig,
-            &AppInfo {
-                name:   "ion",
-                author: "Redox OS Developers",
-            },
-        ) {
-            Ok(mut initrc) => {
-                initrc.push("initrc");
-                if initrc.exists() {
-                    if let Err(err) = self.execute_script(&initrc) {
-                        eprintln!("ion: {}", err);
-                    }
-                } else {
-                    eprintln!("ion: creating initrc file at {:?}", initrc);
-                    if let Err(why) = File::create(initrc) {
-                        eprintln!("ion: could not create initrc file: {}", why);
-                    }
+        self.source_init_file(Path::new("/etc/ion/initrc"));
+        if let Some(config_dir) = xdg::config_dir() {
+            let initrc = config_dir.join("initrc");
+            if initrc.exists() {
+                self.source_init_file(&initrc);
+            } else {
+                eprintln!("ion: creating initrc file at {:?}", initrc);
+                if let Err(why) = File::create(initrc) {
+                    eprintln!("ion: could not create initrc file: {}", why);
                 }
             }
-            Err(why) => {
-                eprintln!("ion: unable to get config root: {}", why);
+        }
+        if let Some(session) = env::var_os("ION_INITRC") {
+            if !self.source_init_file(Path::new(&session)) {
+                eprintln!("ion: ION_INITRC: {:?} does not exist", session);
             }
         }
     }
 
+    /// Evaluates the system-wide `/etc/ion/profile`, followed by the profile within the user's
+    /// config directory. These are only read by login shells, before any init files.
+    pub(crate) fn evaluate_profile_files(&mut self) {
+        self.source_init_file(Path::new("/etc/ion/profile"));
+        if let Some(config_dir) = xdg::config_dir() {
+            self.source_init_file(&config_dir.join("profile"));
+        }
+    }
+
+    /// Executes the given file if it exists, appending its path to the `INIT_FILES` array so
+    /// that the files which were loaded may be inspected.
+    fn source_init_file(&mut self, path: &Path) -> bool {
+        if !path.is_file() {
+            return false;
+        }
+        let mut loaded = self.variables.get_array("INIT_FILES").cloned().unwrap_or_default();
+        loaded.push(path.to_string_lossy().into_owned());
+        self.variables.set_array("INIT_FILES", loaded);
+        if let Err(err) = self.execute_script(path) {
+            eprintln!("ion: {}: {}", path.display(), err);
+        }
+        true
+    }
+
+    /// Executes a pipeline as a condition, in which failures will not trigger the `ERR` trap
+    /// or cause the shell to exit when `errexit` is set.
+    fn run_condition(&mut self, pipeline: &mut Pipeline) -> Option<i32> {
+        self.conditions += 1;
+        let boolean = pipeline
+            .sole_variable()
+            .and_then(|name| self.variables.get_var(name))
+            .and_then(|value| is_boolean(&value).ok().map(|boolean| boolean == "true"));
+        let status = if pipeline.is_boolean_method() {
+            // Methods such as `$contains()` expand to `1` when true, and `0` otherwise, whereas
+            // logical methods such as `$and()` expand to `true` or `false`.
+            self.trace(&pipeline.to_string());
+            pipeline.expand(self);
+            let is_true = pipeline.items[0].job.args.first().map_or(false, |arg| {
+                is_boolean(arg) == Ok("true")
+            });
+            Some(if is_true { SUCCESS } else { FAILURE })
+        } else if let Some(is_true) = boolean {
+            // A variable which holds a boolean is evaluated as one, rather than as a command.
+            self.trace(&pipeline.to_string());
+            Some(if is_true { SUCCESS } else { FAILURE })
+        } else {
+            self.run_pipeline(pipeline)
+        };
+        self.conditions -= 1;
+        status
+    }
+
     /// Executes a pipeline and returns the final exit status of the pipeline.
     /// To avoid infinite recursion when using aliases, the noalias boolean will be set the true
     /// if an alias branch was executed.
     fn run_pipeline(&mut self, pipeline: &mut Pipeline) -> Option<i32> {
-        // TODO: Find a way to only need to execute this once, without
-        // complicating our public API.
-        //
-        // Ensure that the shell pointer is set before executing.
-        // This is needed for subprocess expansions to function.
-        let pointer = self as *mut Shell;
-        self.pointer = pointer;
+        self.conditional_status = false;
 
         let command_start_time = SystemTime::now();
         let builtins = self.builtins;
 
+        self.flow_control.line = pipeline.line;
+
         // Expand any aliases found
-        for job_no in 0..pipeline.items.len() {
-            if let Some(alias) = {
-                let key: &str = pipeline.items[job_no].job.command.as_ref();
-                self.variables.aliases.get(key)
-            } {
-                let new_args = ArgumentSplitter::new(alias)
-                    .map(String::from)
-                    .chain(pipeline.items[job_no].job.args.drain().skip(1))
-                    .collect::<SmallVec<[String; 4]>>();
-                pipeline.items[job_no].job.command = new_args[0].clone().into();
-                pipeline.items[job_no].job.args = new_args;
-            }
+        expand_aliases(&self.variables, pipeline);
+
+        // Functions of a namespace may call one another without naming their namespace.
+        self.qualify(pipeline);
+
+        // Functions which have yet to be defined are loaded from `FPATH` before they are called.
+        for item in &pipeline.items {
+            self.autoload(&item.job.args[0]);
         }
 
         // Branch if -> input == shell command i.e. echo
@@ -245,16 +585,23 @@ impl<'a> Shell {
         } {
             pipeline.expand(self);
             // Run the 'main' of the command and set exit_status
-            if !pipeline.requires_piping() {
-                if self.flags & PRINT_COMMS != 0 {
-                    eprintln!("> {}", pipeline.to_string());
-                }
+            if self.expansion_failed() || !self.ask(pipeline) || !self.confirm(pipeline) {
+                Some(FAILURE)
+            } else if !pipeline.requires_piping() {
+                self.trace(&pipeline.to_string());
                 let borrowed = &pipeline.items[0].job.args;
                 let small: SmallVec<[&str; 4]> = borrowed.iter().map(|x| x as &str).collect();
-                if self.flags & NO_EXEC != 0 {
+                if self.flags & NO_EXEC != 0 || self.dry_run(pipeline) {
                     Some(SUCCESS)
                 } else {
-                    Some((command.main)(&small, self))
+                    let status = (command.main)(&small, self);
+                    // A long-running builtin may have been interrupted by the user, whereas
+                    // `return` interrupts the file which is being sourced with its own status.
+                    if self.returning.is_none() && self.was_interrupted() {
+                        Some(get_signal_code(sys::SIGINT))
+                    } else {
+                        Some(status)
+                    }
                 }
             } else {
                 Some(self.execute_pipeline(pipeline))
@@ -262,21 +609,13 @@ impl<'a> Shell {
         // Branch else if -> input == shell function and set the exit_status
         } else if let Some(function) = self.functions.get(&pipeline.items[0].job.command).cloned() {
             if !pipeline.requires_piping() {
+                self.trace(&pipeline.to_string());
                 let args: &[String] = pipeline.items[0].job.args.deref();
                 let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
                 match function.execute(self, &args) {
                     Ok(()) => None,
-                    Err(FunctionError::InvalidArgumentCount) => {
-                        eprintln!("ion: invalid number of function arguments supplied");
-                        Some(FAILURE)
-                    }
-                    Err(FunctionError::InvalidArgumentType(expected_type, value)) => {
-                        eprintln!(
-                            "ion: function argument has invalid type: expected {}, found value \
-                             \'{}\'",
-                            expected_type,
-                            value
-                        );
+                    Err(why) => {
+                        self.report(why.describe(&pipeline.items[0].job.command));
                         Some(FAILURE)
                     }
                 }
@@ -285,13 +624,17 @@ impl<'a> Shell {
             }
         } else {
             pipeline.expand(self);
-            Some(self.execute_pipeline(pipeline))
+            if self.expansion_failed() || !self.ask(pipeline) || !self.confirm(pipeline) {
+                Some(FAILURE)
+            } else {
+                Some(self.execute_pipeline(pipeline))
+            }
         };
 
         // If `RECORD_SUMMARY` is set to "1" (True, Yes), then write a summary of the pipline
         // just executed to the the file and context histories. At the moment, this means
         // record how long it took.
-        if let Some(context) = self.context.as_mut() {
+        if let Some(editor) = self.editor.as_mut() {
             if "1" == self.variables.get_var_or_empty("RECORD_SUMMARY") {
                 if let Ok(elapsed_time) = command_start_time.elapsed() {
                     let summary = format!(
@@ -299,7 +642,7 @@ impl<'a> Shell {
                         elapsed_time.as_secs(),
                         elapsed_time.subsec_nanos()
                     );
-                    context.history.push(summary.into()).unwrap_or_else(|err| {
+                    editor.push_history(&summary).unwrap_or_else(|err| {
                         let stderr = io::stderr();
                         let mut stderr = stderr.lock();
                         let _ = writeln!(stderr, "ion: {}\n", err);
@@ -308,13 +651,103 @@ impl<'a> Shell {
             }
         }
 
+        if self.flags & AUDIT != 0 {
+            self.audit(&pipeline.to_string(), exit_status.unwrap_or(self.previous_status));
+        }
+
         // Retrieve the exit_status and set the $? variable and history.previous_status
         if let Some(code) = exit_status {
             self.variables.set_var("?", &code.to_string());
             self.previous_status = code;
+            self.record_status(code);
         }
         exit_status
     }
+
+    /// Executes the closure within a forked child of the shell, without waiting for it. The
+    /// child's PID is returned, along with a pipe from its standard output, which reaches its
+    /// end once the child has exited.
+    fn fork_subshell<F: FnOnce(&mut Shell) -> i32>(&mut self, body: F) -> Option<(u32, File)> {
+        use std::os::unix::io::{AsRawFd, FromRawFd};
+        use std::process::exit;
+        use sys;
+
+        let (out_read, out_write) = match sys::pipe2(sys::O_CLOEXEC) {
+            Ok(fds) => unsafe { (File::from_raw_fd(fds.0), File::from_raw_fd(fds.1)) },
+            Err(why) => {
+                eprintln!("ion: unable to create pipe: {}", why);
+                return None;
+            }
+        };
+
+        match unsafe { sys::fork() } {
+            Ok(0) => {
+                // Errors which are collected within the child would be lost when it exits.
+                self.reporter.collect(false);
+
+                // TODO: Figure out how to properly enable stdin in the child.
+                // Without this line, the parent will hang. Can test with:
+                //     echo $(read x)
+                sys::close_stdin();
+
+                // Redirect stdout in the child to the write end of the pipe.
+                // Also close the read end of the pipe because we don't need it.
+                let _ = sys::dup2(out_write.as_raw_fd(), sys::STDOUT_FILENO);
+                drop(out_write);
+                drop(out_read);
+
+                // The child owns its own copy of the shell, with which the body is executed.
+                let status = body(self);
+
+                // Reap the child, enabling the parent to get EOF from the read end of the pipe.
+                exit(status);
+            }
+            Ok(pid) => {
+                // Drop the write end of the pipe, because the parent will not use it.
+                drop(out_write);
+                Some((pid, out_read))
+            }
+            Err(why) => {
+                eprintln!("ion: fork error: {}", why);
+                None
+            }
+        }
+    }
+
+    /// Executes the closure within a forked child of the shell, and collects the standard
+    /// output of the child, along with whether the exit status returned by the closure was
+    /// successful.
+    fn subshell<F: FnOnce(&mut Shell) -> i32>(&mut self, body: F) -> Option<(Value, bool)> {
+        use std::io::Read;
+        use sys;
+
+        let (pid, mut out_read) = self.fork_subshell(body)?;
+
+        // Read from the read end of the pipe into a String.
+        let mut output = String::new();
+        let _ = out_read.read_to_string(&mut output);
+
+        // Reap the child, so that it does not linger as a zombie.
+        let status = sys::waitpid(pid);
+
+        Some((output, status.ok() == Some(0)))
+    }
+
+    /// Executes the closure within the shell itself while its standard output is redirected
+    /// into a pipe, and collects that output, along with whether the exit status returned by the
+    /// closure was successful. Unlike a subshell, any changes that the closure makes to the
+    /// shell persist.
+    fn capture<F: FnOnce(&mut Shell) -> i32>(&mut self, body: F) -> Option<(Value, bool)> {
+        let capture = match Capture::start(sys::STDOUT_FILENO) {
+            Ok(capture) => capture,
+            Err(why) => {
+                eprintln!("ion: {}", why);
+                return None;
+            }
+        };
+        let status = body(self);
+        Some((capture.finish(), status == SUCCESS))
+    }
 }
 
 impl<'a> Expander for Shell {
@@ -332,13 +765,10 @@ impl<'a> Expander for Shell {
                 Select::Index(id) => id.resolve(array.len())
                     .and_then(|n| array.get(n))
                     .map(|x| Array::from_iter(Some(x.to_owned()))),
-                Select::Range(range) => if let Some((start, length)) = range.bounds(array.len()) {
-                    let array = array
-                        .iter()
-                        .skip(start)
-                        .take(length)
-                        .map(|x| x.to_owned())
-                        .collect::<Array>();
+                Select::Range(range) => if let Some(elements) =
+                    range.elements(array.iter(), array.len())
+                {
+                    let array = elements.map(|x| x.to_owned()).collect::<Array>();
                     if array.is_empty() {
                         None
                     } else {
@@ -372,64 +802,101 @@ impl<'a> Expander for Shell {
         found
     }
     /// Expand a string variable given if its quoted / unquoted
-    fn variable(&self, variable: &str, quoted: bool) -> Option<Value> {
+    fn variable(&mut self, variable: &str, quoted: bool) -> Option<Value> {
         use ascii_helpers::AsciiReplace;
+        let value = match variable {
+            "history::last" => self.last_command(),
+            _ if variable.starts_with("git::") => self.git.get(&variable[5..]),
+            _ if variable.starts_with("async::") => self.async_segment(&variable[7..]),
+            "RANDOM" => Some(self.random.next().to_string()),
+            "HOSTNAME" | "USER" if self.flags & SANDBOX != 0 => {
+                let fixed = sandbox::fixed_variable(variable).map(Into::into);
+                self.variables.variables.get(variable).cloned().or(fixed)
+            }
+            _ => self.variables.get_var(variable),
+        };
         if quoted {
-            self.variables.get_var(variable)
+            value
         } else {
-            self.variables.get_var(variable).map(|x| x.ascii_replace('\n', ' ').into())
+            value.map(|x| x.ascii_replace('\n', ' ').into())
         }
     }
-    /// Uses a subshell to expand a given command.
-    fn command(&self, command: &str) -> Option<Value> {
-        use std::io::Read;
-        use std::os::unix::io::{AsRawFd, FromRawFd};
-        use std::process::exit;
-        use sys;
-
-        let (mut out_read, out_write) = match sys::pipe2(sys::O_CLOEXEC) {
-            Ok(fds) => unsafe { (File::from_raw_fd(fds.0), File::from_raw_fd(fds.1)) },
-            Err(why) => {
-                eprintln!("ion: unable to create pipe: {}", why);
-                return None;
-            }
+    /// Reports a reference to a variable which could not be expanded
+    fn unset(&self, name: &str) {
+        if self.variables.is_set(name) {
+            return;
+        }
+        let failed = if self.flags & NO_UNSET != 0 {
+            self.report(IonError::new(ErrorKind::Expansion, "unbound variable").context(name));
+            true
+        } else {
+            self.warn(Warning::Undefined, &format!("{}: undefined variable", name))
         };
-
-        match unsafe { sys::fork() } {
-            Ok(0) => {
-                // TODO: Figure out how to properly enable stdin in the child.
-                // Without this line, the parent will hang. Can test with:
-                //     echo $(read x)
-                sys::close_stdin();
-
-                // Redirect stdout in the child to the write end of the pipe.
-                // Also close the read end of the pipe because we don't need it.
-                let _ = sys::dup2(out_write.as_raw_fd(), sys::STDOUT_FILENO);
-                drop(out_write);
-                drop(out_read);
-
-                // Now obtain ownership of the child's shell through a mutable pointer,
-                // and then use that shell to execute the command.
-                let shell: &mut Shell = unsafe { &mut *self.pointer };
-                shell.on_command(command);
-
-                // Reap the child, enabling the parent to get EOF from the read end of the pipe.
-                exit(0);
+        // A warning which is raised as an error aborts the command, as `nounset` does.
+        if failed {
+            self.expansion_errors.set(self.expansion_errors.get() | NO_UNSET);
+        }
+    }
+    /// Reports a glob which did not match any paths
+    fn unmatched(&self, pattern: &str) {
+        if self.flags & FAIL_GLOB != 0 {
+            let message = format!("no matches found: {}", pattern);
+            self.report(IonError::new(ErrorKind::Expansion, message));
+            self.expansion_errors.set(self.expansion_errors.get() | FAIL_GLOB);
+        }
+    }
+    /// Aborts expansions which are nested beyond the recursion limit.
+    fn enter_expansion(&self) -> bool {
+        let depth = self.expansion_depth.get();
+        if depth >= self.recursion_limit() {
+            if self.expansion_errors.get() & TOO_DEEP == 0 {
+                let message = "expansions are nested too deeply";
+                self.report(IonError::new(ErrorKind::Expansion, message));
             }
-            Ok(_pid) => {
-                // Drop the write end of the pipe, because the parent will not use it.
-                drop(out_write);
-
-                // Read from the read end of the pipe into a String.
-                let mut output = String::new();
-                let _ = out_read.read_to_string(&mut output);
-
-                Some(output)
+            self.expansion_errors.set(self.expansion_errors.get() | TOO_DEEP);
+            return false;
+        }
+        self.expansion_depth.set(depth + 1);
+        true
+    }
+    fn exit_expansion(&self) { self.expansion_depth.set(self.expansion_depth.get() - 1); }
+    fn strip_newlines(&self) -> bool { self.flags & KEEP_NEWLINES == 0 }
+    fn split_words(&self) -> bool { self.flags & SPLIT_WORDS != 0 }
+    fn option(&self, name: &str) -> Option<bool> { self.get_option(name) }
+    /// Uses a subshell to expand a given command.
+    fn command(&mut self, command: &str) -> Option<Value> {
+        self.subshell(|shell| {
+            shell.location = None;
+            shell.on_command(command);
+            shell.previous_status
+        }).map(|(output, _)| output)
+    }
+    /// Uses a subshell to call a function with a single argument.
+    fn function(&mut self, name: &str, argument: &str) -> Option<(Value, bool)> {
+        let function = self.functions.get(name).cloned()?;
+        self.subshell(move |shell| match function.execute(shell, &[name, argument]) {
+            Ok(()) => shell.previous_status,
+            Err(_) => {
+                let message = "function must take exactly one argument";
+                shell.report(IonError::new(ErrorKind::Function, message).context(name));
+                FAILURE
             }
+        })
+    }
+    fn assign(&mut self, name: &str, value: &str) { self.variables.set_var(name, value); }
+    fn has_function(&self, name: &str) -> bool { self.functions.contains_key(name) }
+    /// Calls a function as a method within the shell itself, rather than within a subshell.
+    fn method(&mut self, name: &str, arguments: &[&str]) -> Option<Value> {
+        let function = self.functions.get(name).cloned()?;
+        let mut args = vec![name];
+        args.extend_from_slice(arguments);
+        self.capture(move |shell| match function.execute(shell, &args) {
+            Ok(()) => shell.previous_status,
             Err(why) => {
-                eprintln!("ion: fork error: {}", why);
-                None
+                shell.report(why.describe(name));
+                FAILURE
             }
-        }
+        }).map(|(output, _)| output.trim_right_matches('\n').to_owned())
     }
+
 }
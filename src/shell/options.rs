@@ -0,0 +1,135 @@
+//! Contains the registry of shell options, which may be toggled by name with `set -o` or the
+//! `shopt` builtin, and queried from scripts with `$option(name)`.
+
+use super::flags::*;
+
+/// A named shell option, and the flag within `Shell::flags` that it controls.
+pub(crate) struct ShellOption {
+    pub(crate) name:        &'static str,
+    pub(crate) flag:        u32,
+    pub(crate) description: &'static str,
+}
+
+/// Every option that may be toggled by name, in the order in which they are listed.
+pub(crate) static OPTIONS: [ShellOption; 20] = [
+    ShellOption {
+        name:        "audit",
+        flag:        AUDIT,
+        description: "Append every executed command to the audit log given by `AUDIT_FILE`",
+    },
+    ShellOption {
+        name:        "autocd",
+        flag:        AUTO_CD,
+        description: "Change into the directory named by a command which is not found",
+    },
+    ShellOption {
+        name:        "cleanenv",
+        flag:        CLEAN_ENV,
+        description: "Give external commands only the allowed environment variables",
+    },
+    ShellOption {
+        name:        "deprecations",
+        flag:        WARN_DEPRECATED,
+        description: "Warn when deprecated syntax is used",
+    },
+    ShellOption {
+        name:        "dryrun",
+        flag:        DRY_RUN,
+        description: "Print external commands instead of executing them, as with `ion --dry-run`",
+    },
+    ShellOption {
+        name:        "errexit",
+        flag:        ERR_EXIT,
+        description: "Exit when a command fails, as with `set -e`",
+    },
+    ShellOption {
+        name:        "failglob",
+        flag:        FAIL_GLOB,
+        description: "Fail commands in which a glob does not match any paths",
+    },
+    ShellOption {
+        name:        "history",
+        flag:        SAVE_HISTORY,
+        description: "Record interactive commands in the history",
+    },
+    ShellOption {
+        name:        "keepnewlines",
+        flag:        KEEP_NEWLINES,
+        description: "Keep trailing newlines in quoted process expansions",
+    },
+    ShellOption {
+        name:        "marks",
+        flag:        MARKS,
+        description: "Mark prompts and the output of commands for the terminal, with OSC 133",
+    },
+    ShellOption {
+        name:        "noclobber",
+        flag:        NO_CLOBBER,
+        description: "Refuse to overwrite existing files with the `>` redirection",
+    },
+    ShellOption {
+        name:        "noexec",
+        flag:        NO_EXEC,
+        description: "Read commands without executing them, as with `ion -n`",
+    },
+    ShellOption {
+        name:        "nounset",
+        flag:        NO_UNSET,
+        description: "Treat references to unset variables as errors, as with `set -u`",
+    },
+    ShellOption {
+        name:        "pipefail",
+        flag:        PIPE_FAIL,
+        description: "Fail a pipeline if any of its commands fail",
+    },
+    ShellOption {
+        name:        "reportcwd",
+        flag:        REPORT_CWD,
+        description: "Report the current directory to the terminal, with OSC 7",
+    },
+    ShellOption {
+        name:        "sandbox",
+        flag:        SANDBOX,
+        description: "Fix random numbers, the time, and names for tests, as with `ion --sandbox`",
+    },
+    ShellOption {
+        name:        "title",
+        flag:        TITLE,
+        description: "Set the terminal's title to the running command, or the current directory",
+    },
+    ShellOption {
+        name:        "typedenv",
+        flag:        IMPORT_ENV,
+        description: "Import arrays and maps encoded within environment variables",
+    },
+    ShellOption {
+        name:        "wordsplit",
+        flag:        SPLIT_WORDS,
+        description: "Split the output of unquoted process expansions into words",
+    },
+    ShellOption {
+        name:        "xtrace",
+        flag:        PRINT_COMMS,
+        description: "Print commands before they are executed, as with `set -x`",
+    },
+];
+
+/// Finds the option with the given name.
+pub(crate) fn find(name: &str) -> Option<&'static ShellOption> {
+    OPTIONS.iter().find(|option| option.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_are_unique() {
+        for (id, option) in OPTIONS.iter().enumerate() {
+            assert!(OPTIONS[id + 1..].iter().all(|other| other.name != option.name));
+            assert!(OPTIONS[id + 1..].iter().all(|other| other.flag != option.flag));
+            assert_eq!(find(option.name).map(|found| found.flag), Some(option.flag));
+        }
+        assert!(find("nonexistent").is_none());
+    }
+}
@@ -0,0 +1,106 @@
+//! Integrates interactive sessions with terminal emulators through operating system commands,
+//! which are escape sequences that the terminal interprets rather than displays. The `title`
+//! option sets the title of the terminal to the command which is being executed, and to the
+//! current directory at the prompt. The `reportcwd` option reports the current directory with
+//! OSC 7, so that the terminal may open new tabs within it. The `marks` option marks the start
+//! of each prompt, and the start and end of the output of each command, with OSC 133, so that
+//! the terminal may jump between commands within its scrollback.
+
+use super::Shell;
+use super::editor;
+use super::flags::*;
+use std::env;
+use std::io::{self, Write};
+use sys;
+
+/// Sets the title of the terminal. Control characters, which would end the sequence early, are
+/// removed from the title.
+fn title(text: &str) -> String {
+    let text = text.chars().filter(|character| !character.is_control()).collect::<String>();
+    format!("\x1b]0;{}\x07", text)
+}
+
+/// Reports the current directory as a URL, in which the bytes of the path that may not be
+/// written literally are percent-encoded.
+fn current_dir(host: &str, path: &str) -> String {
+    let mut url = format!("\x1b]7;file://{}", host);
+    for &byte in path.as_bytes() {
+        match byte {
+            b'A'...b'Z' | b'a'...b'z' | b'0'...b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url.push('\x07');
+    url
+}
+
+fn write(sequences: &str) {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let _ = stdout.write_all(sequences.as_bytes()).and_then(|_| stdout.flush());
+}
+
+impl Shell {
+    /// Whether the sequences may be written, which requires that one of the options is enabled,
+    /// and that the standard output is a terminal that is able to interpret them.
+    fn osc_enabled(&self) -> bool {
+        self.flags & (TITLE | REPORT_CWD | MARKS) != 0 && sys::isatty(sys::STDOUT_FILENO)
+            && !editor::is_dumb_terminal()
+    }
+
+    /// Writes the sequences which precede a prompt, after the previous command, if there was
+    /// one, finished with the given status.
+    pub(crate) fn osc_prompt(&self, status: Option<i32>) {
+        if !self.osc_enabled() {
+            return;
+        }
+        let mut sequences = String::new();
+        if let (true, Some(status)) = (self.flags & MARKS != 0, status) {
+            sequences.push_str(&format!("\x1b]133;D;{}\x07", status));
+        }
+        if self.flags & TITLE != 0 {
+            sequences.push_str(&title(&self.variables.get_var_or_empty("SWD")));
+        }
+        if self.flags & REPORT_CWD != 0 {
+            if let Ok(dir) = env::current_dir() {
+                let host = sys::hostname().unwrap_or_default();
+                sequences.push_str(&current_dir(&host, &dir.to_string_lossy()));
+            }
+        }
+        if self.flags & MARKS != 0 {
+            sequences.push_str("\x1b]133;A\x07");
+        }
+        write(&sequences);
+    }
+
+    /// Writes the sequences which precede the execution of a command from the prompt.
+    pub(crate) fn osc_command(&self, command: &str) {
+        if !self.osc_enabled() {
+            return;
+        }
+        let mut sequences = String::new();
+        if self.flags & TITLE != 0 {
+            sequences.push_str(&title(command));
+        }
+        if self.flags & MARKS != 0 {
+            sequences.push_str("\x1b]133;C\x07");
+        }
+        write(&sequences);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequences() {
+        assert_eq!(title("vim\x07 src/main.rs\n"), "\x1b]0;vim src/main.rs\x07");
+        assert_eq!(
+            current_dir("host", "/home/ion/my dir/ü"),
+            "\x1b]7;file://host/home/ion/my%20dir/%C3%BC\x07"
+        );
+    }
+}
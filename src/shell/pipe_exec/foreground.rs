@@ -1,9 +1,9 @@
 //! Contains the logic for enabling foreground management.
-use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use std::sync::atomic::{AtomicI32, AtomicU32, AtomicU8, Ordering};
 
 pub(crate) enum BackgroundResult {
     Errored,
-    Status(u8),
+    Status(i32),
 }
 
 const REPLIED: u8 = 1;
@@ -15,7 +15,7 @@ const ERRORED: u8 = 2;
 /// the exit status back to the `fg` function.
 pub(crate) struct ForegroundSignals {
     grab:   AtomicU32,
-    status: AtomicU8,
+    status: AtomicI32,
     reply:  AtomicU8,
 }
 
@@ -23,16 +23,16 @@ impl ForegroundSignals {
     pub(crate) fn new() -> ForegroundSignals {
         ForegroundSignals {
             grab:   AtomicU32::new(0),
-            status: AtomicU8::new(0),
+            status: AtomicI32::new(0),
             reply:  AtomicU8::new(0),
         }
     }
 
     pub(crate) fn signal_to_grab(&self, pid: u32) { self.grab.store(pid, Ordering::Relaxed); }
 
-    pub(crate) fn reply_with(&self, status: i8) {
+    pub(crate) fn reply_with(&self, status: i32) {
         self.grab.store(0, Ordering::Relaxed);
-        self.status.store(status as u8, Ordering::Relaxed);
+        self.status.store(status, Ordering::Relaxed);
         self.reply.store(REPLIED, Ordering::Relaxed);
     }
 
@@ -47,7 +47,7 @@ impl ForegroundSignals {
         if reply & ERRORED != 0 {
             Some(BackgroundResult::Errored)
         } else if reply & REPLIED != 0 {
-            Some(BackgroundResult::Status(self.status.load(Ordering::Relaxed) as u8))
+            Some(BackgroundResult::Status(self.status.load(Ordering::Relaxed)))
         } else {
             None
         }
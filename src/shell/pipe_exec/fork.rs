@@ -3,11 +3,19 @@ use sys;
 /// Ensures that the forked child is given a unique process ID.
 pub(crate) fn create_process_group(pgid: u32) { let _ = sys::setpgid(0, pgid); }
 
+/// Places a forked child within the given process group, or a new group of its own if `pgid` is
+/// zero, from the parent. The child does the same with `create_process_group`, as either may run
+/// first, and the parent must not wait on or give the terminal to a group which does not exist.
+pub(crate) fn join_process_group(pid: u32, pgid: u32) { let _ = sys::setpgid(pid, pgid); }
+
 use super::job_control::{JobControl, ProcessState};
 use super::pipe;
+use super::terminal::TerminalGuard;
 use super::super::Shell;
+use super::super::flow::FlowLogic;
 use super::super::job::{JobKind, RefinedJob};
 use super::super::status::*;
+use super::super::traps::Traps;
 use std::process::exit;
 
 /// Forks the shell, adding the child to the parent's background list, and executing
@@ -30,6 +38,7 @@ pub(crate) fn fork_pipe(
             exit(pipe(shell, commands, false));
         }
         Ok(pid) => {
+            join_process_group(pid, pid);
             // The parent process should add the child fork's PID to the background.
             shell.send_to_background(pid, ProcessState::Running, command_name);
             SUCCESS
@@ -40,3 +49,31 @@ pub(crate) fn fork_pipe(
         }
     }
 }
+
+/// Forks the shell to evaluate the body of a `( ... )` group, so that any changes that the body
+/// makes to variables, functions, or the working directory will not leak into the parent. The
+/// child is given its own process group, and the parent waits for it in the foreground.
+pub(crate) fn fork_subshell(shell: &mut Shell, body: &str) -> i32 {
+    match unsafe { sys::fork() } {
+        Ok(0) => {
+            create_process_group(0);
+            // Traps are not inherited by subshells, errors collected within them would be lost
+            // when they exit, and their commands are not located within the script.
+            shell.traps = Traps::default();
+            shell.reporter.collect(false);
+            shell.location = None;
+            shell.on_command(body);
+            exit(shell.previous_status);
+        }
+        Ok(pid) => {
+            join_process_group(pid, pid);
+            let terminal = TerminalGuard::new(!shell.is_background_shell && !shell.is_library);
+            terminal.give_to(pid);
+            shell.watch_foreground(&[pid], || format!("({})", body), |_| ())
+        }
+        Err(why) => {
+            eprintln!("ion: subshell fork failed: {}", why);
+            FAILURE
+        }
+    }
+}
@@ -1,23 +1,17 @@
 use super::foreground::BackgroundResult;
+use super::terminal::TerminalGuard;
 use super::super::Shell;
 use super::super::signals;
 use super::super::status::*;
 use std::fmt;
 use std::sync::{Arc, Mutex};
 use std::thread::{sleep, spawn};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use sys;
 
 use sys::job_control as self_sys;
 pub(crate) use sys::job_control::watch_background;
 
-/// When given a process ID, that process's group will be assigned as the foreground process group.
-pub(crate) fn set_foreground_as(pid: u32) {
-    signals::block();
-    let _ = sys::tcsetpgrp(0, pid);
-    signals::unblock();
-}
-
 pub(crate) trait JobControl {
     /// Waits for background jobs to finish before returning.
     fn wait_for_background(&mut self);
@@ -30,16 +24,14 @@ pub(crate) trait JobControl {
     fn handle_signal(&self, signal: i32) -> bool;
     fn foreground_send(&self, signal: i32);
     fn background_send(&self, signal: i32);
-    fn watch_foreground<F, D>(
-        &mut self,
-        pid: u32,
-        last_pid: u32,
-        get_command: F,
-        drop_command: D,
-    ) -> i32
+    /// Waits for each process of a foreground job, which are given in the order of its
+    /// pipeline, and the first of which leads its process group.
+    fn watch_foreground<F, D>(&mut self, children: &[u32], get_command: F, drop_command: D) -> i32
         where F: FnOnce() -> String,
               D: FnMut(i32);
     fn send_to_background(&mut self, child: u32, state: ProcessState, command: String);
+    /// Reports each background job that has finished since the last report, freeing its slot.
+    fn report_finished_jobs(&self);
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -47,6 +39,8 @@ pub(crate) trait JobControl {
 pub enum ProcessState {
     Running,
     Stopped,
+    /// The process has exited with the given status, which has not yet been reported.
+    Done(i32),
     Empty,
 }
 
@@ -55,6 +49,8 @@ impl fmt::Display for ProcessState {
         match *self {
             ProcessState::Running => write!(f, "Running"),
             ProcessState::Stopped => write!(f, "Stopped"),
+            ProcessState::Done(0) => write!(f, "Done"),
+            ProcessState::Done(status) => write!(f, "Exit {}", status),
             ProcessState::Empty => write!(f, "Empty"),
         }
     }
@@ -63,6 +59,7 @@ impl fmt::Display for ProcessState {
 pub(crate) fn add_to_background(
     processes: Arc<Mutex<Vec<BackgroundProcess>>>,
     pid: u32,
+    pgid: u32,
     state: ProcessState,
     command: String,
 ) -> u32 {
@@ -71,9 +68,11 @@ pub(crate) fn add_to_background(
         Some(id) => {
             (*processes)[id] = BackgroundProcess {
                 pid:           pid,
+                pgid:          pgid,
                 ignore_sighup: false,
                 state:         state,
                 name:          command,
+                started:       Instant::now(),
             };
             id as u32
         }
@@ -81,15 +80,29 @@ pub(crate) fn add_to_background(
             let njobs = (*processes).len();
             (*processes).push(BackgroundProcess {
                 pid:           pid,
+                pgid:          pgid,
                 ignore_sighup: false,
                 state:         state,
                 name:          command,
+                started:       Instant::now(),
             });
             njobs as u32
         }
     }
 }
 
+/// Obtains the status of a pipeline from those of its processes, given in the order of the
+/// pipeline: that of the last process, or with `pipefail`, that of the rightmost process which
+/// failed. Processes which could not be waited upon are taken to have succeeded.
+pub(crate) fn pipeline_status(statuses: &[Option<i32>], pipefail: bool) -> i32 {
+    let mut statuses = statuses.iter().map(|status| status.unwrap_or(0));
+    if pipefail {
+        statuses.rev().find(|&status| status != 0).unwrap_or(0)
+    } else {
+        statuses.last().unwrap_or(0)
+    }
+}
+
 #[derive(Clone, Debug)]
 /// A background process is a process that is attached to, but not directly managed
 /// by the shell. The shell will only retain information about the process, such
@@ -97,9 +110,24 @@ pub(crate) fn add_to_background(
 /// process is executing.
 pub struct BackgroundProcess {
     pub pid:           u32,
+    /// The process group of the job, which its processes share.
+    pub pgid:          u32,
     pub ignore_sighup: bool,
     pub state:         ProcessState,
     pub name:          String,
+    /// When the job was placed in the background.
+    pub started:       Instant,
+}
+
+impl BackgroundProcess {
+    /// True if the process has exited, or its slot has been freed. Signals should no longer be
+    /// sent to it, as its PID may have been reused.
+    pub(crate) fn is_finished(&self) -> bool {
+        match self.state {
+            ProcessState::Done(_) | ProcessState::Empty => true,
+            _ => false,
+        }
+    }
 }
 
 impl JobControl for Shell {
@@ -108,8 +136,10 @@ impl JobControl for Shell {
         if cont {
             signals::resume(pid);
         }
-        // Pass the TTY to the background job
-        set_foreground_as(pid);
+        // Pass the TTY to the background job. Once dropped, the shell will reclaim the TTY and
+        // restore its terminal modes.
+        let terminal = TerminalGuard::new(true);
+        terminal.give_to(pid);
         // Signal the background thread that is waiting on this process to stop waiting.
         self.foreground_signals.signal_to_grab(pid);
         let status = loop {
@@ -117,25 +147,28 @@ impl JobControl for Shell {
             // the status of that process will be communicated back. To avoid consuming CPU cycles,
             // we wait 25 ms between polls.
             match self.foreground_signals.was_processed() {
-                Some(BackgroundResult::Status(stat)) => break stat as i32,
+                Some(BackgroundResult::Status(stat)) => break stat,
                 Some(BackgroundResult::Errored) => break TERMINATED,
                 None => sleep(Duration::from_millis(25)),
             }
         };
-        // Have the shell reclaim the TTY
-        set_foreground_as(sys::getpid().unwrap());
         status
     }
 
     /// Waits until all running background tasks have completed, and listens for signals in the
-    /// event that a signal is sent to kill the running tasks.
+    /// event that a signal is sent to kill the running tasks. An interrupt will be forwarded to
+    /// the background tasks, and will stop the wait without exiting the shell.
     fn wait_for_background(&mut self) {
         let sigcode;
         'event: loop {
             for process in self.background.lock().unwrap().iter() {
                 if let ProcessState::Running = process.state {
                     while let Some(signal) = self.next_signal() {
-                        if signal != sys::SIGTSTP {
+                        if signal == sys::SIGINT {
+                            self.background_send(signal);
+                            self.break_flow = true;
+                            return;
+                        } else if signal != sys::SIGTSTP {
                             self.background_send(signal);
                             sigcode = get_signal_code(signal);
                             break 'event;
@@ -150,17 +183,11 @@ impl JobControl for Shell {
         self.exit(sigcode);
     }
 
-    fn watch_foreground<F, D>(
-        &mut self,
-        pid: u32,
-        last_pid: u32,
-        get_command: F,
-        drop_command: D,
-    ) -> i32
+    fn watch_foreground<F, D>(&mut self, children: &[u32], get_command: F, drop_command: D) -> i32
         where F: FnOnce() -> String,
               D: FnMut(i32)
     {
-        self_sys::watch_foreground(self, pid, last_pid, get_command, drop_command)
+        self_sys::watch_foreground(self, children, get_command, drop_command)
     }
 
     /// Send a kill signal to all running foreground tasks.
@@ -174,7 +201,7 @@ impl JobControl for Shell {
     fn background_send(&self, signal: i32) {
         if signal == sys::SIGHUP {
             for process in self.background.lock().unwrap().iter() {
-                if !process.ignore_sighup {
+                if !process.ignore_sighup && !process.is_finished() {
                     let _ = sys::killpg(process.pid, signal);
                 }
             }
@@ -195,7 +222,8 @@ impl JobControl for Shell {
 
         // Add the process to the background list, and mark the job's ID as
         // the previous job in the shell (in case fg/bg is executed w/ no args).
-        let njob = add_to_background(processes.clone(), pid, state, command);
+        let pgid = sys::getpgid(pid).unwrap_or(pid);
+        let njob = add_to_background(processes.clone(), pid, pgid, state, command);
         self.previous_job = njob;
         eprintln!("ion: bg [{}] {}", njob, pid);
 
@@ -207,6 +235,15 @@ impl JobControl for Shell {
         });
     }
 
+    fn report_finished_jobs(&self) {
+        for (id, process) in self.background.lock().unwrap().iter_mut().enumerate() {
+            if let ProcessState::Done(status) = process.state {
+                eprintln!("ion: ([{}] {}) exited with {}", id, process.pid, status);
+                process.state = ProcessState::Empty;
+            }
+        }
+    }
+
     /// If a SIGTERM is received, a SIGTERM will be sent to all background processes
     /// before the shell terminates itself.
     fn handle_signal(&self, signal: i32) -> bool {
@@ -218,3 +255,18 @@ impl JobControl for Shell {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipeline_statuses() {
+        let statuses = [Some(0), Some(141), Some(1), Some(0)];
+        assert_eq!(pipeline_status(&statuses, false), 0);
+        assert_eq!(pipeline_status(&statuses, true), 1);
+        assert_eq!(pipeline_status(&[Some(2), Some(130), None], true), 130);
+        assert_eq!(pipeline_status(&[Some(0), Some(0)], true), 0);
+        assert_eq!(pipeline_status(&[], false), 0);
+    }
+}
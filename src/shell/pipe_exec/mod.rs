@@ -8,17 +8,26 @@ pub mod foreground;
 mod fork;
 pub mod job_control;
 mod streams;
+mod terminal;
 
-use self::fork::{create_process_group, fork_pipe};
+pub(crate) use self::fork::fork_subshell;
+use self::fork::{create_process_group, fork_pipe, join_process_group};
 use self::job_control::JobControl;
 use self::streams::{duplicate_streams, redir, redirect_streams};
+use self::terminal::TerminalGuard;
 use super::{JobKind, Shell};
 use super::flags::*;
-use super::flow_control::FunctionError;
+use super::errors::{ErrorKind, IonError, Reporter};
 use super::job::{RefinedJob, TeeItem};
-use super::signals::{self, SignalHandler};
+use super::environment::CleanEnv;
+use super::isolation::Isolation;
+use super::limits::Limits;
+use super::sandbox::FakeCommands;
+use super::signals;
 use super::status::*;
+use builtins::CONTROLS_PROCESSES;
 use parser::pipelines::{Input, PipeItem, Pipeline, RedirectFrom, Redirection};
+use std::env;
 use std::fs::{File, OpenOptions};
 use std::io::{self, Error, Write};
 use std::iter;
@@ -27,6 +36,7 @@ use std::os::unix::process::CommandExt;
 use std::path::Path;
 use std::process::{exit, Command};
 use sys;
+use types::{Array, Value};
 
 type RefinedItem = (RefinedJob, JobKind, Vec<Redirection>, Vec<Input>);
 
@@ -47,20 +57,12 @@ pub unsafe fn stdin_of<T: AsRef<[u8]>>(input: T) -> Result<RawFd, Error> {
     Ok(reader)
 }
 
-/// This function serves three purposes:
+/// This function serves two purposes:
 /// 1. If the result is `Some`, then we will fork the pipeline executing into the background.
 /// 2. The value stored within `Some` will be that background job's command name.
-/// 3. If `set -x` was set, print the command.
-fn gen_background_string(pipeline: &Pipeline, print_comm: bool) -> Option<String> {
+fn gen_background_string(pipeline: &Pipeline) -> Option<String> {
     if pipeline.items[pipeline.items.len() - 1].job.kind == JobKind::Background {
-        let command = pipeline.to_string();
-        if print_comm {
-            eprintln!("> {}", command);
-        }
-        Some(command)
-    } else if print_comm {
-        eprintln!("> {}", pipeline.to_string());
-        None
+        Some(pipeline.to_string())
     } else {
         None
     }
@@ -77,16 +79,118 @@ fn is_implicit_cd(argument: &str) -> bool {
         && Path::new(argument).is_dir()
 }
 
+/// Determines if the command names a program within the directories of `PATH`.
+fn is_in_path(command: &str) -> bool {
+    !command.contains('/')
+        && env::var("PATH")
+            .unwrap_or_default()
+            .split(sys::PATH_SEPARATOR)
+            .any(|directory| Path::new(directory).join(command).is_file())
+}
+
+/// Determines the directory which the command changes to when the `autocd` option is set, if the
+/// command names no function, builtin, or program within `PATH`. Either the command is the path
+/// of a directory, or three or more dots, each dot after the first of which climbs to a parent
+/// directory, so that `...` changes to `../..`.
+fn auto_cd(shell: &Shell, command: &str) -> Option<String> {
+    if shell.flags & AUTO_CD == 0 || is_in_path(command) {
+        return None;
+    }
+    if command.len() > 2 && command.chars().all(|c| c == '.') {
+        Some(vec![".."; command.len() - 1].join("/"))
+    } else if Path::new(command).is_dir() {
+        Some(command.to_owned())
+    } else {
+        None
+    }
+}
+
+/// Determines if the command would execute an external program, rather than changing the
+/// directory, calling a function or builtin, or executing a fake command of the sandbox.
+pub(super) fn is_external(shell: &Shell, command: &str) -> bool {
+    !(is_implicit_cd(command) || shell.functions.contains_key(command)
+        || shell.builtins.contains_key(command) || shell.fake_command(command).is_some()
+        || auto_cd(shell, command).is_some())
+}
+
+/// Determines if the command is `env --clean`, which executes a command with a clean
+/// environment.
+fn is_clean_env(args: &[Value]) -> bool {
+    args.len() >= 2 && args[0] == "env" && args[1] == "--clean"
+}
+
+/// Determines if the command is prefixed by `isolate` or `limit`, which apply to the external
+/// command that follows them.
+fn is_prefix(command: &str) -> bool { command == "isolate" || command == "limit" }
+
+/// Creates the external command which follows the `isolate` and `limit` prefixes, each of which
+/// may be given once, as in `limit cpu=10s isolate --net cmd`.
+fn prefixed_command(shell: &Shell, mut args: &[Value]) -> Result<RefinedJob, IonError> {
+    let mut isolation = None;
+    let mut limits = None;
+    loop {
+        match args[0].as_str() {
+            "isolate" if isolation.is_none() => {
+                let (parsed, command) = Isolation::parse(&args[1..])
+                    .map_err(|why| IonError::new(ErrorKind::Pipeline, why).context("isolate"))?;
+                isolation = Some(parsed);
+                args = command;
+            }
+            "limit" if limits.is_none() => {
+                let (parsed, command) = Limits::parse(&args[1..])
+                    .map_err(|why| IonError::new(ErrorKind::Pipeline, why).context("limit"))?;
+                limits = Some(parsed);
+                args = command;
+            }
+            _ => break,
+        }
+    }
+
+    let mut command = Command::new(&args[0]);
+    command.args(&args[1..]);
+    if shell.flags & CLEAN_ENV != 0 {
+        shell.clean(&mut command);
+    }
+    if let Some(limits) = limits {
+        limits
+            .apply(&mut command)
+            .map_err(|why| IonError::new(ErrorKind::Pipeline, why).context("limit"))?;
+    }
+    Ok(RefinedJob::external(command, args.iter().cloned().collect(), isolation))
+}
+
+/// Opens the file that output is redirected to. While `noclobber` is set, existing files will
+/// not be truncated, though they may still be appended to.
+fn open_output(output: &Redirection, noclobber: bool) -> io::Result<File> {
+    if output.append {
+        OpenOptions::new().create(true).write(true).append(true).open(&output.file)
+    } else if noclobber && Path::new(&output.file).is_file() {
+        Err(io::Error::new(io::ErrorKind::AlreadyExists, "cannot overwrite existing file"))
+    } else {
+        File::create(&output.file)
+    }
+}
+
 /// Insert the multiple redirects as pipelines if necessary. Handle both input and output
 /// redirection if necessary.
-fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, JobKind)>> {
+fn do_redirection(
+    piped_commands: Vec<RefinedItem>,
+    noclobber: bool,
+    reporter: &Reporter,
+) -> Option<Vec<(RefinedJob, JobKind)>> {
+    macro_rules! report {
+        ($($arg:tt)*) => {
+            reporter.report(IonError::new(ErrorKind::Pipeline, format!($($arg)*)))
+        }
+    }
+
     macro_rules! get_infile {
         ($input:expr) => {
             match $input {
                 Input::File(ref filename) => match File::open(filename) {
                     Ok(file) => Some(file),
                     Err(e) => {
-                        eprintln!("ion: failed to redirect '{}' to stdin: {}", filename, e);
+                        report!("failed to redirect '{}' to stdin: {}", filename, e);
                         None
                     }
                 },
@@ -97,8 +201,8 @@ fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, J
                     match unsafe { stdin_of(&string) } {
                         Ok(stdio) => Some(unsafe { File::from_raw_fd(stdio) }),
                         Err(e) => {
-                            eprintln!("ion: failed to redirect herestring '{}' to stdin: {}",
-                                      string, e);
+                            report!("failed to redirect herestring '{}' to stdin: {}",
+                                    string, e);
                             None
                         }
                     }
@@ -139,11 +243,7 @@ fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, J
         ($outputs:ident, $job:ident) => {
             // XXX: Possibly add an assertion here for correctness
             for output in $outputs {
-                match if output.append {
-                    OpenOptions::new().create(true).write(true).append(true).open(&output.file)
-                } else {
-                    File::create(&output.file)
-                } {
+                match open_output(&output, noclobber) {
                     Ok(f) => match output.from {
                         RedirectFrom::Stderr => $job.stderr(f),
                         RedirectFrom::Stdout => $job.stdout(f),
@@ -153,8 +253,8 @@ fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, J
                                 $job.stderr(f_copy);
                             },
                             Err(e) => {
-                                eprintln!(
-                                    "ion: failed to redirect both stdout and stderr to file '{:?}': {}",
+                                report!(
+                                    "failed to redirect both stdout and stderr to file '{:?}': {}",
                                     f,
                                     e);
                                 return None;
@@ -162,7 +262,7 @@ fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, J
                         }
                     },
                     Err(e) => {
-                        eprintln!("ion: failed to redirect output into {}: {}", output.file, e);
+                        report!("failed to redirect output into {}: {}", output.file, e);
                         return None;
                     }
                 }
@@ -172,15 +272,14 @@ fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, J
 
     macro_rules! set_one_tee {
         ($new:ident, $outputs:ident, $job:ident, $kind:ident, $teed:ident, $other:ident) => {{
-            let mut tee = TeeItem { sinks: Vec::new(), source: None };
+            let mut tee = TeeItem { sinks: Vec::new(), source: None, outputs: Vec::new() };
             for output in $outputs {
-                match if output.append {
-                    OpenOptions::new().create(true).write(true).append(true).open(&output.file)
-                } else {
-                    File::create(&output.file)
-                } {
+                match open_output(&output, noclobber) {
                     Ok(f) => match output.from {
-                        RedirectFrom::$teed => tee.sinks.push(f),
+                        RedirectFrom::$teed => {
+                            tee.sinks.push(f);
+                            tee.outputs.push(output.clone());
+                        }
                         RedirectFrom::$other => if RedirectFrom::Stdout == RedirectFrom::$teed {
                             $job.stderr(f);
                         } else {
@@ -194,10 +293,11 @@ fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, J
                                     $job.stdout(f);
                                 }
                                 tee.sinks.push(f_copy);
+                                tee.outputs.push(output.clone());
                             },
                             Err(e) => {
-                                eprintln!(
-                                    "ion: failed to redirect both stdout and stderr to file '{:?}': {}",
+                                report!(
+                                    "failed to redirect both stdout and stderr to file '{:?}': {}",
                                     f,
                                     e);
                                 return None;
@@ -205,7 +305,7 @@ fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, J
                         }
                     },
                     Err(e) => {
-                        eprintln!("ion: failed to redirect output into {}: {}", output.file, e);
+                        report!("failed to redirect output into {}: {}", output.file, e);
                         return None;
                     }
                 }
@@ -229,11 +329,14 @@ fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, J
         match (inputs.len(), prev_kind) {
             (0, _) => {}
             (1, JobKind::Pipe(_)) => {
+                let described = inputs.clone();
                 let sources = vec![get_infile!(inputs[0])?];
-                new_commands.push((RefinedJob::cat(sources), JobKind::Pipe(RedirectFrom::Stdout)));
+                let cat = RefinedJob::cat(sources, described);
+                new_commands.push((cat, JobKind::Pipe(RedirectFrom::Stdout)));
             }
             (1, _) => job.stdin(get_infile!(inputs[0])?),
             _ => {
+                let described = inputs.clone();
                 let mut sources = Vec::new();
                 for mut input in inputs {
                     sources.push(if let Some(f) = get_infile!(input) {
@@ -242,7 +345,8 @@ fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, J
                         return None;
                     });
                 }
-                new_commands.push((RefinedJob::cat(sources), JobKind::Pipe(RedirectFrom::Stdout)));
+                let cat = RefinedJob::cat(sources, described);
+                new_commands.push((cat, JobKind::Pipe(RedirectFrom::Stdout)));
             }
         }
         prev_kind = kind;
@@ -263,30 +367,36 @@ fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, J
             // tee both
             (true, true) => {
                 let mut tee_out = TeeItem {
-                    sinks:  Vec::new(),
-                    source: None,
+                    sinks:   Vec::new(),
+                    source:  None,
+                    outputs: Vec::new(),
                 };
                 let mut tee_err = TeeItem {
-                    sinks:  Vec::new(),
-                    source: None,
+                    sinks:   Vec::new(),
+                    source:  None,
+                    outputs: Vec::new(),
                 };
                 for output in outputs {
-                    match if output.append {
-                        OpenOptions::new().create(true).write(true).append(true).open(&output.file)
-                    } else {
-                        File::create(&output.file)
-                    } {
+                    match open_output(&output, noclobber) {
                         Ok(f) => match output.from {
-                            RedirectFrom::Stdout => tee_out.sinks.push(f),
-                            RedirectFrom::Stderr => tee_err.sinks.push(f),
+                            RedirectFrom::Stdout => {
+                                tee_out.sinks.push(f);
+                                tee_out.outputs.push(output.clone());
+                            }
+                            RedirectFrom::Stderr => {
+                                tee_err.sinks.push(f);
+                                tee_err.outputs.push(output.clone());
+                            }
                             RedirectFrom::Both => match f.try_clone() {
                                 Ok(f_copy) => {
                                     tee_out.sinks.push(f);
                                     tee_err.sinks.push(f_copy);
+                                    tee_out.outputs.push(output.clone());
+                                    tee_err.outputs.push(output.clone());
                                 }
                                 Err(e) => {
-                                    eprintln!(
-                                        "ion: failed to redirect both stdout and stderr to file \
+                                    report!(
+                                        "failed to redirect both stdout and stderr to file \
                                          '{:?}': {}",
                                         f,
                                         e
@@ -296,7 +406,7 @@ fn do_redirection(piped_commands: Vec<RefinedItem>) -> Option<Vec<(RefinedJob, J
                             },
                         },
                         Err(e) => {
-                            eprintln!("ion: failed to redirect output into {}: {}", output.file, e);
+                            report!("failed to redirect output into {}: {}", output.file, e);
                             return None;
                         }
                     }
@@ -330,6 +440,11 @@ pub(crate) trait PipelineExecution {
     /// over time.
     fn execute_pipeline(&mut self, pipeline: &mut Pipeline) -> i32;
 
+    /// Prints the pipeline to stderr instead of executing it, if `dryrun` is enabled and the
+    /// pipeline would act outside of the shell, by executing an external command, redirecting
+    /// output to a file, or controlling other processes. Returns whether it was printed.
+    fn dry_run(&self, pipeline: &Pipeline) -> bool;
+
     /// Generates a vector of commands from a given `Pipeline`.
     ///
     /// Each generated command will either be a builtin or external command, and will be
@@ -393,12 +508,14 @@ pub(crate) trait PipelineExecution {
 
 impl PipelineExecution for Shell {
     fn execute_pipeline(&mut self, pipeline: &mut Pipeline) -> i32 {
+        if self.dry_run(pipeline) {
+            return SUCCESS;
+        }
         // Remove any leftover foreground tasks from the last execution.
         self.foreground.clear();
         // If the supplied pipeline is a background, a string representing the command will be
         // stored here.
-        let possible_background_name =
-            gen_background_string(&pipeline, self.flags & PRINT_COMMS != 0);
+        let possible_background_name = gen_background_string(&pipeline);
         // Generates commands for execution, differentiating between external and builtin commands.
         let piped_commands = match self.generate_commands(pipeline) {
             Ok(commands) => commands,
@@ -410,27 +527,40 @@ impl PipelineExecution for Shell {
             return SUCCESS;
         }
 
-        let piped_commands = if let Some(c) = do_redirection(piped_commands) {
-            c
-        } else {
-            return COULD_NOT_EXEC;
+        let noclobber = self.flags & NO_CLOBBER != 0;
+        let piped_commands = match do_redirection(piped_commands, noclobber, &self.reporter) {
+            Some(commands) => commands,
+            None => return COULD_NOT_EXEC,
         };
 
         // If the given pipeline is a background task, fork the shell.
         if let Some(command_name) = possible_background_name {
             fork_pipe(self, piped_commands, command_name)
         } else {
-            // While active, the SIGTTOU signal will be ignored.
-            let _sig_ignore = SignalHandler::new();
-            let foreground = !self.is_background_shell;
-            // Execute each command in the pipeline, giving each command the foreground.
-            let exit_status = pipe(self, piped_commands, foreground);
-            // Set the shell as the foreground process again to regain the TTY.
-            if foreground && !self.is_library {
-                let _ = sys::tcsetpgrp(0, sys::getpid().unwrap());
-            }
-            exit_status
+            // Once dropped, the shell will regain the TTY and restore its terminal modes.
+            let terminal = TerminalGuard::new(!self.is_background_shell && !self.is_library);
+            // Execute each command in the pipeline, giving each command the foreground, unless
+            // the shell is not in the foreground itself.
+            let foreground = terminal.is_owned();
+            pipe(self, piped_commands, foreground)
+        }
+    }
+
+    fn dry_run(&self, pipeline: &Pipeline) -> bool {
+        if self.flags & DRY_RUN == 0 {
+            return false;
+        }
+        let acts_outside = pipeline.items.iter().any(|item| {
+            let command = item.job.args.first().map_or("", |arg| arg.as_str());
+            !item.outputs.is_empty() || CONTROLS_PROCESSES.contains(&command)
+                || is_external(self, command)
+        });
+        if acts_outside {
+            let stderr = io::stderr();
+            let pipeline = self.variables.mask(&pipeline.to_string());
+            let _ = writeln!(stderr.lock(), "ion: dry run: {}", pipeline);
         }
+        acts_outside
     }
 
     fn generate_commands(&self, pipeline: &mut Pipeline) -> Result<Vec<RefinedItem>, i32> {
@@ -447,16 +577,48 @@ impl PipelineExecution for Shell {
                         "cd".into(),
                         iter::once("cd".into()).chain(job.args.drain()).collect(),
                     )
-                } else if self.functions.contains_key(job.args[0].as_str()) {
-                    RefinedJob::function(job.args[0].clone().into(), job.args.drain().collect())
                 } else if self.builtins.contains_key(job.args[0].as_str()) {
+                    // Builtins take precedence over the functions which share their names.
                     RefinedJob::builtin(job.args[0].clone().into(), job.args.drain().collect())
+                } else if self.functions.contains_key(job.args[0].as_str()) {
+                    RefinedJob::function(job.args[0].clone().into(), job.args.drain().collect())
+                } else if let Some(command) = self.fake_command(&job.args[0]) {
+                    // Fake commands are evaluated in place of the commands that they replace.
+                    RefinedJob::builtin("eval".into(), array!["eval", command])
+                } else if is_clean_env(&job.args) {
+                    match self.clean_command(&job.args[2..]) {
+                        Some((command, args)) => {
+                            RefinedJob::external(command, args.iter().cloned().collect(), None)
+                        }
+                        None => {
+                            let error = IonError::new(ErrorKind::Pipeline, "expected a command");
+                            self.report(error.context("env --clean"));
+                            return Err(BAD_ARG);
+                        }
+                    }
+                } else if is_prefix(&job.args[0]) {
+                    match prefixed_command(self, &job.args) {
+                        Ok(refined) => refined,
+                        Err(error) => {
+                            self.report(error);
+                            return Err(BAD_ARG);
+                        }
+                    }
+                } else if let Some(directory) = auto_cd(self, &job.args[0]) {
+                    RefinedJob::builtin(
+                        "cd".into(),
+                        iter::once("cd".into())
+                            .chain(iter::once(directory))
+                            .chain(job.args.drain().skip(1))
+                            .collect(),
+                    )
                 } else {
-                    let mut command = Command::new(job.args[0].clone());
-                    for arg in job.args.drain().skip(1) {
-                        command.arg(arg);
+                    let mut command = Command::new(&job.args[0]);
+                    command.args(&job.args[1..]);
+                    if self.flags & CLEAN_ENV != 0 {
+                        self.clean(&mut command);
                     }
-                    RefinedJob::External(command)
+                    RefinedJob::external(command, job.args.drain().collect(), None)
                 }
             };
             results.push((refined, job.kind, outputs, inputs));
@@ -468,16 +630,10 @@ impl PipelineExecution for Shell {
         // TODO: Find a way to only do this when absolutely necessary.
         let as_string = commands.iter().map(RefinedJob::long).collect::<Vec<String>>().join(" | ");
 
-        // Each process in the pipe has the same PGID, which is the first process's PID.
-        let pgid = children[0];
-
-        // If the last process exits, we know that all processes should exit.
-        let last_pid = children[children.len() - 1];
-
         // Watch the foreground group, dropping all commands that exit as they exit.
+        let pids = children.clone();
         self.watch_foreground(
-            pgid,
-            last_pid,
+            &pids,
             move || as_string,
             move |pid| if let Some(id) = children.iter().position(|&x| x as i32 == pid) {
                 commands.remove(id);
@@ -490,26 +646,34 @@ impl PipelineExecution for Shell {
         let short = job.short();
         let long = job.long();
         match *job {
-            RefinedJob::External(ref mut command) => match {
-                command
-                    .before_exec(move || {
-                        signals::unblock();
-                        create_process_group(0);
-                        Ok(())
-                    })
-                    .spawn()
+            RefinedJob::External {
+                ref mut command,
+                ref isolation,
+                ..
+            } => match {
+                command.before_exec(move || {
+                    signals::unblock();
+                    create_process_group(0);
+                    Ok(())
+                });
+                if let Some(ref isolation) = *isolation {
+                    isolation.apply(command);
+                }
+                command.spawn()
             } {
                 Ok(child) => {
                     if foreground && !self.is_library {
                         let _ = sys::tcsetpgrp(0, child.id());
                     }
-                    self.watch_foreground(child.id(), child.id(), move || long, |_| ())
+                    self.watch_foreground(&[child.id()], move || long, |_| ())
                 }
                 Err(e) => if e.kind() == io::ErrorKind::NotFound {
-                    eprintln!("ion: command not found: {}", short);
+                    let error = IonError::new(ErrorKind::Pipeline, "command not found");
+                    self.report(error.context(short));
                     NO_SUCH_COMMAND
                 } else {
-                    eprintln!("ion: error spawning process: {}", e);
+                    let message = format!("error spawning process: {}", e);
+                    self.report(IonError::new(ErrorKind::Pipeline, message));
                     COULD_NOT_EXEC
                 },
             },
@@ -593,16 +757,8 @@ impl PipelineExecution for Shell {
         let function = self.functions.get(name).cloned().unwrap();
         match function.execute(self, args) {
             Ok(()) => SUCCESS,
-            Err(FunctionError::InvalidArgumentCount) => {
-                eprintln!("ion: invalid number of function arguments supplied");
-                FAILURE
-            }
-            Err(FunctionError::InvalidArgumentType(expected_type, value)) => {
-                eprintln!(
-                    "ion: function argument has invalid type: expected {}, found value \'{}\'",
-                    expected_type,
-                    value
-                );
+            Err(why) => {
+                self.report(why.describe(name));
                 FAILURE
             }
         }
@@ -712,6 +868,8 @@ pub(crate) fn pipe(
 
     let mut previous_status = SUCCESS;
     let mut previous_kind = JobKind::And;
+    // Set when the final job was not executed because of an `&&` or `||` operator.
+    let mut skipped = false;
     let mut commands = commands.into_iter();
     // A vector to hold possible external command stdout/stderr pipes.
     // If it is Some, then we close the various file descriptors after
@@ -726,16 +884,19 @@ pub(crate) fn pipe(
                     if let JobKind::Or = kind {
                         previous_kind = kind
                     }
+                    skipped = true;
                     continue;
                 },
                 JobKind::Or => if previous_status == SUCCESS {
                     if let JobKind::And = kind {
                         previous_kind = kind
                     }
+                    skipped = true;
                     continue;
                 },
                 _ => (),
             }
+            skipped = false;
 
             match kind {
                 JobKind::Pipe(mut mode) => {
@@ -752,15 +913,24 @@ pub(crate) fn pipe(
 
                     macro_rules! spawn_proc {
                         ($cmd:expr) => {
+                            shell.trace(&$cmd.long());
                             let short = $cmd.short();
                             match $cmd {
-                                RefinedJob::External(ref mut command) => {
+                                RefinedJob::External {
+                                    ref mut command,
+                                    ref isolation,
+                                    ..
+                                } => {
                                     match {
                                         command.before_exec(move || {
                                             signals::unblock();
                                             create_process_group(pgid);
                                             Ok(())
-                                        }).spawn()
+                                        });
+                                        if let Some(ref isolation) = *isolation {
+                                            isolation.apply(command);
+                                        }
+                                        command.spawn()
                                     } {
                                         Ok(child) => {
                                             if pgid == 0 {
@@ -774,10 +944,18 @@ pub(crate) fn pipe(
                                         },
                                         Err(e) => {
                                             return if e.kind() == io::ErrorKind::NotFound {
-                                                eprintln!("ion: command not found: {}", short);
+                                                let error = IonError::new(
+                                                    ErrorKind::Pipeline,
+                                                    "command not found",
+                                                );
+                                                shell.report(error.context(short));
                                                 NO_SUCH_COMMAND
                                             } else {
-                                                eprintln!("ion: error spawning process: {}", e);
+                                                let message =
+                                                    format!("error spawning process: {}", e);
+                                                let error =
+                                                    IonError::new(ErrorKind::Pipeline, message);
+                                                shell.report(error);
                                                 COULD_NOT_EXEC
                                             }
                                         }
@@ -789,6 +967,9 @@ pub(crate) fn pipe(
                                                       ref stderr,
                                                       ref stdin, } =>
                                 {
+                                    // Piped builtins and functions are executed within a fork,
+                                    // so that changes which they make to the shell's state,
+                                    // such as to its variables, are isolated from the shell.
                                     match unsafe { sys::fork() } {
                                         Ok(0) => {
                                             signals::unblock();
@@ -810,6 +991,7 @@ pub(crate) fn pipe(
                                             exit(ret)
                                         },
                                         Ok(pid) => {
+                                            join_process_group(pid, pgid);
                                             close(stdout);
                                             close(stderr);
                                             if pgid == 0 {
@@ -855,6 +1037,7 @@ pub(crate) fn pipe(
                                             exit(ret)
                                         },
                                         Ok(pid) => {
+                                            join_process_group(pid, pgid);
                                             close(stdout);
                                             close(stderr);
                                             if pgid == 0 {
@@ -875,7 +1058,8 @@ pub(crate) fn pipe(
                                 }
                                 RefinedJob::Cat { ref mut sources,
                                                   ref stdout,
-                                                  ref mut stdin } => {
+                                                  ref mut stdin,
+                                                  .. } => {
                                     match unsafe { sys::fork() } {
                                         Ok(0) => {
                                             let _ = sys::reset_signal(sys::SIGINT);
@@ -892,6 +1076,7 @@ pub(crate) fn pipe(
                                             exit(ret);
                                         }
                                         Ok(pid) => {
+                                            join_process_group(pid, pgid);
                                             close(stdout);
                                             if pgid == 0 {
                                                 pgid = pid;
@@ -928,6 +1113,7 @@ pub(crate) fn pipe(
                                             exit(ret);
                                         },
                                         Ok(pid) => {
+                                            join_process_group(pid, pgid);
                                             close(stdout);
                                             close(stderr);
                                             if pgid == 0 {
@@ -952,7 +1138,7 @@ pub(crate) fn pipe(
                         // output pipes, so we can properly close them after the job has been
                         // spawned.
                         let is_external =
-                            if let RefinedJob::External(..) = parent { true } else { false };
+                            if let RefinedJob::External { .. } = parent { true } else { false };
 
                         // If we need to tee both stdout and stderr, we directly connect pipes to
                         // the relevant sources in both of them.
@@ -1052,13 +1238,30 @@ pub(crate) fn pipe(
                     }
                 }
                 _ => {
+                    // Commands on the left-hand side of `&&` and `||` are conditions.
+                    let condition = kind == JobKind::And || kind == JobKind::Or;
+                    if condition {
+                        shell.conditions += 1;
+                    }
+                    shell.trace(&parent.long());
                     previous_status = shell.exec_job(&mut parent, foreground);
+                    if condition {
+                        shell.conditions -= 1;
+                    }
                     previous_kind = kind;
                 }
             }
+
+            // The remaining jobs will not be executed if the user interrupted the pipeline.
+            if shell.was_interrupted() {
+                return previous_status;
+            }
         } else {
             break;
         }
     }
+    if skipped {
+        shell.conditional_status = true;
+    }
     previous_status
 }
@@ -0,0 +1,59 @@
+//! Manages the ownership and the modes of the controlling terminal while foreground jobs run.
+
+use super::super::signals::SignalHandler;
+use sys;
+
+/// Saves the shell's terminal modes before a foreground job is given the terminal. When the
+/// guard is dropped, the shell reclaims the terminal and restores those modes, regardless of
+/// whether the job exited, crashed, was killed, or was stopped.
+pub(crate) struct TerminalGuard {
+    modes:    Option<sys::Termios>,
+    /// The process group of the shell, if the shell is in the foreground of the terminal.
+    owner:    Option<u32>,
+    // While active, the SIGTTOU signal will be ignored. This must be dropped last, as the
+    // terminal is reclaimed from the background.
+    _signals: SignalHandler,
+}
+
+impl TerminalGuard {
+    /// If `enabled` is false, such as when the shell is running in the background or as a
+    /// library, the terminal will not be touched. Nor will it be if the shell does not own the
+    /// terminal, as when a program which runs `ion -c` in the background keeps it for itself.
+    pub(crate) fn new(enabled: bool) -> TerminalGuard {
+        let _signals = SignalHandler::new();
+        let owner = if enabled && sys::isatty(sys::STDIN_FILENO) {
+            match (sys::tcgetpgrp(sys::STDIN_FILENO), sys::getpgrp()) {
+                (Ok(foreground), Ok(pgid)) if foreground == pgid => Some(pgid),
+                _ => None,
+            }
+        } else {
+            None
+        };
+        let modes = match owner {
+            Some(_) => sys::tcgetattr(sys::STDIN_FILENO).ok(),
+            None => None,
+        };
+        TerminalGuard { modes, owner, _signals }
+    }
+
+    /// True if the shell owns the terminal, and may therefore pass it to its jobs.
+    pub(crate) fn is_owned(&self) -> bool { self.owner.is_some() }
+
+    /// Passes the terminal to the given process group.
+    pub(crate) fn give_to(&self, pgid: u32) {
+        if self.owner.is_some() {
+            let _ = sys::tcsetpgrp(sys::STDIN_FILENO, pgid);
+        }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if let Some(owner) = self.owner {
+            let _ = sys::tcsetpgrp(sys::STDIN_FILENO, owner);
+            if let Some(ref modes) = self.modes {
+                let _ = sys::tcsetattr(sys::STDIN_FILENO, modes);
+            }
+        }
+    }
+}
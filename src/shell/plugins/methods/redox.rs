@@ -1,4 +1,5 @@
 use super::super::StringError;
+use std::path::Path;
 
 pub(crate) enum MethodArguments {
     StringArg(String, Vec<String>),
@@ -25,3 +26,7 @@ impl StringMethodPlugins {
 /// This function is meant to be called with `lazy_static` to ensure that there isn't a
 /// cost to collecting all this information when the shell never uses it in the first place!
 pub(crate) fn collect() -> StringMethodPlugins { StringMethodPlugins::new() }
+
+pub(crate) fn inspect(_path: &Path) -> Result<usize, String> {
+    Err("Redox doesn't support plugins yet".into())
+}
@@ -6,6 +6,7 @@ use std::ffi::CString;
 use std::fs::read_dir;
 use std::mem::forget;
 use std::os::raw::c_char;
+use std::path::Path;
 use std::ptr;
 use std::slice;
 use std::str;
@@ -242,3 +243,12 @@ pub(crate) fn collect() -> StringMethodPlugins {
     }
     methods
 }
+
+/// Loads the method library at the given path on its own, returning the number of methods which
+/// it provides, or the reason that it could not be loaded.
+pub(crate) fn inspect(path: &Path) -> Result<usize, String> {
+    let library = Library::new(path.as_os_str()).map_err(|why| why.to_string())?;
+    let mut methods = StringMethodPlugins::new();
+    methods.load(library).map_err(|why| why.to_string())?;
+    Ok(methods.symbols.len())
+}
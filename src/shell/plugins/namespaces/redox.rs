@@ -1,6 +1,7 @@
 use fnv::FnvHashMap;
 
 use super::super::StringError;
+use std::path::Path;
 use types::Identifier;
 
 pub(crate) struct StringNamespace;
@@ -17,3 +18,7 @@ pub(crate) fn collect() -> FnvHashMap<Identifier, StringNamespace> {
     eprintln!("ion: Redox doesn't support plugins yet");
     FnvHashMap::default()
 }
+
+pub(crate) fn inspect(_path: &Path) -> Result<usize, String> {
+    Err("Redox doesn't support plugins yet".into())
+}
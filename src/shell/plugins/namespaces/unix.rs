@@ -5,6 +5,7 @@ use libloading::os::unix::Symbol as RawSymbol;
 use std::ffi::CString;
 use std::fs::read_dir;
 use std::os::raw::c_char;
+use std::path::Path;
 use std::slice;
 use std::str;
 use types::Identifier;
@@ -154,3 +155,11 @@ pub(crate) fn collect() -> FnvHashMap<Identifier, StringNamespace> {
     }
     hashmap
 }
+
+/// Loads the namespace library at the given path on its own, returning the number of functions
+/// which it provides, or the reason that it could not be loaded.
+pub(crate) fn inspect(path: &Path) -> Result<usize, String> {
+    let library = Library::new(path.as_os_str()).map_err(|why| why.to_string())?;
+    let namespace = StringNamespace::new(library).map_err(|why| why.to_string())?;
+    Ok(namespace.symbols.len())
+}
@@ -0,0 +1,310 @@
+//! A profiler for scripts, which is enabled with `ion --profile`. The shell records how many
+//! times each statement and function was executed, and for how long, and prints a report of
+//! them to stderr when it exits.
+
+use super::Shell;
+use super::errors::Frame;
+use super::flow_control::Statement;
+use fnv::FnvHashMap;
+use parser::Location;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use sys;
+
+/// How the report of the profiler is printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Format {
+    /// A table of statements and functions, from the slowest to the fastest.
+    Text,
+    /// The same statistics as an object, for other programs to consume.
+    Json,
+    /// The time spent within each stack of function calls, as folded stacks for flamegraphs.
+    Folded,
+}
+
+impl Format {
+    /// Parses the format which follows `--profile`, which is either nothing or `=FORMAT`.
+    pub(crate) fn parse(format: &str) -> Option<Format> {
+        match format {
+            "" | "=text" => Some(Format::Text),
+            "=json" => Some(Format::Json),
+            "=folded" => Some(Format::Folded),
+            _ => None,
+        }
+    }
+}
+
+/// How many times a statement or function was executed, and the total time that it took.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Entry {
+    count: usize,
+    time:  Duration,
+}
+
+impl Entry {
+    fn record(&mut self, time: Duration) {
+        self.count += 1;
+        self.time += time;
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Profiler {
+    format:     Format,
+    statements: FnvHashMap<String, Entry>,
+    functions:  FnvHashMap<String, Entry>,
+    /// The time spent within each stack of calls, excluding the calls which were made from it.
+    stacks:     FnvHashMap<String, Duration>,
+    /// When each function which is being called began, and the time spent within the calls
+    /// which it made, beginning with the script itself.
+    calls:      Vec<(Instant, Duration)>,
+    /// The shell which is being profiled, as opposed to the children which are forked from it.
+    pid:        u32,
+}
+
+impl Profiler {
+    pub(crate) fn new(format: Format) -> Profiler {
+        Profiler {
+            format,
+            statements: FnvHashMap::default(),
+            functions: FnvHashMap::default(),
+            stacks: FnvHashMap::default(),
+            calls: vec![(Instant::now(), Duration::default())],
+            pid: sys::getpid().unwrap_or(0),
+        }
+    }
+
+    /// Records that a function was called.
+    pub(crate) fn enter(&mut self) { self.calls.push((Instant::now(), Duration::default())); }
+
+    /// Records that the most recent call of the given stack has returned.
+    pub(crate) fn leave(&mut self, stack: &[Frame]) {
+        let (start, children) = match self.calls.pop() {
+            Some(call) => call,
+            None => return,
+        };
+        let time = start.elapsed();
+        if let Some(&mut (_, ref mut parent)) = self.calls.last_mut() {
+            *parent += time;
+        }
+        if let Some(frame) = stack.last() {
+            let function = self.functions.entry(frame.function.clone());
+            function.or_insert_with(Entry::default).record(time);
+        }
+        let own = time.checked_sub(children).unwrap_or_default();
+        *self.stacks.entry(folded_stack(stack)).or_insert_with(Duration::default) += own;
+    }
+
+    fn record(&mut self, statement: String, time: Duration) {
+        self.statements.entry(statement).or_insert_with(Entry::default).record(time);
+    }
+
+    /// Prints the report to stderr, unless the shell is a child which was forked from the shell
+    /// which is being profiled.
+    pub(crate) fn print(&self) {
+        if sys::getpid().ok() == Some(self.pid) {
+            eprint!("{}", self.report());
+        }
+    }
+
+    /// Renders the report in its format.
+    fn report(&self) -> String {
+        let total = self.calls.first().map_or(Duration::default(), |&(start, _)| start.elapsed());
+        let tables = [("statement", &self.statements), ("function", &self.functions)];
+        let mut report = String::new();
+        match self.format {
+            Format::Text => {
+                let _ = writeln!(report, "profile: {:.6}s in total", seconds(total));
+                for &(title, entries) in &tables {
+                    let _ = writeln!(report, "{:>10} {:>12}  {}", "count", "seconds", title);
+                    for (name, entry) in sorted(entries) {
+                        let time = seconds(entry.time);
+                        let _ = writeln!(report, "{:>10} {:>12.6}  {}", entry.count, time, name);
+                    }
+                }
+            }
+            Format::Json => {
+                let _ = write!(report, "{{\"total\":{:.6}", seconds(total));
+                for &(title, entries) in &tables {
+                    let _ = write!(report, ",\"{}s\":[", title);
+                    for (id, (name, entry)) in sorted(entries).into_iter().enumerate() {
+                        let _ = write!(
+                            report,
+                            "{}{{\"name\":\"{}\",\"count\":{},\"seconds\":{:.6}}}",
+                            if id == 0 { "" } else { "," },
+                            escape(name),
+                            entry.count,
+                            seconds(entry.time)
+                        );
+                    }
+                    report.push(']');
+                }
+                report.push_str("}\n");
+            }
+            Format::Folded => {
+                let mut stacks = self.stacks.clone();
+                // The script itself spent the time which was not spent within its calls.
+                let children = self.calls.first().map_or(Duration::default(), |&(_, time)| time);
+                *stacks.entry(folded_stack(&[])).or_insert_with(Duration::default) +=
+                    total.checked_sub(children).unwrap_or_default();
+                let mut stacks = stacks.into_iter().collect::<Vec<(String, Duration)>>();
+                stacks.sort();
+                for (stack, time) in stacks {
+                    let micros = time.as_secs() * 1_000_000 + (time.subsec_nanos() / 1000) as u64;
+                    let _ = writeln!(report, "{} {}", stack, micros);
+                }
+            }
+        }
+        report
+    }
+}
+
+fn seconds(time: Duration) -> f64 { time.as_secs() as f64 + time.subsec_nanos() as f64 / 1e9 }
+
+/// Sorts entries from the slowest to the fastest, and then by name.
+fn sorted(entries: &FnvHashMap<String, Entry>) -> Vec<(&String, &Entry)> {
+    let mut entries = entries.iter().collect::<Vec<(&String, &Entry)>>();
+    entries.sort_by(|a, b| b.1.time.cmp(&a.1.time).then_with(|| a.0.cmp(b.0)));
+    entries
+}
+
+/// Names a stack of calls as its functions, separated by semicolons, beginning with `main`.
+fn folded_stack(stack: &[Frame]) -> String {
+    let mut folded = String::from("main");
+    for frame in stack {
+        folded.push(';');
+        folded.push_str(&frame.function);
+    }
+    folded
+}
+
+/// Escapes a string so that it may be written within quotes in JSON.
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for character in string.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            character if (character as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", character as u32);
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Records the statements which the shell executes for the profiler.
+pub(crate) trait Profiling {
+    /// Begins to time a statement, which is located if it is executed directly from a script,
+    /// and is otherwise named by the function which contains it.
+    fn start_profile(
+        &self,
+        statement: &Statement,
+        location: Option<&Location>,
+    ) -> Option<(String, Instant)>;
+
+    /// Records the time taken by a statement, unless it began a block which is still open.
+    fn finish_profile(&mut self, started: Option<(String, Instant)>);
+}
+
+impl Profiling for Shell {
+    fn start_profile(
+        &self,
+        statement: &Statement,
+        location: Option<&Location>,
+    ) -> Option<(String, Instant)> {
+        self.profiler.as_ref()?;
+        let description = statement.describe();
+        let statement = match (location, self.flow_control.call_stack.last()) {
+            (Some(location), _) => format!("{}: {}", location, description),
+            (None, Some(frame)) => format!("{}: {}", frame.function, description),
+            (None, None) => description,
+        };
+        Some((statement, Instant::now()))
+    }
+
+    fn finish_profile(&mut self, started: Option<(String, Instant)>) {
+        if let (Some((statement, start)), Some(profiler)) = (started, self.profiler.as_mut()) {
+            if self.flow_control.level == 0 {
+                profiler.record(statement, start.elapsed());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(function: &str) -> Frame {
+        Frame {
+            function:  function.into(),
+            location:  None,
+            call_site: None,
+        }
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(Format::parse(""), Some(Format::Text));
+        assert_eq!(Format::parse("=json"), Some(Format::Json));
+        assert_eq!(Format::parse("=folded"), Some(Format::Folded));
+        assert_eq!(Format::parse("=xml"), None);
+        assert_eq!(escape("say \"hi\"\\\n"), "say \\\"hi\\\"\\\\\\n");
+    }
+
+    #[test]
+    fn statements() {
+        let mut profiler = Profiler::new(Format::Text);
+        profiler.record("script.ion:2: sleep 1".into(), Duration::from_millis(1500));
+        profiler.record("script.ion:3: echo".into(), Duration::from_millis(1));
+        profiler.record("script.ion:2: sleep 1".into(), Duration::from_millis(500));
+        assert_eq!(
+            profiler.statements["script.ion:2: sleep 1"],
+            Entry {
+                count: 2,
+                time:  Duration::from_secs(2),
+            }
+        );
+        let report = profiler.report();
+        let lines = report.lines().skip(1).collect::<Vec<&str>>();
+        assert_eq!(lines[0], "     count      seconds  statement");
+        assert_eq!(lines[1], "         2     2.000000  script.ion:2: sleep 1");
+        assert_eq!(lines[2], "         1     0.001000  script.ion:3: echo");
+        assert_eq!(lines[3], "     count      seconds  function");
+
+        profiler.format = Format::Json;
+        let report = profiler.report();
+        assert!(report.starts_with("{\"total\":"));
+        assert!(report.ends_with(
+            ",\"statements\":[\
+             {\"name\":\"script.ion:2: sleep 1\",\"count\":2,\"seconds\":2.000000},\
+             {\"name\":\"script.ion:3: echo\",\"count\":1,\"seconds\":0.001000}],\
+             \"functions\":[]}\n"
+        ));
+    }
+
+    #[test]
+    fn calls() {
+        let mut profiler = Profiler::new(Format::Folded);
+        profiler.enter();
+        profiler.enter();
+        profiler.leave(&[frame("outer"), frame("inner")]);
+        profiler.leave(&[frame("outer")]);
+        profiler.enter();
+        profiler.leave(&[frame("outer")]);
+        assert_eq!(profiler.functions["outer"].count, 2);
+        assert_eq!(profiler.functions["inner"].count, 1);
+        assert_eq!(profiler.calls.len(), 1);
+
+        let report = profiler.report();
+        let stacks = report
+            .lines()
+            .map(|line| line.split(' ').next().unwrap())
+            .collect::<Vec<&str>>();
+        assert_eq!(stacks, vec!["main", "main;outer", "main;outer;inner"]);
+    }
+}
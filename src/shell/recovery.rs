@@ -0,0 +1,134 @@
+//! Recovery from bugs within the shell during interactive sessions. When a command panics, the
+//! panic is caught, the state of the shell and of the terminal is restored, and an internal error
+//! is reported, so that the session returns to the prompt with its history intact.
+
+use super::Shell;
+use super::flow_control::{restore_functions, Statement};
+use super::scopes::Scopes;
+use super::status::*;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::panic::{self, AssertUnwindSafe, PanicInfo};
+use std::process;
+use sys;
+
+thread_local! {
+    /// Set while a command is being executed by `catch`, so that its panic is not printed.
+    static CATCHING: Cell<bool> = Cell::new(false);
+    /// The description of the last panic which occurred within `catch`.
+    static LAST_PANIC: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// The message with which a panic was raised.
+fn message(payload: &Any) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => (*message).into(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "unknown panic".into(),
+        },
+    }
+}
+
+/// Describes a panic by its message and the location in the source of the shell which raised it.
+fn describe(info: &PanicInfo) -> String {
+    let message = message(info.payload());
+    match info.location() {
+        Some(location) => {
+            format!("{}, at {}:{}:{}", message, location.file(), location.line(), location.column())
+        }
+        None => message,
+    }
+}
+
+/// Replaces the panic hook with one which records the panics that are caught by `catch`, rather
+/// than printing them. Other panics are still printed by the previous hook.
+pub(crate) fn install_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if CATCHING.with(Cell::get) {
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(describe(info)));
+        } else {
+            previous(info);
+        }
+    }));
+}
+
+/// Executes the given command, returning a description of the panic which it raised, if any. A
+/// child which was forked from the shell while executing the command exits if it panics, rather
+/// than returning to the prompt alongside its parent.
+pub(crate) fn catch<F: FnOnce()>(command: F) -> Result<(), String> {
+    let pid = sys::getpid().ok();
+    CATCHING.with(|catching| catching.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(command));
+    CATCHING.with(|catching| catching.set(false));
+    result.map_err(|payload| {
+        let description = LAST_PANIC.with(|last| last.borrow_mut().take());
+        let description = description.unwrap_or_else(|| message(&*payload));
+        if sys::getpid().ok() != pid {
+            eprintln!("ion: internal error: {}", description);
+            process::exit(FAILURE);
+        }
+        description
+    })
+}
+
+/// The modes of the terminal before a command was executed.
+pub(crate) struct TerminalState {
+    modes: Option<sys::Termios>,
+}
+
+impl TerminalState {
+    pub(crate) fn save() -> TerminalState {
+        let modes = if sys::isatty(sys::STDIN_FILENO) {
+            sys::tcgetattr(sys::STDIN_FILENO).ok()
+        } else {
+            None
+        };
+        TerminalState { modes }
+    }
+
+    /// Takes the terminal back from any process group which was given it, and restores its modes.
+    pub(crate) fn restore(&self) {
+        if let Some(ref modes) = self.modes {
+            if let Ok(pid) = sys::getpid() {
+                let _ = sys::tcsetpgrp(sys::STDIN_FILENO, pid);
+            }
+            let _ = sys::tcsetattr(sys::STDIN_FILENO, modes);
+        }
+    }
+}
+
+/// Discards the state of the command which panicked, such as its partial blocks and calls, and
+/// restores the settings which its `with` blocks replaced. The guards which the command held,
+/// such as those which block signals, were dropped as it unwound.
+pub(crate) fn reset(shell: &mut Shell) {
+    shell.leave_scopes(0);
+    shell.flow_control.level = 0;
+    shell.flow_control.current_if_mode = 0;
+    shell.flow_control.current_statement = Statement::Default;
+    shell.flow_control.call_stack.clear();
+    restore_functions(shell, 0);
+    shell.depth = 0;
+    shell.conditions = 0;
+    shell.expansion_depth.set(0);
+    shell.expansion_errors.set(0);
+    shell.foreground.clear();
+    shell.break_flow = false;
+    shell.previous_status = FAILURE;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages() {
+        let payload: Box<Any + Send> = Box::new("index out of bounds");
+        assert_eq!(message(&*payload), "index out of bounds");
+        let payload: Box<Any + Send> = Box::new(String::from("unterminated brace"));
+        assert_eq!(message(&*payload), "unterminated brace");
+        let payload: Box<Any + Send> = Box::new(42);
+        assert_eq!(message(&*payload), "unknown panic");
+    }
+}
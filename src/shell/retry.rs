@@ -0,0 +1,98 @@
+//! Retry blocks, such as `retry 5 --delay 2s --backoff 2x`, which execute their statements again
+//! until they succeed, or until every attempt has been made, waiting between each attempt.
+
+use std::time::Duration;
+use units;
+
+/// The attempts which a `retry` block makes, and how long it waits between them.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Retry {
+    pub(crate) attempts: usize,
+    /// The number of seconds to wait after the first attempt fails.
+    pub(crate) delay:    f64,
+    /// The factor by which the delay is multiplied after each attempt which fails.
+    pub(crate) backoff:  f64,
+}
+
+impl Retry {
+    /// Parses the expanded arguments of a `retry` block: the number of attempts, followed by a
+    /// `--delay` which may be given with the suffix of a unit, as in `250ms`, and a `--backoff`
+    /// which may be followed by an `x`, as in `2x`.
+    pub(crate) fn parse<S: AsRef<str>>(args: &[S]) -> Result<Retry, String> {
+        let mut args = args.iter().map(AsRef::as_ref);
+        let attempts = match args.next().map(|arg| (arg, arg.parse::<usize>())) {
+            Some((_, Ok(attempts))) if attempts > 0 => attempts,
+            Some((arg, _)) => return Err(format!("{}: expected a number of attempts", arg)),
+            None => return Err("expected a number of attempts, as in `retry 5`".into()),
+        };
+
+        let mut retry = Retry {
+            attempts,
+            delay: 0.0,
+            backoff: 1.0,
+        };
+        while let Some(option) = args.next() {
+            let value = match option {
+                "--delay" | "--backoff" => {
+                    args.next().ok_or_else(|| format!("{}: expected a value", option))?
+                }
+                _ => return Err(format!("{}: invalid option", option)),
+            };
+            if option == "--delay" {
+                retry.delay = match units::parse_float(value) {
+                    Some(delay) if delay >= 0.0 => delay,
+                    _ => return Err(format!("{}: expected a duration, as in 2s", value)),
+                };
+            } else {
+                retry.backoff = match units::parse_float(value.trim_right_matches('x')) {
+                    Some(backoff) if backoff > 0.0 => backoff,
+                    _ => return Err(format!("{}: expected a factor, as in 2x", value)),
+                };
+            }
+        }
+        Ok(retry)
+    }
+
+    /// The time to wait after the given attempt, counting from one, has failed.
+    pub(crate) fn delay(&self, attempt: usize) -> Duration {
+        let seconds = self.delay * self.backoff.powi(attempt as i32 - 1);
+        Duration::new(seconds as u64, (seconds.fract() * 1e9) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        let retry = |attempts, delay, backoff| {
+            Ok(Retry {
+                attempts,
+                delay,
+                backoff,
+            })
+        };
+        assert_eq!(Retry::parse(&["5"]), retry(5, 0.0, 1.0));
+        assert_eq!(Retry::parse(&["3", "--delay", "250ms"]), retry(3, 0.25, 1.0));
+        assert_eq!(Retry::parse(&["5", "--delay", "2s", "--backoff", "2x"]), retry(5, 2.0, 2.0));
+        assert_eq!(Retry::parse(&["2", "--backoff", "1.5"]), retry(2, 0.0, 1.5));
+
+        let empty: [&str; 0] = [];
+        assert!(Retry::parse(&empty).is_err());
+        assert!(Retry::parse(&["0"]).is_err());
+        assert!(Retry::parse(&["five"]).is_err());
+        assert!(Retry::parse(&["5", "--delay"]).is_err());
+        assert!(Retry::parse(&["5", "--delay", "soon"]).is_err());
+        assert!(Retry::parse(&["5", "--backoff", "0x"]).is_err());
+        assert!(Retry::parse(&["5", "--jitter", "1s"]).is_err());
+    }
+
+    #[test]
+    fn delays() {
+        let retry = Retry::parse(&["5", "--delay", "500ms", "--backoff", "3x"]).unwrap();
+        assert_eq!(retry.delay(1), Duration::from_millis(500));
+        assert_eq!(retry.delay(2), Duration::from_millis(1500));
+        assert_eq!(retry.delay(3), Duration::from_millis(4500));
+    }
+}
@@ -0,0 +1,140 @@
+//! Sandbox mode, which is enabled with `ion --sandbox` or `shopt -s sandbox`, so that scripts
+//! produce the same output wherever and whenever they are executed. `$RANDOM` yields the same
+//! sequence of numbers, the current time is fixed, the user and host names are fixed, and the
+//! external commands which are named within the table given by `ion --sandbox=FILE` are replaced
+//! by fake commands.
+
+use super::Shell;
+use super::flags::SANDBOX;
+use fnv::FnvHashMap;
+use std::cell::Cell;
+use std::fs::File;
+use std::io::Read;
+use std::time::{SystemTime, UNIX_EPOCH};
+use sys;
+use types::{Identifier, Value};
+
+/// The seed of `$RANDOM` when the sandbox is enabled.
+pub(crate) const SEED: u32 = 0x2545_f491;
+
+/// The value of a variable which is otherwise obtained from the system, within the sandbox.
+pub(crate) fn fixed_variable(name: &str) -> Option<&'static str> {
+    match name {
+        "HOSTNAME" => Some("sandbox"),
+        "USER" => Some("ion"),
+        _ => None,
+    }
+}
+
+/// A xorshift generator of pseudo-random numbers between 0 and 32767, for `$RANDOM`.
+#[derive(Debug)]
+pub(crate) struct Random {
+    state: Cell<u32>,
+}
+
+impl Random {
+    /// Creates a generator which is seeded from the current time and the ID of the shell.
+    pub(crate) fn new() -> Random {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| {
+            time.subsec_nanos() ^ time.as_secs() as u32
+        });
+        let random = Random {
+            state: Cell::new(0),
+        };
+        random.seed(nanos ^ sys::getpid().unwrap_or(0).rotate_left(16));
+        random
+    }
+
+    /// Restarts the sequence of numbers, which is the same for every generator with the seed.
+    pub(crate) fn seed(&self, seed: u32) {
+        // Xorshift can never leave a state of zero.
+        self.state.set(if seed == 0 { SEED } else { seed });
+    }
+
+    pub(crate) fn next(&self) -> u16 {
+        let mut state = self.state.get();
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        self.state.set(state);
+        (state >> 16) as u16 & 0x7fff
+    }
+}
+
+/// Parses a table of fake commands, in which each line maps the name of a command to the Ion
+/// command which replaces it, as in `curl = cat tests/response.json`. Empty lines and lines
+/// which begin with `#` are ignored.
+fn parse_fake_commands(table: &str) -> Result<FnvHashMap<Identifier, Value>, String> {
+    let mut commands = FnvHashMap::default();
+    for (id, line) in table.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.find('=').map(|pos| (line[..pos].trim(), line[pos + 1..].trim())) {
+            Some((name, command)) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                commands.insert(name.into(), command.into());
+            }
+            _ => return Err(format!("line {}: expected `NAME = COMMAND`", id + 1)),
+        }
+    }
+    Ok(commands)
+}
+
+/// Reads the table of fake commands at the given path.
+pub(crate) fn read_fake_commands(path: &str) -> Result<FnvHashMap<Identifier, Value>, String> {
+    let mut table = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut table))
+        .map_err(|why| format!("{}: {}", path, why))?;
+    parse_fake_commands(&table).map_err(|why| format!("{}: {}", path, why))
+}
+
+/// Replaces external commands with fake commands within the sandbox.
+pub(crate) trait FakeCommands {
+    /// The Ion command which replaces the given command, if the sandbox is enabled and the
+    /// command is named within the table of fake commands.
+    fn fake_command(&self, command: &str) -> Option<Value>;
+}
+
+impl FakeCommands for Shell {
+    fn fake_command(&self, command: &str) -> Option<Value> {
+        if self.flags & SANDBOX == 0 {
+            return None;
+        }
+        self.fake_commands.get(command).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_numbers() {
+        let (first, second) = (Random::new(), Random::new());
+        first.seed(SEED);
+        second.seed(SEED);
+        let numbers = (0..100).map(|_| first.next()).collect::<Vec<u16>>();
+        assert_eq!(numbers, (0..100).map(|_| second.next()).collect::<Vec<u16>>());
+        assert!(numbers.iter().all(|&number| number <= 32767));
+        assert!(numbers.iter().any(|&number| number != numbers[0]));
+
+        first.seed(0);
+        assert_ne!(first.next(), 0);
+    }
+
+    #[test]
+    fn fake_commands() {
+        let table = "# responses\n\ncurl = echo '{\"status\": \"ok\"}'\n  hostname=echo web1\n";
+        let commands = parse_fake_commands(table).unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands["curl"], "echo '{\"status\": \"ok\"}'");
+        assert_eq!(commands["hostname"], "echo web1");
+        assert_eq!(
+            parse_fake_commands("curl\n"),
+            Err("line 1: expected `NAME = COMMAND`".into())
+        );
+        assert!(parse_fake_commands("git log = echo\n").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! Scoped blocks, such as `with umask 077` and `with cd /tmp`, which change a setting of the
+//! shell process for the statements within them. The setting which was replaced is pushed onto
+//! the scope stack of the flow control, and is restored when the block exits, including when
+//! the block is cut short by `break`, by an interrupt, by the shell exiting, or by a panic.
+
+use super::Shell;
+use super::errors::{ErrorKind, IonError};
+use super::flow_control::Scope;
+use parser::expand_string;
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use sys;
+
+/// A setting which was replaced by a `with` block.
+#[derive(Debug)]
+pub(crate) enum Saved {
+    Umask(u32),
+    Directory {
+        directory: PathBuf,
+        /// The value of `PWD`, which is set to the directory of the block.
+        pwd:       Option<OsString>,
+    },
+}
+
+impl Saved {
+    fn restore(self) -> Result<(), String> {
+        match self {
+            Saved::Umask(mask) => {
+                sys::umask(mask).map(|_| ()).map_err(|why| format!("umask: {}", why))
+            }
+            Saved::Directory { directory, pwd } => {
+                match pwd {
+                    Some(pwd) => env::set_var("PWD", pwd),
+                    None => env::remove_var("PWD"),
+                }
+                env::set_current_dir(&directory)
+                    .map_err(|why| format!("cd: {}: {}", directory.display(), why))
+            }
+        }
+    }
+}
+
+/// Parses a umask, which is given in octal, as in `077` or `0022`.
+fn parse_umask(mode: &str) -> Option<u32> {
+    if mode.is_empty() || mode.len() > 4 {
+        return None;
+    }
+    match u32::from_str_radix(mode, 8) {
+        Ok(mask) if mask <= 0o777 => Some(mask),
+        _ => None,
+    }
+}
+
+/// Changes the setting of the scope to the expanded value, returning the setting it replaced.
+fn enter(scope: &Scope, value: &str) -> Result<Saved, String> {
+    match *scope {
+        Scope::Umask(_) => {
+            let mask = parse_umask(value)
+                .ok_or_else(|| format!("umask: {}: expected an octal mode", value))?;
+            sys::umask(mask)
+                .map(Saved::Umask)
+                .map_err(|why| format!("umask: {}", why))
+        }
+        Scope::Directory(_) => {
+            let directory = env::current_dir().map_err(|why| format!("cd: {}", why))?;
+            env::set_current_dir(value).map_err(|why| format!("cd: {}: {}", value, why))?;
+            let pwd = env::var_os("PWD");
+            if let Ok(current) = env::current_dir() {
+                env::set_var("PWD", current);
+            }
+            Ok(Saved::Directory { directory, pwd })
+        }
+    }
+}
+
+/// Enters and leaves the scopes of `with` blocks.
+pub(crate) trait Scopes {
+    /// Expands the value of the scope and changes its setting, pushing the setting which it
+    /// replaced onto the scope stack. Returns `false` if the setting could not be changed.
+    fn enter_scope(&mut self, scope: &Scope) -> bool;
+
+    /// Restores the settings of the innermost scopes, until only `depth` scopes remain.
+    fn leave_scopes(&mut self, depth: usize);
+}
+
+impl Scopes for Shell {
+    fn enter_scope(&mut self, scope: &Scope) -> bool {
+        let value = expand_string(scope.value(), self, false).join(" ");
+        if self.expansion_failed() {
+            return false;
+        }
+        match enter(scope, &value) {
+            Ok(saved) => {
+                self.flow_control.scopes.push(saved);
+                true
+            }
+            Err(why) => {
+                self.report(IonError::new(ErrorKind::Scope, why).context("with"));
+                false
+            }
+        }
+    }
+
+    fn leave_scopes(&mut self, depth: usize) {
+        while self.flow_control.scopes.len() > depth {
+            let saved = self.flow_control.scopes.pop().unwrap();
+            if let Err(why) = saved.restore() {
+                let message = format!("could not restore the setting: {}", why);
+                self.report(IonError::new(ErrorKind::Scope, message).context("with"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn umasks() {
+        assert_eq!(parse_umask("077"), Some(0o077));
+        assert_eq!(parse_umask("0022"), Some(0o022));
+        assert_eq!(parse_umask("7"), Some(0o007));
+        assert_eq!(parse_umask("0777"), Some(0o777));
+        assert_eq!(parse_umask("1777"), None);
+        assert_eq!(parse_umask("089"), None);
+        assert_eq!(parse_umask("u=rwx"), None);
+        assert_eq!(parse_umask(""), None);
+    }
+}
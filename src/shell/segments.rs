@@ -0,0 +1,189 @@
+//! Asynchronous prompt segments, which are expanded with `${async::NAME}`, where `NAME` is a
+//! function whose output is slow to obtain, such as one which describes the current context of
+//! `kubectl`. Each expansion executes the function within a subshell in the background, unless
+//! it is already being executed, and expands to its most recent output at once, or to the
+//! `PROMPT_PLACEHOLDER` until it has finished for the first time. Within the prompt, segments
+//! are left as markers, which are filled each time that the prompt is drawn, so that the line
+//! editor may redraw the prompt once a segment has finished. The outputs of the subshells are
+//! collected on the shell's own thread, by reading their pipes without blocking. As the line
+//! editor only returns to the shell when a key is pressed, a segment which finishes while the
+//! user is idle is not shown until the next key press.
+
+use super::Shell;
+use super::errors::{ErrorKind, IonError};
+use super::status::*;
+use fnv::FnvHashMap;
+use parser::expand_string;
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::unix::io::AsRawFd;
+use sys;
+use types::Value;
+
+/// Encloses the name of each segment which is left within the prompt, to be filled later.
+const MARKER: char = '\0';
+
+/// The subshell which is executing the function of a segment.
+struct Child {
+    pid:    u32,
+    pipe:   File,
+    output: Vec<u8>,
+}
+
+impl Child {
+    /// Reads the output which is available without blocking, returning whether it has ended.
+    fn read(&mut self) -> bool {
+        let mut buffer = [0; 4096];
+        loop {
+            match self.pipe.read(&mut buffer) {
+                Ok(0) => return true,
+                Ok(read) => self.output.extend_from_slice(&buffer[..read]),
+                Err(ref why) if why.kind() == io::ErrorKind::Interrupted => (),
+                Err(ref why) if why.kind() == io::ErrorKind::WouldBlock => return false,
+                Err(_) => return true,
+            }
+        }
+    }
+}
+
+/// The state of a segment.
+#[derive(Default)]
+struct Segment {
+    /// The most recent output of the function, once it has finished.
+    output: Option<Value>,
+    /// The subshell which is executing the function, if it is being executed.
+    child:  Option<Child>,
+}
+
+/// The segments which have been expanded, along with the subshells which have closed their
+/// outputs but are yet to be reaped.
+#[derive(Default)]
+pub(crate) struct Segments {
+    segments:  RefCell<FnvHashMap<Value, Segment>>,
+    exited:    RefCell<Vec<u32>>,
+    in_prompt: Cell<bool>,
+}
+
+impl Segments {
+    /// Records the output of each segment which has finished, returning whether there were
+    /// any.
+    pub(crate) fn collect(&self) -> bool {
+        let mut finished = false;
+        let mut exited = self.exited.borrow_mut();
+        for segment in self.segments.borrow_mut().values_mut() {
+            if !segment.child.as_mut().map_or(false, Child::read) {
+                continue;
+            }
+            if let Some(child) = segment.child.take() {
+                let output = String::from_utf8_lossy(&child.output);
+                segment.output = Some(output.trim_right_matches('\n').into());
+                exited.push(child.pid);
+                finished = true;
+            }
+        }
+        exited.retain(|&pid| sys::try_waitpid(pid).map_or(false, |status| status.is_none()));
+        finished
+    }
+
+    /// Replaces each segment which was left within the prompt with its most recent output, or
+    /// with the placeholder if it has yet to finish.
+    pub(crate) fn fill(&self, prompt: &str, placeholder: &str) -> String {
+        let segments = self.segments.borrow();
+        let mut output = String::with_capacity(prompt.len());
+        for (id, part) in prompt.split(MARKER).enumerate() {
+            if id % 2 == 0 {
+                output.push_str(part);
+            } else {
+                match segments.get(part).and_then(|segment| segment.output.as_ref()) {
+                    Some(segment) => output.push_str(segment),
+                    None => output.push_str(placeholder),
+                }
+            }
+        }
+        output
+    }
+
+    fn is_running(&self, name: &str) -> bool {
+        self.segments.borrow().get(name).map_or(false, |segment| segment.child.is_some())
+    }
+}
+
+impl Shell {
+    /// Expands the prompt, leaving each segment as a marker to be filled by `Segments::fill`.
+    pub(crate) fn expand_prompt(&mut self, prompt: &str) -> String {
+        self.segments.in_prompt.set(true);
+        let prompt = expand_string(prompt, self, false).join(" ");
+        self.segments.in_prompt.set(false);
+        prompt
+    }
+
+    /// Expands the segment of the given function, starting its execution if it is not already
+    /// being executed.
+    pub(crate) fn async_segment(&mut self, name: &str) -> Option<Value> {
+        self.segments.collect();
+        let function = match self.functions.get(name) {
+            Some(function) => function.clone(),
+            None => {
+                let error = IonError::new(ErrorKind::Function, "function not found");
+                self.report(error.context(format!("async: {}", name)));
+                return None;
+            }
+        };
+
+        // The segments are not borrowed while the subshell is created, as the function may
+        // expand segments of its own.
+        if !self.segments.is_running(name) {
+            let owned_name: Value = name.into();
+            let started = self.fork_subshell(move |shell| {
+                shell.segments.in_prompt.set(false);
+                match function.execute(shell, &[owned_name.as_str()]) {
+                    Ok(()) => shell.previous_status,
+                    Err(_) => FAILURE,
+                }
+            });
+            if let Some((pid, pipe)) = started {
+                if let Err(why) = sys::set_nonblocking(pipe.as_raw_fd()) {
+                    let error = IonError::new(ErrorKind::Pipeline, why.to_string());
+                    self.report(error.context(format!("async: {}", name)));
+                }
+                let child = Child {
+                    pid,
+                    pipe,
+                    output: Vec::new(),
+                };
+                let mut segments = self.segments.segments.borrow_mut();
+                segments.entry(name.into()).or_insert_with(Segment::default).child = Some(child);
+            }
+        }
+
+        if self.segments.in_prompt.get() {
+            return Some(format!("{0}{1}{0}", MARKER, name));
+        }
+        let segments = self.segments.segments.borrow();
+        match segments.get(name).and_then(|segment| segment.output.clone()) {
+            Some(output) => Some(output),
+            None => self.variables.get_var("PROMPT_PLACEHOLDER"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filled_prompts() {
+        let segments = Segments::default();
+        segments.segments.borrow_mut().insert(
+            "kube".into(),
+            Segment {
+                output: Some("minikube".into()),
+                child:  None,
+            },
+        );
+        assert_eq!(segments.fill("~ [\0kube\0]# ", "…"), "~ [minikube]# ");
+        assert_eq!(segments.fill("~ [\0git\0]# ", "…"), "~ […]# ");
+        assert_eq!(segments.fill("~# ", "…"), "~# ");
+    }
+}
@@ -0,0 +1,126 @@
+//! Runs the tests of Ion scripts with `ion test`. Tests are the functions whose names begin with
+//! `test_`, which are defined by scripts whose names end with `_test.ion`. Each script is
+//! executed by a shell of its own, and each of its tests is then called within a subshell, with
+//! `errexit` enabled, so that a test fails at the first command which fails, and cannot affect
+//! the tests which follow it. Scripts are executed within the sandbox when `ion --sandbox test`
+//! is used.
+
+use super::Shell;
+use super::flags::{ERR_EXIT, SANDBOX};
+use super::library::IonLibrary;
+use super::status::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use types::Identifier;
+
+/// Collects the test scripts within a directory and its subdirectories, skipping those which
+/// are hidden.
+fn discover(directory: &Path, scripts: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(why) => {
+            eprintln!("ion: test: {}: {}", directory.display(), why);
+            return;
+        }
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        } else if path.is_dir() {
+            discover(&path, scripts);
+        } else if is_test_script(&name) {
+            scripts.push(path);
+        }
+    }
+}
+
+fn is_test_script(name: &str) -> bool { name.ends_with("_test.ion") }
+
+/// Runs the tests within the given scripts and directories, or within the current directory,
+/// and prints whether each of them passed, followed by the number which passed and failed. The
+/// shell which runs the tests passes its sandbox on to the shells which execute the scripts.
+pub(crate) fn run_tests(paths: &[String], runner: &Shell) -> i32 {
+    let mut scripts = Vec::new();
+    if paths.is_empty() {
+        discover(Path::new("."), &mut scripts);
+    }
+    for path in paths.iter().map(Path::new) {
+        if path.is_dir() {
+            discover(path, &mut scripts);
+        } else {
+            scripts.push(path.to_path_buf());
+        }
+    }
+    scripts.sort();
+
+    let (mut passed, mut failed) = (0, 0);
+    for script in &scripts {
+        let script = script.display().to_string();
+        let mut shell = Shell::new_bin();
+        if runner.flags & SANDBOX != 0 {
+            shell.set_option("sandbox", true);
+            shell.fake_commands = runner.fake_commands.clone();
+        }
+        if let Err(why) = shell.execute_script(&script) {
+            eprintln!("ion: test: {}: {}", script, why);
+            failed += 1;
+            continue;
+        }
+
+        let mut tests = shell
+            .functions
+            .keys()
+            .filter(|name| name.starts_with("test_"))
+            .cloned()
+            .collect::<Vec<Identifier>>();
+        tests.sort();
+        for test in tests {
+            let function = shell.functions[&test].clone();
+            let name: &str = &test;
+            let result = shell.subshell(|shell| {
+                shell.flags |= ERR_EXIT;
+                shell.location = None;
+                match function.execute(shell, &[name]) {
+                    Ok(()) => shell.previous_status,
+                    Err(why) => {
+                        shell.report(why.describe(name));
+                        FAILURE
+                    }
+                }
+            });
+            match result {
+                Some((_, true)) => {
+                    println!("test {}: {} ... ok", script, test);
+                    passed += 1;
+                }
+                // The output of a test is only shown if it failed.
+                Some((output, false)) => {
+                    println!("test {}: {} ... FAILED", script, test);
+                    for line in output.lines() {
+                        println!("    {}", line);
+                    }
+                    failed += 1;
+                }
+                None => failed += 1,
+            }
+        }
+    }
+
+    let result = if failed == 0 { "ok" } else { "FAILED" };
+    println!("\ntest result: {}. {} passed; {} failed", result, passed, failed);
+    if failed == 0 { SUCCESS } else { FAILURE }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripts() {
+        assert!(is_test_script("deploy_test.ion"));
+        assert!(!is_test_script("deploy.ion"));
+        assert!(!is_test_script("test.ion"));
+    }
+}
@@ -0,0 +1,1126 @@
+//! Translates bash scripts into Ion scripts, as a first step when migrating existing scripts.
+//! Common constructs, such as conditionals, loops, `case` blocks, functions, arrays, and
+//! parameter expansions, are rewritten into their Ion equivalents. Anything which cannot be
+//! translated is kept as it is, and is reported as a warning.
+
+use super::status::*;
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Block {
+    If,
+    /// A `while` or `until` loop, which may read its input from a file given to its end.
+    While,
+    Loop,
+    Case,
+    Function,
+    Group,
+}
+
+#[derive(Default)]
+struct Translator {
+    blocks:         Vec<Block>,
+    warnings:       Vec<String>,
+    line:           usize,
+    /// Set when a function was declared without its opening brace on the same line.
+    awaiting_brace: bool,
+    /// The delimiter of the heredoc whose body is currently being passed through.
+    heredoc:        Option<String>,
+}
+
+/// Reads the given bash script, or standard input if no script was given, and writes the
+/// translated Ion script to standard output. Warnings are written to standard error.
+pub(crate) fn translate_script(path: Option<&str>) -> i32 {
+    let mut script = String::new();
+    let result = match path {
+        Some(path) if path != "-" => File::open(path).and_then(|mut file| {
+            file.read_to_string(&mut script)
+        }),
+        _ => io::stdin().read_to_string(&mut script),
+    };
+
+    if let Err(why) = result {
+        eprintln!("ion: translate: unable to read script: {}", why);
+        return FAILURE;
+    }
+
+    let (output, warnings) = translate(&script);
+    let stdout = io::stdout();
+    let _ = stdout.lock().write_all(output.as_bytes());
+
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+    for warning in &warnings {
+        let _ = writeln!(stderr, "ion: translate: {}", warning);
+    }
+
+    SUCCESS
+}
+
+/// Translates a bash script, returning the Ion script along with a warning for each construct
+/// that could not be translated.
+pub(crate) fn translate(script: &str) -> (String, Vec<String>) {
+    let mut translator = Translator::default();
+    let mut output = String::with_capacity(script.len());
+
+    for (id, line) in script.lines().enumerate() {
+        translator.line = id + 1;
+        if let Some(delimiter) = translator.heredoc.take() {
+            output.push_str(line);
+            output.push('\n');
+            if line.trim() != delimiter {
+                translator.heredoc = Some(delimiter);
+            }
+            continue;
+        }
+
+        let trimmed = line.trim_left();
+        if id == 0 && trimmed.starts_with("#!") {
+            output.push_str("#!/usr/bin/env ion\n");
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        let (code, comment) = split_comment(trimmed);
+        let statements = translator.translate_line(code);
+        // Lines which only contained keywords such as `then` and `do` are dropped.
+        if statements.is_empty() && comment.is_none() && !code.trim().is_empty() {
+            continue;
+        }
+
+        output.push_str(indent);
+        output.push_str(&statements.join("; "));
+        if let Some(comment) = comment {
+            if !statements.is_empty() {
+                output.push(' ');
+            }
+            output.push_str(comment);
+        }
+        output.push('\n');
+    }
+
+    if !translator.blocks.is_empty() {
+        translator.warn("unexpected end of script: a block was not closed");
+    }
+
+    (output, translator.warnings)
+}
+
+impl Translator {
+    fn warn(&mut self, message: &str) {
+        let warning = format!("line {}: {}", self.line, message);
+        self.warnings.push(warning);
+    }
+
+    fn in_function(&self) -> bool { self.blocks.contains(&Block::Function) }
+
+    fn translate_line(&mut self, code: &str) -> Vec<String> {
+        let mut statements = Vec::new();
+        for statement in split_statements(code) {
+            let statement = statement.trim();
+            if !statement.is_empty() {
+                statements.extend(self.statement(statement));
+            }
+        }
+        statements
+    }
+
+    fn rest(&mut self, rest: &str) -> Vec<String> {
+        if rest.is_empty() {
+            Vec::new()
+        } else {
+            self.statement(rest)
+        }
+    }
+
+    fn statement(&mut self, statement: &str) -> Vec<String> {
+        if self.blocks.last() == Some(&Block::Case) {
+            if let Some(end) = case_pattern_end(statement) {
+                return self.case_arm(statement, end);
+            }
+        }
+
+        let (keyword, rest) = split_first_word(statement);
+        match keyword {
+            "then" | "do" => self.rest(rest),
+            "{" if self.awaiting_brace => {
+                self.awaiting_brace = false;
+                self.rest(rest)
+            }
+            "{" => {
+                self.warn("command groups are not supported, and were kept as they are");
+                self.blocks.push(Block::Group);
+                let mut statements = vec!["{".to_owned()];
+                statements.extend(self.rest(rest));
+                statements
+            }
+            "}" => match self.blocks.last().cloned() {
+                Some(Block::Group) => {
+                    self.blocks.pop();
+                    vec![statement.to_owned()]
+                }
+                _ => self.close(Block::Function, "}", rest),
+            },
+            "fi" => self.close(Block::If, keyword, rest),
+            "done" => self.done(rest),
+            "esac" => self.close(Block::Case, keyword, rest),
+            "else" => {
+                let mut statements = vec!["else".to_owned()];
+                statements.extend(self.rest(rest));
+                statements
+            }
+            "elif" => vec!["else if ".to_owned() + &self.words(rest, false)],
+            "if" => {
+                self.blocks.push(Block::If);
+                vec!["if ".to_owned() + &self.words(rest, false)]
+            }
+            "while" => {
+                self.blocks.push(Block::While);
+                vec!["while ".to_owned() + &self.words(rest, false)]
+            }
+            "until" => {
+                self.blocks.push(Block::While);
+                vec!["while not ".to_owned() + &self.words(rest, false)]
+            }
+            "for" => {
+                self.blocks.push(Block::Loop);
+                vec![self.for_loop(rest)]
+            }
+            "case" => {
+                self.blocks.push(Block::Case);
+                let subject = rest.trim_right_matches(" in").trim_right_matches("\tin").trim();
+                vec!["match ".to_owned() + &self.words(subject, false)]
+            }
+            "function" => {
+                let (name, rest) = match function_header(rest) {
+                    Some((name, rest)) => (name, rest),
+                    None => split_first_word(rest),
+                };
+                self.function(name, rest)
+            }
+            "local" | "declare" | "typeset" | "readonly" => self.declaration("let", rest),
+            "export" => self.declaration("export", rest),
+            "." | "source" => vec!["source ".to_owned() + &self.words(rest, false)],
+            _ => if let Some((name, rest)) = function_header(statement) {
+                self.function(name, rest)
+            } else if is_assignment(keyword) {
+                self.assignments(statement)
+            } else {
+                vec![self.words(statement, false)]
+            },
+        }
+    }
+
+    fn close(&mut self, block: Block, keyword: &str, rest: &str) -> Vec<String> {
+        if self.blocks.last() == Some(&block) {
+            self.blocks.pop();
+        } else {
+            self.warn(&format!("unexpected `{}`", keyword));
+        }
+        if !rest.is_empty() {
+            self.warn(&format!("`{}` after the end of a block was dropped", rest));
+        }
+        vec!["end".to_owned()]
+    }
+
+    /// Closes a loop, translating `done < file` to `end < file` for `while` loops, from whose
+    /// end Ion reads the input of the loop.
+    fn done(&mut self, rest: &str) -> Vec<String> {
+        if self.blocks.last() != Some(&Block::While) {
+            return self.close(Block::Loop, "done", rest);
+        }
+        if rest.starts_with('<') && !rest.starts_with("<<") {
+            let file = rest[1..].trim();
+            if !file.is_empty() && split_words(file).len() == 1 {
+                self.blocks.pop();
+                return vec!["end < ".to_owned() + &self.words(file, false)];
+            }
+        }
+        self.close(Block::While, "done", rest)
+    }
+
+    fn for_loop(&mut self, rest: &str) -> String {
+        if rest.starts_with("((") {
+            self.warn("C-style for loops are not supported, and were kept as they are");
+            return ["for ", rest].concat();
+        }
+
+        let (variable, rest) = split_first_word(rest);
+        match split_first_word(rest) {
+            ("in", values) => format!("for {} in {}", variable, self.words(values, true)),
+            _ => format!("for {} in @args[1..]", variable),
+        }
+    }
+
+    fn case_arm(&mut self, statement: &str, end: usize) -> Vec<String> {
+        let patterns = statement[..end].trim().trim_left_matches('(');
+        let patterns = patterns.split('|').map(str::trim).collect::<Vec<&str>>();
+        let mut values = Vec::with_capacity(patterns.len());
+        for pattern in patterns {
+            if pattern == "*" {
+                values.push("_".to_owned());
+                continue;
+            }
+            if has_glob(pattern) {
+                let warning = format!("the case pattern `{}` is matched literally", pattern);
+                self.warn(&warning);
+            }
+            values.push(self.words(pattern, false));
+        }
+
+        let case = if values.len() == 1 {
+            ["case ", &values[0]].concat()
+        } else if values.iter().any(|value| value == "_") {
+            "case _".to_owned()
+        } else {
+            format!("case [{}]", values.join(" "))
+        };
+
+        let mut statements = vec![case];
+        statements.extend(self.rest(statement[end + 1..].trim()));
+        statements
+    }
+
+    fn function(&mut self, name: &str, rest: &str) -> Vec<String> {
+        self.blocks.push(Block::Function);
+        let rest = rest.trim();
+        let mut statements = vec!["fn ".to_owned() + name];
+        if rest.starts_with('{') {
+            statements.extend(self.rest(rest[1..].trim()));
+        } else {
+            self.awaiting_brace = true;
+        }
+        statements
+    }
+
+    /// Translates `local`, `declare`, `readonly`, and `export` declarations.
+    fn declaration(&mut self, keyword: &str, rest: &str) -> Vec<String> {
+        let mut statements = Vec::new();
+        for word in split_words(rest) {
+            if word.starts_with('-') {
+                if word.contains('A') {
+                    self.warn("associative arrays are not supported, and were declared as arrays");
+                }
+                continue;
+            }
+
+            if is_assignment(word) {
+                statements.push(self.assignment(keyword, word));
+            } else if keyword == "export" {
+                statements.push(["export ", word].concat());
+            } else {
+                statements.push(format!("let {} = \"\"", word));
+            }
+        }
+        statements
+    }
+
+    fn assignments(&mut self, statement: &str) -> Vec<String> {
+        let words = split_words(statement);
+        if words.iter().all(|word| is_assignment(word)) {
+            words.into_iter().map(|word| self.assignment("let", word)).collect()
+        } else {
+            // Assignments which prefix a command only apply to that command.
+            vec!["env ".to_owned() + &self.words(statement, false)]
+        }
+    }
+
+    fn assignment(&mut self, keyword: &str, word: &str) -> String {
+        let equals = word.find('=').unwrap();
+        let (name, append) = match word[..equals].find('+') {
+            Some(plus) => (&word[..plus], true),
+            None => (&word[..equals], false),
+        };
+
+        if name.contains('[') {
+            self.warn("assignments to array elements are not supported, and were kept as they are");
+            return word.to_owned();
+        }
+
+        let value = &word[equals + 1..];
+        if value.starts_with('(') && value.ends_with(')') {
+            let elements = self.words(&value[1..value.len() - 1], true);
+            if append {
+                format!("{} {} = [@{} {}]", keyword, name, name, elements)
+            } else {
+                format!("{} {} = [{}]", keyword, name, elements)
+            }
+        } else if value.is_empty() {
+            format!("{} {} = \"\"", keyword, name)
+        } else if append {
+            format!("{} {} = \"${}\"{}", keyword, name, name, self.words(value, false))
+        } else {
+            format!("{} {} = {}", keyword, name, self.words(value, false))
+        }
+    }
+
+    /// Translates the expansions within a command. If `split_commands` is set, unquoted command
+    /// substitutions will be expanded as arrays, as bash would split their output into words.
+    fn words(&mut self, input: &str, split_commands: bool) -> String {
+        let chars = input.chars().collect::<Vec<char>>();
+        let mut output = String::with_capacity(input.len());
+        let mut command_start = true;
+        let mut dquote = false;
+        let mut id = 0;
+
+        while id < chars.len() {
+            let character = chars[id];
+            let next = chars.get(id + 1).cloned();
+            let word_start = id == 0 || chars[id - 1].is_whitespace();
+            match character {
+                '\\' => {
+                    output.push(character);
+                    if let Some(next) = next {
+                        output.push(next);
+                        id += 1;
+                    }
+                }
+                '\'' if !dquote => {
+                    let end = find_char(&chars, id + 1, '\'').unwrap_or(chars.len() - 1);
+                    output.extend(&chars[id..end + 1]);
+                    id = end;
+                }
+                '"' if !dquote && quoted_array(&chars[id..]).is_some() => {
+                    // Each element of `"${array[@]}"` remains a word of its own, which is
+                    // what a bare array expansion does in Ion.
+                    let (array, end) = quoted_array(&chars[id..]).unwrap();
+                    output.push_str(&array);
+                    id += end;
+                    command_start = false;
+                }
+                '"' => {
+                    dquote = !dquote;
+                    output.push(character);
+                }
+                '`' => {
+                    id = match find_char(&chars, id + 1, '`') {
+                        Some(end) => {
+                            let command = chars[id + 1..end].iter().collect::<String>();
+                            output.push_str("$(");
+                            output.push_str(&self.words(&command, false));
+                            output.push(')');
+                            end
+                        }
+                        None => self.unclosed(&chars, id, &mut output),
+                    };
+                    command_start = false;
+                }
+                '$' => {
+                    id = self.dollar(&chars, id, dquote, split_commands, &mut output);
+                    command_start = false;
+                }
+                '[' if !dquote && command_start && word_start && next == Some('[') => {
+                    let end = find_test_end(&chars, id + 2, "]]");
+                    let expression = chars[id + 2..end].iter().collect::<String>();
+                    output.push_str(&self.test(&expression));
+                    id = end + 1;
+                    command_start = false;
+                }
+                '[' if !dquote && command_start && word_start
+                    && next.map_or(false, char::is_whitespace) =>
+                {
+                    let end = find_test_end(&chars, id + 1, "]");
+                    let expression = chars[id + 1..end].iter().collect::<String>();
+                    output.push_str("test ");
+                    output.push_str(&self.test_operands(expression.trim()));
+                    id = end;
+                    command_start = false;
+                }
+                '!' if !dquote && command_start && next.map_or(true, char::is_whitespace) => {
+                    output.push_str("not");
+                }
+                '<' if !dquote && next == Some('<') && chars.get(id + 2) != Some(&'<') => {
+                    let delimiter = chars[id + 2..]
+                        .iter()
+                        .skip_while(|&&c| c == '-' || c.is_whitespace())
+                        .take_while(|&&c| !c.is_whitespace())
+                        .filter(|&&c| c != '\'' && c != '"')
+                        .collect::<String>();
+                    self.heredoc = Some(delimiter);
+                    output.extend(&chars[id..]);
+                    break;
+                }
+                '&' | '|' | ';' | '(' if !dquote => {
+                    output.push(character);
+                    command_start = true;
+                }
+                _ => {
+                    output.push(character);
+                    if !character.is_whitespace() {
+                        command_start = false;
+                    }
+                }
+            }
+            id += 1;
+        }
+
+        output
+    }
+
+    /// Translates an expansion that begins with `$`, returning the index of the last character
+    /// that was consumed.
+    fn dollar(
+        &mut self,
+        chars: &[char],
+        id: usize,
+        dquote: bool,
+        split_commands: bool,
+        output: &mut String,
+    ) -> usize {
+        match chars.get(id + 1).cloned() {
+            Some('(') if chars.get(id + 2) == Some(&'(') => {
+                // Arithmetic expansions share the same syntax.
+                let end = find_closing(chars, id + 1).unwrap_or(chars.len() - 1);
+                output.extend(&chars[id..end + 1]);
+                end
+            }
+            Some('(') => {
+                let end = match find_closing(chars, id + 1) {
+                    Some(end) => end,
+                    None => return self.unclosed(chars, id, output),
+                };
+                let command = chars[id + 2..end].iter().collect::<String>();
+                output.push_str(if split_commands && !dquote { "@(" } else { "$(" });
+                output.push_str(&self.words(&command, false));
+                output.push(')');
+                end
+            }
+            Some('{') => {
+                let end = match find_closing(chars, id + 1) {
+                    Some(end) => end,
+                    None => return self.unclosed(chars, id, output),
+                };
+                let parameter = chars[id + 2..end].iter().collect::<String>();
+                let expansion = self.parameter(&parameter);
+                output.push_str(&expansion);
+                end
+            }
+            Some(digit) if digit.is_digit(10) => {
+                let positional = self.positional(&digit.to_string());
+                output.push_str(&positional);
+                id + 1
+            }
+            Some('@') | Some('*') => {
+                output.push_str("@args[1..]");
+                id + 1
+            }
+            Some('$') => {
+                output.push_str("$PID");
+                id + 1
+            }
+            Some('#') => {
+                self.warn("`$#` is not supported; the `$len(@args)` method may be used instead");
+                output.push_str("$#");
+                id + 1
+            }
+            Some('!') => {
+                self.warn("`$!` is not supported, and was kept as it is");
+                output.push_str("$!");
+                id + 1
+            }
+            Some(character) if character.is_alphanumeric() || "_?".contains(character) => {
+                let end = chars[id + 1..]
+                    .iter()
+                    .position(|&c| !(c.is_alphanumeric() || c == '_'))
+                    .map_or(chars.len(), |position| id + 1 + position);
+                let end = if character == '?' { id + 2 } else { end };
+                output.extend(&chars[id..end]);
+                end - 1
+            }
+            _ => {
+                output.push('$');
+                id
+            }
+        }
+    }
+
+    /// Copies an expansion which was not closed to the output as it is, along with the rest of
+    /// the input, returning the index of the last character.
+    fn unclosed(&mut self, chars: &[char], id: usize, output: &mut String) -> usize {
+        self.warn("an expansion was not closed, and was kept as it is");
+        output.extend(&chars[id..]);
+        chars.len() - 1
+    }
+
+    fn positional(&mut self, number: &str) -> String {
+        if self.in_function() {
+            self.warn(&format!(
+                "`${}` within a function should be declared as an argument of the function",
+                number
+            ));
+            ["$", number].concat()
+        } else {
+            format!("@args[{}]", number)
+        }
+    }
+
+    /// Translates the contents of a `${...}` parameter expansion.
+    fn parameter(&mut self, parameter: &str) -> String {
+        let original = || format!("${{{}}}", parameter);
+
+        if parameter.len() > 1 && parameter.starts_with('#') {
+            let name = &parameter[1..];
+            return if name.ends_with("[@]") || name.ends_with("[*]") {
+                format!("$len(@{})", &name[..name.len() - 3])
+            } else {
+                format!("$len({})", name)
+            };
+        }
+
+        let name_end = parameter
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or_else(|| parameter.len());
+        let (name, rest) = parameter.split_at(name_end);
+
+        if name.is_empty() || (name.chars().all(|c| c.is_digit(10)) && !rest.is_empty()) {
+            self.warn(&format!("the expansion `{}` was kept as it is", original()));
+            return original();
+        } else if name.chars().all(|c| c.is_digit(10)) {
+            return self.positional(name);
+        } else if rest.is_empty() {
+            return original();
+        }
+
+        if rest.starts_with('[') && rest.ends_with(']') {
+            let index = &rest[1..rest.len() - 1];
+            return if index == "@" || index == "*" {
+                ["@", name].concat()
+            } else {
+                format!("@{}[{}]", name, self.words(index, false))
+            };
+        }
+
+        let translated = if rest.starts_with("%%") {
+            self.remove_affix(name, &rest[2..], "^(.*?)", "$", false)
+        } else if rest.starts_with('%') {
+            self.remove_affix(name, &rest[1..], "^(.*)", "$", true)
+        } else if rest.starts_with("##") {
+            self.remove_affix(name, &rest[2..], "^", "(.*)$", false)
+        } else if rest.starts_with('#') {
+            self.remove_affix(name, &rest[1..], "^", "(.*)$", true)
+        } else if rest.starts_with("//") {
+            self.substitute(name, &rest[2..], true)
+        } else if rest.starts_with('/') {
+            self.substitute(name, &rest[1..], false)
+        } else if rest == "^^" {
+            Some(format!("$to_uppercase({})", name))
+        } else if rest == ",," {
+            Some(format!("$to_lowercase({})", name))
+        } else if rest.starts_with(':') {
+            slice(name, &rest[1..])
+        } else {
+            None
+        };
+
+        translated.unwrap_or_else(|| {
+            self.warn(&format!("the expansion `{}` was kept as it is", original()));
+            original()
+        })
+    }
+
+    /// Translates `${name%pattern}` and its variants into a regular expression replacement.
+    fn remove_affix(
+        &mut self,
+        name: &str,
+        pattern: &str,
+        before: &str,
+        after: &str,
+        shortest: bool,
+    ) -> Option<String> {
+        glob_to_regex(pattern, shortest).map(|regex| {
+            format!("$regex_replace({}, '{}{}{}' '$1')", name, before, regex, after)
+        })
+    }
+
+    /// Translates `${name/pattern/replacement}` and `${name//pattern/replacement}`.
+    fn substitute(&mut self, name: &str, rest: &str, all: bool) -> Option<String> {
+        let (pattern, replacement) = match rest.find('/') {
+            Some(position) => (&rest[..position], &rest[position + 1..]),
+            None => (rest, ""),
+        };
+
+        if has_glob(pattern) || is_dynamic(pattern) || is_dynamic(replacement) {
+            return None;
+        }
+
+        Some(if all {
+            format!("$replace({}, '{}' '{}')", name, pattern, replacement)
+        } else {
+            format!("$replacen({}, '{}' '{}' 1)", name, pattern, replacement)
+        })
+    }
+
+    /// Translates the contents of a `[[ ... ]]` test into `test` commands.
+    fn test(&mut self, expression: &str) -> String {
+        let mut output = String::new();
+        let mut start = 0;
+        let bytes = expression.as_bytes();
+        let mut id = 0;
+        let mut quote = None;
+        while id < bytes.len() {
+            match bytes[id] {
+                b'\\' => id += 1,
+                b'\'' | b'"' if quote.is_none() => quote = Some(bytes[id]),
+                character if quote == Some(character) => quote = None,
+                b'&' | b'|' if quote.is_none() && bytes.get(id + 1) == Some(&bytes[id]) => {
+                    output.push_str(&self.test_command(&expression[start..id]));
+                    output.push_str(if bytes[id] == b'&' { " && " } else { " || " });
+                    start = id + 2;
+                    id += 1;
+                }
+                b'(' | b')' if quote.is_none() => {
+                    self.warn("parentheses within `[[ ]]` are not supported");
+                }
+                _ => (),
+            }
+            id += 1;
+        }
+        output.push_str(&self.test_command(&expression[start..]));
+        output
+    }
+
+    fn test_command(&mut self, expression: &str) -> String {
+        let expression = expression.trim();
+        if expression.starts_with("! ") {
+            return ["not ", &self.test_command(&expression[2..])].concat();
+        }
+
+        let words = split_words(expression);
+        if words.len() == 3 && words[1] == "=~" {
+            let subject = self.words(words[0], false);
+            return format!("matches {} {}", subject, words[2]);
+        }
+
+        ["test ", &self.test_operands(expression)].concat()
+    }
+
+    fn test_operands(&mut self, expression: &str) -> String {
+        let mut operands = Vec::new();
+        for word in split_words(expression) {
+            match word {
+                "==" => operands.push("=".to_owned()),
+                "<" | ">" | "=~" => {
+                    self.warn(&format!("the `{}` test operator is not supported", word));
+                    operands.push(word.to_owned());
+                }
+                _ => operands.push(self.words(word, false)),
+            }
+        }
+        operands.join(" ")
+    }
+}
+
+/// Splits the trailing comment from a line of code.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let bytes = line.as_bytes();
+    let mut quote = None;
+    let mut id = 0;
+    while id < bytes.len() {
+        match bytes[id] {
+            b'\\' => id += 1,
+            b'\'' | b'"' if quote.is_none() => quote = Some(bytes[id]),
+            character if quote == Some(character) => quote = None,
+            b'#' if quote.is_none() && (id == 0 || (bytes[id - 1] as char).is_whitespace()) => {
+                return (&line[..id], Some(&line[id..]));
+            }
+            _ => (),
+        }
+        id += 1;
+    }
+    (line, None)
+}
+
+/// Splits a line into statements on each `;` or `;;` which is not quoted or nested.
+fn split_statements(line: &str) -> Vec<&str> {
+    let bytes = line.as_bytes();
+    let mut statements = Vec::new();
+    let mut quote = None;
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut id = 0;
+    while id < bytes.len() {
+        match bytes[id] {
+            b'\\' => id += 1,
+            b'\'' | b'"' | b'`' if quote.is_none() => quote = Some(bytes[id]),
+            character if quote == Some(character) => quote = None,
+            _ if quote == Some(b'\'') => (),
+            b'(' | b'{' => depth += 1,
+            b')' | b'}' => depth = depth.saturating_sub(1),
+            b';' if quote.is_none() && depth == 0 => {
+                statements.push(&line[start..id]);
+                if bytes.get(id + 1) == Some(&b';') {
+                    id += 1;
+                }
+                start = id + 1;
+            }
+            _ => (),
+        }
+        id += 1;
+    }
+    statements.push(&line[start..]);
+    statements
+}
+
+/// Splits a statement into words on whitespace which is not quoted or nested.
+fn split_words(statement: &str) -> Vec<&str> {
+    let bytes = statement.as_bytes();
+    let mut words = Vec::new();
+    let mut quote = None;
+    let mut depth = 0usize;
+    let mut start = None;
+    let mut id = 0;
+    while id < bytes.len() {
+        let character = bytes[id];
+        if start.is_none() && !(character as char).is_whitespace() {
+            start = Some(id);
+        }
+        match character {
+            b'\\' => id += 1,
+            b'\'' | b'"' | b'`' if quote.is_none() => quote = Some(character),
+            _ if quote == Some(character) => quote = None,
+            _ if quote.is_some() => (),
+            b'(' | b'{' => depth += 1,
+            b')' | b'}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && (character as char).is_whitespace() => {
+                if let Some(start) = start.take() {
+                    words.push(&statement[start..id]);
+                }
+            }
+            _ => (),
+        }
+        id += 1;
+    }
+    if let Some(start) = start {
+        words.push(&statement[start..]);
+    }
+    words
+}
+
+fn split_first_word(statement: &str) -> (&str, &str) {
+    let statement = statement.trim();
+    match statement.find(char::is_whitespace) {
+        Some(position) => (&statement[..position], statement[position..].trim()),
+        None => (statement, ""),
+    }
+}
+
+/// If the statement is a pattern of a case arm, such as `a|b)`, returns the position of the
+/// closing parenthesis.
+fn case_pattern_end(statement: &str) -> Option<usize> {
+    let bytes = statement.as_bytes();
+    let mut quote = None;
+    let mut id = if bytes.first() == Some(&b'(') { 1 } else { 0 };
+    while id < bytes.len() {
+        match bytes[id] {
+            b'\\' => id += 1,
+            b'\'' | b'"' if quote.is_none() => quote = Some(bytes[id]),
+            character if quote == Some(character) => quote = None,
+            _ if quote.is_some() => (),
+            b')' => return Some(id),
+            b'(' | b'$' | b'`' | b'=' => return None,
+            character if (character as char).is_whitespace() => {
+                // Whitespace may only surround the `|` separator of each pattern.
+                let rest = statement[id..].trim_left();
+                let previous = statement[..id].trim_right();
+                if !(rest.starts_with('|') || rest.starts_with(')') || previous.ends_with('|')) {
+                    return None;
+                }
+            }
+            _ => (),
+        }
+        id += 1;
+    }
+    None
+}
+
+/// If the statement begins with a function declaration, such as `name() {`, returns the name
+/// of the function, and what follows the declaration.
+fn function_header(statement: &str) -> Option<(&str, &str)> {
+    let name_end = statement
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == ':' || c == '.'))?;
+    let name = &statement[..name_end];
+    let rest = statement[name_end..].trim_left();
+    if name.is_empty() || !rest.starts_with('(') {
+        return None;
+    }
+    let rest = rest[1..].trim_left();
+    if rest.starts_with(')') {
+        Some((name, &rest[1..]))
+    } else {
+        None
+    }
+}
+
+fn is_assignment(word: &str) -> bool {
+    match word.find('=') {
+        Some(position) => {
+            let name = word[..position].trim_right_matches('+');
+            let name = name.split('[').next().unwrap_or("");
+            !name.is_empty() && !name.starts_with(|c: char| c.is_digit(10))
+                && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+/// Translates a double-quoted word which only expands every element of an array, such as
+/// `"${array[@]}"` or `"$@"`, returning the array along with the index of its closing quote.
+fn quoted_array(chars: &[char]) -> Option<(String, usize)> {
+    let end = find_char(chars, 1, '"')?;
+    if chars.get(end + 1).map_or(false, |c| !c.is_whitespace() && !";&|)".contains(*c)) {
+        return None;
+    }
+    let word = chars[1..end].iter().collect::<String>();
+    if word == "$@" {
+        return Some(("@args[1..]".into(), end));
+    }
+    if !word.starts_with("${") || !word.ends_with("[@]}") {
+        return None;
+    }
+    let name = &word[2..word.len() - 4];
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((["@", name].concat(), end))
+}
+
+fn has_glob(pattern: &str) -> bool { pattern.contains(|c| c == '*' || c == '?' || c == '[') }
+
+fn is_dynamic(pattern: &str) -> bool {
+    pattern.contains(|c| c == '$' || c == '`' || c == '\'' || c == '"')
+}
+
+/// Converts a glob pattern into an equivalent regular expression.
+fn glob_to_regex(pattern: &str, shortest: bool) -> Option<String> {
+    if is_dynamic(pattern) {
+        return None;
+    }
+
+    let mut regex = String::with_capacity(pattern.len() * 2);
+    let mut chars = pattern.chars();
+    while let Some(character) = chars.next() {
+        match character {
+            '*' => regex.push_str(if shortest { ".*?" } else { ".*" }),
+            '?' => regex.push('.'),
+            '[' => {
+                regex.push('[');
+                let mut class = chars.by_ref().take_while(|&c| c != ']').peekable();
+                if class.peek() == Some(&'!') {
+                    class.next();
+                    regex.push('^');
+                }
+                regex.extend(class);
+                regex.push(']');
+            }
+            '\\' => if let Some(escaped) = chars.next() {
+                regex.push('\\');
+                regex.push(escaped);
+            },
+            '.' | '+' | '(' | ')' | '|' | '{' | '}' | '^' | '$' => {
+                regex.push('\\');
+                regex.push(character);
+            }
+            _ => regex.push(character),
+        }
+    }
+    Some(regex)
+}
+
+/// Translates the `${name:offset:length}` substring expansion into a slice.
+fn slice(name: &str, range: &str) -> Option<String> {
+    let mut parts = range.splitn(2, ':');
+    let offset = parts.next()?.trim().parse::<usize>().ok()?;
+    match parts.next() {
+        Some(length) => {
+            let length = length.trim().parse::<usize>().ok()?;
+            Some(format!("${}[{}..{}]", name, offset, offset + length))
+        }
+        None => Some(format!("${}[{}..]", name, offset)),
+    }
+}
+
+/// Returns the position of the given character, if it is found.
+fn find_char(chars: &[char], start: usize, target: char) -> Option<usize> {
+    let mut id = start;
+    while id < chars.len() {
+        match chars[id] {
+            '\\' if target != '\'' => id += 1,
+            character if character == target => return Some(id),
+            _ => (),
+        }
+        id += 1;
+    }
+    None
+}
+
+/// Returns the position of the bracket which closes the bracket at the given position, if it is
+/// found.
+fn find_closing(chars: &[char], start: usize) -> Option<usize> {
+    let (open, close) = match chars[start] {
+        '(' => ('(', ')'),
+        _ => ('{', '}'),
+    };
+    let mut depth = 0;
+    let mut quote = None;
+    let mut id = start;
+    while id < chars.len() {
+        match chars[id] {
+            '\\' => id += 1,
+            '\'' | '"' if quote.is_none() => quote = Some(chars[id]),
+            character if quote == Some(character) => quote = None,
+            _ if quote.is_some() => (),
+            character if character == open => depth += 1,
+            character if character == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(id);
+                }
+            }
+            _ => (),
+        }
+        id += 1;
+    }
+    None
+}
+
+/// Returns the position of the `]` or `]]` which terminates a test, or the end of the input.
+fn find_test_end(chars: &[char], start: usize, terminator: &str) -> usize {
+    let terminator = terminator.chars().collect::<Vec<char>>();
+    let mut quote = None;
+    let mut id = start;
+    while id < chars.len() {
+        match chars[id] {
+            '\\' => id += 1,
+            '\'' | '"' if quote.is_none() => quote = Some(chars[id]),
+            character if quote == Some(character) => quote = None,
+            _ if quote.is_some() => (),
+            _ if chars[id..].starts_with(&terminator) && chars[id - 1].is_whitespace() => {
+                return id;
+            }
+            _ => (),
+        }
+        id += 1;
+    }
+    chars.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_translates(bash: &str, ion: &str) {
+        let (output, warnings) = translate(bash);
+        assert_eq!(output, ion);
+        assert!(warnings.is_empty(), "unexpected warnings: {:?}", warnings);
+    }
+
+    #[test]
+    fn conditionals() {
+        assert_translates(
+            "if [[ $a == b && -n $c ]]; then\n    echo yes\nelif [ -f file ]\nthen\n    echo \
+             file\nelse\n    echo no\nfi\n",
+            "if test $a = b && test -n $c\n    echo yes\nelse if test -f file\n    echo \
+             file\nelse\n    echo no\nend\n",
+        );
+        assert_translates(
+            "if ! [[ $x =~ ^[0-9]+$ ]]; then exit 1; fi\n",
+            "if not matches $x ^[0-9]+$; exit 1; end\n",
+        );
+    }
+
+    #[test]
+    fn loops() {
+        assert_translates(
+            "for file in $(ls); do\n    echo \"$file\"\ndone\n",
+            "for file in @(ls)\n    echo \"$file\"\nend\n",
+        );
+        assert_translates(
+            "until false; do break; done\n",
+            "while not false; break; end\n",
+        );
+        assert_translates(
+            "while read line; do\n    echo \"$line\"\ndone < \"$log\"\n",
+            "while read line\n    echo \"$line\"\nend < \"$log\"\n",
+        );
+        let (output, warnings) = translate("for x in a; do echo $x; done < file\n");
+        assert_eq!(output, "for x in a; echo $x; end\n");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn cases() {
+        assert_translates(
+            "case $1 in\n    start|run) echo starting ;;\n    *)\n        echo unknown\n        \
+             ;;\nesac\n",
+            "match @args[1]\n    case [start run]; echo starting\n    case _\n        echo \
+             unknown\nend\n",
+        );
+    }
+
+    #[test]
+    fn functions() {
+        assert_translates(
+            "greet() {\n    echo hello\n}\nfunction bye\n{\n    echo bye\n}\n",
+            "fn greet\n    echo hello\nend\nfn bye\n    echo bye\nend\n",
+        );
+        let (_, warnings) = translate("f() {\n    echo $1\n}\n");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn variables() {
+        assert_translates(
+            "name=world\nlocal x=\"a b\" y\nexport PATH=$PATH:/bin\nFOO=1 cmd\n",
+            "let name = world\nlet x = \"a b\"; let y = \"\"\nexport PATH = $PATH:/bin\nenv \
+             FOO=1 cmd\n",
+        );
+        assert_translates(
+            "arr=(one two)\narr+=(three)\necho ${arr[@]} ${arr[0]} ${#arr[@]} ${#name}\n",
+            "let arr = [one two]\nlet arr = [@arr three]\necho @arr @arr[0] $len(@arr) \
+             $len(name)\n",
+        );
+        assert_translates(
+            "cp \"${files[@]}\" \"$@\" \"${files[*]}\" \"${files[@]}\"x dir\n",
+            "cp @files @args[1..] \"@files\" \"@files\"x dir\n",
+        );
+    }
+
+    #[test]
+    fn parameter_expansions() {
+        assert_translates(
+            "echo ${file%.*} ${file%%/*} ${file#*/} ${file##*/}\n",
+            "echo $regex_replace(file, '^(.*)\\..*?$' '$1') $regex_replace(file, \
+             '^(.*?)/.*$' '$1') $regex_replace(file, '^.*?/(.*)$' '$1') \
+             $regex_replace(file, '^.*/(.*)$' '$1')\n",
+        );
+        assert_translates(
+            "echo ${a/x/y} ${a//x/y} ${a^^} ${a:1:3} `date` $$\n",
+            "echo $replacen(a, 'x' 'y' 1) $replace(a, 'x' 'y') $to_uppercase(a) $a[1..4] \
+             $(date) $PID\n",
+        );
+        let (output, warnings) = translate("echo ${a:-default}\n");
+        assert_eq!(output, "echo ${a:-default}\n");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn unclosed_expansions() {
+        for &bash in &["echo `\n", "echo $(date\n", "echo ${name\n"] {
+            let (output, warnings) = translate(bash);
+            assert_eq!(output, bash);
+            assert_eq!(warnings.len(), 1);
+        }
+        let (output, warnings) = translate("x=$(\n");
+        assert_eq!(output, "let x = $(\n");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn comments_and_heredocs() {
+        assert_translates(
+            "#!/bin/bash\n# comment\nx=1 # trailing\ncat <<EOF\nx=$x\nEOF\n",
+            "#!/usr/bin/env ion\n# comment\nlet x = 1 # trailing\ncat <<EOF\nx=$x\nEOF\n",
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Contains the table of handlers that have been registered with the `trap` builtin.
+//!
+//! Handlers are never executed from within a signal handler. Signals are recorded as pending,
+//! and their handlers are executed by the shell once it reaches a safe point between statements.
+
+use sys;
+
+/// Executed right before the shell exits.
+pub(crate) const EXIT: usize = 0;
+/// Executed after a statement has failed with a non-zero exit status.
+pub(crate) const ERR: usize = 1;
+pub(crate) const HUP: usize = 2;
+pub(crate) const INT: usize = 3;
+pub(crate) const TERM: usize = 4;
+
+/// The names of each event that can be trapped, indexed by their event ID.
+pub(crate) const EVENTS: [&str; 5] = ["EXIT", "ERR", "HUP", "INT", "TERM"];
+
+/// Maps a signal that was received by the shell to the event that it triggers.
+pub(crate) fn from_signal(signal: i32) -> Option<usize> {
+    match signal {
+        sys::SIGHUP => Some(HUP),
+        sys::SIGINT => Some(INT),
+        sys::SIGTERM => Some(TERM),
+        _ => None,
+    }
+}
+
+/// Parses an event name as it was supplied to the `trap` builtin. Names are case-insensitive,
+/// may optionally be prefixed with `SIG`, and may be given as a signal number.
+pub(crate) fn parse_event(name: &str) -> Option<usize> {
+    let upper = name.to_uppercase();
+    let upper = if upper.starts_with("SIG") { &upper[3..] } else { &upper[..] };
+    if let Some(event) = EVENTS.iter().position(|&event| event == upper) {
+        return Some(event);
+    }
+    match upper.parse::<i32>() {
+        Ok(0) => Some(EXIT),
+        Ok(signal) => from_signal(signal),
+        Err(_) => None,
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Traps {
+    handlers: [Option<String>; 5],
+    /// Each bit marks a handler that is currently executing, so that a handler may not
+    /// re-trigger itself.
+    active: u8,
+}
+
+impl Traps {
+    pub(crate) fn set(&mut self, event: usize, handler: String) {
+        self.handlers[event] = Some(handler);
+    }
+
+    pub(crate) fn reset(&mut self, event: usize) { self.handlers[event] = None; }
+
+    pub(crate) fn get(&self, event: usize) -> Option<&String> { self.handlers[event].as_ref() }
+
+    /// Marks the handler for the given event as executing, and returns that handler, if it
+    /// exists and is not already executing.
+    pub(crate) fn begin(&mut self, event: usize) -> Option<String> {
+        if self.active & (1 << event) != 0 {
+            return None;
+        }
+        let handler = self.handlers[event].clone();
+        if handler.is_some() {
+            self.active |= 1 << event;
+        }
+        handler
+    }
+
+    /// Marks the handler for the given event as no longer executing.
+    pub(crate) fn end(&mut self, event: usize) { self.active &= 255 ^ (1 << event); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_names() {
+        assert_eq!(parse_event("INT"), Some(INT));
+        assert_eq!(parse_event("sigterm"), Some(TERM));
+        assert_eq!(parse_event("exit"), Some(EXIT));
+        assert_eq!(parse_event("0"), Some(EXIT));
+        assert_eq!(parse_event(&sys::SIGHUP.to_string()), Some(HUP));
+        assert_eq!(parse_event("SIGFOO"), None);
+    }
+
+    #[test]
+    fn handlers_do_not_reenter() {
+        let mut traps = Traps::default();
+        assert_eq!(traps.begin(ERR), None);
+        traps.set(ERR, "echo failed".into());
+        assert_eq!(traps.begin(ERR), Some("echo failed".into()));
+        assert_eq!(traps.begin(ERR), None);
+        traps.end(ERR);
+        assert_eq!(traps.begin(ERR), Some("echo failed".into()));
+    }
+}
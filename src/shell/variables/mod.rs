@@ -1,13 +1,20 @@
 
+mod secrets;
+
+pub(crate) use self::secrets::{Echo, MASK};
+
 use super::colors::Colors;
 use super::directory_stack::DirectoryStack;
+use super::editor;
+use super::input;
+use super::plugins;
 use super::plugins::namespaces::{self, StringNamespace};
 use super::status::{FAILURE, SUCCESS};
-use app_dirs::{app_root, AppDataType, AppInfo};
-use fnv::FnvHashMap;
-use liner::Context;
+use super::xdg;
+use fnv::{FnvHashMap, FnvHashSet};
+use parser::ArgumentSplitter;
 use std::env;
-use std::io::{self, BufRead};
+use std::path::Path;
 use sys::{self, getpid, is_root};
 use sys::variables as self_sys;
 use types::{
@@ -22,11 +29,16 @@ lazy_static! {
 
 #[derive(Clone, Debug)]
 pub struct Variables {
-    pub hashmaps:  HashMapVariableContext,
-    pub arrays:    ArrayVariableContext,
-    pub variables: VariableContext,
-    pub aliases:   VariableContext,
-    flags:         u8,
+    pub hashmaps:       HashMapVariableContext,
+    pub arrays:         ArrayVariableContext,
+    pub variables:      VariableContext,
+    pub aliases:        VariableContext,
+    /// Aliases which replace any word of a command, as defined with `alias -g`.
+    pub global_aliases: VariableContext,
+    /// The commands which open files that have a suffix, as defined with `alias -s`.
+    pub suffix_aliases: VariableContext,
+    secrets:            FnvHashSet<Identifier>,
+    flags:              u8,
 }
 
 impl Default for Variables {
@@ -35,25 +47,41 @@ impl Default for Variables {
         map.insert("DIRECTORY_STACK_SIZE".into(), "1000".into());
         map.insert("HISTORY_SIZE".into(), "1000".into());
         map.insert("HISTFILE_SIZE".into(), "1000".into());
+        map.insert(
+            "JOBS_FORMAT".into(),
+            "[{id}] {pid} {pgid} {state} {elapsed}\t{command}".into(),
+        );
         map.insert(
             "PROMPT".into(),
             "${c::0x55,bold}${USER}${c::default}:${c::0x4B}${SWD}${c::default}# ${c::reset}".into(),
         );
+        map.insert("PROMPT_INPUT_WIDTH".into(), "20".into());
+        map.insert("PROMPT_PLACEHOLDER".into(), "…".into());
+        map.insert("PS4".into(), "+ ".into());
+        map.insert("RECURSION_LIMIT".into(), "256".into());
+        map.insert("STATUS_HISTORY_SIZE".into(), "10".into());
         // Set the PID variable to the PID of the shell
         let pid = getpid().map(|p| p.to_string()).unwrap_or_else(|e| e.to_string());
         map.insert("PID".into(), pid.into());
 
         // Initialize the HISTFILE variable
-        if let Ok(mut home_path) = app_root(
-            AppDataType::UserData,
-            &AppInfo {
-                name:   "ion",
-                author: "Redox OS Developers",
-            },
-        ) {
-            home_path.push("history");
+        if let Some(data_dir) = xdg::data_dir() {
+            let home_path = data_dir.join("history");
             map.insert("HISTFILE".into(), home_path.to_str().unwrap_or("?").into());
             map.insert("HISTFILE_ENABLED".into(), "1".into());
+            let audit_path = data_dir.join("audit.log");
+            map.insert("AUDIT_FILE".into(), audit_path.to_str().unwrap_or("?").into());
+            let frecency_path = data_dir.join("frecency");
+            map.insert("FRECENCY_FILE".into(), frecency_path.to_str().unwrap_or("?").into());
+        }
+
+        // Functions are autoloaded from the `functions` directory of the configuration, and
+        // libraries are imported from its `lib` directory.
+        if let Some(config_dir) = xdg::config_dir() {
+            let functions_path = config_dir.join("functions");
+            map.insert("FPATH".into(), functions_path.to_str().unwrap_or("?").into());
+            let import_path = config_dir.join("lib");
+            map.insert("IMPORT_PATH".into(), import_path.to_str().unwrap_or("?").into());
         }
 
         // Initialize the PWD (Present Working Directory) variable
@@ -68,11 +96,14 @@ impl Default for Variables {
             |path| env::set_var("HOME", path.to_str().unwrap_or("?")),
         );
         Variables {
-            hashmaps:  FnvHashMap::with_capacity_and_hasher(64, Default::default()),
-            arrays:    FnvHashMap::with_capacity_and_hasher(64, Default::default()),
-            variables: map,
-            aliases:   FnvHashMap::with_capacity_and_hasher(64, Default::default()),
-            flags:     0,
+            hashmaps:       FnvHashMap::with_capacity_and_hasher(64, Default::default()),
+            arrays:         FnvHashMap::with_capacity_and_hasher(64, Default::default()),
+            variables:      map,
+            aliases:        FnvHashMap::with_capacity_and_hasher(64, Default::default()),
+            global_aliases: FnvHashMap::default(),
+            suffix_aliases: FnvHashMap::default(),
+            secrets:        FnvHashSet::default(),
+            flags:          0,
         }
     }
 }
@@ -86,30 +117,82 @@ impl Variables {
 
     pub(crate) fn disable_plugins(&mut self) { self.flags &= 255 ^ PLUGIN; }
 
-    pub(crate) fn read<I: IntoIterator>(&mut self, args: I) -> i32
+    /// Reads a line of input into each of the given variables. If `echo` is given, the input is
+    /// a secret which is hidden while it is typed, and the variables are marked as secrets.
+    pub(crate) fn read<I: IntoIterator>(&mut self, args: I, echo: Option<Echo>) -> i32
         where I::Item: AsRef<str>
     {
         if sys::isatty(sys::STDIN_FILENO) {
-            let mut con = Context::new();
-            for arg in args.into_iter().skip(1) {
-                match con.read_line(format!("{}=", arg.as_ref().trim()), &mut |_| {}) {
-                    Ok(buffer) => self.set_var(arg.as_ref(), buffer.trim()),
-                    Err(_) => return FAILURE,
+            let mut editor = editor::new();
+            for arg in args {
+                let prompt = format!("{}=", arg.as_ref().trim());
+                let line = match echo {
+                    Some(echo) => match secrets::read_secret(&prompt, echo) {
+                        Ok(line) => line,
+                        Err(_) => None,
+                    },
+                    None => editor.read_line(prompt, &mut |_| None, &mut || None).ok(),
+                };
+                match line {
+                    Some(buffer) => {
+                        if echo.is_some() {
+                            self.set_secret(arg.as_ref(), true);
+                        }
+                        self.set_var(arg.as_ref(), buffer.trim())
+                    }
+                    None => return FAILURE,
                 }
             }
         } else {
-            let stdin = io::stdin();
-            let handle = stdin.lock();
-            let mut lines = handle.lines();
-            for arg in args.into_iter().skip(1) {
-                if let Some(Ok(line)) = lines.next() {
-                    self.set_var(arg.as_ref(), line.trim());
+            // Lines are read one at a time, and the input which follows is left unread, so that
+            // a loop such as `while read line; ...; end < file` never holds more than a line.
+            for arg in args {
+                if echo.is_some() {
+                    self.set_secret(arg.as_ref(), true);
+                }
+                match input::read_line() {
+                    Ok(Some(line)) => self.set_var(arg.as_ref(), line.trim()),
+                    _ => return FAILURE,
                 }
             }
         }
         SUCCESS
     }
 
+    pub(crate) fn is_secret(&self, name: &str) -> bool { self.secrets.contains(name) }
+
+    /// Marks a variable as a secret, or as no longer being one. The mark is kept when the
+    /// variable is dropped, so that its value remains masked if it is set again.
+    pub(crate) fn set_secret(&mut self, name: &str, secret: bool) {
+        if secret {
+            self.secrets.insert(name.into());
+        } else {
+            self.secrets.remove(name);
+        }
+    }
+
+    pub(crate) fn get_secrets(&self) -> Vec<Identifier> {
+        let mut secrets = self.secrets.iter().cloned().collect::<Vec<Identifier>>();
+        secrets.sort();
+        secrets
+    }
+
+    /// Masks the values of secret string and array variables within text which is printed by
+    /// the shell, such as a traced command.
+    pub(crate) fn mask(&self, text: &str) -> String {
+        if self.secrets.is_empty() {
+            return text.to_owned();
+        }
+        let mut values = Vec::new();
+        for name in &self.secrets {
+            values.extend(self.get_var(name));
+            if let Some(array) = self.arrays.get(name) {
+                values.extend(array.iter().cloned());
+            }
+        }
+        secrets::mask(text, values.iter().map(|value| value.as_str()))
+    }
+
     pub fn set_var(&mut self, name: &str, value: &str) {
         if !name.is_empty() {
             if value.is_empty() {
@@ -140,7 +223,6 @@ impl Variables {
         }
     }
 
-    #[allow(dead_code)]
     pub(crate) fn set_hashmap_value(&mut self, name: &str, key: &str, value: &str) {
         if !name.is_empty() {
             if let Some(map) = self.hashmaps.get_mut(name) {
@@ -206,6 +288,10 @@ impl Variables {
             "MWD" => return Some(self.get_minimal_directory()),
             _ => (),
         }
+        // Positional parameters, such as `$0` for the name of the script, refer to `@args`.
+        if let Ok(id) = name.parse::<usize>() {
+            return self.arrays.get("args").and_then(|args| args.get(id)).cloned();
+        }
         if let Some((name, variable)) = name.find("::").map(|pos| (&name[..pos], &name[pos + 2..]))
         {
             // If the parsed name contains the '::' pattern, then a namespace was designated. Find
@@ -213,6 +299,15 @@ impl Variables {
             match name {
                 "c" | "color" => Colors::collect(variable).into_string(),
                 "env" => env::var(variable).map(Into::into).ok(),
+                "ion" => {
+                    let path = match variable {
+                        "config_dir" => xdg::config_dir().map(Path::to_path_buf),
+                        "data_dir" => xdg::data_dir().map(Path::to_path_buf),
+                        "plugin_dir" => plugins::config_dir(),
+                        _ => None,
+                    };
+                    path.map(|path| path.to_string_lossy().into_owned())
+                }
                 _ => {
                     if is_root() {
                         eprintln!("ion: root is not allowed to execute plugins");
@@ -253,12 +348,50 @@ impl Variables {
 
     pub fn get_var_or_empty(&self, name: &str) -> Value { self.get_var(name).unwrap_or_default() }
 
+    /// Returns `true` if a string, array, or map variable exists with the given name. Variables
+    /// which are derived by the shell, or which are obtained from a namespace, are always set.
+    pub(crate) fn is_set(&self, name: &str) -> bool {
+        name.contains("::") || name == "SWD" || name == "MWD" || name == "RANDOM"
+            || self.variables.contains_key(name) || self.arrays.contains_key(name)
+            || self.hashmaps.contains_key(name) || env::var_os(name).is_some()
+    }
+
     pub fn unset_var(&mut self, name: &str) -> Option<Value> { self.variables.remove(name) }
 
     pub fn get_vars<'a>(&'a self) -> impl Iterator<Item = Identifier> + 'a {
         self.variables.keys().cloned().chain(env::vars().map(|(k, _)| k.into()))
     }
 
+    /// Imports each environment variable whose value is encoded as an array, such as
+    /// `[one two 'three four']`, or as a map, such as `{key=value other=value}`, into an array
+    /// or map variable of the same name. Other environment variables are left as they are.
+    pub(crate) fn import_env(&mut self) {
+        for (key, value) in env::vars() {
+            if !Variables::is_valid_variable_name(&key) || value.len() < 2 {
+                continue;
+            }
+            let (first, last) = (value.as_bytes()[0], value.as_bytes()[value.len() - 1]);
+            let inner = match (first, last) {
+                (b'[', b']') | (b'{', b'}') => &value[1..value.len() - 1],
+                _ => continue,
+            };
+            if first == b'[' {
+                let array = ArgumentSplitter::new(inner).map(|e| unquote(e).into()).collect();
+                self.set_array(&key, array);
+            } else {
+                let pairs = ArgumentSplitter::new(inner)
+                    .map(|pair| pair.find('=').map(|pos| (&pair[..pos], unquote(&pair[pos + 1..]))))
+                    .collect::<Option<Vec<_>>>();
+                // A value such as `{a,b}` is a brace expansion, not an encoded map.
+                if let Some(pairs) = pairs {
+                    for (map_key, map_value) in pairs {
+                        self.set_hashmap_value(&key, map_key, map_value);
+                    }
+                }
+            }
+        }
+    }
+
     pub(crate) fn is_valid_variable_character(c: char) -> bool {
         c.is_alphanumeric() || c == '_' || c == '?'
     }
@@ -335,7 +468,6 @@ impl Variables {
         None
     }
 
-    #[allow(dead_code)]
     pub(crate) fn is_hashmap_reference(key: &str) -> Option<(Identifier, Key)> {
         let mut key_iter = key.split('[');
 
@@ -354,6 +486,17 @@ impl Variables {
     }
 }
 
+/// Strips a matching pair of quotes surrounding an element of an encoded array or map.
+fn unquote(element: &str) -> &str {
+    let bytes = element.as_bytes();
+    match (bytes.first(), bytes.last()) {
+        (Some(&b'\''), Some(&b'\'')) | (Some(&b'"'), Some(&b'"')) if element.len() > 1 => {
+            &element[1..element.len() - 1]
+        }
+        _ => element,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -362,13 +505,26 @@ mod tests {
     struct VariableExpander(pub Variables);
 
     impl Expander for VariableExpander {
-        fn variable(&self, var: &str, _: bool) -> Option<Value> { self.0.get_var(var) }
+        fn variable(&mut self, var: &str, _: bool) -> Option<Value> { self.0.get_var(var) }
+    }
+
+    #[test]
+    fn secret_variables_are_masked() {
+        let mut variables = Variables::default();
+        variables.set_var("TOKEN", "s3cr3t");
+        variables.set_var("USER_NAME", "admin");
+        assert_eq!(variables.mask("login admin s3cr3t"), "login admin s3cr3t");
+        variables.set_secret("TOKEN", true);
+        assert!(variables.is_secret("TOKEN"));
+        assert_eq!(variables.mask("login admin s3cr3t"), "login admin ****");
+        variables.set_secret("TOKEN", false);
+        assert_eq!(variables.mask("login admin s3cr3t"), "login admin s3cr3t");
     }
 
     #[test]
     fn undefined_variable_expands_to_empty_string() {
         let variables = Variables::default();
-        let expanded = expand_string("$FOO", &VariableExpander(variables), false).join("");
+        let expanded = expand_string("$FOO", &mut VariableExpander(variables), false).join("");
         assert_eq!("", &expanded);
     }
 
@@ -376,10 +532,37 @@ mod tests {
     fn set_var_and_expand_a_variable() {
         let mut variables = Variables::default();
         variables.set_var("FOO", "BAR");
-        let expanded = expand_string("$FOO", &VariableExpander(variables), false).join("");
+        let expanded = expand_string("$FOO", &mut VariableExpander(variables), false).join("");
         assert_eq!("BAR", &expanded);
     }
 
+    #[test]
+    fn import_typed_env() {
+        env::set_var("ION_TEST_IMPORT_ARRAY", "[one two 'three four']");
+        env::set_var("ION_TEST_IMPORT_MAP", "{key=value other=\"a b\"}");
+        env::set_var("ION_TEST_IMPORT_BRACES", "{a,b}");
+        let mut variables = Variables::default();
+        variables.import_env();
+        assert_eq!(
+            variables.get_array("ION_TEST_IMPORT_ARRAY"),
+            Some(&array!["one", "two", "three four"])
+        );
+        let map = variables.get_map("ION_TEST_IMPORT_MAP").unwrap();
+        assert_eq!(map.get("key").map(|v| v.as_str()), Some("value"));
+        assert_eq!(map.get("other").map(|v| v.as_str()), Some("a b"));
+        assert!(variables.get_map("ION_TEST_IMPORT_BRACES").is_none());
+    }
+
+    #[test]
+    fn positional_parameters() {
+        let mut variables = Variables::default();
+        variables.set_array("args", array!["script.ion", "one"]);
+        let mut expander = VariableExpander(variables);
+        assert_eq!("script.ion", &expand_string("$0", &mut expander, false).join(""));
+        assert_eq!("one", &expand_string("$1", &mut expander, false).join(""));
+        assert_eq!("", &expand_string("$2", &mut expander, false).join(""));
+    }
+
     #[test]
     fn decompose_map_reference() {
         if let Some((map_name, inner_key)) = Variables::is_hashmap_reference("map[\'key\']") {
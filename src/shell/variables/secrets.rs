@@ -0,0 +1,148 @@
+//! Secret variables, such as passwords, which are read with `read -s` or marked with the
+//! `secret` builtin. Their values are not echoed while they are typed, and are printed as `****`
+//! when variables are listed, when commands are traced, and when commands are printed by a dry
+//! run.
+
+use std::io::{self, Read, Write};
+use sys;
+
+/// Printed in place of the value of a secret variable.
+pub(crate) const MASK: &str = "****";
+
+/// How the characters of a secret are echoed while it is typed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Echo {
+    /// Nothing is echoed.
+    Silent,
+    /// An asterisk is echoed for each character.
+    Masked,
+}
+
+/// Replaces each occurrence of the given secrets within the text with `****`. Longer secrets are
+/// replaced first, so that a secret which contains another is masked entirely.
+pub(crate) fn mask<'a, I: Iterator<Item = &'a str>>(text: &str, secrets: I) -> String {
+    let mut secrets = secrets.filter(|secret| !secret.is_empty()).collect::<Vec<&str>>();
+    secrets.sort_by(|a, b| b.len().cmp(&a.len()));
+    secrets.into_iter().fold(text.to_owned(), |text, secret| text.replace(secret, MASK))
+}
+
+/// The effect of a byte which was typed while a secret was being read.
+#[derive(Debug, PartialEq)]
+enum Edit {
+    /// A byte was added to the secret, which begins a new character if `true`.
+    Push(bool),
+    /// The last character was erased, if there was one.
+    Erase(bool),
+    /// The secret was entered.
+    Enter,
+    /// The end of the input was reached before anything was typed.
+    Abort,
+}
+
+/// Applies a byte which was typed to the secret which is being read.
+fn edit(secret: &mut Vec<u8>, byte: u8) -> Edit {
+    match byte {
+        b'\n' | b'\r' => Edit::Enter,
+        // ^D ends the input if nothing has been typed, and otherwise enters the secret.
+        4 if secret.is_empty() => Edit::Abort,
+        4 => Edit::Enter,
+        // Backspace and delete erase the last character, including its continuation bytes.
+        8 | 127 => {
+            let erased = secret.pop().map(|mut byte| {
+                while byte & 0xC0 == 0x80 {
+                    match secret.pop() {
+                        Some(next) => byte = next,
+                        None => break,
+                    }
+                }
+            });
+            Edit::Erase(erased.is_some())
+        }
+        _ => {
+            secret.push(byte);
+            Edit::Push(byte & 0xC0 != 0x80)
+        }
+    }
+}
+
+/// Reads a line from the terminal without echoing it, or echoing an asterisk for each character
+/// that is typed. The modes of the terminal are restored afterwards. Returns `None` if the input
+/// ended before anything was typed.
+pub(crate) fn read_secret(prompt: &str, echo: Echo) -> io::Result<Option<String>> {
+    let modes = sys::tcgetattr(sys::STDIN_FILENO)?;
+    let mut hidden = modes;
+    hidden.c_lflag &= !sys::ECHO;
+    if echo == Echo::Masked {
+        hidden.c_lflag &= !sys::ICANON;
+    }
+
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+    let _ = write!(stderr, "{}", prompt);
+    let _ = stderr.flush();
+    sys::tcsetattr(sys::STDIN_FILENO, &hidden)?;
+
+    let stdin = io::stdin();
+    let mut bytes = stdin.lock().bytes();
+    let mut secret = Vec::new();
+    let result = loop {
+        let byte = match bytes.next() {
+            Some(Ok(byte)) => byte,
+            Some(Err(why)) => break Err(why),
+            None if secret.is_empty() => break Ok(false),
+            None => break Ok(true),
+        };
+        match edit(&mut secret, byte) {
+            Edit::Push(true) if echo == Echo::Masked => {
+                let _ = write!(stderr, "*");
+            }
+            Edit::Erase(true) if echo == Echo::Masked => {
+                let _ = write!(stderr, "\x08 \x08");
+            }
+            Edit::Push(_) | Edit::Erase(_) => continue,
+            Edit::Enter => break Ok(true),
+            Edit::Abort => break Ok(false),
+        }
+        let _ = stderr.flush();
+    };
+
+    let _ = sys::tcsetattr(sys::STDIN_FILENO, &modes);
+    let _ = writeln!(stderr);
+    match result? {
+        true => Ok(Some(String::from_utf8_lossy(&secret).into_owned())),
+        false => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masking() {
+        let secrets = vec!["hunter2", "", "hunter2!"];
+        assert_eq!(
+            mask("curl -u admin:hunter2! https://hunter2.example", secrets.into_iter()),
+            "curl -u admin:**** https://****.example"
+        );
+        assert_eq!(mask("echo public", vec!["private"].into_iter()), "echo public");
+    }
+
+    #[test]
+    fn editing() {
+        let mut secret = Vec::new();
+        assert_eq!(edit(&mut secret, 4), Edit::Abort);
+        assert_eq!(edit(&mut secret, 127), Edit::Erase(false));
+        for &byte in "pä".as_bytes() {
+            edit(&mut secret, byte);
+        }
+        assert_eq!(edit(&mut secret, b's'), Edit::Push(true));
+        assert_eq!(edit(&mut secret, 0xA4), Edit::Push(false));
+        secret.pop();
+        assert_eq!(edit(&mut secret, 8), Edit::Erase(true));
+        assert_eq!(edit(&mut secret, 127), Edit::Erase(true));
+        assert_eq!(secret, b"p");
+        assert_eq!(edit(&mut secret, 4), Edit::Enter);
+        assert_eq!(edit(&mut secret, b'\r'), Edit::Enter);
+    }
+}
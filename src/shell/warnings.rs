@@ -0,0 +1,193 @@
+//! Warnings about code which is likely to be a mistake, although it may still be executed. Unlike
+//! errors, each kind of warning has a level, which is configured with `ion --warn` or the
+//! `pragma warn` builtin: warnings may be ignored, printed to stderr, or raised as errors.
+
+use super::Shell;
+use super::errors::{ErrorKind, IonError};
+use parser::Location;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+
+/// A kind of code which is warned about.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Warning {
+    /// Syntax which is deprecated, such as backtick command substitutions.
+    Deprecated,
+    /// A reference to a variable which has not been set.
+    Undefined,
+    /// A function which has the same name as a builtin, which it will take the place of.
+    Shadow,
+}
+
+/// Every kind of warning, in the order in which their levels are stored and listed.
+pub(crate) const WARNINGS: [Warning; 3] =
+    [Warning::Deprecated, Warning::Undefined, Warning::Shadow];
+
+impl Warning {
+    fn parse(name: &str) -> Option<Warning> {
+        WARNINGS.iter().cloned().find(|warning| warning.name() == name)
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match *self {
+            Warning::Deprecated => "deprecated",
+            Warning::Undefined => "undefined",
+            Warning::Shadow => "shadow",
+        }
+    }
+}
+
+/// What is done when a kind of warning occurs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Level {
+    Off,
+    Warn,
+    Error,
+}
+
+impl Level {
+    fn parse(name: &str) -> Option<Level> {
+        match name {
+            "off" => Some(Level::Off),
+            "warn" => Some(Level::Warn),
+            "error" => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match *self {
+            Level::Off => "off",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The level of each kind of warning.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Warnings {
+    levels: [Level; 3],
+}
+
+impl Default for Warnings {
+    /// References to undefined variables are commonplace in existing scripts, and so they are
+    /// only warned about when asked to.
+    fn default() -> Warnings {
+        Warnings {
+            levels: [Level::Warn, Level::Off, Level::Warn],
+        }
+    }
+}
+
+impl Warnings {
+    pub(crate) fn level(&self, warning: Warning) -> Level { self.levels[warning as usize] }
+
+    /// Sets the level of the named kind of warning, or of every kind if none is named.
+    pub(crate) fn set(&mut self, warning: Option<&str>, level: &str) -> Result<(), String> {
+        let level = Level::parse(level)
+            .ok_or_else(|| format!("{}: expected `off`, `warn`, or `error`", level))?;
+        match warning {
+            Some(name) => {
+                let warning =
+                    Warning::parse(name).ok_or_else(|| format!("{}: no such warning", name))?;
+                self.levels[warning as usize] = level;
+            }
+            None => self.levels = [level; 3],
+        }
+        Ok(())
+    }
+
+    /// Sets levels as given to `ion --warn`, which is either `LEVEL` or `WARNING:LEVEL`.
+    pub(crate) fn configure(&mut self, spec: &str) -> Result<(), String> {
+        match spec.find(':') {
+            Some(pos) => self.set(Some(&spec[..pos]), &spec[pos + 1..]),
+            None => self.set(None, spec),
+        }
+    }
+}
+
+/// Describes a warning, which is located at the command of the script that is being executed.
+fn render(warning: Warning, location: Option<&Location>, message: &str) -> String {
+    match location {
+        Some(location) => format!("{}: warning: {} [{}]", location, message, warning.name()),
+        None => format!("ion: warning: {} [{}]", message, warning.name()),
+    }
+}
+
+/// Issues warnings from the shell.
+pub(crate) trait Warn {
+    /// Prints a warning to stderr, or reports it as an error, according to its level. Returns
+    /// `true` if it was reported as an error, in which case the code that it concerns should
+    /// not be executed.
+    fn warn(&self, warning: Warning, message: &str) -> bool;
+
+    /// Warns if a function of the given name would be shadowed by a builtin, returning `false`
+    /// if the warning was reported as an error, in which case the function should not be
+    /// defined.
+    fn may_define(&self, function: &str) -> bool;
+}
+
+impl Warn for Shell {
+    fn warn(&self, warning: Warning, message: &str) -> bool {
+        match self.warnings.level(warning) {
+            Level::Off => false,
+            Level::Warn => {
+                let warning = render(warning, self.location.as_ref(), message);
+                let stderr = io::stderr();
+                let _ = writeln!(stderr.lock(), "{}", warning);
+                false
+            }
+            Level::Error => {
+                let message = format!("{} [{}]", message, warning.name());
+                self.report(IonError::new(ErrorKind::Warning, message));
+                true
+            }
+        }
+    }
+
+    fn may_define(&self, function: &str) -> bool {
+        let message = format!("{}: function is shadowed by a builtin", function);
+        !(self.builtins.contains_key(function) && self.warn(Warning::Shadow, &message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels() {
+        let mut warnings = Warnings::default();
+        assert_eq!(warnings.level(Warning::Deprecated), Level::Warn);
+        assert_eq!(warnings.level(Warning::Undefined), Level::Off);
+        assert_eq!(warnings.configure("undefined:error"), Ok(()));
+        assert_eq!(warnings.level(Warning::Undefined), Level::Error);
+        assert_eq!(warnings.configure("off"), Ok(()));
+        assert!(WARNINGS.iter().all(|&warning| warnings.level(warning) == Level::Off));
+        assert_eq!(
+            warnings.configure("unused:warn"),
+            Err("unused: no such warning".into())
+        );
+        assert_eq!(
+            warnings.set(Some("shadow"), "loud"),
+            Err("loud: expected `off`, `warn`, or `error`".into())
+        );
+    }
+
+    #[test]
+    fn rendering() {
+        let location = Location::new("script.ion", 4);
+        assert_eq!(
+            render(Warning::Undefined, Some(&location), "name: undefined variable"),
+            "script.ion:4: warning: name: undefined variable [undefined]"
+        );
+        assert_eq!(
+            render(Warning::Shadow, None, "echo: function is shadowed by a builtin"),
+            "ion: warning: echo: function is shadowed by a builtin [shadow]"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use app_dirs::{get_app_root, AppDataType, AppInfo};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const APP_INFO: AppInfo = AppInfo {
+    name:   "ion",
+    author: "Redox OS Developers",
+};
+
+lazy_static! {
+    static ref CONFIG_DIR: Option<PathBuf> =
+        resolve("XDG_CONFIG_HOME", ".config", AppDataType::UserConfig);
+    static ref DATA_DIR: Option<PathBuf> =
+        resolve("XDG_DATA_HOME", ".local/share", AppDataType::UserData);
+}
+
+/// The directory in which the init files and plugins are kept: `$XDG_CONFIG_HOME/ion`.
+pub(crate) fn config_dir() -> Option<&'static Path> { CONFIG_DIR.as_ref().map(|p| p.as_path()) }
+
+/// The directory in which the history file is kept: `$XDG_DATA_HOME/ion`.
+pub(crate) fn data_dir() -> Option<&'static Path> { DATA_DIR.as_ref().map(|p| p.as_path()) }
+
+/// Obtains the ion directory within the base directory given by `variable`, which defaults to
+/// `default` within the home directory. If that directory does not exist yet, the directory
+/// which previous versions of Ion used on this platform is moved there first.
+fn resolve(variable: &str, default: &str, legacy: AppDataType) -> Option<PathBuf> {
+    // Relative paths are to be ignored, according to the XDG base directory specification.
+    let base = env::var_os(variable).map(PathBuf::from).filter(|path| path.is_absolute());
+    let mut path = match base.or_else(|| env::home_dir().map(|home| home.join(default))) {
+        Some(path) => path,
+        None => {
+            eprintln!("ion: unable to locate {}: no home directory was found", variable);
+            return None;
+        }
+    };
+    path.push("ion");
+
+    if !path.exists() {
+        if let Ok(legacy) = get_app_root(legacy, &APP_INFO) {
+            if legacy != path && legacy.is_dir() {
+                migrate(&legacy, &path);
+            }
+        }
+    }
+
+    if let Err(why) = fs::create_dir_all(&path) {
+        eprintln!("ion: unable to create {}: {}", path.display(), why);
+        return None;
+    }
+    Some(path)
+}
+
+/// Moves the legacy directory to its new location, or copies its contents over if the two
+/// are on different file systems.
+fn migrate(legacy: &Path, path: &Path) {
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let result = fs::rename(legacy, path).or_else(|_| copy_dir(legacy, path));
+    match result {
+        Ok(()) => eprintln!("ion: migrated {} to {}", legacy.display(), path.display()),
+        Err(why) => eprintln!(
+            "ion: unable to migrate {} to {}: {}",
+            legacy.display(),
+            path.display(),
+            why
+        ),
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_directories_are_migrated() {
+        let root = env::temp_dir().join(format!("ion-xdg-test-{}", ::std::process::id()));
+        let legacy = root.join("legacy");
+        let path = root.join("xdg").join("ion");
+        fs::create_dir_all(legacy.join("plugins")).unwrap();
+        fs::write(legacy.join("initrc"), "let a = 1").unwrap();
+        fs::write(legacy.join("plugins").join("lib"), "").unwrap();
+
+        migrate(&legacy, &path);
+        assert!(!legacy.exists());
+        assert_eq!(fs::read_to_string(path.join("initrc")).unwrap(), "let a = 1");
+        assert!(path.join("plugins").join("lib").exists());
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -1,6 +1,8 @@
 extern crate syscall;
 
 use std::{io, mem, slice};
+use std::fs::File;
+use std::io::Read;
 use std::os::unix::io::RawFd;
 
 use syscall::SigAction;
@@ -19,12 +21,22 @@ pub(crate) const STDIN_FILENO: RawFd = 0;
 pub(crate) const STDOUT_FILENO: RawFd = 1;
 pub(crate) const STDERR_FILENO: RawFd = 2;
 
+pub(crate) type Termios = syscall::Termios;
+pub(crate) const ECHO: u32 = 0o000_010;
+pub(crate) const ICANON: u32 = 0o000_002;
+
 pub(crate) fn is_root() -> bool { syscall::geteuid().map(|id| id == 0).unwrap_or(false) }
 
 pub unsafe fn fork() -> io::Result<u32> { cvt(syscall::clone(0)).map(|pid| pid as u32) }
 
 pub(crate) fn getpid() -> io::Result<u32> { cvt(syscall::getpid()).map(|pid| pid as u32) }
 
+pub(crate) fn getpgid(pid: u32) -> io::Result<u32> {
+    cvt(syscall::getpgid(pid as usize)).map(|pgid| pgid as u32)
+}
+
+pub(crate) fn getpgrp() -> io::Result<u32> { cvt(syscall::getpgid(0)).map(|pgid| pgid as u32) }
+
 pub(crate) fn kill(pid: u32, signal: i32) -> io::Result<()> {
     cvt(syscall::kill(pid as usize, signal as usize)).and(Ok(()))
 }
@@ -62,6 +74,19 @@ pub(crate) fn reset_signal(signal: i32) -> io::Result<()> {
     cvt(syscall::sigaction(signal as usize, Some(&new), None)).and(Ok(()))
 }
 
+/// Blocks until the given child exits, returning its raw status.
+pub(crate) fn waitpid(pid: u32) -> io::Result<i32> {
+    let mut status = 0;
+    cvt(syscall::waitpid(pid as usize, &mut status, 0)).and(Ok(status as i32))
+}
+
+/// Obtains the raw status of the given child if it has exited, without blocking.
+pub(crate) fn try_waitpid(pid: u32) -> io::Result<Option<i32>> {
+    let mut status = 0;
+    cvt(syscall::waitpid(pid as usize, &mut status, syscall::WNOHANG))
+        .map(|reaped| if reaped == 0 { None } else { Some(status as i32) })
+}
+
 pub(crate) fn tcsetpgrp(tty_fd: RawFd, pgid: u32) -> io::Result<()> {
     let fd = cvt(syscall::dup(tty_fd, b"pgrp"))?;
 
@@ -75,12 +100,51 @@ pub(crate) fn tcsetpgrp(tty_fd: RawFd, pgid: u32) -> io::Result<()> {
     cvt(res).and(Ok(()))
 }
 
-pub(crate) fn dup(fd: RawFd) -> io::Result<RawFd> { cvt(syscall::dup(fd, &[])) }
+pub(crate) fn tcgetpgrp(tty_fd: RawFd) -> io::Result<u32> {
+    let fd = cvt(syscall::dup(tty_fd, b"pgrp"))?;
+
+    let mut pgid_usize = 0usize;
+    let res = syscall::read(fd, unsafe {
+        slice::from_raw_parts_mut(&mut pgid_usize as *mut usize as *mut u8, mem::size_of::<usize>())
+    });
+
+    let _ = syscall::close(fd);
+
+    cvt(res).and(Ok(pgid_usize as u32))
+}
+
+pub(crate) fn tcgetattr(tty_fd: RawFd) -> io::Result<Termios> {
+    let fd = cvt(syscall::dup(tty_fd, b"termios"))?;
+    let mut termios = Termios::default();
+    let res = syscall::read(fd, &mut termios);
+    let _ = syscall::close(fd);
+    cvt(res).and(Ok(termios))
+}
+
+pub(crate) fn tcsetattr(tty_fd: RawFd, termios: &Termios) -> io::Result<()> {
+    let fd = cvt(syscall::dup(tty_fd, b"termios"))?;
+    let res = syscall::write(fd, termios);
+    let _ = syscall::close(fd);
+    cvt(res).and(Ok(()))
+}
+
+/// Duplicates the descriptor. The duplicate is marked as close-on-exec, so that it will not be
+/// inherited by commands that the shell executes.
+pub(crate) fn dup(fd: RawFd) -> io::Result<RawFd> {
+    let new = cvt(syscall::dup(fd, &[]))?;
+    cvt(syscall::fcntl(new, syscall::F_SETFD, syscall::O_CLOEXEC)).and(Ok(new))
+}
 
 pub(crate) fn dup2(old: RawFd, new: RawFd) -> io::Result<RawFd> {
     cvt(syscall::dup2(old, new, &[]))
 }
 
+/// Marks the descriptor as non-blocking, so that reads which would block fail instead.
+pub(crate) fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    let flags = cvt(syscall::fcntl(fd, syscall::F_GETFL, 0))?;
+    cvt(syscall::fcntl(fd, syscall::F_SETFL, flags | syscall::O_NONBLOCK)).and(Ok(()))
+}
+
 pub(crate) fn close(fd: RawFd) -> io::Result<()> { cvt(syscall::close(fd)).and(Ok(())) }
 
 pub(crate) fn close_stdin() { syscall::close(STDIN_FILENO); }
@@ -94,6 +158,31 @@ pub(crate) fn isatty(fd: RawFd) -> bool {
     }
 }
 
+/// Obtains the number of columns of the terminal.
+pub(crate) fn terminal_width(tty_fd: RawFd) -> io::Result<usize> {
+    let fd = cvt(syscall::dup(tty_fd, b"winsize"))?;
+    let mut size = syscall::Winsize::default();
+    let res = syscall::read(fd, &mut size);
+    let _ = syscall::close(fd);
+    cvt(res).and(Ok(size.ws_col as usize))
+}
+
+/// Obtains the name of the host, which Redox keeps within `/etc/hostname`.
+pub(crate) fn hostname() -> io::Result<String> {
+    let mut name = String::new();
+    File::open("/etc/hostname")?.read_to_string(&mut name)?;
+    Ok(name.trim().into())
+}
+
+/// Sets the file mode creation mask of the shell, returning the previous mask.
+pub(crate) fn umask(mask: u32) -> io::Result<u32> {
+    cvt(syscall::umask(mask as usize)).map(|mask| mask as u32)
+}
+
+/// Obtains the offset of the local time zone from UTC, in seconds, at the given time. Redox
+/// does not yet provide time zones, and so every time is in UTC.
+pub(crate) fn utc_offset(_time: i64) -> i64 { 0 }
+
 // Support function for converting syscall error to io error
 fn cvt(result: Result<usize, syscall::Error>) -> io::Result<usize> {
     result.map_err(|err| io::Error::from_raw_os_error(err.errno))
@@ -113,6 +202,7 @@ pub mod job_control {
     use shell::job_control::*;
 
     use shell::Shell;
+    use shell::flags::PIPE_FAIL;
     use shell::foreground::ForegroundSignals;
     use shell::status::{FAILURE, TERMINATED};
     use std::os::unix::process::ExitStatusExt;
@@ -130,49 +220,54 @@ pub mod job_control {
     }
 
 
+    /// Reports a foreground process which was ended by a signal, returning the status which
+    /// reflects it. The shell carries on, as the signal was sent to the process rather than to
+    /// the shell, although an interrupt also aborts the commands which follow the job.
+    fn signaled(shell: &mut Shell, signal: i32) -> i32 {
+        if signal == syscall::SIGINT as i32 {
+            shell.foreground_send(signal);
+            shell.break_flow = true;
+        } else if signal != syscall::SIGPIPE as i32 {
+            eprintln!("ion: process ended by signal {}", signal);
+        }
+        128 + signal
+    }
+
     pub(crate) fn watch_foreground<F, D>(
         shell: &mut Shell,
-        _pid: u32,
-        last_pid: u32,
+        children: &[u32],
         _get_command: F,
         mut drop_command: D,
     ) -> i32
         where F: FnOnce() -> String,
               D: FnMut(i32)
     {
-        let mut exit_status = 0;
+        let pipefail = shell.flags & PIPE_FAIL != 0;
+        // The status of each process, by its position within the pipeline, once it has ended.
+        let mut statuses = vec![None; children.len()];
         loop {
             let mut status_raw = 0;
             match syscall::waitpid(0, &mut status_raw, 0) {
                 Ok(pid) => {
                     let status = ExitStatus::from_raw(status_raw as i32);
-                    if let Some(code) = status.code() {
-                        if pid == (last_pid as usize) {
-                            break code;
-                        } else {
-                            drop_command(pid as i32);
-                            exit_status = code;
-                        }
-                    } else if let Some(signal) = status.signal() {
-                        eprintln!("ion: process ended by signal: {}", signal);
-                        if signal == syscall::SIGTERM as i32 {
-                            shell.handle_signal(signal);
-                            shell.exit(TERMINATED);
-                        } else if signal == syscall::SIGHUP as i32 {
-                            shell.handle_signal(signal);
-                            shell.exit(TERMINATED);
-                        } else if signal == syscall::SIGINT as i32 {
-                            shell.foreground_send(signal);
-                            shell.break_flow = true;
+                    let code = match (status.code(), status.signal()) {
+                        (Some(code), _) => code,
+                        (None, Some(signal)) => signaled(shell, signal),
+                        (None, None) => {
+                            eprintln!("ion: process ended with unknown status: {}", status);
+                            break TERMINATED;
                         }
-                        break TERMINATED;
-                    } else {
-                        eprintln!("ion: process ended with unknown status: {}", status);
-                        break TERMINATED;
+                    };
+                    if let Some(id) = children.iter().position(|&child| child as usize == pid) {
+                        statuses[id] = Some(code);
+                    }
+                    drop_command(pid as i32);
+                    if statuses.iter().all(Option::is_some) {
+                        break pipeline_status(&statuses, pipefail);
                     }
                 }
                 Err(err) => if err.errno == syscall::ECHILD {
-                    break exit_status;
+                    break pipeline_status(&statuses, pipefail);
                 } else {
                     eprintln!("ion: process doesn't exist: {}", err);
                     break FAILURE;
@@ -182,6 +277,80 @@ pub mod job_control {
     }
 }
 
+pub mod privileges {
+    use super::cvt;
+    use std::io;
+    use syscall;
+
+    /// The real and effective user IDs, followed by the real and effective group IDs.
+    pub(crate) fn ids() -> (u32, u32, u32, u32) {
+        let id = |id: syscall::Result<usize>| id.unwrap_or(0) as u32;
+        let (uid, euid) = (id(syscall::getuid()), id(syscall::geteuid()));
+        (uid, euid, id(syscall::getgid()), id(syscall::getegid()))
+    }
+
+    /// Redox does not yet provide supplementary groups.
+    pub(crate) fn groups() -> io::Result<Vec<u32>> { Ok(Vec::new()) }
+
+    // TODO: Look up users and groups once Redox provides a database of them.
+    pub(crate) fn user_by_name(_name: &str) -> Option<(u32, u32)> { None }
+
+    pub(crate) fn user_name(_uid: u32) -> Option<String> { None }
+
+    pub(crate) fn group_by_name(_name: &str) -> Option<u32> { None }
+
+    pub(crate) fn group_name(_gid: u32) -> Option<String> { None }
+
+    pub(crate) fn set_groups(_user: Option<&str>, _gid: u32) -> io::Result<()> { Ok(()) }
+
+    pub(crate) fn set_ids(uid: u32, gid: u32) -> io::Result<()> {
+        cvt(syscall::setregid(gid as usize, gid as usize))?;
+        cvt(syscall::setreuid(uid as usize, uid as usize)).map(|_| ())
+    }
+
+    pub(crate) fn can_regain_root() -> bool { syscall::setreuid(0, 0).is_ok() }
+}
+
+pub mod limits {
+    use std::io;
+    use syscall;
+
+    pub(crate) const CPU: u8 = 0;
+    pub(crate) const MEMORY: u8 = 1;
+    pub(crate) const FILE_SIZE: u8 = 2;
+    pub(crate) const FILES: u8 = 3;
+    pub(crate) const PROCESSES: u8 = 4;
+
+    // TODO: Limit resources once Redox provides `setrlimit`.
+    pub(crate) fn check(_resource: u8, _value: u64) -> io::Result<()> {
+        let message = "resource limits are not supported on Redox";
+        Err(io::Error::new(io::ErrorKind::Other, message))
+    }
+
+    pub(crate) fn set(_resource: u8, _value: u64) -> io::Result<()> {
+        Err(io::Error::from_raw_os_error(syscall::ENOSYS))
+    }
+}
+
+pub mod namespaces {
+    use std::ffi::CStr;
+    use std::io;
+
+    pub(crate) const MOUNT: u8 = 1;
+    pub(crate) const PID: u8 = 2;
+    pub(crate) const NETWORK: u8 = 4;
+    pub(crate) const USER: u8 = 8;
+
+    // TODO: Isolate commands within Redox's schemes and namespaces.
+    pub(crate) fn enter(namespaces: u8, root: Option<&CStr>) -> io::Result<()> {
+        if namespaces == 0 && root.is_none() {
+            return Ok(());
+        }
+        let message = "isolation is not supported on Redox";
+        Err(io::Error::new(io::ErrorKind::Other, message))
+    }
+}
+
 pub mod variables {
     pub(crate) fn get_user_home(_username: &str) -> Option<String> {
         // TODO
@@ -1,6 +1,7 @@
 use errno::errno;
 use libc::*;
 use shell::Shell;
+use shell::flags::PIPE_FAIL;
 use shell::foreground::ForegroundSignals;
 use shell::job_control::*;
 use shell::status::{FAILURE, TERMINATED};
@@ -39,15 +40,21 @@ pub(crate) fn watch_background(
                     break;
                 }
                 0 => (),
-                _ if WIFEXITED(status) => {
-                    if !fg_was_grabbed {
-                        eprintln!("ion: ([{}] {}) exited with {}", njob, pid, status);
-                    }
+                _ if WIFEXITED(status) || WIFSIGNALED(status) => {
+                    let status = if WIFEXITED(status) {
+                        WEXITSTATUS(status)
+                    } else {
+                        128 + WTERMSIG(status)
+                    };
                     let mut processes = processes.lock().unwrap();
                     let process = &mut processes.iter_mut().nth(njob).unwrap();
-                    process.state = ProcessState::Empty;
+                    // The status is retained until it is reported before the next prompt,
+                    // unless the job was brought into the foreground.
                     if fg_was_grabbed {
-                        fg.reply_with(WEXITSTATUS(status) as i8);
+                        process.state = ProcessState::Empty;
+                        fg.reply_with(status);
+                    } else {
+                        process.state = ProcessState::Done(status);
                     }
                     break;
                 }
@@ -58,7 +65,7 @@ pub(crate) fn watch_background(
                     let mut processes = processes.lock().unwrap();
                     let process = &mut processes.iter_mut().nth(njob).unwrap();
                     if fg_was_grabbed {
-                        fg.reply_with(TERMINATED as i8);
+                        fg.reply_with(TERMINATED);
                         fg_was_grabbed = false;
                     }
                     process.state = ProcessState::Stopped;
@@ -78,30 +85,50 @@ pub(crate) fn watch_background(
     }
 }
 
-const FIRST: u8 = 1;
-const LAST: u8 = 2;
+/// Reports a foreground process which was ended by a signal, returning the status which
+/// reflects it. As the signal was sent to the process rather than to the shell, the shell
+/// carries on once the job's `TerminalGuard` has restored the terminal, although an interrupt
+/// also aborts the commands which follow the job.
+fn signaled(shell: &mut Shell, signal: i32) -> i32 {
+    match signal {
+        // Commands which exceeded the limits given to them by `limit` end, rather than the
+        // shell, and their signal is reflected in their status.
+        SIGXCPU => eprintln!("ion: process exceeded its CPU time limit"),
+        SIGXFSZ => eprintln!("ion: process exceeded its file size limit"),
+        SIGKILL => eprintln!("ion: process was killed"),
+        SIGINT => {
+            shell.foreground_send(signal);
+            shell.break_flow = true;
+        }
+        SIGPIPE => (),
+        _ => eprintln!("ion: process ended by signal {}", signal),
+    }
+    128 + signal
+}
 
 pub(crate) fn watch_foreground<F, D>(
     shell: &mut Shell,
-    first_pid: u32,
-    last_pid: u32,
+    children: &[u32],
     get_command: F,
     mut drop_command: D,
 ) -> i32
     where F: FnOnce() -> String,
           D: FnMut(i32)
 {
-    let mut exit_status = 0;
-    let mut found = 0;
+    let pipefail = shell.flags & PIPE_FAIL != 0;
+    // The status of each process, by its position within the pipeline, once it has ended.
+    let mut statuses = vec![None; children.len()];
     loop {
         unsafe {
             let mut status = 0;
-            let pid = waitpid(-1, &mut status, WUNTRACED);
+            // Only wait on this job's process group, so that the statuses of background jobs
+            // are left for their own watchers to collect.
+            let pid = waitpid(-(children[0] as pid_t), &mut status, WUNTRACED);
             match pid {
                 -1 => {
                     let error = errno();
                     match error.0 {
-                        ECHILD => break exit_status,
+                        ECHILD => break pipeline_status(&statuses, pipefail),
                         _ => {
                             eprintln!("ion: {}", error);
                             break FAILURE;
@@ -109,37 +136,19 @@ pub(crate) fn watch_foreground<F, D>(
                     }
                 }
                 0 => (),
-                _ if WIFEXITED(status) => {
-                    let status = WEXITSTATUS(status) as i32;
-                    if pid == (last_pid as i32) {
-                        found |= LAST;
-                    }
-
-                    if pid == (first_pid as i32) {
-                        found |= FIRST;
-                    }
-
-                    if found == FIRST + LAST {
-                        break status;
+                _ if WIFEXITED(status) || WIFSIGNALED(status) => {
+                    let status = if WIFEXITED(status) {
+                        WEXITSTATUS(status)
                     } else {
-                        drop_command(pid);
-                        exit_status = status;
+                        signaled(shell, WTERMSIG(status))
+                    };
+                    if let Some(id) = children.iter().position(|&child| child as pid_t == pid) {
+                        statuses[id] = Some(status);
                     }
-                }
-                _ if WIFSIGNALED(status) => {
-                    eprintln!("ion: process ended by signal");
-                    let signal = WTERMSIG(status);
-                    match signal {
-                        SIGINT => {
-                            shell.foreground_send(signal as i32);
-                            shell.break_flow = true;
-                        }
-                        _ => {
-                            shell.handle_signal(signal);
-                            shell.exit(TERMINATED);
-                        }
+                    drop_command(pid);
+                    if statuses.iter().all(Option::is_some) {
+                        break pipeline_status(&statuses, pipefail);
                     }
-                    break TERMINATED;
                 }
                 _ if WIFSTOPPED(status) => {
                     shell.send_to_background(pid as u32, ProcessState::Stopped, get_command());
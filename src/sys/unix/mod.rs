@@ -4,7 +4,7 @@ pub mod job_control;
 pub mod signals;
 
 use libc::{c_int, pid_t, sighandler_t};
-use std::io;
+use std::{io, mem};
 use std::os::unix::io::RawFd;
 
 pub(crate) const PATH_SEPARATOR: &str = ":";
@@ -21,12 +21,24 @@ pub(crate) const STDOUT_FILENO: i32 = libc::STDOUT_FILENO;
 pub(crate) const STDERR_FILENO: i32 = libc::STDERR_FILENO;
 pub(crate) const STDIN_FILENO: i32 = libc::STDIN_FILENO;
 
+pub(crate) type Termios = libc::termios;
+pub(crate) const ECHO: libc::tcflag_t = libc::ECHO;
+pub(crate) const ICANON: libc::tcflag_t = libc::ICANON;
+
 pub(crate) fn is_root() -> bool { unsafe { libc::geteuid() == 0 } }
 
 pub unsafe fn fork() -> io::Result<u32> { cvt(libc::fork()).map(|pid| pid as u32) }
 
 pub(crate) fn getpid() -> io::Result<u32> { cvt(unsafe { libc::getpid() }).map(|pid| pid as u32) }
 
+pub(crate) fn getpgid(pid: u32) -> io::Result<u32> {
+    cvt(unsafe { libc::getpgid(pid as pid_t) }).map(|pgid| pgid as u32)
+}
+
+pub(crate) fn getpgrp() -> io::Result<u32> {
+    cvt(unsafe { libc::getpgrp() }).map(|pgid| pgid as u32)
+}
+
 pub(crate) fn kill(pid: u32, signal: i32) -> io::Result<()> {
     cvt(unsafe { libc::kill(pid as pid_t, signal as c_int) }).and(Ok(()))
 }
@@ -68,16 +80,52 @@ pub(crate) fn reset_signal(signal: i32) -> io::Result<()> {
     }
 }
 
+/// Blocks until the given child exits, returning its raw status.
+pub(crate) fn waitpid(pid: u32) -> io::Result<i32> {
+    let mut status = 0;
+    cvt(unsafe { libc::waitpid(pid as pid_t, &mut status, 0) }).and(Ok(status))
+}
+
+/// Obtains the raw status of the given child if it has exited, without blocking.
+pub(crate) fn try_waitpid(pid: u32) -> io::Result<Option<i32>> {
+    let mut status = 0;
+    cvt(unsafe { libc::waitpid(pid as pid_t, &mut status, libc::WNOHANG) })
+        .map(|reaped| if reaped == 0 { None } else { Some(status) })
+}
+
 pub(crate) fn tcsetpgrp(fd: RawFd, pgrp: u32) -> io::Result<()> {
     cvt(unsafe { libc::tcsetpgrp(fd as c_int, pgrp as pid_t) }).and(Ok(()))
 }
 
-pub(crate) fn dup(fd: RawFd) -> io::Result<RawFd> { cvt(unsafe { libc::dup(fd) }) }
+pub(crate) fn tcgetpgrp(fd: RawFd) -> io::Result<u32> {
+    cvt(unsafe { libc::tcgetpgrp(fd as c_int) }).map(|pgrp| pgrp as u32)
+}
+
+pub(crate) fn tcgetattr(fd: RawFd) -> io::Result<Termios> {
+    let mut termios: Termios = unsafe { mem::zeroed() };
+    cvt(unsafe { libc::tcgetattr(fd as c_int, &mut termios) }).and(Ok(termios))
+}
+
+pub(crate) fn tcsetattr(fd: RawFd, termios: &Termios) -> io::Result<()> {
+    cvt(unsafe { libc::tcsetattr(fd as c_int, libc::TCSADRAIN, termios) }).and(Ok(()))
+}
+
+/// Duplicates the descriptor. The duplicate is marked as close-on-exec, so that it will not be
+/// inherited by commands that the shell executes.
+pub(crate) fn dup(fd: RawFd) -> io::Result<RawFd> {
+    cvt(unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) })
+}
 
 pub(crate) fn dup2(old: RawFd, new: RawFd) -> io::Result<RawFd> {
     cvt(unsafe { libc::dup2(old, new) })
 }
 
+/// Marks the descriptor as non-blocking, so that reads which would block fail instead.
+pub(crate) fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    let flags = cvt(unsafe { libc::fcntl(fd, libc::F_GETFL) })?;
+    cvt(unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) }).and(Ok(()))
+}
+
 pub(crate) fn close(fd: RawFd) -> io::Result<()> { cvt(unsafe { libc::close(fd) }).and(Ok(())) }
 
 pub(crate) fn close_stdin() {
@@ -88,6 +136,34 @@ pub(crate) fn close_stdin() {
 
 pub(crate) fn isatty(fd: RawFd) -> bool { unsafe { libc::isatty(fd) == 1 } }
 
+/// Obtains the number of columns of the terminal.
+pub(crate) fn terminal_width(fd: RawFd) -> io::Result<usize> {
+    let mut size: libc::winsize = unsafe { mem::zeroed() };
+    cvt(unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) }).and(Ok(size.ws_col as usize))
+}
+
+/// Obtains the name of the host.
+pub(crate) fn hostname() -> io::Result<String> {
+    let mut name = [0u8; 256];
+    cvt(unsafe { libc::gethostname(name.as_mut_ptr() as *mut libc::c_char, name.len()) })?;
+    let len = name.iter().position(|&byte| byte == 0).unwrap_or(name.len());
+    Ok(String::from_utf8_lossy(&name[..len]).into_owned())
+}
+
+/// Sets the file mode creation mask of the shell, returning the previous mask.
+pub(crate) fn umask(mask: u32) -> io::Result<u32> {
+    Ok(unsafe { libc::umask(mask as libc::mode_t) } as u32)
+}
+
+/// Obtains the offset of the local time zone from UTC, in seconds, at the given time.
+pub(crate) fn utc_offset(time: i64) -> i64 {
+    unsafe {
+        let time = time as libc::time_t;
+        let mut tm: libc::tm = mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() { 0 } else { tm.tm_gmtoff as i64 }
+    }
+}
+
 trait IsMinusOne {
     fn is_minus_one(&self) -> bool;
 }
@@ -110,6 +186,280 @@ fn cvt<T: IsMinusOne>(t: T) -> io::Result<T> {
     }
 }
 
+pub mod privileges {
+    use super::cvt;
+    use libc::{self, gid_t};
+    use std::ffi::CString;
+    use std::io;
+    use users_unix::{get_group_by_gid, get_group_by_name, get_user_by_name, get_user_by_uid};
+
+    /// The real and effective user IDs, followed by the real and effective group IDs.
+    pub(crate) fn ids() -> (u32, u32, u32, u32) {
+        unsafe { (libc::getuid(), libc::geteuid(), libc::getgid(), libc::getegid()) }
+    }
+
+    pub(crate) fn groups() -> io::Result<Vec<u32>> {
+        let count = cvt(unsafe { libc::getgroups(0, 0 as *mut gid_t) })?;
+        let mut groups = vec![0 as gid_t; count as usize];
+        let count = cvt(unsafe { libc::getgroups(count, groups.as_mut_ptr()) })?;
+        groups.truncate(count as usize);
+        Ok(groups)
+    }
+
+    /// The user ID and primary group ID of the named user.
+    pub(crate) fn user_by_name(name: &str) -> Option<(u32, u32)> {
+        get_user_by_name(name).map(|user| (user.uid(), user.primary_group_id()))
+    }
+
+    pub(crate) fn user_name(uid: u32) -> Option<String> {
+        get_user_by_uid(uid).map(|user| user.name().to_owned())
+    }
+
+    pub(crate) fn group_by_name(name: &str) -> Option<u32> {
+        get_group_by_name(name).map(|group| group.gid())
+    }
+
+    pub(crate) fn group_name(gid: u32) -> Option<String> {
+        get_group_by_gid(gid).map(|group| group.name().to_owned())
+    }
+
+    /// Sets the supplementary groups to those of the named user, or to the given group alone.
+    pub(crate) fn set_groups(user: Option<&str>, gid: u32) -> io::Result<()> {
+        match user.and_then(|user| CString::new(user).ok()) {
+            Some(user) => cvt(unsafe { libc::initgroups(user.as_ptr(), gid as _) }),
+            None => cvt(unsafe { libc::setgroups(1, &gid) }),
+        }.map(|_| ())
+    }
+
+    /// Sets the real, effective, and saved group and user IDs, in that order, as the group can
+    /// no longer be changed once the user has been.
+    pub(crate) fn set_ids(uid: u32, gid: u32) -> io::Result<()> {
+        cvt(unsafe { libc::setgid(gid) })?;
+        cvt(unsafe { libc::setuid(uid) }).map(|_| ())
+    }
+
+    /// Returns `true` if the shell is able to become root again.
+    pub(crate) fn can_regain_root() -> bool { unsafe { libc::setuid(0) == 0 } }
+}
+
+pub mod limits {
+    use super::cvt;
+    use libc::{self, rlim_t, rlimit};
+    use std::{cmp, io};
+
+    pub(crate) const CPU: u8 = 0;
+    pub(crate) const MEMORY: u8 = 1;
+    pub(crate) const FILE_SIZE: u8 = 2;
+    pub(crate) const FILES: u8 = 3;
+    pub(crate) const PROCESSES: u8 = 4;
+
+    /// Maps a resource to that of libc, whose type differs between platforms.
+    macro_rules! resource {
+        ($resource:expr) => {
+            match $resource {
+                CPU => libc::RLIMIT_CPU,
+                MEMORY => libc::RLIMIT_AS,
+                FILE_SIZE => libc::RLIMIT_FSIZE,
+                FILES => libc::RLIMIT_NOFILE,
+                _ => libc::RLIMIT_NPROC,
+            }
+        };
+    }
+
+    /// Ensures that the limit of a resource may be lowered to the given value, which may not
+    /// be above its hard limit. This is checked by the shell before the command is forked, so
+    /// that the error may be described without allocating within the child.
+    pub(crate) fn check(resource: u8, value: u64) -> io::Result<()> {
+        let mut limit = rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        cvt(unsafe { libc::getrlimit(resource!(resource), &mut limit) })?;
+        if value as rlim_t > limit.rlim_max {
+            let message = format!("the limit exceeds the hard limit of {}", limit.rlim_max);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+        Ok(())
+    }
+
+    /// Lowers the limit of a resource to the given number of seconds, bytes, files, or
+    /// processes. The hard limit of CPU time is a second above its soft limit, so that a
+    /// command is sent SIGXCPU before it is killed. As this is called within the child, after
+    /// it was forked, errors are given by their codes rather than allocated.
+    pub(crate) fn set(resource: u8, value: u64) -> io::Result<()> {
+        let resource = resource!(resource);
+        let mut limit = rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        cvt(unsafe { libc::getrlimit(resource, &mut limit) })?;
+        let value = value as rlim_t;
+        if value > limit.rlim_max {
+            return Err(io::Error::from_raw_os_error(libc::EINVAL));
+        }
+        let hard = if resource == libc::RLIMIT_CPU { value.saturating_add(1) } else { value };
+        limit.rlim_cur = value;
+        limit.rlim_max = cmp::min(hard, limit.rlim_max);
+        cvt(unsafe { libc::setrlimit(resource, &limit) }).map(|_| ())
+    }
+}
+
+pub mod namespaces {
+    use super::cvt;
+    use libc::{self, c_char};
+    use std::ffi::CStr;
+    use std::io;
+
+    pub(crate) const MOUNT: u8 = 1;
+    pub(crate) const PID: u8 = 2;
+    pub(crate) const NETWORK: u8 = 4;
+    pub(crate) const USER: u8 = 8;
+
+    /// Moves the process into new namespaces, and then into the given root directory. This is
+    /// called within the child which executes an isolated command, once it has joined the
+    /// process group of its pipeline.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn enter(namespaces: u8, root: Option<&CStr>) -> io::Result<()> {
+        // The IDs must be read before a new user namespace hides them.
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let flags = [
+            (USER, libc::CLONE_NEWUSER),
+            (MOUNT, libc::CLONE_NEWNS),
+            (PID, libc::CLONE_NEWPID),
+            (NETWORK, libc::CLONE_NEWNET),
+        ].iter()
+            .filter(|&&(namespace, _)| namespaces & namespace != 0)
+            .fold(0, |flags, &(_, flag)| flags | flag);
+        if flags != 0 {
+            cvt(unsafe { libc::unshare(flags) })?;
+        }
+        if namespaces & USER != 0 {
+            linux::map_ids(uid, gid)?;
+        }
+        if namespaces & MOUNT != 0 {
+            linux::make_mounts_private()?;
+        }
+        if namespaces & PID != 0 {
+            linux::fork_init()?;
+        }
+        if let Some(root) = root {
+            change_root(root)?;
+        }
+        if namespaces & (MOUNT | PID) == MOUNT | PID {
+            linux::mount_proc()?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn enter(namespaces: u8, root: Option<&CStr>) -> io::Result<()> {
+        if namespaces != 0 {
+            let message = "namespaces are only supported on Linux";
+            return Err(io::Error::new(io::ErrorKind::Other, message));
+        }
+        match root {
+            Some(root) => change_root(root),
+            None => Ok(()),
+        }
+    }
+
+    fn change_root(root: &CStr) -> io::Result<()> {
+        cvt(unsafe { libc::chroot(root.as_ptr()) })?;
+        cvt(unsafe { libc::chdir(b"/\0".as_ptr() as *const c_char) }).map(|_| ())
+    }
+
+    #[cfg(target_os = "linux")]
+    mod linux {
+        use super::super::cvt;
+        use libc::{self, c_char, c_int};
+        use std::fs::{self, OpenOptions};
+        use std::io::{self, Write};
+        use std::ptr;
+
+        fn write_proc(path: &str, contents: &str) -> io::Result<()> {
+            OpenOptions::new().write(true).open(path)?.write_all(contents.as_bytes())
+        }
+
+        /// Maps the user and group of the shell to root within a new user namespace, which
+        /// allows the other namespaces to be created without privileges.
+        pub(super) fn map_ids(uid: u32, gid: u32) -> io::Result<()> {
+            // Kernels which predate this file allow the group map to be written regardless.
+            match write_proc("/proc/self/setgroups", "deny") {
+                Err(ref why) if why.kind() == io::ErrorKind::NotFound => (),
+                result => result?,
+            }
+            write_proc("/proc/self/uid_map", &format!("0 {} 1", uid))?;
+            write_proc("/proc/self/gid_map", &format!("0 {} 1", gid))
+        }
+
+        /// Keeps the mounts which are made within a new mount namespace from propagating to the
+        /// namespace of the shell.
+        pub(super) fn make_mounts_private() -> io::Result<()> {
+            let none = b"none\0".as_ptr() as *const c_char;
+            let root = b"/\0".as_ptr() as *const c_char;
+            let flags = libc::MS_REC | libc::MS_PRIVATE;
+            cvt(unsafe { libc::mount(none, root, ptr::null(), flags, ptr::null()) }).map(|_| ())
+        }
+
+        /// Mounts the processes of a new PID namespace at `/proc`, if the root directory has one.
+        pub(super) fn mount_proc() -> io::Result<()> {
+            let proc_ = b"proc\0".as_ptr() as *const c_char;
+            let target = b"/proc\0".as_ptr() as *const c_char;
+            let flags = libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC;
+            match cvt(unsafe { libc::mount(proc_, target, proc_, flags, ptr::null()) }) {
+                Err(ref why) if why.kind() == io::ErrorKind::NotFound => Ok(()),
+                result => result.map(|_| ()),
+            }
+        }
+
+        /// A new PID namespace only applies to the children of the process which created it, so
+        /// the process forks, and its child executes the command as the first process of the
+        /// namespace. The parent closes its descriptors, so that the shell is not kept waiting
+        /// for the command to be executed, and then waits for its child and exits as it did.
+        pub(super) fn fork_init() -> io::Result<()> {
+            let child = cvt(unsafe { libc::fork() })?;
+            if child == 0 {
+                // The command is killed if the process which waits for it is killed.
+                unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) };
+                return Ok(());
+            }
+
+            let fds = fs::read_dir("/proc/self/fd")
+                .map(|entries| {
+                    entries
+                        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                        .collect::<Vec<c_int>>()
+                })
+                .unwrap_or_else(|_| Vec::new());
+            unsafe {
+                for &fd in &fds {
+                    libc::close(fd);
+                }
+                // Interrupts are left to the command, as with `unshare --fork`, while the
+                // handlers of the shell are removed so that the job may still be terminated.
+                libc::signal(libc::SIGINT, libc::SIG_IGN);
+                libc::signal(libc::SIGQUIT, libc::SIG_IGN);
+                libc::signal(libc::SIGHUP, libc::SIG_DFL);
+                libc::signal(libc::SIGTERM, libc::SIG_DFL);
+
+                let mut status = 0;
+                while libc::waitpid(child, &mut status, 0) == -1 {
+                    if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                        libc::_exit(1);
+                    }
+                }
+                if libc::WIFSIGNALED(status) {
+                    let signal = libc::WTERMSIG(status);
+                    libc::signal(signal, libc::SIG_DFL);
+                    libc::kill(libc::getpid(), signal);
+                    libc::_exit(128 + signal);
+                }
+                libc::_exit(libc::WEXITSTATUS(status))
+            }
+        }
+    }
+}
+
 pub mod variables {
     use users_unix::get_user_by_name;
     use users_unix::os::unix::UserExt;
@@ -121,3 +471,38 @@ pub mod variables {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    /// True if a child spawned by the shell is able to write to the given descriptor.
+    fn inherited_by_child(fd: RawFd) -> bool {
+        Command::new("sh")
+            .arg("-c")
+            .arg(format!("true >&{}", fd))
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn children_only_inherit_std_fds() {
+        let (pipe_read, pipe_write) = pipe2(O_CLOEXEC).unwrap();
+        let duplicate = dup(STDOUT_FILENO).unwrap();
+        let (leaked_read, leaked_write) = pipe2(0).unwrap();
+
+        assert!(inherited_by_child(STDOUT_FILENO));
+        // Sanity check, as descriptors without the flag are inherited.
+        assert!(inherited_by_child(leaked_write));
+        for &fd in &[pipe_read, pipe_write, duplicate] {
+            assert!(!inherited_by_child(fd), "descriptor {} was leaked to the child", fd);
+        }
+
+        for &fd in &[pipe_read, pipe_write, duplicate, leaked_read, leaked_write] {
+            close(fd).unwrap();
+        }
+    }
+}
@@ -0,0 +1,467 @@
+//! The Windows backend, which provides the paths of the shell that do not rely upon `fork`.
+//! Descriptors are those of the C runtime, which wraps the handles of the console, files, and
+//! anonymous pipes, whereas processes are spawned with `CreateProcess` by `std::process`, and are
+//! waited upon through their handles. Windows has neither process groups nor job control signals,
+//! so each process is treated as a group of its own, and can only be terminated.
+
+use std::{env, io, ptr};
+use std::os::raw::{c_int, c_uint, c_void};
+
+pub(crate) type RawFd = c_int;
+type Handle = *mut c_void;
+
+pub(crate) const PATH_SEPARATOR: &str = ";";
+
+/// Marks the descriptors of a pipe as not being inherited, as `_O_NOINHERIT` does.
+pub(crate) const O_CLOEXEC: usize = 0x0080;
+pub(crate) const SIGHUP: i32 = 1;
+pub(crate) const SIGINT: i32 = 2;
+pub(crate) const SIGTERM: i32 = 15;
+pub(crate) const SIGCONT: i32 = 18;
+pub(crate) const SIGSTOP: i32 = 19;
+pub(crate) const SIGTSTP: i32 = 20;
+
+pub(crate) const STDIN_FILENO: RawFd = 0;
+pub(crate) const STDOUT_FILENO: RawFd = 1;
+pub(crate) const STDERR_FILENO: RawFd = 2;
+
+/// The mode of the console, whose flags take the place of those of a terminal.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Termios {
+    pub c_lflag: u32,
+}
+
+pub(crate) const ECHO: u32 = 0x0004;
+pub(crate) const ICANON: u32 = 0x0002;
+
+const O_BINARY: c_int = 0x8000;
+const PIPE_SIZE: c_uint = 4096;
+const SIG_DFL: usize = 0;
+const SIG_ERR: usize = !0;
+
+const SYNCHRONIZE: u32 = 0x0010_0000;
+const PROCESS_TERMINATE: u32 = 0x0001;
+const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+const INFINITE: u32 = 0xFFFF_FFFF;
+const WAIT_OBJECT_0: u32 = 0;
+const WAIT_TIMEOUT: u32 = 0x0102;
+const HANDLE_FLAG_INHERIT: u32 = 0x0001;
+const PIPE_NOWAIT: u32 = 0x0001;
+
+#[repr(C)]
+#[derive(Default)]
+struct Coord {
+    x: i16,
+    y: i16,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct SmallRect {
+    left:   i16,
+    top:    i16,
+    right:  i16,
+    bottom: i16,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct ConsoleScreenBufferInfo {
+    size:       Coord,
+    cursor:     Coord,
+    attributes: u16,
+    window:     SmallRect,
+    maximum:    Coord,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct Tm {
+    tm_sec:   c_int,
+    tm_min:   c_int,
+    tm_hour:  c_int,
+    tm_mday:  c_int,
+    tm_mon:   c_int,
+    tm_year:  c_int,
+    tm_wday:  c_int,
+    tm_yday:  c_int,
+    tm_isdst: c_int,
+}
+
+extern "C" {
+    fn _pipe(fds: *mut c_int, size: c_uint, mode: c_int) -> c_int;
+    fn _dup(fd: c_int) -> c_int;
+    fn _dup2(old: c_int, new: c_int) -> c_int;
+    fn _close(fd: c_int) -> c_int;
+    fn _isatty(fd: c_int) -> c_int;
+    fn _get_osfhandle(fd: c_int) -> isize;
+    fn _umask(mode: c_int) -> c_int;
+    fn _localtime64_s(tm: *mut Tm, time: *const i64) -> c_int;
+    fn _mkgmtime64(tm: *mut Tm) -> i64;
+    #[link_name = "signal"]
+    fn crt_signal(signal: c_int, handler: usize) -> usize;
+}
+
+extern "system" {
+    fn GetCurrentProcessId() -> u32;
+    fn OpenProcess(access: u32, inherit: i32, pid: u32) -> Handle;
+    fn WaitForSingleObject(handle: Handle, milliseconds: u32) -> u32;
+    fn GetExitCodeProcess(handle: Handle, code: *mut u32) -> i32;
+    fn TerminateProcess(handle: Handle, code: u32) -> i32;
+    fn CloseHandle(handle: Handle) -> i32;
+    fn SetHandleInformation(handle: Handle, mask: u32, flags: u32) -> i32;
+    fn SetNamedPipeHandleState(
+        handle: Handle,
+        mode: *mut u32,
+        collection_count: *mut u32,
+        collection_timeout: *mut u32,
+    ) -> i32;
+    fn GetConsoleMode(handle: Handle, mode: *mut u32) -> i32;
+    fn SetConsoleMode(handle: Handle, mode: u32) -> i32;
+    fn GetConsoleScreenBufferInfo(handle: Handle, info: *mut ConsoleScreenBufferInfo) -> i32;
+}
+
+/// Administrators are not distinguished from other users.
+pub(crate) fn is_root() -> bool { false }
+
+pub unsafe fn fork() -> io::Result<u32> { Err(unsupported("forking")) }
+
+pub(crate) fn getpid() -> io::Result<u32> { Ok(unsafe { GetCurrentProcessId() }) }
+
+pub(crate) fn getpgid(pid: u32) -> io::Result<u32> { Ok(pid) }
+
+pub(crate) fn getpgrp() -> io::Result<u32> { getpid() }
+
+/// Terminates the process, whose exit code reflects the signal, as the other signals have no
+/// counterpart on Windows.
+pub(crate) fn kill(pid: u32, signal: i32) -> io::Result<()> {
+    if signal == SIGCONT || signal == SIGSTOP || signal == SIGTSTP {
+        return Err(unsupported("job control"));
+    }
+    let process = open_process(pid, PROCESS_TERMINATE)?;
+    cvt_bool(unsafe { TerminateProcess(process.0, 128 + signal as u32) })
+}
+
+pub(crate) fn killpg(pgid: u32, signal: i32) -> io::Result<()> { kill(pgid, signal) }
+
+pub(crate) fn pipe2(flags: usize) -> io::Result<(RawFd, RawFd)> {
+    let mut fds = [0; 2];
+    cvt(unsafe { _pipe(fds.as_mut_ptr(), PIPE_SIZE, O_BINARY | flags as c_int) })?;
+    Ok((fds[0], fds[1]))
+}
+
+pub(crate) fn setpgid(_pid: u32, _pgid: u32) -> io::Result<()> { Ok(()) }
+
+#[allow(dead_code)]
+pub(crate) fn signal(signal: i32, handler: extern "C" fn(i32)) -> io::Result<()> {
+    set_handler(signal, handler as usize)
+}
+
+pub(crate) fn reset_signal(signal: i32) -> io::Result<()> { set_handler(signal, SIG_DFL) }
+
+/// Blocks until the given child exits, returning its exit code.
+pub(crate) fn waitpid(pid: u32) -> io::Result<i32> {
+    wait(pid, INFINITE).map(|code| code.unwrap_or(0))
+}
+
+/// Obtains the exit code of the given child if it has exited, without blocking.
+pub(crate) fn try_waitpid(pid: u32) -> io::Result<Option<i32>> { wait(pid, 0) }
+
+/// The console is shared by every process, which may all read from it.
+pub(crate) fn tcsetpgrp(_fd: RawFd, _pgrp: u32) -> io::Result<()> { Ok(()) }
+
+pub(crate) fn tcgetpgrp(_fd: RawFd) -> io::Result<u32> { getpgrp() }
+
+pub(crate) fn tcgetattr(fd: RawFd) -> io::Result<Termios> {
+    let mut termios = Termios::default();
+    cvt_bool(unsafe { GetConsoleMode(handle(fd)?, &mut termios.c_lflag) }).and(Ok(termios))
+}
+
+pub(crate) fn tcsetattr(fd: RawFd, termios: &Termios) -> io::Result<()> {
+    cvt_bool(unsafe { SetConsoleMode(handle(fd)?, termios.c_lflag) })
+}
+
+/// Duplicates the descriptor. The duplicate is not inheritable, so that it will not be
+/// inherited by commands that the shell executes.
+pub(crate) fn dup(fd: RawFd) -> io::Result<RawFd> {
+    let new = cvt(unsafe { _dup(fd) })?;
+    match handle(new).and_then(|new| {
+        cvt_bool(unsafe { SetHandleInformation(new, HANDLE_FLAG_INHERIT, 0) })
+    }) {
+        Ok(()) => Ok(new),
+        Err(why) => {
+            let _ = close(new);
+            Err(why)
+        }
+    }
+}
+
+pub(crate) fn dup2(old: RawFd, new: RawFd) -> io::Result<RawFd> {
+    cvt(unsafe { _dup2(old, new) }).and(Ok(new))
+}
+
+/// Marks the read end of a pipe as non-blocking, so that reads which would block fail instead.
+pub(crate) fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    let mut mode = PIPE_NOWAIT;
+    cvt_bool(unsafe {
+        SetNamedPipeHandleState(handle(fd)?, &mut mode, ptr::null_mut(), ptr::null_mut())
+    })
+}
+
+pub(crate) fn close(fd: RawFd) -> io::Result<()> { cvt(unsafe { _close(fd) }).and(Ok(())) }
+
+pub(crate) fn close_stdin() {
+    unsafe {
+        _close(STDIN_FILENO);
+    }
+}
+
+pub(crate) fn isatty(fd: RawFd) -> bool { unsafe { _isatty(fd) != 0 } }
+
+/// Obtains the number of columns of the console's window.
+pub(crate) fn terminal_width(fd: RawFd) -> io::Result<usize> {
+    let mut info = ConsoleScreenBufferInfo::default();
+    cvt_bool(unsafe { GetConsoleScreenBufferInfo(handle(fd)?, &mut info) })?;
+    Ok((info.window.right - info.window.left + 1) as usize)
+}
+
+/// Obtains the name of the host, which Windows gives to each process.
+pub(crate) fn hostname() -> io::Result<String> {
+    env::var("COMPUTERNAME").map_err(|why| io::Error::new(io::ErrorKind::NotFound, why))
+}
+
+/// Sets the file mode creation mask of the shell, returning the previous mask. Only the write
+/// permission of the owner is honoured by Windows.
+pub(crate) fn umask(mask: u32) -> io::Result<u32> { Ok(unsafe { _umask(mask as c_int) } as u32) }
+
+/// Obtains the offset of the local time zone from UTC, in seconds, at the given time.
+pub(crate) fn utc_offset(time: i64) -> i64 {
+    unsafe {
+        let mut tm = Tm::default();
+        if _localtime64_s(&mut tm, &time) != 0 {
+            return 0;
+        }
+        match _mkgmtime64(&mut tm) {
+            -1 => 0,
+            local => local - time,
+        }
+    }
+}
+
+/// A handle to a process, which is closed when it is dropped.
+struct Process(Handle);
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+fn open_process(pid: u32, access: u32) -> io::Result<Process> {
+    let process = unsafe { OpenProcess(access, 0, pid) };
+    if process.is_null() {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(Process(process))
+    }
+}
+
+/// Waits up to the given number of milliseconds for the process to exit, returning its exit
+/// code if it has.
+fn wait(pid: u32, milliseconds: u32) -> io::Result<Option<i32>> {
+    let process = open_process(pid, SYNCHRONIZE | PROCESS_QUERY_LIMITED_INFORMATION)?;
+    match unsafe { WaitForSingleObject(process.0, milliseconds) } {
+        WAIT_OBJECT_0 => {
+            let mut code = 0;
+            cvt_bool(unsafe { GetExitCodeProcess(process.0, &mut code) })?;
+            Ok(Some(code as i32))
+        }
+        WAIT_TIMEOUT => Ok(None),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+fn set_handler(signal: i32, handler: usize) -> io::Result<()> {
+    if unsafe { crt_signal(signal as c_int, handler) } == SIG_ERR {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Obtains the handle which underlies the descriptor.
+fn handle(fd: RawFd) -> io::Result<Handle> {
+    match unsafe { _get_osfhandle(fd) } {
+        -1 | -2 => Err(io::Error::from_raw_os_error(6)),
+        handle => Ok(handle as Handle),
+    }
+}
+
+fn unsupported(feature: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{} is not supported on Windows", feature))
+}
+
+fn cvt(result: c_int) -> io::Result<c_int> {
+    if result == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result)
+    }
+}
+
+fn cvt_bool(result: i32) -> io::Result<()> {
+    if result == 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Windows does not have signal masks, as the console delivers its events to every process.
+pub mod signals {
+    pub(crate) fn block() {}
+
+    pub(crate) fn unblock() {}
+}
+
+pub mod job_control {
+    use shell::job_control::*;
+
+    use shell::Shell;
+    use shell::flags::PIPE_FAIL;
+    use shell::foreground::ForegroundSignals;
+    use std::sync::{Arc, Mutex};
+
+    pub(crate) fn watch_background(
+        fg: Arc<ForegroundSignals>,
+        processes: Arc<Mutex<Vec<BackgroundProcess>>>,
+        pid: u32,
+        njob: usize,
+    ) {
+        let status = super::waitpid(pid);
+        let mut processes = processes.lock().unwrap();
+        let process = &mut processes.iter_mut().nth(njob).unwrap();
+        match status {
+            Ok(status) if fg.was_grabbed(pid) => {
+                process.state = ProcessState::Empty;
+                fg.reply_with(status);
+            }
+            Ok(status) => process.state = ProcessState::Done(status),
+            Err(why) => {
+                eprintln!("ion: ([{}] {}) errored: {}", njob, pid, why);
+                process.state = ProcessState::Empty;
+                if fg.was_grabbed(pid) {
+                    fg.errored();
+                }
+            }
+        }
+    }
+
+    /// Waits for each process of the pipeline in turn. As processes cannot be stopped, the job
+    /// is never sent to the background.
+    pub(crate) fn watch_foreground<F, D>(
+        shell: &mut Shell,
+        children: &[u32],
+        _get_command: F,
+        mut drop_command: D,
+    ) -> i32
+        where F: FnOnce() -> String,
+              D: FnMut(i32)
+    {
+        let pipefail = shell.flags & PIPE_FAIL != 0;
+        let mut statuses = Vec::with_capacity(children.len());
+        for &pid in children {
+            match super::waitpid(pid) {
+                Ok(status) => statuses.push(Some(status)),
+                Err(why) => {
+                    eprintln!("ion: process doesn't exist: {}", why);
+                    statuses.push(None);
+                }
+            }
+            drop_command(pid as i32);
+        }
+        pipeline_status(&statuses, pipefail)
+    }
+}
+
+pub mod privileges {
+    use super::unsupported;
+    use std::{env, io};
+
+    /// Windows identifies users by their security identifiers rather than by numbers, and so
+    /// the current user is given as the user with the ID of zero.
+    pub(crate) fn ids() -> (u32, u32, u32, u32) { (0, 0, 0, 0) }
+
+    pub(crate) fn groups() -> io::Result<Vec<u32>> { Ok(Vec::new()) }
+
+    pub(crate) fn user_by_name(name: &str) -> Option<(u32, u32)> {
+        if user_name(0).map_or(false, |user| user == name) { Some((0, 0)) } else { None }
+    }
+
+    pub(crate) fn user_name(uid: u32) -> Option<String> {
+        if uid == 0 { env::var("USERNAME").ok() } else { None }
+    }
+
+    pub(crate) fn group_by_name(_name: &str) -> Option<u32> { None }
+
+    pub(crate) fn group_name(_gid: u32) -> Option<String> { None }
+
+    pub(crate) fn set_groups(_user: Option<&str>, _gid: u32) -> io::Result<()> {
+        Err(unsupported("changing groups"))
+    }
+
+    pub(crate) fn set_ids(_uid: u32, _gid: u32) -> io::Result<()> {
+        Err(unsupported("changing users"))
+    }
+
+    pub(crate) fn can_regain_root() -> bool { false }
+}
+
+pub mod limits {
+    use std::io;
+
+    pub(crate) const CPU: u8 = 0;
+    pub(crate) const MEMORY: u8 = 1;
+    pub(crate) const FILE_SIZE: u8 = 2;
+    pub(crate) const FILES: u8 = 3;
+    pub(crate) const PROCESSES: u8 = 4;
+
+    pub(crate) fn check(_resource: u8, _value: u64) -> io::Result<()> {
+        let message = "resource limits are not supported on Windows";
+        Err(io::Error::new(io::ErrorKind::Other, message))
+    }
+
+    pub(crate) fn set(resource: u8, value: u64) -> io::Result<()> { check(resource, value) }
+}
+
+pub mod namespaces {
+    use super::unsupported;
+    use std::ffi::CStr;
+    use std::io;
+
+    pub(crate) const MOUNT: u8 = 1;
+    pub(crate) const PID: u8 = 2;
+    pub(crate) const NETWORK: u8 = 4;
+    pub(crate) const USER: u8 = 8;
+
+    pub(crate) fn enter(namespaces: u8, root: Option<&CStr>) -> io::Result<()> {
+        if namespaces == 0 && root.is_none() {
+            return Ok(());
+        }
+        Err(unsupported("isolation"))
+    }
+}
+
+pub mod variables {
+    use std::env;
+    use std::path::Path;
+
+    /// Assumes that the profiles of users share the directory of the current user's profile.
+    pub(crate) fn get_user_home(username: &str) -> Option<String> {
+        let profile = env::var("USERPROFILE").ok()?;
+        let parent = Path::new(&profile).parent()?;
+        Some(parent.join(username).to_string_lossy().into_owned())
+    }
+}
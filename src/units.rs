@@ -0,0 +1,169 @@
+//! Numbers written in scientific notation, such as `1.5e3`, or with the suffix of a unit, such as
+//! the `4K` and `2MiB` of sizes, or the `250ms` of durations. Sizes are measured in bytes, and
+//! durations in seconds, and numbers are converted exactly, so that `4K` is the integer `4000`.
+
+use decimal::{Decimal, DIVISION_SCALE};
+use radix;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Kind {
+    Size,
+    Duration,
+}
+
+/// Each unit, with the number of bytes or seconds which it is worth. Units which end with `B`
+/// may also be written without it, as in `4Ki` for `4KiB`, excepting the byte itself.
+const UNITS: &[(&str, &str, Kind)] = &[
+    ("B", "1", Kind::Size),
+    ("kB", "1000", Kind::Size),
+    ("KB", "1000", Kind::Size),
+    ("MB", "1000000", Kind::Size),
+    ("GB", "1000000000", Kind::Size),
+    ("TB", "1000000000000", Kind::Size),
+    ("PB", "1000000000000000", Kind::Size),
+    ("KiB", "1024", Kind::Size),
+    ("MiB", "1048576", Kind::Size),
+    ("GiB", "1073741824", Kind::Size),
+    ("TiB", "1099511627776", Kind::Size),
+    ("PiB", "1125899906842624", Kind::Size),
+    ("ns", "0.000000001", Kind::Duration),
+    ("us", "0.000001", Kind::Duration),
+    ("ms", "0.001", Kind::Duration),
+    ("s", "1", Kind::Duration),
+    ("min", "60", Kind::Duration),
+    ("h", "3600", Kind::Duration),
+    ("d", "86400", Kind::Duration),
+];
+
+/// The largest power of ten by which a number in scientific notation may be multiplied.
+const MAX_EXPONENT: i64 = 4096;
+
+/// Finds the unit with the given name, with or without its trailing `B`.
+fn unit(name: &str) -> Option<(Decimal, Kind)> {
+    UNITS
+        .iter()
+        .find(|&&(unit, ..)| {
+            let is_abbreviated = unit.len() > 1 && unit.ends_with('B');
+            unit == name || (is_abbreviated && unit[..unit.len() - 1] == *name)
+        })
+        .map(|&(_, value, kind)| (Decimal::parse(value).unwrap(), kind))
+}
+
+/// Parses a number which may be written in scientific notation, as in `1.5e3` or `2E-4`.
+fn scientific(literal: &str) -> Option<Decimal> {
+    let (mantissa, exponent) = match literal.find(|c| c == 'e' || c == 'E') {
+        Some(id) => (&literal[..id], literal[id + 1..].parse::<i64>().ok()?),
+        None => (literal, 0),
+    };
+    if exponent.abs() > MAX_EXPONENT {
+        return None;
+    }
+    let mantissa = Decimal::parse(mantissa)?;
+    let power = Decimal::parse("10")?.power(&Decimal::parse(&exponent.abs().to_string())?)?;
+    if exponent < 0 {
+        mantissa.divide(&power, literal.len() + exponent.abs() as usize)
+    } else {
+        Some(mantissa.multiply(&power))
+    }
+}
+
+/// Splits a number from the unit which follows it, if it has one.
+fn split(literal: &str) -> Option<(Decimal, Option<(Decimal, Kind)>)> {
+    if let Some(number) = scientific(literal) {
+        return Some((number, None));
+    }
+    let is_letter = |&byte: &u8| (byte >= b'a' && byte <= b'z') || (byte >= b'A' && byte <= b'Z');
+    let suffix = literal.len() - literal.bytes().rev().take_while(is_letter).count();
+    let unit = unit(&literal[suffix..])?;
+    Some((scientific(&literal[..suffix])?, Some(unit)))
+}
+
+/// Parses a number, which may be written in scientific notation, with the suffix of a unit, or as
+/// an integer in another base, as in `0xff`. Sizes are given in bytes, and durations in seconds.
+pub(crate) fn parse_decimal(literal: &str) -> Option<Decimal> {
+    match split(literal) {
+        Some((number, Some((value, _)))) => Some(number.multiply(&value)),
+        Some((number, None)) => Some(number),
+        None => radix::parse(literal).and_then(|value| Decimal::parse(&value.to_string())),
+    }
+}
+
+/// Parses an integer, which may also be written as any number which `parse_decimal` accepts, so
+/// long as its value is an integer of 64 bits.
+pub(crate) fn parse_integer(literal: &str) -> Option<i64> {
+    radix::parse(literal).or_else(|| match parse_decimal(literal) {
+        Some(ref number) if number.is_integer() => number.to_string().parse::<i64>().ok(),
+        _ => None,
+    })
+}
+
+/// Parses a float, which may also be written as any number which `parse_decimal` accepts.
+pub(crate) fn parse_float(literal: &str) -> Option<f64> {
+    match literal.parse::<f64>() {
+        Ok(value) => Some(value),
+        Err(_) => parse_decimal(literal).and_then(|number| number.to_string().parse::<f64>().ok()),
+    }
+}
+
+/// Converts a number to the given unit, as in `2048` for `2MiB` in `KiB`. A number which has a
+/// unit of its own may only be converted to a unit of the same kind.
+pub(crate) fn to_unit(literal: &str, name: &str) -> Result<String, String> {
+    let (value, kind) = unit(name).ok_or_else(|| format!("'{}' is not a unit", name))?;
+    let number = match split(literal) {
+        Some((number, Some((from, from_kind)))) => if from_kind == kind {
+            number.multiply(&from)
+        } else {
+            return Err(format!("'{}' cannot be converted to {}", literal, name));
+        },
+        Some((number, None)) => number,
+        None => return Err(format!("'{}' is not a number", literal)),
+    };
+    Ok(number.divide(&value, DIVISION_SCALE).unwrap().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(literal: &str) -> Option<String> {
+        parse_decimal(literal).map(|number| number.to_string())
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(decimal("1.5e3"), Some("1500".into()));
+        assert_eq!(decimal("2E-4"), Some("0.0002".into()));
+        assert_eq!(decimal("4K"), Some("4000".into()));
+        assert_eq!(decimal("4kB"), Some("4000".into()));
+        assert_eq!(decimal("2MiB"), Some("2097152".into()));
+        assert_eq!(decimal("1.5Ki"), Some("1536".into()));
+        assert_eq!(decimal("250ms"), Some("0.25".into()));
+        assert_eq!(decimal("-2min"), Some("-120".into()));
+        assert_eq!(decimal("1e3s"), Some("1000".into()));
+        assert_eq!(decimal("0xff"), Some("255".into()));
+        for literal in &["", "K", "4X", "4 K", "e3", "1e", "1.5.2K", "4Bi", "nan", "1e9999"] {
+            assert_eq!(decimal(literal), None);
+        }
+
+        assert_eq!(parse_integer("1.5e3"), Some(1500));
+        assert_eq!(parse_integer("2MiB"), Some(2097152));
+        assert_eq!(parse_integer("-0b101"), Some(-5));
+        assert_eq!(parse_integer("250ms"), None);
+        assert_eq!(parse_integer("1e19"), None);
+        assert_eq!(parse_float("250ms"), Some(0.25));
+        assert_eq!(parse_float("1.5e3"), Some(1500.0));
+        assert_eq!(parse_float("4Ki"), Some(4096.0));
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(to_unit("2MiB", "KiB"), Ok("2048".into()));
+        assert_eq!(to_unit("1536", "Ki"), Ok("1.5".into()));
+        assert_eq!(to_unit("90s", "min"), Ok("1.5".into()));
+        assert_eq!(to_unit("250ms", "us"), Ok("250000".into()));
+        assert_eq!(to_unit("1h", "d"), Ok("0.04166666666666666666".into()));
+        assert!(to_unit("1h", "MB").is_err());
+        assert!(to_unit("1", "X").is_err());
+        assert!(to_unit("one", "s").is_err());
+    }
+}
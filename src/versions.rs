@@ -0,0 +1,129 @@
+//! Ordering of version strings, in which each segment is compared individually so that `1.10.0`
+//! is ordered after `1.9.2`, and pre-releases such as `1.0.0-rc.1` before their releases.
+
+use std::cmp::Ordering;
+
+/// Compares two versions. A leading `v` and any build metadata following a `+` are ignored, and
+/// missing segments are taken to be zero, so that `v1.2` is equal to `1.2.0+build5`.
+pub(crate) fn compare(first: &str, second: &str) -> Ordering {
+    let (first_release, first_pre) = split(first);
+    let (second_release, second_pre) = split(second);
+
+    let mut first = first_release.split('.');
+    let mut second = second_release.split('.');
+    loop {
+        let ordering = match (first.next(), second.next()) {
+            (None, None) => break,
+            (left, right) => compare_segments(left.unwrap_or("0"), right.unwrap_or("0")),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    // A pre-release is ordered before the release which it precedes.
+    match (first_pre, second_pre) {
+        (Some(left), Some(right)) => {
+            let mut left = left.split('.');
+            let mut right = right.split('.');
+            loop {
+                let ordering = match (left.next(), right.next()) {
+                    (Some(left), Some(right)) => compare_segments(left, right),
+                    (Some(_), None) => Ordering::Greater,
+                    (None, Some(_)) => Ordering::Less,
+                    (None, None) => return Ordering::Equal,
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Separates the release from the pre-release, after stripping the prefix and build metadata.
+fn split(version: &str) -> (&str, Option<&str>) {
+    let version = version.trim();
+    let version = version.trim_left_matches(|c| c == 'v' || c == 'V');
+    let version = version.find('+').map_or(version, |id| &version[..id]);
+    match version.find('-') {
+        Some(id) => (&version[..id], Some(&version[id + 1..])),
+        None => (version, None),
+    }
+}
+
+/// Compares two segments by each of their runs of digits and of other characters, in which
+/// digits are compared numerically and are ordered before any other characters.
+fn compare_segments(first: &str, second: &str) -> Ordering {
+    let mut first = runs(first).into_iter();
+    let mut second = runs(second).into_iter();
+    loop {
+        let ordering = match (first.next(), second.next()) {
+            (Some(left), Some(right)) => match (is_number(left), is_number(right)) {
+                (true, true) => compare_numbers(left, right),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => left.cmp(right),
+            },
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => return Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Compares numbers of any length without parsing them.
+fn compare_numbers(first: &str, second: &str) -> Ordering {
+    let first = first.trim_left_matches('0');
+    let second = second.trim_left_matches('0');
+    first.len().cmp(&second.len()).then_with(|| first.cmp(second))
+}
+
+fn is_digit(byte: u8) -> bool { byte >= b'0' && byte <= b'9' }
+
+fn is_number(run: &str) -> bool { run.bytes().next().map_or(false, is_digit) }
+
+/// Splits the segment into alternating runs of digits and of other characters.
+fn runs(segment: &str) -> Vec<&str> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let bytes = segment.as_bytes();
+    for id in 1..bytes.len() {
+        if is_digit(bytes[id]) != is_digit(bytes[id - 1]) {
+            runs.push(&segment[start..id]);
+            start = id;
+        }
+    }
+    if start < segment.len() {
+        runs.push(&segment[start..]);
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions() {
+        let ordered = [
+            "0.9", "1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta.2",
+            "1.0.0-beta.11", "1.0.0-rc.1", "1.0.0", "1.0.1", "1.2a", "1.2b", "1.9.2", "1.10.0",
+            "20180101",
+        ];
+        for (id, first) in ordered.iter().enumerate() {
+            for (jd, second) in ordered.iter().enumerate() {
+                assert_eq!(compare(first, second), id.cmp(&jd), "{} and {}", first, second);
+            }
+        }
+        assert_eq!(compare("v1.2", "1.2.0+build5"), Ordering::Equal);
+        assert_eq!(compare("1.02", "1.2"), Ordering::Equal);
+        assert_eq!(compare("99999999999999999999999", "1"), Ordering::Greater);
+    }
+}